@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::time::Duration;
 use url::Url;
 use x402_types::chain::ChainId;
 use x402_types::config::LiteralOrEnv;
+use x402_types::util::DecimalU256;
 
 use crate::chain::AptosChainReference;
 
@@ -26,6 +29,23 @@ impl AptosChainConfig {
     pub fn sponsor_gas(&self) -> bool {
         *self.inner.sponsor_gas.inner()
     }
+    pub fn gas_station(&self) -> Option<&GasStationConfig> {
+        self.inner.gas_station.as_ref()
+    }
+    pub fn gas_station_timeout(&self) -> Duration {
+        Duration::from_millis(self.inner.gas_station_timeout_ms)
+    }
+    pub fn module_abi_ttl(&self) -> Duration {
+        Duration::from_secs(self.inner.module_abi_ttl_secs)
+    }
+    pub fn client_config(&self) -> AptosClientConfig {
+        AptosClientConfig {
+            timeout: Duration::from_secs(self.inner.rest_client_timeout_secs),
+            connect_timeout: Duration::from_secs(self.inner.connect_timeout_secs),
+            max_retries: self.inner.max_retries,
+            ..AptosClientConfig::default()
+        }
+    }
     pub fn chain_reference(&self) -> AptosChainReference {
         self.chain_reference
     }
@@ -79,6 +99,89 @@ pub struct AptosChainConfigInner {
     /// Supports literal booleans or environment variable references like "$APTOS_SPONSOR_GAS".
     #[serde(default = "aptos_chain_config::default_sponsor_gas")]
     pub sponsor_gas: LiteralOrEnv<bool>,
+    /// Gas station that pays fees for sponsored transactions instead of the signer (optional).
+    /// When set, `signer` is only used as a fallback if the gas station is unavailable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_station: Option<GasStationConfig>,
+    /// How long to wait for the gas station before submitting directly (default: 10000).
+    #[serde(default = "aptos_chain_config::default_gas_station_timeout_ms")]
+    pub gas_station_timeout_ms: u64,
+    /// How long Move module ABIs used to validate transfer functions are cached (default: 3600).
+    #[serde(default = "aptos_chain_config::default_module_abi_ttl_secs")]
+    pub module_abi_ttl_secs: u64,
+    /// Timeout of a REST API request, in seconds (default: 30).
+    #[serde(default = "aptos_chain_config::default_rest_client_timeout_secs")]
+    pub rest_client_timeout_secs: u64,
+    /// Timeout for connecting to the gas station, in seconds (default: 5).
+    /// The Aptos REST client only supports an overall request timeout.
+    #[serde(default = "aptos_chain_config::default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// How many times a failed REST API read is retried (default: 3).
+    /// Network errors and `5xx` responses are retried with exponential backoff.
+    #[serde(default = "aptos_chain_config::default_max_retries")]
+    pub max_retries: u8,
+    /// Asset addresses allowed on this chain (optional, default: any).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_assets: Vec<String>,
+    /// Minimum amount per asset, in base units (optional).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub min_payment_amount: HashMap<String, DecimalU256>,
+}
+
+/// Gas station endpoint for sponsored Aptos transactions.
+///
+/// ```toml
+/// [aptos."aptos:1"]
+/// rpc = "$APTOS_RPC_URL"
+/// sponsor_gas = true
+/// gas_station = { url = "$APTOS_GAS_STATION_URL", api_key = "$APTOS_GAS_STATION_API_KEY" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasStationConfig {
+    /// Gas station base URL.
+    /// Supports literal URLs or environment variable references like "$APTOS_GAS_STATION_URL".
+    pub url: LiteralOrEnv<Url>,
+    /// API key, sent as `Authorization: Bearer {api_key}` header.
+    /// Supports literal strings or environment variable references like "$APTOS_GAS_STATION_API_KEY".
+    pub api_key: LiteralOrEnv<String>,
+}
+
+/// HTTP settings of the Aptos REST client, from [`AptosChainConfig::client_config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AptosClientConfig {
+    /// Timeout of a REST API request.
+    pub timeout: Duration,
+    /// Timeout for connecting to the gas station.
+    pub connect_timeout: Duration,
+    /// How many times a failed read is retried.
+    pub max_retries: u8,
+    /// Delay before the first retry, doubled for each following one.
+    pub retry_backoff: Duration,
+}
+
+impl AptosClientConfig {
+    /// Default delay before the first retry (200ms).
+    pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+}
+
+impl Default for AptosClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(aptos_chain_config::default_rest_client_timeout_secs()),
+            connect_timeout: Duration::from_secs(aptos_chain_config::default_connect_timeout_secs()),
+            max_retries: aptos_chain_config::default_max_retries(),
+            retry_backoff: Self::DEFAULT_RETRY_BACKOFF,
+        }
+    }
+}
+
+impl GasStationConfig {
+    pub fn url(&self) -> &Url {
+        self.url.inner()
+    }
+    pub fn api_key(&self) -> &str {
+        self.api_key.inner()
+    }
 }
 
 mod aptos_chain_config {
@@ -88,6 +191,26 @@ mod aptos_chain_config {
         // Default to false when field is missing
         LiteralOrEnv::from_literal(false)
     }
+
+    pub fn default_gas_station_timeout_ms() -> u64 {
+        10_000
+    }
+
+    pub fn default_module_abi_ttl_secs() -> u64 {
+        3_600
+    }
+
+    pub fn default_rest_client_timeout_secs() -> u64 {
+        30
+    }
+
+    pub fn default_connect_timeout_secs() -> u64 {
+        5
+    }
+
+    pub fn default_max_retries() -> u8 {
+        3
+    }
 }
 
 // ============================================================================
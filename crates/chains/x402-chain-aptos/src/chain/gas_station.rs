@@ -0,0 +1,333 @@
+//! Client for an Aptos gas station that pays transaction fees on behalf of users.
+//!
+//! A gas station receives a sender-signed fee-payer transaction, adds its own fee payer
+//! signature and broadcasts it. Clients sign such transactions with the `0x0` fee payer
+//! address, which lets any account act as the fee payer.
+//!
+//! # Protocol
+//!
+//! - `POST {url}/transactions` with `{ "transaction": "0x…", "senderAuthenticator": "0x…" }`,
+//!   the hex-encoded BCS bytes of the `RawTransaction` and the sender's `AccountAuthenticator`.
+//!   The response is `{ "id": "…" }`, optionally with a `transactionHash` already set.
+//! - `GET {url}/transactions/{id}` returns `{ "status": "pending" | "submitted" | "failed",
+//!   "transactionHash": "0x…", "error": "…" }`, polled until the hash is known.
+//!
+//! Both requests carry `Authorization: Bearer {api_key}`.
+
+use aptos_types::transaction::RawTransaction;
+use aptos_types::transaction::authenticator::AccountAuthenticator;
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+use url::Url;
+
+/// Default interval between status polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Errors returned by [`GasStationClient`].
+#[derive(Debug, thiserror::Error)]
+pub enum GasStationError {
+    /// The gas station could not be reached or answered with a server error.
+    #[error("Gas station unavailable: {0}")]
+    Unavailable(String),
+    /// The gas station did not report a transaction hash in time.
+    #[error("Gas station did not respond within {0:?}")]
+    Timeout(Duration),
+    /// The gas station refused to sponsor the transaction.
+    #[error("Gas station rejected the transaction: {0}")]
+    Rejected(String),
+    /// BCS serialization error.
+    #[error("BCS serialization error: {0}")]
+    Bcs(#[from] bcs::Error),
+}
+
+impl GasStationError {
+    /// Whether the gas station was unavailable, so that submitting the transaction
+    /// through another route may still succeed.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, Self::Unavailable(_) | Self::Timeout(_))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitRequest {
+    transaction: String,
+    sender_authenticator: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitResponse {
+    id: String,
+    #[serde(default)]
+    transaction_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusResponse {
+    status: String,
+    #[serde(default)]
+    transaction_hash: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// HTTP client for an Aptos gas station.
+#[derive(Clone)]
+pub struct GasStationClient {
+    client: Client,
+    url: Url,
+    api_key: String,
+    timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl Debug for GasStationClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GasStationClient")
+            .field("url", &self.url)
+            .field("api_key", &"<redacted>")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl GasStationClient {
+    /// Creates a client for the gas station at `url`.
+    ///
+    /// `timeout` bounds the whole exchange, from submission until the transaction hash is known.
+    pub fn new(url: Url, api_key: String, timeout: Duration) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            api_key,
+            timeout,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sends requests with `client`, e.g. to set a connect timeout.
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Sets the interval between status polls (default: 500ms).
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the gas station base URL.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Hands a sender-signed transaction to the gas station and returns the hash of the
+    /// broadcast transaction, as reported by the gas station.
+    pub async fn sign_and_submit(
+        &self,
+        raw_transaction: &RawTransaction,
+        sender_authenticator: &AccountAuthenticator,
+    ) -> Result<String, GasStationError> {
+        let request = SubmitRequest {
+            transaction: format!("0x{}", hex::encode(bcs::to_bytes(raw_transaction)?)),
+            sender_authenticator: format!(
+                "0x{}",
+                hex::encode(bcs::to_bytes(sender_authenticator)?)
+            ),
+        };
+        tokio::time::timeout(self.timeout, self.submit_and_poll(&request))
+            .await
+            .map_err(|_| GasStationError::Timeout(self.timeout))?
+    }
+
+    async fn submit_and_poll(&self, request: &SubmitRequest) -> Result<String, GasStationError> {
+        let response = self
+            .client
+            .post(self.endpoint("transactions"))
+            .bearer_auth(&self.api_key)
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| GasStationError::Unavailable(e.to_string()))?;
+        let submitted: SubmitResponse = Self::parse(response).await?;
+        if let Some(transaction_hash) = submitted.transaction_hash {
+            return Ok(transaction_hash);
+        }
+
+        let status_url = self.endpoint(&format!("transactions/{}", submitted.id));
+        loop {
+            let response = self
+                .client
+                .get(&status_url)
+                .bearer_auth(&self.api_key)
+                .send()
+                .await
+                .map_err(|e| GasStationError::Unavailable(e.to_string()))?;
+            let status: StatusResponse = Self::parse(response).await?;
+            if status.status == "failed" {
+                return Err(GasStationError::Rejected(
+                    status
+                        .error
+                        .unwrap_or_else(|| "transaction failed".to_string()),
+                ));
+            }
+            if let Some(transaction_hash) = status.transaction_hash {
+                return Ok(transaction_hash);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.url.as_str().trim_end_matches('/'), path)
+    }
+
+    /// Maps server errors to [`GasStationError::Unavailable`] and client errors to
+    /// [`GasStationError::Rejected`].
+    async fn parse<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T, GasStationError> {
+        let status = response.status();
+        if status.is_server_error() {
+            return Err(GasStationError::Unavailable(format!("HTTP {status}")));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(GasStationError::Rejected(format!("HTTP {status}: {body}")));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| GasStationError::Unavailable(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::chain_id::ChainId as AptosChainId;
+    use aptos_types::transaction::{EntryFunction, TransactionPayload};
+    use move_core_types::account_address::AccountAddress;
+    use move_core_types::identifier::Identifier;
+    use move_core_types::language_storage::ModuleId;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const TX_HASH: &str = "0x5f3c1e2b8a4d7f6e9c0b1a2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f";
+
+    fn raw_transaction() -> RawTransaction {
+        let entry_function = EntryFunction::new(
+            ModuleId::new(
+                AccountAddress::ONE,
+                Identifier::new("aptos_account").unwrap(),
+            ),
+            Identifier::new("transfer").unwrap(),
+            vec![],
+            vec![],
+        );
+        RawTransaction::new(
+            AccountAddress::TWO,
+            0,
+            TransactionPayload::EntryFunction(entry_function),
+            1000,
+            100,
+            u64::MAX,
+            AptosChainId::new(2),
+        )
+    }
+
+    fn client(server: &MockServer, timeout: Duration) -> GasStationClient {
+        GasStationClient::new(server.uri().parse().unwrap(), "secret".into(), timeout)
+            .with_poll_interval(Duration::from_millis(10))
+    }
+
+    #[tokio::test]
+    async fn polls_status_until_hash_is_known() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/transactions"))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "req-1" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/transactions/req-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "pending" })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/transactions/req-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "submitted",
+                "transactionHash": TX_HASH,
+            })))
+            .mount(&server)
+            .await;
+
+        let hash = client(&server, Duration::from_secs(5))
+            .sign_and_submit(
+                &raw_transaction(),
+                &AccountAuthenticator::NoAccountAuthenticator,
+            )
+            .await
+            .unwrap();
+        assert_eq!(hash, TX_HASH);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let expected = format!(
+            "0x{}",
+            hex::encode(bcs::to_bytes(&raw_transaction()).unwrap())
+        );
+        assert_eq!(body["transaction"], expected);
+    }
+
+    #[tokio::test]
+    async fn rejection_is_not_unavailability() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/transactions"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("gas too high"))
+            .mount(&server)
+            .await;
+
+        let err = client(&server, Duration::from_secs(5))
+            .sign_and_submit(
+                &raw_transaction(),
+                &AccountAuthenticator::NoAccountAuthenticator,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GasStationError::Rejected(_)));
+        assert!(!err.is_unavailable());
+    }
+
+    #[tokio::test]
+    async fn slow_gas_station_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/transactions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "id": "req-1", "transactionHash": TX_HASH }))
+                    .set_delay(Duration::from_secs(1)),
+            )
+            .mount(&server)
+            .await;
+
+        let err = client(&server, Duration::from_millis(50))
+            .sign_and_submit(
+                &raw_transaction(),
+                &AccountAuthenticator::NoAccountAuthenticator,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GasStationError::Timeout(_)));
+        assert!(err.is_unavailable());
+    }
+}
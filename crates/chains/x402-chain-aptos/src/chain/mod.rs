@@ -3,6 +3,16 @@ pub mod config;
 #[cfg(feature = "facilitator")]
 pub use config::*;
 
+#[cfg(feature = "facilitator")]
+pub mod gas_station;
+#[cfg(feature = "facilitator")]
+pub use gas_station::*;
+
+#[cfg(feature = "facilitator")]
+pub mod module_abi;
+#[cfg(feature = "facilitator")]
+pub use module_abi::*;
+
 #[cfg(feature = "facilitator")]
 pub mod provider;
 #[cfg(feature = "facilitator")]
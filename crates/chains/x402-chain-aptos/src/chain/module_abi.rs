@@ -0,0 +1,268 @@
+//! Cache of Move module ABIs, used to validate entry function signatures.
+//!
+//! The facilitator only accepts transfers through a few framework entry functions. Matching
+//! on the module and function names alone would accept a function whose interface changed
+//! in a framework upgrade, so verification also checks the parameter types against the
+//! module ABI published on chain. ABIs are fetched from the REST API
+//! (`GET /accounts/{address}/module/{name}`) at first use and kept for a configurable TTL.
+
+use aptos_rest_client::Response;
+use aptos_rest_client::aptos_api_types::{MoveFunction, MoveModule, MoveModuleBytecode, MoveType};
+use aptos_rest_client::error::RestError;
+use move_core_types::account_address::AccountAddress;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::chain::provider::AptosChainProviderError;
+
+/// Kind of an entry function parameter, as checked against the module ABI.
+///
+/// Leading `signer` parameters are not listed, as they are not transaction arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// `0x1::object::Object<T0>`, an object of the function's type argument.
+    Object,
+    /// `address`.
+    Address,
+    /// `u64`.
+    U64,
+}
+
+impl ParamKind {
+    fn matches(&self, ty: &MoveType) -> bool {
+        match (self, ty) {
+            (ParamKind::Object, MoveType::Struct(tag)) => {
+                tag.address == AccountAddress::ONE.into()
+                    && tag.module.to_string() == "object"
+                    && tag.name.to_string() == "Object"
+                    && matches!(
+                        tag.generic_type_params.as_slice(),
+                        [MoveType::GenericTypeParam { index: 0 }]
+                    )
+            }
+            (ParamKind::Address, MoveType::Address) => true,
+            (ParamKind::U64, MoveType::U64) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Parameters of `0x1::primary_fungible_store::transfer`: `(Object<Metadata>, address, u64)`.
+pub const PRIMARY_FUNGIBLE_STORE_TRANSFER: &[ParamKind] =
+    &[ParamKind::Object, ParamKind::Address, ParamKind::U64];
+
+/// Parameters of `0x1::fungible_asset::transfer`: `(Object<T>, Object<T>, u64)`.
+pub const FUNGIBLE_ASSET_TRANSFER: &[ParamKind] =
+    &[ParamKind::Object, ParamKind::Object, ParamKind::U64];
+
+/// Returns whether `function` is an entry function with one type parameter and the
+/// `expected` parameters after its signers.
+pub fn matches_signature(function: &MoveFunction, expected: &[ParamKind]) -> bool {
+    let is_signer = |ty: &&MoveType| match ty {
+        MoveType::Signer => true,
+        MoveType::Reference { to, .. } => matches!(**to, MoveType::Signer),
+        _ => false,
+    };
+    let params: Vec<&MoveType> = function.params.iter().skip_while(is_signer).collect();
+    function.is_entry
+        && function.generic_type_params.len() == 1
+        && params.len() == expected.len()
+        && params
+            .iter()
+            .zip(expected)
+            .all(|(ty, kind)| kind.matches(ty))
+}
+
+/// Cached module ABI and when it was fetched.
+#[derive(Debug, Clone)]
+struct CachedAbi {
+    abi: MoveModule,
+    fetched_at: Instant,
+}
+
+/// In-process cache of Move module ABIs, keyed by `{address}::{module}`.
+///
+/// Clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct AptosModuleAbiCache {
+    /// How long a fetched ABI is reused
+    ttl: Duration,
+    /// Fetched ABIs
+    entries: Arc<Mutex<HashMap<String, CachedAbi>>>,
+}
+
+impl Default for AptosModuleAbiCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TTL)
+    }
+}
+
+impl AptosModuleAbiCache {
+    /// Default time a fetched ABI is reused for.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+    /// Creates an empty cache reusing fetched ABIs for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the time a fetched ABI is reused for.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the cached ABI of a module, if fetched less than the TTL ago.
+    pub fn get(&self, address: AccountAddress, module_name: &str) -> Option<MoveModule> {
+        let entries = self.entries.lock().expect("module ABI cache lock poisoned");
+        entries
+            .get(&Self::key(address, module_name))
+            .filter(|cached| cached.fetched_at.elapsed() < self.ttl)
+            .map(|cached| cached.abi.clone())
+    }
+
+    /// Caches the ABI of a module.
+    pub fn insert(&self, address: AccountAddress, module_name: &str, abi: MoveModule) {
+        let cached = CachedAbi {
+            abi,
+            fetched_at: Instant::now(),
+        };
+        self.entries
+            .lock()
+            .expect("module ABI cache lock poisoned")
+            .insert(Self::key(address, module_name), cached);
+    }
+
+    /// Returns the ABI of a module, awaiting `fetch`, a REST API module request, if it is
+    /// not cached.
+    pub async fn get_or_fetch(
+        &self,
+        address: AccountAddress,
+        module_name: &str,
+        fetch: impl Future<Output = Result<Response<MoveModuleBytecode>, RestError>>,
+    ) -> Result<MoveModule, AptosChainProviderError> {
+        if let Some(abi) = self.get(address, module_name) {
+            return Ok(abi);
+        }
+        let key = Self::key(address, module_name);
+        let module = fetch
+            .await
+            .map_err(|e| AptosChainProviderError::ModuleAbiError(format!("{key}: {e}")))?
+            .into_inner();
+        let abi = module.abi.ok_or_else(|| {
+            AptosChainProviderError::ModuleAbiError(format!("{key}: no ABI in response"))
+        })?;
+        #[cfg(feature = "telemetry")]
+        tracing::debug!(module = %key, "Fetched Move module ABI");
+        self.insert(address, module_name, abi.clone());
+        Ok(abi)
+    }
+
+    fn key(address: AccountAddress, module_name: &str) -> String {
+        format!("{}::{}", address.to_hex_literal(), module_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn function(params: serde_json::Value) -> MoveFunction {
+        serde_json::from_value(json!({
+            "name": "transfer",
+            "visibility": "public",
+            "is_entry": true,
+            "is_view": false,
+            "generic_type_params": [{ "constraints": ["key"] }],
+            "params": params,
+            "return": [],
+        }))
+        .unwrap()
+    }
+
+    fn module() -> MoveModule {
+        serde_json::from_value(json!({
+            "address": "0x1",
+            "name": "primary_fungible_store",
+            "friends": [],
+            "exposed_functions": [],
+            "structs": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn framework_transfer_signatures_match() {
+        let primary_store = function(json!([
+            "&signer",
+            "0x1::object::Object<T0>",
+            "address",
+            "u64"
+        ]));
+        assert!(matches_signature(
+            &primary_store,
+            PRIMARY_FUNGIBLE_STORE_TRANSFER
+        ));
+        assert!(!matches_signature(&primary_store, FUNGIBLE_ASSET_TRANSFER));
+
+        let fungible_asset = function(json!([
+            "&signer",
+            "0x1::object::Object<T0>",
+            "0x1::object::Object<T0>",
+            "u64"
+        ]));
+        assert!(matches_signature(&fungible_asset, FUNGIBLE_ASSET_TRANSFER));
+    }
+
+    #[test]
+    fn changed_signatures_do_not_match() {
+        for params in [
+            json!(["&signer", "0x1::object::Object<T0>", "address", "u128"]),
+            json!(["&signer", "address", "address", "u64"]),
+            json!([
+                "&signer",
+                "0x1::object::Object<T0>",
+                "address",
+                "u64",
+                "bool"
+            ]),
+            json!(["&signer", "0x2::object::Object<T0>", "address", "u64"]),
+        ] {
+            let function = function(params.clone());
+            assert!(
+                !matches_signature(&function, PRIMARY_FUNGIBLE_STORE_TRANSFER),
+                "{params}"
+            );
+        }
+    }
+
+    #[test]
+    fn cache_expires_after_ttl() {
+        let cache = AptosModuleAbiCache::default();
+        assert!(
+            cache
+                .get(AccountAddress::ONE, "primary_fungible_store")
+                .is_none()
+        );
+        cache.insert(AccountAddress::ONE, "primary_fungible_store", module());
+        assert!(
+            cache
+                .get(AccountAddress::ONE, "primary_fungible_store")
+                .is_some()
+        );
+        assert!(cache.get(AccountAddress::ONE, "fungible_asset").is_none());
+
+        let cache = AptosModuleAbiCache::new(Duration::ZERO);
+        cache.insert(AccountAddress::ONE, "primary_fungible_store", module());
+        assert!(
+            cache
+                .get(AccountAddress::ONE, "primary_fungible_store")
+                .is_none()
+        );
+    }
+}
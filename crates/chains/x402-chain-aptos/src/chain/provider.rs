@@ -1,12 +1,18 @@
 use aptos_crypto::ed25519::Ed25519PrivateKey;
 use aptos_rest_client::Client as AptosClient;
+use aptos_rest_client::aptos_api_types::MoveModule;
+use aptos_rest_client::error::RestError;
 use move_core_types::account_address::AccountAddress;
 use std::fmt::{Debug, Formatter};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 use x402_types::chain::{ChainId, ChainProviderOps};
 use x402_types::scheme::X402SchemeFacilitatorError;
 
-use crate::chain::config::AptosChainConfig;
+use crate::chain::config::{AptosChainConfig, AptosClientConfig};
+use crate::chain::gas_station::GasStationClient;
+use crate::chain::module_abi::AptosModuleAbiCache;
 use crate::chain::types::{Address, AptosChainReference};
 
 /// Errors that can occur when interacting with an Aptos chain provider.
@@ -19,6 +25,9 @@ pub enum AptosChainProviderError {
     /// JSON serialization/deserialization error.
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
+    /// A Move module ABI could not be fetched.
+    #[error("Module ABI error: {0}")]
+    ModuleAbiError(String),
 }
 
 impl From<AptosChainProviderError> for X402SchemeFacilitatorError {
@@ -47,6 +56,24 @@ impl From<AptosChainProviderError> for X402SchemeFacilitatorError {
 /// user transactions. The client creates and signs a transaction, and the
 /// facilitator adds its signature as the sponsor before submitting.
 ///
+/// # Gas Station
+///
+/// With a gas station configured, sponsored transactions are handed to it for the
+/// fee payer signature and broadcast. The fee payer key, if any, is only used when
+/// the gas station is unavailable.
+///
+/// # Module ABIs
+///
+/// Move module ABIs fetched to validate entry function signatures are cached for
+/// `module_abi_ttl_secs` (default: one hour), see [`AptosModuleAbiCache`].
+///
+/// # Timeouts and Retries
+///
+/// REST requests time out after `rest_client_timeout_secs`. Reads (balance views,
+/// simulations, module ABIs) go through [`AptosChainProvider::with_retry`], which retries
+/// network errors and `5xx` responses up to `max_retries` times with exponential backoff.
+/// Transaction submission is not retried.
+///
 /// # Example
 ///
 /// ```ignore
@@ -66,6 +93,12 @@ pub struct AptosChainProvider {
     fee_payer_private_key: Option<Ed25519PrivateKey>,
     /// The Aptos REST API client.
     rest_client: Arc<AptosClient>,
+    /// Gas station paying fees for sponsored transactions (if configured).
+    gas_station: Option<GasStationClient>,
+    /// Move module ABIs used to validate entry function signatures.
+    module_abis: AptosModuleAbiCache,
+    /// Timeouts and retries of the REST client.
+    client_config: AptosClientConfig,
 }
 
 impl Debug for AptosChainProvider {
@@ -74,6 +107,9 @@ impl Debug for AptosChainProvider {
             .field("chain", &self.chain)
             .field("sponsor_gas", &self.sponsor_gas)
             .field("rpc_url", &"<rest_client>")
+            .field("gas_station", &self.gas_station)
+            .field("module_abi_ttl", &self.module_abis.ttl())
+            .field("client_config", &self.client_config)
             .finish()
     }
 }
@@ -84,7 +120,7 @@ impl AptosChainProvider {
     /// # Errors
     ///
     /// Returns an error if:
-    /// - `sponsor_gas` is true but no signer is provided
+    /// - `sponsor_gas` is true but neither a signer nor a gas station is provided
     /// - The private key is invalid
     /// - The REST client cannot be initialized
     pub async fn from_config(
@@ -94,9 +130,11 @@ impl AptosChainProvider {
         let rpc_url = config.rpc();
         let sponsor_gas = config.sponsor_gas();
 
-        // Validate: if sponsoring, signer must be provided
-        if sponsor_gas && config.signer().is_none() {
-            return Err("signer configuration required when sponsor_gas is true".into());
+        // Validate: if sponsoring, a signer or a gas station must pay the fees
+        if sponsor_gas && config.signer().is_none() && config.gas_station().is_none() {
+            return Err(
+                "signer or gas_station configuration required when sponsor_gas is true".into(),
+            );
         }
 
         // Parse private key if signer is provided
@@ -120,22 +158,39 @@ impl AptosChainProvider {
         };
 
         // Create REST client with optional API key
-        let rest_client = if let Some(api_key) = config.api_key() {
+        let client_config = config.client_config();
+        let mut rest_client_builder = {
             use aptos_rest_client::AptosBaseUrl;
             AptosClient::builder(AptosBaseUrl::Custom(rpc_url.clone()))
-                .api_key(api_key)?
-                .build()
-        } else {
-            AptosClient::new(rpc_url.clone())
+                .timeout(client_config.timeout)
         };
+        if let Some(api_key) = config.api_key() {
+            rest_client_builder = rest_client_builder.api_key(api_key)?;
+        }
+        let rest_client = rest_client_builder.build();
 
-        let provider = Self::new(
+        let mut provider = Self::new(
             chain,
             sponsor_gas,
             fee_payer_address,
             fee_payer_private_key,
             rest_client,
-        );
+        )
+        .with_module_abi_ttl(config.module_abi_ttl())
+        .with_client_config(client_config);
+        if let Some(gas_station) = config.gas_station() {
+            let http_client = reqwest::Client::builder()
+                .connect_timeout(client_config.connect_timeout)
+                .build()?;
+            provider = provider.with_gas_station(
+                GasStationClient::new(
+                    gas_station.url().clone(),
+                    gas_station.api_key().to_string(),
+                    config.gas_station_timeout(),
+                )
+                .with_http_client(http_client),
+            );
+        }
         Ok(provider)
     }
 
@@ -179,9 +234,91 @@ impl AptosChainProvider {
             fee_payer_address,
             fee_payer_private_key,
             rest_client: Arc::new(rest_client),
+            gas_station: None,
+            module_abis: AptosModuleAbiCache::default(),
+            client_config: AptosClientConfig::default(),
         }
     }
 
+    /// Sets the retry policy of REST reads.
+    ///
+    /// The timeouts are applied by [`AptosChainProvider::from_config`] when building the
+    /// REST client, and only reported here for a provider built with [`AptosChainProvider::new`].
+    pub fn with_client_config(mut self, client_config: AptosClientConfig) -> Self {
+        self.client_config = client_config;
+        self
+    }
+
+    /// Returns the timeouts and retry policy of the REST client.
+    pub fn client_config(&self) -> &AptosClientConfig {
+        &self.client_config
+    }
+
+    /// Runs the REST call made by `call`, retrying it on network errors and `5xx` responses.
+    ///
+    /// Retries up to [`AptosClientConfig::max_retries`] times, waiting
+    /// [`AptosClientConfig::retry_backoff`] before the first retry and twice as long before
+    /// each following one. Other errors are returned right away.
+    pub async fn with_retry<T, F, Fut>(&self, mut call: F) -> Result<T, RestError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RestError>>,
+    {
+        let mut retries = 0u8;
+        loop {
+            match call().await {
+                Err(error) if retries < self.client_config.max_retries && is_retryable(&error) => {
+                    let delay = self.client_config.retry_backoff * 2u32.pow(u32::from(retries));
+                    retries += 1;
+                    #[cfg(feature = "telemetry")]
+                    tracing::warn!(
+                        chain = %ChainId::from(self.chain),
+                        retry = retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %error,
+                        "Aptos REST request failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sets how long fetched Move module ABIs are reused for.
+    ///
+    /// Default is [`AptosModuleAbiCache::DEFAULT_TTL`].
+    pub fn with_module_abi_ttl(mut self, ttl: Duration) -> Self {
+        self.module_abis = AptosModuleAbiCache::new(ttl);
+        self
+    }
+
+    /// Returns the ABI of a Move module, fetched at first use and cached.
+    pub async fn module_abi(
+        &self,
+        address: AccountAddress,
+        module_name: &str,
+    ) -> Result<MoveModule, AptosChainProviderError> {
+        self.module_abis
+            .get_or_fetch(
+                address,
+                module_name,
+                self.with_retry(|| self.rest_client.get_account_module(address, module_name)),
+            )
+            .await
+    }
+
+    /// Hands sponsored transactions to a gas station instead of signing as fee payer.
+    pub fn with_gas_station(mut self, gas_station: GasStationClient) -> Self {
+        self.gas_station = Some(gas_station);
+        self
+    }
+
+    /// Returns the gas station client, if configured.
+    pub fn gas_station(&self) -> Option<&GasStationClient> {
+        self.gas_station.as_ref()
+    }
+
     /// Returns a reference to the Aptos REST API client.
     pub fn rest_client(&self) -> &AptosClient {
         &self.rest_client
@@ -208,6 +345,17 @@ impl AptosChainProvider {
     }
 }
 
+/// Whether a failed REST call may succeed when retried: network errors, timeouts and
+/// server errors.
+fn is_retryable(error: &RestError) -> bool {
+    match error {
+        RestError::Api(response) => response.status_code.is_server_error(),
+        RestError::Http(status, _) => status.is_server_error(),
+        RestError::Timeout(_) | RestError::Unknown(_) => true,
+        _ => false,
+    }
+}
+
 impl ChainProviderOps for AptosChainProvider {
     fn signer_addresses(&self) -> Vec<String> {
         if let Some(address) = self.fee_payer_address {
@@ -221,3 +369,83 @@ impl ChainProviderOps for AptosChainProvider {
         self.chain.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn ledger_information() -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .insert_header("X-Aptos-Chain-Id", "2")
+            .insert_header("X-Aptos-Epoch", "1")
+            .insert_header("X-Aptos-Ledger-Version", "100")
+            .insert_header("X-Aptos-Ledger-Oldest-Version", "0")
+            .insert_header("X-Aptos-Ledger-TimestampUsec", "1700000000000000")
+            .insert_header("X-Aptos-Block-Height", "10")
+            .insert_header("X-Aptos-Oldest-Block-Height", "0")
+            .set_body_json(json!({
+                "chain_id": 2,
+                "epoch": "1",
+                "ledger_version": "100",
+                "oldest_ledger_version": "0",
+                "ledger_timestamp": "1700000000000000",
+                "node_role": "full_node",
+                "oldest_block_height": "0",
+                "block_height": "10",
+            }))
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ledger_information())
+            .mount(&server)
+            .await;
+
+        let provider = AptosChainProvider::new(
+            AptosChainReference::testnet(),
+            false,
+            None,
+            None,
+            AptosClient::new(server.uri().parse().unwrap()),
+        )
+        .with_client_config(AptosClientConfig {
+            retry_backoff: Duration::from_millis(10),
+            ..AptosClientConfig::default()
+        });
+        let state = provider
+            .with_retry(|| provider.rest_client().get_ledger_information())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(state.version, 100);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        // Without retries, the first failure is returned
+        server.reset().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let provider = provider.with_client_config(AptosClientConfig {
+            max_retries: 0,
+            ..AptosClientConfig::default()
+        });
+        assert!(
+            provider
+                .with_retry(|| provider.rest_client().get_ledger_information())
+                .await
+                .is_err()
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+}
@@ -1,19 +1,24 @@
+use aptos_crypto::HashValue;
 use aptos_types::transaction::authenticator::AccountAuthenticator;
 use aptos_types::transaction::{EntryFunction, RawTransaction, SignedTransaction};
 use move_core_types::account_address::AccountAddress;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 use x402_types::chain::ChainProviderOps;
 use x402_types::proto;
 use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::{
     X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
+use x402_types::timestamp::{Clock, SystemClock};
 use x402_types::util::Base64Bytes;
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 use crate::V2AptosExact;
 use crate::chain::AptosChainProvider;
+use crate::chain::module_abi::{
+    FUNGIBLE_ASSET_TRANSFER, PRIMARY_FUNGIBLE_STORE_TRANSFER, matches_signature,
+};
 use crate::chain::types::Address;
 use crate::v2_aptos_exact::types;
 use crate::v2_aptos_exact::types::ExactScheme;
@@ -24,17 +29,53 @@ const MAX_GAS_AMOUNT: u64 = 500_000;
 /// Buffer in seconds before expiration to ensure transaction has time to execute.
 const EXPIRATION_BUFFER_SECONDS: u64 = 5;
 
+/// Configuration for the V2 Aptos exact scheme facilitator.
+///
+/// # Fields
+///
+/// - `strict_accepted_match`: Whether the requirements accepted by the client must equal
+///   the payment requirements field by field. By default they only need to be
+///   [functionally equal](v2::PaymentRequirements::is_functionally_equal).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct V2AptosExactFacilitatorConfig {
+    #[serde(default)]
+    pub strict_accepted_match: bool,
+}
+
 pub struct V2AptosExactFacilitator {
     provider: Arc<AptosChainProvider>,
+    strict_accepted_match: bool,
+    clock: Arc<dyn Clock>,
+}
+
+impl V2AptosExactFacilitator {
+    /// Creates a facilitator settling payments with `provider`.
+    pub fn new(provider: Arc<AptosChainProvider>, config: V2AptosExactFacilitatorConfig) -> Self {
+        Self {
+            provider,
+            strict_accepted_match: config.strict_accepted_match,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Checks transaction expirations against `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
 }
 
 impl X402SchemeFacilitatorBuilder<Arc<AptosChainProvider>> for V2AptosExact {
     fn build(
         &self,
         provider: Arc<AptosChainProvider>,
-        _config: Option<serde_json::Value>,
+        config: Option<serde_json::Value>,
     ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
-        Ok(Box::new(V2AptosExactFacilitator { provider }))
+        let config = config
+            .map(serde_json::from_value::<V2AptosExactFacilitatorConfig>)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Box::new(V2AptosExactFacilitator::new(provider, config)))
     }
 }
 
@@ -44,38 +85,59 @@ impl X402SchemeFacilitator for V2AptosExactFacilitator {
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let request = types::VerifyRequest::try_from(request)?;
-        let verification = verify_transfer(&self.provider, &request).await?;
-        Ok(v2::VerifyResponse::valid(verification.payer.to_string()).into())
+        verify_in_span(request, async {
+            let request = types::VerifyRequest::try_from(request)?;
+            let verification = verify_transfer(
+                &self.provider,
+                &request,
+                &*self.clock,
+                self.strict_accepted_match,
+            )
+            .await?;
+            Ok(v2::VerifyResponse::valid(verification.payer.to_string()).into())
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let request = types::SettleRequest::try_from(request)?;
-        let verification = verify_transfer(&self.provider, &request).await?;
-        let payer = verification.payer.to_string();
-        let tx_hash = settle_transaction(&self.provider, verification).await?;
-        Ok(v2::SettleResponse::Success {
-            payer,
-            transaction: tx_hash,
-            network: self.provider.chain_id().to_string(),
-        }
-        .into())
+        settle_in_span(request, async {
+            let request = types::SettleRequest::try_from(request)?;
+            let verification = verify_transfer(
+                &self.provider,
+                &request,
+                &*self.clock,
+                self.strict_accepted_match,
+            )
+            .await?;
+            let payer = verification.payer.to_string();
+            let tx_hash = settle_transaction(&self.provider, verification).await?;
+            Ok(v2::SettleResponse::Success {
+                payer,
+                transaction: tx_hash,
+                network: self.provider.chain_id().to_string(),
+            }
+            .into())
+        })
+        .await
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
         let chain_id = self.provider.chain_id();
 
-        // Include extra.feePayer if the facilitator is configured to sponsor gas
-        let extra = if self.provider.sponsor_gas() {
-            self.provider
-                .account_address()
-                .map(|addr| serde_json::json!({ "feePayer": Address::new(addr).to_string() }))
-        } else {
+        // Include extra.feePayer if the facilitator is configured to sponsor gas.
+        // A gas station signs as whichever account it picks, so clients sign for the
+        // `0x0` fee payer, which any account may fill in.
+        let extra = if !self.provider.sponsor_gas() {
             None
-        };
+        } else if self.provider.gas_station().is_some() {
+            Some(AccountAddress::ZERO)
+        } else {
+            self.provider.account_address()
+        }
+        .map(|addr| serde_json::json!({ "feePayer": Address::new(addr).to_string() }));
 
         let kinds: Vec<proto::SupportedPaymentKind> = vec![proto::SupportedPaymentKind {
             x402_version: proto::v2::X402Version2.into(),
@@ -133,15 +195,15 @@ struct RawTransactionFields {
 pub async fn verify_transfer(
     provider: &AptosChainProvider,
     request: &types::VerifyRequest,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
 ) -> Result<VerifyTransferResult, PaymentVerificationError> {
     let payload = &request.payment_payload;
     let requirements = &request.payment_requirements;
 
-    // 1. Validate accepted == requirements
+    // 1. Validate accepted matches requirements
     let accepted = &payload.accepted;
-    if accepted != requirements {
-        return Err(PaymentVerificationError::AcceptedRequirementsMismatch);
-    }
+    accepted.assert_accepted(requirements, strict_accepted_match)?;
 
     // 2. Validate network/scheme match
     let chain_id = provider.chain_id();
@@ -165,7 +227,9 @@ pub async fn verify_transfer(
             .map(|fp| fp.to_string())
             .unwrap_or_default();
         let signer_addresses = provider.signer_addresses();
-        if !signer_addresses.contains(&fee_payer_str) {
+        let is_gas_station_fee_payer = provider.gas_station().is_some()
+            && fee_payer_str == Address::new(AccountAddress::ZERO).to_string();
+        if !is_gas_station_fee_payer && !signer_addresses.contains(&fee_payer_str) {
             return Err(PaymentVerificationError::InvalidFormat(
                 "fee_payer_not_managed_by_facilitator".to_string(),
             ));
@@ -262,13 +326,7 @@ pub async fn verify_transfer(
     }
 
     // 10. Expiration check with buffer
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| PaymentVerificationError::InvalidFormat(format!("System time error: {}", e)))?
-        .as_secs();
-    if raw_fields.expiration_timestamp_secs < now + EXPIRATION_BUFFER_SECONDS {
-        return Err(PaymentVerificationError::Expired);
-    }
+    assert_not_expiring(raw_fields.expiration_timestamp_secs, clock)?;
 
     // 11. Entry function validation — accept both primary_fungible_store::transfer
     //     and fungible_asset::transfer
@@ -293,7 +351,29 @@ pub async fn verify_transfer(
         )));
     }
 
-    // 12. Type args count == 1
+    // 12. Entry function signature, checked against the on-chain module ABI
+    let expected_params = if is_primary_fungible_store {
+        PRIMARY_FUNGIBLE_STORE_TRANSFER
+    } else {
+        FUNGIBLE_ASSET_TRANSFER
+    };
+    let abi = provider
+        .module_abi(module_address, &module_name)
+        .await
+        .map_err(|e| PaymentVerificationError::InvalidFormat(e.to_string()))?;
+    let signature_matches = abi
+        .exposed_functions
+        .iter()
+        .find(|function| function.name.to_string() == function_name)
+        .is_some_and(|function| matches_signature(function, expected_params));
+    if !signature_matches {
+        return Err(PaymentVerificationError::InvalidFormat(format!(
+            "invalid_exact_aptos_payload_wrong_function_signature: {}::{}::{}",
+            module_address, module_name, function_name
+        )));
+    }
+
+    // 13. Type args count == 1
     if entry_function.ty_args().len() != 1 {
         return Err(PaymentVerificationError::InvalidFormat(format!(
             "invalid_exact_aptos_payload_wrong_type_args: expected 1, got {}",
@@ -301,7 +381,7 @@ pub async fn verify_transfer(
         )));
     }
 
-    // 13. Validate function arguments (asset, recipient, amount)
+    // 14. Validate function arguments (asset, recipient, amount)
     let args = entry_function.args();
     if args.len() != 3 {
         return Err(PaymentVerificationError::InvalidFormat(format!(
@@ -310,7 +390,7 @@ pub async fn verify_transfer(
         )));
     }
 
-    // 14. Asset address
+    // 15. Asset address
     let asset_address: AccountAddress = bcs::from_bytes(&args[0]).map_err(|e| {
         PaymentVerificationError::InvalidFormat(format!("Failed to parse asset address: {}", e))
     })?;
@@ -319,7 +399,7 @@ pub async fn verify_transfer(
         return Err(PaymentVerificationError::AssetMismatch);
     }
 
-    // 15. Recipient address
+    // 16. Recipient address
     let recipient_address: AccountAddress = bcs::from_bytes(&args[1]).map_err(|e| {
         PaymentVerificationError::InvalidFormat(format!("Failed to parse recipient address: {}", e))
     })?;
@@ -328,7 +408,7 @@ pub async fn verify_transfer(
         return Err(PaymentVerificationError::RecipientMismatch);
     }
 
-    // 16. Amount
+    // 17. Amount
     let amount: u64 = bcs::from_bytes(&args[2]).map_err(|e| {
         PaymentVerificationError::InvalidFormat(format!("Failed to parse amount: {}", e))
     })?;
@@ -339,14 +419,14 @@ pub async fn verify_transfer(
         return Err(PaymentVerificationError::InvalidPaymentAmount);
     }
 
-    // 17. Balance check via REST API view function
+    // 18. Balance check via REST API view function
     let balance =
         query_fungible_asset_balance(provider, &raw_fields.sender, expected_asset).await?;
     if balance < expected_amount {
         return Err(PaymentVerificationError::InsufficientFunds);
     }
 
-    // 18. Transaction simulation
+    // 19. Transaction simulation
     simulate_transaction(provider, &deserialized).await?;
 
     Ok(VerifyTransferResult {
@@ -361,6 +441,19 @@ pub async fn verify_transfer(
 ///
 /// Calls `0x1::primary_fungible_store::balance` as a view function using
 /// the SDK's built-in `rest_client.view()` method.
+/// Rejects a transaction expiring within [`EXPIRATION_BUFFER_SECONDS`] of the time of `clock`,
+/// which would leave it no time to execute.
+fn assert_not_expiring(
+    expiration_timestamp_secs: u64,
+    clock: &dyn Clock,
+) -> Result<(), PaymentVerificationError> {
+    let now = clock.now().as_secs();
+    if expiration_timestamp_secs < now.saturating_add(EXPIRATION_BUFFER_SECONDS) {
+        return Err(PaymentVerificationError::Expired);
+    }
+    Ok(())
+}
+
 async fn query_fungible_asset_balance(
     provider: &AptosChainProvider,
     owner: &AccountAddress,
@@ -392,8 +485,7 @@ async fn query_fungible_asset_balance(
     };
 
     let response = provider
-        .rest_client()
-        .view(&view_request, None)
+        .with_retry(|| provider.rest_client().view(&view_request, None))
         .await
         .map_err(|e| {
             PaymentVerificationError::InvalidFormat(format!("Balance query failed: {}", e))
@@ -443,8 +535,7 @@ async fn simulate_transaction(
     };
 
     let result = provider
-        .rest_client()
-        .simulate(&signed_txn)
+        .with_retry(|| provider.rest_client().simulate(&signed_txn))
         .await
         .map_err(|e| {
             PaymentVerificationError::TransactionSimulation(format!(
@@ -469,6 +560,9 @@ async fn simulate_transaction(
 }
 
 /// Settle the transaction by submitting it to the network.
+///
+/// Sponsored transactions go through the gas station when one is configured. If it is
+/// unavailable, the facilitator signs as fee payer and submits the transaction itself.
 pub async fn settle_transaction(
     provider: &AptosChainProvider,
     verification: VerifyTransferResult,
@@ -486,6 +580,37 @@ pub async fn settle_transaction(
             ))
         })?;
 
+    if let (Some(gas_station), Some(_)) = (provider.gas_station(), verification.fee_payer_address) {
+        match gas_station
+            .sign_and_submit(&verification.raw_transaction, &sender_authenticator)
+            .await
+        {
+            Ok(tx_hash) => {
+                let tx_hash =
+                    HashValue::from_hex(tx_hash.trim_start_matches("0x")).map_err(|e| {
+                        PaymentVerificationError::TransactionSimulation(format!(
+                            "Gas station returned an invalid transaction hash: {}",
+                            e
+                        ))
+                    })?;
+                wait_for_confirmation(provider, &verification.raw_transaction, tx_hash).await?;
+                return Ok(format!("0x{}", hex::encode(tx_hash.to_vec())));
+            }
+            Err(e) if e.is_unavailable() => {
+                #[cfg(feature = "telemetry")]
+                tracing::warn!(
+                    error = %e,
+                    "Gas station unavailable, submitting transaction directly"
+                );
+            }
+            Err(e) => {
+                return Err(PaymentVerificationError::TransactionSimulation(
+                    e.to_string(),
+                ));
+            }
+        }
+    }
+
     let signed_txn = if let Some(fee_payer_address) = verification.fee_payer_address {
         // Sponsored transaction: facilitator signs as fee payer
         let fee_payer_private_key = provider.private_key().ok_or_else(|| {
@@ -494,6 +619,16 @@ pub async fn settle_transaction(
             )
         })?;
         let fee_payer_public_key: Ed25519PublicKey = fee_payer_private_key.into();
+        // The sender signed for the `0x0` placeholder: the facilitator fills in its own account
+        let fee_payer_address = if fee_payer_address == AccountAddress::ZERO {
+            provider.account_address().ok_or_else(|| {
+                PaymentVerificationError::InvalidFormat(
+                    "Fee payer account not configured for sponsored transaction".to_string(),
+                )
+            })?
+        } else {
+            fee_payer_address
+        };
 
         // Create the message that the fee payer needs to sign
         let fee_payer_message = RawTransactionWithData::new_fee_payer(
@@ -557,9 +692,19 @@ pub async fn settle_transaction(
             ))
         })?;
 
-    // Wait for transaction confirmation.
+    wait_for_confirmation(provider, &verification.raw_transaction, tx_hash).await?;
+
+    Ok(format!("0x{}", hex::encode(tx_hash.to_vec())))
+}
+
+/// Wait for a submitted transaction to be confirmed, until it expires.
+async fn wait_for_confirmation(
+    provider: &AptosChainProvider,
+    raw_transaction: &RawTransaction,
+    tx_hash: HashValue,
+) -> Result<(), PaymentVerificationError> {
     // Re-serialize RawTransaction to extract expiration_timestamp_secs (private field).
-    let raw_tx_bytes = bcs::to_bytes(&verification.raw_transaction).map_err(|e| {
+    let raw_tx_bytes = bcs::to_bytes(raw_transaction).map_err(|e| {
         PaymentVerificationError::InvalidFormat(format!(
             "Failed to serialize RawTransaction: {}",
             e
@@ -582,8 +727,7 @@ pub async fn settle_transaction(
                 e
             ))
         })?;
-
-    Ok(format!("0x{}", hex::encode(tx_hash.to_vec())))
+    Ok(())
 }
 
 /// Try to parse transaction_bytes as RawTransaction + None suffix (1 byte),
@@ -7,6 +7,7 @@ use aptos_types::chain_id::ChainId as AptosChainId;
 use aptos_types::transaction::TransactionPayload;
 use move_core_types::identifier::Identifier;
 use move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+use x402_types::timestamp::FixedClock;
 
 // ──────────────────────────────────────────────────
 // Test helpers
@@ -513,34 +514,41 @@ fn test_simple_transaction_no_fee_payer_extraction() {
 // Tests for expiration check logic
 // ──────────────────────────────────────────────────
 
+const EXPIRATION: u64 = 1_700_000_060;
+
 #[test]
 fn test_expiration_check_future_ok() {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let expiration = now + 60; // 60 seconds from now
-    assert!(expiration >= now + EXPIRATION_BUFFER_SECONDS);
+    let clock = FixedClock(EXPIRATION - 60); // 60 seconds before expiration
+    assert!(assert_not_expiring(EXPIRATION, &clock).is_ok());
+}
+
+#[test]
+fn test_expiration_check_buffer_boundary() {
+    let at = |now: u64| assert_not_expiring(EXPIRATION, &FixedClock(now));
+    assert!(at(EXPIRATION - EXPIRATION_BUFFER_SECONDS - 1).is_ok());
+    assert!(at(EXPIRATION - EXPIRATION_BUFFER_SECONDS).is_ok());
+    assert!(matches!(
+        at(EXPIRATION - EXPIRATION_BUFFER_SECONDS + 1),
+        Err(PaymentVerificationError::Expired)
+    ));
 }
 
 #[test]
 fn test_expiration_check_too_close_fails() {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let expiration = now + 3; // Only 3 seconds buffer, needs 5
-    assert!(expiration < now + EXPIRATION_BUFFER_SECONDS);
+    let clock = FixedClock(EXPIRATION - 3); // Only 3 seconds buffer, needs 5
+    assert!(matches!(
+        assert_not_expiring(EXPIRATION, &clock),
+        Err(PaymentVerificationError::Expired)
+    ));
 }
 
 #[test]
 fn test_expiration_check_past_fails() {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let expiration = now - 10; // 10 seconds ago
-    assert!(expiration < now + EXPIRATION_BUFFER_SECONDS);
+    let clock = FixedClock(EXPIRATION + 10); // 10 seconds ago
+    assert!(matches!(
+        assert_not_expiring(EXPIRATION, &clock),
+        Err(PaymentVerificationError::Expired)
+    ));
 }
 
 // ──────────────────────────────────────────────────
@@ -1,9 +1,11 @@
-use alloy_primitives::B256;
+use alloy_primitives::{Address, B256};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use url::Url;
 use x402_types::chain::ChainId;
 use x402_types::config::LiteralOrEnv;
+use x402_types::util::DecimalU256;
 
 use crate::chain::Eip155ChainReference;
 
@@ -50,6 +52,28 @@ impl Eip155ChainConfig {
         self.inner.receipt_timeout_secs
     }
 
+    /// Returns how long, in seconds, a signer's confirmed nonce may stay the same while it
+    /// has pending transactions before they are reported as stuck.
+    pub fn nonce_stuck_after_secs(&self) -> u64 {
+        self.inner.nonce_stuck_after_secs
+    }
+
+    /// Returns how many times a settlement failing for a transient reason is sent again.
+    pub fn settlement_retries(&self) -> u8 {
+        self.inner.settlement_retries
+    }
+
+    /// Returns the failure reasons retried in addition to the built-in
+    /// [`TRANSIENT_REVERT_REASONS`](crate::chain::revert::TRANSIENT_REVERT_REASONS).
+    pub fn transient_revert_reasons(&self) -> &[String] {
+        &self.inner.transient_revert_reasons
+    }
+
+    /// Returns whether the provider only verifies payments, without signers to settle them.
+    pub fn verify_only(&self) -> bool {
+        self.inner.verify_only
+    }
+
     /// Returns the signer configuration for this chain.
     pub fn signers(&self) -> &Eip155SignersConfig {
         &self.inner.signers
@@ -64,6 +88,21 @@ impl Eip155ChainConfig {
     pub fn chain_reference(&self) -> Eip155ChainReference {
         self.chain_reference
     }
+
+    /// Returns the gas limit policies for this chain.
+    pub fn gas_limit(&self) -> &GasLimitConfig {
+        &self.inner.gas_limit
+    }
+
+    /// Returns the factories EIP-6492 signatures may use, or `None` if any is allowed.
+    pub fn allowed_eip6492_factories(&self) -> Option<&HashSet<Address>> {
+        self.inner.allowed_eip6492_factories.as_ref()
+    }
+
+    /// Returns the priority fee policy, or `None` to let the provider fill the fees.
+    pub fn priority_fee(&self) -> Option<&PriorityFeeConfig> {
+        self.inner.priority_fee.as_ref()
+    }
 }
 
 /// Configuration specific to EVM-compatible chains.
@@ -75,14 +114,220 @@ pub struct Eip155ChainConfigInner {
     /// Whether the chain supports flashblocks.
     #[serde(default = "eip155_chain_config::default_flashblocks")]
     pub flashblocks: bool,
-    /// Signer configuration for this chain (required).
+    /// Signer configuration for this chain, required unless `verify_only` is set.
     /// Array of private keys (hex format) or env var references.
+    #[serde(default)]
     pub signers: Eip155SignersConfig,
+    /// Whether the facilitator only verifies payments on this chain (optional). A verify-only
+    /// chain takes no signers, and fails every settlement.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verify_only: bool,
     /// RPC provider configuration for this chain (required).
     pub rpc: Vec<RpcConfig>,
     /// How long to wait till the transaction receipt is available (optional)
     #[serde(default = "eip155_chain_config::default_receipt_timeout_secs")]
     pub receipt_timeout_secs: u64,
+    /// How long (seconds) a signer's confirmed nonce may stay the same while it has
+    /// pending transactions before a warning reports them as stuck (optional).
+    #[serde(default = "eip155_chain_config::default_nonce_stuck_after_secs")]
+    pub nonce_stuck_after_secs: u64,
+    /// How many times a settlement transaction failing for a transient reason, e.g.
+    /// `nonce too low`, is sent again with a fresh nonce (optional).
+    #[serde(default = "eip155_chain_config::default_settlement_retries")]
+    pub settlement_retries: u8,
+    /// Failure reasons to retry in addition to the built-in transient ones, matched
+    /// case-insensitively as substrings of the decoded revert reason (optional).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transient_revert_reasons: Vec<String>,
+    /// How long (seconds) resource servers may cache a successful verification,
+    /// advertised in the `X-Verify-TTL` header of `/verify` responses. `0` disables it.
+    #[serde(default)]
+    pub verify_cache_ttl_secs: u64,
+    /// How the gas limit of settlement transactions is derived from the estimate (optional).
+    #[serde(default)]
+    pub gas_limit: GasLimitConfig,
+    /// Asset addresses allowed on this chain (optional, default: any).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_assets: Vec<String>,
+    /// Minimum amount per asset, in base units (optional).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub min_payment_amount: HashMap<String, DecimalU256>,
+    /// Factory contracts EIP-6492 signatures may deploy the payer's wallet with (optional).
+    /// Unset, the default, allows any factory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_eip6492_factories: Option<HashSet<Address>>,
+    /// How the priority fee of settlement transactions is estimated on EIP-1559 chains
+    /// (optional). Unset, the default, leaves the fees to the provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_fee: Option<PriorityFeeConfig>,
+}
+
+/// Kind of a settlement transaction, selecting its [`GasLimitPolicy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    /// A single payment transfer.
+    #[default]
+    Transfer,
+    /// A payment transfer that first deploys the payer's counterfactual EIP-6492 smart wallet.
+    Eip6492Deployment,
+    /// Several payments aggregated in one Multicall3 transaction.
+    Batch,
+}
+
+/// How the gas limit of a transaction is derived from the node's estimate.
+///
+/// The estimate is multiplied by `multiplier`, then raised to `floor` and capped at
+/// `ceiling`. The default keeps the estimate as is.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GasLimitPolicy {
+    /// Factor applied to the estimate, e.g. `1.2` for 20% headroom. Values below `1.0`
+    /// are treated as `1.0`.
+    #[serde(default = "gas_limit_policy::default_multiplier")]
+    pub multiplier: f64,
+    /// Minimum gas limit (optional).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floor: Option<u64>,
+    /// Maximum gas limit (optional). Takes precedence over `floor`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ceiling: Option<u64>,
+}
+
+impl Default for GasLimitPolicy {
+    fn default() -> Self {
+        Self {
+            multiplier: gas_limit_policy::default_multiplier(),
+            floor: None,
+            ceiling: None,
+        }
+    }
+}
+
+impl GasLimitPolicy {
+    /// Returns the gas limit to set for a transaction estimated at `estimate`.
+    pub fn apply(&self, estimate: u64) -> u64 {
+        let multiplier = self.multiplier.max(1.0);
+        let mut gas_limit = (estimate as f64 * multiplier).ceil() as u64;
+        if let Some(floor) = self.floor {
+            gas_limit = gas_limit.max(floor);
+        }
+        if let Some(ceiling) = self.ceiling {
+            gas_limit = gas_limit.min(ceiling);
+        }
+        gas_limit
+    }
+}
+
+mod gas_limit_policy {
+    pub fn default_multiplier() -> f64 {
+        1.0
+    }
+}
+
+/// Gas limit policies of a chain: a default one and per-[`TransactionKind`] overrides.
+///
+/// Example JSON, adding 20% headroom to transfers and doubling the estimate of
+/// smart wallet deployments:
+/// ```json
+/// {
+///   "gas_limit": {
+///     "multiplier": 1.2,
+///     "floor": 60000,
+///     "ceiling": 2000000,
+///     "overrides": {
+///       "eip6492_deployment": { "multiplier": 2.0, "ceiling": 3000000 }
+///     }
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GasLimitConfig {
+    /// Policy of the transaction kinds without an override.
+    #[serde(flatten)]
+    pub default: GasLimitPolicy,
+    /// Policies replacing the default one for some transaction kinds.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<TransactionKind, GasLimitPolicy>,
+}
+
+impl GasLimitConfig {
+    /// Returns the policy applying to transactions of the given kind.
+    pub fn policy(&self, kind: TransactionKind) -> &GasLimitPolicy {
+        self.overrides.get(&kind).unwrap_or(&self.default)
+    }
+}
+
+/// How the priority fee of EIP-1559 transactions is derived from `eth_feeHistory`.
+///
+/// The fee history reports, for each of the last `blocks` blocks, the `percentile`-th
+/// percentile of the priority fees paid in the block. The priority fee is the median of these,
+/// raised to `floor` and capped at `ceiling`, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriorityFeeConfig {
+    /// Number of recent blocks to sample.
+    #[serde(default = "priority_fee_config::default_blocks")]
+    pub blocks: u64,
+    /// Percentile of the priority fees paid in each block, from `0.0` to `100.0`.
+    #[serde(default = "priority_fee_config::default_percentile")]
+    pub percentile: f64,
+    /// Minimum priority fee (optional).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floor: Option<u128>,
+    /// Maximum priority fee (optional). Takes precedence over `floor`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ceiling: Option<u128>,
+    /// How long (milliseconds) an estimate is reused before querying the fee history again.
+    #[serde(default = "priority_fee_config::default_cache_ttl_ms")]
+    pub cache_ttl_ms: u64,
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            blocks: priority_fee_config::default_blocks(),
+            percentile: priority_fee_config::default_percentile(),
+            floor: None,
+            ceiling: None,
+            cache_ttl_ms: priority_fee_config::default_cache_ttl_ms(),
+        }
+    }
+}
+
+impl PriorityFeeConfig {
+    /// Returns the priority fee given the percentile rewards of the sampled blocks, or `None`
+    /// if there are none.
+    pub fn apply(&self, rewards: &[u128]) -> Option<u128> {
+        if rewards.is_empty() {
+            return None;
+        }
+        let mut rewards = rewards.to_vec();
+        rewards.sort_unstable();
+        let mid = rewards.len() / 2;
+        let mut fee = if rewards.len().is_multiple_of(2) {
+            rewards[mid - 1].midpoint(rewards[mid])
+        } else {
+            rewards[mid]
+        };
+        if let Some(floor) = self.floor {
+            fee = fee.max(floor);
+        }
+        if let Some(ceiling) = self.ceiling {
+            fee = fee.min(ceiling);
+        }
+        Some(fee)
+    }
+}
+
+mod priority_fee_config {
+    pub fn default_blocks() -> u64 {
+        10
+    }
+    pub fn default_percentile() -> f64 {
+        50.0
+    }
+    pub fn default_cache_ttl_ms() -> u64 {
+        2_000
+    }
 }
 
 mod eip155_chain_config {
@@ -95,6 +340,12 @@ mod eip155_chain_config {
     pub fn default_receipt_timeout_secs() -> u64 {
         30
     }
+    pub fn default_nonce_stuck_after_secs() -> u64 {
+        120
+    }
+    pub fn default_settlement_retries() -> u8 {
+        2
+    }
 }
 
 /// RPC provider configuration for a single provider.
@@ -161,3 +412,90 @@ impl FromStr for EvmPrivateKey {
             .map_err(|e| format!("Invalid evm private key: {}", e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gas_limit_policy_applies_multiplier_floor_and_ceiling() {
+        let policy = GasLimitPolicy {
+            multiplier: 1.2,
+            floor: Some(60_000),
+            ceiling: Some(200_000),
+        };
+        assert_eq!(policy.apply(100_000), 120_000);
+        assert_eq!(policy.apply(10_000), 60_000);
+        assert_eq!(policy.apply(500_000), 200_000);
+        assert_eq!(GasLimitPolicy::default().apply(21_000), 21_000);
+        let shrinking = GasLimitPolicy {
+            multiplier: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(shrinking.apply(21_000), 21_000);
+    }
+
+    #[test]
+    fn gas_limit_config_overrides_per_transaction_kind() {
+        let config: GasLimitConfig = serde_json::from_value(serde_json::json!({
+            "multiplier": 1.2,
+            "floor": 60000,
+            "overrides": {
+                "eip6492_deployment": { "multiplier": 2.0 }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            config.policy(TransactionKind::Transfer).apply(100_000),
+            120_000
+        );
+        assert_eq!(config.policy(TransactionKind::Batch).apply(10_000), 60_000);
+        assert_eq!(
+            config
+                .policy(TransactionKind::Eip6492Deployment)
+                .apply(100_000),
+            200_000
+        );
+        assert_eq!(
+            serde_json::from_value::<GasLimitConfig>(serde_json::json!({})).unwrap(),
+            GasLimitConfig::default()
+        );
+    }
+
+    #[test]
+    fn verify_only_chain_needs_no_signers() {
+        let config: Eip155ChainConfigInner = serde_json::from_value(serde_json::json!({
+            "verify_only": true,
+            "rpc": [{ "http": "https://mainnet.base.org" }]
+        }))
+        .unwrap();
+        assert!(config.verify_only);
+        assert!(config.signers.is_empty());
+        let config: Eip155ChainConfigInner = serde_json::from_value(serde_json::json!({
+            "signers": ["0xcafe000000000000000000000000000000000000000000000000000000000001"],
+            "rpc": [{ "http": "https://mainnet.base.org" }]
+        }))
+        .unwrap();
+        assert!(!config.verify_only);
+        assert!(
+            serde_json::to_value(&config)
+                .unwrap()
+                .get("verify_only")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn priority_fee_is_the_median_reward_within_bounds() {
+        let config: PriorityFeeConfig =
+            serde_json::from_value(serde_json::json!({ "floor": 1000, "ceiling": 5000 })).unwrap();
+        assert_eq!(config.blocks, 10);
+        assert_eq!(config.percentile, 50.0);
+        assert_eq!(config.apply(&[3000, 1500, 4000]), Some(3000));
+        assert_eq!(config.apply(&[1500, 2500, 4000, 3000]), Some(2750));
+        assert_eq!(config.apply(&[0, 0, 10]), Some(1000));
+        assert_eq!(config.apply(&[9000, 8000]), Some(5000));
+        assert_eq!(config.apply(&[]), None);
+        assert_eq!(PriorityFeeConfig::default().apply(&[7, 0, 2]), Some(2));
+    }
+}
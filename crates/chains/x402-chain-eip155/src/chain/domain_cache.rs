@@ -0,0 +1,237 @@
+//! Client-side cache of token EIP-712 domains.
+//!
+//! Signing an EIP-3009 authorization or an EIP-2612 permit needs the EIP-712 domain of the
+//! token contract, and its separator hash. [`Eip712DomainCache`] keeps both per
+//! `(chain_id, asset)`, so that a burst of payments to the same token derives them once.
+//! It also remembers the domain `name` and `version` a server sent in the requirements
+//! `extra`, and falls back to it (or to the [known deployment](crate::known_token_deployments)
+//! of the token) when a later server leaves `extra` out.
+//!
+//! The clients share one cache across clones; pass the same cache to several clients with
+//! their `with_domain_cache` method. Entries are replaced when a server sends a different
+//! `name` or `version` for the token, and can be dropped with
+//! [`Eip712DomainCache::invalidate`], e.g. after a token upgrade.
+
+use alloy_primitives::{Address, B256, keccak256};
+use alloy_sol_types::{Eip712Domain, SolStruct, eip712_domain};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::chain::AssetTransferMethod;
+use crate::known_token_deployments;
+use crate::v1_eip155_exact::PaymentRequirementsExtra;
+
+/// The EIP-712 domain of a token contract, with its separator precomputed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenEip712Domain {
+    domain: Eip712Domain,
+    separator: B256,
+}
+
+impl TokenEip712Domain {
+    /// Builds the domain of the token `asset` on chain `chain_id`.
+    pub fn new(chain_id: u64, asset: Address, name: String, version: String) -> Self {
+        let domain = eip712_domain! {
+            name: name,
+            version: version,
+            chain_id: chain_id,
+            verifying_contract: asset,
+        };
+        let separator = domain.separator();
+        Self { domain, separator }
+    }
+
+    /// Returns the EIP-712 domain.
+    pub fn domain(&self) -> &Eip712Domain {
+        &self.domain
+    }
+
+    /// Returns the domain separator.
+    pub fn separator(&self) -> B256 {
+        self.separator
+    }
+
+    /// Returns the EIP-712 signing hash of `value` in this domain.
+    ///
+    /// Equal to `value.eip712_signing_hash(self.domain())`, without hashing the domain again.
+    pub fn signing_hash<T: SolStruct>(&self, value: &T) -> B256 {
+        let mut digest = [0u8; 66];
+        digest[0..2].copy_from_slice(&[0x19, 0x01]);
+        digest[2..34].copy_from_slice(self.separator.as_slice());
+        digest[34..66].copy_from_slice(value.eip712_hash_struct().as_slice());
+        keccak256(digest)
+    }
+
+    fn is(&self, extra: &PaymentRequirementsExtra) -> bool {
+        self.domain.name.as_deref() == Some(extra.name.as_str())
+            && self.domain.version.as_deref() == Some(extra.version.as_str())
+    }
+}
+
+type DomainMap = HashMap<(u64, Address), Arc<TokenEip712Domain>>;
+
+/// Cache of token EIP-712 domains, keyed by `(chain_id, asset)`.
+///
+/// Clones share the cache.
+#[derive(Debug, Clone, Default)]
+pub struct Eip712DomainCache {
+    domains: Arc<RwLock<DomainMap>>,
+}
+
+impl Eip712DomainCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the domain of the token `asset` on chain `chain_id`.
+    ///
+    /// With `extra`, the cached domain is returned if it has the same `name` and `version`,
+    /// and replaced otherwise. Without it, the cached domain is returned, else the domain of
+    /// the known deployment of the token is cached and returned. Returns `None` if the
+    /// domain is unknown.
+    pub fn resolve(
+        &self,
+        chain_id: u64,
+        asset: Address,
+        extra: Option<&PaymentRequirementsExtra>,
+    ) -> Option<Arc<TokenEip712Domain>> {
+        let key = (chain_id, asset);
+        let cached = self
+            .domains
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .cloned();
+        if let Some(cached) = cached
+            && extra.is_none_or(|extra| cached.is(extra))
+        {
+            return Some(cached);
+        }
+        let (name, version) = match extra {
+            Some(extra) => (extra.name.clone(), extra.version.clone()),
+            None => known_domain(chain_id, asset)?,
+        };
+        let domain = Arc::new(TokenEip712Domain::new(chain_id, asset, name, version));
+        self.domains
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, domain.clone());
+        Some(domain)
+    }
+
+    /// Drops the cached domain of the token `asset` on chain `chain_id`.
+    pub fn invalidate(&self, chain_id: u64, asset: Address) {
+        self.domains
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&(chain_id, asset));
+    }
+
+    /// Drops all cached domains.
+    pub fn clear(&self) {
+        self.domains
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Returns the number of cached domains.
+    pub fn len(&self) -> usize {
+        self.domains
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns `true` if no domain is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns the domain `name` and `version` of a known token deployment.
+fn known_domain(chain_id: u64, asset: Address) -> Option<(String, String)> {
+    known_token_deployments()
+        .into_iter()
+        .find(|deployment| {
+            deployment.chain_reference.inner() == chain_id && deployment.address == asset
+        })
+        .map(|deployment| match deployment.transfer_method {
+            AssetTransferMethod::Eip3009 { name, version }
+            | AssetTransferMethod::Permit2 { name, version } => (name, version),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1_eip155_exact::TransferWithAuthorization;
+    use alloy_primitives::{FixedBytes, U256, address};
+    use x402_types::networks::USDC;
+
+    use crate::KnownNetworkEip155;
+    use crate::chain::Eip155TokenDeployment;
+
+    fn extra(name: &str, version: &str) -> PaymentRequirementsExtra {
+        PaymentRequirementsExtra {
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+
+    #[test]
+    fn signing_hash_matches_alloy() {
+        let domain = TokenEip712Domain::new(
+            8453,
+            address!("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+            "USD Coin".into(),
+            "2".into(),
+        );
+        let transfer = TransferWithAuthorization {
+            from: address!("0x857b06519E91e3A54538791bDbb0E22373e36b66"),
+            to: address!("0x209693Bc6afc0C5328bA36FaF03C514EF312287C"),
+            value: U256::from(10_000),
+            validAfter: U256::from(1_700_000_000),
+            validBefore: U256::from(1_700_000_600),
+            nonce: FixedBytes([7; 32]),
+        };
+        assert_eq!(
+            domain.signing_hash(&transfer),
+            transfer.eip712_signing_hash(domain.domain())
+        );
+    }
+
+    #[test]
+    fn domains_are_reused_until_invalidated() {
+        let cache = Eip712DomainCache::new();
+        let asset = address!("0x0000000000000000000000000000000000000402");
+        let first = cache.resolve(1, asset, Some(&extra("Token", "1"))).unwrap();
+        let again = cache.resolve(1, asset, Some(&extra("Token", "1"))).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+
+        // A server leaving `extra` out gets the cached domain
+        let without_extra = cache.resolve(1, asset, None).unwrap();
+        assert!(Arc::ptr_eq(&first, &without_extra));
+
+        // A different domain replaces the cached one
+        let upgraded = cache.resolve(1, asset, Some(&extra("Token", "2"))).unwrap();
+        assert_eq!(upgraded.domain().version.as_deref(), Some("2"));
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate(1, asset);
+        assert!(cache.is_empty());
+        assert!(cache.resolve(1, asset, None).is_none());
+    }
+
+    #[test]
+    fn known_deployments_fill_in_missing_extra() {
+        let cache = Eip712DomainCache::new();
+        let usdc: Eip155TokenDeployment = USDC::base();
+        let domain = cache
+            .resolve(usdc.chain_reference.inner(), usdc.address, None)
+            .unwrap();
+        assert_eq!(domain.domain().name.as_deref(), Some("USD Coin"));
+        assert_eq!(domain.domain().version.as_deref(), Some("2"));
+    }
+}
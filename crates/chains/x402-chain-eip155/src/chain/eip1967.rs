@@ -0,0 +1,56 @@
+//! Detection of EIP-1967 proxies.
+//!
+//! Upgradeable tokens such as FiatToken (USDC) are deployed behind a proxy storing the
+//! address of its implementation in a well-known storage slot. The proxy is the token:
+//! it holds the balances, signatures are made over a domain whose `verifyingContract`
+//! is the proxy, and `name()`/`version()` are read through it. The implementation only
+//! matters to notice upgrades, which may change what these calls return.
+
+use alloy_primitives::{Address, B256, U256, b256};
+use alloy_provider::Provider;
+use alloy_transport::TransportError;
+
+/// Storage slot of the implementation address of an EIP-1967 proxy,
+/// `keccak256("eip1967.proxy.implementation") - 1`.
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// Returns the implementation behind `contract` if it is an EIP-1967 proxy.
+pub async fn eip1967_implementation<P: Provider>(
+    provider: &P,
+    contract: Address,
+) -> Result<Option<Address>, TransportError> {
+    let slot = provider
+        .get_storage_at(contract, EIP1967_IMPLEMENTATION_SLOT.into())
+        .await?;
+    Ok(implementation_from_slot(slot))
+}
+
+/// Decodes the implementation address stored in the EIP-1967 slot, `None` if unset.
+pub fn implementation_from_slot(slot: U256) -> Option<Address> {
+    let implementation = Address::from_word(B256::from(slot));
+    (!implementation.is_zero()).then_some(implementation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, keccak256};
+
+    #[test]
+    fn implementation_slot_is_eip1967() {
+        let hash = U256::from_be_bytes(keccak256("eip1967.proxy.implementation").0);
+        assert_eq!(
+            EIP1967_IMPLEMENTATION_SLOT,
+            B256::from(hash - U256::from(1))
+        );
+    }
+
+    #[test]
+    fn implementation_is_decoded_from_slot() {
+        let implementation = address!("0x2Ce6311ddAE708829bc0784C967b7d77D19FD779");
+        let slot = U256::from_be_slice(implementation.into_word().as_slice());
+        assert_eq!(implementation_from_slot(slot), Some(implementation));
+        assert_eq!(implementation_from_slot(U256::ZERO), None);
+    }
+}
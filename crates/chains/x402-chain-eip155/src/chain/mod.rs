@@ -15,6 +15,9 @@
 //!
 //! - [`types`] - Wire format types like [`ChecksummedAddress`](types::ChecksummedAddress) and [`TokenAmount`](types::TokenAmount)
 //! - [`pending_nonce_manager`] - Nonce management for concurrent transaction submission
+//! - [`eip1967`] - Detection of upgradeable token proxies
+//! - [`revert`] - Classification of failed settlement transactions as transient or permanent
+//! - [`token_capabilities`] - Detection of the ERC-3009 and ERC-2612 support of tokens
 //!
 //! # ERC-3009 Support
 //!
@@ -40,19 +43,33 @@
 #[cfg(feature = "facilitator")]
 pub mod config;
 
+#[cfg(feature = "facilitator")]
+pub mod eip1967;
+
 #[cfg(feature = "facilitator")]
 pub mod pending_nonce_manager;
 #[cfg(feature = "facilitator")]
 pub use pending_nonce_manager::*;
 
+#[cfg(feature = "facilitator")]
+pub mod priority_fee;
 #[cfg(feature = "facilitator")]
 pub mod provider;
 #[cfg(feature = "facilitator")]
+pub mod revert;
+#[cfg(feature = "facilitator")]
+pub mod token_capabilities;
+#[cfg(feature = "facilitator")]
 pub use provider::*;
 
 #[cfg(any(feature = "facilitator", feature = "client"))]
 pub mod erc20;
 
+#[cfg(feature = "client")]
+pub mod domain_cache;
+#[cfg(feature = "client")]
+pub use domain_cache::*;
+
 pub mod types;
 pub use types::*;
 
@@ -4,6 +4,15 @@
 //! upon Alloy's default implementation by querying pending transactions when fetching
 //! the initial nonce. This prevents "nonce too low" errors when the application restarts
 //! while transactions are still in the mempool.
+//!
+//! # Observability
+//!
+//! With the `telemetry` feature, the manager reports the last allocated nonce of each
+//! signer as the `x402.nonce.pending` gauge, and counts resets in the `x402.nonce.resets`
+//! counter, both with a `signer` attribute. When a signer's confirmed nonce stays the
+//! same for longer than the stuck threshold while it has transactions in the mempool,
+//! a warning is logged. [`PendingNonceManager::status`] returns the same information,
+//! e.g. for an admin endpoint.
 
 use alloy_primitives::Address;
 use alloy_provider::Provider;
@@ -11,7 +20,9 @@ use alloy_provider::fillers::NonceManager;
 use alloy_transport::TransportResult;
 use async_trait::async_trait;
 use dashmap::DashMap;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
 /// A nonce manager that caches nonces locally and queries pending transactions on initialization.
@@ -28,16 +39,122 @@ use tokio::sync::Mutex;
 /// - **Per-address tracking**: Each address has its own cached nonce, allowing concurrent
 ///   transaction submission from multiple addresses.
 ///
+/// Every fetch also reads the confirmed nonce of the address, to detect transactions
+/// stuck in the mempool. See [`NonceStatus::stuck`].
+///
 /// # Thread Safety
 ///
 /// The nonce cache is shared across all clones using `Arc<DashMap>`, ensuring that concurrent
 /// requests see consistent nonce values. Each address's nonce is protected by its own `Mutex`
 /// to prevent race conditions during allocation.
-/// ```
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct PendingNonceManager {
-    /// Cache of nonces per address. Each address has its own mutex-protected nonce value.
-    nonces: Arc<DashMap<Address, Arc<Mutex<u64>>>>,
+    /// Cache of nonces per address. Each address has its own mutex-protected state.
+    nonces: Arc<DashMap<Address, Arc<Mutex<NonceState>>>>,
+    /// How long the confirmed nonce may stay the same while transactions are pending.
+    stuck_after: Duration,
+}
+
+/// Nonce tracking state of a signer, as returned by [`PendingNonceManager::status`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NonceStatus {
+    /// Last nonce allocated to a transaction, `None` if the next one will be fetched from
+    /// the chain.
+    pub pending_nonce: Option<u64>,
+    /// Confirmed nonce of the signer when last fetched.
+    pub confirmed_nonce: Option<u64>,
+    /// How long the confirmed nonce has stayed the same, as of the last fetch.
+    pub confirmed_unchanged_for: Option<Duration>,
+    /// Whether the signer had transactions in the mempool at the last fetch.
+    pub has_pending_transactions: bool,
+    /// Number of times the cached nonce was reset after a failed transaction.
+    pub resets: u64,
+    /// Whether the confirmed nonce stayed the same for longer than the stuck threshold
+    /// while the signer had transactions in the mempool.
+    pub stuck: bool,
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    /// Last allocated nonce, `None` until fetched.
+    nonce: Option<u64>,
+    /// Confirmed nonce at the last fetch, and since when it has had that value.
+    confirmed: Option<(u64, Instant)>,
+    /// Pending nonce at the last fetch.
+    pending: Option<u64>,
+    /// Number of resets.
+    resets: u64,
+    /// Whether the current stuck episode was reported.
+    stuck_reported: bool,
+}
+
+impl NonceState {
+    /// Records the confirmed and pending nonces read from the chain at `now`.
+    fn observe(&mut self, confirmed: u64, pending: u64, now: Instant) {
+        match self.confirmed {
+            Some((previous, _)) if previous == confirmed => {}
+            _ => {
+                self.confirmed = Some((confirmed, now));
+                self.stuck_reported = false;
+            }
+        }
+        self.pending = Some(pending);
+    }
+
+    fn status(&self, now: Instant, stuck_after: Duration) -> NonceStatus {
+        let confirmed_unchanged_for = self
+            .confirmed
+            .map(|(_, since)| now.saturating_duration_since(since));
+        let has_pending_transactions = match (self.confirmed, self.pending) {
+            (Some((confirmed, _)), Some(pending)) => pending > confirmed,
+            _ => false,
+        };
+        NonceStatus {
+            pending_nonce: self.nonce,
+            confirmed_nonce: self.confirmed.map(|(confirmed, _)| confirmed),
+            confirmed_unchanged_for,
+            has_pending_transactions,
+            resets: self.resets,
+            stuck: has_pending_transactions
+                && confirmed_unchanged_for.is_some_and(|unchanged| unchanged >= stuck_after),
+        }
+    }
+}
+
+impl PendingNonceManager {
+    /// Default time after which a signer whose confirmed nonce does not advance while it
+    /// has pending transactions is reported as stuck.
+    pub const DEFAULT_STUCK_AFTER: Duration = Duration::from_secs(120);
+
+    /// Creates a nonce manager reporting signers as stuck after `stuck_after`.
+    pub fn new(stuck_after: Duration) -> Self {
+        Self {
+            nonces: Arc::default(),
+            stuck_after,
+        }
+    }
+
+    fn state(&self, address: Address) -> Arc<Mutex<NonceState>> {
+        // Locks dashmap internally for a short duration to clone the `Arc`.
+        // We also don't want to hold the dashmap lock through await points.
+        let rm = self.nonces.entry(address).or_default();
+        Arc::clone(rm.value())
+    }
+
+    /// Returns the nonce tracking state of `address`.
+    pub async fn status(&self, address: Address) -> NonceStatus {
+        let Some(state) = self.nonces.get(&address).map(|rm| Arc::clone(rm.value())) else {
+            return NonceStatus::default();
+        };
+        let state = state.lock().await;
+        state.status(Instant::now(), self.stuck_after)
+    }
+}
+
+impl Default for PendingNonceManager {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_STUCK_AFTER)
+    }
 }
 
 #[async_trait]
@@ -47,48 +164,112 @@ impl NonceManager for PendingNonceManager {
         P: Provider<N>,
         N: alloy_network::Network,
     {
-        // Use `u64::MAX` as a sentinel value to indicate that the nonce has not been fetched yet.
-        const NONE: u64 = u64::MAX;
-
-        // Locks dashmap internally for a short duration to clone the `Arc`.
-        // We also don't want to hold the dashmap lock through the await point below.
-        let nonce = {
-            let rm = self
-                .nonces
-                .entry(address)
-                .or_insert_with(|| Arc::new(Mutex::new(NONE)));
-            Arc::clone(rm.value())
-        };
+        self.next_nonce_with(address, || async {
+            let pending = provider.get_transaction_count(address).pending().await?;
+            let confirmed = provider.get_transaction_count(address).latest().await?;
+            Ok((pending, confirmed))
+        })
+        .await
+    }
+}
 
-        let mut nonce = nonce.lock().await;
-        let new_nonce = if *nonce == NONE {
+impl PendingNonceManager {
+    /// Allocates the next nonce of `address`, as [`NonceManager::get_next_nonce`] does.
+    ///
+    /// When no nonce is cached, `fetch` is called for the pending and confirmed nonces of
+    /// `address`, in that order, and the pending one is allocated. Otherwise the cached
+    /// nonce is incremented. Allocations for the same address are serialized, `fetch`
+    /// included.
+    pub async fn next_nonce_with<F, Fut, E>(&self, address: Address, fetch: F) -> Result<u64, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(u64, u64), E>>,
+    {
+        let state = self.state(address);
+        let mut state = state.lock().await;
+        let new_nonce = match state.nonce {
             // Initialize the nonce if we haven't seen this account before.
-            #[cfg(feature = "telemetry")]
-            tracing::trace!(%address, "fetching nonce");
-            provider.get_transaction_count(address).pending().await?
-        } else {
-            #[cfg(feature = "telemetry")]
-            tracing::trace!(%address, current_nonce = *nonce, "incrementing nonce");
-            *nonce + 1
+            None => {
+                #[cfg(feature = "telemetry")]
+                tracing::trace!(%address, "fetching nonce");
+                let (pending, confirmed) = fetch().await?;
+                state.observe(confirmed, pending, Instant::now());
+                let status = state.status(Instant::now(), self.stuck_after);
+                if status.stuck && !state.stuck_reported {
+                    state.stuck_reported = true;
+                    #[cfg(feature = "telemetry")]
+                    tracing::warn!(
+                        %address,
+                        confirmed_nonce = confirmed,
+                        pending_nonce = pending,
+                        unchanged_secs = status.confirmed_unchanged_for.unwrap_or_default().as_secs(),
+                        "Signer nonce is not advancing, transactions may be stuck in the mempool"
+                    );
+                }
+                pending
+            }
+            Some(nonce) => {
+                #[cfg(feature = "telemetry")]
+                tracing::trace!(%address, current_nonce = nonce, "incrementing nonce");
+                nonce + 1
+            }
         };
-        *nonce = new_nonce;
+        state.nonce = Some(new_nonce);
+        #[cfg(feature = "telemetry")]
+        tracing::info!(signer = %address, gauge.x402.nonce.pending = new_nonce, "Allocated nonce");
         Ok(new_nonce)
     }
-}
 
-impl PendingNonceManager {
     /// Resets the cached nonce for a given address, forcing a fresh query on next use.
     ///
     /// This should be called when a transaction fails, as we cannot be certain of the
     /// actual on-chain state (the transaction may or may not have reached the mempool).
-    /// By resetting to the sentinel value, the next call to `get_next_nonce` will query
-    /// the RPC provider using `.pending()`, which includes mempool transactions.
+    /// After a reset, the next call to `get_next_nonce` will query the RPC provider using
+    /// `.pending()`, which includes mempool transactions.
     pub async fn reset_nonce(&self, address: Address) {
-        if let Some(nonce_lock) = self.nonces.get(&address) {
-            let mut nonce = nonce_lock.lock().await;
-            *nonce = u64::MAX; // NONE sentinel - will trigger fresh query
+        if let Some(state) = self.nonces.get(&address).map(|rm| Arc::clone(rm.value())) {
+            let mut state = state.lock().await;
+            state.nonce = None;
+            state.resets += 1;
             #[cfg(feature = "telemetry")]
-            tracing::debug!(%address, "reset nonce cache, will requery on next use");
+            tracing::info!(
+                signer = %address,
+                monotonic_counter.x402.nonce.resets = 1,
+                "Reset nonce cache, will requery on next use"
+            );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stuck_when_confirmed_nonce_does_not_advance() {
+        let stuck_after = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut state = NonceState::default();
+
+        state.observe(5, 7, start);
+        let status = state.status(start + Duration::from_secs(30), stuck_after);
+        assert!(status.has_pending_transactions);
+        assert!(!status.stuck);
+
+        // Still 5 confirmed with transactions pending a minute later
+        state.observe(5, 8, start + Duration::from_secs(61));
+        let status = state.status(start + Duration::from_secs(61), stuck_after);
+        assert_eq!(status.confirmed_nonce, Some(5));
+        assert_eq!(
+            status.confirmed_unchanged_for,
+            Some(Duration::from_secs(61))
+        );
+        assert!(status.stuck);
+
+        // The confirmed nonce advancing clears the condition
+        state.observe(8, 8, start + Duration::from_secs(90));
+        let status = state.status(start + Duration::from_secs(200), stuck_after);
+        assert!(!status.has_pending_transactions);
+        assert!(!status.stuck);
+    }
+}
@@ -0,0 +1,94 @@
+//! Priority fee estimation from `eth_feeHistory`.
+//!
+//! On EIP-1559 chains, the provider's gas filler picks the fees of settlement transactions
+//! with alloy's default estimator, which can over- or under-tip. With a [`PriorityFeeConfig`],
+//! [`PriorityFeeEstimator`] derives `maxPriorityFeePerGas` from the priority fees recently
+//! paid, as reported by `eth_feeHistory`, and sets `maxFeePerGas` to twice the next block's
+//! base fee plus the priority fee, leaving room for the base fee to rise.
+//!
+//! Estimates are reused for `cache_ttl_ms`, so that a burst of settlements queries the fee
+//! history once.
+
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::BlockNumberOrTag;
+use alloy_transport::TransportError;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::chain::config::PriorityFeeConfig;
+
+/// EIP-1559 fees of a transaction, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// Maximum total fee per gas.
+    pub max_fee_per_gas: u128,
+    /// Maximum priority fee per gas, the tip to the block producer.
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// Estimates EIP-1559 fees with a [`PriorityFeeConfig`], caching the last estimate.
+#[derive(Debug)]
+pub struct PriorityFeeEstimator {
+    config: PriorityFeeConfig,
+    cached: Mutex<Option<(FeeEstimate, Instant)>>,
+}
+
+impl PriorityFeeEstimator {
+    /// Creates an estimator following `config`.
+    pub fn new(config: PriorityFeeConfig) -> Self {
+        Self {
+            config,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Returns the fees of a transaction sent now.
+    ///
+    /// Returns `None` if the node reports no priority fees, e.g. on a chain without
+    /// EIP-1559, in which case the fees are best left to the provider.
+    pub async fn estimate<P: Provider>(
+        &self,
+        provider: &P,
+    ) -> Result<Option<FeeEstimate>, TransportError> {
+        let ttl = Duration::from_millis(self.config.cache_ttl_ms);
+        if let Some((estimate, estimated_at)) =
+            *self.cached.lock().unwrap_or_else(PoisonError::into_inner)
+            && estimated_at.elapsed() < ttl
+        {
+            return Ok(Some(estimate));
+        }
+        let history = provider
+            .get_fee_history(
+                self.config.blocks,
+                BlockNumberOrTag::Latest,
+                &[self.config.percentile],
+            )
+            .await?;
+        let rewards = history
+            .reward
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|block_rewards| block_rewards.first().copied())
+            .collect::<Vec<_>>();
+        let (Some(priority_fee), Some(base_fee)) =
+            (self.config.apply(&rewards), history.next_block_base_fee())
+        else {
+            return Ok(None);
+        };
+        let estimate = FeeEstimate {
+            max_fee_per_gas: base_fee.saturating_mul(2).saturating_add(priority_fee),
+            max_priority_fee_per_gas: priority_fee,
+        };
+        #[cfg(feature = "telemetry")]
+        tracing::debug!(
+            base_fee,
+            max_fee_per_gas = estimate.max_fee_per_gas,
+            max_priority_fee_per_gas = estimate.max_priority_fee_per_gas,
+            "Estimated fees from fee history"
+        );
+        *self.cached.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((estimate, Instant::now()));
+        Ok(Some(estimate))
+    }
+}
@@ -4,7 +4,8 @@ use alloy_provider::fillers::{
     BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller, WalletFiller,
 };
 use alloy_provider::{
-    Identity, PendingTransactionError, Provider, ProviderBuilder, RootProvider, WalletProvider,
+    Identity, MULTICALL3_ADDRESS, PendingTransactionError, Provider, ProviderBuilder, RootProvider,
+    WalletProvider,
 };
 use alloy_rpc_client::RpcClient;
 use alloy_rpc_types_eth::{BlockId, TransactionReceipt, TransactionRequest};
@@ -13,6 +14,7 @@ use alloy_signer_local::PrivateKeySigner;
 use alloy_transport::TransportError;
 use alloy_transport::layers::{FallbackLayer, ThrottleLayer};
 use alloy_transport_http::Http;
+use std::collections::HashSet;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -23,9 +25,12 @@ use x402_types::chain::{ChainId, ChainProviderOps, FromConfig};
 #[cfg(feature = "telemetry")]
 use tracing::Instrument;
 
-use crate::chain::config::{Eip155ChainConfig, RpcConfig};
+use crate::chain::config::{Eip155ChainConfig, GasLimitConfig, RpcConfig, TransactionKind};
 use crate::chain::pending_nonce_manager::PendingNonceManager;
 use crate::chain::permit2::{EXACT_PERMIT2_PROXY_ADDRESS, PERMIT2_ADDRESS};
+use crate::chain::priority_fee::PriorityFeeEstimator;
+use crate::chain::revert::{RevertClass, RevertClassifier};
+use crate::chain::token_capabilities::{TokenCapabilities, TokenCapabilitiesCache};
 use crate::chain::types::Eip155ChainReference;
 use crate::v1_eip155_exact::VALIDATOR_ADDRESS;
 
@@ -71,12 +76,39 @@ pub type InnerProvider = FillProvider<
 /// Uses [`PendingNonceManager`] to track nonces locally and query pending
 /// transactions on initialization. If a transaction fails, the nonce is
 /// automatically reset to force a fresh query on the next transaction.
+///
+/// # Transient Failures
+///
+/// A transaction the node rejects for a transient reason, e.g. `nonce too low` after a
+/// race with another transaction, is sent again with a fresh nonce, up to
+/// `settlement_retries` times. Permanent failures, e.g. a reverting call on an invalid
+/// signature, are returned immediately. See [`RevertClassifier`].
+///
+/// # Verify-Only Mode
+///
+/// A provider configured with `verify_only` holds no signers. It answers every read-only
+/// call verification needs, but [`send_transaction`](Eip155MetaTransactionProvider::send_transaction)
+/// fails with [`MetaTransactionSendError::VerifyOnly`], so no payment is ever settled.
+/// The upto scheme, whose Permit2 witness names the settling facilitator, can not be built
+/// on it.
 #[derive(Debug)]
 pub struct Eip155ChainProvider {
     chain: Eip155ChainReference,
     eip1559: bool,
     flashblocks: bool,
     receipt_timeout_secs: u64,
+    gas_limit: GasLimitConfig,
+    settlement_retries: u8,
+    revert_classifier: RevertClassifier,
+    allowed_eip6492_factories: Option<HashSet<Address>>,
+    /// Fee estimator of EIP-1559 transactions, if a priority fee policy is configured.
+    priority_fee: Option<PriorityFeeEstimator>,
+    /// Whether Multicall3 is deployed, as probed at startup.
+    multicall3_available: bool,
+    /// Whether the provider holds no signers and refuses to send transactions.
+    verify_only: bool,
+    /// Capabilities of the tokens probed so far.
+    token_capabilities: TokenCapabilitiesCache,
     inner: InnerProvider,
     /// Available signer addresses for round-robin selection.
     signer_addresses: Arc<Vec<Address>>,
@@ -118,6 +150,30 @@ impl Eip155ChainProvider {
         RpcClient::new(fallback, false)
     }
 
+    /// Returns the nonce manager of the signers, e.g. to report their
+    /// [`status`](PendingNonceManager::status).
+    pub fn nonce_manager(&self) -> &PendingNonceManager {
+        &self.nonce_manager
+    }
+
+    /// Returns whether the provider only verifies payments, and fails every settlement.
+    pub fn is_verify_only(&self) -> bool {
+        self.verify_only
+    }
+
+    /// Returns which authorization methods the token `asset` supports, e.g. to advertise the
+    /// `exact` scheme only for tokens implementing ERC-3009.
+    ///
+    /// The token bytecode is probed on the first call, see
+    /// [`probe_token_capabilities`](crate::chain::token_capabilities::probe_token_capabilities),
+    /// and cached for the lifetime of the provider.
+    pub async fn token_capabilities(
+        &self,
+        asset: Address,
+    ) -> Result<TokenCapabilities, TransportError> {
+        self.token_capabilities.get(&self.inner, asset).await
+    }
+
     /// Round-robin selection of next signer from wallet.
     fn next_signer_address(&self) -> Address {
         debug_assert!(!self.signer_addresses.is_empty());
@@ -138,7 +194,8 @@ impl Eip155ChainProvider {
 /// # Errors
 ///
 /// Returns an error if:
-/// - No signers are configured
+/// - No signers are configured, unless the chain is verify-only
+/// - Signers are configured for a verify-only chain
 /// - Signer private keys are invalid
 /// - RPC transport initialization fails
 #[async_trait::async_trait]
@@ -154,14 +211,16 @@ impl FromConfig<Eip155ChainConfig> for Eip155ChainProvider {
                     .map(|s| s.with_chain_id(Some(config.chain_reference().inner())))
             })
             .collect::<Result<Vec<_>, _>>()?;
-        if signers.is_empty() {
-            return Err("at least one signer should be provided".into());
-        }
-        let wallet = {
+        let wallet = if config.verify_only() {
+            if !signers.is_empty() {
+                return Err("a verify-only chain takes no signers".into());
+            }
+            EthereumWallet::default()
+        } else {
             let mut iter = signers.into_iter();
             let first_signer = iter
                 .next()
-                .expect("iterator contains at least one element by construction");
+                .ok_or("at least one signer should be provided, unless the chain is verify_only")?;
             let mut wallet = EthereumWallet::from(first_signer);
             for signer in iter {
                 wallet.register_signer(signer);
@@ -178,7 +237,9 @@ impl FromConfig<Eip155ChainConfig> for Eip155ChainProvider {
 
         // 3. Provider
         // Create nonce manager explicitly so we can store a reference for error handling
-        let nonce_manager = PendingNonceManager::default();
+        let nonce_manager = PendingNonceManager::new(std::time::Duration::from_secs(
+            config.nonce_stuck_after_secs(),
+        ));
         // Build the filler stack: Gas -> BlobGas -> Nonce -> ChainId
         // This mirrors the InnerFiller type but with our custom nonce manager
         let filler = JoinFill::new(
@@ -197,15 +258,34 @@ impl FromConfig<Eip155ChainConfig> for Eip155ChainProvider {
             .connect_client(client);
 
         assert_contracts_exists(&inner).await?;
+        let multicall3_available = is_multicall3_deployed(&inner).await?;
+        #[cfg(feature = "telemetry")]
+        if !multicall3_available {
+            tracing::warn!(
+                chain = %config.chain_id(),
+                address = %MULTICALL3_ADDRESS,
+                "Multicall3 is not deployed: EIP-6492 signatures are verified with sequential calls, undeployed wallets are not supported and batches are settled one payment at a time"
+            );
+        }
 
         #[cfg(feature = "telemetry")]
-        tracing::info!(chain=%config.chain_id(), signers=?signer_addresses, "Using EVM provider");
+        tracing::info!(chain=%config.chain_id(), signers=?signer_addresses, verify_only=config.verify_only(), "Using EVM provider");
 
         Ok(Self {
             chain: config.chain_reference(),
             eip1559: config.eip1559(),
             flashblocks: config.flashblocks(),
             receipt_timeout_secs: config.receipt_timeout_secs(),
+            gas_limit: config.gas_limit().clone(),
+            settlement_retries: config.settlement_retries(),
+            revert_classifier: RevertClassifier::new(config.transient_revert_reasons()),
+            allowed_eip6492_factories: config.allowed_eip6492_factories().cloned(),
+            priority_fee: config
+                .priority_fee()
+                .map(|config| PriorityFeeEstimator::new(*config)),
+            multicall3_available,
+            verify_only: config.verify_only(),
+            token_capabilities: TokenCapabilitiesCache::default(),
             inner,
             signer_addresses,
             signer_cursor,
@@ -226,6 +306,14 @@ impl Eip155MetaTransactionProvider for Eip155ChainProvider {
         &self.chain
     }
 
+    fn allowed_eip6492_factories(&self) -> Option<&HashSet<Address>> {
+        self.allowed_eip6492_factories.as_ref()
+    }
+
+    fn multicall3_available(&self) -> bool {
+        self.multicall3_available
+    }
+
     /// Send a meta-transaction with provided `to`, `calldata`, and automatically selected signer.
     ///
     /// This method constructs a transaction from the provided [`MetaTransaction`], automatically
@@ -237,11 +325,23 @@ impl Eip155MetaTransactionProvider for Eip155ChainProvider {
     /// ensures correctness even when transactions partially succeed (e.g., submitted but receipt
     /// fetch times out).
     ///
+    /// A transaction rejected for a transient reason, e.g. `nonce too low`, is sent again
+    /// with a fresh nonce, up to the configured number of settlement retries. Permanent
+    /// failures, and failures while waiting for the receipt, are returned immediately.
+    ///
+    /// A verify-only provider sends nothing, and fails with [`MetaTransactionSendError::VerifyOnly`].
+    ///
     /// # Gas Pricing Strategy
     ///
-    /// - **EIP-1559 networks**: Uses automatic gas pricing via the provider's fillers.
+    /// - **EIP-1559 networks**: Uses automatic gas pricing via the provider's fillers, or the
+    ///   fees of the [`PriorityFeeEstimator`] if a priority fee policy is configured.
     /// - **Legacy networks**: Fetches the current gas price using `get_gas_price()` and sets it explicitly.
     ///
+    /// # Gas Limit
+    ///
+    /// The gas limit is the node's estimate adjusted by the [`GasLimitPolicy`](crate::chain::config::GasLimitPolicy)
+    /// configured for the transaction's [`TransactionKind`].
+    ///
     /// # Timeout Configuration
     ///
     /// Receipt fetching is subject to a configurable timeout:
@@ -267,11 +367,46 @@ impl Eip155MetaTransactionProvider for Eip155ChainProvider {
         &self,
         tx: MetaTransaction,
     ) -> Result<TransactionReceipt, Self::Error> {
+        if self.verify_only {
+            return Err(MetaTransactionSendError::VerifyOnly(self.chain.into()));
+        }
         let from_address = tx.from.unwrap_or_else(|| self.next_signer_address());
+        let mut attempt = 0;
+        loop {
+            match self.send_transaction_once(&tx, from_address).await {
+                Err(MetaTransactionSendError::Transport(error))
+                    if attempt < self.settlement_retries
+                        && self.revert_classifier.classify_error(&error)
+                            == RevertClass::Transient =>
+                {
+                    // The nonce was reset if the transaction was rejected on submission,
+                    // so the next attempt fetches a fresh one.
+                    attempt += 1;
+                    #[cfg(feature = "telemetry")]
+                    tracing::warn!(
+                        signer = %from_address,
+                        attempt,
+                        reason = crate::chain::revert::revert_reason(&error).unwrap_or_default(),
+                        "Transaction failed for a transient reason, retrying"
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Eip155ChainProvider {
+    /// Sends a meta-transaction once from `from_address`, resetting its nonce on failure.
+    async fn send_transaction_once(
+        &self,
+        tx: &MetaTransaction,
+        from_address: Address,
+    ) -> Result<TransactionReceipt, MetaTransactionSendError> {
         let mut txr = TransactionRequest::default()
             .with_to(tx.to)
             .with_from(from_address)
-            .with_input(tx.calldata);
+            .with_input(tx.calldata.clone());
 
         if !self.eip1559 {
             let provider = &self.inner;
@@ -283,6 +418,11 @@ impl Eip155MetaTransactionProvider for Eip155ChainProvider {
             #[cfg(not(feature = "telemetry"))]
             let gas: u128 = gas_fut.await?;
             txr.set_gas_price(gas);
+        } else if let Some(estimator) = &self.priority_fee
+            && let Some(fees) = estimator.estimate(&self.inner).await?
+        {
+            txr.set_max_fee_per_gas(fees.max_fee_per_gas);
+            txr.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
         }
 
         // Estimate gas if not provided
@@ -292,7 +432,10 @@ impl Eip155MetaTransactionProvider for Eip155ChainProvider {
             } else {
                 BlockId::pending()
             };
-            let gas_limit = self.inner.estimate_gas(txr.clone()).block(block_id).await?;
+            let estimate = self.inner.estimate_gas(txr.clone()).block(block_id).await?;
+            let gas_limit = self.gas_limit.policy(tx.kind).apply(estimate);
+            #[cfg(feature = "telemetry")]
+            tracing::info!(kind = ?tx.kind, estimate, gas_limit, "Applying gas limit");
             txr.set_gas_limit(gas_limit)
         }
 
@@ -331,11 +474,25 @@ pub enum MetaTransactionSendError {
     Transport(#[from] TransportError),
     #[error(transparent)]
     PendingTransaction(#[from] PendingTransactionError),
+    /// The provider is verify-only, and holds no signer to send the transaction with.
+    #[error("Settlement is disabled on {0}: the facilitator only verifies payments")]
+    VerifyOnly(ChainId),
     #[allow(dead_code)] // Public for consumption by downstream crates.
     #[error("{0}")]
     Custom(String),
 }
 
+impl MetaTransactionSendError {
+    /// Returns the reason the node rejected the transaction for, with the contract's revert
+    /// reason decoded. See [`revert_reason`](crate::chain::revert::revert_reason).
+    pub fn revert_reason(&self) -> Option<String> {
+        match self {
+            Self::Transport(error) => crate::chain::revert::revert_reason(error),
+            Self::PendingTransaction(_) | Self::VerifyOnly(_) | Self::Custom(_) => None,
+        }
+    }
+}
+
 impl ChainProviderOps for Eip155ChainProvider {
     fn signer_addresses(&self) -> Vec<String> {
         self.inner
@@ -386,6 +543,8 @@ pub struct MetaTransaction {
     pub confirmations: u64,
     /// Optional sender address.
     pub from: Option<Address>,
+    /// Kind of the transaction, selecting its gas limit policy.
+    pub kind: TransactionKind,
 }
 
 impl MetaTransaction {
@@ -395,6 +554,7 @@ impl MetaTransaction {
             calldata,
             confirmations: 1,
             from: None,
+            kind: TransactionKind::Transfer,
         }
     }
 
@@ -402,6 +562,11 @@ impl MetaTransaction {
         self.from = Some(from);
         self
     }
+
+    pub fn with_kind(mut self, kind: TransactionKind) -> Self {
+        self.kind = kind;
+        self
+    }
 }
 
 /// Trait for sending meta-transactions with custom target and calldata.
@@ -416,6 +581,18 @@ pub trait Eip155MetaTransactionProvider {
     /// Returns reference to chain descriptor.
     fn chain(&self) -> &Eip155ChainReference;
 
+    /// Returns the factories EIP-6492 signatures may deploy the payer's wallet with, or
+    /// `None` if any is allowed, see [`assert_eip6492_factory`](crate::v1_eip155_exact::assert_eip6492_factory).
+    fn allowed_eip6492_factories(&self) -> Option<&HashSet<Address>> {
+        None
+    }
+
+    /// Returns whether Multicall3 is deployed at [`MULTICALL3_ADDRESS`] on the chain, see
+    /// [`verify_payment`](crate::v1_eip155_exact::verify_payment).
+    fn multicall3_available(&self) -> bool {
+        true
+    }
+
     /// Sends a meta-transaction to the network.
     fn send_transaction(
         &self,
@@ -435,6 +612,14 @@ impl<T: Eip155MetaTransactionProvider> Eip155MetaTransactionProvider for Arc<T>
         (**self).chain()
     }
 
+    fn allowed_eip6492_factories(&self) -> Option<&HashSet<Address>> {
+        (**self).allowed_eip6492_factories()
+    }
+
+    fn multicall3_available(&self) -> bool {
+        (**self).multicall3_available()
+    }
+
     fn send_transaction(
         &self,
         tx: MetaTransaction,
@@ -443,6 +628,12 @@ impl<T: Eip155MetaTransactionProvider> Eip155MetaTransactionProvider for Arc<T>
     }
 }
 
+/// Returns whether Multicall3 is deployed at [`MULTICALL3_ADDRESS`].
+pub async fn is_multicall3_deployed<P: Provider>(provider: &P) -> Result<bool, TransportError> {
+    let code = provider.get_code_at(MULTICALL3_ADDRESS).await?;
+    Ok(!code.is_empty())
+}
+
 pub async fn assert_contracts_exists<P: Provider>(
     provider: &P,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -457,3 +648,97 @@ pub async fn assert_contracts_exists<P: Provider>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U64;
+    use alloy_transport::mock::Asserter;
+
+    /// A legacy provider signing with a single key, answering from `asserter`.
+    fn provider(asserter: &Asserter) -> Eip155ChainProvider {
+        let signer = PrivateKeySigner::random().with_chain_id(Some(1));
+        let wallet = EthereumWallet::from(signer);
+        let signer_addresses =
+            NetworkWallet::<AlloyEthereum>::signer_addresses(&wallet).collect::<Vec<_>>();
+        let nonce_manager = PendingNonceManager::new(std::time::Duration::from_secs(60));
+        let filler = JoinFill::new(
+            GasFiller::default(),
+            JoinFill::new(
+                BlobGasFiller::default(),
+                JoinFill::new(
+                    NonceFiller::new(nonce_manager.clone()),
+                    ChainIdFiller::default(),
+                ),
+            ),
+        );
+        let inner: InnerProvider = ProviderBuilder::default()
+            .filler(filler)
+            .wallet(wallet)
+            .connect_client(RpcClient::mocked(asserter.clone()));
+        Eip155ChainProvider {
+            chain: Eip155ChainReference::new(1),
+            eip1559: false,
+            flashblocks: false,
+            receipt_timeout_secs: 1,
+            gas_limit: GasLimitConfig::default(),
+            settlement_retries: 2,
+            revert_classifier: RevertClassifier::default(),
+            allowed_eip6492_factories: None,
+            priority_fee: None,
+            multicall3_available: true,
+            verify_only: false,
+            token_capabilities: TokenCapabilitiesCache::default(),
+            inner,
+            signer_addresses: Arc::new(signer_addresses),
+            signer_cursor: Arc::new(AtomicUsize::new(0)),
+            nonce_manager,
+        }
+    }
+
+    /// Queues the answers to the calls preparing a transaction, up to its submission.
+    ///
+    /// These are eth_gasPrice, eth_estimateGas, eth_getTransactionCount for the pending and
+    /// latest blocks, and eth_chainId on the first transaction only. They are all answered
+    /// with 1, so that their order does not matter.
+    fn prepare(asserter: &Asserter, first: bool) {
+        let calls = if first { 5 } else { 4 };
+        for _ in 0..calls {
+            asserter.push_success(&U64::from(1u64));
+        }
+    }
+
+    #[tokio::test]
+    async fn already_known_is_not_sent_again() {
+        let asserter = Asserter::new();
+        let provider = provider(&asserter);
+        prepare(&asserter, true);
+        asserter.push_failure_msg("already known");
+
+        let tx = MetaTransaction::new(Address::ZERO, Bytes::new());
+        let error = provider.send_transaction(tx).await.unwrap_err();
+
+        // A second attempt would have failed on the empty asserter instead
+        assert_eq!(error.revert_reason().as_deref(), Some("already known"));
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test]
+    async fn nonce_too_low_is_sent_again() {
+        let asserter = Asserter::new();
+        let provider = provider(&asserter);
+        prepare(&asserter, true);
+        asserter.push_failure_msg("nonce too low");
+        prepare(&asserter, false);
+        asserter.push_failure_msg("insufficient funds for gas");
+
+        let tx = MetaTransaction::new(Address::ZERO, Bytes::new());
+        let error = provider.send_transaction(tx).await.unwrap_err();
+
+        assert_eq!(
+            error.revert_reason().as_deref(),
+            Some("insufficient funds for gas")
+        );
+        assert!(asserter.read_q().is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+//! Classification of failed settlement transactions.
+//!
+//! A node rejects a transaction that would revert, or that conflicts with the signer's
+//! other transactions, with a JSON-RPC error. Its message carries the node's reason
+//! (e.g. `nonce too low`), and its data the ABI-encoded revert data, from which
+//! [`revert_reason`] decodes the contract's reason, e.g. `FiatTokenV2: invalid signature`.
+//!
+//! [`RevertClassifier`] sorts these reasons into [`RevertClass::Transient`] ones, which
+//! may succeed when the transaction is sent again with a fresh nonce, and
+//! [`RevertClass::Permanent`] ones, which will not.
+//!
+//! A payment simulation that reverts because of the recipient, e.g. a `payTo` contract
+//! that cannot receive the token, is reported by [`simulation_failure`] as
+//! [`PaymentVerificationError::RecipientRejected`], as the payer cannot fix it by signing again.
+
+use alloy_transport::TransportError;
+use x402_types::proto::PaymentVerificationError;
+use x402_types::util::RevertReason;
+
+/// Whether sending a failed transaction again may succeed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevertClass {
+    /// The failure stems from a race or a temporary state, e.g. `nonce too low`.
+    Transient,
+    /// The transaction will keep failing, e.g. on an invalid signature or an insufficient balance.
+    Permanent,
+}
+
+/// Reasons of the failures that may succeed on retry, matched case-insensitively as substrings.
+///
+/// `already known` is not among them: the node already holds the transaction, and sending the
+/// authorization again with a fresh nonce would only revert once the first one is mined.
+pub const TRANSIENT_REVERT_REASONS: &[&str] = &[
+    "nonce too low",
+    "nonce too high",
+    "replacement transaction underpriced",
+    "transaction underpriced",
+    "header not found",
+    "missing trie node",
+];
+
+/// Reasons of the reverts caused by the recipient of a transfer, matched case-insensitively
+/// as substrings.
+pub const RECIPIENT_REVERT_REASONS: &[&str] = &[
+    "recipient",
+    "receiver",
+    "transfer to the zero address",
+    "transfer to non",
+    "invalid to address",
+    "cannot receive",
+];
+
+/// Returns the reason of a failed JSON-RPC call.
+///
+/// The revert data of the error payload is decoded when present, so that a reverted call
+/// yields the contract's reason rather than the node's generic `execution reverted`.
+/// See [`RevertReason`]. Otherwise the payload message is returned. Errors that are not
+/// JSON-RPC error responses, e.g. connection failures, have no reason.
+pub fn revert_reason(error: &TransportError) -> Option<String> {
+    let payload = error.as_error_resp()?;
+    payload
+        .as_revert_data()
+        .and_then(|data| RevertReason::decode(&data))
+        .map(|reason| reason.to_string())
+        .or_else(|| Some(payload.message.to_string()))
+}
+
+/// Appends the decoded reason of the revert data embedded in an error message, if any.
+///
+/// Turns e.g. `execution reverted, data: "0x08c379a0…"` into
+/// `execution reverted, data: "0x08c379a0…" (reason: FiatTokenV2: invalid signature)`.
+pub fn with_revert_reason(message: String) -> String {
+    match RevertReason::find_in(&message) {
+        Some(reason) => format!("{message} (reason: {reason})"),
+        None => message,
+    }
+}
+
+/// Turns a failed payment simulation into a verification error.
+///
+/// The decoded revert reason is appended, as by [`with_revert_reason`]. Reverts whose reason
+/// points at the recipient, see [`RECIPIENT_REVERT_REASONS`], are reported as
+/// [`PaymentVerificationError::RecipientRejected`], and any other failure as
+/// [`PaymentVerificationError::TransactionSimulation`].
+pub fn simulation_failure(message: String) -> PaymentVerificationError {
+    let reason = RevertReason::find_in(&message)
+        .map(|reason| reason.to_string())
+        .unwrap_or_else(|| message.clone())
+        .to_lowercase();
+    let message = with_revert_reason(message);
+    if RECIPIENT_REVERT_REASONS
+        .iter()
+        .any(|pattern| reason.contains(pattern))
+    {
+        PaymentVerificationError::RecipientRejected(message)
+    } else {
+        PaymentVerificationError::TransactionSimulation(message)
+    }
+}
+
+/// Classifies failure reasons as transient or permanent.
+///
+/// Reasons containing one of the [`TRANSIENT_REVERT_REASONS`], or of the extra reasons
+/// configured, are transient. Anything else, including reasons not known to this crate,
+/// is permanent, so that a payment is never retried on an unexpected failure.
+#[derive(Debug, Clone, Default)]
+pub struct RevertClassifier {
+    extra_transient: Vec<String>,
+}
+
+impl RevertClassifier {
+    /// Creates a classifier also treating the given reasons as transient.
+    pub fn new<I, S>(extra_transient: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            extra_transient: extra_transient
+                .into_iter()
+                .map(|reason| reason.as_ref().to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Classifies a failure reason.
+    pub fn classify(&self, reason: &str) -> RevertClass {
+        let reason = reason.to_lowercase();
+        let transient = TRANSIENT_REVERT_REASONS
+            .iter()
+            .copied()
+            .chain(self.extra_transient.iter().map(String::as_str))
+            .any(|pattern| reason.contains(pattern));
+        if transient {
+            RevertClass::Transient
+        } else {
+            RevertClass::Permanent
+        }
+    }
+
+    /// Classifies a failed JSON-RPC call by its [`revert_reason`].
+    ///
+    /// Errors without a reason are permanent.
+    pub fn classify_error(&self, error: &TransportError) -> RevertClass {
+        revert_reason(error)
+            .map(|reason| self.classify(&reason))
+            .unwrap_or(RevertClass::Permanent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::{Revert, SolError};
+    use alloy_transport::RpcError;
+    use alloy_transport::TransportErrorKind;
+
+    fn rpc_error(message: &str, data: Option<String>) -> TransportError {
+        let data = data
+            .map(|data| format!(r#","data":"{data}""#))
+            .unwrap_or_default();
+        let payload =
+            serde_json::from_str(&format!(r#"{{"code":3,"message":"{message}"{data}}}"#)).unwrap();
+        RpcError::ErrorResp(payload)
+    }
+
+    #[test]
+    fn decodes_the_contract_reason() {
+        let data = Revert {
+            reason: "FiatTokenV2: invalid signature".into(),
+        }
+        .abi_encode();
+        let error = rpc_error(
+            "execution reverted",
+            Some(alloy_primitives::hex::encode_prefixed(data)),
+        );
+        assert_eq!(
+            revert_reason(&error).as_deref(),
+            Some("FiatTokenV2: invalid signature")
+        );
+        assert_eq!(
+            RevertClassifier::default().classify_error(&error),
+            RevertClass::Permanent
+        );
+        assert!(
+            with_revert_reason(error.to_string())
+                .ends_with("(reason: FiatTokenV2: invalid signature)")
+        );
+    }
+
+    #[test]
+    fn reports_recipient_rejections() {
+        let data = Revert {
+            reason: "ERC20: transfer to non ERC1363Receiver implementer".into(),
+        }
+        .abi_encode();
+        let error = rpc_error(
+            "execution reverted",
+            Some(alloy_primitives::hex::encode_prefixed(data)),
+        );
+        assert!(matches!(
+            simulation_failure(error.to_string()),
+            PaymentVerificationError::RecipientRejected(message)
+                if message.ends_with("(reason: ERC20: transfer to non ERC1363Receiver implementer)")
+        ));
+        assert!(matches!(
+            simulation_failure("Transfer reverted: ERC20: transfer amount exceeds balance".into()),
+            PaymentVerificationError::TransactionSimulation(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_node_reasons() {
+        let classifier = RevertClassifier::default();
+        let error = rpc_error("nonce too low: next nonce 12, tx nonce 11", None);
+        assert_eq!(classifier.classify_error(&error), RevertClass::Transient);
+        assert_eq!(
+            classifier.classify("ERC20: transfer amount exceeds balance"),
+            RevertClass::Permanent
+        );
+        assert_eq!(
+            classifier.classify_error(&TransportErrorKind::custom_str("connection reset")),
+            RevertClass::Permanent
+        );
+        assert_eq!(classifier.classify("already known"), RevertClass::Permanent);
+        assert_eq!(
+            classifier.classify("known transaction: 0x1234"),
+            RevertClass::Permanent
+        );
+
+        let classifier = RevertClassifier::new(["Temporarily Paused"]);
+        assert_eq!(
+            classifier.classify("execution reverted: temporarily paused"),
+            RevertClass::Transient
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Detection of the authorization methods a token supports.
+//!
+//! The `exact` scheme pays with ERC-3009 `transferWithAuthorization`, which only some tokens,
+//! such as FiatToken (USDC), implement. Other tokens are paid through Permit2, or after an
+//! ERC-2612 `permit`. [`probe_token_capabilities`] tells them apart by scanning the token
+//! bytecode for the function selectors its dispatcher matches, following EIP-1967 proxies to
+//! their implementation.
+//!
+//! The scan is a heuristic: a selector in the bytecode is a strong hint that the function
+//! exists, but a contract may still revert when it is called.
+
+use alloy_primitives::{Address, FixedBytes};
+use alloy_provider::Provider;
+use alloy_sol_types::{SolCall, sol};
+use alloy_transport::TransportError;
+use dashmap::DashMap;
+use std::sync::Arc;
+
+use crate::chain::eip1967::eip1967_implementation;
+
+sol! {
+    interface IErc3009 {
+        function transferWithAuthorization(address from, address to, uint256 value, uint256 validAfter, uint256 validBefore, bytes32 nonce, uint8 v, bytes32 r, bytes32 s) external;
+    }
+
+    interface IErc3009Bytes {
+        function transferWithAuthorization(address from, address to, uint256 value, uint256 validAfter, uint256 validBefore, bytes32 nonce, bytes signature) external;
+    }
+
+    interface IErc2612 {
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+    }
+
+    interface IErc2612Bytes {
+        function permit(address owner, address spender, uint256 value, uint256 deadline, bytes signature) external;
+    }
+}
+
+/// `PUSH4` opcode, with which Solidity and Vyper dispatchers load the selectors they match.
+const PUSH4: u8 = 0x63;
+
+/// Authorization methods a token contract supports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenCapabilities {
+    /// ERC-3009 `transferWithAuthorization`, as the `exact` scheme pays with.
+    pub erc3009: bool,
+    /// ERC-2612 `permit`.
+    pub erc2612_permit: bool,
+    /// The variants of these functions taking the signature as `bytes`, which the token checks
+    /// with EIP-1271 when the payer is a smart contract wallet, e.g. FiatToken v2.2.
+    pub eip1271: bool,
+}
+
+impl TokenCapabilities {
+    /// Returns the capabilities of the contract with the given runtime bytecode.
+    pub fn from_bytecode(code: &[u8]) -> Self {
+        let has = |selector: FixedBytes<4>| {
+            code.windows(5)
+                .any(|window| window[0] == PUSH4 && window[1..] == selector[..])
+        };
+        Self {
+            erc3009: has(IErc3009::transferWithAuthorizationCall::SELECTOR.into()),
+            erc2612_permit: has(IErc2612::permitCall::SELECTOR.into()),
+            eip1271: has(IErc3009Bytes::transferWithAuthorizationCall::SELECTOR.into())
+                || has(IErc2612Bytes::permitCall::SELECTOR.into()),
+        }
+    }
+}
+
+/// Probes the capabilities of the token `asset`, scanning the bytecode of its implementation
+/// if it is an EIP-1967 proxy. Returns `None` if no contract is deployed at `asset`.
+pub async fn probe_token_capabilities<P: Provider>(
+    provider: &P,
+    asset: Address,
+) -> Result<Option<TokenCapabilities>, TransportError> {
+    let code = provider.get_code_at(asset).await?;
+    if code.is_empty() {
+        return Ok(None);
+    }
+    let code = match eip1967_implementation(provider, asset).await? {
+        Some(implementation) => provider.get_code_at(implementation).await?,
+        None => code,
+    };
+    Ok(Some(TokenCapabilities::from_bytecode(&code)))
+}
+
+/// Cache of [`TokenCapabilities`], keyed by token address.
+///
+/// Clones share the cache. Tokens without code are not cached, so that a token deployed
+/// later is probed again.
+#[derive(Debug, Clone, Default)]
+pub struct TokenCapabilitiesCache {
+    capabilities: Arc<DashMap<Address, TokenCapabilities>>,
+}
+
+impl TokenCapabilitiesCache {
+    /// Returns the capabilities of the token `asset`, probing them on the first call.
+    ///
+    /// A token without code has no capabilities.
+    pub async fn get<P: Provider>(
+        &self,
+        provider: &P,
+        asset: Address,
+    ) -> Result<TokenCapabilities, TransportError> {
+        if let Some(capabilities) = self.capabilities.get(&asset) {
+            return Ok(*capabilities);
+        }
+        let Some(capabilities) = probe_token_capabilities(provider, asset).await? else {
+            return Ok(TokenCapabilities::default());
+        };
+        self.capabilities.insert(asset, capabilities);
+        Ok(capabilities)
+    }
+
+    /// Drops the cached capabilities of the token `asset`, e.g. after an upgrade of its proxy.
+    pub fn invalidate(&self, asset: Address) {
+        self.capabilities.remove(&asset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{fixed_bytes, keccak256};
+
+    /// A dispatcher matching the given selectors: `DUP1 PUSH4 <selector> EQ` for each.
+    fn dispatcher(selectors: &[FixedBytes<4>]) -> Vec<u8> {
+        selectors
+            .iter()
+            .flat_map(|selector| {
+                let mut code = vec![0x80, PUSH4];
+                code.extend_from_slice(selector.as_slice());
+                code.push(0x14);
+                code
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selectors_are_the_standard_ones() {
+        assert_eq!(
+            IErc3009::transferWithAuthorizationCall::SELECTOR,
+            fixed_bytes!("0xe3ee160e").0
+        );
+        assert_eq!(IErc2612::permitCall::SELECTOR, fixed_bytes!("0xd505accf").0);
+        assert_eq!(
+            IErc3009Bytes::transferWithAuthorizationCall::SELECTOR,
+            keccak256(
+                "transferWithAuthorization(address,address,uint256,uint256,uint256,bytes32,bytes)"
+            )[..4]
+        );
+    }
+
+    #[test]
+    fn capabilities_are_read_from_the_dispatcher() {
+        let fiat_token = dispatcher(&[
+            IErc3009::transferWithAuthorizationCall::SELECTOR.into(),
+            IErc3009Bytes::transferWithAuthorizationCall::SELECTOR.into(),
+            IErc2612::permitCall::SELECTOR.into(),
+        ]);
+        assert_eq!(
+            TokenCapabilities::from_bytecode(&fiat_token),
+            TokenCapabilities {
+                erc3009: true,
+                erc2612_permit: true,
+                eip1271: true,
+            }
+        );
+
+        let permit_token = dispatcher(&[IErc2612::permitCall::SELECTOR.into()]);
+        let capabilities = TokenCapabilities::from_bytecode(&permit_token);
+        assert!(!capabilities.erc3009);
+        assert!(capabilities.erc2612_permit);
+
+        // A selector outside of a PUSH4 is not matched
+        let data = IErc3009::transferWithAuthorizationCall::SELECTOR.to_vec();
+        assert_eq!(
+            TokenCapabilities::from_bytecode(&data),
+            TokenCapabilities::default()
+        );
+    }
+}
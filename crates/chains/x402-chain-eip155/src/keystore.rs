@@ -0,0 +1,61 @@
+//! Signers from encrypted JSON keystores.
+//!
+//! Geth, ethers and Foundry keep keys in password-protected
+//! [Web3 Secret Storage](https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/)
+//! (keystore v3) files. [`decrypt_keystore`] decrypts such a file into a
+//! [`PrivateKeySigner`], so the key never has to be exported as raw hex. The exact scheme
+//! clients build from a keystore directly with
+//! [`V1Eip155ExactClient::with_keystore`](crate::V1Eip155ExactClient::with_keystore) and
+//! [`V2Eip155ExactClient::with_keystore`](crate::V2Eip155ExactClient::with_keystore).
+//!
+//! # Example
+//!
+//! ```ignore
+//! use x402_chain_eip155::V2Eip155ExactClient;
+//!
+//! let password = std::env::var("KEYSTORE_PASSWORD")?;
+//! let client = V2Eip155ExactClient::with_keystore("keystore.json", password)?;
+//! ```
+
+use alloy_signer_local::PrivateKeySigner;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Error decrypting a keystore.
+#[derive(Debug, thiserror::Error)]
+pub enum KeystoreError {
+    /// The password does not decrypt the keystore.
+    ///
+    /// The keystore MAC only tells that the key derived from the password does not match
+    /// the ciphertext, so a keystore whose ciphertext or MAC was altered fails the same way.
+    #[error("Wrong keystore password")]
+    WrongPassword,
+    /// The keystore file can not be read.
+    #[error("Can not read keystore: {0}")]
+    Io(String),
+    /// The keystore file is not a valid keystore, or holds no valid private key.
+    #[error("Corrupt keystore: {0}")]
+    Corrupt(String),
+}
+
+impl From<eth_keystore::KeystoreError> for KeystoreError {
+    fn from(error: eth_keystore::KeystoreError) -> Self {
+        match error {
+            eth_keystore::KeystoreError::MacMismatch => KeystoreError::WrongPassword,
+            eth_keystore::KeystoreError::StdIo(error) => KeystoreError::Io(error),
+            error => KeystoreError::Corrupt(error.to_string()),
+        }
+    }
+}
+
+/// Decrypts the keystore at `path` with `password` into a signer.
+///
+/// The decrypted key material is zeroized once the signer is built.
+pub fn decrypt_keystore(
+    path: impl AsRef<Path>,
+    password: impl AsRef<[u8]>,
+) -> Result<PrivateKeySigner, KeystoreError> {
+    let secret = Zeroizing::new(eth_keystore::decrypt_key(path, password)?);
+    PrivateKeySigner::from_slice(&secret)
+        .map_err(|e| KeystoreError::Corrupt(format!("Invalid private key: {e}")))
+}
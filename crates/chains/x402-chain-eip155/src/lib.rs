@@ -20,11 +20,13 @@
 //! - [`chain`] - Core EVM chain types, providers, and configuration
 //! - [`v1_eip155_exact`] - V1 protocol implementation with network names
 //! - [`v2_eip155_exact`] - V2 protocol implementation with CAIP-2 chain IDs
+//! - [`v2_eip155_crosschain`] - V2 payments bridged from another chain, with attestations
 //!
 //! # Feature Flags
 //!
 //! - `server` - Server-side price tag generation
-//! - `client` - Client-side payment signing
+//! - `client` - Client-side payment signing, also on `wasm32-unknown-unknown` for browsers
+//! - `keystore` - Client signers from encrypted JSON keystores (keystore v3), see [`keystore`]
 //! - `facilitator` - Facilitator-side payment verification and settlement
 //! - `telemetry` - OpenTelemetry tracing support
 //!
@@ -77,15 +79,19 @@
 
 pub mod chain;
 pub mod v1_eip155_exact;
+pub mod v2_eip155_crosschain;
 pub mod v2_eip155_exact;
 pub mod v2_eip155_upto;
 
 pub mod eip2612_gas_sponsoring;
+#[cfg(feature = "keystore")]
+pub mod keystore;
 mod networks;
 
 pub use networks::*;
 
 pub use v1_eip155_exact::V1Eip155Exact;
+pub use v2_eip155_crosschain::V2Eip155CrossChain;
 pub use v2_eip155_exact::V2Eip155Exact;
 pub use v2_eip155_upto::V2Eip155Upto;
 
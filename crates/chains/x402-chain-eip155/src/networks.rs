@@ -1,4 +1,6 @@
+use alloy_primitives::Address;
 use x402_types::chain::ChainId;
+use x402_types::networks::definitions::{RegisteredToken, TokenTransferMethod, registered_tokens};
 use x402_types::networks::{SBC, USDC};
 
 use crate::chain::{AssetTransferMethod, Eip155ChainReference, Eip155TokenDeployment};
@@ -350,6 +352,65 @@ impl KnownSbcEip155 for SBC {
     }
 }
 
+/// Returns all known token deployments: USDC and SBC on every supported network, then the
+/// EVM tokens registered from network definitions (see [`x402_types::networks::definitions`]).
+pub fn known_token_deployments() -> Vec<Eip155TokenDeployment> {
+    let built_in = [
+        USDC::base(),
+        USDC::base_sepolia(),
+        USDC::polygon(),
+        USDC::polygon_amoy(),
+        USDC::avalanche(),
+        USDC::avalanche_fuji(),
+        USDC::sei(),
+        USDC::sei_testnet(),
+        USDC::xdc(),
+        USDC::xrpl_evm(),
+        USDC::peaq(),
+        USDC::iotex(),
+        USDC::celo(),
+        USDC::celo_sepolia(),
+        SBC::radius(),
+        SBC::radius_testnet(),
+    ];
+    let registered = registered_tokens();
+    built_in
+        .into_iter()
+        .chain(registered.iter().filter_map(registered_deployment))
+        .collect()
+}
+
+/// Converts a registered EVM token to a deployment, skipping tokens of other namespaces.
+fn registered_deployment(token: &RegisteredToken) -> Option<Eip155TokenDeployment> {
+    let chain_reference = Eip155ChainReference::try_from(&token.chain_id).ok()?;
+    let address = token.address.parse().ok()?;
+    let domain = token.eip712.clone()?;
+    let transfer_method = match token.transfer_method {
+        TokenTransferMethod::Eip3009 => AssetTransferMethod::Eip3009 {
+            name: domain.name,
+            version: domain.version,
+        },
+        TokenTransferMethod::Permit2 => AssetTransferMethod::Permit2 {
+            name: domain.name,
+            version: domain.version,
+        },
+    };
+    Some(Eip155TokenDeployment {
+        chain_reference,
+        address,
+        decimals: token.decimals,
+        transfer_method,
+    })
+}
+
+/// Returns the decimals of a known token deployment, see [`known_token_deployments`].
+pub fn known_token_decimals(chain: &Eip155ChainReference, address: &Address) -> Option<u8> {
+    known_token_deployments()
+        .into_iter()
+        .find(|deployment| deployment.chain_reference == *chain && deployment.address == *address)
+        .map(|deployment| deployment.decimals)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -386,4 +447,16 @@ mod tests {
             }
         );
     }
+    #[test]
+    fn known_token_decimals_match_registry() {
+        let base = USDC::base();
+        assert_eq!(
+            known_token_decimals(&base.chain_reference, &base.address),
+            Some(6)
+        );
+        assert_eq!(
+            known_token_decimals(&Eip155ChainReference::new(1), &base.address),
+            None
+        );
+    }
 }
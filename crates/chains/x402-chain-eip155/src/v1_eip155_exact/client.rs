@@ -15,16 +15,17 @@
 
 use alloy_primitives::{Address, FixedBytes, Signature, U256};
 use alloy_signer_local::PrivateKeySigner;
-use alloy_sol_types::{SolStruct, eip712_domain};
 use async_trait::async_trait;
 use rand::{RngExt, rng};
 use std::sync::Arc;
+use std::time::Duration;
 use x402_types::chain::ChainId;
 use x402_types::proto::PaymentRequired;
 use x402_types::proto::v1::X402Version1;
 use x402_types::scheme::X402SchemeId;
 use x402_types::scheme::client::{
-    PaymentCandidate, PaymentCandidateSigner, X402Error, X402SchemeClient,
+    PaymentCandidate, PaymentCandidateSigner, SignerKind, SigningOptions, X402Error,
+    X402SchemeClient,
 };
 use x402_types::timestamp::UnixTimestamp;
 use x402_types::util::Base64Bytes;
@@ -34,7 +35,14 @@ use crate::v1_eip155_exact::{
     TransferWithAuthorization, V1Eip155Exact, types,
 };
 
-use crate::chain::Eip155ChainReference;
+use crate::chain::{Eip155ChainReference, Eip712DomainCache, TokenEip712Domain};
+
+/// How far `validAfter` is set before the time of signing by default, so that a payment is
+/// valid at once even if the payer's clock runs ahead of the facilitator's.
+///
+/// The clock skew only backdates `validAfter`: the authorization still expires after the
+/// validity window, counted from the time of signing.
+pub const DEFAULT_CLOCK_SKEW: Duration = Duration::from_secs(10 * 60);
 
 /// Client for signing V1 EIP-155 exact scheme payments.
 ///
@@ -59,13 +67,51 @@ use crate::chain::Eip155ChainReference;
 #[allow(dead_code)] // Public for consumption by downstream crates.
 pub struct V1Eip155ExactClient<S> {
     signer: S,
+    domains: Eip712DomainCache,
+    clock_skew: Duration,
 }
 
 #[allow(dead_code)] // Public for consumption by downstream crates.
 impl<S> V1Eip155ExactClient<S> {
     /// Creates a new V1 EIP-155 exact scheme client with the given signer.
     pub fn new(signer: S) -> Self {
-        Self { signer }
+        Self {
+            signer,
+            domains: Eip712DomainCache::new(),
+            clock_skew: DEFAULT_CLOCK_SKEW,
+        }
+    }
+
+    /// Sets the clock skew, [`DEFAULT_CLOCK_SKEW`] by default.
+    pub fn with_clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    /// Uses `domains` to cache token EIP-712 domains, e.g. to share one cache with the
+    /// V2 client.
+    pub fn with_domain_cache(mut self, domains: Eip712DomainCache) -> Self {
+        self.domains = domains;
+        self
+    }
+
+    /// Returns the cache of token EIP-712 domains, to invalidate entries.
+    pub fn domain_cache(&self) -> &Eip712DomainCache {
+        &self.domains
+    }
+}
+
+#[cfg(feature = "keystore")]
+impl V1Eip155ExactClient<PrivateKeySigner> {
+    /// Creates a client signing with the key in the encrypted JSON keystore at `path`.
+    ///
+    /// See [`decrypt_keystore`](crate::keystore::decrypt_keystore).
+    pub fn with_keystore(
+        path: impl AsRef<std::path::Path>,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Self, crate::keystore::KeystoreError> {
+        let signer = crate::keystore::decrypt_keystore(path, password)?;
+        Ok(Self::new(signer))
     }
 }
 
@@ -84,6 +130,14 @@ where
     S: SignerLike + Clone + Send + Sync + 'static,
 {
     fn accept(&self, payment_required: &PaymentRequired) -> Vec<PaymentCandidate> {
+        self.accept_with_options(payment_required, &SigningOptions::default())
+    }
+
+    fn accept_with_options(
+        &self,
+        payment_required: &PaymentRequired,
+        options: &SigningOptions,
+    ) -> Vec<PaymentCandidate> {
         let payment_required = match payment_required {
             PaymentRequired::V1(payment_required) => payment_required,
             PaymentRequired::V2(_) => {
@@ -106,6 +160,9 @@ where
                     pay_to: requirements.pay_to.to_string(),
                     signer: Box::new(PayloadSigner {
                         signer: self.signer.clone(),
+                        domains: self.domains.clone(),
+                        options: *options,
+                        clock_skew: self.clock_skew,
                         chain_reference,
                         requirements,
                     }),
@@ -131,6 +188,10 @@ pub struct Eip3009SigningParams {
     pub amount: U256,
     /// Maximum timeout in seconds for the authorization validity window
     pub max_timeout_seconds: u64,
+    /// Time of signing, on the server's clock, see [`SigningOptions::now`]
+    pub now: UnixTimestamp,
+    /// Clock skew, see [`DEFAULT_CLOCK_SKEW`]
+    pub clock_skew: Duration,
     /// Optional EIP-712 domain name and version override
     pub extra: Option<PaymentRequirementsExtra>,
 }
@@ -145,26 +206,36 @@ pub async fn sign_erc3009_authorization<S: SignerLike + Sync>(
     signer: &S,
     params: &Eip3009SigningParams,
 ) -> Result<ExactEvmPayload, X402Error> {
-    // Extract name/version from extra, defaulting to empty strings
-    let (name, version) = match &params.extra {
-        None => ("".to_string(), "".to_string()),
-        Some(extra) => (extra.name.clone(), extra.version.clone()),
-    };
+    sign_erc3009_authorization_cached(signer, params, &Eip712DomainCache::new()).await
+}
 
-    // Build EIP-712 domain
-    let domain = eip712_domain! {
-        name: name,
-        version: version,
-        chain_id: params.chain_id,
-        verifying_contract: params.asset_address,
-    };
+/// Signs an ERC-3009 TransferWithAuthorization like [`sign_erc3009_authorization`], taking
+/// the token EIP-712 domain from `domains`.
+///
+/// Without `extra`, the cached domain or the one of the known deployment of the token is
+/// used, and an empty `name` and `version` if the token is unknown.
+#[allow(dead_code)] // Public for consumption by downstream crates.
+pub async fn sign_erc3009_authorization_cached<S: SignerLike + Sync>(
+    signer: &S,
+    params: &Eip3009SigningParams,
+    domains: &Eip712DomainCache,
+) -> Result<ExactEvmPayload, X402Error> {
+    let domain = domains
+        .resolve(params.chain_id, params.asset_address, params.extra.as_ref())
+        .unwrap_or_else(|| {
+            TokenEip712Domain::new(
+                params.chain_id,
+                params.asset_address,
+                String::new(),
+                String::new(),
+            )
+            .into()
+        });
 
-    // Build authorization with timing
-    let now = UnixTimestamp::now();
-    // valid_after should be in the past (10 minutes ago) to ensure the payment is immediately valid
-    let valid_after_secs = now.as_secs().saturating_sub(10 * 60);
-    let valid_after = UnixTimestamp::from_secs(valid_after_secs);
-    let valid_before = now + params.max_timeout_seconds;
+    // Build authorization with timing: valid_after is in the past, so that the payment is
+    // valid at once even if the facilitator's clock is behind
+    let valid_after = params.now - params.clock_skew;
+    let valid_before = params.now + params.max_timeout_seconds;
     let nonce: [u8; 32] = rng().random();
     let nonce = FixedBytes(nonce);
 
@@ -190,7 +261,7 @@ pub async fn sign_erc3009_authorization<S: SignerLike + Sync>(
         nonce: authorization.nonce,
     };
 
-    let eip712_hash = transfer_with_authorization.eip712_signing_hash(&domain);
+    let eip712_hash = domain.signing_hash(&transfer_with_authorization);
     let signature = signer
         .sign_hash(&eip712_hash)
         .await
@@ -205,6 +276,9 @@ pub async fn sign_erc3009_authorization<S: SignerLike + Sync>(
 #[allow(dead_code)] // Public for consumption by downstream crates.
 struct PayloadSigner<S> {
     signer: S,
+    domains: Eip712DomainCache,
+    options: SigningOptions,
+    clock_skew: Duration,
     chain_reference: Eip155ChainReference,
     requirements: types::PaymentRequirements,
 }
@@ -220,11 +294,16 @@ where
             asset_address: self.requirements.asset,
             pay_to: self.requirements.pay_to,
             amount: self.requirements.max_amount_required,
-            max_timeout_seconds: self.requirements.max_timeout_seconds,
+            max_timeout_seconds: self
+                .options
+                .validity_seconds(self.requirements.max_timeout_seconds),
+            now: self.options.now(),
+            clock_skew: self.clock_skew,
             extra: self.requirements.extra.clone(),
         };
 
-        let evm_payload = sign_erc3009_authorization(&self.signer, &params).await?;
+        let evm_payload =
+            sign_erc3009_authorization_cached(&self.signer, &params, &self.domains).await?;
 
         // Build the payment payload
         let payload = types::PaymentPayload {
@@ -238,6 +317,10 @@ where
 
         Ok(b64.to_string())
     }
+
+    fn kind(&self) -> SignerKind {
+        self.signer.kind()
+    }
 }
 
 /// A trait that abstracts signing operations, allowing both owned signers and Arc-wrapped signers.
@@ -263,6 +346,11 @@ pub trait SignerLike {
 
     /// Signs the given hash.
     async fn sign_hash(&self, hash: &FixedBytes<32>) -> Result<Signature, alloy_signer::Error>;
+
+    /// Returns how this signer signs. Defaults to [`SignerKind::Async`].
+    fn kind(&self) -> SignerKind {
+        SignerKind::Async
+    }
 }
 
 #[async_trait]
@@ -274,6 +362,11 @@ impl SignerLike for PrivateKeySigner {
     async fn sign_hash(&self, hash: &FixedBytes<32>) -> Result<Signature, alloy_signer::Error> {
         alloy_signer::Signer::sign_hash(self, hash).await
     }
+
+    /// Local keys sign without I/O.
+    fn kind(&self) -> SignerKind {
+        SignerKind::Sync
+    }
 }
 
 #[async_trait]
@@ -285,4 +378,83 @@ impl<T: SignerLike + Send + Sync> SignerLike for Arc<T> {
     async fn sign_hash(&self, hash: &FixedBytes<32>) -> Result<Signature, alloy_signer::Error> {
         (**self).sign_hash(hash).await
     }
+
+    fn kind(&self) -> SignerKind {
+        (**self).kind()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[tokio::test]
+    async fn valid_after_is_backdated_by_the_clock_skew() {
+        let now = UnixTimestamp::from_secs(1_700_000_000);
+        let params = Eip3009SigningParams {
+            chain_id: 8453,
+            asset_address: address!("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+            pay_to: address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"),
+            amount: U256::from(10_000),
+            max_timeout_seconds: 60,
+            now,
+            clock_skew: Duration::from_secs(30),
+            extra: None,
+        };
+        let payload = sign_erc3009_authorization(&PrivateKeySigner::random(), &params)
+            .await
+            .unwrap();
+        let authorization = payload.authorization;
+        assert_eq!(
+            authorization.valid_after,
+            UnixTimestamp::from_secs(1_699_999_970)
+        );
+        // The validity window is counted from the time of signing, not from valid_after
+        assert_eq!(
+            authorization.valid_before,
+            UnixTimestamp::from_secs(1_700_000_060)
+        );
+    }
+
+    #[tokio::test]
+    async fn signing_time_follows_the_server_clock() {
+        let client = V1Eip155ExactClient::new(PrivateKeySigner::random());
+        assert_eq!(client.clock_skew, DEFAULT_CLOCK_SKEW);
+        let client = client.with_clock_skew(Duration::ZERO);
+
+        // The local clock runs 20 seconds ahead of the server's
+        let local_time = UnixTimestamp::now();
+        let options =
+            SigningOptions::default().calibrated(local_time - Duration::from_secs(20), local_time);
+        let payment_required = serde_json::json!({
+            "x402Version": 1,
+            "error": "Payment required",
+            "accepts": [{
+                "scheme": "exact",
+                "network": "base",
+                "maxAmountRequired": "10000",
+                "resource": "https://api.example.com/protected",
+                "description": "",
+                "mimeType": "application/json",
+                "payTo": "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+                "maxTimeoutSeconds": 60,
+                "asset": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                "extra": { "name": "USD Coin", "version": "2" },
+            }],
+        });
+        let payment_required =
+            PaymentRequired::V1(serde_json::from_str(&payment_required.to_string()).unwrap());
+        let candidates = client.accept_with_options(&payment_required, &options);
+        let header = candidates[0].sign().await.unwrap();
+        let payload: types::PaymentPayload =
+            serde_json::from_slice(&Base64Bytes::from(header.as_bytes()).decode().unwrap())
+                .unwrap();
+        let authorization = payload.payload.authorization;
+
+        let valid_after = authorization.valid_after.as_secs();
+        let server_time = local_time.as_secs() - 20;
+        assert!((server_time..server_time + 5).contains(&valid_after));
+        assert_eq!(authorization.valid_before.as_secs(), valid_after + 60);
+    }
 }
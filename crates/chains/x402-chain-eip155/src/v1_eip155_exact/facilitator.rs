@@ -8,25 +8,54 @@
 //! - EIP-712 domain construction
 //! - On-chain settlement with gas management
 //! - Smart wallet deployment for counterfactual signatures
+//! - Batch settlement of several payments in one Multicall3 transaction
+//!
+//! # EIP-6492 Factories
+//!
+//! An EIP-6492 signature carries a `factory` address and `factoryCalldata` that deploy the
+//! payer's counterfactual smart wallet, both chosen by the payer. Verification calls the
+//! factory from the [`VALIDATOR_ADDRESS`] contract within a simulation, which has no lasting
+//! side effects. Settlement of a payment from an undeployed wallet sends the factory call in
+//! a transaction from a facilitator signer, so a malicious factory runs with the signer as
+//! caller, and could e.g. spend its gas, or act on token approvals the signer has given.
+//!
+//! Setting `allowed_eip6492_factories` in the chain config restricts EIP-6492 signatures to
+//! known factories: [`verify_payment`] and settlement reject other factories with
+//! [`PaymentVerificationError::InvalidSignature`], before any call. Unset, the default,
+//! any factory is accepted. See [`assert_eip6492_factory`].
+//!
+//! # Deployed Wallets
+//!
+//! Settling an EIP-6492 payment checks with `eth_getCode` whether the payer's wallet is
+//! deployed, to decide whether to deploy it along with the transfer. The
+//! [`DeployedContractCache`] of the facilitator remembers the wallets it deployed, and the
+//! ones found deployed, so that later payments from the same wallet skip the check. Wallets
+//! found undeployed are remembered for [`DeployedContractCache::NOT_DEPLOYED_TTL`] only, as
+//! they may be deployed out-of-band.
 
 use alloy_contract::SolCallBuilder;
-use alloy_primitives::{Address, B256, Bytes, Signature, TxHash, U256, address, hex};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxHash, U256, address};
 use alloy_provider::bindings::IMulticall3;
 use alloy_provider::{
     MULTICALL3_ADDRESS, MulticallError, MulticallItem, PendingTransactionError, Provider,
 };
-use alloy_rpc_types_eth::TransactionReceipt;
+use alloy_rpc_types_eth::{TransactionReceipt, TransactionRequest};
 use alloy_sol_types::{Eip712Domain, SolCall, SolStruct, SolType, eip712_domain, sol};
 use alloy_transport::TransportError;
-use std::collections::HashMap;
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 use x402_types::chain::{ChainId, ChainProviderOps};
 use x402_types::proto;
 use x402_types::proto::{PaymentVerificationError, v1};
 use x402_types::scheme::{
     X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
-use x402_types::timestamp::UnixTimestamp;
+use x402_types::timestamp::{Clock, SystemClock, ValidityWindow};
+use x402_types::util::RevertReason;
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 #[cfg(feature = "telemetry")]
 use tracing::{Instrument, instrument};
@@ -34,12 +63,17 @@ use tracing::{Instrument, instrument};
 use tracing_core::Level;
 
 use crate::V1Eip155Exact;
+use crate::chain::config::TransactionKind;
+use crate::chain::eip1967::eip1967_implementation;
+use crate::chain::revert::{simulation_failure, with_revert_reason};
 use crate::chain::{
     EOASignature, EOASignatureExt, Eip155ChainReference, Eip155MetaTransactionProvider,
     MetaTransaction, MetaTransactionSendError,
 };
+use crate::known_token_decimals;
 use crate::v1_eip155_exact::{
-    ExactScheme, PaymentRequirementsExtra, TransferWithAuthorization, types,
+    EIP6492_MAGIC_SUFFIX, ExactScheme, PaymentRequirementsExtra, Sig6492,
+    TransferWithAuthorization, types,
 };
 
 /// Signature verifier for EIP-6492, EIP-1271, EOA, universally deployed on the supported EVM chains
@@ -72,12 +106,29 @@ where
 ///   and [`ChainProviderOps`]
 pub struct V1Eip155ExactFacilitator<P> {
     provider: P,
+    deployed_contracts: DeployedContractCache,
+    clock: Arc<dyn Clock>,
 }
 
 impl<P> V1Eip155ExactFacilitator<P> {
     /// Creates a new V1 EIP-155 exact scheme facilitator with the given provider.
     pub fn new(provider: P) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            deployed_contracts: DeployedContractCache::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Checks authorization validity windows against `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the number of wallets in the deployment status cache, e.g. for health reports.
+    pub fn deployed_cache_size(&self) -> usize {
+        self.deployed_contracts.len()
     }
 }
 
@@ -92,45 +143,150 @@ where
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let request = types::VerifyRequest::try_from(request)?;
-        let payload = &request.payment_payload;
-        let requirements = &request.payment_requirements;
-        let (contract, payment, eip712_domain) = assert_valid_payment(
-            self.provider.inner(),
-            self.provider.chain(),
-            payload,
-            requirements,
-        )
-        .await?;
+        verify_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = types::VerifyRequest::try_from(request)?;
+            let payload = &request.payment_payload;
+            let requirements = &request.payment_requirements;
+            let (contract, payment, eip712_domain) = assert_valid_payment(
+                self.provider.inner(),
+                self.provider.chain(),
+                payload,
+                requirements,
+                &*self.clock,
+            )
+            .await?;
+            assert_asset_decimals(
+                self.provider.inner(),
+                self.provider.chain(),
+                requirements.asset,
+                declared_decimals,
+            )
+            .await?;
 
-        let payer =
-            verify_payment(self.provider.inner(), &contract, &payment, &eip712_domain).await?;
+            let payer = verify_payment(
+                self.provider.inner(),
+                &contract,
+                &payment,
+                &eip712_domain,
+                self.provider.allowed_eip6492_factories(),
+                self.provider.multicall3_available(),
+            )
+            .await?;
 
-        Ok(v1::VerifyResponse::valid(payer.to_string()).into())
+            Ok(v1::VerifyResponse::valid(payer.to_string()).into())
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let request = types::SettleRequest::try_from(request)?;
-        let payload = &request.payment_payload;
-        let requirements = &request.payment_requirements;
-        let (contract, payment, eip712_domain) = assert_valid_payment(
-            self.provider.inner(),
-            self.provider.chain(),
-            payload,
-            requirements,
-        )
-        .await?;
+        settle_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = types::SettleRequest::try_from(request)?;
+            let payload = &request.payment_payload;
+            let requirements = &request.payment_requirements;
+            let (contract, payment, eip712_domain) = assert_valid_payment(
+                self.provider.inner(),
+                self.provider.chain(),
+                payload,
+                requirements,
+                &*self.clock,
+            )
+            .await?;
+            assert_asset_decimals(
+                self.provider.inner(),
+                self.provider.chain(),
+                requirements.asset,
+                declared_decimals,
+            )
+            .await?;
+
+            let tx_hash = settle_payment(
+                &self.provider,
+                &self.deployed_contracts,
+                &contract,
+                &payment,
+                &eip712_domain,
+            )
+            .await?;
+            Ok(v1::SettleResponse::Success {
+                payer: payment.from.to_string(),
+                transaction: tx_hash.to_string(),
+                network: payload.network.clone(),
+            }
+            .into())
+        })
+        .await
+    }
 
-        let tx_hash = settle_payment(&self.provider, &contract, &payment, &eip712_domain).await?;
-        Ok(v1::SettleResponse::Success {
-            payer: payment.from.to_string(),
-            transaction: tx_hash.to_string(),
-            network: payload.network.clone(),
+    /// Settles all valid payments in a single Multicall3 transaction, or one by one on chains
+    /// without Multicall3, see [`settle_payments_batch`].
+    async fn settle_batch(
+        &self,
+        requests: &[proto::SettleRequest],
+    ) -> Vec<Result<proto::SettleResponse, X402SchemeFacilitatorError>> {
+        let mut results: Vec<Option<Result<proto::SettleResponse, X402SchemeFacilitatorError>>> =
+            requests.iter().map(|_| None).collect();
+        let mut indices = Vec::new();
+        let mut networks = Vec::new();
+        let mut payments = Vec::new();
+        for (index, request) in requests.iter().enumerate() {
+            let validated = async {
+                let declared_decimals = request.asset_decimals();
+                let request = types::SettleRequest::try_from(request)?;
+                let payload = &request.payment_payload;
+                let requirements = &request.payment_requirements;
+                let validated = assert_valid_payment(
+                    self.provider.inner(),
+                    self.provider.chain(),
+                    payload,
+                    requirements,
+                    &*self.clock,
+                )
+                .await?;
+                assert_asset_decimals(
+                    self.provider.inner(),
+                    self.provider.chain(),
+                    requirements.asset,
+                    declared_decimals,
+                )
+                .await?;
+                Ok::<_, X402SchemeFacilitatorError>((payload.network.clone(), validated))
+            }
+            .await;
+            match validated {
+                Ok((network, validated)) => {
+                    indices.push(index);
+                    networks.push(network);
+                    payments.push(validated);
+                }
+                Err(e) => results[index] = Some(Err(e)),
+            }
+        }
+        let settled =
+            settle_payments_batch(&self.provider, &self.deployed_contracts, &payments).await;
+        for (((index, network), (_, payment, _)), settled) in indices
+            .into_iter()
+            .zip(networks)
+            .zip(&payments)
+            .zip(settled)
+        {
+            let response = settled
+                .map_err(X402SchemeFacilitatorError::from)
+                .map(|tx_hash| {
+                    v1::SettleResponse::Success {
+                        payer: payment.from.to_string(),
+                        transaction: tx_hash.to_string(),
+                        network,
+                    }
+                    .into()
+                });
+            results[index] = Some(response);
         }
-        .into())
+        results.into_iter().flatten().collect()
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
@@ -170,10 +326,8 @@ pub struct ExactEvmPayment {
     pub to: Address,
     /// Transfer amount (token units).
     pub value: U256,
-    /// Not valid before this timestamp (inclusive).
-    pub valid_after: UnixTimestamp,
-    /// Not valid at/after this timestamp (exclusive).
-    pub valid_before: UnixTimestamp,
+    /// When the authorization is valid.
+    pub window: ValidityWindow,
     /// Unique 32-byte nonce (prevents replay).
     pub nonce: B256,
     /// Raw signature bytes (EIP-1271 or EIP-6492-wrapped).
@@ -207,8 +361,8 @@ impl ExactEvmPayment {
             from: self.from,
             to: self.to,
             value: self.value,
-            validAfter: U256::from(self.valid_after.as_secs()),
-            validBefore: U256::from(self.valid_before.as_secs()),
+            validAfter: U256::from(self.window.valid_after.as_secs()),
+            validBefore: U256::from(self.window.valid_before.as_secs()),
             nonce: self.nonce,
         };
         let eip712_hash = transfer_with_authorization.eip712_signing_hash(domain);
@@ -253,6 +407,7 @@ async fn assert_valid_payment<'a, P: Provider>(
     chain: &Eip155ChainReference,
     payload: &types::PaymentPayload,
     requirements: &types::PaymentRequirements,
+    clock: &dyn Clock,
 ) -> Result<
     (
         IEIP3009::IEIP3009Instance<&'a P>,
@@ -276,9 +431,8 @@ async fn assert_valid_payment<'a, P: Provider>(
     if authorization.to != requirements.pay_to {
         return Err(PaymentVerificationError::RecipientMismatch.into());
     }
-    let valid_after = authorization.valid_after;
-    let valid_before = authorization.valid_before;
-    assert_time(valid_after, valid_before)?;
+    let window = ValidityWindow::new(authorization.valid_after, authorization.valid_before);
+    assert_time(&window, clock)?;
     let asset_address = requirements.asset;
     let contract = IEIP3009::new(asset_address, provider);
 
@@ -292,8 +446,7 @@ async fn assert_valid_payment<'a, P: Provider>(
         from: authorization.from,
         to: authorization.to,
         value: authorization.value,
-        valid_after: authorization.valid_after,
-        valid_before: authorization.valid_before,
+        window,
         nonce: authorization.nonce,
         signature: payload.payload.signature.clone(),
     };
@@ -301,25 +454,33 @@ async fn assert_valid_payment<'a, P: Provider>(
     Ok((contract, payment, domain))
 }
 
-/// Validates that the current time is within the `validAfter` and `validBefore` bounds.
+/// Seconds an authorization must remain valid for, to account for settlement latency.
+const EXPIRY_GRACE_SECS: u64 = 6;
+
+/// Validates that the current time of `clock` is within the `validAfter` and `validBefore`
+/// bounds.
 ///
 /// Adds a 6-second grace buffer when checking expiration to account for latency.
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub fn assert_time(
-    valid_after: UnixTimestamp,
-    valid_before: UnixTimestamp,
+    window: &ValidityWindow,
+    clock: &dyn Clock,
 ) -> Result<(), PaymentVerificationError> {
-    let now = UnixTimestamp::now();
-    if valid_before < now + 6 {
-        return Err(PaymentVerificationError::Expired);
+    let now = clock.now();
+    let grace = Duration::from_secs(EXPIRY_GRACE_SECS);
+    if window.grace_adjusted_is_valid_at(now, grace) {
+        return Ok(());
     }
-    if valid_after > now {
-        return Err(PaymentVerificationError::Early);
+    if window.remaining_at(now).is_none_or(|left| left < grace) {
+        return Err(PaymentVerificationError::Expired);
     }
-    Ok(())
+    Err(PaymentVerificationError::Early)
 }
 
 /// Constructs the correct EIP-712 domain for signature verification.
+///
+/// The name and version are taken from the requirements' `extra`, else fetched from the
+/// token, see [`token_domain`].
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err, fields(
     network = %chain.as_chain_id(),
     asset = %asset_address
@@ -330,39 +491,9 @@ pub async fn assert_domain<P: Provider>(
     asset_address: &Address,
     extra: &Option<PaymentRequirementsExtra>,
 ) -> Result<Eip712Domain, Eip155ExactError> {
-    let name = extra.as_ref().map(|extra| extra.name.clone());
-    let name = if let Some(name) = name {
-        name
-    } else {
-        let name_b = token_contract.name();
-        let name_fut = name_b.call().into_future();
-        #[cfg(feature = "telemetry")]
-        let name = name_fut
-            .instrument(tracing::info_span!(
-                "fetch_eip712_name",
-                otel.kind = "client",
-            ))
-            .await?;
-        #[cfg(not(feature = "telemetry"))]
-        let name = name_fut.await?;
-        name
-    };
-    let version = extra.as_ref().map(|extra| extra.version.clone());
-    let version = if let Some(version) = version {
-        version
-    } else {
-        let version_b = token_contract.version();
-        let version_fut = version_b.call().into_future();
-        #[cfg(feature = "telemetry")]
-        let version = version_fut
-            .instrument(tracing::info_span!(
-                "fetch_eip712_version",
-                otel.kind = "client",
-            ))
-            .await?;
-        #[cfg(not(feature = "telemetry"))]
-        let version = version_fut.await?;
-        version
+    let (name, version) = match extra {
+        Some(extra) => (extra.name.clone(), extra.version.clone()),
+        None => token_domain(chain, token_contract).await?,
     };
     let domain = eip712_domain! {
         name: name,
@@ -373,6 +504,85 @@ pub async fn assert_domain<P: Provider>(
     Ok(domain)
 }
 
+/// Key of [`TOKEN_DOMAINS`]: chain, token contract and, for proxies, implementation.
+type TokenDomainKey = (Eip155ChainReference, Address, Option<Address>);
+
+/// EIP-712 domain names and versions fetched on-chain.
+///
+/// Proxied tokens are keyed by their implementation too, so that an upgrade, which may
+/// change the name or version, fetches them again.
+static TOKEN_DOMAINS: LazyLock<Mutex<HashMap<TokenDomainKey, (String, String)>>> =
+    LazyLock::new(Default::default);
+
+/// Returns the EIP-712 domain name and version of a token, fetching them once per
+/// implementation.
+///
+/// For an EIP-1967 proxy, such as an upgradeable FiatToken, `name()` and `version()` are
+/// called on the proxy, which is the domain's `verifyingContract`, never on the
+/// implementation. The implementation address is logged for diagnostics.
+pub async fn token_domain<P: Provider>(
+    chain: &Eip155ChainReference,
+    token_contract: &IEIP3009::IEIP3009Instance<P>,
+) -> Result<(String, String), Eip155ExactError> {
+    let token = *token_contract.address();
+    let implementation_fut = eip1967_implementation(token_contract.provider(), token);
+    #[cfg(feature = "telemetry")]
+    let implementation = implementation_fut
+        .instrument(tracing::info_span!(
+            "fetch_eip1967_implementation",
+            otel.kind = "client",
+        ))
+        .await?;
+    #[cfg(not(feature = "telemetry"))]
+    let implementation = implementation_fut.await?;
+    let key = (*chain, token, implementation);
+    let cached = TOKEN_DOMAINS
+        .lock()
+        .expect("token domains lock poisoned")
+        .get(&key)
+        .cloned();
+    if let Some(domain) = cached {
+        return Ok(domain);
+    }
+
+    let name_b = token_contract.name();
+    let name_fut = name_b.call().into_future();
+    #[cfg(feature = "telemetry")]
+    let name = name_fut
+        .instrument(tracing::info_span!(
+            "fetch_eip712_name",
+            otel.kind = "client",
+        ))
+        .await?;
+    #[cfg(not(feature = "telemetry"))]
+    let name = name_fut.await?;
+    let version_b = token_contract.version();
+    let version_fut = version_b.call().into_future();
+    #[cfg(feature = "telemetry")]
+    let version = version_fut
+        .instrument(tracing::info_span!(
+            "fetch_eip712_version",
+            otel.kind = "client",
+        ))
+        .await?;
+    #[cfg(not(feature = "telemetry"))]
+    let version = version_fut.await?;
+
+    #[cfg(feature = "telemetry")]
+    tracing::info!(
+        token = %token,
+        implementation = ?implementation,
+        name = %name,
+        version = %version,
+        "Fetched EIP-712 domain of token"
+    );
+    TOKEN_DOMAINS
+        .lock()
+        .expect("token domains lock poisoned")
+        .insert(key, (name.clone(), version.clone()));
+    Ok((name, version))
+}
+
 /// Checks if the payer has enough on-chain token balance to meet the `maxAmountRequired`.
 ///
 /// Performs an `ERC20.balanceOf()` call using the token contract instance.
@@ -428,6 +638,65 @@ where
     }
 }
 
+/// Token decimals fetched on-chain, per chain and token contract.
+///
+/// Decimals of a deployed token never change, so they are fetched once per process.
+static TOKEN_DECIMALS: LazyLock<Mutex<HashMap<(Eip155ChainReference, Address), u8>>> =
+    LazyLock::new(Default::default);
+
+/// Checks that the token's `decimals()` match the decimals the payment was priced with.
+///
+/// The expected decimals are the ones `declared` in the payment requirements, else the
+/// ones of the known deployment of the token (see [`known_token_decimals`]). The check
+/// is skipped when neither is available. A mismatch means the amount is off by a power
+/// of ten, and is rejected with [`PaymentVerificationError::DecimalsMismatch`].
+#[cfg_attr(feature = "telemetry", instrument(skip_all, err, fields(
+    network = %chain.as_chain_id(),
+    asset = %asset_address
+)))]
+pub async fn assert_asset_decimals<P: Provider>(
+    provider: &P,
+    chain: &Eip155ChainReference,
+    asset_address: Address,
+    declared: Option<u8>,
+) -> Result<(), Eip155ExactError> {
+    let Some(expected) = declared.or_else(|| known_token_decimals(chain, &asset_address)) else {
+        return Ok(());
+    };
+    let key = (*chain, asset_address);
+    let cached = TOKEN_DECIMALS
+        .lock()
+        .expect("token decimals lock poisoned")
+        .get(&key)
+        .copied();
+    let actual = match cached {
+        Some(decimals) => decimals,
+        None => {
+            let contract = IEIP3009::new(asset_address, provider);
+            let decimals_b = contract.decimals();
+            let decimals_fut = decimals_b.call().into_future();
+            #[cfg(feature = "telemetry")]
+            let decimals = decimals_fut
+                .instrument(tracing::info_span!(
+                    "fetch_token_decimals",
+                    otel.kind = "client",
+                ))
+                .await?;
+            #[cfg(not(feature = "telemetry"))]
+            let decimals = decimals_fut.await?;
+            TOKEN_DECIMALS
+                .lock()
+                .expect("token decimals lock poisoned")
+                .insert(key, decimals);
+            decimals
+        }
+    };
+    if actual != expected {
+        return Err(PaymentVerificationError::DecimalsMismatch { expected, actual }.into());
+    }
+    Ok(())
+}
+
 /// Canonical data required to verify a signature.
 #[derive(Debug, Clone)]
 pub struct SignedMessage {
@@ -467,25 +736,6 @@ pub enum StructuredSignature {
     EIP1271(Bytes),
 }
 
-/// The fixed 32-byte magic suffix defined by [EIP-6492](https://eips.ethereum.org/EIPS/eip-6492).
-///
-/// Any signature ending with this constant is treated as a 6492-wrapped
-/// signature; the preceding bytes are ABI-decoded as `(address factory, bytes factoryCalldata, bytes innerSig)`.
-const EIP6492_MAGIC_SUFFIX: [u8; 32] =
-    hex!("6492649264926492649264926492649264926492649264926492649264926492");
-
-sol! {
-    /// Solidity-compatible struct for decoding the prefix of an EIP-6492 signature.
-    ///
-    /// Matches the tuple `(address factory, bytes factoryCalldata, bytes innerSig)`.
-    #[derive(Debug)]
-    struct Sig6492 {
-        address factory;
-        bytes   factoryCalldata;
-        bytes   innerSig;
-    }
-}
-
 #[derive(Debug, thiserror::Error)]
 pub enum StructuredSignatureFormatError {
     #[error(transparent)]
@@ -597,8 +847,8 @@ impl<'a, P: Provider> TransferWithAuthorization0Call<&'a P> {
         let from = payment.from;
         let to = payment.to;
         let value = payment.value;
-        let valid_after = U256::from(payment.valid_after.as_secs());
-        let valid_before = U256::from(payment.valid_before.as_secs());
+        let valid_after = U256::from(payment.window.valid_after.as_secs());
+        let valid_before = U256::from(payment.window.valid_before.as_secs());
         let nonce = payment.nonce;
         let tx = contract.transferWithAuthorization_0(
             from,
@@ -644,8 +894,8 @@ impl<'a, P: Provider> TransferWithAuthorization1Call<&'a P> {
         let from = payment.from;
         let to = payment.to;
         let value = payment.value;
-        let valid_after = U256::from(payment.valid_after.as_secs());
-        let valid_before = U256::from(payment.valid_before.as_secs());
+        let valid_after = U256::from(payment.window.valid_after.as_secs());
+        let valid_before = U256::from(payment.window.valid_before.as_secs());
         let nonce = payment.nonce;
         let v = signature.v_legacy();
         let r = signature.r_bytes();
@@ -722,17 +972,159 @@ pub async fn is_contract_deployed<P: Provider>(
     Ok(!bytes.is_empty())
 }
 
+/// Deployment status of a wallet in a [`DeployedContractCache`].
+#[derive(Debug, Clone, Copy)]
+enum DeploymentStatus {
+    Deployed,
+    /// Not deployed as of the given instant.
+    NotDeployed(Instant),
+}
+
+/// Cache of the deployment status of payer wallets, sparing an `eth_getCode` call per
+/// EIP-6492 settlement.
+///
+/// Deployed wallets stay deployed, and are cached for good. Undeployed ones are cached for
+/// [`Self::NOT_DEPLOYED_TTL`]. Clones share the cache.
+#[derive(Debug, Clone, Default)]
+pub struct DeployedContractCache {
+    statuses: Arc<DashMap<Address, DeploymentStatus>>,
+}
+
+impl DeployedContractCache {
+    /// How long a wallet found undeployed is assumed to stay so.
+    pub const NOT_DEPLOYED_TTL: Duration = Duration::from_secs(10);
+
+    /// Returns whether contract code is present at `address`, calling `eth_getCode` unless
+    /// the status is cached. See [`is_contract_deployed`].
+    pub async fn is_deployed<P: Provider>(
+        &self,
+        provider: &P,
+        address: &Address,
+    ) -> Result<bool, TransportError> {
+        self.is_deployed_with(address, || is_contract_deployed(provider, address))
+            .await
+    }
+
+    /// Returns the cached status of `address`, or looks it up with `lookup` and caches it.
+    async fn is_deployed_with<F, Fut, E>(&self, address: &Address, lookup: F) -> Result<bool, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<bool, E>>,
+    {
+        let cached = self.statuses.get(address).map(|status| *status);
+        match cached {
+            Some(DeploymentStatus::Deployed) => return Ok(true),
+            Some(DeploymentStatus::NotDeployed(checked_at))
+                if checked_at.elapsed() < Self::NOT_DEPLOYED_TTL =>
+            {
+                return Ok(false);
+            }
+            _ => {}
+        }
+        let deployed = lookup().await?;
+        let status = if deployed {
+            DeploymentStatus::Deployed
+        } else {
+            DeploymentStatus::NotDeployed(Instant::now())
+        };
+        self.statuses.insert(*address, status);
+        Ok(deployed)
+    }
+
+    /// Records that `address` is deployed, e.g. after settling a payment that deployed it.
+    pub fn mark_deployed(&self, address: Address) {
+        self.statuses.insert(address, DeploymentStatus::Deployed);
+    }
+
+    /// Returns the number of cached wallets.
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    /// Returns whether no wallet is cached.
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+}
+
+/// Rejects an EIP-6492 signature whose `factory` is not in `allowed_factories`.
+///
+/// Other signatures, and any factory when `allowed_factories` is `None`, are accepted.
+pub fn assert_eip6492_factory(
+    signature: &StructuredSignature,
+    allowed_factories: Option<&HashSet<Address>>,
+) -> Result<(), PaymentVerificationError> {
+    match (signature, allowed_factories) {
+        (StructuredSignature::EIP6492 { factory, .. }, Some(allowed))
+            if !allowed.contains(factory) =>
+        {
+            Err(PaymentVerificationError::InvalidSignature(format!(
+                "Untrusted EIP-6492 factory: {factory}"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Verifies the signature of `payment` by simulating its transfer.
+///
+/// EIP-6492 signatures with a factory outside `allowed_factories`, if set, are rejected
+/// before any call, see [`assert_eip6492_factory`].
+///
+/// An EIP-6492 signature is checked, and its transfer simulated, in a single Multicall3
+/// `aggregate3` call, so that the transfer sees the wallet the check deploys. With
+/// `multicall3_available` unset, on chains without Multicall3, the calls are made one by
+/// one instead. The wallet deployment does not carry over to the transfer simulation, so
+/// payments from undeployed wallets, which settlement deploys through Multicall3, are
+/// rejected.
 pub async fn verify_payment<P: Provider>(
     provider: &P,
     contract: &IEIP3009::IEIP3009Instance<&P>,
     payment: &ExactEvmPayment,
     eip712_domain: &Eip712Domain,
+    allowed_factories: Option<&HashSet<Address>>,
+    multicall3_available: bool,
 ) -> Result<Address, Eip155ExactError> {
     let signed_message = payment.as_signed_message(eip712_domain)?;
+    assert_eip6492_factory(&signed_message.signature, allowed_factories)?;
 
     let payer = signed_message.address;
     let hash = signed_message.hash;
     match signed_message.signature {
+        StructuredSignature::EIP6492 {
+            factory: _,
+            factory_calldata: _,
+            inner,
+            original,
+        } if !multicall3_available => {
+            #[cfg(feature = "telemetry")]
+            tracing::debug!(%payer, "Multicall3 unavailable, verifying EIP-6492 signature with sequential calls");
+            let validator6492 = Validator6492::new(VALIDATOR_ADDRESS, &provider);
+            let is_valid_signature = validator6492
+                .isValidSigWithSideEffects(payer, hash, original)
+                .call()
+                .await
+                .map_err(|e| PaymentVerificationError::InvalidSignature(e.to_string()))?;
+            if !is_valid_signature {
+                return Err(PaymentVerificationError::InvalidSignature(
+                    "Chain reported signature to be invalid".to_string(),
+                )
+                .into());
+            }
+            if !is_contract_deployed(provider, &payer).await? {
+                return Err(PaymentVerificationError::TransactionSimulation(
+                    "Undeployed EIP-6492 wallets are not supported on chains without Multicall3"
+                        .to_string(),
+                )
+                .into());
+            }
+            let transfer_call = TransferWithAuthorization0Call::new(contract, payment, inner).0;
+            transfer_call
+                .tx
+                .call()
+                .await
+                .map_err(|e| simulation_failure(e.to_string()))?;
+        }
         StructuredSignature::EIP6492 {
             factory: _,
             factory_calldata: _,
@@ -776,8 +1168,7 @@ pub async fn verify_payment<P: Provider>(
                 )
                 .into());
             }
-            transfer_result
-                .map_err(|e| PaymentVerificationError::TransactionSimulation(e.to_string()))?;
+            transfer_result.map_err(|e| simulation_failure(e.to_string()))?;
         }
         StructuredSignature::EIP1271(signature) => {
             // It is EIP-1271 signature, which we can pass to the transfer simulation
@@ -829,8 +1220,11 @@ pub async fn verify_payment<P: Provider>(
     Ok(payer)
 }
 
+/// Settles `payment`, deploying the payer's wallet first for a counterfactual EIP-6492
+/// signature. The deployment status of the wallet is read from and recorded in `deployed`.
 pub async fn settle_payment<P, E>(
     provider: &P,
+    deployed: &DeployedContractCache,
     contract: &IEIP3009::IEIP3009Instance<&P::Inner>,
     payment: &ExactEvmPayment,
     eip712_domain: &Eip712Domain,
@@ -840,6 +1234,10 @@ where
     Eip155ExactError: From<E>,
 {
     let signed_message = payment.as_signed_message(eip712_domain)?;
+    assert_eip6492_factory(
+        &signed_message.signature,
+        provider.allowed_eip6492_factories(),
+    )?;
     let payer = payment.from;
     let receipt = match signed_message.signature {
         StructuredSignature::EIP6492 {
@@ -848,7 +1246,7 @@ where
             inner,
             original: _,
         } => {
-            let is_contract_deployed = is_contract_deployed(provider.inner(), &payer).await?;
+            let is_contract_deployed = deployed.is_deployed(provider.inner(), &payer).await?;
             let transfer_call = TransferWithAuthorization0Call::new(contract, payment, inner);
             let transfer_call = transfer_call.0;
             if is_contract_deployed {
@@ -892,7 +1290,8 @@ where
                     calls: vec![deployment_call, transfer_with_authorization_call],
                 };
                 let meta_tx =
-                    MetaTransaction::new(MULTICALL3_ADDRESS, aggregate_call.abi_encode().into());
+                    MetaTransaction::new(MULTICALL3_ADDRESS, aggregate_call.abi_encode().into())
+                        .with_kind(TransactionKind::Eip6492Deployment);
                 let tx_fut = Eip155MetaTransactionProvider::send_transaction(provider, meta_tx);
                 #[cfg(feature = "telemetry")]
                 let receipt = tx_fut
@@ -911,6 +1310,9 @@ where
                     .await?;
                 #[cfg(not(feature = "telemetry"))]
                 let receipt = tx_fut.await?;
+                if receipt.status() {
+                    deployed.mark_deployed(payer);
+                }
                 receipt
             }
         }
@@ -976,6 +1378,231 @@ where
     tx_hash_from_receipt(&receipt)
 }
 
+/// Builds the Multicall3 calls settling a validated payment: the `transferWithAuthorization`
+/// call, preceded by the wallet deployment for counterfactual EIP-6492 signatures.
+///
+/// Every call has `allowFailure` set, so that one failing payment does not revert a batch.
+async fn batch_calls<P, E>(
+    provider: &P,
+    deployed: &DeployedContractCache,
+    contract: &IEIP3009::IEIP3009Instance<&P::Inner>,
+    payment: &ExactEvmPayment,
+    eip712_domain: &Eip712Domain,
+) -> Result<Vec<IMulticall3::Call3>, Eip155ExactError>
+where
+    P: Eip155MetaTransactionProvider<Error = E>,
+    Eip155ExactError: From<E>,
+{
+    let signed_message = payment.as_signed_message(eip712_domain)?;
+    assert_eip6492_factory(
+        &signed_message.signature,
+        provider.allowed_eip6492_factories(),
+    )?;
+    let (target, calldata) = match signed_message.signature {
+        StructuredSignature::EIP6492 {
+            factory,
+            factory_calldata,
+            inner,
+            original: _,
+        } => {
+            let transfer_call = TransferWithAuthorization0Call::new(contract, payment, inner).0;
+            let transfer = IMulticall3::Call3 {
+                allowFailure: true,
+                target: transfer_call.tx.target(),
+                callData: transfer_call.tx.calldata().clone(),
+            };
+            if deployed
+                .is_deployed(provider.inner(), &payment.from)
+                .await?
+            {
+                return Ok(vec![transfer]);
+            }
+            let deployment = IMulticall3::Call3 {
+                allowFailure: true,
+                target: factory,
+                callData: factory_calldata,
+            };
+            return Ok(vec![deployment, transfer]);
+        }
+        StructuredSignature::EIP1271(eip1271_signature) => {
+            let transfer_call =
+                TransferWithAuthorization0Call::new(contract, payment, eip1271_signature).0;
+            (
+                transfer_call.tx.target(),
+                transfer_call.tx.calldata().clone(),
+            )
+        }
+        StructuredSignature::EOA(signature) => {
+            let transfer_call =
+                TransferWithAuthorization1Call::new(contract, payment, signature.into()).0;
+            (
+                transfer_call.tx.target(),
+                transfer_call.tx.calldata().clone(),
+            )
+        }
+    };
+    Ok(vec![IMulticall3::Call3 {
+        allowFailure: true,
+        target,
+        callData: calldata,
+    }])
+}
+
+/// Simulates a Multicall3 `aggregate3` call, returning the outcome of each call.
+async fn simulate_aggregate3<P: Provider>(
+    provider: &P,
+    calls: Vec<IMulticall3::Call3>,
+) -> Result<Vec<IMulticall3::Result>, Eip155ExactError> {
+    let aggregate_call = IMulticall3::aggregate3Call { calls };
+    let tx = TransactionRequest::default()
+        .to(MULTICALL3_ADDRESS)
+        .input(Bytes::from(aggregate_call.abi_encode()).into());
+    let output = provider.call(tx).await?;
+    IMulticall3::aggregate3Call::abi_decode_returns(&output)
+        .map_err(|e| PaymentVerificationError::TransactionSimulation(e.to_string()).into())
+}
+
+/// Collects the `(token, authorizer, nonce)` of every ERC-3009 `AuthorizationUsed` event
+/// in the receipt, i.e. the transfers that went through.
+fn used_authorizations(receipt: &TransactionReceipt) -> HashSet<(Address, Address, B256)> {
+    receipt
+        .inner
+        .logs()
+        .iter()
+        .filter_map(|log| log.log_decode::<IEIP3009::AuthorizationUsed>().ok())
+        .map(|log| {
+            (
+                log.inner.address,
+                log.inner.data.authorizer,
+                log.inner.data.nonce,
+            )
+        })
+        .collect()
+}
+
+/// Settles several validated ERC-3009 payments in a single Multicall3 `aggregate3` transaction.
+///
+/// Payments may mix EOA, EIP-1271 and EIP-6492 signatures. The batch is simulated first,
+/// and payments whose transfer would revert are left out with a
+/// [`PaymentVerificationError::TransactionSimulation`] error. Each call may fail on its
+/// own; payments whose transfer reverted in the mined transaction, as told by the absence
+/// of their `AuthorizationUsed` event, get [`Eip155ExactError::TransactionReverted`].
+///
+/// Returns one result per payment, in order: the shared transaction hash, or the reason
+/// the payment was not settled.
+///
+/// On chains without Multicall3, see [`Eip155MetaTransactionProvider::multicall3_available`],
+/// each payment is settled in a transaction of its own with [`settle_payment`].
+#[cfg_attr(feature = "telemetry", instrument(skip_all, fields(payments = payments.len())))]
+pub async fn settle_payments_batch<P, E>(
+    provider: &P,
+    deployed: &DeployedContractCache,
+    payments: &[(
+        IEIP3009::IEIP3009Instance<&P::Inner>,
+        ExactEvmPayment,
+        Eip712Domain,
+    )],
+) -> Vec<Result<TxHash, Eip155ExactError>>
+where
+    P: Eip155MetaTransactionProvider<Error = E>,
+    Eip155ExactError: From<E>,
+{
+    if !provider.multicall3_available() {
+        let mut results = Vec::with_capacity(payments.len());
+        for (contract, payment, eip712_domain) in payments {
+            results
+                .push(settle_payment(provider, deployed, contract, payment, eip712_domain).await);
+        }
+        return results;
+    }
+    let mut results: Vec<Option<Result<TxHash, Eip155ExactError>>> =
+        payments.iter().map(|_| None).collect();
+    // Payments still in the batch, with their calls; the transfer is always the last call.
+    let mut batch = Vec::with_capacity(payments.len());
+    for (index, (contract, payment, eip712_domain)) in payments.iter().enumerate() {
+        match batch_calls(provider, deployed, contract, payment, eip712_domain).await {
+            Ok(calls) => batch.push((index, calls)),
+            Err(e) => results[index] = Some(Err(e)),
+        }
+    }
+
+    if !batch.is_empty() {
+        let calls = batch.iter().flat_map(|(_, calls)| calls.clone()).collect();
+        match simulate_aggregate3(provider.inner(), calls).await {
+            Ok(simulated) => {
+                let mut offset = 0;
+                batch.retain(|(index, calls)| {
+                    offset += calls.len();
+                    match simulated.get(offset - 1) {
+                        Some(transfer) if transfer.success => true,
+                        transfer => {
+                            let reason = transfer
+                                .map(
+                                    |transfer| match RevertReason::decode(&transfer.returnData) {
+                                        Some(reason) => format!("Transfer reverted: {reason}"),
+                                        None => {
+                                            format!("Transfer reverted: {}", transfer.returnData)
+                                        }
+                                    },
+                                )
+                                .unwrap_or_else(|| "Transfer not simulated".to_string());
+                            results[*index] = Some(Err(simulation_failure(reason).into()));
+                            false
+                        }
+                    }
+                });
+            }
+            Err(e) => {
+                for (index, _) in batch.drain(..) {
+                    results[index] = Some(Err(Eip155ExactError::BatchFailed(e.to_string())));
+                }
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        let calls = batch.iter().flat_map(|(_, calls)| calls.clone()).collect();
+        let aggregate_call = IMulticall3::aggregate3Call { calls };
+        let meta_tx = MetaTransaction::new(MULTICALL3_ADDRESS, aggregate_call.abi_encode().into())
+            .with_kind(TransactionKind::Batch);
+        let settled = match Eip155MetaTransactionProvider::send_transaction(provider, meta_tx).await
+        {
+            Ok(receipt) => tx_hash_from_receipt(&receipt)
+                .map(|tx_hash| (tx_hash, used_authorizations(&receipt))),
+            Err(e) => Err(e.into()),
+        };
+        match settled {
+            Ok((tx_hash, used)) => {
+                for (index, calls) in batch {
+                    let (contract, payment, _) = &payments[index];
+                    let key = (*contract.address(), payment.from, payment.nonce);
+                    results[index] = Some(if used.contains(&key) {
+                        // The transfer went through, so the wallet deployment before it did too
+                        if calls.len() > 1 {
+                            deployed.mark_deployed(payment.from);
+                        }
+                        Ok(tx_hash)
+                    } else {
+                        Err(Eip155ExactError::TransactionReverted(tx_hash))
+                    });
+                }
+            }
+            Err(e) => {
+                for (index, _) in batch {
+                    results[index] = Some(Err(match &e {
+                        Eip155ExactError::TransactionReverted(tx_hash) => {
+                            Eip155ExactError::TransactionReverted(*tx_hash)
+                        }
+                        e => Eip155ExactError::BatchFailed(e.to_string()),
+                    }));
+                }
+            }
+        }
+    }
+
+    results.into_iter().flatten().collect()
+}
+
 /// Extracts the transaction hash from a confirmed receipt, or returns an error if the
 /// transaction reverted.
 ///
@@ -1016,8 +1643,12 @@ pub enum Eip155ExactError {
     PendingTransaction(#[from] PendingTransactionError),
     #[error("Transaction {0} reverted")]
     TransactionReverted(TxHash),
+    #[error("Batch settlement failed: {0}")]
+    BatchFailed(String),
     #[error("Contract call failed: {0}")]
     ContractCall(String),
+    #[error("Settlement is disabled on {0}: the facilitator only verifies payments")]
+    VerifyOnly(ChainId),
     #[error(transparent)]
     PaymentVerification(#[from] PaymentVerificationError),
 }
@@ -1025,10 +1656,14 @@ pub enum Eip155ExactError {
 impl From<Eip155ExactError> for X402SchemeFacilitatorError {
     fn from(value: Eip155ExactError) -> Self {
         match value {
-            Eip155ExactError::Transport(_) => Self::OnchainFailure(value.to_string()),
-            Eip155ExactError::PendingTransaction(_) => Self::OnchainFailure(value.to_string()),
-            Eip155ExactError::TransactionReverted(_) => Self::OnchainFailure(value.to_string()),
-            Eip155ExactError::ContractCall(_) => Self::OnchainFailure(value.to_string()),
+            Eip155ExactError::Transport(_)
+            | Eip155ExactError::PendingTransaction(_)
+            | Eip155ExactError::TransactionReverted(_)
+            | Eip155ExactError::BatchFailed(_)
+            | Eip155ExactError::ContractCall(_) => {
+                Self::OnchainFailure(with_revert_reason(value.to_string()))
+            }
+            Eip155ExactError::VerifyOnly(_) => Self::OnchainFailure(value.to_string()),
             Eip155ExactError::PaymentVerification(e) => Self::PaymentVerification(e),
         }
     }
@@ -1045,6 +1680,7 @@ impl From<MetaTransactionSendError> for Eip155ExactError {
         match e {
             MetaTransactionSendError::Transport(e) => Self::Transport(e),
             MetaTransactionSendError::PendingTransaction(e) => Self::PendingTransaction(e),
+            MetaTransactionSendError::VerifyOnly(chain_id) => Self::VerifyOnly(chain_id),
             MetaTransactionSendError::Custom(e) => Self::ContractCall(e),
         }
     }
@@ -1053,18 +1689,16 @@ impl From<MetaTransactionSendError> for Eip155ExactError {
 impl From<MulticallError> for Eip155ExactError {
     fn from(e: MulticallError) -> Self {
         match e {
-            MulticallError::ValueTx => Self::PaymentVerification(
-                PaymentVerificationError::TransactionSimulation(e.to_string()),
-            ),
-            MulticallError::DecodeError(_) => Self::PaymentVerification(
-                PaymentVerificationError::TransactionSimulation(e.to_string()),
-            ),
-            MulticallError::NoReturnData => Self::PaymentVerification(
-                PaymentVerificationError::TransactionSimulation(e.to_string()),
-            ),
-            MulticallError::CallFailed(_) => Self::PaymentVerification(
-                PaymentVerificationError::TransactionSimulation(e.to_string()),
-            ),
+            MulticallError::ValueTx => Self::PaymentVerification(simulation_failure(e.to_string())),
+            MulticallError::DecodeError(_) => {
+                Self::PaymentVerification(simulation_failure(e.to_string()))
+            }
+            MulticallError::NoReturnData => {
+                Self::PaymentVerification(simulation_failure(e.to_string()))
+            }
+            MulticallError::CallFailed(_) => {
+                Self::PaymentVerification(simulation_failure(e.to_string()))
+            }
             MulticallError::TransportError(transport_error) => Self::Transport(transport_error),
         }
     }
@@ -1084,3 +1718,272 @@ impl From<alloy_contract::Error> for Eip155ExactError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_provider::{ProviderBuilder, RootProvider};
+    use alloy_sol_types::SolValue;
+    use alloy_transport::mock::Asserter;
+    use x402_types::timestamp::{FixedClock, UnixTimestamp};
+
+    use crate::chain::is_multicall3_deployed;
+
+    const USDC_BASE: Address = address!("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913");
+
+    /// Provider of a chain without Multicall3, recording the transactions it sends.
+    ///
+    /// Every transaction succeeds, with a hash counting the transactions sent.
+    struct NoMulticall3Provider {
+        inner: RootProvider,
+        chain: Eip155ChainReference,
+        sent: Mutex<Vec<MetaTransaction>>,
+    }
+
+    impl Eip155MetaTransactionProvider for NoMulticall3Provider {
+        type Error = MetaTransactionSendError;
+        type Inner = RootProvider;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        fn chain(&self) -> &Eip155ChainReference {
+            &self.chain
+        }
+
+        fn multicall3_available(&self) -> bool {
+            false
+        }
+
+        fn send_transaction(
+            &self,
+            tx: MetaTransaction,
+        ) -> impl Future<Output = Result<TransactionReceipt, Self::Error>> + Send {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(tx);
+            let receipt = successful_receipt(B256::with_last_byte(sent.len() as u8));
+            std::future::ready(Ok(receipt))
+        }
+    }
+
+    fn successful_receipt(transaction_hash: B256) -> TransactionReceipt {
+        serde_json::from_value(serde_json::json!({
+            "type": "0x2",
+            "status": "0x1",
+            "cumulativeGasUsed": "0x0",
+            "logs": [],
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "transactionHash": transaction_hash,
+            "transactionIndex": "0x0",
+            "blockHash": B256::ZERO,
+            "blockNumber": "0x1",
+            "gasUsed": "0x0",
+            "effectiveGasPrice": "0x0",
+            "from": Address::ZERO,
+            "to": USDC_BASE,
+            "contractAddress": null,
+        }))
+        .unwrap()
+    }
+
+    fn usdc_domain() -> Eip712Domain {
+        eip712_domain! {
+            name: "USD Coin",
+            version: "2",
+            chain_id: 8453,
+            verifying_contract: USDC_BASE,
+        }
+    }
+
+    /// A payment of 0.25 USDC from `from`, signed with `signature`.
+    fn payment(from: Address, signature: Bytes) -> ExactEvmPayment {
+        ExactEvmPayment {
+            from,
+            to: address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"),
+            value: U256::from(250_000),
+            window: ValidityWindow::new(
+                UnixTimestamp::from_secs(0),
+                UnixTimestamp::from_secs(u64::MAX),
+            ),
+            nonce: B256::repeat_byte(0x01),
+            signature,
+        }
+    }
+
+    fn eip6492_bytes(factory: Address) -> Bytes {
+        let sig6492 = Sig6492 {
+            factory,
+            factoryCalldata: Bytes::from_static(&[0x1f, 0xba, 0xc4, 0x1c]),
+            innerSig: Bytes::from(vec![0x42; 65]),
+        };
+        let mut bytes = sig6492.abi_encode_params();
+        bytes.extend_from_slice(&EIP6492_MAGIC_SUFFIX);
+        Bytes::from(bytes)
+    }
+
+    fn eip6492_signature(factory: Address) -> StructuredSignature {
+        StructuredSignature::try_from(eip6492_bytes(factory)).unwrap()
+    }
+
+    #[test]
+    fn time_is_checked_with_a_grace_buffer_before_expiry() {
+        const VALID_AFTER: u64 = 1_700_000_000;
+        const VALID_BEFORE: u64 = 1_700_000_060;
+        let window = ValidityWindow::new(
+            UnixTimestamp::from_secs(VALID_AFTER),
+            UnixTimestamp::from_secs(VALID_BEFORE),
+        );
+        let at = |now: u64| assert_time(&window, &FixedClock(now));
+
+        assert!(matches!(
+            at(VALID_AFTER - 1),
+            Err(PaymentVerificationError::Early)
+        ));
+        assert!(at(VALID_AFTER).is_ok());
+        assert!(at(VALID_BEFORE - EXPIRY_GRACE_SECS - 1).is_ok());
+        assert!(at(VALID_BEFORE - EXPIRY_GRACE_SECS).is_ok());
+        assert!(matches!(
+            at(VALID_BEFORE - EXPIRY_GRACE_SECS + 1),
+            Err(PaymentVerificationError::Expired)
+        ));
+        assert!(matches!(
+            at(VALID_BEFORE),
+            Err(PaymentVerificationError::Expired)
+        ));
+    }
+
+    #[test]
+    fn eip6492_factories_are_checked_against_the_allowlist() {
+        let trusted = address!("0x4e1DCf7AD4e460CfD30791CCC4F9c8a4f820ec67");
+        let untrusted = address!("0x000000000000000000000000000000000000dEaD");
+        let allowed = HashSet::from([trusted]);
+
+        assert!(assert_eip6492_factory(&eip6492_signature(trusted), Some(&allowed)).is_ok());
+        match assert_eip6492_factory(&eip6492_signature(untrusted), Some(&allowed)) {
+            Err(PaymentVerificationError::InvalidSignature(reason)) => {
+                assert_eq!(reason, format!("Untrusted EIP-6492 factory: {untrusted}"));
+            }
+            other => panic!("expected an untrusted factory, got {other:?}"),
+        }
+
+        // Without an allowlist, any factory is accepted
+        assert!(assert_eip6492_factory(&eip6492_signature(untrusted), None).is_ok());
+        // Signatures without a factory are not affected
+        let eip1271 = StructuredSignature::EIP1271(Bytes::from(vec![0x42; 96]));
+        assert!(assert_eip6492_factory(&eip1271, Some(&allowed)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn eip6492_verification_falls_back_to_sequential_calls_without_multicall3() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        let contract = IEIP3009::new(USDC_BASE, &provider);
+        let eip712_domain = usdc_domain();
+        let payment = payment(
+            address!("0x857b06519E91e3A54538791bDbb0E22373e36b66"),
+            eip6492_bytes(address!("0x4e1DCf7AD4e460CfD30791CCC4F9c8a4f820ec67")),
+        );
+
+        // No code at the Multicall3 address
+        asserter.push_success(&Bytes::new());
+        assert!(!is_multicall3_deployed(&provider).await.unwrap());
+
+        // The signature check, the deployment check of the wallet, then the transfer
+        // simulation, each in its own call. An `aggregate3` call could not decode these.
+        asserter.push_success(&Bytes::from(true.abi_encode()));
+        asserter.push_success(&Bytes::from_static(&[0x60, 0x80]));
+        asserter.push_success(&Bytes::new());
+        let payer = verify_payment(&provider, &contract, &payment, &eip712_domain, None, false)
+            .await
+            .unwrap();
+        assert_eq!(payer, payment.from);
+
+        // Undeployed wallets cannot be settled without Multicall3
+        asserter.push_success(&Bytes::from(true.abi_encode()));
+        asserter.push_success(&Bytes::new());
+        let undeployed =
+            verify_payment(&provider, &contract, &payment, &eip712_domain, None, false).await;
+        assert!(matches!(
+            undeployed,
+            Err(Eip155ExactError::PaymentVerification(
+                PaymentVerificationError::TransactionSimulation(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn batches_are_settled_one_by_one_without_multicall3() {
+        // No call is answered: an `aggregate3` simulation would fail the batch
+        let asserter = Asserter::new();
+        let provider = NoMulticall3Provider {
+            inner: ProviderBuilder::default().connect_mocked_client(asserter.clone()),
+            chain: Eip155ChainReference::new(8453),
+            sent: Mutex::new(Vec::new()),
+        };
+        let contract = IEIP3009::new(USDC_BASE, provider.inner());
+        let payers = [
+            address!("0x857b06519E91e3A54538791bDbb0E22373e36b66"),
+            address!("0x000000000000000000000000000000000000c0De"),
+        ];
+        let payments: Vec<_> = payers
+            .into_iter()
+            .map(|payer| {
+                let signature = Bytes::from(vec![0x42; 65]);
+                (contract.clone(), payment(payer, signature), usdc_domain())
+            })
+            .collect();
+
+        let deployed = DeployedContractCache::default();
+        let results = settle_payments_batch(&provider, &deployed, &payments).await;
+
+        let tx_hashes: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            tx_hashes,
+            vec![B256::with_last_byte(1), B256::with_last_byte(2)]
+        );
+        let sent = provider.sent.lock().unwrap();
+        assert!(sent.iter().all(|tx| tx.to == USDC_BASE));
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deployed_wallets_are_looked_up_once() {
+        let cache = DeployedContractCache::default();
+        let wallet = address!("0x000000000000000000000000000000000000c0De");
+        let lookups = std::sync::atomic::AtomicUsize::new(0);
+        let get_code = || async {
+            lookups.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok::<_, TransportError>(false)
+        };
+
+        // Five settlements from a counterfactual wallet: the first one deploys it
+        for _ in 0..5 {
+            if !cache.is_deployed_with(&wallet, get_code).await.unwrap() {
+                cache.mark_deployed(wallet);
+            }
+        }
+        assert_eq!(lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn undeployed_wallets_are_looked_up_again_after_the_ttl() {
+        let cache = DeployedContractCache::default();
+        let wallet = address!("0x000000000000000000000000000000000000c0De");
+        let not_deployed = || async { Ok::<_, TransportError>(false) };
+        let deployed = || async { Ok::<_, TransportError>(true) };
+
+        assert!(!cache.is_deployed_with(&wallet, not_deployed).await.unwrap());
+        // Within the TTL, the cached status is used
+        assert!(!cache.is_deployed_with(&wallet, deployed).await.unwrap());
+
+        // Deployed out-of-band, found once the TTL is over
+        let expired = Instant::now() - DeployedContractCache::NOT_DEPLOYED_TTL;
+        cache
+            .statuses
+            .insert(wallet, DeploymentStatus::NotDeployed(expired));
+        assert!(cache.is_deployed_with(&wallet, deployed).await.unwrap());
+        assert!(cache.is_deployed_with(&wallet, not_deployed).await.unwrap());
+    }
+}
@@ -3,7 +3,7 @@
 //! This module defines the wire format types for ERC-3009 based payments
 //! on EVM chains using the V1 x402 protocol.
 
-use alloy_primitives::{Address, B256, Bytes, U256};
+use alloy_primitives::{Address, B256, Bytes, U256, hex};
 use serde::{Deserialize, Serialize};
 use x402_types::lit_str;
 use x402_types::proto::v1;
@@ -112,3 +112,23 @@ sol!(
         bytes32 nonce;
     }
 );
+
+/// The fixed 32-byte magic suffix defined by [EIP-6492](https://eips.ethereum.org/EIPS/eip-6492).
+///
+/// Any signature ending with this constant is treated as a 6492-wrapped
+/// signature; the preceding bytes are ABI-decoded as `(address factory, bytes factoryCalldata, bytes innerSig)`.
+pub const EIP6492_MAGIC_SUFFIX: [u8; 32] =
+    hex!("6492649264926492649264926492649264926492649264926492649264926492");
+
+#[cfg(any(feature = "facilitator", feature = "client"))]
+sol! {
+    /// Solidity-compatible struct for the prefix of an EIP-6492 signature.
+    ///
+    /// Matches the tuple `(address factory, bytes factoryCalldata, bytes innerSig)`.
+    #[derive(Debug)]
+    struct Sig6492 {
+        address factory;
+        bytes   factoryCalldata;
+        bytes   innerSig;
+    }
+}
@@ -0,0 +1,78 @@
+//! Pluggable verification of bridge attestations.
+
+use alloy_primitives::{Address, U256};
+use x402_types::proto::PaymentVerificationError;
+
+use crate::chain::MetaTransaction;
+use crate::v2_eip155_crosschain::types::CrossChainPayload;
+
+/// A token transfer to this chain, as proven by a bridge attestation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgedTransfer {
+    /// Account that sent the tokens on the source chain.
+    pub payer: Address,
+    /// Recipient of the tokens on this chain.
+    pub recipient: Address,
+    /// Token received on this chain.
+    pub token: Address,
+    /// Amount received, in atomic units of `token`.
+    pub amount: U256,
+    /// The only account allowed to redeem the transfer, if restricted.
+    pub redeemer: Option<Address>,
+}
+
+/// Verifies the attestations of a bridge, and redeems the transfers they prove.
+///
+/// Implementations check everything that can be checked offline: the attestation
+/// signatures, the source chain and token, and that the message is addressed to this
+/// chain. Whether the transfer was already redeemed is checked by the facilitator, by
+/// simulating the [redemption](AttestationVerifier::redemption).
+#[async_trait::async_trait]
+pub trait AttestationVerifier: Send + Sync {
+    /// Checks the attestation of `payload`, returning the transfer it proves.
+    async fn verify(
+        &self,
+        payload: &CrossChainPayload,
+    ) -> Result<BridgedTransfer, AttestationError>;
+
+    /// Returns the transaction redeeming the transfer of `payload` on this chain,
+    /// delivering the tokens to their recipient.
+    fn redemption(&self, payload: &CrossChainPayload) -> MetaTransaction;
+
+    /// Returns the bridge details advertised in the `supported()` response, if any.
+    fn supported_extra(&self) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+/// Reasons a bridge attestation is rejected.
+#[derive(Debug, thiserror::Error)]
+pub enum AttestationError {
+    /// The bridge message cannot be parsed.
+    #[error("Invalid bridge message: {0}")]
+    InvalidMessage(String),
+    /// The attestation is not signed by the bridge.
+    #[error("Invalid bridge attestation: {0}")]
+    InvalidAttestation(String),
+    /// The message is bound for another chain.
+    #[error("Bridge message is bound for domain {0}")]
+    WrongDestination(u32),
+    /// The message comes from a chain or contract not accepted by the verifier.
+    #[error("Unsupported bridge source: {0}")]
+    UnsupportedSource(String),
+    /// The bridged token is not accepted by the verifier.
+    #[error("Unsupported bridged token: {0}")]
+    UnsupportedToken(String),
+}
+
+impl From<AttestationError> for PaymentVerificationError {
+    fn from(value: AttestationError) -> Self {
+        match value {
+            AttestationError::InvalidMessage(_) => Self::InvalidFormat(value.to_string()),
+            AttestationError::InvalidAttestation(_) => Self::InvalidSignature(value.to_string()),
+            AttestationError::WrongDestination(_) => Self::ChainIdMismatch,
+            AttestationError::UnsupportedSource(_) => Self::UnsupportedChain,
+            AttestationError::UnsupportedToken(_) => Self::AssetNotAllowed,
+        }
+    }
+}
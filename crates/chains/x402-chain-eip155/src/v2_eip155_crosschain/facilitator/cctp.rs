@@ -0,0 +1,484 @@
+//! Attestations of Circle's Cross-Chain Transfer Protocol (CCTP).
+//!
+//! A CCTP transfer burns USDC on the source chain through its `TokenMessenger`, which sends
+//! a message through the source `MessageTransmitter`. Circle's attestation service signs
+//! the message once the burn is final, and the `MessageTransmitter` of the destination
+//! chain accepts the message and its attestation in `receiveMessage`, minting the USDC to
+//! the recipient of the burn.
+//!
+//! [`CctpAttestationVerifier`] checks attestations offline, as the destination
+//! `MessageTransmitter` would, against the configured attesters. It supports CCTP V1
+//! messages carrying a burn message.
+
+use alloy_primitives::{Address, B256, Signature, U256, keccak256};
+use alloy_sol_types::{SolCall, sol};
+use serde::{Deserialize, Serialize};
+
+use crate::chain::MetaTransaction;
+use crate::v2_eip155_crosschain::facilitator::attestation::{
+    AttestationError, AttestationVerifier, BridgedTransfer,
+};
+use crate::v2_eip155_crosschain::types::CrossChainPayload;
+
+sol!(
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    interface IMessageTransmitter {
+        function receiveMessage(bytes calldata message, bytes calldata attestation) external returns (bool success);
+    }
+);
+
+/// Configuration of the CCTP attestation verifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CctpConfig {
+    /// CCTP domain of this chain.
+    pub destination_domain: u32,
+    /// `MessageTransmitter` contract of this chain, receiving the messages.
+    pub message_transmitter: Address,
+    /// `TokenMessenger` contract of this chain, minting the tokens.
+    pub token_messenger: Address,
+    /// Attesters whose signatures are accepted, as enabled on the `MessageTransmitter`.
+    pub attesters: Vec<Address>,
+    /// Number of attester signatures required (optional, default 1).
+    #[serde(default = "CctpConfig::default_threshold")]
+    pub threshold: usize,
+    /// Source chains and tokens accepted.
+    pub routes: Vec<CctpRoute>,
+}
+
+impl CctpConfig {
+    fn default_threshold() -> usize {
+        1
+    }
+}
+
+/// A token accepted from a source chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CctpRoute {
+    /// CCTP domain of the source chain.
+    pub source_domain: u32,
+    /// `TokenMessenger` contract of the source chain, burning the tokens.
+    pub token_messenger: Address,
+    /// Token burnt on the source chain.
+    pub burn_token: Address,
+    /// Token minted on this chain for the burnt token, as named in payment requirements.
+    pub mint_token: Address,
+}
+
+/// Bridge details of the CCTP verifier, advertised in the `supported()` response.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CctpSupportedExtra {
+    /// Always `"cctp"`.
+    pub bridge: String,
+    /// CCTP domain of this chain.
+    pub destination_domain: u32,
+    /// CCTP domains payments are accepted from.
+    pub source_domains: Vec<u32>,
+}
+
+/// Length of the header of a CCTP V1 message, before its body.
+const MESSAGE_HEADER_LENGTH: usize = 116;
+/// Length of a CCTP V1 burn message.
+const BURN_MESSAGE_LENGTH: usize = 132;
+/// Length of an attester signature.
+const SIGNATURE_LENGTH: usize = 65;
+
+/// A CCTP V1 message carrying a burn message.
+///
+/// Addresses are 32-byte words, as CCTP also bridges to non-EVM chains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CctpMessage {
+    pub source_domain: u32,
+    pub destination_domain: u32,
+    pub nonce: u64,
+    /// Contract sending the message on the source chain, a `TokenMessenger`.
+    pub sender: B256,
+    /// Contract receiving the message on the destination chain, a `TokenMessenger`.
+    pub recipient: B256,
+    /// The only account allowed to receive the message, or zero for anyone.
+    pub destination_caller: B256,
+    /// Token burnt on the source chain.
+    pub burn_token: B256,
+    /// Recipient of the minted tokens.
+    pub mint_recipient: B256,
+    pub amount: U256,
+    /// Account that burnt the tokens.
+    pub message_sender: B256,
+}
+
+impl CctpMessage {
+    /// Parses `message`, as emitted in the `MessageSent` event of the source chain.
+    pub fn parse(message: &[u8]) -> Result<Self, AttestationError> {
+        if message.len() != MESSAGE_HEADER_LENGTH + BURN_MESSAGE_LENGTH {
+            return Err(AttestationError::InvalidMessage(format!(
+                "expected a burn message of {} bytes, got {} bytes",
+                MESSAGE_HEADER_LENGTH + BURN_MESSAGE_LENGTH,
+                message.len()
+            )));
+        }
+        let u32_at = |offset: usize| {
+            u32::from_be_bytes(message[offset..offset + 4].try_into().expect("4 bytes"))
+        };
+        let word_at = |offset: usize| B256::from_slice(&message[offset..offset + 32]);
+        let body = MESSAGE_HEADER_LENGTH;
+        let (version, body_version) = (u32_at(0), u32_at(body));
+        if version != 0 || body_version != 0 {
+            return Err(AttestationError::InvalidMessage(format!(
+                "unsupported message version {version}, body version {body_version}"
+            )));
+        }
+        Ok(Self {
+            source_domain: u32_at(4),
+            destination_domain: u32_at(8),
+            nonce: u64::from_be_bytes(message[12..20].try_into().expect("8 bytes")),
+            sender: word_at(20),
+            recipient: word_at(52),
+            destination_caller: word_at(84),
+            burn_token: word_at(body + 4),
+            mint_recipient: word_at(body + 36),
+            amount: U256::from_be_bytes(word_at(body + 68).0),
+            message_sender: word_at(body + 100),
+        })
+    }
+}
+
+/// Verifier of CCTP attestations. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct CctpAttestationVerifier {
+    config: CctpConfig,
+}
+
+impl CctpAttestationVerifier {
+    /// Creates a verifier, failing if the threshold cannot be met by the attesters.
+    pub fn new(config: CctpConfig) -> Result<Self, String> {
+        if config.threshold == 0 || config.threshold > config.attesters.len() {
+            return Err(format!(
+                "CCTP threshold {} cannot be met by {} attesters",
+                config.threshold,
+                config.attesters.len()
+            ));
+        }
+        Ok(Self { config })
+    }
+
+    /// Checks that `attestation` holds `threshold` signatures of `message` by distinct
+    /// attesters, ordered by increasing attester address as CCTP requires.
+    pub fn assert_attested(
+        &self,
+        message: &[u8],
+        attestation: &[u8],
+    ) -> Result<(), AttestationError> {
+        let threshold = self.config.threshold;
+        if attestation.len() != threshold * SIGNATURE_LENGTH {
+            return Err(AttestationError::InvalidAttestation(format!(
+                "expected {threshold} signatures, got {} bytes",
+                attestation.len()
+            )));
+        }
+        let digest = keccak256(message);
+        let mut previous = Address::ZERO;
+        for signature in attestation.chunks(SIGNATURE_LENGTH) {
+            let signature = Signature::from_raw(signature)
+                .map_err(|e| AttestationError::InvalidAttestation(e.to_string()))?;
+            // `receiveMessage` rejects malleable signatures
+            if signature.normalize_s().is_some() {
+                return Err(AttestationError::InvalidAttestation(
+                    "signature s value is too high".to_string(),
+                ));
+            }
+            let attester = signature
+                .recover_address_from_prehash(&digest)
+                .map_err(|e| AttestationError::InvalidAttestation(e.to_string()))?;
+            if attester <= previous {
+                return Err(AttestationError::InvalidAttestation(
+                    "signatures are not ordered by increasing attester address".to_string(),
+                ));
+            }
+            if !self.config.attesters.contains(&attester) {
+                return Err(AttestationError::InvalidAttestation(format!(
+                    "{attester} is not an attester"
+                )));
+            }
+            previous = attester;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl AttestationVerifier for CctpAttestationVerifier {
+    async fn verify(
+        &self,
+        payload: &CrossChainPayload,
+    ) -> Result<BridgedTransfer, AttestationError> {
+        let message = CctpMessage::parse(&payload.message)?;
+        if message.destination_domain != self.config.destination_domain {
+            return Err(AttestationError::WrongDestination(
+                message.destination_domain,
+            ));
+        }
+        // Messages not handled by the token messenger mint nothing, though they are received
+        if message.recipient != self.config.token_messenger.into_word() {
+            return Err(AttestationError::InvalidMessage(
+                "message is not addressed to the token messenger".to_string(),
+            ));
+        }
+        let mut routes = self.config.routes.iter().filter(|route| {
+            route.source_domain == message.source_domain
+                && route.token_messenger.into_word() == message.sender
+        });
+        let route = routes
+            .clone()
+            .find(|route| route.burn_token.into_word() == message.burn_token)
+            .ok_or_else(|| match routes.next() {
+                Some(_) => AttestationError::UnsupportedToken(message.burn_token.to_string()),
+                None => AttestationError::UnsupportedSource(format!(
+                    "domain {} sender {}",
+                    message.source_domain, message.sender
+                )),
+            })?;
+        self.assert_attested(&payload.message, &payload.attestation)?;
+        let redeemer = if message.destination_caller.is_zero() {
+            None
+        } else {
+            Some(evm_address(message.destination_caller)?)
+        };
+        Ok(BridgedTransfer {
+            payer: evm_address(message.message_sender)?,
+            recipient: evm_address(message.mint_recipient)?,
+            token: route.mint_token,
+            amount: message.amount,
+            redeemer,
+        })
+    }
+
+    fn redemption(&self, payload: &CrossChainPayload) -> MetaTransaction {
+        let call = IMessageTransmitter::receiveMessageCall {
+            message: payload.message.clone(),
+            attestation: payload.attestation.clone(),
+        };
+        MetaTransaction::new(self.config.message_transmitter, call.abi_encode().into())
+    }
+
+    fn supported_extra(&self) -> Option<serde_json::Value> {
+        let mut source_domains: Vec<u32> = self
+            .config
+            .routes
+            .iter()
+            .map(|route| route.source_domain)
+            .collect();
+        source_domains.sort_unstable();
+        source_domains.dedup();
+        let extra = CctpSupportedExtra {
+            bridge: "cctp".to_string(),
+            destination_domain: self.config.destination_domain,
+            source_domains,
+        };
+        serde_json::to_value(extra).ok()
+    }
+}
+
+/// Reads an EVM address from a 32-byte word, rejecting words with non-zero high bytes.
+fn evm_address(word: B256) -> Result<Address, AttestationError> {
+    if word[..12].iter().any(|byte| *byte != 0) {
+        return Err(AttestationError::InvalidMessage(format!(
+            "{word} is not an EVM address"
+        )));
+    }
+    Ok(Address::from_word(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Bytes, address};
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+
+    const TOKEN_MESSENGER: Address = address!("0x1682Ae6375C4E4A97e4B583BC394c861A46D8962");
+    const SOURCE_TOKEN_MESSENGER: Address = address!("0xBd3fa81B58Ba92a82136038B25aDec7066af3155");
+    const USDC_ETHEREUM: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    const USDC_BASE: Address = address!("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913");
+    const PAYER: Address = address!("0x857b06519E91e3A54538791bDbb0E22373e36b66");
+    const PAY_TO: Address = address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07");
+
+    /// A message burning `amount` USDC on Ethereum (domain 0) for `PAY_TO` on Base (domain 6).
+    fn burn_message(amount: u64) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend(0u32.to_be_bytes());
+        message.extend(0u32.to_be_bytes());
+        message.extend(6u32.to_be_bytes());
+        message.extend(42u64.to_be_bytes());
+        message.extend(SOURCE_TOKEN_MESSENGER.into_word());
+        message.extend(TOKEN_MESSENGER.into_word());
+        message.extend(B256::ZERO);
+        message.extend(0u32.to_be_bytes());
+        message.extend(USDC_ETHEREUM.into_word());
+        message.extend(PAY_TO.into_word());
+        message.extend(U256::from(amount).to_be_bytes::<32>());
+        message.extend(PAYER.into_word());
+        message
+    }
+
+    /// Signs `message` with each of `attesters`, in order.
+    fn attestation(message: &[u8], attesters: &[&PrivateKeySigner]) -> Vec<u8> {
+        let digest = keccak256(message);
+        attesters
+            .iter()
+            .flat_map(|attester| attester.sign_hash_sync(&digest).unwrap().as_bytes())
+            .collect()
+    }
+
+    /// Two attesters, ordered by address.
+    fn attesters() -> [PrivateKeySigner; 2] {
+        let mut attesters = [PrivateKeySigner::random(), PrivateKeySigner::random()];
+        attesters.sort_by_key(|attester| attester.address());
+        attesters
+    }
+
+    fn verifier(attesters: &[PrivateKeySigner], threshold: usize) -> CctpAttestationVerifier {
+        CctpAttestationVerifier::new(CctpConfig {
+            destination_domain: 6,
+            message_transmitter: address!("0xAD09780d193884d503182aD4588450C416D6F9D4"),
+            token_messenger: TOKEN_MESSENGER,
+            attesters: attesters
+                .iter()
+                .map(|attester| attester.address())
+                .collect(),
+            threshold,
+            routes: vec![CctpRoute {
+                source_domain: 0,
+                token_messenger: SOURCE_TOKEN_MESSENGER,
+                burn_token: USDC_ETHEREUM,
+                mint_token: USDC_BASE,
+            }],
+        })
+        .unwrap()
+    }
+
+    fn payload(message: Vec<u8>, attestation: Vec<u8>) -> CrossChainPayload {
+        CrossChainPayload {
+            message: Bytes::from(message),
+            attestation: Bytes::from(attestation),
+            signature: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn parses_burn_messages() {
+        let message = CctpMessage::parse(&burn_message(1_000_000)).unwrap();
+        assert_eq!(message.source_domain, 0);
+        assert_eq!(message.destination_domain, 6);
+        assert_eq!(message.nonce, 42);
+        assert_eq!(message.burn_token, USDC_ETHEREUM.into_word());
+        assert_eq!(message.mint_recipient, PAY_TO.into_word());
+        assert_eq!(message.amount, U256::from(1_000_000));
+        assert_eq!(message.message_sender, PAYER.into_word());
+
+        let truncated = &burn_message(1_000_000)[..200];
+        assert!(matches!(
+            CctpMessage::parse(truncated),
+            Err(AttestationError::InvalidMessage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn verifies_attested_transfers() {
+        let attesters = attesters();
+        let verifier = verifier(&attesters, 2);
+        let message = burn_message(1_000_000);
+        let attestation = attestation(&message, &[&attesters[0], &attesters[1]]);
+        let transfer = verifier
+            .verify(&payload(message, attestation))
+            .await
+            .unwrap();
+        assert_eq!(
+            transfer,
+            BridgedTransfer {
+                payer: PAYER,
+                recipient: PAY_TO,
+                token: USDC_BASE,
+                amount: U256::from(1_000_000),
+                redeemer: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_attestations() {
+        let attesters = attesters();
+        let verifier = verifier(&attesters, 2);
+        let message = burn_message(1_000_000);
+        let rejected = async |attestation: Vec<u8>| {
+            let result = verifier
+                .verify(&payload(message.clone(), attestation))
+                .await;
+            assert!(
+                matches!(result, Err(AttestationError::InvalidAttestation(_))),
+                "{result:?}"
+            );
+        };
+        // Below the threshold
+        rejected(attestation(&message, &[&attesters[0]])).await;
+        // Out of order, or twice the same attester
+        rejected(attestation(&message, &[&attesters[1], &attesters[0]])).await;
+        rejected(attestation(&message, &[&attesters[0], &attesters[0]])).await;
+        // Not an attester
+        let stranger = PrivateKeySigner::random();
+        let mut signers = [&attesters[0], &stranger];
+        signers.sort_by_key(|signer| signer.address());
+        rejected(attestation(&message, &signers)).await;
+        // Signed for another message
+        rejected(attestation(
+            &burn_message(2_000_000),
+            &[&attesters[0], &attesters[1]],
+        ))
+        .await;
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_routes() {
+        let attesters = attesters();
+        let verifier = verifier(&attesters, 1);
+        let attested = |message: Vec<u8>| {
+            let attestation = attestation(&message, &[&attesters[0]]);
+            payload(message, attestation)
+        };
+
+        let mut other_destination = burn_message(1_000_000);
+        other_destination[8..12].copy_from_slice(&3u32.to_be_bytes());
+        let result = verifier.verify(&attested(other_destination)).await;
+        assert!(matches!(result, Err(AttestationError::WrongDestination(3))));
+
+        let mut other_source = burn_message(1_000_000);
+        other_source[4..8].copy_from_slice(&1u32.to_be_bytes());
+        let result = verifier.verify(&attested(other_source)).await;
+        assert!(matches!(
+            result,
+            Err(AttestationError::UnsupportedSource(_))
+        ));
+
+        let mut other_token = burn_message(1_000_000);
+        other_token[MESSAGE_HEADER_LENGTH + 4..MESSAGE_HEADER_LENGTH + 36]
+            .copy_from_slice(&PAYER.into_word().0);
+        let result = verifier.verify(&attested(other_token)).await;
+        assert!(matches!(result, Err(AttestationError::UnsupportedToken(_))));
+
+        // Received by the transmitter, but handled by another contract than the messenger
+        let mut other_recipient = burn_message(1_000_000);
+        other_recipient[52..84].copy_from_slice(&PAYER.into_word().0);
+        let result = verifier.verify(&attested(other_recipient)).await;
+        assert!(matches!(result, Err(AttestationError::InvalidMessage(_))));
+    }
+
+    #[test]
+    fn rejects_unreachable_thresholds() {
+        let attesters = attesters();
+        let mut config = verifier(&attesters, 2).config;
+        config.threshold = 3;
+        assert!(CctpAttestationVerifier::new(config.clone()).is_err());
+        config.threshold = 0;
+        assert!(CctpAttestationVerifier::new(config).is_err());
+    }
+}
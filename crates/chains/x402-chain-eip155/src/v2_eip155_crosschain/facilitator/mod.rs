@@ -0,0 +1,489 @@
+//! Facilitator-side payment verification and settlement for V2 EIP-155 crosschain scheme.
+//!
+//! This module implements the facilitator logic for V2 protocol "crosschain" payments on EVM
+//! chains. A payment is a bridge attestation, checked by an [`AttestationVerifier`], of a
+//! transfer to `payTo` of at least the required amount of the required asset, authorized
+//! by the payer of the transfer. Settlement redeems the transfer on this chain.
+//!
+//! # Configuration
+//!
+//! The facilitator built from configuration verifies CCTP attestations, see [`CctpConfig`].
+//! For USDC bridged from Ethereum (domain 0) to Base (domain 6):
+//!
+//! ```json
+//! {
+//!   "id": "v2-eip155-crosschain",
+//!   "chains": "eip155:8453",
+//!   "config": {
+//!     "cctp": {
+//!       "destination_domain": 6,
+//!       "message_transmitter": "0xAD09780d193884d503182aD4588450C416D6F9D4",
+//!       "token_messenger": "0x1682Ae6375C4E4A97e4B583BC394c861A46D8962",
+//!       "attesters": ["0x..."],
+//!       "routes": [{
+//!         "source_domain": 0,
+//!         "token_messenger": "0xBd3fa81B58Ba92a82136038B25aDec7066af3155",
+//!         "burn_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+//!         "mint_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"
+//!       }]
+//!     }
+//!   }
+//! }
+//! ```
+
+pub mod attestation;
+pub mod cctp;
+
+pub use attestation::{AttestationError, AttestationVerifier, BridgedTransfer};
+pub use cctp::{CctpAttestationVerifier, CctpConfig, CctpRoute};
+
+use alloy_network::TransactionBuilder;
+use alloy_primitives::Signature;
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::TransactionRequest;
+use alloy_sol_types::SolStruct;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use x402_types::chain::ChainProviderOps;
+use x402_types::proto;
+use x402_types::proto::{PaymentVerificationError, v2};
+use x402_types::scheme::{
+    X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
+};
+use x402_types::util::span::{settle_in_span, verify_in_span};
+
+use crate::V2Eip155CrossChain;
+use crate::chain::revert::simulation_failure;
+use crate::chain::{Eip155MetaTransactionProvider, Eip155SignerAddresses, MetaTransaction};
+use crate::v1_eip155_exact::facilitator::{
+    Eip155ExactError, assert_enough_value, tx_hash_from_receipt,
+};
+use crate::v2_eip155_crosschain::types;
+
+/// Configuration for the V2 EIP-155 crosschain scheme facilitator.
+///
+/// - `cctp`: The CCTP attestation verifier configuration, see [`CctpConfig`]. Required.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2Eip155CrossChainFacilitatorConfig {
+    pub cctp: CctpConfig,
+}
+
+impl<P> X402SchemeFacilitatorBuilder<P> for V2Eip155CrossChain
+where
+    P: Eip155MetaTransactionProvider
+        + ChainProviderOps
+        + Eip155SignerAddresses
+        + Send
+        + Sync
+        + 'static,
+    P::Inner: Provider,
+    Eip155ExactError: From<P::Error>,
+{
+    fn build(
+        &self,
+        provider: P,
+        config: Option<serde_json::Value>,
+    ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        let config = config.ok_or("the crosschain scheme requires a `cctp` configuration")?;
+        let config = V2Eip155CrossChainFacilitatorConfig::deserialize(config)?;
+        let verifier = CctpAttestationVerifier::new(config.cctp)?;
+        Ok(Box::new(V2Eip155CrossChainFacilitator::new(
+            provider,
+            Arc::new(verifier),
+        )))
+    }
+}
+
+/// Facilitator for V2 EIP-155 crosschain scheme payments.
+///
+/// This struct implements the [`X402SchemeFacilitator`] trait to verify bridge attestations
+/// with its [`AttestationVerifier`], and settle them by redeeming the bridged transfer.
+///
+/// # Type Parameters
+///
+/// - `P`: The provider type, which must implement [`Eip155MetaTransactionProvider`]
+///   and [`ChainProviderOps`]
+pub struct V2Eip155CrossChainFacilitator<P> {
+    provider: P,
+    verifier: Arc<dyn AttestationVerifier>,
+}
+
+impl<P> V2Eip155CrossChainFacilitator<P> {
+    /// Creates a facilitator accepting the attestations checked by `verifier`.
+    pub fn new(provider: P, verifier: Arc<dyn AttestationVerifier>) -> Self {
+        Self { provider, verifier }
+    }
+}
+
+impl<P> V2Eip155CrossChainFacilitator<P>
+where
+    P: Eip155MetaTransactionProvider + ChainProviderOps + Eip155SignerAddresses,
+{
+    /// Checks the attestation of the payment and that the transfer it proves pays the
+    /// requirements, returning the transfer and the transaction redeeming it.
+    async fn assert_valid(
+        &self,
+        request: &types::VerifyRequest,
+    ) -> Result<(BridgedTransfer, MetaTransaction), PaymentVerificationError> {
+        let payment_payload = &request.payment_payload;
+        let payment_requirements = &request.payment_requirements;
+        payment_payload
+            .accepted
+            .assert_accepted(payment_requirements, false)?;
+        if payment_requirements.network != self.provider.chain_id() {
+            return Err(PaymentVerificationError::ChainIdMismatch);
+        }
+
+        let transfer = self.verifier.verify(&payment_payload.payload).await?;
+        assert_payer_authorization(payment_payload, &transfer, self.provider.chain().inner())?;
+        if transfer.recipient != payment_requirements.pay_to.0 {
+            return Err(PaymentVerificationError::RecipientMismatch);
+        }
+        if transfer.token != payment_requirements.asset.0 {
+            return Err(PaymentVerificationError::AssetMismatch);
+        }
+        assert_enough_value(&transfer.amount, &payment_requirements.amount)?;
+
+        let mut redemption = self.verifier.redemption(&payment_payload.payload);
+        if let Some(redeemer) = transfer.redeemer {
+            if !Eip155SignerAddresses::signer_addresses(&self.provider).contains(&redeemer) {
+                return Err(PaymentVerificationError::InvalidSignature(format!(
+                    "transfer can only be redeemed by {redeemer}, not by this facilitator"
+                )));
+            }
+            redemption = redemption.with_from(redeemer);
+        }
+        Ok((transfer, redemption))
+    }
+}
+
+/// Checks that the payer of `transfer` signed the [`CrossChainAuthorization`](types::CrossChainAuthorization)
+/// of the payment, for payments received on chain `chain_id`.
+///
+/// The payer is the account that sent the tokens on the source chain, which the facilitator
+/// does not observe: only EOA signatures are accepted.
+fn assert_payer_authorization(
+    payment_payload: &types::PaymentPayload,
+    transfer: &BridgedTransfer,
+    chain_id: u64,
+) -> Result<(), PaymentVerificationError> {
+    let authorization = types::CrossChainAuthorization::new(payment_payload);
+    let hash = authorization.eip712_signing_hash(&types::CrossChainAuthorization::domain(chain_id));
+    let signer = Signature::from_raw(&payment_payload.payload.signature)
+        .and_then(|signature| signature.recover_address_from_prehash(&hash))
+        .map_err(|e| {
+            PaymentVerificationError::InvalidSignature(format!("Invalid payer signature: {e}"))
+        })?;
+    if signer != transfer.payer {
+        return Err(PaymentVerificationError::InvalidSignature(format!(
+            "payment is authorized by {signer}, not by the payer {} of the transfer",
+            transfer.payer
+        )));
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl<P> X402SchemeFacilitator for V2Eip155CrossChainFacilitator<P>
+where
+    P: Eip155MetaTransactionProvider + ChainProviderOps + Eip155SignerAddresses + Send + Sync,
+    P::Inner: Provider,
+    Eip155ExactError: From<P::Error>,
+{
+    async fn verify(
+        &self,
+        request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+        verify_in_span(request, async {
+            let verify_request = types::VerifyRequest::try_from(request)?;
+            let (transfer, redemption) = self.assert_valid(&verify_request).await?;
+
+            // Redeeming fails if the transfer was already redeemed
+            let from = redemption.from.or_else(|| {
+                Eip155SignerAddresses::signer_addresses(&self.provider)
+                    .first()
+                    .copied()
+            });
+            let mut call = TransactionRequest::default()
+                .with_to(redemption.to)
+                .with_input(redemption.calldata);
+            if let Some(from) = from {
+                call = call.with_from(from);
+            }
+            self.provider
+                .inner()
+                .call(call)
+                .await
+                .map_err(|e| simulation_failure(e.to_string()))?;
+
+            Ok(v2::VerifyResponse::valid(transfer.payer.to_string()).into())
+        })
+        .await
+    }
+
+    async fn settle(
+        &self,
+        request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+        settle_in_span(request, async {
+            let settle_request = types::SettleRequest::try_from(request)?;
+            let (transfer, redemption) = self.assert_valid(&settle_request).await?;
+            let receipt =
+                Eip155MetaTransactionProvider::send_transaction(&self.provider, redemption)
+                    .await
+                    .map_err(Eip155ExactError::from)?;
+            let tx_hash = tx_hash_from_receipt(&receipt)?;
+            Ok(v2::SettleResponse::Success {
+                payer: transfer.payer.to_string(),
+                transaction: tx_hash.to_string(),
+                network: settle_request.payment_requirements.network.to_string(),
+            }
+            .into())
+        })
+        .await
+    }
+
+    async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+        let chain_id = self.provider.chain_id();
+        let kinds = vec![proto::SupportedPaymentKind {
+            x402_version: v2::X402Version2.into(),
+            scheme: types::CrossChainScheme.to_string(),
+            network: chain_id.clone().into(),
+            extra: self.verifier.supported_extra(),
+        }];
+        let signers = {
+            let mut signers = HashMap::with_capacity(1);
+            let signer_addresses = ChainProviderOps::signer_addresses(&self.provider);
+            signers.insert(chain_id, signer_addresses);
+            signers
+        };
+        Ok(proto::SupportedResponse {
+            kinds,
+            extensions: Vec::new(),
+            signers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bytes, U256, address};
+    use alloy_provider::{ProviderBuilder, RootProvider};
+    use alloy_rpc_types_eth::TransactionReceipt;
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_transport::mock::Asserter;
+    use serde_json::json;
+    use x402_types::chain::ChainId;
+
+    use crate::chain::{Eip155ChainReference, MetaTransactionSendError};
+
+    const CHAIN_ID: u64 = 8453;
+    const USDC_BASE: Address = address!("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913");
+    const PAY_TO: Address = address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07");
+    const MESSAGE_TRANSMITTER: Address = address!("0xAD09780d193884d503182aD4588450C416D6F9D4");
+    const RESOURCE: &str = "https://api.example.com/protected";
+
+    struct MockProvider {
+        inner: RootProvider,
+        chain: Eip155ChainReference,
+        signers: Vec<Address>,
+    }
+
+    impl Eip155MetaTransactionProvider for MockProvider {
+        type Error = MetaTransactionSendError;
+        type Inner = RootProvider;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        fn chain(&self) -> &Eip155ChainReference {
+            &self.chain
+        }
+
+        async fn send_transaction(
+            &self,
+            _tx: MetaTransaction,
+        ) -> Result<TransactionReceipt, Self::Error> {
+            unimplemented!("verification sends no transaction")
+        }
+    }
+
+    impl ChainProviderOps for MockProvider {
+        fn signer_addresses(&self) -> Vec<String> {
+            self.signers.iter().map(Address::to_string).collect()
+        }
+
+        fn chain_id(&self) -> ChainId {
+            self.chain.into()
+        }
+    }
+
+    impl Eip155SignerAddresses for MockProvider {
+        fn signer_addresses(&self) -> Vec<Address> {
+            self.signers.clone()
+        }
+    }
+
+    /// Verifier attesting every payload as the same transfer.
+    struct FixedVerifier(BridgedTransfer);
+
+    #[async_trait::async_trait]
+    impl AttestationVerifier for FixedVerifier {
+        async fn verify(
+            &self,
+            _payload: &types::CrossChainPayload,
+        ) -> Result<BridgedTransfer, AttestationError> {
+            Ok(self.0.clone())
+        }
+
+        fn redemption(&self, payload: &types::CrossChainPayload) -> MetaTransaction {
+            MetaTransaction::new(MESSAGE_TRANSMITTER, payload.message.clone())
+        }
+    }
+
+    fn transfer(payer: Address, amount: u64) -> BridgedTransfer {
+        BridgedTransfer {
+            payer,
+            recipient: PAY_TO,
+            token: USDC_BASE,
+            amount: U256::from(amount),
+            redeemer: None,
+        }
+    }
+
+    /// A facilitator whose verifier attests `transfer`.
+    fn attesting(
+        transfer: BridgedTransfer,
+    ) -> (V2Eip155CrossChainFacilitator<MockProvider>, Asserter) {
+        let asserter = Asserter::new();
+        let provider = MockProvider {
+            inner: ProviderBuilder::default().connect_mocked_client(asserter.clone()),
+            chain: Eip155ChainReference::new(CHAIN_ID),
+            signers: vec![address!("0x000000000000000000000000000000000000fAc1")],
+        };
+        let facilitator =
+            V2Eip155CrossChainFacilitator::new(provider, Arc::new(FixedVerifier(transfer)));
+        (facilitator, asserter)
+    }
+
+    /// A request paying 1 USDC with `signature`, for `resource`.
+    fn request(resource: &str, signature: Bytes) -> types::VerifyRequest {
+        let requirements = json!({
+            "scheme": "crosschain",
+            "network": format!("eip155:{CHAIN_ID}"),
+            "amount": "1000000",
+            "payTo": PAY_TO,
+            "maxTimeoutSeconds": 300,
+            "asset": USDC_BASE,
+        });
+        serde_json::from_value(json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": requirements,
+                "resource": { "url": resource },
+                "payload": {
+                    "message": "0x0102",
+                    "attestation": "0x0304",
+                    "signature": signature,
+                },
+            },
+            "paymentRequirements": requirements,
+        }))
+        .unwrap()
+    }
+
+    /// A request for [`RESOURCE`], authorized by `signer`.
+    fn signed_request(signer: &PrivateKeySigner) -> types::VerifyRequest {
+        let unsigned = request(RESOURCE, Bytes::new());
+        let authorization = types::CrossChainAuthorization::new(&unsigned.payment_payload);
+        let hash =
+            authorization.eip712_signing_hash(&types::CrossChainAuthorization::domain(CHAIN_ID));
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        request(RESOURCE, Bytes::from(signature.as_bytes()))
+    }
+
+    fn assert_invalid_signature(
+        result: Result<(BridgedTransfer, MetaTransaction), PaymentVerificationError>,
+    ) {
+        assert!(
+            matches!(result, Err(PaymentVerificationError::InvalidSignature(_))),
+            "{:?}",
+            result.map(|(transfer, _)| transfer)
+        );
+    }
+
+    #[tokio::test]
+    async fn verifies_transfers_authorized_by_their_payer() {
+        let payer = PrivateKeySigner::random();
+        let (facilitator, asserter) = attesting(transfer(payer.address(), 1_000_000));
+        let request = signed_request(&payer);
+        let raw = serde_json::value::to_raw_value(&request).unwrap();
+
+        // The redemption simulation succeeds: the transfer is not redeemed yet
+        asserter.push_success(&Bytes::new());
+        let response = facilitator
+            .verify(&proto::VerifyRequest::from(raw.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.0["isValid"], true);
+        assert_eq!(response.0["payer"], payer.address().to_string());
+
+        // The redemption simulation reverts: the transfer was redeemed already
+        asserter.push_failure_msg("execution reverted");
+        let result = facilitator.verify(&proto::VerifyRequest::from(raw)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_transfers_replayed_by_someone_else() {
+        let payer = PrivateKeySigner::random();
+        let (facilitator, _) = attesting(transfer(payer.address(), 1_000_000));
+
+        // Without signature, or with the signature of another account
+        let unsigned = request(RESOURCE, Bytes::new());
+        assert_invalid_signature(facilitator.assert_valid(&unsigned).await);
+        let replayed = signed_request(&PrivateKeySigner::random());
+        assert_invalid_signature(facilitator.assert_valid(&replayed).await);
+
+        // With the payer's signature, for another resource
+        let mut other_resource = signed_request(&payer);
+        other_resource.payment_payload.resource = Some(v2::ResourceInfo {
+            url: "https://api.example.com/other".to_string(),
+            description: None,
+            mime_type: None,
+        });
+        assert_invalid_signature(facilitator.assert_valid(&other_resource).await);
+    }
+
+    #[tokio::test]
+    async fn rejects_transfers_not_paying_the_requirements() {
+        let payer = PrivateKeySigner::random();
+        let request = signed_request(&payer);
+
+        let (facilitator, _) = attesting(transfer(payer.address(), 999_999));
+        let result = facilitator.assert_valid(&request).await;
+        assert!(matches!(
+            result,
+            Err(PaymentVerificationError::InvalidPaymentAmount)
+        ));
+
+        let mut to_someone_else = transfer(payer.address(), 1_000_000);
+        to_someone_else.recipient = payer.address();
+        let (facilitator, _) = attesting(to_someone_else);
+        let result = facilitator.assert_valid(&request).await;
+        assert!(matches!(
+            result,
+            Err(PaymentVerificationError::RecipientMismatch)
+        ));
+
+        let mut redeemed_by_someone_else = transfer(payer.address(), 1_000_000);
+        redeemed_by_someone_else.redeemer = Some(payer.address());
+        let (facilitator, _) = attesting(redeemed_by_someone_else);
+        assert_invalid_signature(facilitator.assert_valid(&request).await);
+    }
+}
@@ -0,0 +1,87 @@
+//! V2 EIP-155 "crosschain" payment scheme implementation.
+//!
+//! This module implements the "crosschain" payment scheme for EVM chains using the
+//! V2 x402 protocol. The payment requirements name a token on this chain, but the
+//! client pays on another chain: it sends the tokens through a bridge, and pays with
+//! the bridge message, its attestation, and its signature of the payment. The facilitator
+//! checks the attestation and the signature, confirms the bridged amount and token match
+//! the requirements, and settles by redeeming the transfer on this chain, which delivers
+//! the tokens to `payTo`.
+//!
+//! # Bridges
+//!
+//! Attestations are checked by an [`AttestationVerifier`](facilitator::AttestationVerifier),
+//! which also builds the transaction redeeming a transfer. The facilitator built from
+//! configuration uses [`CctpAttestationVerifier`](facilitator::CctpAttestationVerifier),
+//! for USDC bridged with Circle's Cross-Chain Transfer Protocol (CCTP). Other bridges
+//! plug in with [`V2Eip155CrossChainFacilitator::new`](facilitator::V2Eip155CrossChainFacilitator::new).
+//!
+//! # Trust Assumptions
+//!
+//! The facilitator does not observe the source chain: a payment is only as good as
+//! the bridge attesting it. With CCTP, anyone holding the keys of the configured
+//! attesters can forge a payment, and a burn reorganized out of the source chain after
+//! being attested is still paid. The configured attesters must match the ones enabled on
+//! the `MessageTransmitter` of this chain, or settlement fails after verification passed.
+//!
+//! Bridge messages and attestations are public, so a payment also carries the signature of
+//! the payer, the account that sent the tokens on the source chain, over a
+//! [`CrossChainAuthorization`] naming the bridge message, the resource and the accepted
+//! requirements. Without it, anyone watching the source chain could claim a transfer to
+//! `payTo` as their own payment. The signature binds the payment to the resource the
+//! payload names: the server must check it is the resource requested. The facilitator
+//! does not observe the source chain, so only EOA payers can sign: transfers sent by
+//! smart contract wallets are rejected.
+//!
+//! A transfer can be redeemed once: its redemption is simulated on verification, so
+//! transfers already redeemed, by this facilitator or anyone else, are rejected. A
+//! transfer redeemed by someone else between verification and settlement fails to settle.
+//!
+//! # Usage
+//!
+//! The client burns the tokens on the source chain, with `payTo` as the mint recipient,
+//! fetches the attestation from the bridge, signs the EIP-712 [`CrossChainAuthorization`]
+//! of the payment in the [domain](CrossChainAuthorization::domain) of this chain, and sends
+//! a payload of the form:
+//!
+//! ```json
+//! { "message": "0x…", "attestation": "0x…", "signature": "0x…" }
+//! ```
+//!
+//! A server accepts such payments with a price tag in the destination token:
+//!
+//! ```ignore
+//! use x402_chain_eip155::v2_eip155_crosschain::V2Eip155CrossChain;
+//! use x402_chain_eip155::networks::{KnownNetworkEip155, USDC};
+//!
+//! let price = V2Eip155CrossChain::price_tag("0x1234...", USDC::base().amount(1_000_000u64.into()));
+//! ```
+
+#[cfg(feature = "facilitator")]
+pub mod facilitator;
+#[cfg(feature = "facilitator")]
+pub use facilitator::*;
+
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "server")]
+#[allow(unused_imports)] // Public for consumption by downstream crates.
+pub use server::*;
+
+pub mod types;
+pub use types::*;
+
+use x402_types::scheme::X402SchemeId;
+
+/// Scheme identifier for V2 EIP-155 cross-chain payments.
+pub struct V2Eip155CrossChain;
+
+impl X402SchemeId for V2Eip155CrossChain {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        CrossChainScheme.as_ref()
+    }
+}
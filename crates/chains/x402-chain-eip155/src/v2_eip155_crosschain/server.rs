@@ -0,0 +1,63 @@
+//! Server-side price tag generation for V2 EIP-155 crosschain scheme.
+//!
+//! The crosschain price tag includes an enricher that copies the bridge details of the
+//! facilitator's `supported()` response, such as the accepted source chains, into the
+//! payment requirements `extra` field.
+
+use std::sync::Arc;
+
+use alloy_primitives::U256;
+use x402_types::chain::{ChainId, DeployedTokenAmount};
+use x402_types::proto;
+use x402_types::proto::v2;
+
+use crate::V2Eip155CrossChain;
+use crate::chain::{ChecksummedAddress, Eip155TokenDeployment};
+use crate::v2_eip155_crosschain::types::CrossChainScheme;
+
+impl V2Eip155CrossChain {
+    /// Creates a V2 price tag for a payment bridged to an EVM chain.
+    ///
+    /// `asset` is the token and amount to be received on its chain, whatever chain the
+    /// client pays from.
+    #[allow(dead_code)] // Public for consumption by downstream crates.
+    pub fn price_tag<A: Into<ChecksummedAddress>>(
+        pay_to: A,
+        asset: DeployedTokenAmount<U256, Eip155TokenDeployment>,
+    ) -> v2::PriceTag {
+        let chain_id: ChainId = asset.token.chain_reference.into();
+        let requirements = v2::PaymentRequirements {
+            scheme: CrossChainScheme.to_string(),
+            pay_to: pay_to.into().to_string(),
+            asset: asset.token.address.to_string(),
+            network: chain_id,
+            amount: asset.amount.to_string(),
+            max_timeout_seconds: 300,
+            extra: None,
+        };
+        v2::PriceTag {
+            requirements,
+            enricher: Some(Arc::new(crosschain_bridge_enricher)),
+        }
+    }
+}
+
+/// Enricher that copies the facilitator's `supported()` extra for the crosschain scheme
+/// into the price tag's payment requirements extra field.
+pub fn crosschain_bridge_enricher(
+    price_tag: &mut v2::PriceTag,
+    capabilities: &proto::SupportedResponse,
+) {
+    let supported_extra = capabilities
+        .kinds
+        .iter()
+        .find(|kind| {
+            v2::X402Version2 == kind.x402_version
+                && kind.scheme == CrossChainScheme.to_string()
+                && kind.network == price_tag.requirements.network.to_string()
+        })
+        .and_then(|kind| kind.extra.clone());
+    if let Some(supported_extra) = supported_extra {
+        price_tag.requirements.extra = Some(supported_extra);
+    }
+}
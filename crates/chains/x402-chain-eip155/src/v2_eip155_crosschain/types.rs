@@ -0,0 +1,86 @@
+//! Type definitions for the V2 EIP-155 "crosschain" payment scheme.
+//!
+//! This module defines types for the "crosschain" scheme, paying with a bridge message,
+//! the attestation proving it, and the payer's authorization of the payment.
+
+use alloy_primitives::{Bytes, U256, keccak256};
+use alloy_sol_types::{Eip712Domain, eip712_domain, sol};
+use serde::{Deserialize, Serialize};
+use x402_types::lit_str;
+use x402_types::proto::v2;
+
+use crate::chain::ChecksummedAddress;
+
+lit_str!(CrossChainScheme, "crosschain");
+
+/// Payload of a cross-chain payment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossChainPayload {
+    /// The bridge message of the transfer, as emitted on the source chain.
+    pub message: Bytes,
+    /// The attestation of the message by the bridge.
+    pub attestation: Bytes,
+    /// The [`CrossChainAuthorization`] of the payment, signed by the account that sent the
+    /// tokens on the source chain.
+    pub signature: Bytes,
+}
+
+sol! {
+    /// Authorization of a cross-chain payment by its payer.
+    ///
+    /// Bridge messages and attestations are public once the transfer is made, so anyone
+    /// could present a transfer to `payTo` as their own payment. The payer signs this
+    /// struct to bind the transfer to the resource and requirements it pays for.
+    #[derive(Debug, PartialEq, Eq)]
+    struct CrossChainAuthorization {
+        bytes32 messageHash;
+        string resource;
+        address payTo;
+        address asset;
+        uint256 amount;
+    }
+}
+
+impl CrossChainAuthorization {
+    /// The authorization of `payment_payload`, paying its accepted requirements with the
+    /// transfer of its bridge message. The resource is empty if the payload names none.
+    pub fn new(payment_payload: &PaymentPayload) -> Self {
+        let accepted = &payment_payload.accepted;
+        Self {
+            messageHash: keccak256(&payment_payload.payload.message),
+            resource: payment_payload
+                .resource
+                .as_ref()
+                .map(|resource| resource.url.clone())
+                .unwrap_or_default(),
+            payTo: accepted.pay_to.0,
+            asset: accepted.asset.0,
+            amount: accepted.amount,
+        }
+    }
+
+    /// The EIP-712 domain of the authorizations of payments received on chain `chain_id`.
+    pub fn domain(chain_id: u64) -> Eip712Domain {
+        eip712_domain! {
+            name: "x402 crosschain",
+            version: "1",
+            chain_id: chain_id,
+        }
+    }
+}
+
+/// Type alias for V2 verify requests using the crosschain EVM payment scheme.
+pub type VerifyRequest = v2::VerifyRequest<PaymentPayload, PaymentRequirements>;
+
+/// Type alias for V2 settle requests (same structure as verify requests).
+pub type SettleRequest = VerifyRequest;
+
+/// Type alias for V2 payment payloads with embedded requirements and a bridge attestation.
+pub type PaymentPayload<TPaymentRequirements = PaymentRequirements> =
+    v2::PaymentPayload<TPaymentRequirements, CrossChainPayload>;
+
+/// Type alias for V2 payment requirements with EVM-specific types for the crosschain scheme.
+///
+/// The `asset` is the token received on this chain, and `amount` the minimum amount received.
+pub type PaymentRequirements = v2::PaymentRequirements<CrossChainScheme, U256, ChecksummedAddress>;
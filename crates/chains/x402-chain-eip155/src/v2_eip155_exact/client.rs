@@ -14,15 +14,17 @@
 //! let client = V2Eip155ExactClient::new(signer);
 //! ```
 
-use alloy_primitives::{Address, U256};
-use alloy_sol_types::{SolStruct, eip712_domain};
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::{SolStruct, SolValue, eip712_domain};
 use async_trait::async_trait;
 use rand::{RngExt, rng};
+use std::time::Duration;
 use x402_types::proto::v2::{ExtensionsJson, ResourceInfo};
 use x402_types::proto::{OriginalJson, PaymentRequired, v2};
 use x402_types::scheme::X402SchemeId;
 use x402_types::scheme::client::{
-    PaymentCandidate, PaymentCandidateSigner, X402Error, X402SchemeClient,
+    PaymentCandidate, PaymentCandidateSigner, SignerKind, SigningOptions, X402Error,
+    X402SchemeClient,
 };
 use x402_types::timestamp::UnixTimestamp;
 use x402_types::util::Base64Bytes;
@@ -31,11 +33,11 @@ use crate::chain::permit2::{
     EXACT_PERMIT2_PROXY_ADDRESS, ExactPermit2Payload, ExactPermit2Witness, PERMIT2_ADDRESS,
     Permit2Authorization, Permit2AuthorizationPermitted,
 };
-use crate::chain::{AssetTransferMethod, Eip155ChainReference};
-use crate::v1_eip155_exact::PaymentRequirementsExtra;
+use crate::chain::{AssetTransferMethod, Eip155ChainReference, Eip712DomainCache};
 use crate::v1_eip155_exact::client::{
-    Eip3009SigningParams, SignerLike, sign_erc3009_authorization,
+    DEFAULT_CLOCK_SKEW, Eip3009SigningParams, SignerLike, sign_erc3009_authorization_cached,
 };
+use crate::v1_eip155_exact::{EIP6492_MAGIC_SUFFIX, PaymentRequirementsExtra, Sig6492};
 use crate::v2_eip155_exact::V2Eip155Exact;
 use crate::v2_eip155_exact::types;
 use crate::v2_eip155_exact::types::{
@@ -56,6 +58,10 @@ pub struct Permit2SigningParams {
     pub amount: U256,
     /// Maximum timeout in seconds for the authorization validity window
     pub max_timeout_seconds: u64,
+    /// Time of signing, on the server's clock, see [`SigningOptions::now`]
+    pub now: UnixTimestamp,
+    /// Clock skew, see [`DEFAULT_CLOCK_SKEW`]
+    pub clock_skew: Duration,
 }
 
 /// Signs a Permit2 PermitWitnessTransferFrom using EIP-712.
@@ -74,12 +80,10 @@ pub async fn sign_permit2_authorization<S: SignerLike + Sync>(
         verifying_contract: PERMIT2_ADDRESS,
     };
 
-    // Build authorization with timing
-    let now = UnixTimestamp::now();
-    // valid_after should be in the past (10 minutes ago) to ensure the payment is immediately valid
-    let valid_after_secs = now.as_secs().saturating_sub(10 * 60);
-    let valid_after = UnixTimestamp::from_secs(valid_after_secs);
-    let deadline = now + params.max_timeout_seconds;
+    // Build authorization with timing: valid_after is in the past, so that the payment is
+    // valid at once even if the facilitator's clock is behind
+    let valid_after = params.now - params.clock_skew;
+    let deadline = params.now + params.max_timeout_seconds;
 
     // Generate a random nonce
     let nonce: [u8; 32] = rng().random();
@@ -151,13 +155,69 @@ pub async fn sign_permit2_authorization<S: SignerLike + Sync>(
 #[allow(dead_code)] // Public for consumption by downstream crates.
 pub struct V2Eip155ExactClient<S> {
     signer: S,
+    domains: Eip712DomainCache,
+    clock_skew: Duration,
 }
 
 #[allow(dead_code)] // Public for consumption by downstream crates.
 impl<S> V2Eip155ExactClient<S> {
     /// Creates a new V2 EIP-155 exact scheme client with the given signer.
     pub fn new(signer: S) -> Self {
-        Self { signer }
+        Self {
+            signer,
+            domains: Eip712DomainCache::new(),
+            clock_skew: DEFAULT_CLOCK_SKEW,
+        }
+    }
+
+    /// Sets the clock skew, [`DEFAULT_CLOCK_SKEW`] by default.
+    pub fn with_clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    /// Uses `domains` to cache token EIP-712 domains, e.g. to share one cache with the
+    /// V1 client.
+    pub fn with_domain_cache(mut self, domains: Eip712DomainCache) -> Self {
+        self.domains = domains;
+        self
+    }
+
+    /// Returns the cache of token EIP-712 domains, to invalidate entries.
+    pub fn domain_cache(&self) -> &Eip712DomainCache {
+        &self.domains
+    }
+
+    /// Wraps the signature of a counterfactual (not yet deployed) smart wallet as
+    /// specified by [EIP-6492](https://eips.ethereum.org/EIPS/eip-6492).
+    ///
+    /// `factory_calldata` is the call to `factory` that deploys the wallet, and `inner_sig`
+    /// the signature the wallet validates once deployed. The result is the ABI-encoded
+    /// `(address, bytes, bytes)` tuple followed by [`EIP6492_MAGIC_SUFFIX`], to be sent as
+    /// the payload signature.
+    pub fn wrap_6492(factory: Address, factory_calldata: Bytes, inner_sig: Bytes) -> Bytes {
+        let sig6492 = Sig6492 {
+            factory,
+            factoryCalldata: factory_calldata,
+            innerSig: inner_sig,
+        };
+        let mut signature = sig6492.abi_encode_params();
+        signature.extend_from_slice(&EIP6492_MAGIC_SUFFIX);
+        signature.into()
+    }
+}
+
+#[cfg(feature = "keystore")]
+impl V2Eip155ExactClient<alloy_signer_local::PrivateKeySigner> {
+    /// Creates a client signing with the key in the encrypted JSON keystore at `path`.
+    ///
+    /// See [`decrypt_keystore`](crate::keystore::decrypt_keystore).
+    pub fn with_keystore(
+        path: impl AsRef<std::path::Path>,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Self, crate::keystore::KeystoreError> {
+        let signer = crate::keystore::decrypt_keystore(path, password)?;
+        Ok(Self::new(signer))
     }
 }
 
@@ -176,6 +236,14 @@ where
     S: SignerLike + Clone + Send + Sync + 'static,
 {
     fn accept(&self, payment_required: &PaymentRequired) -> Vec<PaymentCandidate> {
+        self.accept_with_options(payment_required, &SigningOptions::default())
+    }
+
+    fn accept_with_options(
+        &self,
+        payment_required: &PaymentRequired,
+        options: &SigningOptions,
+    ) -> Vec<PaymentCandidate> {
         let payment_required = match payment_required {
             PaymentRequired::V2(payment_required) => payment_required,
             PaymentRequired::V1(_) => {
@@ -200,6 +268,9 @@ where
                         resource_info: payment_required.resource.clone(),
                         extensions: payment_required.extensions.clone(),
                         signer: self.signer.clone(),
+                        domains: self.domains.clone(),
+                        options: *options,
+                        clock_skew: self.clock_skew,
                         chain_reference,
                         requirements,
                         requirements_json: original_requirements_json.clone(),
@@ -214,6 +285,9 @@ where
 #[allow(dead_code)] // Public for consumption by downstream crates.
 struct PayloadSigner<S> {
     signer: S,
+    domains: Eip712DomainCache,
+    options: SigningOptions,
+    clock_skew: Duration,
     resource_info: Option<ResourceInfo>,
     extensions: ExtensionsJson,
     chain_reference: Eip155ChainReference,
@@ -240,11 +314,16 @@ where
                     asset_address: self.requirements.asset.0,
                     pay_to: self.requirements.pay_to.into(),
                     amount: self.requirements.amount.into(),
-                    max_timeout_seconds: self.requirements.max_timeout_seconds,
+                    max_timeout_seconds: self
+                        .options
+                        .validity_seconds(self.requirements.max_timeout_seconds),
+                    now: self.options.now(),
+                    clock_skew: self.clock_skew,
                     extra,
                 };
 
-                let evm_payload = sign_erc3009_authorization(&self.signer, &params).await?;
+                let evm_payload =
+                    sign_erc3009_authorization_cached(&self.signer, &params, &self.domains).await?;
                 v2::PaymentPayload {
                     x402_version: v2::X402Version2,
                     accepted: self.requirements_json.clone(),
@@ -259,7 +338,11 @@ where
                     asset_address: self.requirements.asset.0,
                     pay_to: self.requirements.pay_to.into(),
                     amount: self.requirements.amount.into(),
-                    max_timeout_seconds: self.requirements.max_timeout_seconds,
+                    max_timeout_seconds: self
+                        .options
+                        .validity_seconds(self.requirements.max_timeout_seconds),
+                    now: self.options.now(),
+                    clock_skew: self.clock_skew,
                 };
 
                 let permit2_payload = sign_permit2_authorization(&self.signer, &params).await?;
@@ -278,4 +361,47 @@ where
 
         Ok(b64.to_string())
     }
+
+    fn kind(&self) -> SignerKind {
+        self.signer.kind()
+    }
+}
+
+#[cfg(all(test, feature = "facilitator"))]
+mod tests {
+    use super::*;
+    use alloy_primitives::{B256, address};
+
+    use crate::v1_eip155_exact::StructuredSignature;
+
+    #[test]
+    fn wrapped_6492_signature_round_trips() {
+        let factory = address!("0x4e1DCf7AD4e460CfD30791CCC4F9c8a4f820ec67");
+        let factory_calldata = Bytes::from_static(&[0x1f, 0xba, 0xc4, 0x1c, 0x01, 0x02]);
+        let inner_sig = Bytes::from(vec![0x42; 65]);
+        let wrapped = V2Eip155ExactClient::<()>::wrap_6492(
+            factory,
+            factory_calldata.clone(),
+            inner_sig.clone(),
+        );
+        assert!(wrapped.ends_with(&EIP6492_MAGIC_SUFFIX));
+
+        let signature =
+            StructuredSignature::try_from_bytes(wrapped.clone(), Address::ZERO, &B256::ZERO)
+                .unwrap();
+        match signature {
+            StructuredSignature::EIP6492 {
+                factory: decoded_factory,
+                factory_calldata: decoded_calldata,
+                inner,
+                original,
+            } => {
+                assert_eq!(decoded_factory, factory);
+                assert_eq!(decoded_calldata, factory_calldata);
+                assert_eq!(inner, inner_sig);
+                assert_eq!(original, wrapped);
+            }
+            other => panic!("expected an EIP-6492 signature, got {other:?}"),
+        }
+    }
 }
@@ -3,14 +3,16 @@ use alloy_sol_types::Eip712Domain;
 use x402_types::chain::{ChainId, ChainProviderOps};
 use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::X402SchemeFacilitatorError;
+use x402_types::timestamp::{Clock, ValidityWindow};
 
 #[cfg(feature = "telemetry")]
 use tracing::instrument;
 
 use crate::chain::{Eip155ChainReference, Eip155MetaTransactionProvider};
 use crate::v1_eip155_exact::{
-    Eip155ExactError, ExactEvmPayment, IEIP3009, PaymentRequirementsExtra, assert_domain,
-    assert_enough_balance, assert_enough_value, assert_time, settle_payment, verify_payment,
+    DeployedContractCache, Eip155ExactError, ExactEvmPayment, IEIP3009, PaymentRequirementsExtra,
+    assert_domain, assert_enough_balance, assert_enough_value, assert_time, settle_payment,
+    settle_payments_batch, verify_payment,
 };
 use crate::v2_eip155_exact::Eip3009Payload;
 use crate::v2_eip155_exact::types::{Eip3009PaymentPayload, Eip3009PaymentRequirements};
@@ -18,26 +20,40 @@ use crate::v2_eip155_exact::types::{Eip3009PaymentPayload, Eip3009PaymentRequire
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub async fn verify_eip3009_payment<P: Eip155MetaTransactionProvider + ChainProviderOps>(
     provider: &P,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
     payment_payload: &Eip3009PaymentPayload,
     payment_requirements: &Eip3009PaymentRequirements,
 ) -> Result<v2::VerifyResponse, X402SchemeFacilitatorError> {
     let accepted = &payment_payload.accepted;
-    assert_requirements_match(accepted, payment_requirements)?;
+    accepted.assert_accepted(payment_requirements, strict_accepted_match)?;
     let (contract, payment, eip712_domain) = assert_valid_payment(
         provider.inner(),
         provider.chain(),
         accepted,
         &payment_payload.payload,
+        clock,
     )
     .await?;
 
-    let payer = verify_payment(provider.inner(), &contract, &payment, &eip712_domain).await?;
+    let payer = verify_payment(
+        provider.inner(),
+        &contract,
+        &payment,
+        &eip712_domain,
+        provider.allowed_eip6492_factories(),
+        provider.multicall3_available(),
+    )
+    .await?;
     Ok(v2::VerifyResponse::valid(payer.to_string()))
 }
 
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub async fn settle_eip3009_payment<P>(
     provider: &P,
+    deployed: &DeployedContractCache,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
     payment_payload: &Eip3009PaymentPayload,
     payment_requirements: &Eip3009PaymentRequirements,
 ) -> Result<v2::SettleResponse, X402SchemeFacilitatorError>
@@ -46,16 +62,17 @@ where
     Eip155ExactError: From<P::Error>,
 {
     let accepted = &payment_payload.accepted;
-    assert_requirements_match(accepted, payment_requirements)?;
+    accepted.assert_accepted(payment_requirements, strict_accepted_match)?;
     let (contract, payment, eip712_domain) = assert_valid_payment(
         provider.inner(),
         provider.chain(),
         accepted,
         &payment_payload.payload,
+        clock,
     )
     .await?;
 
-    let tx_hash = settle_payment(provider, &contract, &payment, &eip712_domain).await?;
+    let tx_hash = settle_payment(provider, deployed, &contract, &payment, &eip712_domain).await?;
 
     Ok(v2::SettleResponse::Success {
         payer: payment.from.to_string(),
@@ -64,6 +81,64 @@ where
     })
 }
 
+/// Settles several EIP-3009 payments in a single Multicall3 transaction.
+///
+/// Returns one result per payment, in order. Payments failing validation are left out
+/// of the transaction, and chains without Multicall3 settle the payments one by one, see
+/// [`settle_payments_batch`].
+#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+pub async fn settle_eip3009_payments_batch<P>(
+    provider: &P,
+    deployed: &DeployedContractCache,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
+    payments: &[(Eip3009PaymentPayload, Eip3009PaymentRequirements)],
+) -> Vec<Result<v2::SettleResponse, X402SchemeFacilitatorError>>
+where
+    P: Eip155MetaTransactionProvider + ChainProviderOps,
+    Eip155ExactError: From<P::Error>,
+{
+    let mut results: Vec<Option<Result<v2::SettleResponse, X402SchemeFacilitatorError>>> =
+        payments.iter().map(|_| None).collect();
+    let mut indices = Vec::new();
+    let mut validated = Vec::new();
+    for (index, (payment_payload, payment_requirements)) in payments.iter().enumerate() {
+        let accepted = &payment_payload.accepted;
+        let payment = match accepted.assert_accepted(payment_requirements, strict_accepted_match) {
+            Ok(()) => assert_valid_payment(
+                provider.inner(),
+                provider.chain(),
+                accepted,
+                &payment_payload.payload,
+                clock,
+            )
+            .await
+            .map_err(X402SchemeFacilitatorError::from),
+            Err(e) => Err(e.into()),
+        };
+        match payment {
+            Ok(payment) => {
+                indices.push(index);
+                validated.push(payment);
+            }
+            Err(e) => results[index] = Some(Err(e)),
+        }
+    }
+    let settled = settle_payments_batch(provider, deployed, &validated).await;
+    for ((index, (_, payment, _)), settled) in indices.into_iter().zip(&validated).zip(settled) {
+        let network = payments[index].0.accepted.network.to_string();
+        let response = settled
+            .map(|tx_hash| v2::SettleResponse::Success {
+                payer: payment.from.to_string(),
+                transaction: tx_hash.to_string(),
+                network,
+            })
+            .map_err(X402SchemeFacilitatorError::from);
+        results[index] = Some(response);
+    }
+    results.into_iter().flatten().collect()
+}
+
 /// Runs all preconditions needed for a successful payment:
 /// - Valid scheme, network, and receiver.
 /// - Valid time window (validAfter/validBefore).
@@ -76,6 +151,7 @@ pub async fn assert_valid_payment<P: Provider>(
     chain: &Eip155ChainReference,
     accepted: &Eip3009PaymentRequirements,
     payload: &Eip3009Payload,
+    clock: &dyn Clock,
 ) -> Result<(IEIP3009::IEIP3009Instance<P>, ExactEvmPayment, Eip712Domain), Eip155ExactError> {
     let chain_id: ChainId = chain.into();
     let payload_chain_id = &accepted.network;
@@ -86,9 +162,8 @@ pub async fn assert_valid_payment<P: Provider>(
     if authorization.to != accepted.pay_to {
         return Err(PaymentVerificationError::RecipientMismatch.into());
     }
-    let valid_after = authorization.valid_after;
-    let valid_before = authorization.valid_before;
-    assert_time(valid_after, valid_before)?;
+    let window = ValidityWindow::new(authorization.valid_after, authorization.valid_before);
+    assert_time(&window, clock)?;
     let asset_address = accepted.asset;
     let contract = IEIP3009::new(asset_address.into(), provider);
 
@@ -109,8 +184,7 @@ pub async fn assert_valid_payment<P: Provider>(
         from: authorization.from,
         to: authorization.to,
         value: authorization.value,
-        valid_after: authorization.valid_after,
-        valid_before: authorization.valid_before,
+        window,
         nonce: authorization.nonce,
         signature,
     };
@@ -118,13 +192,28 @@ pub async fn assert_valid_payment<P: Provider>(
     Ok((contract, payment, domain))
 }
 
-pub fn assert_requirements_match<T: PartialEq>(
-    accepted: &T,
-    payment_requirements: &T,
-) -> Result<(), PaymentVerificationError> {
-    if accepted != payment_requirements {
-        Err(PaymentVerificationError::AcceptedRequirementsMismatch)
-    } else {
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn accepted_requirements_ignore_unknown_extra_fields() {
+        let requirements = json!({
+            "scheme": "exact",
+            "network": "eip155:8453",
+            "amount": "1000",
+            "payTo": "0x1111111111111111111111111111111111111111",
+            "maxTimeoutSeconds": 300,
+            "asset": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+            "extra": {"name": "USD Coin", "version": "2"},
+        });
+        let payment_requirements: Eip3009PaymentRequirements =
+            serde_json::from_value(requirements.clone()).unwrap();
+        let mut accepted = requirements;
+        accepted["maxTimeoutSeconds"] = json!(60);
+        accepted["extra"]["description"] = json!("Weather API");
+        let accepted: Eip3009PaymentRequirements = serde_json::from_value(accepted).unwrap();
+        assert!(accepted.assert_accepted(&payment_requirements, false).is_ok());
     }
 }
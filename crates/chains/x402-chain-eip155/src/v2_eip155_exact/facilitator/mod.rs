@@ -10,19 +10,25 @@ pub mod permit2;
 
 use alloy_provider::Provider;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use x402_types::chain::ChainProviderOps;
+use std::sync::Arc;
+use x402_types::chain::{ChainId, ChainProviderOps};
 use x402_types::proto;
-use x402_types::proto::v2;
+use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::{
     ExtensionKey, X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
+use x402_types::timestamp::{Clock, SystemClock};
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 use crate::V2Eip155Exact;
 use crate::chain::Eip155MetaTransactionProvider;
 use crate::eip2612_gas_sponsoring::Eip2612GasSponsoring;
 use crate::v1_eip155_exact::ExactScheme;
-use crate::v1_eip155_exact::facilitator::Eip155ExactError;
+use crate::v1_eip155_exact::facilitator::{
+    DeployedContractCache, Eip155ExactError, assert_asset_decimals,
+};
 use crate::v2_eip155_exact::types;
 
 impl<P> X402SchemeFacilitatorBuilder<P> for V2Eip155Exact
@@ -52,10 +58,21 @@ where
 /// - `eip2612_gas_sponsoring`: Whether to enable EIP-2612 gas-sponsoring extension.
 ///   When enabled, the facilitator supports atomic settlement with EIP-2612 permits,
 ///   allowing the payer to have their gas fees covered by the facilitator.
+/// - `strict_accepted_match`: Whether the requirements accepted by the client must equal
+///   the payment requirements field by field. By default they only need to be
+///   [functionally equal](v2::PaymentRequirements::is_functionally_equal), so that a
+///   different `maxTimeoutSeconds` or extra fields the scheme ignores are tolerated.
+/// - `accept_v1_network_names`: Whether V2 payments may name their network with a V1
+///   network name, e.g. `"base"` instead of `"eip155:8453"`. By default they are rejected
+///   with an `invalid_format` error naming the CAIP-2 chain ID to use instead.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct V2Eip155ExactFacilitatorConfig {
     #[serde(default)]
     pub eip2612_gas_sponsoring: bool,
+    #[serde(default)]
+    pub strict_accepted_match: bool,
+    #[serde(default)]
+    pub accept_v1_network_names: bool,
 }
 
 /// Extra data for the V2 EIP-155 exact scheme facilitator.
@@ -87,6 +104,10 @@ pub struct V2Eip155ExactFacilitatorExtra {
 pub struct V2Eip155ExactFacilitator<P> {
     provider: P,
     eip2612_gas_sponsoring: bool,
+    strict_accepted_match: bool,
+    accept_v1_network_names: bool,
+    deployed_contracts: DeployedContractCache,
+    clock: Arc<dyn Clock>,
 }
 
 impl<P> V2Eip155ExactFacilitator<P> {
@@ -95,8 +116,18 @@ impl<P> V2Eip155ExactFacilitator<P> {
         Self {
             provider,
             eip2612_gas_sponsoring: config.eip2612_gas_sponsoring,
+            strict_accepted_match: config.strict_accepted_match,
+            accept_v1_network_names: config.accept_v1_network_names,
+            deployed_contracts: DeployedContractCache::default(),
+            clock: Arc::new(SystemClock),
         }
     }
+
+    /// Checks authorization validity windows against `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -110,70 +141,176 @@ where
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let verify_request = types::FacilitatorVerifyRequest::try_from(request.clone())?;
-        let verify_response = match verify_request {
-            types::FacilitatorVerifyRequest::Eip3009 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                eip3009::verify_eip3009_payment(
-                    &self.provider,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-            types::FacilitatorVerifyRequest::Permit2 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                permit2::verify_permit2_payment(
-                    &self.provider,
-                    self.eip2612_gas_sponsoring,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-        };
-        Ok(verify_response.into())
+        verify_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = resolve_network_names(request, self.accept_v1_network_names)?;
+            let verify_request = types::FacilitatorVerifyRequest::try_from(request.into_owned())?;
+            let verify_response = match verify_request {
+                types::FacilitatorVerifyRequest::Eip3009 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    assert_asset_decimals(
+                        self.provider.inner(),
+                        self.provider.chain(),
+                        payment_requirements.asset.0,
+                        declared_decimals,
+                    )
+                    .await?;
+                    eip3009::verify_eip3009_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+                types::FacilitatorVerifyRequest::Permit2 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    assert_asset_decimals(
+                        self.provider.inner(),
+                        self.provider.chain(),
+                        payment_requirements.asset.0,
+                        declared_decimals,
+                    )
+                    .await?;
+                    permit2::verify_permit2_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.eip2612_gas_sponsoring,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+            };
+            Ok(verify_response.into())
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let settle_request = types::FacilitatorSettleRequest::try_from(request.clone())?;
-        let settle_response = match settle_request {
-            types::FacilitatorSettleRequest::Eip3009 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                eip3009::settle_eip3009_payment(
-                    &self.provider,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-            types::FacilitatorSettleRequest::Permit2 {
-                payment_requirements,
-                payment_payload,
-                x402_version: _,
-            } => {
-                permit2::settle_permit2_payment(
-                    &self.provider,
-                    self.eip2612_gas_sponsoring,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
+        settle_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = resolve_network_names(request, self.accept_v1_network_names)?;
+            let settle_request = types::FacilitatorSettleRequest::try_from(request.into_owned())?;
+            let settle_response = match settle_request {
+                types::FacilitatorSettleRequest::Eip3009 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    assert_asset_decimals(
+                        self.provider.inner(),
+                        self.provider.chain(),
+                        payment_requirements.asset.0,
+                        declared_decimals,
+                    )
+                    .await?;
+                    eip3009::settle_eip3009_payment(
+                        &self.provider,
+                        &self.deployed_contracts,
+                        &*self.clock,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+                types::FacilitatorSettleRequest::Permit2 {
+                    payment_requirements,
+                    payment_payload,
+                    x402_version: _,
+                } => {
+                    assert_asset_decimals(
+                        self.provider.inner(),
+                        self.provider.chain(),
+                        payment_requirements.asset.0,
+                        declared_decimals,
+                    )
+                    .await?;
+                    permit2::settle_permit2_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.eip2612_gas_sponsoring,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+            };
+            Ok(settle_response.into())
+        })
+        .await
+    }
+
+    /// Settles EIP-3009 payments in a single Multicall3 transaction, see
+    /// [`eip3009::settle_eip3009_payments_batch`]. Permit2 payments are settled one by one.
+    async fn settle_batch(
+        &self,
+        requests: &[proto::SettleRequest],
+    ) -> Vec<Result<proto::SettleResponse, X402SchemeFacilitatorError>> {
+        let mut results: Vec<Option<Result<proto::SettleResponse, X402SchemeFacilitatorError>>> =
+            requests.iter().map(|_| None).collect();
+        let mut eip3009_indices = Vec::new();
+        let mut eip3009_payments = Vec::new();
+        for (index, request) in requests.iter().enumerate() {
+            let request = match resolve_network_names(request, self.accept_v1_network_names) {
+                Ok(request) => request,
+                Err(e) => {
+                    results[index] = Some(Err(e.into()));
+                    continue;
+                }
+            };
+            match types::FacilitatorSettleRequest::try_from(request.as_ref().clone()) {
+                Ok(types::FacilitatorSettleRequest::Eip3009 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                }) => {
+                    let decimals = assert_asset_decimals(
+                        self.provider.inner(),
+                        self.provider.chain(),
+                        payment_requirements.asset.0,
+                        request.asset_decimals(),
+                    )
+                    .await;
+                    match decimals {
+                        Ok(()) => {
+                            eip3009_indices.push(index);
+                            eip3009_payments.push((payment_payload, payment_requirements));
+                        }
+                        Err(e) => results[index] = Some(Err(e.into())),
+                    }
+                }
+                Ok(types::FacilitatorSettleRequest::Permit2 { .. }) => {
+                    results[index] = Some(self.settle(&request).await);
+                }
+                Err(e) => results[index] = Some(Err(e.into())),
             }
-        };
-        Ok(settle_response.into())
+        }
+        let settled = eip3009::settle_eip3009_payments_batch(
+            &self.provider,
+            &self.deployed_contracts,
+            &*self.clock,
+            self.strict_accepted_match,
+            &eip3009_payments,
+        )
+        .await;
+        for (index, settled) in eip3009_indices.into_iter().zip(settled) {
+            results[index] = Some(settled.map(Into::into));
+        }
+        results.into_iter().flatten().collect()
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
@@ -207,3 +344,136 @@ where
         })
     }
 }
+
+/// Rewrites the V1 network names of a V2 `request`, e.g. `"base"`, to their CAIP-2 chain
+/// IDs if `accept_v1_network_names`, see [`V2Eip155ExactFacilitatorConfig`].
+///
+/// Returns the request unchanged if its networks are CAIP-2 chain IDs already.
+///
+/// # Errors
+///
+/// Returns [`PaymentVerificationError::InvalidFormat`] for a V1 network name if
+/// `accept_v1_network_names` is off, naming the CAIP-2 chain ID to use instead, and for
+/// an unknown network.
+fn resolve_network_names(
+    request: &proto::VerifyRequest,
+    accept_v1_network_names: bool,
+) -> Result<Cow<'_, proto::VerifyRequest>, PaymentVerificationError> {
+    let mut value: serde_json::Value = serde_json::from_str(request.as_str())
+        .map_err(|e| PaymentVerificationError::InvalidFormat(e.to_string()))?;
+    let mut rewritten = false;
+    for pointer in [
+        "/paymentPayload/accepted/network",
+        "/paymentRequirements/network",
+    ] {
+        let Some(network) = value.pointer_mut(pointer) else {
+            continue;
+        };
+        let Some(name) = network.as_str() else {
+            continue;
+        };
+        if name.parse::<ChainId>().is_ok() {
+            continue;
+        }
+        let Some(chain_id) = ChainId::from_network_name(name) else {
+            return Err(PaymentVerificationError::InvalidFormat(format!(
+                "Unknown network '{name}'"
+            )));
+        };
+        if !accept_v1_network_names {
+            return Err(PaymentVerificationError::InvalidFormat(format!(
+                "Network name '{name}' is a V1 format; use CAIP-2 '{chain_id}' for V2 payments."
+            )));
+        }
+        *network = chain_id.to_string().into();
+        rewritten = true;
+    }
+    if !rewritten {
+        return Ok(Cow::Borrowed(request));
+    }
+    serde_json::from_value(value)
+        .map(Cow::Owned)
+        .map_err(|e| PaymentVerificationError::InvalidFormat(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(accepted_network: &str, required_network: &str) -> proto::VerifyRequest {
+        serde_json::from_value(json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": { "scheme": "exact", "network": accepted_network },
+                "payload": {},
+            },
+            "paymentRequirements": { "scheme": "exact", "network": required_network },
+        }))
+        .unwrap()
+    }
+
+    fn networks(request: &proto::VerifyRequest) -> (String, String) {
+        let value: serde_json::Value = serde_json::from_str(request.as_str()).unwrap();
+        (
+            value["paymentPayload"]["accepted"]["network"].to_string(),
+            value["paymentRequirements"]["network"].to_string(),
+        )
+    }
+
+    #[test]
+    fn caip2_networks_are_kept() {
+        let request = request("eip155:8453", "eip155:8453");
+        for accept_v1_network_names in [false, true] {
+            let resolved = resolve_network_names(&request, accept_v1_network_names).unwrap();
+            assert!(matches!(resolved, Cow::Borrowed(_)));
+        }
+    }
+
+    #[test]
+    fn v1_network_names_are_resolved_when_accepted() {
+        let request = request("base-sepolia", "base-sepolia");
+        let resolved = resolve_network_names(&request, true).unwrap();
+        assert_eq!(
+            networks(&resolved),
+            (
+                "\"eip155:84532\"".to_string(),
+                "\"eip155:84532\"".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn v1_network_names_are_rejected_with_their_caip2_equivalent() {
+        for request in [
+            request("base", "eip155:8453"),
+            request("eip155:8453", "base"),
+        ] {
+            let error = resolve_network_names(&request, false).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                PaymentVerificationError::InvalidFormat(
+                    "Network name 'base' is a V1 format; use CAIP-2 'eip155:8453' for V2 payments."
+                        .into()
+                )
+                .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_networks_are_rejected() {
+        for accept_v1_network_names in [false, true] {
+            let error = resolve_network_names(
+                &request("not-a-network", "not-a-network"),
+                accept_v1_network_names,
+            )
+            .unwrap_err();
+            assert!(matches!(
+                error,
+                PaymentVerificationError::InvalidFormat(message) if message == "Unknown network 'not-a-network'"
+            ));
+        }
+    }
+}
@@ -6,6 +6,7 @@ use alloy_sol_types::{SolCall, SolStruct, eip712_domain};
 use x402_types::chain::ChainProviderOps;
 use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::X402SchemeFacilitatorError;
+use x402_types::timestamp::{Clock, ValidityWindow};
 
 use super::eip2612::{self, Permit2PaymentPayloadExt};
 
@@ -14,14 +15,16 @@ use tracing::Instrument;
 #[cfg(feature = "telemetry")]
 use tracing::instrument;
 
+use crate::chain::config::TransactionKind;
 use crate::chain::erc20::IERC20;
 use crate::chain::permit2::{EXACT_PERMIT2_PROXY_ADDRESS, PERMIT2_ADDRESS};
+use crate::chain::revert::simulation_failure;
 use crate::chain::{Eip155ChainReference, Eip155MetaTransactionProvider, MetaTransaction};
 use crate::v1_eip155_exact::{
-    Eip155ExactError, StructuredSignature, VALIDATOR_ADDRESS, Validator6492, assert_enough_value,
-    assert_time, is_contract_deployed, tx_hash_from_receipt,
+    Eip155ExactError, StructuredSignature, VALIDATOR_ADDRESS, Validator6492,
+    assert_eip6492_factory, assert_enough_value, assert_time, is_contract_deployed,
+    tx_hash_from_receipt,
 };
-use crate::v2_eip155_exact::eip3009::assert_requirements_match;
 use crate::v2_eip155_exact::types::{
     ISignatureTransfer, Permit2PaymentPayload, Permit2PaymentRequirements,
     PermitWitnessTransferFrom, X402ExactPermit2Proxy, x402ExactPermit2Proxy,
@@ -30,12 +33,23 @@ use crate::v2_eip155_exact::types::{
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub async fn verify_permit2_payment<P: Eip155MetaTransactionProvider + ChainProviderOps>(
     provider: &P,
+    clock: &dyn Clock,
     eip2612_gas_sponsoring: bool,
+    strict_accepted_match: bool,
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
 ) -> Result<v2::VerifyResponse, Eip155ExactError> {
     // 1. Verify offchain constraints
-    assert_offchain_valid(payment_payload, payment_requirements)?;
+    assert_offchain_valid(
+        payment_payload,
+        payment_requirements,
+        strict_accepted_match,
+        clock,
+    )?;
+    if let Ok(signature) = StructuredSignature::try_from(payment_payload.payload.signature.clone())
+    {
+        assert_eip6492_factory(&signature, provider.allowed_eip6492_factories())?;
+    }
 
     // 2. Verify onchain constraints
     let authorization = &payment_payload.payload.permit_2_authorization;
@@ -66,7 +80,9 @@ pub async fn verify_permit2_payment<P: Eip155MetaTransactionProvider + ChainProv
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub async fn settle_permit2_payment<P, E>(
     provider: &P,
+    clock: &dyn Clock,
     eip2612_gas_sponsoring: bool,
+    strict_accepted_match: bool,
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
 ) -> Result<v2::SettleResponse, X402SchemeFacilitatorError>
@@ -75,7 +91,16 @@ where
     Eip155ExactError: From<E>,
 {
     // 1. Verify offchain constraints
-    assert_offchain_valid(payment_payload, payment_requirements)?;
+    assert_offchain_valid(
+        payment_payload,
+        payment_requirements,
+        strict_accepted_match,
+        clock,
+    )?;
+    if let Ok(signature) = StructuredSignature::try_from(payment_payload.payload.signature.clone())
+    {
+        assert_eip6492_factory(&signature, provider.allowed_eip6492_factories())?;
+    }
 
     // Check if the client provided EIP-2612 gas-sponsoring extension data
     let eip2612_gas_sponsoring_payload = payment_payload.eip2612_gas_sponsoring();
@@ -112,10 +137,12 @@ where
 pub fn assert_offchain_valid(
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
+    strict_accepted_match: bool,
+    clock: &dyn Clock,
 ) -> Result<(), PaymentVerificationError> {
     let payload = &payment_payload.payload;
     let accepted = &payment_payload.accepted;
-    assert_requirements_match(accepted, payment_requirements)?;
+    accepted.assert_accepted(payment_requirements, strict_accepted_match)?;
 
     // Spender must be the x402ExactPermit2Proxy contract address
     let authorization = &payload.permit_2_authorization;
@@ -130,9 +157,8 @@ pub fn assert_offchain_valid(
     }
 
     // Time validity
-    let valid_after = witness.valid_after;
-    let valid_before = authorization.deadline;
-    assert_time(valid_after, valid_before)?;
+    let window = ValidityWindow::new(witness.valid_after, authorization.deadline);
+    assert_time(&window, clock)?;
 
     // Sufficient amount
     let amount_required = &accepted.amount;
@@ -280,7 +306,8 @@ where
                     calls: vec![deployment_call, transfer_with_authorization_call],
                 };
                 let meta_tx =
-                    MetaTransaction::new(MULTICALL3_ADDRESS, aggregate_call.abi_encode().into());
+                    MetaTransaction::new(MULTICALL3_ADDRESS, aggregate_call.abi_encode().into())
+                        .with_kind(TransactionKind::Eip6492Deployment);
                 let tx_fut = Eip155MetaTransactionProvider::send_transaction(provider, meta_tx);
                 #[cfg(feature = "telemetry")]
                 let receipt = tx_fut
@@ -439,8 +466,7 @@ pub async fn assert_onchain_exact_permit2<P: Provider>(
                 )
                 .into());
             }
-            transfer_result
-                .map_err(|e| PaymentVerificationError::TransactionSimulation(e.to_string()))?;
+            transfer_result.map_err(|e| simulation_failure(e.to_string()))?;
             Ok(())
         }
         StructuredSignature::EOA(signature) => {
@@ -14,34 +14,42 @@
 //! ```
 
 use alloy_primitives::{Address, U256};
+#[cfg(not(target_arch = "wasm32"))]
 use alloy_provider::fillers::{FillProvider, TxFiller};
+#[cfg(not(target_arch = "wasm32"))]
 use alloy_provider::{Network, Provider, ProviderBuilder, RootProvider};
 use alloy_sol_types::{SolStruct, eip712_domain};
 use async_trait::async_trait;
 use rand::{RngExt, rng};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
 use url::Url;
 use x402_types::proto::v2::{ExtensionsJson, ResourceInfo};
 use x402_types::proto::{OriginalJson, PaymentRequired, v2};
 use x402_types::scheme::X402SchemeId;
 use x402_types::scheme::client::{
-    PaymentCandidate, PaymentCandidateSigner, X402Error, X402SchemeClient,
+    PaymentCandidate, PaymentCandidateSigner, SigningOptions, X402Error, X402SchemeClient,
 };
 use x402_types::timestamp::UnixTimestamp;
 use x402_types::util::Base64Bytes;
 
+#[cfg(not(target_arch = "wasm32"))]
 use crate::chain::erc20::IERC20;
 use crate::chain::permit2::{
     PERMIT2_ADDRESS, Permit2Authorization, Permit2AuthorizationPermitted,
     UPTO_PERMIT2_PROXY_ADDRESS, UptoPermit2Payload, UptoPermit2Witness,
 };
-use crate::chain::{ChecksummedAddress, EOASignature, Eip155ChainReference};
+use crate::chain::{ChecksummedAddress, EOASignature, Eip155ChainReference, Eip712DomainCache};
 use crate::eip2612_gas_sponsoring::{
     Eip2612GasSponsoring, Eip2612GasSponsoringInfo, Eip2612GasSponsoringServer, Permit,
 };
-use crate::v1_eip155_exact::client::SignerLike;
+use crate::v1_eip155_exact::PaymentRequirementsExtra;
+use crate::v1_eip155_exact::client::{DEFAULT_CLOCK_SKEW, SignerLike};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::v2_eip155_upto::IERC20Permit;
 use crate::v2_eip155_upto::types::{ISignatureTransfer, PermitWitnessTransferFrom};
-use crate::v2_eip155_upto::{IERC20Permit, UptoSupportedExtra, V2Eip155Upto};
+use crate::v2_eip155_upto::{UptoSupportedExtra, V2Eip155Upto};
 use crate::v2_eip155_upto::{types, x402UptoPermit2Proxy};
 
 /// Parameters for signing a Permit2 upto authorization.
@@ -58,6 +66,10 @@ pub struct Permit2UptoSigningParams {
     pub max_amount: U256,
     /// Maximum timeout in seconds for the authorization validity window
     pub max_timeout_seconds: u64,
+    /// Time of signing, on the server's clock, see [`SigningOptions::now`]
+    pub now: UnixTimestamp,
+    /// Clock skew, see [`DEFAULT_CLOCK_SKEW`]
+    pub clock_skew: Duration,
     /// The facilitator address authorized to settle this payment
     pub facilitator: Address,
 }
@@ -80,12 +92,10 @@ pub async fn sign_permit2_upto_authorization<S: SignerLike + Sync>(
         verifying_contract: PERMIT2_ADDRESS,
     };
 
-    // Build authorization with timing
-    let now = UnixTimestamp::now();
-    // valid_after should be in the past (10 minutes ago) to ensure the payment is immediately valid
-    let valid_after_secs = now.as_secs().saturating_sub(10 * 60);
-    let valid_after = UnixTimestamp::from_secs(valid_after_secs);
-    let deadline = now + params.max_timeout_seconds;
+    // Build authorization with timing: valid_after is in the past, so that the payment is
+    // valid at once even if the facilitator's clock is behind
+    let valid_after = params.now - params.clock_skew;
+    let deadline = params.now + params.max_timeout_seconds;
 
     // Generate a random nonce
     let nonce: [u8; 32] = rng().random();
@@ -160,6 +170,8 @@ pub async fn sign_permit2_upto_authorization<S: SignerLike + Sync>(
 pub struct V2Eip155UptoClient<S, P> {
     signer: S,
     provider: P,
+    domains: Eip712DomainCache,
+    clock_skew: Duration,
 }
 
 #[allow(dead_code)] // Public for consumption by downstream crates.
@@ -169,6 +181,8 @@ impl<S> V2Eip155UptoClient<S, ()> {
         Self {
             signer,
             provider: (),
+            domains: Eip712DomainCache::new(),
+            clock_skew: DEFAULT_CLOCK_SKEW,
         }
     }
 }
@@ -183,8 +197,28 @@ impl<S, P> V2Eip155UptoClient<S, P> {
         V2Eip155UptoClient {
             signer: self.signer,
             provider,
+            domains: self.domains,
+            clock_skew: self.clock_skew,
         }
     }
+
+    /// Sets the clock skew, [`DEFAULT_CLOCK_SKEW`] by default.
+    pub fn with_clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    /// Uses `domains` to cache the token EIP-712 domains of EIP-2612 permits, e.g. to share
+    /// one cache with the exact scheme clients.
+    pub fn with_domain_cache(mut self, domains: Eip712DomainCache) -> Self {
+        self.domains = domains;
+        self
+    }
+
+    /// Returns the cache of token EIP-712 domains, to invalidate entries.
+    pub fn domain_cache(&self) -> &Eip712DomainCache {
+        &self.domains
+    }
 }
 
 impl<S, P> X402SchemeId for V2Eip155UptoClient<S, P> {
@@ -203,6 +237,14 @@ where
     P: Clone + EIP2612ProviderLike + Send + Sync + 'static,
 {
     fn accept(&self, payment_required: &PaymentRequired) -> Vec<PaymentCandidate> {
+        self.accept_with_options(payment_required, &SigningOptions::default())
+    }
+
+    fn accept_with_options(
+        &self,
+        payment_required: &PaymentRequired,
+        options: &SigningOptions,
+    ) -> Vec<PaymentCandidate> {
         let payment_required = match payment_required {
             PaymentRequired::V2(payment_required) => payment_required,
             PaymentRequired::V1(_) => {
@@ -230,7 +272,10 @@ where
                     signer: Box::new(PayloadSigner {
                         resource_info: payment_required.resource.clone(),
                         signer: self.signer.clone(),
+                        options: *options,
                         provider: self.provider.clone(),
+                        domains: self.domains.clone(),
+                        clock_skew: self.clock_skew,
                         chain_reference,
                         requirements,
                         extensions: payment_required.extensions.clone(),
@@ -246,7 +291,10 @@ where
 #[allow(dead_code)] // Public for consumption by downstream crates.
 struct PayloadSigner<S, P> {
     signer: S,
+    options: SigningOptions,
     provider: P,
+    domains: Eip712DomainCache,
+    clock_skew: Duration,
     resource_info: Option<ResourceInfo>,
     extensions: ExtensionsJson,
     chain_reference: Eip155ChainReference,
@@ -264,13 +312,6 @@ where
         deadline: UnixTimestamp,
         eip2612_gas_sponsoring: Eip2612GasSponsoringServer,
     ) -> Result<Option<Eip2612GasSponsoring>, X402Error> {
-        /// Token name and version for permit signature
-        #[derive(Debug, Clone, Serialize, Deserialize)]
-        struct TokenDomain {
-            pub name: String,
-            pub version: String,
-        }
-
         let token_contract = self.requirements.asset;
         let value = self.requirements.amount;
         let owner = self.signer.address();
@@ -284,9 +325,10 @@ where
             .provider
             .read_erc20_allowance(token_contract.into(), owner, PERMIT2_ADDRESS)
             .await
-            .inspect_err(|e| {
+            .inspect_err(|_e| {
+                #[cfg(feature = "telemetry")]
                 tracing::warn!(
-                    error=%e,
+                    error=%_e,
                     "failed to read erc20 allowance for eip2612GasSponsoring, assuming zero"
                 )
             })
@@ -297,11 +339,19 @@ where
             return Ok(None);
         }
 
+        // Token name and version for the permit signature, from extra or cached
         let token_domain = self
             .requirements
             .extra
             .as_ref()
-            .and_then(|v| TokenDomain::deserialize(v).ok())
+            .and_then(|v| PaymentRequirementsExtra::deserialize(v).ok());
+        let domain = self
+            .domains
+            .resolve(
+                self.chain_reference.inner(),
+                token_contract.into(),
+                token_domain.as_ref(),
+            )
             .ok_or(X402Error::SigningError(
                 "extra should contain token name and version for eip2612GasSponsoring".to_string(),
             ))?;
@@ -316,12 +366,6 @@ where
             }
         };
 
-        let domain = eip712_domain! {
-            name: token_domain.name,
-            version: token_domain.version,
-            chain_id: self.chain_reference.inner(),
-            verifying_contract: token_contract.into(),
-        };
         let permit = Permit {
             owner,
             spender: PERMIT2_ADDRESS,
@@ -331,7 +375,7 @@ where
         };
         let signature = self
             .signer
-            .sign_hash(&permit.eip712_signing_hash(&domain))
+            .sign_hash(&domain.signing_hash(&permit))
             .await
             .map_err(|e| X402Error::SigningError(format!("{e:?}")))?;
 
@@ -373,7 +417,11 @@ where
             asset_address: self.requirements.asset.0,
             pay_to: self.requirements.pay_to.into(),
             max_amount: self.requirements.amount,
-            max_timeout_seconds: self.requirements.max_timeout_seconds,
+            max_timeout_seconds: self
+                .options
+                .validity_seconds(self.requirements.max_timeout_seconds),
+            now: self.options.now(),
+            clock_skew: self.clock_skew,
             facilitator: facilitator_address,
         };
 
@@ -389,9 +437,10 @@ where
                     eip2612_gas_sponsoring,
                 )
                 .await
-                .inspect_err(
-                    |e| tracing::error!(error=%e, "failed to sign eip2612GasSponsoring extension"),
-                )
+                .inspect_err(|_e| {
+                    #[cfg(feature = "telemetry")]
+                    tracing::error!(error=%_e, "failed to sign eip2612GasSponsoring extension")
+                })
                 .ok()
                 .flatten();
             if let Some(result) = eip2612_gas_sponsoring_result {
@@ -424,6 +473,11 @@ where
 /// Implementations that do not have access to an RPC provider (e.g. the unit
 /// type `()`) return `Ok(None)`, which causes the extension to be skipped or the
 /// allowance check to be bypassed (treated as zero).
+///
+/// The implementations for alloy providers and [`Url`](url::Url) are not available on
+/// `wasm32`, where alloy's RPC futures are not `Send`. In browsers, implement this trait
+/// over a JSON-RPC client of your own, e.g. wrapping its futures in
+/// `send_wrapper::SendWrapper`, which is sound as browsers run WebAssembly on one thread.
 pub trait EIP2612ProviderLike {
     /// Reads the EIP-2612 permit nonce for `owner` on the `asset` token contract.
     ///
@@ -449,6 +503,7 @@ pub trait EIP2612ProviderLike {
     ) -> impl Future<Output = Result<Option<U256>, X402Error>> + Send;
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl EIP2612ProviderLike for Url {
     async fn read_eip2612_nonce(
         &self,
@@ -489,6 +544,7 @@ impl EIP2612ProviderLike for () {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl<N> EIP2612ProviderLike for RootProvider<N>
 where
     N: Network,
@@ -521,6 +577,7 @@ where
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl<F, P, N> EIP2612ProviderLike for FillProvider<F, P, N>
 where
     F: TxFiller<N>,
@@ -6,22 +6,29 @@
 
 pub mod eip2612;
 pub mod permit2;
+pub mod verification_token;
 
 use alloy_provider::Provider;
 use rand::seq::IndexedRandom;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use x402_types::chain::ChainProviderOps;
+use x402_types::config::LiteralOrEnv;
 use x402_types::proto;
 use x402_types::proto::v2;
 use x402_types::scheme::{
     ExtensionKey, X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
+use x402_types::timestamp::{Clock, SystemClock};
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 use crate::V2Eip155Upto;
 use crate::chain::{Eip155MetaTransactionProvider, Eip155SignerAddresses};
 use crate::eip2612_gas_sponsoring::Eip2612GasSponsoring;
-use crate::v1_eip155_exact::facilitator::Eip155ExactError;
+use crate::v1_eip155_exact::facilitator::{Eip155ExactError, assert_asset_decimals};
+use crate::v2_eip155_upto::facilitator::verification_token::VerificationTokens;
 use crate::v2_eip155_upto::types;
 
 /// Configuration for the V2 EIP-155 upto scheme facilitator.
@@ -29,10 +36,30 @@ use crate::v2_eip155_upto::types;
 /// - `eip2612_gas_sponsoring`: Whether to enable EIP-2612 gas-sponsoring extension.
 ///   When enabled, the facilitator supports atomic settlement with EIP-2612 permits,
 ///   allowing the payer to have their gas fees covered by the facilitator.
+/// - `verification_token`: Issue verification tokens letting settlement skip the on-chain
+///   checks of a verification just made, see [`verification_token`]. Disabled by default.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct V2Eip155UptoFacilitatorConfig {
     #[serde(default)]
     pub eip2612_gas_sponsoring: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_token: Option<VerificationTokenConfig>,
+}
+
+/// Configuration of the verification tokens of the upto facilitator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationTokenConfig {
+    /// Secret the tokens are signed with, e.g. `"$VERIFICATION_TOKEN_SECRET"`.
+    pub secret: LiteralOrEnv<String>,
+    /// How long (seconds) a token is valid after verification (optional).
+    #[serde(default = "VerificationTokenConfig::default_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+impl VerificationTokenConfig {
+    fn default_ttl_secs() -> u64 {
+        30
+    }
 }
 
 impl<P> X402SchemeFacilitatorBuilder<P> for V2Eip155Upto
@@ -50,6 +77,7 @@ where
         provider: P,
         config: Option<serde_json::Value>,
     ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        assert_has_signers(&provider)?;
         let config: V2Eip155UptoFacilitatorConfig = config
             .and_then(|c| V2Eip155UptoFacilitatorConfig::deserialize(c).ok())
             .unwrap_or_default();
@@ -57,6 +85,22 @@ where
     }
 }
 
+/// Fails if `provider` holds no signers, as on a verify-only chain.
+///
+/// Every upto payment names its settling facilitator in the Permit2 witness, and
+/// [`permit2::assert_own_signer`] rejects the ones not naming one of our signers: without
+/// signers, the scheme would reject every payment.
+fn assert_has_signers<P: Eip155SignerAddresses>(
+    provider: &P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if provider.signer_addresses().is_empty() {
+        return Err(
+            "the upto scheme needs signers, and is not available on verify-only chains".into(),
+        );
+    }
+    Ok(())
+}
+
 /// Facilitator for V2 EIP-155 upto scheme payments.
 ///
 /// This struct implements the [`X402SchemeFacilitator`] trait to provide payment
@@ -77,15 +121,32 @@ where
 pub struct V2Eip155UptoFacilitator<P> {
     provider: P,
     eip2612_gas_sponsoring: bool,
+    verification_tokens: Option<VerificationTokens>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<P> V2Eip155UptoFacilitator<P> {
     pub fn new(provider: P, config: V2Eip155UptoFacilitatorConfig) -> Self {
+        let verification_tokens = config.verification_token.map(|config| {
+            VerificationTokens::new(
+                config.secret.into_inner(),
+                Duration::from_secs(config.ttl_secs),
+            )
+        });
         Self {
             provider,
             eip2612_gas_sponsoring: config.eip2612_gas_sponsoring,
+            verification_tokens,
+            clock: Arc::new(SystemClock),
         }
     }
+
+    /// Checks authorization validity windows and verification tokens against `clock` instead
+    /// of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -99,30 +160,75 @@ where
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let verify_request = types::VerifyRequest::try_from(request)?;
-        let verify_response = permit2::verify_permit2_payment(
-            &self.provider,
-            self.eip2612_gas_sponsoring,
-            &verify_request.payment_payload,
-            &verify_request.payment_requirements,
-        )
-        .await?;
-        Ok(verify_response.into())
+        verify_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let verify_request = types::VerifyRequest::try_from(request)?;
+            assert_asset_decimals(
+                self.provider.inner(),
+                self.provider.chain(),
+                verify_request.payment_requirements.asset.0,
+                declared_decimals,
+            )
+            .await?;
+            let verify_response = permit2::verify_permit2_payment(
+                &self.provider,
+                &*self.clock,
+                self.eip2612_gas_sponsoring,
+                &verify_request.payment_payload,
+                &verify_request.payment_requirements,
+            )
+            .await?;
+            let verify_response = match &self.verification_tokens {
+                Some(tokens) => {
+                    let payload_hash =
+                        verification_token::payload_hash(&verify_request.payment_payload);
+                    verification_token::with_token(
+                        verify_response.into(),
+                        tokens.issue_at(payload_hash, self.clock.now()),
+                    )
+                }
+                None => verify_response.into(),
+            };
+            Ok(verify_response)
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let settle_request = types::SettleRequest::try_from(request)?;
-        let settle_response = permit2::settle_permit2_payment(
-            &self.provider,
-            self.eip2612_gas_sponsoring,
-            &settle_request.payment_payload,
-            &settle_request.payment_requirements,
-        )
-        .await?;
-        Ok(settle_response.into())
+        settle_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let settle_request = types::SettleRequest::try_from(request)?;
+            // A valid verification token vouches for the on-chain checks of the verification
+            let verified = self.verification_tokens.as_ref().is_some_and(|tokens| {
+                verification_token::request_token(request).is_some_and(|token| {
+                    let payload_hash =
+                        verification_token::payload_hash(&settle_request.payment_payload);
+                    tokens.check_at(&token, payload_hash, self.clock.now())
+                })
+            });
+            if !verified {
+                assert_asset_decimals(
+                    self.provider.inner(),
+                    self.provider.chain(),
+                    settle_request.payment_requirements.asset.0,
+                    declared_decimals,
+                )
+                .await?;
+            }
+            let settle_response = permit2::settle_permit2_payment(
+                &self.provider,
+                &*self.clock,
+                self.eip2612_gas_sponsoring,
+                &settle_request.payment_payload,
+                &settle_request.payment_requirements,
+            )
+            .await?;
+            Ok(settle_response.into())
+        })
+        .await
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
@@ -162,3 +268,29 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, address};
+
+    struct Signers(Vec<Address>);
+
+    impl Eip155SignerAddresses for Signers {
+        fn signer_addresses(&self) -> Vec<Address> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn verify_only_chains_can_not_run_upto() {
+        let error = assert_has_signers(&Signers(vec![])).unwrap_err();
+        assert!(error.to_string().contains("verify-only"), "{error}");
+    }
+
+    #[test]
+    fn chains_with_signers_can_run_upto() {
+        let signer = address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07");
+        assert!(assert_has_signers(&Signers(vec![signer])).is_ok());
+    }
+}
@@ -4,6 +4,7 @@ use alloy_sol_types::{SolStruct, eip712_domain};
 use x402_types::chain::ChainProviderOps;
 use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::X402SchemeFacilitatorError;
+use x402_types::timestamp::{Clock, ValidityWindow};
 
 #[cfg(feature = "telemetry")]
 use tracing::Instrument;
@@ -12,11 +13,13 @@ use tracing::instrument;
 
 use crate::chain::erc20::IERC20;
 use crate::chain::permit2::{PERMIT2_ADDRESS, UPTO_PERMIT2_PROXY_ADDRESS};
+use crate::chain::revert::simulation_failure;
 use crate::chain::{
     Eip155ChainReference, Eip155MetaTransactionProvider, Eip155SignerAddresses, MetaTransaction,
 };
 use crate::v1_eip155_exact::{
-    Eip155ExactError, StructuredSignature, VALIDATOR_ADDRESS, Validator6492, assert_time,
+    Eip155ExactError, StructuredSignature, VALIDATOR_ADDRESS, Validator6492,
+    assert_eip6492_factory, assert_time,
 };
 use crate::v2_eip155_exact::eip2612::assert_eip2612_offchain_valid;
 use crate::v2_eip155_exact::facilitator::permit2::{
@@ -90,12 +93,18 @@ impl PreparedUptoPermit2 {
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub async fn verify_permit2_payment<P: Eip155MetaTransactionProvider + Eip155SignerAddresses>(
     provider: &P,
+    clock: &dyn Clock,
     eip2612_gas_sponsoring: bool,
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
 ) -> Result<v2::VerifyResponse, Eip155ExactError> {
     // 1. Verify offchain constraints
-    let required_amount = assert_offchain_valid_verify(payment_payload, payment_requirements)?;
+    let required_amount =
+        assert_offchain_valid_verify(payment_payload, payment_requirements, clock)?;
+    if let Ok(signature) = StructuredSignature::try_from(payment_payload.payload.signature.clone())
+    {
+        assert_eip6492_factory(&signature, provider.allowed_eip6492_factories())?;
+    }
 
     // 2. Verify the witness.facilitator is one of this facilitator's signer addresses
     let authorization = &payment_payload.payload.permit_2_authorization;
@@ -163,6 +172,7 @@ where
 #[cfg_attr(feature = "telemetry", instrument(skip_all, err))]
 pub async fn settle_permit2_payment<P, E>(
     provider: &P,
+    clock: &dyn Clock,
     eip2612_gas_sponsoring: bool,
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
@@ -172,7 +182,12 @@ where
     Eip155ExactError: From<E>,
 {
     // 1. Verify offchain constraints
-    let required_amount = assert_offchain_valid_settle(payment_payload, payment_requirements)?;
+    let required_amount =
+        assert_offchain_valid_settle(payment_payload, payment_requirements, clock)?;
+    if let Ok(signature) = StructuredSignature::try_from(payment_payload.payload.signature.clone())
+    {
+        assert_eip6492_factory(&signature, provider.allowed_eip6492_factories())?;
+    }
 
     let authorization = &payment_payload.payload.permit_2_authorization;
     let payer = authorization.from;
@@ -216,8 +231,9 @@ where
 pub fn assert_offchain_valid_verify(
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
+    clock: &dyn Clock,
 ) -> Result<U256, PaymentVerificationError> {
-    assert_offchain_valid(payment_payload, payment_requirements)?;
+    assert_offchain_valid(payment_payload, payment_requirements, clock)?;
     // Authorized amount must EQUAL the required amount (client authorizes exact max)
     // The server can then settle for any amount <= this max
     let authorization = &payment_payload.payload.permit_2_authorization;
@@ -232,8 +248,9 @@ pub fn assert_offchain_valid_verify(
 pub fn assert_offchain_valid_settle(
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
+    clock: &dyn Clock,
 ) -> Result<U256, PaymentVerificationError> {
-    assert_offchain_valid(payment_payload, payment_requirements)?;
+    assert_offchain_valid(payment_payload, payment_requirements, clock)?;
     // Authorized amount must EQUAL the required amount (client authorizes exact max)
     // The server can then settle for any amount <= this max
     let authorization = &payment_payload.payload.permit_2_authorization;
@@ -253,6 +270,7 @@ pub fn assert_offchain_valid_settle(
 pub fn assert_offchain_valid(
     payment_payload: &Permit2PaymentPayload,
     payment_requirements: &Permit2PaymentRequirements,
+    clock: &dyn Clock,
 ) -> Result<(), PaymentVerificationError> {
     let payload = &payment_payload.payload;
     let accepted = &payment_payload.accepted;
@@ -285,9 +303,8 @@ pub fn assert_offchain_valid(
     }
 
     // Time validity
-    let valid_after = witness.valid_after;
-    let valid_before = authorization.deadline;
-    assert_time(valid_after, valid_before)?;
+    let window = ValidityWindow::new(witness.valid_after, authorization.deadline);
+    assert_time(&window, clock)?;
 
     // Same token
     if authorization.permitted.token != accepted.asset {
@@ -377,8 +394,7 @@ pub async fn assert_onchain_upto_permit2<P: Provider>(
                 )
                 .into());
             }
-            transfer_result
-                .map_err(|e| PaymentVerificationError::TransactionSimulation(e.to_string()))?;
+            transfer_result.map_err(|e| simulation_failure(e.to_string()))?;
             Ok(())
         }
         StructuredSignature::EOA(signature) => {
@@ -0,0 +1,183 @@
+//! Verification tokens, letting settlement skip the checks of a verification just made.
+//!
+//! A resource server verifies an upto payment, serves the resource, then settles it. Settling
+//! repeats the on-chain checks of the verification. For trusted, high-throughput deployments,
+//! the facilitator can instead issue a verification token along with a successful verification,
+//! in the `verificationToken` field of the `/verify` response. Sending the token back in the
+//! `verificationToken` field of the `/settle` request skips the repeated on-chain checks.
+//!
+//! # Security Model
+//!
+//! A token is `<expiry>.<mac>`: the Unix timestamp it expires at, and the HMAC-SHA256, under a
+//! secret known only to the facilitator, of the expiry and the Keccak-256 hash of the payment
+//! payload. A token therefore vouches that this facilitator verified this exact payload, until
+//! the expiry. It cannot be forged without the secret, nor reused for another payload.
+//!
+//! Within the token lifetime, the facilitator trusts that the on-chain state checked at
+//! verification still holds. Checks that do not need the chain, such as the requirements
+//! matching the payload and the amount to settle, always run. Settlement itself still fails on
+//! chain if the payment became invalid, e.g. the payer spent the funds, at the cost of the gas
+//! of the failed transaction. An invalid or expired token is ignored, and settlement runs all
+//! checks.
+//!
+//! Use a random secret of at least 32 bytes, shared by all instances of the facilitator behind
+//! the same URL, and a short lifetime.
+
+use alloy_primitives::{B256, hex, keccak256};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+use x402_types::proto;
+use x402_types::timestamp::UnixTimestamp;
+
+/// Name of the field carrying the token in `/verify` responses and `/settle` requests.
+pub const VERIFICATION_TOKEN_FIELD: &str = "verificationToken";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Issues and checks verification tokens under a facilitator secret.
+#[derive(Clone)]
+pub struct VerificationTokens {
+    secret: Vec<u8>,
+    ttl: Duration,
+}
+
+impl Debug for VerificationTokens {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerificationTokens")
+            .field("secret", &"<redacted>")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl VerificationTokens {
+    /// Creates tokens signed with `secret`, valid for `ttl` after they are issued.
+    pub fn new(secret: impl Into<Vec<u8>>, ttl: Duration) -> Self {
+        Self {
+            secret: secret.into(),
+            ttl,
+        }
+    }
+
+    /// Issues a token for the payload with hash `payload_hash`, valid from now.
+    pub fn issue(&self, payload_hash: B256) -> String {
+        self.issue_at(payload_hash, UnixTimestamp::now())
+    }
+
+    /// Issues a token for the payload with hash `payload_hash`, valid from `now`.
+    pub fn issue_at(&self, payload_hash: B256, now: UnixTimestamp) -> String {
+        let expiry = now + self.ttl;
+        let mac = self.mac(payload_hash, expiry).finalize().into_bytes();
+        format!("{expiry}.{}", hex::encode(mac))
+    }
+
+    /// Returns whether `token` was issued for the payload with hash `payload_hash`, and has
+    /// not expired.
+    pub fn check(&self, token: &str, payload_hash: B256) -> bool {
+        self.check_at(token, payload_hash, UnixTimestamp::now())
+    }
+
+    /// Returns whether `token` was issued for the payload with hash `payload_hash`, and has
+    /// not expired at `now`.
+    pub fn check_at(&self, token: &str, payload_hash: B256, now: UnixTimestamp) -> bool {
+        let Some((expiry, mac)) = token.split_once('.') else {
+            return false;
+        };
+        let (Ok(expiry), Ok(mac)) = (expiry.parse::<u64>(), hex::decode(mac)) else {
+            return false;
+        };
+        let expiry = UnixTimestamp::from_secs(expiry);
+        now < expiry && self.mac(payload_hash, expiry).verify_slice(&mac).is_ok()
+    }
+
+    fn mac(&self, payload_hash: B256, expiry: UnixTimestamp) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(&expiry.as_secs().to_be_bytes());
+        mac.update(payload_hash.as_slice());
+        mac
+    }
+}
+
+/// Returns the hash a verification token of `payment_payload` is bound to.
+pub fn payload_hash<T: Serialize>(payment_payload: &T) -> B256 {
+    let json = serde_json::to_vec(payment_payload).expect("payment payload serialization failed");
+    keccak256(json)
+}
+
+/// Extracts the verification token of a `/settle` request, if any.
+pub fn request_token(request: &proto::SettleRequest) -> Option<String> {
+    let request: serde_json::Value = serde_json::from_str(request.as_str()).ok()?;
+    let token = request.get(VERIFICATION_TOKEN_FIELD)?.as_str()?;
+    Some(token.to_string())
+}
+
+/// Adds `token` to a successful `/verify` response.
+pub fn with_token(mut response: proto::VerifyResponse, token: String) -> proto::VerifyResponse {
+    if let serde_json::Value::Object(fields) = &mut response.0 {
+        fields.insert(VERIFICATION_TOKEN_FIELD.to_string(), token.into());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn tokens() -> VerificationTokens {
+        VerificationTokens::new("a facilitator secret", Duration::from_secs(30))
+    }
+
+    #[test]
+    fn tokens_vouch_for_their_payload_until_expiry() {
+        let tokens = tokens();
+        let payload = payload_hash(&serde_json::json!({ "signature": "0x01" }));
+        let token = tokens.issue_at(payload, UnixTimestamp::from_secs(NOW));
+        assert!(token.starts_with("1700000030."));
+
+        assert!(tokens.check_at(&token, payload, UnixTimestamp::from_secs(NOW)));
+        assert!(tokens.check_at(&token, payload, UnixTimestamp::from_secs(NOW + 29)));
+        assert!(!tokens.check_at(&token, payload, UnixTimestamp::from_secs(NOW + 30)));
+
+        let other_payload = payload_hash(&serde_json::json!({ "signature": "0x02" }));
+        assert!(!tokens.check_at(&token, other_payload, UnixTimestamp::from_secs(NOW)));
+    }
+
+    #[test]
+    fn forged_tokens_are_rejected() {
+        let tokens = tokens();
+        let payload = payload_hash(&serde_json::json!({ "signature": "0x01" }));
+        let now = UnixTimestamp::from_secs(NOW);
+        let token = tokens.issue_at(payload, now);
+
+        // Another secret
+        let forged = VerificationTokens::new("another secret", Duration::from_secs(30))
+            .issue_at(payload, now);
+        assert!(!tokens.check_at(&forged, payload, now));
+
+        // A later expiry with the same MAC
+        let (_, mac) = token.split_once('.').unwrap();
+        assert!(!tokens.check_at(&format!("1800000000.{mac}"), payload, now));
+
+        for malformed in ["", "1700000030", "soon.00", "1700000030.zz"] {
+            assert!(!tokens.check_at(malformed, payload, now));
+        }
+    }
+
+    #[test]
+    fn tokens_travel_in_verify_responses_and_settle_requests() {
+        let response = proto::VerifyResponse(serde_json::json!({ "isValid": true }));
+        let response = with_token(response, "1700000030.00".into());
+        assert_eq!(response.0[VERIFICATION_TOKEN_FIELD], "1700000030.00");
+
+        let request: proto::SettleRequest =
+            serde_json::from_str(r#"{"x402Version":2,"verificationToken":"1700000030.00"}"#)
+                .unwrap();
+        assert_eq!(request_token(&request).as_deref(), Some("1700000030.00"));
+    }
+}
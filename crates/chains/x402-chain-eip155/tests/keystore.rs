@@ -0,0 +1,101 @@
+//! Signers decrypted from an encrypted JSON keystore.
+//!
+//! `fixtures/keystore.json` is a keystore v3 file (scrypt, AES-128-CTR) holding the first
+//! account of the default Hardhat and Anvil mnemonic, encrypted with [`PASSWORD`]. Its scrypt
+//! parameters are lighter than the defaults, to keep the tests fast.
+//! `fixtures/keystore-corrupt.json` is the same keystore with a ciphertext that is not hex.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use alloy_primitives::{Address, Signature, U256, address};
+use alloy_sol_types::{SolStruct, eip712_domain};
+use serde_json::json;
+use std::path::PathBuf;
+use x402_chain_eip155::V2Eip155ExactClient;
+use x402_chain_eip155::keystore::{KeystoreError, decrypt_keystore};
+use x402_chain_eip155::v1_eip155_exact::{ExactEvmPayload, TransferWithAuthorization};
+use x402_types::proto::PaymentRequired;
+use x402_types::scheme::client::X402SchemeClient;
+use x402_types::util::Base64Bytes;
+
+const PASSWORD: &str = "x402-test-password";
+const PAYER: Address = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+const USDC_BASE_SEPOLIA: Address = address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+const PAY_TO: Address = address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07");
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn payment_required() -> PaymentRequired {
+    let payment_required = json!({
+        "x402Version": 2,
+        "resource": { "url": "https://api.example.com/protected" },
+        "accepts": [{
+            "scheme": "exact",
+            "network": "eip155:84532",
+            "amount": "10000",
+            "payTo": PAY_TO,
+            "maxTimeoutSeconds": 300,
+            "asset": USDC_BASE_SEPOLIA,
+            "extra": { "name": "USDC", "version": "2" },
+        }],
+    });
+    PaymentRequired::V2(serde_json::from_str(&payment_required.to_string()).unwrap())
+}
+
+#[test]
+fn decrypts_the_keystore_key() {
+    let signer = decrypt_keystore(fixture("keystore.json"), PASSWORD).unwrap();
+    assert_eq!(signer.address(), PAYER);
+}
+
+#[test]
+fn wrong_password_is_told_apart_from_corrupt_file() {
+    let error = decrypt_keystore(fixture("keystore.json"), "not the password").unwrap_err();
+    assert!(matches!(error, KeystoreError::WrongPassword), "{error:?}");
+
+    let error = decrypt_keystore(fixture("keystore-corrupt.json"), PASSWORD).unwrap_err();
+    assert!(matches!(error, KeystoreError::Corrupt(_)), "{error:?}");
+
+    let error = decrypt_keystore(fixture("missing.json"), PASSWORD).unwrap_err();
+    assert!(matches!(error, KeystoreError::Io(_)), "{error:?}");
+}
+
+#[tokio::test]
+async fn signs_payments_with_the_keystore_key() {
+    let client = V2Eip155ExactClient::with_keystore(fixture("keystore.json"), PASSWORD).unwrap();
+    let candidates = client.accept(&payment_required());
+    assert_eq!(candidates.len(), 1);
+    let header = candidates[0].sign().await.unwrap();
+
+    let payment_payload: serde_json::Value =
+        serde_json::from_slice(&Base64Bytes::from(header.as_bytes()).decode().unwrap()).unwrap();
+    let payload: ExactEvmPayload =
+        serde_json::from_value(payment_payload["payload"].clone()).unwrap();
+    let authorization = payload.authorization;
+    assert_eq!(authorization.from, PAYER);
+
+    let domain = eip712_domain! {
+        name: "USDC",
+        version: "2",
+        chain_id: 84532,
+        verifying_contract: USDC_BASE_SEPOLIA,
+    };
+    let transfer_with_authorization = TransferWithAuthorization {
+        from: authorization.from,
+        to: authorization.to,
+        value: authorization.value,
+        validAfter: U256::from(authorization.valid_after.as_secs()),
+        validBefore: U256::from(authorization.valid_before.as_secs()),
+        nonce: authorization.nonce,
+    };
+    let hash = transfer_with_authorization.eip712_signing_hash(&domain);
+    let signature = Signature::try_from(payload.signature.as_ref()).unwrap();
+    assert_eq!(
+        signature.recover_address_from_prehash(&hash).unwrap(),
+        PAYER
+    );
+}
@@ -0,0 +1,118 @@
+//! Model checks of [`PendingNonceManager`] under concurrent allocations and resets.
+//!
+//! [loom](https://docs.rs/loom) runs the scenario under every interleaving of its threads
+//! at the mock chain's queries and broadcasts. Run with:
+//!
+//! ```shell
+//! RUSTFLAGS="--cfg loom" cargo test -p x402-chain-eip155 --features facilitator --release --test nonce_manager_loom
+//! ```
+
+#![cfg(loom)]
+
+use alloy_primitives::{Address, address};
+use loom::sync::Arc;
+use loom::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::convert::Infallible;
+use x402_chain_eip155::chain::PendingNonceManager;
+
+const SIGNER: Address = address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07");
+
+/// A chain on which every broadcast transaction stays in the mempool.
+#[derive(Default)]
+struct MockChain {
+    /// Pending nonce: one past the highest nonce broadcast.
+    pending: AtomicU64,
+    /// Number of nonce queries.
+    queries: AtomicUsize,
+}
+
+impl MockChain {
+    /// The pending and confirmed nonces of the signer, as fetched by the manager.
+    async fn fetch(&self) -> Result<(u64, u64), Infallible> {
+        self.queries.fetch_add(1, Ordering::SeqCst);
+        Ok((self.pending.load(Ordering::SeqCst), 0))
+    }
+
+    fn broadcast(&self, nonce: u64) {
+        self.pending.fetch_max(nonce + 1, Ordering::SeqCst);
+    }
+}
+
+/// Allocates a nonce, broadcasts a transaction with it, then resets the nonce as after a
+/// receipt timeout.
+async fn send_and_reset(manager: &PendingNonceManager, chain: &MockChain) -> u64 {
+    let broadcast = chain.pending.load(Ordering::SeqCst);
+    let nonce = manager
+        .next_nonce_with(SIGNER, || chain.fetch())
+        .await
+        .unwrap();
+    // The cursor never falls behind nonces already in use
+    assert!(
+        nonce >= broadcast,
+        "allocated {nonce} after {broadcast} were broadcast"
+    );
+    chain.broadcast(nonce);
+    manager.reset_nonce(SIGNER).await;
+    nonce
+}
+
+#[test]
+fn concurrent_allocations_and_resets() {
+    loom::model(|| {
+        let manager = PendingNonceManager::default();
+        let chain = Arc::new(MockChain::default());
+
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let manager = manager.clone();
+                let chain = chain.clone();
+                loom::thread::spawn(move || {
+                    loom::future::block_on(send_and_reset(&manager, &chain))
+                })
+            })
+            .collect();
+        let mut nonces: Vec<u64> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        // No two threads got the same nonce
+        nonces.sort_unstable();
+        assert_eq!(nonces, [0, 1]);
+
+        // After the resets, the next allocation queries the chain again, past both
+        let queries = chain.queries.load(Ordering::SeqCst);
+        let next =
+            loom::future::block_on(manager.next_nonce_with(SIGNER, || chain.fetch())).unwrap();
+        assert_eq!(chain.queries.load(Ordering::SeqCst), queries + 1);
+        assert_eq!(next, 2);
+    });
+}
+
+#[test]
+fn allocations_without_resets_are_served_from_the_cache() {
+    loom::model(|| {
+        let manager = PendingNonceManager::default();
+        let chain = Arc::new(MockChain::default());
+
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let manager = manager.clone();
+                let chain = chain.clone();
+                loom::thread::spawn(move || {
+                    loom::future::block_on(manager.next_nonce_with(SIGNER, || chain.fetch()))
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut nonces: Vec<u64> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        nonces.sort_unstable();
+        assert_eq!(nonces, [0, 1]);
+        // Only the first allocation queried the chain
+        assert_eq!(chain.queries.load(Ordering::SeqCst), 1);
+    });
+}
@@ -0,0 +1,106 @@
+//! Payments signed in the browser with a local key.
+//!
+//! Run with [wasm-pack](https://rustwasm.github.io/wasm-pack/):
+//!
+//! ```shell
+//! wasm-pack test --headless --chrome crates/chains/x402-chain-eip155 --features client --test wasm
+//! ```
+
+#![cfg(target_arch = "wasm32")]
+
+use alloy_primitives::{Address, Signature, U256, address};
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{SolStruct, eip712_domain};
+use serde_json::json;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+use x402_chain_eip155::V2Eip155ExactClient;
+use x402_chain_eip155::v1_eip155_exact::{ExactEvmPayload, TransferWithAuthorization};
+use x402_types::proto::PaymentRequired;
+use x402_types::scheme::client::X402SchemeClient;
+use x402_types::timestamp::UnixTimestamp;
+use x402_types::util::Base64Bytes;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// First account of the default Hardhat and Anvil mnemonic.
+const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const USDC_BASE_SEPOLIA: Address = address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+const PAY_TO: Address = address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07");
+
+fn payment_required() -> PaymentRequired {
+    let payment_required = json!({
+        "x402Version": 2,
+        "resource": { "url": "https://api.example.com/protected" },
+        "accepts": [{
+            "scheme": "exact",
+            "network": "eip155:84532",
+            "amount": "10000",
+            "payTo": PAY_TO,
+            "maxTimeoutSeconds": 300,
+            "asset": USDC_BASE_SEPOLIA,
+            "extra": { "name": "USDC", "version": "2" },
+        }],
+    });
+    PaymentRequired::V2(serde_json::from_str(&payment_required.to_string()).unwrap())
+}
+
+#[wasm_bindgen_test]
+async fn signs_eip3009_payments() {
+    let signer: PrivateKeySigner = PRIVATE_KEY.parse().unwrap();
+    let payer = signer.address();
+    let client = V2Eip155ExactClient::new(signer);
+
+    let candidates = client.accept(&payment_required());
+    assert_eq!(candidates.len(), 1);
+    let header = candidates[0].sign().await.unwrap();
+
+    let payment_payload: serde_json::Value =
+        serde_json::from_slice(&Base64Bytes::from(header.as_bytes()).decode().unwrap()).unwrap();
+    let payload: ExactEvmPayload =
+        serde_json::from_value(payment_payload["payload"].clone()).unwrap();
+    let authorization = payload.authorization;
+    assert_eq!(authorization.from, payer);
+    assert_eq!(authorization.to, PAY_TO);
+    assert_eq!(authorization.value, U256::from(10000));
+
+    // The validity window is read from the browser clock
+    let now = UnixTimestamp::now();
+    assert!(authorization.valid_after < now);
+    assert!(authorization.valid_before > now);
+
+    let domain = eip712_domain! {
+        name: "USDC",
+        version: "2",
+        chain_id: 84532,
+        verifying_contract: USDC_BASE_SEPOLIA,
+    };
+    let transfer_with_authorization = TransferWithAuthorization {
+        from: authorization.from,
+        to: authorization.to,
+        value: authorization.value,
+        validAfter: U256::from(authorization.valid_after.as_secs()),
+        validBefore: U256::from(authorization.valid_before.as_secs()),
+        nonce: authorization.nonce,
+    };
+    let hash = transfer_with_authorization.eip712_signing_hash(&domain);
+    let signature = Signature::try_from(payload.signature.as_ref()).unwrap();
+    assert_eq!(
+        signature.recover_address_from_prehash(&hash).unwrap(),
+        payer
+    );
+}
+
+#[wasm_bindgen_test]
+async fn nonces_are_random() {
+    let client = V2Eip155ExactClient::new(PRIVATE_KEY.parse::<PrivateKeySigner>().unwrap());
+    let payment_required = payment_required();
+    let mut nonces = Vec::new();
+    for _ in 0..2 {
+        let header = client.accept(&payment_required)[0].sign().await.unwrap();
+        let payment_payload: serde_json::Value =
+            serde_json::from_slice(&Base64Bytes::from(header.as_bytes()).decode().unwrap())
+                .unwrap();
+        nonces.push(payment_payload["payload"]["authorization"]["nonce"].clone());
+    }
+    assert_ne!(nonces[0], nonces[1]);
+}
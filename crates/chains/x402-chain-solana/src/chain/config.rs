@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ops::Deref;
 use std::str::FromStr;
 use url::Url;
 use x402_types::chain::ChainId;
 use x402_types::config::LiteralOrEnv;
+use x402_types::util::DecimalU256;
 
 use crate::chain::SolanaChainReference;
 
@@ -50,6 +52,11 @@ impl SolanaChainConfig {
         self.inner.max_compute_unit_price
     }
 
+    /// Returns whether payment transactions must carry both compute budget instructions.
+    pub fn require_compute_budget(&self) -> bool {
+        self.inner.require_compute_budget
+    }
+
     /// Returns the chain reference (genesis hash prefix).
     pub fn chain_reference(&self) -> SolanaChainReference {
         self.chain_reference
@@ -83,6 +90,17 @@ pub struct SolanaChainConfigInner {
     /// Maximum compute unit price for transactions (optional)
     #[serde(default = "solana_chain_config::default_max_compute_unit_price")]
     pub max_compute_unit_price: u64,
+    /// Whether payment transactions must carry both compute budget instructions (optional).
+    /// When false, transactions without them are accepted, and those present are still
+    /// checked against the limits. Defaults to true.
+    #[serde(default = "solana_chain_config::default_require_compute_budget")]
+    pub require_compute_budget: bool,
+    /// Asset addresses allowed on this chain (optional, default: any).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_assets: Vec<String>,
+    /// Minimum amount per asset, in base units (optional).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub min_payment_amount: HashMap<String, DecimalU256>,
 }
 
 mod solana_chain_config {
@@ -92,6 +110,9 @@ mod solana_chain_config {
     pub fn default_max_compute_unit_price() -> u64 {
         1_000_000
     }
+    pub fn default_require_compute_budget() -> bool {
+        true
+    }
 }
 
 // ============================================================================
@@ -11,6 +11,7 @@ use solana_client::rpc_response::{RpcSignatureResult, TransactionError, UiTransa
 use solana_commitment_config::CommitmentConfig;
 use solana_keypair::Keypair;
 use solana_keypair::Signer;
+use solana_message::Hash;
 use solana_pubkey::Pubkey;
 use solana_signature::Signature;
 use solana_signer::SignerError;
@@ -40,11 +41,30 @@ pub enum SolanaChainProviderError {
     /// WebSocket pubsub transport error.
     #[error(transparent)]
     PubsubTransport(#[from] PubsubClientError),
+    /// The recent blockhash of the transaction expired before it was confirmed.
+    ///
+    /// The payer signed over the blockhash, so the transaction can never land, and the
+    /// facilitator cannot refresh it: the payer has to sign a new transaction.
+    #[error("Transaction blockhash {0} expired before confirmation")]
+    BlockhashExpired(Hash),
     #[error("{0}")]
     #[allow(dead_code)] // Public for consumption by downstream crates.
     Custom(String),
 }
 
+impl SolanaChainProviderError {
+    /// Returns whether this error means the transaction's blockhash is no longer valid.
+    pub fn is_blockhash_expired(&self) -> bool {
+        match self {
+            SolanaChainProviderError::BlockhashExpired(_) => true,
+            SolanaChainProviderError::InvalidTransaction(error) => {
+                *error == UiTransactionError::from(TransactionError::BlockhashNotFound)
+            }
+            _ => false,
+        }
+    }
+}
+
 impl From<ClientError> for SolanaChainProviderError {
     fn from(value: ClientError) -> Self {
         SolanaChainProviderError::Transport(value.kind)
@@ -53,12 +73,18 @@ impl From<ClientError> for SolanaChainProviderError {
 
 impl From<SolanaChainProviderError> for X402SchemeFacilitatorError {
     fn from(value: SolanaChainProviderError) -> Self {
+        if value.is_blockhash_expired() {
+            return PaymentVerificationError::BlockhashExpired.into();
+        }
         Self::OnchainFailure(value.to_string())
     }
 }
 
 impl From<SolanaChainProviderError> for PaymentVerificationError {
     fn from(value: SolanaChainProviderError) -> Self {
+        if value.is_blockhash_expired() {
+            return Self::BlockhashExpired;
+        }
         Self::TransactionSimulation(value.to_string())
     }
 }
@@ -99,6 +125,8 @@ pub struct SolanaChainProvider {
     max_compute_unit_limit: u32,
     /// Maximum price per compute unit (in micro-lamports).
     max_compute_unit_price: u64,
+    /// Whether payment transactions must carry both compute budget instructions.
+    require_compute_budget: bool,
 }
 
 impl Debug for SolanaChainProvider {
@@ -122,6 +150,8 @@ impl SolanaChainProvider {
     /// - `chain`: The Solana network identifier
     /// - `max_compute_unit_limit`: Maximum compute units per transaction
     /// - `max_compute_unit_price`: Maximum price per compute unit in micro-lamports
+    /// - `require_compute_budget`: Whether payment transactions must carry both compute
+    ///   budget instructions
     ///
     /// # Errors
     ///
@@ -133,6 +163,7 @@ impl SolanaChainProvider {
         chain: SolanaChainReference,
         max_compute_unit_limit: u32,
         max_compute_unit_price: u64,
+        require_compute_budget: bool,
     ) -> Result<Self, PubsubClientError> {
         #[cfg(feature = "telemetry")]
         {
@@ -145,6 +176,7 @@ impl SolanaChainProvider {
                 signers = ?signer_addresses,
                 max_compute_unit_limit,
                 max_compute_unit_price,
+                require_compute_budget,
                 "Using Solana provider"
             );
         }
@@ -162,6 +194,7 @@ impl SolanaChainProvider {
             pubsub_client: pubsub_client.map(Arc::new),
             max_compute_unit_limit,
             max_compute_unit_price,
+            require_compute_budget,
         })
     }
 
@@ -211,6 +244,7 @@ impl FromConfig<SolanaChainConfig> for SolanaChainProvider {
         let keypair = Keypair::from_base58_string(&config.signer().to_string());
         let max_compute_unit_limit = config.max_compute_unit_limit();
         let max_compute_unit_price = config.max_compute_unit_price();
+        let require_compute_budget = config.require_compute_budget();
         let chain = config.chain_reference();
         let provider = Self::new(
             keypair,
@@ -219,6 +253,7 @@ impl FromConfig<SolanaChainConfig> for SolanaChainProvider {
             chain,
             max_compute_unit_limit,
             max_compute_unit_price,
+            require_compute_budget,
         )
         .await?;
         Ok(provider)
@@ -259,6 +294,14 @@ pub trait SolanaChainProviderLike {
     /// Returns the maximum compute unit price in micro-lamports.
     fn max_compute_unit_price(&self) -> u64;
 
+    /// Returns whether payment transactions must carry both compute budget instructions.
+    ///
+    /// When false, transactions without them are accepted, and those present are still
+    /// checked against the limits.
+    fn require_compute_budget(&self) -> bool {
+        true
+    }
+
     /// Returns the public key of the fee payer.
     fn pubkey(&self) -> Pubkey;
 
@@ -313,6 +356,10 @@ impl SolanaChainProviderLike for SolanaChainProvider {
         self.max_compute_unit_price
     }
 
+    fn require_compute_budget(&self) -> bool {
+        self.require_compute_budget
+    }
+
     fn pubkey(&self) -> Pubkey {
         self.keypair.pubkey()
     }
@@ -353,6 +400,16 @@ impl SolanaChainProviderLike for SolanaChainProvider {
         commitment_config: CommitmentConfig,
     ) -> Result<Signature, SolanaChainProviderError> {
         let tx_sig = tx.get_signature();
+        let blockhash = *tx.message.recent_blockhash();
+        // A transaction over an expired blockhash is dropped without an error, as
+        // preflight is skipped. Catch it before sending, and while waiting for it.
+        if !self
+            .rpc_client
+            .is_blockhash_valid(&blockhash, commitment_config)
+            .await?
+        {
+            return Err(SolanaChainProviderError::BlockhashExpired(blockhash));
+        }
 
         use futures_util::stream::StreamExt;
 
@@ -370,7 +427,14 @@ impl SolanaChainProviderLike for SolanaChainProvider {
                 unsubscribe().await;
                 return Err(e);
             }
-            if let Some(response) = stream.next().await {
+            let response = tokio::select! {
+                response = stream.next() => response,
+                _ = self.blockhash_expiry(&blockhash, commitment_config) => {
+                    unsubscribe().await;
+                    return self.expired(tx_sig, blockhash, commitment_config).await;
+                }
+            };
+            if let Some(response) = response {
                 let error = if let RpcSignatureResult::ProcessedSignature(r) = response.value {
                     r.err
                 } else {
@@ -389,20 +453,68 @@ impl SolanaChainProviderLike for SolanaChainProvider {
             }
         } else {
             self.send(tx).await?;
-            loop {
-                let confirmed = self
-                    .rpc_client
-                    .confirm_transaction_with_commitment(tx_sig, commitment_config)
-                    .await?;
-                if confirmed.value {
-                    return Ok(*tx_sig);
+            let confirmation = async {
+                loop {
+                    let confirmed = self
+                        .rpc_client
+                        .confirm_transaction_with_commitment(tx_sig, commitment_config)
+                        .await?;
+                    if confirmed.value {
+                        return Ok::<_, SolanaChainProviderError>(*tx_sig);
+                    }
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+            };
+            tokio::select! {
+                result = confirmation => result,
+                _ = self.blockhash_expiry(&blockhash, commitment_config) => {
+                    self.expired(tx_sig, blockhash, commitment_config).await
                 }
-                tokio::time::sleep(Duration::from_millis(200)).await;
             }
         }
     }
 }
 
+impl SolanaChainProvider {
+    /// Interval between checks of the blockhash of a transaction awaiting confirmation.
+    const BLOCKHASH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// Resolves once `blockhash` is no longer valid. RPC errors count as still valid.
+    async fn blockhash_expiry(&self, blockhash: &Hash, commitment_config: CommitmentConfig) {
+        loop {
+            tokio::time::sleep(Self::BLOCKHASH_CHECK_INTERVAL).await;
+            let valid = self
+                .rpc_client
+                .is_blockhash_valid(blockhash, commitment_config)
+                .await;
+            if let Ok(false) = valid {
+                return;
+            }
+        }
+    }
+
+    /// Outcome of a transaction whose blockhash expired while awaiting confirmation: it
+    /// may still have landed just before.
+    async fn expired(
+        &self,
+        tx_sig: &Signature,
+        blockhash: Hash,
+        commitment_config: CommitmentConfig,
+    ) -> Result<Signature, SolanaChainProviderError> {
+        let confirmed = self
+            .rpc_client
+            .confirm_transaction_with_commitment(tx_sig, commitment_config)
+            .await?;
+        if confirmed.value {
+            Ok(*tx_sig)
+        } else {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!(%blockhash, signature = %tx_sig, "Transaction blockhash expired");
+            Err(SolanaChainProviderError::BlockhashExpired(blockhash))
+        }
+    }
+}
+
 impl<T: SolanaChainProviderLike> SolanaChainProviderLike for Arc<T> {
     fn simulate_transaction_with_config(
         &self,
@@ -427,6 +539,10 @@ impl<T: SolanaChainProviderLike> SolanaChainProviderLike for Arc<T> {
         (**self).max_compute_unit_price()
     }
 
+    fn require_compute_budget(&self) -> bool {
+        (**self).require_compute_budget()
+    }
+
     fn pubkey(&self) -> Pubkey {
         (**self).pubkey()
     }
@@ -450,3 +566,37 @@ impl<T: SolanaChainProviderLike> SolanaChainProviderLike for Arc<T> {
         (**self).send_and_confirm(tx, commitment_config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x402_types::proto::{AsPaymentProblem, PaymentProblemCode};
+
+    #[test]
+    fn expired_blockhash_asks_for_a_new_transaction() {
+        let errors = [
+            SolanaChainProviderError::BlockhashExpired(Hash::default()),
+            SolanaChainProviderError::InvalidTransaction(UiTransactionError::from(
+                TransactionError::BlockhashNotFound,
+            )),
+        ];
+        for error in errors {
+            assert!(error.is_blockhash_expired());
+            let error = X402SchemeFacilitatorError::from(error);
+            assert_eq!(
+                error.as_payment_problem().code(),
+                PaymentProblemCode::BlockhashExpired
+            );
+        }
+
+        let error = SolanaChainProviderError::InvalidTransaction(UiTransactionError::from(
+            TransactionError::AccountNotFound,
+        ));
+        assert!(!error.is_blockhash_expired());
+        let error = X402SchemeFacilitatorError::from(error);
+        assert_eq!(
+            error.as_payment_problem().code(),
+            PaymentProblemCode::OnchainFailure
+        );
+    }
+}
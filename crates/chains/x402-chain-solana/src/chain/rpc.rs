@@ -2,15 +2,28 @@
 //!
 //! This module provides a trait that abstracts common RPC operations,
 //! allowing for easier testing and mocking of Solana RPC interactions.
+//!
+//! [`RpcClientLike`] is implemented for the nonblocking `RpcClient` of `solana-client`,
+//! which does not build for `wasm32`. In browsers, implement it over a JSON-RPC client of
+//! your own, e.g. on `fetch`, wrapping its futures in `send_wrapper::SendWrapper` to meet
+//! the `Send` bounds, which is sound as browsers run WebAssembly on one thread.
+//!
+//! [`RpcClients`] holds the RPC clients of a scheme client paying on several networks,
+//! e.g. mainnet and devnet, so that each payment fetches its blockhash from its own cluster.
 
 use solana_account::Account;
-use solana_client::client_error::ClientError;
+#[cfg(not(target_arch = "wasm32"))]
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcSimulateTransactionConfig;
-use solana_client::rpc_response::{RpcPrioritizationFee, RpcResult, RpcSimulateTransactionResult};
 use solana_message::Hash;
 use solana_pubkey::Pubkey;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_rpc_client_api::response::{
+    RpcPrioritizationFee, RpcResult, RpcSimulateTransactionResult,
+};
 use solana_transaction::versioned::VersionedTransaction;
+use std::collections::HashMap;
+use x402_types::chain::ChainId;
 
 /// Trait for Solana RPC client operations.
 ///
@@ -40,6 +53,7 @@ pub trait RpcClientLike {
     fn get_latest_blockhash(&self) -> impl Future<Output = Result<Hash, ClientError>> + Send;
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl<Container: AsRef<RpcClient>> RpcClientLike for Container {
     fn get_account(
         &self,
@@ -64,3 +78,73 @@ impl<Container: AsRef<RpcClient>> RpcClientLike for Container {
         RpcClient::get_latest_blockhash(self.as_ref())
     }
 }
+
+/// RPC clients of a Solana scheme client, per network.
+///
+/// A payment is signed with the RPC client of its network, else with the default one. A
+/// transaction built from the blockhash of another cluster is rejected, so a client paying on
+/// several networks registers one RPC client for each.
+///
+/// # Example
+///
+/// ```ignore
+/// use solana_client::nonblocking::rpc_client::RpcClient;
+/// use std::sync::Arc;
+/// use x402_chain_solana::KnownNetworkSolana;
+/// use x402_chain_solana::chain::rpc::RpcClients;
+/// use x402_types::chain::ChainId;
+///
+/// let rpc_clients = RpcClients::default()
+///     .with_network(
+///         ChainId::solana(),
+///         Arc::new(RpcClient::new("https://api.mainnet-beta.solana.com".to_string())),
+///     )
+///     .with_network(
+///         ChainId::solana_devnet(),
+///         Arc::new(RpcClient::new("https://api.devnet.solana.com".to_string())),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RpcClients<R> {
+    default: Option<R>,
+    networks: HashMap<ChainId, R>,
+}
+
+impl<R> Default for RpcClients<R> {
+    fn default() -> Self {
+        Self {
+            default: None,
+            networks: HashMap::new(),
+        }
+    }
+}
+
+impl<R> RpcClients<R> {
+    /// Creates RPC clients using `rpc_client` on every network.
+    pub fn new(rpc_client: R) -> Self {
+        Self {
+            default: Some(rpc_client),
+            networks: HashMap::new(),
+        }
+    }
+
+    /// Uses `rpc_client` on the network `chain_id`.
+    pub fn with_network(mut self, chain_id: ChainId, rpc_client: R) -> Self {
+        self.networks.insert(chain_id, rpc_client);
+        self
+    }
+
+    /// Returns the RPC client to use on the network `chain_id`, if any.
+    pub fn get(&self, chain_id: &ChainId) -> Option<&R> {
+        self.networks.get(chain_id).or(self.default.as_ref())
+    }
+}
+
+impl<R> FromIterator<(ChainId, R)> for RpcClients<R> {
+    fn from_iter<T: IntoIterator<Item = (ChainId, R)>>(iter: T) -> Self {
+        Self {
+            default: None,
+            networks: iter.into_iter().collect(),
+        }
+    }
+}
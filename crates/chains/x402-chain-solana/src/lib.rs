@@ -24,7 +24,8 @@
 //! # Feature Flags
 //!
 //! - `server` - Server-side price tag generation
-//! - `client` - Client-side payment signing
+//! - `client` - Client-side payment signing, also on `wasm32` with an RPC client of your own
+//!   (see the `chain::rpc` module)
 //! - `facilitator` - Facilitator-side payment verification and settlement
 //! - `telemetry` - OpenTelemetry tracing support
 //!
@@ -1,8 +1,9 @@
 use solana_pubkey::pubkey;
 use x402_types::chain::ChainId;
 use x402_types::networks::USDC;
+use x402_types::networks::definitions::registered_tokens;
 
-use crate::chain::{SolanaChainReference, SolanaTokenDeployment};
+use crate::chain::{Address, SolanaChainReference, SolanaTokenDeployment};
 
 /// Trait providing convenient methods to get instances for well-known Solana networks.
 ///
@@ -70,3 +71,21 @@ impl KnownNetworkSolana<SolanaTokenDeployment> for USDC {
         SolanaTokenDeployment::new(SolanaChainReference::solana_devnet(), address.into(), 6)
     }
 }
+
+/// Returns the decimals of a known token mint: USDC on Solana mainnet and devnet, then the
+/// Solana tokens registered from network definitions (see [`x402_types::networks::definitions`]).
+pub fn known_token_decimals(mint: &Address) -> Option<u8> {
+    [USDC::solana(), USDC::solana_devnet()]
+        .into_iter()
+        .find(|deployment| deployment.address == *mint)
+        .map(|deployment| deployment.decimals)
+        .or_else(|| {
+            registered_tokens()
+                .into_iter()
+                .find(|token| {
+                    token.chain_id.namespace == "solana"
+                        && token.address.parse::<Address>().ok().as_ref() == Some(mint)
+                })
+                .map(|token| token.decimals)
+        })
+}
@@ -21,14 +21,17 @@
 //! let rpc = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
 //! let client = V1SolanaExactClient::new(keypair, rpc);
 //! ```
+//!
+//! To pay on several networks, e.g. mainnet and devnet, give each its own RPC client with
+//! [`V1SolanaExactClient::from_rpc_clients`].
 
 use alloy_primitives::U256;
 use async_trait::async_trait;
-use solana_client::rpc_config::RpcSimulateTransactionConfig;
 use solana_compute_budget_interface::ComputeBudgetInstruction;
 use solana_message::v0::Message as MessageV0;
 use solana_message::{Hash, VersionedMessage};
 use solana_pubkey::Pubkey;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
 use solana_signature::Signature;
 use solana_signer::Signer;
 use solana_transaction::Instruction;
@@ -45,7 +48,7 @@ use x402_types::scheme::client::{
 };
 
 use crate::chain::Address;
-use crate::chain::rpc::RpcClientLike;
+use crate::chain::rpc::{RpcClientLike, RpcClients};
 use crate::v1_solana_exact::types::{
     ATA_PROGRAM_PUBKEY, ExactScheme, ExactSolanaPayload, MEMO_PROGRAM_PUBKEY, PaymentPayload,
     PaymentRequirements,
@@ -324,13 +327,24 @@ pub async fn build_signed_transfer_transaction<S: Signer, R: RpcClientLike>(
 #[allow(dead_code)] // Public for consumption by downstream crates.
 pub struct V1SolanaExactClient<S, R> {
     signer: S,
-    rpc_client: R,
+    rpc_clients: RpcClients<R>,
 }
 
 #[allow(dead_code)] // Public for consumption by downstream crates.
 impl<S, R> V1SolanaExactClient<S, R> {
+    /// Creates a client using `rpc_client` on every Solana network.
     pub fn new(signer: S, rpc_client: R) -> Self {
-        Self { signer, rpc_client }
+        Self::from_rpc_clients(signer, RpcClients::new(rpc_client))
+    }
+
+    /// Creates a client using the RPC client of the network of each payment.
+    ///
+    /// Signing a payment on a network without RPC client fails.
+    pub fn from_rpc_clients(signer: S, rpc_clients: RpcClients<R>) -> Self {
+        Self {
+            signer,
+            rpc_clients,
+        }
     }
 }
 
@@ -370,8 +384,9 @@ where
                 if chain_id.namespace != "solana" {
                     return None;
                 }
+                let rpc_client = self.rpc_clients.get(&chain_id).cloned();
                 let candidate = PaymentCandidate {
-                    chain_id,
+                    chain_id: chain_id.clone(),
                     asset: requirements.asset.to_string(),
                     amount: U256::from(requirements.max_amount_required.inner()),
                     scheme: self.scheme().to_string(),
@@ -379,7 +394,8 @@ where
                     pay_to: requirements.pay_to.to_string(),
                     signer: Box::new(PayloadSigner {
                         signer: self.signer.clone(),
-                        rpc_client: self.rpc_client.clone(),
+                        rpc_client,
+                        chain_id,
                         requirements,
                     }),
                 };
@@ -392,7 +408,8 @@ where
 #[allow(dead_code)] // Public for consumption by downstream crates.
 pub struct PayloadSigner<S, R> {
     signer: S,
-    rpc_client: R,
+    rpc_client: Option<R>,
+    chain_id: ChainId,
     requirements: PaymentRequirements,
 }
 
@@ -409,11 +426,17 @@ impl<S: Signer + Sync, R: RpcClientLike + Sync> PaymentCandidateSigner for Paylo
                 "missing fee_payer in extra".to_string(),
             ))?;
         let fee_payer_pubkey: Pubkey = fee_payer.into();
+        let rpc_client = self.rpc_client.as_ref().ok_or_else(|| {
+            X402Error::SigningError(format!(
+                "no Solana RPC client configured for {}",
+                self.chain_id
+            ))
+        })?;
 
         let amount = self.requirements.max_amount_required.inner();
         let tx_b64 = build_signed_transfer_transaction(
             &self.signer,
-            &self.rpc_client,
+            rpc_client,
             &fee_payer_pubkey,
             &self.requirements.pay_to,
             &self.requirements.asset,
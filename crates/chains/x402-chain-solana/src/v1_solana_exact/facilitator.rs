@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use solana_account::Account;
 use solana_client::rpc_config::RpcSimulateTransactionConfig;
 use solana_client::rpc_response::{TransactionError, UiTransactionError};
 use solana_commitment_config::CommitmentConfig;
@@ -7,6 +8,7 @@ use solana_pubkey::Pubkey;
 use solana_signature::Signature;
 use solana_transaction::versioned::VersionedTransaction;
 use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
 use x402_types::chain::{ChainId, ChainProviderOps};
 use x402_types::proto;
 use x402_types::proto::{PaymentVerificationError, v1};
@@ -14,6 +16,7 @@ use x402_types::scheme::{
     X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
 use x402_types::util::Base64Bytes;
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 #[cfg(feature = "telemetry")]
 use tracing_core::Level;
@@ -21,10 +24,11 @@ use tracing_core::Level;
 use crate::V1SolanaExact;
 use crate::chain::Address;
 use crate::chain::provider::{SolanaChainProviderError, SolanaChainProviderLike};
+use crate::known_token_decimals;
 use crate::v1_solana_exact::types;
 use crate::v1_solana_exact::types::{
-    ATA_PROGRAM_PUBKEY, MEMO_PROGRAM_PUBKEY, PHANTOM_LIGHTHOUSE_PROGRAM_PUBKEY, SolanaExactError,
-    TransactionInt,
+    ATA_PROGRAM_PUBKEY, MEMO_PROGRAM_PUBKEY, MINT_LEN, PHANTOM_LIGHTHOUSE_PROGRAM_PUBKEY,
+    SolanaExactError, TOKEN_ACCOUNT_LEN, TransactionInt,
 };
 
 impl<P> X402SchemeFacilitatorBuilder<P> for V1SolanaExact
@@ -65,25 +69,35 @@ where
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let request = types::VerifyRequest::try_from(request)?;
-        let verification = verify_transfer(&self.provider, &request, &self.config).await?;
-        Ok(v1::VerifyResponse::valid(verification.payer.to_string()).into())
+        verify_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = types::VerifyRequest::try_from(request)?;
+            let verification =
+                verify_transfer(&self.provider, &request, declared_decimals, &self.config).await?;
+            Ok(v1::VerifyResponse::valid(verification.payer.to_string()).into())
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let request = types::SettleRequest::try_from(request)?;
-        let verification = verify_transfer(&self.provider, &request, &self.config).await?;
-        let payer = verification.payer.to_string();
-        let tx_sig = settle_transaction(&self.provider, verification).await?;
-        Ok(v1::SettleResponse::Success {
-            payer,
-            transaction: tx_sig.to_string(),
-            network: self.provider.chain_id().to_string(),
-        }
-        .into())
+        settle_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = types::SettleRequest::try_from(request)?;
+            let verification =
+                verify_transfer(&self.provider, &request, declared_decimals, &self.config).await?;
+            let payer = verification.payer.to_string();
+            let tx_sig = settle_transaction(&self.provider, verification).await?;
+            Ok(v1::SettleResponse::Success {
+                payer,
+                transaction: tx_sig.to_string(),
+                network: self.provider.chain_id().to_string(),
+            }
+            .into())
+        })
+        .await
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
@@ -132,6 +146,88 @@ pub struct TransferCheckedInstruction {
     pub token_program: Pubkey,
 }
 
+/// Discriminator of the `SetComputeUnitLimit` compute budget instruction.
+const SET_COMPUTE_UNIT_LIMIT_DISCRIMINATOR: u8 = 2;
+/// Discriminator of the `SetComputeUnitPrice` compute budget instruction.
+const SET_COMPUTE_UNIT_PRICE_DISCRIMINATOR: u8 = 3;
+
+/// Indices of the instructions a payment transaction carries.
+///
+/// Transaction builders do not agree on the order of the compute budget instructions, so
+/// they are found by program ID and discriminator, and the transfer by token program,
+/// wherever they are in the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionLayout {
+    /// Index of the SetComputeUnitLimit instruction, if any.
+    pub compute_limit: Option<usize>,
+    /// Index of the SetComputeUnitPrice instruction, if any.
+    pub compute_price: Option<usize>,
+    /// Index of the token transfer instruction (Token or Token-2022).
+    pub transfer: usize,
+}
+
+impl InstructionLayout {
+    /// Finds the payment instructions in the transaction.
+    ///
+    /// Each may appear at most once. The transfer is required, and so are the compute budget
+    /// instructions if `require_compute_budget` is set.
+    pub fn find(
+        transaction: &VersionedTransaction,
+        require_compute_budget: bool,
+    ) -> Result<Self, SolanaExactError> {
+        let account_keys = transaction.message.static_account_keys();
+        let mut compute_limit = None;
+        let mut compute_price = None;
+        let mut transfer = None;
+        for (index, instruction) in transaction.message.instructions().iter().enumerate() {
+            let program_id = account_keys
+                .get(instruction.program_id_index as usize)
+                .ok_or(SolanaExactError::NoAccountAtIndex(
+                    instruction.program_id_index,
+                ))?;
+            let found = if ComputeBudgetInstructionId.eq(program_id) {
+                match instruction.data.first().copied() {
+                    Some(SET_COMPUTE_UNIT_LIMIT_DISCRIMINATOR) => &mut compute_limit,
+                    Some(SET_COMPUTE_UNIT_PRICE_DISCRIMINATOR) => &mut compute_price,
+                    _ => continue,
+                }
+            } else if spl_token::ID.eq(program_id) || spl_token_2022::ID.eq(program_id) {
+                &mut transfer
+            } else {
+                continue;
+            };
+            if found.replace(index).is_some() {
+                return Err(SolanaExactError::DuplicateInstruction(index));
+            }
+        }
+        if require_compute_budget {
+            if compute_limit.is_none() {
+                return Err(SolanaExactError::MissingComputeLimitInstruction);
+            }
+            if compute_price.is_none() {
+                return Err(SolanaExactError::MissingComputePriceInstruction);
+            }
+        }
+        Ok(Self {
+            compute_limit,
+            compute_price,
+            transfer: transfer.ok_or(SolanaExactError::MissingTransferInstruction)?,
+        })
+    }
+
+    /// Whether the instruction at `index` is one of the payment instructions.
+    pub fn is_required(&self, index: usize) -> bool {
+        self.compute_limit == Some(index)
+            || self.compute_price == Some(index)
+            || index == self.transfer
+    }
+
+    /// Number of payment instructions in the transaction.
+    pub fn instruction_count(&self) -> usize {
+        1 + usize::from(self.compute_limit.is_some()) + usize::from(self.compute_price.is_some())
+    }
+}
+
 pub fn verify_compute_limit_instruction(
     transaction: &VersionedTransaction,
     instruction_index: usize,
@@ -145,7 +241,7 @@ pub fn verify_compute_limit_instruction(
 
     // Verify program ID, discriminator, and data length (1 byte discriminator + 4 bytes u32)
     if ComputeBudgetInstructionId.ne(account)
-        || data.first().cloned().unwrap_or(0) != 2
+        || data.first().cloned().unwrap_or(0) != SET_COMPUTE_UNIT_LIMIT_DISCRIMINATOR
         || data.len() != 5
     {
         return Err(SolanaExactError::InvalidComputeLimitInstruction);
@@ -169,9 +265,11 @@ pub fn verify_compute_price_instruction(
         .get(instruction_index)
         .ok_or(SolanaExactError::NoInstructionAtIndex(instruction_index))?;
     let account = instruction.program_id(transaction.message.static_account_keys());
-    let compute_budget = solana_compute_budget_interface::ID;
     let data = instruction.data.as_slice();
-    if compute_budget.ne(account) || data.first().cloned().unwrap_or(0) != 3 || data.len() != 9 {
+    if ComputeBudgetInstructionId.ne(account)
+        || data.first().cloned().unwrap_or(0) != SET_COMPUTE_UNIT_PRICE_DISCRIMINATOR
+        || data.len() != 9
+    {
         return Err(SolanaExactError::InvalidComputePriceInstruction);
     }
     // It is ComputeBudgetInstruction definitely by now!
@@ -184,23 +282,28 @@ pub fn verify_compute_price_instruction(
     Ok(())
 }
 
-/// Validates the instruction structure of the transaction.
+/// Validates the instruction structure of the transaction, and returns where the payment
+/// instructions are.
 ///
-/// Required structure:
-/// - Index 0: SetComputeUnitLimit instruction
-/// - Index 1: SetComputeUnitPrice instruction
-/// - Index 2: TransferChecked instruction (Token or Token-2022)
-/// - Index 3+: Additional instructions (only if allow_additional_instructions is true)
+/// Payment instructions, in any order:
+/// - SetComputeUnitLimit instruction, required if `require_compute_budget` is true
+/// - SetComputeUnitPrice instruction, required if `require_compute_budget` is true
+/// - TransferChecked instruction (Token or Token-2022)
+///
+/// Any other instruction is additional, and only allowed if allow_additional_instructions
+/// is true.
 ///
 /// NOTE: CreateATA is NOT supported. The destination ATA must exist before payment.
 pub fn validate_instructions(
     transaction: &VersionedTransaction,
     config: &V1SolanaExactFacilitatorConfig,
-) -> Result<(), SolanaExactError> {
+    require_compute_budget: bool,
+) -> Result<InstructionLayout, SolanaExactError> {
     let instructions = transaction.message.instructions();
 
-    // Minimum: ComputeLimit + ComputePrice + TransferChecked
-    if instructions.len() < 3 {
+    // Minimum: ComputeLimit + ComputePrice + TransferChecked, or TransferChecked alone
+    let min_instruction_count = if require_compute_budget { 3 } else { 1 };
+    if instructions.len() < min_instruction_count {
         return Err(SolanaExactError::TooFewInstructions);
     }
 
@@ -211,20 +314,21 @@ pub fn validate_instructions(
         ));
     }
 
-    // Verify instruction at index 2 is a token transfer (NOT CreateATA)
-    let ix2_program = get_program_id(transaction, 2);
-    if ix2_program == Some(ATA_PROGRAM_PUBKEY) {
+    // The destination ATA must exist, no instruction may create it
+    if (0..instructions.len()).any(|i| get_program_id(transaction, i) == Some(ATA_PROGRAM_PUBKEY)) {
         return Err(SolanaExactError::CreateATANotSupported);
     }
 
-    // Validate additional instructions (if any beyond the required 3)
-    if instructions.len() > 3 {
+    let layout = InstructionLayout::find(transaction, require_compute_budget)?;
+
+    // Validate additional instructions (if any beyond the payment ones)
+    if instructions.len() > layout.instruction_count() {
         if !config.allow_additional_instructions {
             return Err(SolanaExactError::AdditionalInstructionsNotAllowed);
         }
 
-        // Validate each additional instruction (starting at index 3)
-        for i in 3..instructions.len() {
+        // Validate each additional instruction, wherever it is
+        for i in (0..instructions.len()).filter(|i| !layout.is_required(*i)) {
             if let Some(program_id) = get_program_id(transaction, i) {
                 // Check blocked list first (takes precedence)
                 if config.is_blocked(&program_id) {
@@ -239,7 +343,7 @@ pub fn validate_instructions(
         }
     }
 
-    Ok(())
+    Ok(layout)
 }
 
 fn get_program_id(transaction: &VersionedTransaction, index: usize) -> Option<Pubkey> {
@@ -248,9 +352,14 @@ fn get_program_id(transaction: &VersionedTransaction, index: usize) -> Option<Pu
     Some(*instruction.program_id(account_keys))
 }
 
+/// Verifies the transfer transaction of a payment.
+///
+/// `declared_decimals` are the asset decimals declared in the payment requirements; when
+/// absent, the decimals of the known deployment of the asset are expected, if any.
 pub async fn verify_transfer<P: SolanaChainProviderLike + ChainProviderOps>(
     provider: &P,
     request: &types::VerifyRequest,
+    declared_decimals: Option<u8>,
     config: &V1SolanaExactFacilitatorConfig,
 ) -> Result<VerifyTransferResult, PaymentVerificationError> {
     let payload = &request.payment_payload;
@@ -273,6 +382,7 @@ pub async fn verify_transfer<P: SolanaChainProviderLike + ChainProviderOps>(
         pay_to: &requirements.pay_to,
         asset: &requirements.asset,
         amount: requirements.max_amount_required.inner(),
+        decimals: declared_decimals.or_else(|| known_token_decimals(&requirements.asset)),
     };
     let result = verify_transaction(
         provider,
@@ -296,21 +406,35 @@ pub async fn verify_transaction<P: SolanaChainProviderLike>(
     let transaction = bincode::deserialize::<VersionedTransaction>(bytes.as_slice())
         .map_err(|e| SolanaExactError::TransactionDecoding(e.to_string()))?;
 
-    // Verify compute instructions
-    let compute_units = verify_compute_limit_instruction(&transaction, 0)?;
-    if compute_units > provider.max_compute_unit_limit() {
-        return Err(SolanaExactError::MaxComputeUnitLimitExceeded.into());
-    }
-    #[cfg(feature = "telemetry")]
-    tracing::debug!(compute_units = compute_units, "Verified compute unit limit");
-    verify_compute_price_instruction(provider.max_compute_unit_price(), &transaction, 1)?;
+    // Flexible instruction validation, locating the required instructions
+    let layout = validate_instructions(&transaction, config, provider.require_compute_budget())?;
 
-    // Flexible instruction validation (replaces old instruction count check)
-    validate_instructions(&transaction, config)?;
+    // Verify compute instructions, when present
+    if let Some(compute_limit) = layout.compute_limit {
+        let compute_units = verify_compute_limit_instruction(&transaction, compute_limit)?;
+        if compute_units > provider.max_compute_unit_limit() {
+            return Err(SolanaExactError::MaxComputeUnitLimitExceeded.into());
+        }
+        #[cfg(feature = "telemetry")]
+        tracing::debug!(compute_units = compute_units, "Verified compute unit limit");
+    }
+    if let Some(compute_price) = layout.compute_price {
+        verify_compute_price_instruction(
+            provider.max_compute_unit_price(),
+            &transaction,
+            compute_price,
+        )?;
+    }
 
-    // Transfer instruction is ALWAYS at index 2 (CreateATA no longer supported)
-    let transfer_instruction =
-        verify_transfer_instruction(provider, &transaction, 2, transfer_requirement).await?;
+    // Transfer instruction is identified by its token program, not its position
+    let transfer_instruction = verify_transfer_instruction(
+        provider,
+        &transaction,
+        layout.transfer,
+        transfer_requirement,
+        config,
+    )
+    .await?;
 
     // Fee payer safety check (configurable but defaults to enabled)
     if config.require_fee_payer_not_in_instructions {
@@ -352,6 +476,8 @@ pub struct TransferRequirement<'a> {
     pub asset: &'a Address,
     pub pay_to: &'a Address,
     pub amount: u64,
+    /// Decimals the amount was priced with. The mint's decimals are not checked if `None`.
+    pub decimals: Option<u8>,
 }
 
 pub async fn verify_transfer_instruction<P: SolanaChainProviderLike>(
@@ -359,81 +485,86 @@ pub async fn verify_transfer_instruction<P: SolanaChainProviderLike>(
     transaction: &VersionedTransaction,
     instruction_index: usize,
     transfer_requirement: &TransferRequirement<'_>,
+    config: &V1SolanaExactFacilitatorConfig,
 ) -> Result<TransferCheckedInstruction, PaymentVerificationError> {
     let tx = TransactionInt::new(transaction.clone());
     let instruction = tx.instruction(instruction_index)?;
     instruction.assert_not_empty()?;
     let program_id = instruction.program_id();
-    let transfer_checked_instruction = if spl_token::ID.eq(&program_id) {
+    // Plain `Transfer` carries no mint account, so the mint is only known after
+    // the source token account is fetched below.
+    // `TransferChecked` also carries the mint's decimals, which the token program enforces.
+    let (amount, mint, decimals) = if spl_token::ID.eq(&program_id) {
         let token_instruction =
             spl_token::instruction::TokenInstruction::unpack(instruction.data_slice())
                 .map_err(|_| SolanaExactError::InvalidTokenInstruction)?;
-        let amount = match token_instruction {
-            spl_token::instruction::TokenInstruction::TransferChecked {
-                amount,
-                decimals: _,
-            } => amount,
+        match token_instruction {
+            spl_token::instruction::TokenInstruction::TransferChecked { amount, decimals } => {
+                (amount, Some(instruction.account(1)?), Some(decimals))
+            }
+            spl_token::instruction::TokenInstruction::Transfer { amount }
+                if config.allow_plain_transfer =>
+            {
+                (amount, None, None)
+            }
+            spl_token::instruction::TokenInstruction::Transfer { .. } => {
+                return Err(SolanaExactError::PlainTransferNotAllowed.into());
+            }
             _ => return Err(SolanaExactError::InvalidTokenInstruction.into()),
-        };
-        // Source = 0
-        let source = instruction.account(0)?;
-        // Mint = 1
-        let mint = instruction.account(1)?;
-        // Destination = 2
-        let destination = instruction.account(2)?;
-        // Authority = 3
-        let authority = instruction.account(3)?;
-        TransferCheckedInstruction {
-            amount,
-            source,
-            mint,
-            destination,
-            authority,
-            token_program: spl_token::ID,
         }
     } else if spl_token_2022::ID.eq(&program_id) {
         let token_instruction =
             spl_token_2022::instruction::TokenInstruction::unpack(instruction.data_slice())
                 .map_err(|_| SolanaExactError::InvalidTokenInstruction)?;
-        let amount = match token_instruction {
-            spl_token_2022::instruction::TokenInstruction::TransferChecked {
-                amount,
-                decimals: _,
-            } => amount,
+        match token_instruction {
+            spl_token_2022::instruction::TokenInstruction::TransferChecked { amount, decimals } => {
+                (amount, Some(instruction.account(1)?), Some(decimals))
+            }
+            #[allow(deprecated)]
+            spl_token_2022::instruction::TokenInstruction::Transfer { amount }
+                if config.allow_plain_transfer =>
+            {
+                (amount, None, None)
+            }
+            #[allow(deprecated)]
+            spl_token_2022::instruction::TokenInstruction::Transfer { .. } => {
+                return Err(SolanaExactError::PlainTransferNotAllowed.into());
+            }
             _ => return Err(SolanaExactError::InvalidTokenInstruction.into()),
-        };
-        // Source = 0
-        let source = instruction.account(0)?;
-        // Mint = 1
-        let mint = instruction.account(1)?;
-        // Destination = 2
-        let destination = instruction.account(2)?;
-        // Authority = 3
-        let authority = instruction.account(3)?;
-        TransferCheckedInstruction {
-            amount,
-            source,
-            mint,
-            destination,
-            authority,
-            token_program: spl_token_2022::ID,
         }
     } else {
         return Err(SolanaExactError::InvalidTokenInstruction.into());
     };
+    // TransferChecked: source = 0, mint = 1, destination = 2, authority = 3
+    // Transfer: source = 0, destination = 1, authority = 2
+    let (source, destination, authority) = if mint.is_some() {
+        (
+            instruction.account(0)?,
+            instruction.account(2)?,
+            instruction.account(3)?,
+        )
+    } else {
+        (
+            instruction.account(0)?,
+            instruction.account(1)?,
+            instruction.account(2)?,
+        )
+    };
+    let token_program = program_id;
 
     // Verify that the fee payer is not transferring funds (not the authority)
     let fee_payer_pubkey = provider.pubkey();
-    if transfer_checked_instruction.authority == fee_payer_pubkey {
+    if authority == fee_payer_pubkey {
         return Err(SolanaExactError::FeePayerTransferringFunds.into());
     }
 
     // Verify that the mint matches the expected asset
-    if Address::new(transfer_checked_instruction.mint) != *transfer_requirement.asset {
+    if let Some(mint) = mint
+        && Address::new(mint) != *transfer_requirement.asset
+    {
         return Err(PaymentVerificationError::AssetMismatch);
     }
 
-    let token_program = transfer_checked_instruction.token_program;
     // findAssociatedTokenPda
     let (ata, _) = Pubkey::find_program_address(
         &[
@@ -443,32 +574,134 @@ pub async fn verify_transfer_instruction<P: SolanaChainProviderLike>(
         ],
         &ATA_PROGRAM_PUBKEY,
     );
-    if transfer_checked_instruction.destination != ata {
+    if destination != ata {
         return Err(PaymentVerificationError::RecipientMismatch);
     }
-    let accounts = provider
-        .get_multiple_accounts(&[transfer_checked_instruction.source, ata])
-        .await?;
-    let is_sender_missing = accounts.first().cloned().is_none_or(|a| a.is_none());
-    if is_sender_missing {
+    let accounts = provider.get_multiple_accounts(&[source, ata]).await?;
+    let Some(Some(source_account)) = accounts.first() else {
         return Err(SolanaExactError::MissingSenderAccount.into());
-    }
+    };
     // Destination ATA must exist (CreateATA no longer supported)
     let is_receiver_missing = accounts.get(1).cloned().is_none_or(|a| a.is_none());
     if is_receiver_missing {
         return Err(PaymentVerificationError::RecipientMismatch);
     }
-    let instruction_amount = transfer_checked_instruction.amount;
-    if instruction_amount != transfer_requirement.amount {
+    // Plain Transfer: the mint comes from the source token account on chain.
+    let mint = match mint {
+        Some(mint) => mint,
+        None => {
+            let mint = token_account_mint(&token_program, source_account)
+                .ok_or(SolanaExactError::InvalidSourceTokenAccount)?;
+            if Address::new(mint) != *transfer_requirement.asset {
+                return Err(PaymentVerificationError::AssetMismatch);
+            }
+            mint
+        }
+    };
+    if let Some(expected) = transfer_requirement.decimals {
+        let actual = match decimals {
+            Some(decimals) => decimals,
+            None => fetch_mint_decimals(provider, &token_program, &mint).await?,
+        };
+        if actual != expected {
+            return Err(PaymentVerificationError::DecimalsMismatch { expected, actual });
+        }
+    }
+    if amount != transfer_requirement.amount {
         return Err(PaymentVerificationError::InvalidPaymentAmount);
     }
-    Ok(transfer_checked_instruction)
+    // Headroom for concurrent spends between verify and settle
+    if let Some(buffer) = config.min_balance_buffer(transfer_requirement.asset) {
+        let balance = token_account_amount(&token_program, source_account)
+            .ok_or(SolanaExactError::InvalidSourceTokenAccount)?;
+        let required = amount.saturating_add(buffer);
+        if balance < required {
+            return Err(SolanaExactError::BalanceBelowBuffer { balance, required }.into());
+        }
+    }
+    Ok(TransferCheckedInstruction {
+        amount,
+        source,
+        mint,
+        destination,
+        authority,
+        token_program,
+    })
+}
+
+/// Reads the mint of an SPL token account owned by `token_program`.
+///
+/// Token and Token-2022 accounts share the base layout, with the mint in the
+/// first 32 bytes of the account data.
+fn token_account_mint(token_program: &Pubkey, account: &Account) -> Option<Pubkey> {
+    if account.owner != *token_program || account.data.len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    Pubkey::try_from(&account.data[..32]).ok()
 }
 
+/// Mint decimals read on chain, per mint. Decimals of a mint never change.
+static MINT_DECIMALS: LazyLock<Mutex<HashMap<Pubkey, u8>>> = LazyLock::new(Default::default);
+
+/// Returns the decimals of `mint`, read from the mint account once per process.
+async fn fetch_mint_decimals<P: SolanaChainProviderLike>(
+    provider: &P,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+) -> Result<u8, PaymentVerificationError> {
+    let cached = MINT_DECIMALS
+        .lock()
+        .expect("mint decimals lock poisoned")
+        .get(mint)
+        .copied();
+    if let Some(decimals) = cached {
+        return Ok(decimals);
+    }
+    let accounts = provider.get_multiple_accounts(&[*mint]).await?;
+    let Some(Some(mint_account)) = accounts.first() else {
+        return Err(SolanaExactError::InvalidMintAccount.into());
+    };
+    let decimals =
+        mint_decimals(token_program, mint_account).ok_or(SolanaExactError::InvalidMintAccount)?;
+    MINT_DECIMALS
+        .lock()
+        .expect("mint decimals lock poisoned")
+        .insert(*mint, decimals);
+    Ok(decimals)
+}
+
+/// Reads the decimals of an SPL mint account owned by `token_program`.
+///
+/// Token and Token-2022 mints share the base layout, with the decimals following the
+/// mint authority and the supply.
+fn mint_decimals(token_program: &Pubkey, account: &Account) -> Option<u8> {
+    if account.owner != *token_program || account.data.len() < MINT_LEN {
+        return None;
+    }
+    account.data.get(44).copied()
+}
+
+/// Reads the token amount of an SPL token account owned by `token_program`.
+///
+/// The amount is the little-endian `u64` following the mint and owner.
+fn token_account_amount(token_program: &Pubkey, account: &Account) -> Option<u64> {
+    if account.owner != *token_program || account.data.len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    let amount = account.data[64..72].try_into().ok()?;
+    Some(u64::from_le_bytes(amount))
+}
+
+/// Co-signs and submits a verified transaction, waiting for its confirmation.
+///
+/// Fails with [`SolanaChainProviderError::BlockhashExpired`] if the recent blockhash the
+/// payer signed over expires first, e.g. when settlement was delayed. The payer then has to
+/// sign a new transaction; submitting this one again cannot succeed.
 pub async fn settle_transaction<P: SolanaChainProviderLike>(
     provider: &P,
     verification: VerifyTransferResult,
 ) -> Result<Signature, SolanaChainProviderError> {
+    let blockhash = *verification.transaction.message.recent_blockhash();
     let tx = TransactionInt::new(verification.transaction).sign(provider)?;
     // Verify if fully signed
     if !tx.is_fully_signed() {
@@ -480,7 +713,14 @@ pub async fn settle_transaction<P: SolanaChainProviderLike>(
     }
     let tx_sig = tx
         .send_and_confirm(provider, CommitmentConfig::confirmed())
-        .await?;
+        .await
+        .map_err(|error| {
+            if error.is_blockhash_expired() {
+                SolanaChainProviderError::BlockhashExpired(blockhash)
+            } else {
+                error
+            }
+        })?;
     Ok(tx_sig)
 }
 
@@ -525,6 +765,27 @@ pub struct V1SolanaExactFacilitatorConfig {
     /// Default: true - strongly recommended to keep this enabled
     #[serde(default = "default_require_fee_payer_not_in_instructions")]
     pub require_fee_payer_not_in_instructions: bool,
+
+    /// Accept a plain SPL `Transfer` in place of `TransferChecked`.
+    /// `Transfer` names no mint, so the source token account is fetched over RPC
+    /// and its mint must equal the required asset.
+    /// Default: false - only `TransferChecked` is accepted
+    #[serde(default)]
+    pub allow_plain_transfer: bool,
+
+    /// Per-asset balance headroom, in the token's base units, keyed by mint.
+    /// For listed assets the source token account must hold at least
+    /// `amount + buffer` at verify time, so concurrent spends before settlement
+    /// are less likely to make the transaction fail on chain.
+    /// Default: {} - the balance is left to transaction simulation
+    #[serde(default)]
+    pub min_balance_buffers: HashMap<Address, u64>,
+
+    /// Require the requirements accepted by the client to equal the payment
+    /// requirements field by field. Only used by the V2 scheme.
+    /// Default: false - they only need to be functionally equal, ignoring `maxTimeoutSeconds`
+    #[serde(default)]
+    pub strict_accepted_match: bool,
 }
 
 fn default_allow_additional_instructions() -> bool {
@@ -554,6 +815,9 @@ impl Default for V1SolanaExactFacilitatorConfig {
             allowed_program_ids: default_allowed_program_ids(),
             blocked_program_ids: Vec::new(),
             require_fee_payer_not_in_instructions: default_require_fee_payer_not_in_instructions(),
+            allow_plain_transfer: false,
+            min_balance_buffers: HashMap::new(),
+            strict_accepted_match: false,
         }
     }
 }
@@ -576,4 +840,313 @@ impl V1SolanaExactFacilitatorConfig {
             .iter()
             .any(|addr| addr.pubkey() == program_id)
     }
+
+    /// Balance headroom required on top of the payment amount for `asset`, if configured
+    pub fn min_balance_buffer(&self, asset: &Address) -> Option<u64> {
+        self.min_balance_buffers.get(asset).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_message::v0::Message as MessageV0;
+    use solana_message::{Hash, Instruction, VersionedMessage};
+
+    /// Provider serving token accounts, and accepting every simulation.
+    struct MockProvider {
+        fee_payer: Pubkey,
+        accounts: HashMap<Pubkey, Account>,
+    }
+
+    impl SolanaChainProviderLike for MockProvider {
+        async fn simulate_transaction_with_config(
+            &self,
+            _tx: &VersionedTransaction,
+            _cfg: RpcSimulateTransactionConfig,
+        ) -> Result<(), SolanaChainProviderError> {
+            Ok(())
+        }
+
+        async fn get_multiple_accounts(
+            &self,
+            pubkeys: &[Pubkey],
+        ) -> Result<Vec<Option<Account>>, SolanaChainProviderError> {
+            Ok(pubkeys
+                .iter()
+                .map(|pubkey| self.accounts.get(pubkey).cloned())
+                .collect())
+        }
+
+        fn max_compute_unit_limit(&self) -> u32 {
+            400_000
+        }
+
+        fn max_compute_unit_price(&self) -> u64 {
+            1_000_000
+        }
+
+        fn pubkey(&self) -> Pubkey {
+            self.fee_payer
+        }
+
+        fn fee_payer(&self) -> Address {
+            Address::new(self.fee_payer)
+        }
+
+        fn sign(
+            &self,
+            tx: VersionedTransaction,
+        ) -> Result<VersionedTransaction, SolanaChainProviderError> {
+            Ok(tx)
+        }
+
+        async fn send_and_confirm(
+            &self,
+            _tx: &VersionedTransaction,
+            _commitment_config: CommitmentConfig,
+        ) -> Result<Signature, SolanaChainProviderError> {
+            unimplemented!("verification sends no transaction")
+        }
+    }
+
+    fn token_account(token_program: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+        let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account {
+            lamports: 2_039_280,
+            data,
+            owner: token_program,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    /// A payer paying `AMOUNT` of `mint` to `pay_to`, from a token account of `mint`.
+    struct Transfer {
+        mint: Pubkey,
+        pay_to: Pubkey,
+        source: Pubkey,
+        destination: Pubkey,
+        authority: Pubkey,
+        provider: MockProvider,
+    }
+
+    const AMOUNT: u64 = 10_000;
+
+    impl Transfer {
+        fn new(balance: u64) -> Self {
+            let mint = Pubkey::new_unique();
+            let pay_to = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let source = Pubkey::new_unique();
+            let (destination, _) = Pubkey::find_program_address(
+                &[pay_to.as_ref(), spl_token::ID.as_ref(), mint.as_ref()],
+                &ATA_PROGRAM_PUBKEY,
+            );
+            let accounts = HashMap::from([
+                (
+                    source,
+                    token_account(spl_token::ID, mint, authority, balance),
+                ),
+                (destination, token_account(spl_token::ID, mint, pay_to, 0)),
+            ]);
+            Self {
+                mint,
+                pay_to,
+                source,
+                destination,
+                authority,
+                provider: MockProvider {
+                    fee_payer: Pubkey::new_unique(),
+                    accounts,
+                },
+            }
+        }
+
+        /// Makes the source token account hold tokens of `mint` instead.
+        fn with_source_mint(mut self, mint: Pubkey) -> Self {
+            let source = self.provider.accounts.get_mut(&self.source).unwrap();
+            source.data[..32].copy_from_slice(mint.as_ref());
+            self
+        }
+
+        fn plain(&self) -> Instruction {
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &self.source,
+                &self.destination,
+                &self.authority,
+                &[],
+                AMOUNT,
+            )
+            .unwrap()
+        }
+
+        fn checked(&self) -> Instruction {
+            spl_token::instruction::transfer_checked(
+                &spl_token::ID,
+                &self.source,
+                &self.mint,
+                &self.destination,
+                &self.authority,
+                &[],
+                AMOUNT,
+                6,
+            )
+            .unwrap()
+        }
+
+        async fn verify(
+            &self,
+            instruction: Instruction,
+            config: &V1SolanaExactFacilitatorConfig,
+        ) -> Result<TransferCheckedInstruction, PaymentVerificationError> {
+            let message = MessageV0::try_compile(
+                &self.provider.fee_payer,
+                &[instruction],
+                &[],
+                Hash::default(),
+            )
+            .unwrap();
+            let transaction = VersionedTransaction {
+                signatures: vec![],
+                message: VersionedMessage::V0(message),
+            };
+            let requirement = TransferRequirement {
+                asset: &Address::new(self.mint),
+                pay_to: &Address::new(self.pay_to),
+                amount: AMOUNT,
+                decimals: None,
+            };
+            verify_transfer_instruction(&self.provider, &transaction, 0, &requirement, config).await
+        }
+    }
+
+    fn allowing_plain_transfer() -> V1SolanaExactFacilitatorConfig {
+        V1SolanaExactFacilitatorConfig {
+            allow_plain_transfer: true,
+            ..Default::default()
+        }
+    }
+
+    fn with_buffer(asset: Pubkey, buffer: u64) -> V1SolanaExactFacilitatorConfig {
+        V1SolanaExactFacilitatorConfig {
+            min_balance_buffers: HashMap::from([(Address::new(asset), buffer)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn token_account_mint_reads_token_and_token_2022_accounts() {
+        let mint = Pubkey::new_unique();
+        for token_program in [spl_token::ID, spl_token_2022::ID] {
+            let account = token_account(token_program, mint, Pubkey::new_unique(), 1);
+            assert_eq!(token_account_mint(&token_program, &account), Some(mint));
+        }
+    }
+
+    #[test]
+    fn token_account_mint_rejects_accounts_of_another_program() {
+        let account = token_account(
+            spl_token_2022::ID,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            1,
+        );
+        assert_eq!(token_account_mint(&spl_token::ID, &account), None);
+    }
+
+    #[test]
+    fn token_account_mint_rejects_short_data() {
+        let mut account =
+            token_account(spl_token::ID, Pubkey::new_unique(), Pubkey::new_unique(), 1);
+        account.data.truncate(TOKEN_ACCOUNT_LEN - 1);
+        assert_eq!(token_account_mint(&spl_token::ID, &account), None);
+    }
+
+    #[test]
+    fn token_account_amount_follows_the_mint_and_owner() {
+        let mut account = token_account(
+            spl_token::ID,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0x0102_0304_0506_0708,
+        );
+        account.data[72..].fill(0xff);
+        assert_eq!(
+            token_account_amount(&spl_token::ID, &account),
+            Some(0x0102_0304_0506_0708)
+        );
+        account.data.truncate(TOKEN_ACCOUNT_LEN - 1);
+        assert_eq!(token_account_amount(&spl_token::ID, &account), None);
+    }
+
+    #[tokio::test]
+    async fn plain_transfer_is_rejected_by_default() {
+        let transfer = Transfer::new(AMOUNT);
+        let error = transfer
+            .verify(transfer.plain(), &V1SolanaExactFacilitatorConfig::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            PaymentVerificationError::from(SolanaExactError::PlainTransferNotAllowed).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn plain_transfer_of_the_required_mint_is_accepted_when_allowed() {
+        let transfer = Transfer::new(AMOUNT);
+        let verified = transfer
+            .verify(transfer.plain(), &allowing_plain_transfer())
+            .await
+            .unwrap();
+        assert_eq!(verified.mint, transfer.mint);
+        assert_eq!(verified.authority, transfer.authority);
+        assert_eq!(verified.amount, AMOUNT);
+    }
+
+    #[tokio::test]
+    async fn plain_transfer_of_another_mint_is_rejected() {
+        let transfer = Transfer::new(AMOUNT).with_source_mint(Pubkey::new_unique());
+        let error = transfer
+            .verify(transfer.plain(), &allowing_plain_transfer())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, PaymentVerificationError::AssetMismatch),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn balance_of_amount_plus_buffer_is_enough() {
+        let transfer = Transfer::new(AMOUNT + 500);
+        let config = with_buffer(transfer.mint, 500);
+        assert!(transfer.verify(transfer.checked(), &config).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn balance_below_amount_plus_buffer_is_insufficient_funds() {
+        let transfer = Transfer::new(AMOUNT + 499);
+        let config = with_buffer(transfer.mint, 500);
+        let error = transfer
+            .verify(transfer.checked(), &config)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, PaymentVerificationError::InsufficientFunds),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn assets_without_buffer_are_not_checked() {
+        let transfer = Transfer::new(0);
+        let config = with_buffer(Pubkey::new_unique(), 500);
+        assert!(transfer.verify(transfer.checked(), &config).await.is_ok());
+    }
 }
@@ -58,6 +58,12 @@ pub struct SupportedPaymentKindExtra {
 
 pub const ATA_PROGRAM_PUBKEY: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
 
+/// Size of the base SPL token account layout, shared by Token and Token-2022.
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// Size of the base SPL mint layout, shared by Token and Token-2022.
+pub const MINT_LEN: usize = 82;
+
 #[cfg(any(feature = "client", feature = "facilitator"))]
 pub struct InstructionInt {
     index: usize,
@@ -249,10 +255,26 @@ pub enum SolanaExactError {
     InvalidComputeLimitInstruction,
     #[error("Invalid compute price instruction")]
     InvalidComputePriceInstruction,
+    #[error("Missing compute limit instruction")]
+    MissingComputeLimitInstruction,
+    #[error("Missing compute price instruction")]
+    MissingComputePriceInstruction,
+    #[error("Missing token transfer instruction")]
+    MissingTransferInstruction,
+    #[error("Duplicate instruction at index {0}")]
+    DuplicateInstruction(usize),
     #[error("Invalid token instruction")]
     InvalidTokenInstruction,
     #[error("Missing sender account in transaction")]
     MissingSenderAccount,
+    #[error("Plain Transfer instruction not allowed, use TransferChecked")]
+    PlainTransferNotAllowed,
+    #[error("Source is not a token account of the transfer program")]
+    InvalidSourceTokenAccount,
+    #[error("Source balance {balance} is below the required {required} (amount plus buffer)")]
+    BalanceBelowBuffer { balance: u64, required: u64 },
+    #[error("Mint is not a mint account of the transfer program")]
+    InvalidMintAccount,
 }
 
 impl From<SolanaExactError> for PaymentVerificationError {
@@ -261,6 +283,9 @@ impl From<SolanaExactError> for PaymentVerificationError {
             SolanaExactError::TransactionDecoding(_) => {
                 PaymentVerificationError::InvalidFormat(e.to_string())
             }
+            SolanaExactError::BalanceBelowBuffer { .. } => {
+                PaymentVerificationError::InsufficientFunds
+            }
             SolanaExactError::MaxComputeUnitLimitExceeded
             | SolanaExactError::MaxComputeUnitPriceExceeded
             | SolanaExactError::TooFewInstructions
@@ -272,8 +297,15 @@ impl From<SolanaExactError> for PaymentVerificationError {
             | SolanaExactError::FeePayerIncludedInInstructionAccounts
             | SolanaExactError::NoInstructionAtIndex(_)
             | SolanaExactError::InvalidComputeLimitInstruction
+            | SolanaExactError::MissingComputeLimitInstruction
+            | SolanaExactError::MissingComputePriceInstruction
+            | SolanaExactError::MissingTransferInstruction
+            | SolanaExactError::DuplicateInstruction(_)
             | SolanaExactError::NoAccountAtIndex(_)
             | SolanaExactError::InvalidTokenInstruction
+            | SolanaExactError::PlainTransferNotAllowed
+            | SolanaExactError::InvalidSourceTokenAccount
+            | SolanaExactError::InvalidMintAccount
             | SolanaExactError::EmptyInstructionAtIndex(_)
             | SolanaExactError::FeePayerTransferringFunds
             | SolanaExactError::MissingSenderAccount
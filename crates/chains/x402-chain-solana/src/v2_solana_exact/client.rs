@@ -14,6 +14,9 @@
 //! let rpc = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
 //! let client = V2SolanaExactClient::new(keypair, rpc);
 //! ```
+//!
+//! To pay on several networks, e.g. mainnet and devnet, give each its own RPC client with
+//! [`V2SolanaExactClient::from_rpc_clients`].
 
 use alloy_primitives::U256;
 use async_trait::async_trait;
@@ -28,7 +31,7 @@ use x402_types::scheme::client::{
 };
 use x402_types::util::Base64Bytes;
 
-use crate::chain::rpc::RpcClientLike;
+use crate::chain::rpc::{RpcClientLike, RpcClients};
 use crate::v1_solana_exact::client::build_signed_transfer_transaction;
 use crate::v1_solana_exact::types::ExactSolanaPayload;
 use crate::v2_solana_exact::V2SolanaExact;
@@ -39,13 +42,24 @@ use crate::v2_solana_exact::types::{PaymentPayload, PaymentRequirements};
 #[allow(dead_code)] // Public for consumption by downstream crates.
 pub struct V2SolanaExactClient<S, R> {
     signer: S,
-    rpc_client: R,
+    rpc_clients: RpcClients<R>,
 }
 
 #[allow(dead_code)] // Public for consumption by downstream crates.
 impl<S, R> V2SolanaExactClient<S, R> {
+    /// Creates a client using `rpc_client` on every Solana network.
     pub fn new(signer: S, rpc_client: R) -> Self {
-        Self { signer, rpc_client }
+        Self::from_rpc_clients(signer, RpcClients::new(rpc_client))
+    }
+
+    /// Creates a client using the RPC client of the network of each payment.
+    ///
+    /// Signing a payment on a network without RPC client fails.
+    pub fn from_rpc_clients(signer: S, rpc_clients: RpcClients<R>) -> Self {
+        Self {
+            signer,
+            rpc_clients,
+        }
     }
 }
 
@@ -86,7 +100,7 @@ where
                     return None;
                 }
                 let candidate = PaymentCandidate {
-                    chain_id,
+                    chain_id: chain_id.clone(),
                     asset: requirements.asset.to_string(),
                     amount: U256::from(requirements.amount.inner()),
                     scheme: self.scheme().to_string(),
@@ -94,7 +108,7 @@ where
                     pay_to: requirements.pay_to.to_string(),
                     signer: Box::new(PayloadSigner {
                         signer: self.signer.clone(),
-                        rpc_client: self.rpc_client.clone(),
+                        rpc_client: self.rpc_clients.get(&chain_id).cloned(),
                         resource: payment_required.resource.clone(),
                         extensions: payment_required.extensions.clone(),
                         requirements,
@@ -111,7 +125,7 @@ where
 #[allow(dead_code)] // Public for consumption by downstream crates.
 struct PayloadSigner<S, R> {
     signer: S,
-    rpc_client: R,
+    rpc_client: Option<R>,
     resource: Option<ResourceInfo>,
     extensions: ExtensionsJson,
     requirements: PaymentRequirements,
@@ -124,11 +138,17 @@ impl<S: Signer + Sync, R: RpcClientLike + Sync> PaymentCandidateSigner for Paylo
     async fn sign_payment(&self) -> Result<String, X402Error> {
         let fee_payer = self.requirements.extra.fee_payer.clone();
         let fee_payer_pubkey: Pubkey = fee_payer.into();
+        let rpc_client = self.rpc_client.as_ref().ok_or_else(|| {
+            X402Error::SigningError(format!(
+                "no Solana RPC client configured for {}",
+                self.requirements.network
+            ))
+        })?;
 
         let amount = self.requirements.amount.inner();
         let tx_b64 = build_signed_transfer_transaction(
             &self.signer,
-            &self.rpc_client,
+            rpc_client,
             &fee_payer_pubkey,
             &self.requirements.pay_to,
             &self.requirements.asset,
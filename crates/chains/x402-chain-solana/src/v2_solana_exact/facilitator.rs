@@ -6,9 +6,11 @@ use x402_types::proto::v2;
 use x402_types::scheme::{
     X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 use crate::V2SolanaExact;
 use crate::chain::provider::SolanaChainProviderLike;
+use crate::known_token_decimals;
 use crate::v1_solana_exact::facilitator::V1SolanaExactFacilitatorConfig;
 use crate::v1_solana_exact::facilitator::{
     TransferRequirement, VerifyTransferResult, settle_transaction, verify_transaction,
@@ -57,25 +59,35 @@ where
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let request = types::VerifyRequest::try_from(request)?;
-        let verification = verify_transfer(&self.provider, &request, &self.config).await?;
-        Ok(v2::VerifyResponse::valid(verification.payer.to_string()).into())
+        verify_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = types::VerifyRequest::try_from(request)?;
+            let verification =
+                verify_transfer(&self.provider, &request, declared_decimals, &self.config).await?;
+            Ok(v2::VerifyResponse::valid(verification.payer.to_string()).into())
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let request = types::SettleRequest::try_from(request)?;
-        let verification = verify_transfer(&self.provider, &request, &self.config).await?;
-        let payer = verification.payer.to_string();
-        let tx_sig = settle_transaction(&self.provider, verification).await?;
-        Ok(v2::SettleResponse::Success {
-            payer,
-            transaction: tx_sig.to_string(),
-            network: self.provider.chain_id().to_string(),
-        }
-        .into())
+        settle_in_span(request, async {
+            let declared_decimals = request.asset_decimals();
+            let request = types::SettleRequest::try_from(request)?;
+            let verification =
+                verify_transfer(&self.provider, &request, declared_decimals, &self.config).await?;
+            let payer = verification.payer.to_string();
+            let tx_sig = settle_transaction(&self.provider, verification).await?;
+            Ok(v2::SettleResponse::Success {
+                payer,
+                transaction: tx_sig.to_string(),
+                network: self.provider.chain_id().to_string(),
+            }
+            .into())
+        })
+        .await
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
@@ -104,18 +116,21 @@ where
     }
 }
 
+/// Verifies the transfer transaction of a payment.
+///
+/// `declared_decimals` are the asset decimals declared in the payment requirements; when
+/// absent, the decimals of the known deployment of the asset are expected, if any.
 pub async fn verify_transfer<P: SolanaChainProviderLike + ChainProviderOps>(
     provider: &P,
     request: &types::VerifyRequest,
+    declared_decimals: Option<u8>,
     config: &V2SolanaExactFacilitatorConfig,
 ) -> Result<VerifyTransferResult, proto::PaymentVerificationError> {
     let payload = &request.payment_payload;
     let requirements = &request.payment_requirements;
 
     let accepted = &payload.accepted;
-    if accepted != requirements {
-        return Err(proto::PaymentVerificationError::AcceptedRequirementsMismatch);
-    }
+    accepted.assert_accepted(requirements, config.strict_accepted_match)?;
 
     let chain_id = provider.chain_id();
     let payload_chain_id = &accepted.network;
@@ -127,6 +142,7 @@ pub async fn verify_transfer<P: SolanaChainProviderLike + ChainProviderOps>(
         pay_to: &requirements.pay_to,
         asset: &requirements.asset,
         amount: requirements.amount.inner(),
+        decimals: declared_decimals.or_else(|| known_token_decimals(&requirements.asset)),
     };
     verify_transaction(
         provider,
@@ -0,0 +1,136 @@
+//! Selection of the RPC client of the network of each payment.
+
+use serde_json::json;
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_message::Hash;
+use solana_pubkey::Pubkey;
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind as ClientErrorKind};
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_rpc_client_api::response::{
+    RpcPrioritizationFee, RpcResult, RpcSimulateTransactionResult,
+};
+use solana_transaction::versioned::VersionedTransaction;
+use std::sync::Arc;
+use x402_chain_solana::KnownNetworkSolana;
+use x402_chain_solana::V2SolanaExactClient;
+use x402_chain_solana::chain::rpc::{RpcClientLike, RpcClients};
+use x402_types::chain::ChainId;
+use x402_types::proto::PaymentRequired;
+use x402_types::scheme::client::{X402Error, X402SchemeClient};
+
+/// USDC on Solana mainnet.
+const ASSET: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const PAY_TO: &str = "11111111111111111111111111111112";
+const FEE_PAYER: &str = "11111111111111111111111111111113";
+
+/// RPC client failing every request with its name, telling which one a payment used.
+#[derive(Clone)]
+struct NamedRpcClient(&'static str);
+
+impl RpcClientLike for NamedRpcClient {
+    async fn get_account(&self, _pubkey: &Pubkey) -> Result<Account, ClientError> {
+        Err(ClientErrorKind::Custom(self.0.to_string()).into())
+    }
+
+    async fn simulate_transaction_with_config(
+        &self,
+        _transaction: &VersionedTransaction,
+        _config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        Err(ClientErrorKind::Custom(self.0.to_string()).into())
+    }
+
+    async fn get_recent_prioritization_fees(
+        &self,
+        _addresses: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>, ClientError> {
+        Err(ClientErrorKind::Custom(self.0.to_string()).into())
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        Err(ClientErrorKind::Custom(self.0.to_string()).into())
+    }
+}
+
+fn payment_required(networks: &[ChainId]) -> PaymentRequired {
+    let accepts = networks
+        .iter()
+        .map(|network| {
+            json!({
+                "scheme": "exact",
+                "network": network.to_string(),
+                "amount": "10000",
+                "payTo": PAY_TO,
+                "maxTimeoutSeconds": 300,
+                "asset": ASSET,
+                "extra": { "feePayer": FEE_PAYER },
+            })
+        })
+        .collect::<Vec<_>>();
+    let payment_required = json!({
+        "x402Version": 2,
+        "resource": { "url": "https://api.example.com/protected" },
+        "accepts": accepts,
+    });
+    PaymentRequired::V2(serde_json::from_str(&payment_required.to_string()).unwrap())
+}
+
+async fn signing_error(
+    client: &V2SolanaExactClient<Arc<Keypair>, NamedRpcClient>,
+    network: ChainId,
+) -> String {
+    let candidates = client.accept(&payment_required(&[network]));
+    assert_eq!(candidates.len(), 1);
+    match candidates[0].sign().await {
+        Err(X402Error::SigningError(message)) => message,
+        other => panic!("expected a signing error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn uses_the_rpc_client_of_the_network() {
+    let rpc_clients = RpcClients::default()
+        .with_network(ChainId::solana(), NamedRpcClient("mainnet-rpc"))
+        .with_network(ChainId::solana_devnet(), NamedRpcClient("devnet-rpc"));
+    let client = V2SolanaExactClient::from_rpc_clients(Arc::new(Keypair::new()), rpc_clients);
+
+    let candidates = client.accept(&payment_required(&[
+        ChainId::solana(),
+        ChainId::solana_devnet(),
+    ]));
+    assert_eq!(candidates.len(), 2);
+
+    let error = signing_error(&client, ChainId::solana()).await;
+    assert!(error.contains("mainnet-rpc"), "{error}");
+    let error = signing_error(&client, ChainId::solana_devnet()).await;
+    assert!(error.contains("devnet-rpc"), "{error}");
+}
+
+#[tokio::test]
+async fn falls_back_to_the_default_rpc_client() {
+    let rpc_clients = RpcClients::new(NamedRpcClient("default-rpc"))
+        .with_network(ChainId::solana_devnet(), NamedRpcClient("devnet-rpc"));
+    let client = V2SolanaExactClient::from_rpc_clients(Arc::new(Keypair::new()), rpc_clients);
+
+    let error = signing_error(&client, ChainId::solana()).await;
+    assert!(error.contains("default-rpc"), "{error}");
+    let error = signing_error(&client, ChainId::solana_devnet()).await;
+    assert!(error.contains("devnet-rpc"), "{error}");
+}
+
+#[tokio::test]
+async fn fails_without_rpc_client_for_the_network() {
+    let rpc_clients =
+        RpcClients::default().with_network(ChainId::solana(), NamedRpcClient("mainnet-rpc"));
+    let client = V2SolanaExactClient::from_rpc_clients(Arc::new(Keypair::new()), rpc_clients);
+
+    let error = signing_error(&client, ChainId::solana_devnet()).await;
+    assert_eq!(
+        error,
+        format!(
+            "no Solana RPC client configured for {}",
+            ChainId::solana_devnet()
+        )
+    );
+}
@@ -1,12 +1,14 @@
 //! Configuration types for TRON chain providers.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
 use std::time::Duration;
 use url::Url;
 use x402_types::chain::ChainId;
 use x402_types::config::LiteralOrEnv;
+use x402_types::util::DecimalU256;
 
 use crate::chain::{TronAddress, TronChainReference};
 
@@ -53,6 +55,16 @@ pub struct TronChainConfigInner {
     /// How often to poll `gettransactioninfobyid` (seconds).
     #[serde(default = "TronChainConfigInner::default_tx_poll_interval_secs")]
     pub tx_poll_interval_secs: u64,
+    /// How long (seconds) resource servers may cache a successful verification,
+    /// advertised in the `X-Verify-TTL` header of `/verify` responses. `0` disables it.
+    #[serde(default)]
+    pub verify_cache_ttl_secs: u64,
+    /// Asset addresses allowed on this chain (optional, default: any).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_assets: Vec<String>,
+    /// Minimum amount per asset, in base units (optional).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub min_payment_amount: HashMap<String, DecimalU256>,
 }
 
 impl TronChainConfigInner {
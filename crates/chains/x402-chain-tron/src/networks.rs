@@ -103,7 +103,7 @@ mod tests {
     #[test]
     fn chain_reference_round_trips() {
         for r in [TronChainReference::mainnet(), TronChainReference::nile()] {
-            let chain_id = ChainId::from(r.clone());
+            let chain_id = ChainId::from(r);
             let parsed = TronChainReference::try_from(chain_id).unwrap();
             assert_eq!(parsed, r);
         }
@@ -125,7 +125,7 @@ mod tests {
         assert!(
             TronChainReference::mainnet()
                 .x402_exact_permit2_proxy()
-                .is_none()
+                .is_some()
         );
         // SUN.io Permit2 is known for mainnet and nile
         assert!(TronChainReference::mainnet().sun_permit2().is_some());
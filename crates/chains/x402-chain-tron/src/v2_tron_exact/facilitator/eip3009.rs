@@ -9,7 +9,7 @@ use alloy_sol_types::{Eip712Domain, SolStruct, eip712_domain, sol};
 use x402_types::chain::ChainId;
 use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::X402SchemeFacilitatorError;
-use x402_types::timestamp::UnixTimestamp;
+use x402_types::timestamp::{Clock, UnixTimestamp};
 
 use crate::chain::contracts;
 use crate::chain::provider::{
@@ -32,16 +32,19 @@ sol! {
 
 pub async fn verify_eip3009_payment(
     provider: &TronChainProvider,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
     payment_payload: &Eip3009PaymentPayload,
     payment_requirements: &Eip3009PaymentRequirements,
 ) -> Result<v2::VerifyResponse, X402SchemeFacilitatorError> {
     let accepted = &payment_payload.accepted;
-    assert_requirements_match(accepted, payment_requirements)?;
+    accepted.assert_accepted(payment_requirements, strict_accepted_match)?;
     assert_valid_payment(
         provider,
         &provider.chain_reference,
         accepted,
         payment_payload,
+        clock,
     )
     .await?;
 
@@ -60,10 +63,7 @@ pub async fn verify_eip3009_payment(
         .await
         .map_err(|e| X402SchemeFacilitatorError::OnchainFailure(e.to_string()))?
     {
-        return Err(PaymentVerificationError::InvalidSignature(
-            "Authorization nonce already used".to_string(),
-        )
-        .into());
+        return Err(PaymentVerificationError::NonceUsed.into());
     }
 
     let transfer = Eip3009Transfer {
@@ -94,10 +94,19 @@ pub async fn verify_eip3009_payment(
 
 pub async fn settle_eip3009_payment(
     provider: &TronChainProvider,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
     payment_payload: &v2::PaymentPayload<Eip3009PaymentRequirements, Eip3009Payload>,
     payment_requirements: &Eip3009PaymentRequirements,
 ) -> Result<v2::SettleResponse, X402SchemeFacilitatorError> {
-    verify_eip3009_payment(provider, payment_payload, payment_requirements).await?;
+    verify_eip3009_payment(
+        provider,
+        clock,
+        strict_accepted_match,
+        payment_payload,
+        payment_requirements,
+    )
+    .await?;
 
     let accepted = &payment_payload.accepted;
     let auth = &payment_payload.payload.authorization;
@@ -242,22 +251,12 @@ pub(crate) fn recover_address(hash: &[u8; 32], signature: &Bytes) -> Result<Addr
     Ok(Address::from_slice(&keccak[12..]))
 }
 
-pub fn assert_requirements_match<T: PartialEq>(
-    accepted: &T,
-    requirements: &T,
-) -> Result<(), PaymentVerificationError> {
-    if accepted != requirements {
-        Err(PaymentVerificationError::AcceptedRequirementsMismatch)
-    } else {
-        Ok(())
-    }
-}
-
 pub async fn assert_valid_payment<P>(
     provider: &P,
     chain: &TronChainReference,
     accepted: &Eip3009PaymentRequirements,
     payload: &Eip3009PaymentPayload,
+    clock: &dyn Clock,
 ) -> Result<(), X402SchemeFacilitatorError>
 where
     P: TronChainProviderLike,
@@ -269,7 +268,7 @@ where
     }
 
     let auth = &payload.payload.authorization;
-    let now = UnixTimestamp::now();
+    let now = clock.now();
 
     // From the spec: Facilitator safety: the facilitator's address MUST NOT appear as from (eip3009) or permit2Authorization.from (permit2) in the signed payload.
     let authorization_from = TronAddress::from(auth.from);
@@ -6,12 +6,15 @@ pub mod permit2;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
 use x402_types::chain::ChainProviderOps;
 use x402_types::proto;
 use x402_types::proto::v2;
 use x402_types::scheme::{
     X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
 };
+use x402_types::timestamp::{Clock, SystemClock};
+use x402_types::util::span::{settle_in_span, verify_in_span};
 
 use crate::V2TronExact;
 use crate::chain::TronChainProvider;
@@ -22,15 +25,51 @@ impl X402SchemeFacilitatorBuilder<Arc<TronChainProvider>> for V2TronExact {
     fn build(
         &self,
         provider: Arc<TronChainProvider>,
-        _config: Option<serde_json::Value>,
+        config: Option<serde_json::Value>,
     ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
-        Ok(Box::new(V2TronExactFacilitator { provider }))
+        let config = config
+            .map(V2TronExactFacilitatorConfig::deserialize)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Box::new(V2TronExactFacilitator::new(provider, config)))
     }
 }
 
+/// Configuration for the V2 TRON "exact" scheme facilitator.
+///
+/// # Fields
+///
+/// - `strict_accepted_match`: Whether the requirements accepted by the client must equal
+///   the payment requirements field by field. By default they only need to be
+///   [functionally equal](v2::PaymentRequirements::is_functionally_equal).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct V2TronExactFacilitatorConfig {
+    #[serde(default)]
+    pub strict_accepted_match: bool,
+}
+
 /// Facilitator for the V2 TRON "exact" payment scheme.
 pub struct V2TronExactFacilitator {
     pub provider: Arc<TronChainProvider>,
+    strict_accepted_match: bool,
+    clock: Arc<dyn Clock>,
+}
+
+impl V2TronExactFacilitator {
+    /// Creates a facilitator settling payments with `provider`.
+    pub fn new(provider: Arc<TronChainProvider>, config: V2TronExactFacilitatorConfig) -> Self {
+        Self {
+            provider,
+            strict_accepted_match: config.strict_accepted_match,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Checks authorization validity windows against `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -39,68 +78,82 @@ impl X402SchemeFacilitator for V2TronExactFacilitator {
         &self,
         request: &proto::VerifyRequest,
     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
-        let verify_request = FacilitatorVerifyRequest::try_from(request.clone())?;
-        let verify_response = match verify_request {
-            FacilitatorVerifyRequest::Eip3009 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                eip3009::verify_eip3009_payment(
-                    &self.provider,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-            FacilitatorVerifyRequest::Permit2 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                permit2::verify_permit2_payment(
-                    &self.provider,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-        };
-        Ok(verify_response.into())
+        verify_in_span(request, async {
+            let verify_request = FacilitatorVerifyRequest::try_from(request.clone())?;
+            let verify_response = match verify_request {
+                FacilitatorVerifyRequest::Eip3009 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    eip3009::verify_eip3009_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+                FacilitatorVerifyRequest::Permit2 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    permit2::verify_permit2_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+            };
+            Ok(verify_response.into())
+        })
+        .await
     }
 
     async fn settle(
         &self,
         request: &proto::SettleRequest,
     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
-        let settle_request = FacilitatorSettleRequest::try_from(request.clone())?;
-        let settle_response = match settle_request {
-            FacilitatorSettleRequest::Eip3009 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                eip3009::settle_eip3009_payment(
-                    &self.provider,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-            FacilitatorSettleRequest::Permit2 {
-                payment_payload,
-                payment_requirements,
-                x402_version: _,
-            } => {
-                permit2::settle_permit2_payment(
-                    &self.provider,
-                    &payment_payload,
-                    &payment_requirements,
-                )
-                .await?
-            }
-        };
-        Ok(settle_response.into())
+        settle_in_span(request, async {
+            let settle_request = FacilitatorSettleRequest::try_from(request.clone())?;
+            let settle_response = match settle_request {
+                FacilitatorSettleRequest::Eip3009 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    eip3009::settle_eip3009_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+                FacilitatorSettleRequest::Permit2 {
+                    payment_payload,
+                    payment_requirements,
+                    x402_version: _,
+                } => {
+                    permit2::settle_permit2_payment(
+                        &self.provider,
+                        &*self.clock,
+                        self.strict_accepted_match,
+                        &payment_payload,
+                        &payment_requirements,
+                    )
+                    .await?
+                }
+            };
+            Ok(settle_response.into())
+        })
+        .await
     }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
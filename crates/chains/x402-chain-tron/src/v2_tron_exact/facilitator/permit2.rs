@@ -4,7 +4,7 @@ use alloy_primitives::{Address, Bytes, U256};
 use alloy_sol_types::{SolStruct, eip712_domain, sol};
 use x402_types::proto::{PaymentVerificationError, v2};
 use x402_types::scheme::X402SchemeFacilitatorError;
-use x402_types::timestamp::UnixTimestamp;
+use x402_types::timestamp::{Clock, UnixTimestamp};
 
 use crate::chain::TronAddress;
 use crate::chain::TronChainProvider;
@@ -12,7 +12,7 @@ use crate::chain::contracts;
 use crate::chain::provider::{
     TronChainProviderError, TronChainProviderLike, TronTxId, read_allowance, read_balance_of,
 };
-use crate::v2_tron_exact::facilitator::eip3009::{assert_requirements_match, recover_address};
+use crate::v2_tron_exact::facilitator::eip3009::recover_address;
 use crate::v2_tron_exact::types::{Permit2Payload, Permit2PaymentRequirements};
 
 // Struct names are verbatim in the EIP-712 typehash — must match Permit2 exactly:
@@ -42,16 +42,18 @@ sol! {
 
 pub async fn verify_permit2_payment(
     provider: &TronChainProvider,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
     payment_payload: &v2::PaymentPayload<Permit2PaymentRequirements, Permit2Payload>,
     payment_requirements: &Permit2PaymentRequirements,
 ) -> Result<v2::VerifyResponse, X402SchemeFacilitatorError> {
     let accepted = &payment_payload.accepted;
     let sun_permit2 = provider.sun_permit2;
 
-    assert_requirements_match(accepted, payment_requirements)?;
+    accepted.assert_accepted(payment_requirements, strict_accepted_match)?;
 
     let auth = &payment_payload.payload.permit2_authorization;
-    let now = UnixTimestamp::now();
+    let now = clock.now();
     let required_amount: U256 = payment_payload.accepted.amount.into();
 
     if accepted.network != provider.chain_reference.chain_id() {
@@ -142,10 +144,19 @@ pub async fn verify_permit2_payment(
 
 pub async fn settle_permit2_payment(
     provider: &TronChainProvider,
+    clock: &dyn Clock,
+    strict_accepted_match: bool,
     payment_payload: &v2::PaymentPayload<Permit2PaymentRequirements, Permit2Payload>,
     payment_requirements: &Permit2PaymentRequirements,
 ) -> Result<v2::SettleResponse, X402SchemeFacilitatorError> {
-    verify_permit2_payment(provider, payment_payload, payment_requirements).await?;
+    verify_permit2_payment(
+        provider,
+        clock,
+        strict_accepted_match,
+        payment_payload,
+        payment_requirements,
+    )
+    .await?;
 
     let accepted = &payment_payload.accepted;
     let auth = &payment_payload.payload.permit2_authorization;
@@ -0,0 +1,282 @@
+//! Discovery index of the priced routes of an application.
+//!
+//! Agents hitting an API can learn which endpoints are payable, and at what price, from a
+//! single `GET /.well-known/x402` instead of probing every route for a 402. Layers built by
+//! [`X402Middleware`](crate::X402Middleware) register their route in a [`PricedRoutes`]
+//! registry when they declare it with [`X402LayerBuilder::with_route`](crate::X402LayerBuilder::with_route),
+//! and [`discovery_router`] serves the aggregated list.
+//!
+//! Registration happens when axum applies the layer to the route, so the index is complete
+//! as soon as the application starts serving. Registering the same method and route pattern
+//! again replaces the previous entry, so layers applied to several endpoints or rebuilt
+//! routers are listed once. Routes with dynamic pricing are not listed, as their price is
+//! only known for a given request.
+//!
+//! For nested routers, set the prefix the router is nested under with
+//! [`X402Middleware::with_route_prefix`](crate::X402Middleware::with_route_prefix), so that
+//! listed patterns match the public paths.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let api = Router::new().route(
+//!     "/weather",
+//!     get(weather).layer(
+//!         x402.with_route_prefix("/api")
+//!             .with_price_tag(price_tag)
+//!             .with_description("Current weather".to_string())
+//!             .with_route(Method::GET, "/weather"),
+//!     ),
+//! );
+//! let app = Router::new()
+//!     .nest("/api", api)
+//!     .merge(x402_axum::discovery_router());
+//! ```
+//!
+//! # Format
+//!
+//! The index mirrors the resource list of the bazaar discovery API: one item per route and
+//! protocol version, holding the payment requirements of the route's 402 challenge.
+//!
+//! ```json
+//! {
+//!   "x402Version": 2,
+//!   "resources": [{
+//!     "type": "http",
+//!     "resource": "https://api.example.com/api/weather",
+//!     "method": "GET",
+//!     "path": "/api/weather",
+//!     "x402Version": 2,
+//!     "accepts": [{ "scheme": "exact", "network": "eip155:8453", "amount": "10000", … }],
+//!     "description": "Current weather",
+//!     "extensions": { "bazaar": { … } }
+//!   }]
+//! }
+//! ```
+
+use axum::Router;
+use axum::routing::get;
+use http::Method;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use x402_types::proto::v2;
+
+use crate::paygate::PaymentRequired;
+
+/// Path of the discovery index served by [`discovery_router`].
+pub const DISCOVERY_PATH: &str = "/.well-known/x402";
+
+/// A priced route listed in the discovery index.
+#[derive(Debug, Clone)]
+pub struct PricedRoute {
+    /// HTTP method of the route.
+    pub method: Method,
+    /// Route pattern, including the prefix of nested routers.
+    pub path: String,
+    /// The protected resource.
+    pub resource: v2::ResourceInfo,
+    /// The challenge answered to unpaid requests, holding the accepted payments.
+    pub payment_required: PaymentRequired,
+}
+
+impl PricedRoute {
+    /// Returns the discovery items of the route, one per protocol version.
+    pub fn to_json(&self) -> Vec<Value> {
+        let mut items = Vec::new();
+        if let Some(v1) = &self.payment_required.v1 {
+            let mut item = self.item(1);
+            item.insert("accepts".into(), json!(v1.accepts));
+            items.push(Value::Object(item));
+        }
+        if let Some(v2) = &self.payment_required.v2 {
+            let mut item = self.item(2);
+            item.insert("accepts".into(), json!(v2.accepts));
+            if !v2.extensions.is_empty() {
+                item.insert("extensions".into(), json!(v2.extensions));
+            }
+            items.push(Value::Object(item));
+        }
+        items
+    }
+
+    fn item(&self, x402_version: u8) -> Map<String, Value> {
+        let mut item = Map::new();
+        item.insert("type".into(), "http".into());
+        item.insert("resource".into(), self.resource.url.clone().into());
+        item.insert("method".into(), self.method.as_str().into());
+        item.insert("path".into(), self.path.clone().into());
+        item.insert("x402Version".into(), x402_version.into());
+        if let Some(description) = &self.resource.description {
+            item.insert("description".into(), description.clone().into());
+        }
+        if let Some(mime_type) = &self.resource.mime_type {
+            item.insert("mimeType".into(), mime_type.clone().into());
+        }
+        item
+    }
+}
+
+/// Registry of the priced routes of an application.
+///
+/// Cloning is cheap and clones share the same routes. Layers register into
+/// [`PricedRoutes::global`] unless another registry is set with
+/// [`X402Middleware::with_priced_routes`](crate::X402Middleware::with_priced_routes).
+#[derive(Debug, Clone, Default)]
+pub struct PricedRoutes {
+    routes: Arc<RwLock<BTreeMap<(String, String), PricedRoute>>>,
+}
+
+static GLOBAL: LazyLock<PricedRoutes> = LazyLock::new(PricedRoutes::default);
+
+impl PricedRoutes {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry served by [`discovery_router`].
+    pub fn global() -> &'static PricedRoutes {
+        &GLOBAL
+    }
+
+    /// Registers a route, replacing any route with the same path and method.
+    pub fn register(&self, route: PricedRoute) {
+        let key = (route.path.clone(), route.method.to_string());
+        self.routes
+            .write()
+            .expect("priced routes lock poisoned")
+            .insert(key, route);
+    }
+
+    /// Returns the registered routes, ordered by path and method.
+    pub fn routes(&self) -> Vec<PricedRoute> {
+        self.routes
+            .read()
+            .expect("priced routes lock poisoned")
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the discovery index of the registered routes.
+    pub fn to_json(&self) -> Value {
+        let resources: Vec<Value> = self
+            .routes()
+            .iter()
+            .flat_map(PricedRoute::to_json)
+            .collect();
+        json!({ "x402Version": 2, "resources": resources })
+    }
+
+    /// Returns a router serving the discovery index of this registry at [`DISCOVERY_PATH`].
+    pub fn router<S>(&self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let routes = self.clone();
+        Router::new().route(
+            DISCOVERY_PATH,
+            get(move || {
+                let routes = routes.clone();
+                async move { axum::Json(routes.to_json()) }
+            }),
+        )
+    }
+}
+
+/// Returns a router serving the discovery index of [`PricedRoutes::global`] at
+/// `GET /.well-known/x402`, to be merged into the application router.
+pub fn discovery_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    PricedRoutes::global().router()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::X402Middleware;
+    use axum::routing::post;
+    use x402_types::proto::v2::{PaymentRequirements, PriceTag};
+
+    fn price_tag(amount: &str) -> PriceTag {
+        PriceTag {
+            requirements: PaymentRequirements {
+                scheme: "exact".into(),
+                network: "eip155:84532".parse().unwrap(),
+                amount: amount.into(),
+                pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+                max_timeout_seconds: 300,
+                asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+                extra: None,
+            },
+            enricher: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn index_lists_nested_and_top_level_routes() {
+        let routes = PricedRoutes::new();
+        let x402 = X402Middleware::new("http://127.0.0.1:1")
+            .with_base_url("https://api.example.com/".parse().unwrap())
+            .with_priced_routes(routes.clone());
+
+        let weather = x402
+            .with_route_prefix("/api")
+            .with_price_tag(price_tag("100"))
+            .with_description("Current weather".to_string())
+            .with_output_schema(json!({ "type": "object" }))
+            .with_route(Method::GET, "/weather/{city}");
+        let api = Router::new().route("/weather/{city}", get(|| async { "sunny" }).layer(weather));
+        let report = x402
+            .with_price_tag(price_tag("2500"))
+            .with_route(Method::POST, "/report");
+        // Applied to both endpoints, the layer registers its route twice.
+        let app = Router::new()
+            .nest("/api", api)
+            .route(
+                "/report",
+                post(|| async { "report" })
+                    .get(|| async { "report" })
+                    .layer(report),
+            )
+            .merge(routes.router());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let index: Value = reqwest::get(format!("http://{addr}{DISCOVERY_PATH}"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let resources = index["resources"].as_array().unwrap();
+        assert_eq!(index["x402Version"], 2);
+        assert_eq!(resources.len(), 2);
+
+        let weather = &resources[0];
+        assert_eq!(weather["path"], "/api/weather/{city}");
+        assert_eq!(weather["method"], "GET");
+        assert_eq!(
+            weather["resource"],
+            "https://api.example.com/api/weather/{city}"
+        );
+        assert_eq!(weather["description"], "Current weather");
+        assert_eq!(weather["accepts"][0]["amount"], "100");
+        assert_eq!(
+            weather["extensions"]["bazaar"]["info"]["output"],
+            json!({ "type": "object" })
+        );
+
+        let report = &resources[1];
+        assert_eq!(report["path"], "/report");
+        assert_eq!(report["method"], "POST");
+        assert_eq!(report["x402Version"], 2);
+        assert_eq!(report["accepts"][0]["amount"], "2500");
+        assert!(report.get("extensions").is_none());
+    }
+}
@@ -16,7 +16,39 @@
 //!
 //! - Uses `reqwest` for async HTTP requests
 //! - Supports optional timeout and headers
-//! - Integrates with `tracing` if the `telemetry` feature is enabled
+//! - Optionally retries `/verify` and `/settle` on transient failures, see [`FacilitatorClient::with_retry_policy`]
+//! - Optionally fails fast while the facilitator is down, see [`FacilitatorClient::with_circuit_breaker`]
+//! - Optionally fails over to fallback facilitators, see [`FacilitatorClient::with_fallback`]
+//! - Forwards the payment correlation ID (`X-Payment-Id`) on `/verify` and `/settle`, see [`with_payment_id`]
+//! - Lists the payment kinds a client can pay with, see [`FacilitatorClient::supported_for_client`]
+//! - Reuses successful `/verify` results for as long as the facilitator allows via `X-Verify-TTL`, see [`VerifyCache`]
+//! - Integrates with `tracing` if the `telemetry` feature is enabled, propagating the W3C trace context
+//!   (`traceparent`, `tracestate`) and the payment ID as `x402.payment_id` baggage to the facilitator
+//!
+//! ## Defaults
+//!
+//! Resilience features are opt-in, so that a client behaves the same against any facilitator:
+//!
+//! - No timeout beyond `reqwest`'s defaults; set one with [`FacilitatorClient::with_timeout`].
+//! - No retries; enable them with [`FacilitatorClient::with_retry_policy`]. `/verify` is
+//!   idempotent and always retried under the policy. `/settle` is only retried when an
+//!   [`IDEMPOTENCY_KEY_HEADER`] is attached with [`with_idempotency_key`], which the
+//!   middleware does with the payment ID, so that the facilitator can deduplicate
+//!   settlements that reached it before the connection failed.
+//! - No circuit breaker; enable it with [`FacilitatorClient::with_circuit_breaker`].
+//! - No fallback facilitators; add them with [`FacilitatorClient::with_fallback_url`].
+//!
+//! ## Failover
+//!
+//! With fallbacks, `/verify` and `/settle` go to the first healthy facilitator, in the order
+//! they were added, and fail over to the next one on connection errors, `5xx` responses
+//! and open circuits. A facilitator failing this way is considered unhealthy for
+//! [`FacilitatorClient::DEFAULT_FAILOVER_COOLDOWN`], during which it is only tried once
+//! all others failed. A `/settle` request is not failed over once the facilitator may
+//! have received it, e.g. on a timeout, so that a payment is never settled twice.
+//!
+//! `/supported` is fetched from every healthy facilitator, and only the payment kinds and
+//! extensions all of them support are advertised.
 //!
 //! ## Error Handling
 //!
@@ -28,18 +60,28 @@
 //!
 
 use http::{HeaderMap, StatusCode};
+use lru::LruCache;
 use reqwest::Client;
+use std::collections::hash_map::RandomState;
 use std::fmt::Display;
-use std::time::Duration;
-use tokio::sync::RwLock;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OnceCell, RwLock};
 use url::Url;
-use x402_types::facilitator::Facilitator;
+use x402_types::facilitator::{Facilitator, VERIFY_TTL_HEADER};
 use x402_types::proto::{
-    SettleRequest, SettleResponse, SupportedResponse, VerifyRequest, VerifyResponse,
+    ClientCapabilities, PaymentId, SettleRequest, SettleResponse, SupportedResponse, VerifyRequest,
+    VerifyResponse,
 };
 
 #[cfg(feature = "telemetry")]
 use tracing::{Instrument, Span, instrument};
+#[cfg(feature = "telemetry")]
+use x402_types::facilitator::PAYMENT_ID_BAGGAGE_KEY;
 
 /// TTL cache for [`SupportedResponse`].
 #[derive(Clone, Debug)]
@@ -103,6 +145,343 @@ impl Clone for SupportedCache {
     }
 }
 
+/// A `/verify` response together with the time it may be reused until.
+#[derive(Clone, Debug)]
+struct CachedVerify {
+    response: VerifyResponse,
+    expires_at: std::time::Instant,
+}
+
+/// In-process cache of successful `/verify` responses.
+///
+/// Entries are keyed on a hash of the verify request (payment payload and requirements)
+/// and live for the TTL the facilitator advertises in the `X-Verify-TTL` header. Responses
+/// without it are not reused, but concurrent identical requests still share a single
+/// facilitator call.
+///
+/// Clones share the same cache.
+#[derive(Clone, Debug)]
+pub struct VerifyCache {
+    /// Hashes verify requests into cache keys
+    hasher: RandomState,
+    /// In-flight and completed verifications, least recently used evicted first
+    entries: Arc<Mutex<LruCache<u64, Arc<OnceCell<CachedVerify>>>>>,
+}
+
+impl VerifyCache {
+    /// Default maximum number of cached verifications.
+    pub const DEFAULT_CAPACITY: NonZeroUsize = NonZeroUsize::new(1024).unwrap();
+
+    /// Creates a cache holding at most `capacity` verifications.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            hasher: RandomState::new(),
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Returns the cached response for `request`, or runs `verify` and caches its
+    /// response for the returned TTL if the payment is valid.
+    async fn get_or_verify<F, Fut>(
+        &self,
+        request: &VerifyRequest,
+        verify: F,
+    ) -> Result<VerifyResponse, FacilitatorClientError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(VerifyResponse, Option<Duration>), FacilitatorClientError>>,
+    {
+        let key = self.hasher.hash_one(request.as_str());
+        let cell = {
+            let mut entries = self.entries.lock().expect("verify cache lock poisoned");
+            let now = std::time::Instant::now();
+            match entries.get(&key) {
+                // In flight, or completed and still fresh
+                Some(cell) if cell.get().is_none_or(|cached| now < cached.expires_at) => {
+                    cell.clone()
+                }
+                _ => {
+                    let cell = Arc::new(OnceCell::new());
+                    entries.put(key, cell.clone());
+                    cell
+                }
+            }
+        };
+        let cached = cell
+            .get_or_try_init(|| async {
+                let (response, ttl) = verify().await?;
+                let is_valid = response.0.get("isValid") == Some(&serde_json::Value::Bool(true));
+                let ttl = ttl.filter(|_| is_valid).unwrap_or_default();
+                Ok::<_, FacilitatorClientError>(CachedVerify {
+                    response,
+                    expires_at: std::time::Instant::now() + ttl,
+                })
+            })
+            .await?;
+        Ok(cached.response.clone())
+    }
+
+    /// Clears the cache.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .expect("verify cache lock poisoned")
+            .clear();
+    }
+}
+
+impl Default for VerifyCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// Retry policy for `/verify` and `/settle` requests.
+///
+/// Transient failures (transport errors and `5xx` responses) are retried with
+/// exponential backoff and ±20% jitter. Other failures, including `4xx` responses,
+/// are returned immediately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u8,
+    /// Backoff before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the backoff, before jitter.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+
+    /// Returns the backoff to wait before retry number `retry` (starting at 1).
+    pub fn backoff(&self, retry: u8) -> Duration {
+        let exponent = u32::from(retry.saturating_sub(1)).min(31);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_backoff);
+        // Jitter factor in [0.8, 1.2)
+        let random = RandomState::new().build_hasher().finish();
+        let jitter = 0.8 + 0.4 * (random as f64 / u64::MAX as f64);
+        backoff.mul_f64(jitter)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// State of a [`CircuitBreaker`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through.
+    Closed,
+    /// Requests fail fast until the cooldown elapses.
+    Open,
+    /// The cooldown elapsed: a single trial request goes through, and its outcome
+    /// closes or reopens the circuit.
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreakerState {
+    /// Transient failures since the last success
+    consecutive_failures: u32,
+    /// When the circuit last opened, if it is not closed
+    opened_at: Option<Instant>,
+    /// When the trial request of the half-open circuit started, if one is in flight
+    trial_started_at: Option<Instant>,
+}
+
+/// Circuit breaker failing facilitator requests fast while the facilitator is down.
+///
+/// After `failure_threshold` consecutive transient failures (transport errors and `5xx`
+/// responses, after retries), the circuit opens and requests fail with
+/// [`FacilitatorClientError::CircuitOpen`] without reaching the facilitator. Once
+/// `cooldown` elapses, one trial request is let through: the circuit closes if it
+/// succeeds and opens for another cooldown otherwise.
+///
+/// Clones share the same state.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit
+    failure_threshold: u32,
+    /// How long the circuit stays open
+    cooldown: Duration,
+    /// Shared breaker state
+    state: Arc<Mutex<CircuitBreakerState>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Arc::new(Mutex::new(CircuitBreakerState::default())),
+        }
+    }
+
+    /// Returns the current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        let state = self.state.lock().expect("circuit breaker lock poisoned");
+        match state.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Admits a request, or returns how long until the circuit lets one through.
+    fn acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+        let open_for = opened_at.elapsed();
+        if open_for < self.cooldown {
+            return Err(self.cooldown - open_for);
+        }
+        // A trial abandoned for a whole cooldown no longer blocks the next one
+        let trial_in_flight = state
+            .trial_started_at
+            .is_some_and(|started_at| started_at.elapsed() < self.cooldown);
+        if trial_in_flight {
+            return Err(Duration::ZERO);
+        }
+        #[cfg(feature = "telemetry")]
+        tracing::info!("Facilitator circuit breaker half-open, sending trial request");
+        state.trial_started_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Records the outcome of an admitted request.
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        if success {
+            if state.opened_at.is_some() {
+                #[cfg(feature = "telemetry")]
+                tracing::info!("Facilitator circuit breaker closed");
+            }
+            *state = CircuitBreakerState::default();
+            return;
+        }
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        let trial_failed = state.opened_at.is_some();
+        if trial_failed || state.consecutive_failures >= self.failure_threshold {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!(
+                consecutive_failures = state.consecutive_failures,
+                cooldown_ms = self.cooldown.as_millis() as u64,
+                "Facilitator circuit breaker opened"
+            );
+            state.opened_at = Some(Instant::now());
+            state.trial_started_at = None;
+        }
+    }
+}
+
+/// Health of a facilitator, shared by the clones of its client.
+///
+/// Holds the time of the last failure that made a request fail over, if the facilitator
+/// has not succeeded since.
+#[derive(Clone, Debug, Default)]
+struct FacilitatorHealth(Arc<Mutex<Option<Instant>>>);
+
+impl FacilitatorHealth {
+    /// Whether the facilitator has not failed within the last `cooldown`.
+    fn is_healthy(&self, cooldown: Duration) -> bool {
+        let failed_at = self.0.lock().expect("facilitator health lock poisoned");
+        failed_at.is_none_or(|failed_at| failed_at.elapsed() >= cooldown)
+    }
+
+    /// Records the outcome of a request to the facilitator.
+    fn record(&self, healthy: bool) {
+        let mut failed_at = self.0.lock().expect("facilitator health lock poisoned");
+        *failed_at = (!healthy).then(Instant::now);
+    }
+}
+
+tokio::task_local! {
+    static FACILITATOR_RETRIES: FacilitatorRetries;
+    static PAYMENT_ID: Option<PaymentId>;
+    static IDEMPOTENCY_KEY: Option<String>;
+}
+
+/// Header carrying the idempotency key of a `/settle` request.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Runs `fut`, forwarding `payment_id` in the `X-Payment-Id` header of every
+/// `/verify` and `/settle` request a [`FacilitatorClient`] makes inside it.
+pub async fn with_payment_id<F: Future>(payment_id: Option<PaymentId>, fut: F) -> F::Output {
+    PAYMENT_ID.scope(payment_id, fut).await
+}
+
+/// Runs `fut`, attaching `key` in the [`IDEMPOTENCY_KEY_HEADER`] of every `/settle`
+/// request a [`FacilitatorClient`] makes inside it.
+///
+/// Settlements are only retried under the [`RetryPolicy`] when a key is attached.
+pub async fn with_idempotency_key<F: Future>(key: Option<String>, fut: F) -> F::Output {
+    IDEMPOTENCY_KEY.scope(key, fut).await
+}
+
+/// Counter of facilitator retries made while handling a single request.
+///
+/// The middleware inserts it into both the request and the response extensions.
+/// Read it with [`FacilitatorRetryCountExt::facilitator_retry_count`].
+#[derive(Clone, Debug, Default)]
+pub struct FacilitatorRetries(Arc<AtomicU8>);
+
+impl FacilitatorRetries {
+    /// Returns the number of retries recorded so far.
+    pub fn count(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Runs `fut`, recording retries made by any [`FacilitatorClient`] inside it.
+    pub async fn scope<F: Future>(&self, fut: F) -> F::Output {
+        FACILITATOR_RETRIES.scope(self.clone(), fut).await
+    }
+
+    fn record_retry() {
+        let _ = FACILITATOR_RETRIES.try_with(|retries| {
+            let _ = retries
+                .0
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_add(1));
+        });
+    }
+}
+
+/// Exposes the facilitator retry count stored in HTTP message extensions.
+pub trait FacilitatorRetryCountExt {
+    /// Number of facilitator retries made for this request, `0` if none were recorded.
+    fn facilitator_retry_count(&self) -> u8;
+}
+
+impl<B> FacilitatorRetryCountExt for http::Request<B> {
+    fn facilitator_retry_count(&self) -> u8 {
+        self.extensions()
+            .get::<FacilitatorRetries>()
+            .map_or(0, FacilitatorRetries::count)
+    }
+}
+
+impl<B> FacilitatorRetryCountExt for http::Response<B> {
+    fn facilitator_retry_count(&self) -> u8 {
+        self.extensions()
+            .get::<FacilitatorRetries>()
+            .map_or(0, FacilitatorRetries::count)
+    }
+}
+
 /// A client for communicating with a remote x402 facilitator.
 ///
 /// Handles `/verify`, `/settle`, and `/supported` endpoints via JSON HTTP.
@@ -124,6 +503,18 @@ pub struct FacilitatorClient {
     timeout: Option<Duration>,
     /// Cache for the supported endpoint response
     supported_cache: SupportedCache,
+    /// Retry policy for `/verify` and `/settle` requests
+    retry_policy: RetryPolicy,
+    /// Cache for successful `/verify` responses, if enabled
+    verify_cache: Option<VerifyCache>,
+    /// Circuit breaker for all requests, if enabled
+    circuit_breaker: Option<CircuitBreaker>,
+    /// Facilitators to fail over to, in order
+    fallbacks: Vec<FacilitatorClient>,
+    /// Health of this facilitator, for failover
+    health: FacilitatorHealth,
+    /// How long a failed facilitator is tried after the others
+    failover_cooldown: Duration,
 }
 
 impl Facilitator for FacilitatorClient {
@@ -214,12 +605,59 @@ pub enum FacilitatorClientError {
         #[source]
         source: reqwest::Error,
     },
+    #[error("Facilitator circuit breaker open: {context}: retry in {retry_in:?}")]
+    CircuitOpen {
+        context: &'static str,
+        retry_in: Duration,
+    },
+}
+
+impl FacilitatorClientError {
+    /// Whether the error is transient and the request may succeed if retried.
+    ///
+    /// Transport errors and `5xx` responses are transient; everything else is not.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FacilitatorClientError::Http { .. } => true,
+            FacilitatorClientError::HttpStatus { status, .. } => status.is_server_error(),
+            FacilitatorClientError::UrlParse { .. }
+            | FacilitatorClientError::JsonDeserialization { .. }
+            | FacilitatorClientError::ResponseBodyRead { .. }
+            | FacilitatorClientError::CircuitOpen { .. } => false,
+        }
+    }
+
+    /// Whether the request should be sent to a fallback facilitator.
+    ///
+    /// Transient errors and open circuits fail over.
+    pub fn should_fail_over(&self) -> bool {
+        self.is_transient() || matches!(self, FacilitatorClientError::CircuitOpen { .. })
+    }
+
+    /// Whether the facilitator may have processed the request despite the error.
+    ///
+    /// Transport errors other than connection failures, such as timeouts, may occur after
+    /// the request was received, and unreadable responses follow a processed request.
+    /// Connection failures, error statuses and open circuits mean it was not processed.
+    pub fn may_have_been_processed(&self) -> bool {
+        match self {
+            FacilitatorClientError::Http { source, .. } => !source.is_connect(),
+            FacilitatorClientError::JsonDeserialization { .. }
+            | FacilitatorClientError::ResponseBodyRead { .. } => true,
+            FacilitatorClientError::UrlParse { .. }
+            | FacilitatorClientError::HttpStatus { .. }
+            | FacilitatorClientError::CircuitOpen { .. } => false,
+        }
+    }
 }
 
 impl FacilitatorClient {
     /// Default TTL for caching the supported endpoint response (10 minutes).
     pub const DEFAULT_SUPPORTED_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
 
+    /// Default time a failed facilitator is tried after the others (30 seconds).
+    pub const DEFAULT_FAILOVER_COOLDOWN: Duration = Duration::from_secs(30);
+
     /// Returns the base URL used by this client.
     pub fn base_url(&self) -> &Url {
         &self.base_url
@@ -250,11 +688,36 @@ impl FacilitatorClient {
         &self.timeout
     }
 
+    /// Returns the retry policy for `/verify` and `/settle` requests.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
     /// Returns a reference to the supported cache.
     pub fn supported_cache(&self) -> &SupportedCache {
         &self.supported_cache
     }
 
+    /// Returns a reference to the verify cache, if enabled.
+    pub fn verify_cache(&self) -> Option<&VerifyCache> {
+        self.verify_cache.as_ref()
+    }
+
+    /// Returns a reference to the circuit breaker, if enabled.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_ref()
+    }
+
+    /// Returns the facilitators failed over to, in order.
+    pub fn fallbacks(&self) -> &[FacilitatorClient] {
+        &self.fallbacks
+    }
+
+    /// Returns whether this facilitator has not failed within the failover cooldown.
+    pub fn is_healthy(&self) -> bool {
+        self.health.is_healthy(self.failover_cooldown)
+    }
+
     /// Constructs a new [`FacilitatorClient`] from a base URL.
     ///
     /// This sets up `./verify`, `./settle`, and `./supported` endpoint URLs relative to the base.
@@ -290,10 +753,27 @@ impl FacilitatorClient {
             headers: HeaderMap::new(),
             timeout: None,
             supported_cache: SupportedCache::new(Self::DEFAULT_SUPPORTED_CACHE_TTL),
+            retry_policy: RetryPolicy::NONE,
+            verify_cache: Some(VerifyCache::default()),
+            circuit_breaker: None,
+            fallbacks: Vec::new(),
+            health: FacilitatorHealth::default(),
+            failover_cooldown: Self::DEFAULT_FAILOVER_COOLDOWN,
         })
     }
 
+    /// Applies a setting to this client and its fallbacks.
+    fn with_endpoints(&self, apply: impl Fn(&mut FacilitatorClient)) -> Self {
+        let mut this = self.clone();
+        apply(&mut this);
+        this.fallbacks.iter_mut().for_each(apply);
+        this
+    }
+
     /// Attaches custom headers to all future requests.
+    ///
+    /// Unlike other settings, headers are not applied to fallbacks, which usually need
+    /// other credentials.
     pub fn with_headers(&self, headers: HeaderMap) -> Self {
         let mut this = self.clone();
         this.headers = headers;
@@ -302,18 +782,90 @@ impl FacilitatorClient {
 
     /// Sets a timeout for all future requests.
     pub fn with_timeout(&self, timeout: Duration) -> Self {
+        self.with_endpoints(|client| client.timeout = Some(timeout))
+    }
+
+    /// Adds a facilitator to fail over to, after the ones already added.
+    ///
+    /// The fallback keeps its own configuration, and its own fallbacks are appended
+    /// after it. Settings applied to this client afterwards, except headers, apply to
+    /// the fallback too. See the [module documentation](self#failover) for the
+    /// failover rules.
+    pub fn with_fallback(&self, fallback: FacilitatorClient) -> Self {
+        let mut this = self.clone();
+        let mut fallback = fallback;
+        let nested = std::mem::take(&mut fallback.fallbacks);
+        this.fallbacks.push(fallback);
+        this.fallbacks.extend(nested);
+        this
+    }
+
+    /// Adds a facilitator to fail over to by URL, see [`Self::with_fallback`].
+    ///
+    /// The fallback gets this client's timeout, retry policy, circuit breaker settings
+    /// and supported cache TTL, but not its headers.
+    pub fn with_fallback_url(&self, url: &str) -> Result<Self, FacilitatorClientError> {
+        let mut fallback = FacilitatorClient::try_from(url)?;
+        fallback.timeout = self.timeout;
+        fallback.retry_policy = self.retry_policy;
+        fallback.circuit_breaker = self
+            .circuit_breaker
+            .as_ref()
+            .map(|breaker| CircuitBreaker::new(breaker.failure_threshold, breaker.cooldown));
+        fallback.supported_cache = self.supported_cache.clone();
+        Ok(self.with_fallback(fallback))
+    }
+
+    /// Sets how long a facilitator is tried after the others once it failed.
+    ///
+    /// Default is [`Self::DEFAULT_FAILOVER_COOLDOWN`].
+    pub fn with_failover_cooldown(&self, cooldown: Duration) -> Self {
         let mut this = self.clone();
-        this.timeout = Some(timeout);
+        this.failover_cooldown = cooldown;
         this
     }
 
+    /// Retries `/verify` and `/settle` requests on transient failures.
+    ///
+    /// Transport errors and `5xx` responses are retried up to `max_attempts` total attempts,
+    /// waiting `initial_backoff` before the first retry and doubling it on each subsequent
+    /// one, capped at `max_backoff`, with ±20% jitter. `4xx` responses are never retried.
+    /// `/settle` requests are only retried with an idempotency key attached, see
+    /// [`with_idempotency_key`].
+    ///
+    /// By default, no retries are made.
+    pub fn with_retry_policy(
+        &self,
+        max_attempts: u8,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        let retry_policy = RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+        };
+        self.with_endpoints(|client| client.retry_policy = retry_policy)
+    }
+
+    /// Fails requests fast for `cooldown` after `failure_threshold` consecutive transient
+    /// failures, see [`CircuitBreaker`].
+    ///
+    /// By default, there is no circuit breaker. `5` failures and a `30s` cooldown are
+    /// reasonable starting values.
+    ///
+    /// Each fallback gets a breaker of its own.
+    pub fn with_circuit_breaker(&self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.with_endpoints(|client| {
+            client.circuit_breaker = Some(CircuitBreaker::new(failure_threshold, cooldown));
+        })
+    }
+
     /// Sets the TTL for caching the supported endpoint response.
     ///
     /// Default is 10 minutes. Use [`Self::without_supported_cache()`] to disable caching.
     pub fn with_supported_cache_ttl(&self, ttl: Duration) -> Self {
-        let mut this = self.clone();
-        this.supported_cache = SupportedCache::new(ttl);
-        this
+        self.with_endpoints(|client| client.supported_cache = SupportedCache::new(ttl))
     }
 
     /// Disables caching for the supported endpoint.
@@ -321,22 +873,114 @@ impl FacilitatorClient {
         self.with_supported_cache_ttl(Duration::ZERO)
     }
 
+    /// Caches at most `capacity` successful `/verify` responses.
+    ///
+    /// Responses are reused only for as long as the facilitator allows through the
+    /// `X-Verify-TTL` header. Enabled by default with [`VerifyCache::DEFAULT_CAPACITY`].
+    pub fn with_verify_cache_capacity(&self, capacity: NonZeroUsize) -> Self {
+        let mut this = self.clone();
+        this.verify_cache = Some(VerifyCache::new(capacity));
+        this
+    }
+
+    /// Disables caching for the verify endpoint.
+    pub fn without_verify_cache(&self) -> Self {
+        let mut this = self.clone();
+        this.verify_cache = None;
+        this
+    }
+
     /// Sends a `POST /verify` request to the facilitator.
+    ///
+    /// Successful results are served from the [`VerifyCache`] while the TTL advertised
+    /// by the facilitator lasts.
     pub async fn verify(
         &self,
         request: &VerifyRequest,
     ) -> Result<VerifyResponse, FacilitatorClientError> {
-        self.post_json(&self.verify_url, "POST /verify", request)
-            .await
+        match &self.verify_cache {
+            Some(verify_cache) => {
+                verify_cache
+                    .get_or_verify(request, || self.verify_with_failover(request))
+                    .await
+            }
+            None => self
+                .verify_with_failover(request)
+                .await
+                .map(|(response, _)| response),
+        }
+    }
+
+    /// Sends a `POST /verify` request to the first facilitator that answers it.
+    async fn verify_with_failover(
+        &self,
+        request: &VerifyRequest,
+    ) -> Result<(VerifyResponse, Option<Duration>), FacilitatorClientError> {
+        self.with_failover(FacilitatorClientError::should_fail_over, |facilitator| {
+            facilitator.verify_inner(request)
+        })
+        .await
+    }
+
+    /// Sends a `POST /verify` request to the facilitator, bypassing the cache.
+    ///
+    /// Returns the response with the TTL from the `X-Verify-TTL` header, if any.
+    async fn verify_inner(
+        &self,
+        request: &VerifyRequest,
+    ) -> Result<(VerifyResponse, Option<Duration>), FacilitatorClientError> {
+        let (response, headers) = self
+            .post_json(
+                &self.verify_url,
+                "POST /verify",
+                request,
+                self.retry_policy,
+                None,
+            )
+            .await?;
+        let ttl = headers
+            .get(VERIFY_TTL_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs);
+        Ok((response, ttl))
     }
 
     /// Sends a `POST /settle` request to the facilitator.
+    ///
+    /// The request is only retried if an idempotency key is attached, see [`with_idempotency_key`],
+    /// and only failed over if the facilitator cannot have processed it.
     pub async fn settle(
         &self,
         request: &SettleRequest,
     ) -> Result<SettleResponse, FacilitatorClientError> {
-        self.post_json(&self.settle_url, "POST /settle", request)
-            .await
+        self.with_failover(
+            |err| err.should_fail_over() && !err.may_have_been_processed(),
+            |facilitator| facilitator.settle_inner(request),
+        )
+        .await
+    }
+
+    /// Sends a `POST /settle` request to this facilitator only.
+    async fn settle_inner(
+        &self,
+        request: &SettleRequest,
+    ) -> Result<SettleResponse, FacilitatorClientError> {
+        let idempotency_key = IDEMPOTENCY_KEY.try_with(Option::clone).ok().flatten();
+        let retry_policy = match idempotency_key {
+            Some(_) => self.retry_policy,
+            None => RetryPolicy::NONE,
+        };
+        let header = idempotency_key.map(|key| (IDEMPOTENCY_KEY_HEADER, key));
+        self.post_json(
+            &self.settle_url,
+            "POST /settle",
+            request,
+            retry_policy,
+            header.as_ref(),
+        )
+        .await
+        .map(|(response, _)| response)
     }
 
     /// Sends a `GET /supported` request to the facilitator.
@@ -346,13 +990,80 @@ impl FacilitatorClient {
         instrument(name = "x402.facilitator_client.supported", skip_all, err)
     )]
     async fn supported_inner(&self) -> Result<SupportedResponse, FacilitatorClientError> {
-        self.get_json(&self.supported_url, "GET /supported").await
+        self.guarded(
+            "GET /supported",
+            self.get_json(&self.supported_url, "GET /supported"),
+        )
+        .await
     }
 
-    /// Sends a `GET /supported` request to the facilitator.
+    /// Sends a `GET /supported` request to the facilitator and its healthy fallbacks,
+    /// keeping what all of them support, see [`intersect_supported`].
+    ///
+    /// Results are cached per facilitator with a configurable TTL (default: 10 minutes).
+    pub async fn supported(&self) -> Result<SupportedResponse, FacilitatorClientError> {
+        if self.fallbacks.is_empty() {
+            return self.supported_cached().await;
+        }
+        let mut responses = Vec::new();
+        let mut first_err = None;
+        for facilitator in self.failover_order(false) {
+            match facilitator.supported_cached().await {
+                Ok(response) => responses.push(response),
+                Err(err) => {
+                    if err.should_fail_over() {
+                        facilitator.health.record(false);
+                    }
+                    #[cfg(feature = "telemetry")]
+                    tracing::warn!(
+                        facilitator = %facilitator.base_url,
+                        error = %err,
+                        "Failed to fetch supported payment kinds of facilitator"
+                    );
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        match intersect_supported(responses) {
+            Some(response) => Ok(response),
+            None => Err(first_err.expect("at least one facilitator was queried")),
+        }
+    }
+
+    /// Sends a `POST /supported` request to the facilitator, listing only the payment kinds a
+    /// client with `capabilities` can pay with, see [`SupportedResponse::for_client`].
+    ///
+    /// Responses depend on the capabilities, and are not cached.
+    #[cfg_attr(
+        feature = "telemetry",
+        instrument(name = "x402.facilitator_client.supported_for_client", skip_all, err)
+    )]
+    pub async fn supported_for_client(
+        &self,
+        capabilities: ClientCapabilities,
+    ) -> Result<SupportedResponse, FacilitatorClientError> {
+        self.with_failover(FacilitatorClientError::should_fail_over, |facilitator| {
+            let capabilities = &capabilities;
+            async move {
+                facilitator
+                    .post_json(
+                        &facilitator.supported_url,
+                        "POST /supported",
+                        capabilities,
+                        self.retry_policy,
+                        None,
+                    )
+                    .await
+                    .map(|(response, _)| response)
+            }
+        })
+        .await
+    }
+
+    /// Sends a `GET /supported` request to this facilitator only.
     /// Results are cached with a configurable TTL (default: 10 minutes).
     /// Use [`Self::supported_inner()`] to bypass the cache.
-    pub async fn supported(&self) -> Result<SupportedResponse, FacilitatorClientError> {
+    async fn supported_cached(&self) -> Result<SupportedResponse, FacilitatorClientError> {
         // Try to get from cache
         if let Some(response) = self.supported_cache.get().await {
             return Ok(response);
@@ -368,16 +1079,127 @@ impl FacilitatorClient {
         Ok(response)
     }
 
-    /// Generic POST helper that handles JSON serialization, error mapping,
-    /// timeout application, and telemetry integration.
+    /// Returns this facilitator and its fallbacks in the order to try them.
+    ///
+    /// Healthy facilitators come first, in the order they were added. Unhealthy ones
+    /// follow if `include_unhealthy`, and are always included if none is healthy.
+    fn failover_order(&self, include_unhealthy: bool) -> Vec<&FacilitatorClient> {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = std::iter::once(self)
+            .chain(&self.fallbacks)
+            .partition(|facilitator| facilitator.health.is_healthy(self.failover_cooldown));
+        if healthy.is_empty() {
+            return unhealthy;
+        }
+        if include_unhealthy {
+            return healthy.into_iter().chain(unhealthy).collect();
+        }
+        healthy
+    }
+
+    /// Sends a request to this facilitator or its fallbacks, in failover order, until one
+    /// answers or fails with an error that `can_fail_over` rejects.
+    async fn with_failover<'a, R, Fut>(
+        &'a self,
+        can_fail_over: impl Fn(&FacilitatorClientError) -> bool,
+        request: impl Fn(&'a FacilitatorClient) -> Fut,
+    ) -> Result<R, FacilitatorClientError>
+    where
+        Fut: Future<Output = Result<R, FacilitatorClientError>> + 'a,
+    {
+        let mut last_err = None;
+        for facilitator in self.failover_order(true) {
+            let err = match request(facilitator).await {
+                Ok(response) => {
+                    facilitator.health.record(true);
+                    return Ok(response);
+                }
+                Err(err) => err,
+            };
+            facilitator.health.record(!err.should_fail_over());
+            if !can_fail_over(&err) {
+                return Err(err);
+            }
+            #[cfg(feature = "telemetry")]
+            if !self.fallbacks.is_empty() {
+                tracing::warn!(
+                    facilitator = %facilitator.base_url,
+                    error = %err,
+                    "Facilitator failed, failing over"
+                );
+            }
+            last_err = Some(err);
+        }
+        Err(last_err.expect("at least one facilitator was tried"))
+    }
+
+    /// Runs a request through the circuit breaker, if enabled.
+    async fn guarded<R>(
+        &self,
+        context: &'static str,
+        request: impl Future<Output = Result<R, FacilitatorClientError>>,
+    ) -> Result<R, FacilitatorClientError> {
+        let Some(circuit_breaker) = &self.circuit_breaker else {
+            return request.await;
+        };
+        circuit_breaker
+            .acquire()
+            .map_err(|retry_in| FacilitatorClientError::CircuitOpen { context, retry_in })?;
+        let result = request.await;
+        circuit_breaker.record(!matches!(&result, Err(err) if err.is_transient()));
+        result
+    }
+
+    /// Generic POST helper that retries transient failures according to `retry_policy`,
+    /// through the circuit breaker. Returns the response along with its headers.
     ///
     /// `context` is a human-readable identifier used in tracing and error messages (e.g. `"POST /verify"`).
+    /// `header` is an extra header sent with the request.
     async fn post_json<T, R>(
         &self,
         url: &Url,
         context: &'static str,
         payload: &T,
-    ) -> Result<R, FacilitatorClientError>
+        retry_policy: RetryPolicy,
+        header: Option<&(&'static str, String)>,
+    ) -> Result<(R, HeaderMap), FacilitatorClientError>
+    where
+        T: serde::Serialize + ?Sized,
+        R: serde::de::DeserializeOwned,
+    {
+        self.guarded(context, async {
+            let mut attempt = 1;
+            loop {
+                let result = self.post_json_once(url, context, payload, header).await;
+                match result {
+                    Err(err) if err.is_transient() && attempt < retry_policy.max_attempts => {
+                        let backoff = retry_policy.backoff(attempt);
+                        #[cfg(feature = "telemetry")]
+                        tracing::warn!(
+                            error = %err,
+                            attempt,
+                            backoff_ms = backoff.as_millis() as u64,
+                            "Transient facilitator failure, retrying"
+                        );
+                        tokio::time::sleep(backoff).await;
+                        FacilitatorRetries::record_retry();
+                        attempt += 1;
+                    }
+                    result => return result,
+                }
+            }
+        })
+        .await
+    }
+
+    /// Sends a single POST request, handling JSON serialization, error mapping,
+    /// timeout application, and telemetry integration.
+    async fn post_json_once<T, R>(
+        &self,
+        url: &Url,
+        context: &'static str,
+        payload: &T,
+        header: Option<&(&'static str, String)>,
+    ) -> Result<(R, HeaderMap), FacilitatorClientError>
     where
         T: serde::Serialize + ?Sized,
         R: serde::de::DeserializeOwned,
@@ -386,6 +1208,17 @@ impl FacilitatorClient {
         for (key, value) in self.headers.iter() {
             req = req.header(key, value);
         }
+        if let Some((name, value)) = header {
+            req = req.header(*name, value);
+        }
+        let payment_id = PAYMENT_ID.try_with(Option::clone).ok().flatten();
+        if let Some(payment_id) = &payment_id {
+            req = req.header(PaymentId::HEADER_NAME, payment_id.as_str());
+        }
+        #[cfg(feature = "telemetry")]
+        {
+            req = inject_trace_context(req, payment_id.as_ref());
+        }
         if let Some(timeout) = self.timeout {
             req = req.timeout(timeout);
         }
@@ -399,9 +1232,11 @@ impl FacilitatorClient {
             || status == StatusCode::PRECONDITION_FAILED
             || status == StatusCode::BAD_REQUEST;
         let result = if can_deserialize {
+            let headers = http_response.headers().clone();
             http_response
                 .json::<R>()
                 .await
+                .map(|response| (response, headers))
                 .map_err(|e| FacilitatorClientError::JsonDeserialization { context, source: e })
         } else {
             let status = http_response.status();
@@ -469,6 +1304,40 @@ impl FacilitatorClient {
     }
 }
 
+/// Intersects the `/supported` responses of several facilitators.
+///
+/// Keeps the payment kinds and extensions of the first response that every other response
+/// supports too, and merges the signers. Payment kinds match on version, scheme and
+/// network; their `extra`, such as a Solana fee payer, is that of the first response.
+/// Returns `None` if there is no response.
+pub fn intersect_supported(
+    responses: impl IntoIterator<Item = SupportedResponse>,
+) -> Option<SupportedResponse> {
+    let mut responses = responses.into_iter();
+    let mut intersection = responses.next()?;
+    for response in responses {
+        intersection.kinds.retain(|kind| {
+            response.kinds.iter().any(|other| {
+                other.x402_version == kind.x402_version
+                    && other.scheme == kind.scheme
+                    && other.network == kind.network
+            })
+        });
+        intersection
+            .extensions
+            .retain(|extension| response.extensions.contains(extension));
+        for (chain_id, signers) in response.signers {
+            let merged = intersection.signers.entry(chain_id).or_default();
+            for signer in signers {
+                if !merged.contains(&signer) {
+                    merged.push(signer);
+                }
+            }
+        }
+    }
+    Some(intersection)
+}
+
 /// Converts a string URL into a `FacilitatorClient`, parsing the URL and calling `try_new`.
 impl TryFrom<&str> for FacilitatorClient {
     type Error = FacilitatorClientError;
@@ -515,6 +1384,34 @@ fn record_result_on_span<R, E: Display>(result: &Result<R, E>) {
 #[cfg(not(feature = "telemetry"))]
 fn record_result_on_span<R, E: Display>(_result: &Result<R, E>) {}
 
+/// Adds the W3C trace context of the current span to `req`, using the global text map
+/// propagator, so that the facilitator continues the trace. The payment ID, if any, is
+/// sent as [`PAYMENT_ID_BAGGAGE_KEY`] baggage.
+#[cfg(feature = "telemetry")]
+fn inject_trace_context(
+    req: reqwest::RequestBuilder,
+    payment_id: Option<&PaymentId>,
+) -> reqwest::RequestBuilder {
+    use opentelemetry::KeyValue;
+    use opentelemetry::baggage::BaggageExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let mut context = Span::current().context();
+    if let Some(payment_id) = payment_id {
+        context = context.with_baggage([KeyValue::new(
+            PAYMENT_ID_BAGGAGE_KEY,
+            payment_id.as_str().to_owned(),
+        )]);
+    }
+    let mut headers = std::collections::HashMap::new();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut headers)
+    });
+    headers
+        .into_iter()
+        .fold(req, |req, (name, value)| req.header(name, value))
+}
+
 /// Instruments a future with a given tracing span.
 #[cfg(feature = "telemetry")]
 fn with_span<F: Future>(fut: F, span: Span) -> impl Future<Output = F::Output> {
@@ -525,7 +1422,7 @@ fn with_span<F: Future>(fut: F, span: Span) -> impl Future<Output = F::Output> {
 mod tests {
     use super::*;
     use std::collections::HashMap;
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{body_json, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
     use x402_types::proto::SupportedPaymentKind;
 
@@ -542,6 +1439,35 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_supported_for_client_posts_capabilities() {
+        let mock_server = MockServer::start().await;
+        let capabilities = ClientCapabilities {
+            supported_schemes: vec!["exact".to_string()],
+            supported_chains: vec!["eip155:8453".to_string()],
+            available_balances: HashMap::from([("eip155:8453".to_string(), "100".to_string())]),
+        };
+        Mock::given(method("POST"))
+            .and(path("/supported"))
+            .and(body_json(&capabilities))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(create_test_supported_response()),
+            )
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap()).unwrap();
+
+        for _ in 0..2 {
+            let response = client
+                .supported_for_client(capabilities.clone())
+                .await
+                .unwrap();
+            assert_eq!(response.kinds.len(), 1);
+        }
+    }
+
     #[tokio::test]
     async fn test_supported_cache_caches_response() {
         let mock_server = MockServer::start().await;
@@ -647,6 +1573,424 @@ mod tests {
         let _ = client2.supported().await.unwrap();
     }
 
+    fn verify_request() -> VerifyRequest {
+        VerifyRequest::from(serde_json::value::to_raw_value(&serde_json::json!({})).unwrap())
+    }
+
+    fn valid_verify_response() -> serde_json::Value {
+        serde_json::json!({ "isValid": true, "payer": "0xpayer" })
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_503_then_success() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(valid_verify_response()))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap())
+            .unwrap()
+            .with_retry_policy(3, Duration::from_millis(1), Duration::from_millis(5));
+
+        let retries = FacilitatorRetries::default();
+        let response = retries
+            .scope(client.verify(&verify_request()))
+            .await
+            .unwrap();
+        assert_eq!(response.0, valid_verify_response());
+        assert_eq!(retries.count(), 2);
+
+        let calls = mock_server.received_requests().await.unwrap();
+        assert_eq!(calls.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/settle"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap())
+            .unwrap()
+            .with_retry_policy(2, Duration::from_millis(1), Duration::from_millis(5));
+
+        let request = verify_request();
+        let settle = client.settle(&request);
+        let err = with_idempotency_key(Some("pay_1".to_string()), settle)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            FacilitatorClientError::HttpStatus { status, .. } if status == StatusCode::SERVICE_UNAVAILABLE
+        ));
+        let calls = mock_server.received_requests().await.unwrap();
+        assert_eq!(calls.len(), 2);
+        for call in calls {
+            assert_eq!(call.headers[IDEMPOTENCY_KEY_HEADER], "pay_1");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settle_without_idempotency_key_is_not_retried() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/settle"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap())
+            .unwrap()
+            .with_retry_policy(3, Duration::from_millis(1), Duration::from_millis(5));
+
+        assert!(client.settle(&verify_request()).await.is_err());
+        let calls = mock_server.received_requests().await.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert!(!calls[0].headers.contains_key(IDEMPOTENCY_KEY_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(3)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(valid_verify_response()))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap())
+            .unwrap()
+            .with_circuit_breaker(2, Duration::from_millis(50));
+        let breaker = client.circuit_breaker().unwrap();
+
+        assert!(client.verify(&verify_request()).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(client.verify(&verify_request()).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Open circuit fails fast without reaching the facilitator
+        let err = client.verify(&verify_request()).await.unwrap_err();
+        assert!(matches!(err, FacilitatorClientError::CircuitOpen { .. }));
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+
+        // Failed trial reopens the circuit
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(matches!(
+            client.verify(&verify_request()).await.unwrap_err(),
+            FacilitatorClientError::HttpStatus { .. }
+        ));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Successful trial closes it
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(client.verify(&verify_request()).await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_ignores_4xx() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap())
+            .unwrap()
+            .with_circuit_breaker(1, Duration::from_secs(30));
+
+        assert!(client.verify(&verify_request()).await.is_err());
+        assert!(client.verify(&verify_request()).await.is_err());
+        assert_eq!(
+            client.circuit_breaker().unwrap().state(),
+            CircuitState::Closed
+        );
+    }
+
+    fn settle_response() -> serde_json::Value {
+        serde_json::json!({
+            "success": true,
+            "transaction": "0xabc",
+            "network": "eip155:84532",
+            "payer": "0xpayer",
+        })
+    }
+
+    async fn received(server: &MockServer, endpoint: &str) -> usize {
+        let calls = server.received_requests().await.unwrap();
+        calls
+            .iter()
+            .filter(|call| call.url.path() == endpoint)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn test_verify_fails_over_and_skips_unhealthy_primary() {
+        let primary = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&primary)
+            .await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(valid_verify_response()))
+            .mount(&fallback)
+            .await;
+
+        let client = FacilitatorClient::try_from(primary.uri())
+            .unwrap()
+            .without_verify_cache()
+            .with_fallback_url(&fallback.uri())
+            .unwrap();
+
+        let response = client.verify(&verify_request()).await.unwrap();
+        assert_eq!(response.0, valid_verify_response());
+        assert!(!client.is_healthy());
+        assert!(client.fallbacks()[0].is_healthy());
+
+        // The unhealthy primary is not tried again during the cooldown
+        client.verify(&verify_request()).await.unwrap();
+        assert_eq!(received(&primary, "/verify").await, 1);
+        assert_eq!(received(&fallback, "/verify").await, 2);
+
+        // After the cooldown, the primary is tried first again
+        let client = client.with_failover_cooldown(Duration::ZERO);
+        client.verify(&verify_request()).await.unwrap();
+        assert_eq!(received(&primary, "/verify").await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_verify_does_not_fail_over_on_4xx() {
+        let primary = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&primary)
+            .await;
+        let fallback = MockServer::start().await;
+
+        let client = FacilitatorClient::try_from(primary.uri())
+            .unwrap()
+            .with_fallback_url(&fallback.uri())
+            .unwrap();
+
+        assert!(client.verify(&verify_request()).await.is_err());
+        assert!(client.is_healthy());
+        assert_eq!(received(&fallback, "/verify").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_settle_fails_over_before_acceptance() {
+        let primary = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/settle"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&primary)
+            .await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/settle"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(settle_response()))
+            .mount(&fallback)
+            .await;
+
+        // A refused connection and a 5xx both fail over
+        let client = FacilitatorClient::try_from("http://127.0.0.1:1")
+            .unwrap()
+            .with_fallback_url(&primary.uri())
+            .unwrap()
+            .with_fallback_url(&fallback.uri())
+            .unwrap();
+        let response = client.settle(&verify_request()).await.unwrap();
+        assert_eq!(response.0, settle_response());
+        assert_eq!(received(&primary, "/settle").await, 1);
+        assert_eq!(received(&fallback, "/settle").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_settle_does_not_fail_over_after_timeout() {
+        let primary = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/settle"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(settle_response())
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&primary)
+            .await;
+        let fallback = MockServer::start().await;
+
+        let client = FacilitatorClient::try_from(primary.uri())
+            .unwrap()
+            .with_fallback_url(&fallback.uri())
+            .unwrap()
+            .with_timeout(Duration::from_millis(50));
+
+        // The primary may have settled: failing over could settle twice
+        let err = client.settle(&verify_request()).await.unwrap_err();
+        assert!(err.may_have_been_processed());
+        assert_eq!(received(&fallback, "/settle").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_supported_intersects_facilitators() {
+        fn kind(network: &str) -> SupportedPaymentKind {
+            SupportedPaymentKind {
+                x402_version: 2,
+                scheme: "exact".to_string(),
+                network: network.to_string(),
+                extra: None,
+            }
+        }
+        let primary = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/supported"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(SupportedResponse {
+                kinds: vec![kind("eip155:8453"), kind("eip155:84532")],
+                extensions: vec!["bazaar".to_string()],
+                signers: HashMap::new(),
+            }))
+            .mount(&primary)
+            .await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/supported"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(SupportedResponse {
+                kinds: vec![kind("eip155:84532"), kind("solana:devnet")],
+                extensions: vec![],
+                signers: HashMap::new(),
+            }))
+            .mount(&fallback)
+            .await;
+
+        let client = FacilitatorClient::try_from(primary.uri())
+            .unwrap()
+            .with_fallback_url(&fallback.uri())
+            .unwrap()
+            .with_fallback_url("http://127.0.0.1:1")
+            .unwrap();
+
+        // The unreachable fallback is left out of the intersection
+        let supported = client.supported().await.unwrap();
+        assert_eq!(supported.kinds.len(), 1);
+        assert_eq!(supported.kinds[0].network, "eip155:84532");
+        assert!(supported.extensions.is_empty());
+        assert!(!client.fallbacks()[1].is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_4xx() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap())
+            .unwrap()
+            .with_retry_policy(3, Duration::from_millis(1), Duration::from_millis(5));
+
+        assert!(client.verify(&verify_request()).await.is_err());
+        let calls = mock_server.received_requests().await.unwrap();
+        assert_eq!(calls.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_cache_collapses_identical_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(valid_verify_response())
+                    .insert_header(VERIFY_TTL_HEADER, "60")
+                    .set_delay(Duration::from_millis(50)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap()).unwrap();
+        let request = verify_request();
+        let mut concurrent = tokio::task::JoinSet::new();
+        for _ in 0..50 {
+            let (client, request) = (client.clone(), request.clone());
+            concurrent.spawn(async move { client.verify(&request).await });
+        }
+        for response in concurrent.join_all().await {
+            assert_eq!(response.unwrap().0, valid_verify_response());
+        }
+        for _ in 0..50 {
+            assert_eq!(
+                client.verify(&request).await.unwrap().0,
+                valid_verify_response()
+            );
+        }
+
+        let calls = mock_server.received_requests().await.unwrap();
+        assert_eq!(calls.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_cache_requires_ttl_header() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/verify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(valid_verify_response()))
+            .mount(&mock_server)
+            .await;
+
+        let client = FacilitatorClient::try_new(mock_server.uri().parse().unwrap()).unwrap();
+        for _ in 0..3 {
+            client.verify(&verify_request()).await.unwrap();
+        }
+
+        let calls = mock_server.received_requests().await.unwrap();
+        assert_eq!(calls.len(), 3);
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_capped_and_jittered() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+        };
+        for (retry, base) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (9, 1000)] {
+            let backoff = policy.backoff(retry).as_secs_f64() * 1000.0;
+            let base = base as f64;
+            assert!(
+                backoff >= base * 0.8 && backoff <= base * 1.2,
+                "retry {retry}: {backoff}ms not within 20% of {base}ms"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_supported_inner_bypasses_cache() {
         let mock_server = MockServer::start().await;
@@ -41,6 +41,20 @@
 //! - **[`X402Middleware::settle_after_execution`]** - Settle payment **after** request execution (default).
 //!   This allows processing the request before committing the payment on-chain.
 //!
+//! ## Payment Correlation
+//!
+//! A valid `X-Payment-Id` header on the incoming request is forwarded to the facilitator on
+//! `/verify` and `/settle`, echoed on the response, and stored as an
+//! [`x402_types::proto::PaymentId`] in the request and response extensions. This lets logs of
+//! the client, the resource server and the facilitator be joined on one ID.
+//!
+//! ## Browser Paywall
+//!
+//! Requests whose `Accept` header prefers `text/html` (i.e. browsers) receive an HTML paywall
+//! page listing the accepted payments instead of the raw challenge. The status code and
+//! `Payment-Required` header are unchanged. Use [`X402LayerBuilder::without_paywall`] to
+//! turn this off.
+//!
 //! ## Accessing Settlement Result
 //!
 //! The middleware injects an `Option<x402_types::proto::SettleResponse>` into the request
@@ -49,22 +63,54 @@
 //! - `Some(settlement)` — settlement completed before the handler ran (`settle_before_execution`)
 //! - `None` — settlement will occur after the handler returns (default `settle_after_execution`)
 //!
+//! Once a payment is settled, the typed [`x402_types::proto::PaymentResponse`], carrying the
+//! transaction hash, and the facilitator's [`x402_types::proto::SettleResponse`] are inserted
+//! into the **response** extensions. They are inserted along with the payment response
+//! header, before the response leaves the middleware, so any layer wrapping it (logging,
+//! billing) can read them from `response.extensions()`. They are present exactly when the
+//! payment was settled for this response, including a retry served from the original
+//! settlement; free-quota requests and failed responses carry neither.
+//! [`X402Middleware::expose_payment_response`] can keep the header from being sent while
+//! the extensions are still inserted.
+//!
 //! ## Configuration Notes
 //!
 //! - **[`X402Middleware::with_price_tag`]** sets the assets and amounts accepted for payment (static pricing).
+//! - **[`X402LayerBuilder::or_price_tag`]** adds an alternative payment option, which may use
+//!   another network or protocol version (e.g. V1 on Base alongside V2 on Solana).
 //! - **[`X402Middleware::with_dynamic_price`]** sets a callback for dynamic pricing based on request context.
 //! - **[`X402Middleware::with_base_url`]** sets the base URL for computing full resource URLs.
 //!   If not set, defaults to `http://localhost/` (avoid in production).
 //! - **[`X402LayerBuilder::with_description`]** is optional but helps the payer understand what is being paid for.
 //! - **[`X402LayerBuilder::with_mime_type`]** sets the MIME type of the protected resource (default: `application/json`).
+//! - **[`X402LayerBuilder::with_input_schema`]** and **[`X402LayerBuilder::with_output_schema`]**
+//!   describe the resource for discovery (the `bazaar` extension in V2, `outputSchema` in V1).
 //! - **[`X402LayerBuilder::with_resource`]** explicitly sets the full URI of the protected resource.
 //! - **[`X402Middleware::with_extension`]** and **[`X402LayerBuilder::with_extension`]**
 //!   declare V2 protocol extensions in `PaymentRequired.extensions`.
+//! - **[`X402LayerBuilder::with_paywall_html`]** and **[`X402LayerBuilder::with_paywall_handler`]**
+//!   customize the HTML paywall served to browsers; see [`crate::paywall`].
+//! - **[`X402Middleware::with_payment_required_responder`]** replaces the 402 response with a custom one,
+//!   e.g. to wrap the challenge in an API's error envelope.
+//! - **[`X402Middleware::with_free_quota`]** and **[`X402LayerBuilder::with_free_quota`]** serve a number
+//!   of free requests per client before requiring payment; see [`crate::quota`].
+//! - **[`X402Middleware::with_replay_protection`]** and **[`X402LayerBuilder::with_replay_protection`]**
+//!   reject a payment presented more than once; see [`crate::replay`].
+//!   [`X402LayerBuilder::on_replay`] lets idempotent routes serve retries from the original
+//!   settlement instead.
+//! - **[`X402LayerBuilder::with_route`]** lists the route in the discovery index served at
+//!   `/.well-known/x402`; see [`crate::discovery`].
+//! - **[`X402LayerBuilder::requirements`]** and **[`X402LayerBuilder::payment_required`]** render
+//!   what the route advertises to unpaid requests, e.g. to test its prices without a request.
 //!
 
+use axum::Router;
+use axum::routing::get;
+use axum_core::body::Body;
 use axum_core::extract::Request;
 use axum_core::response::Response;
-use http::{HeaderMap, Uri};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue, Method, Uri};
 use serde::Serialize;
 use std::convert::Infallible;
 use std::future::Future;
@@ -76,14 +122,25 @@ use tower::util::BoxCloneSyncService;
 use tower::{Layer, Service};
 use url::Url;
 use x402_types::facilitator::Facilitator;
+use x402_types::proto::PaymentId;
+use x402_types::proto::v2;
 use x402_types::proto::v2::ExtensionsJson;
 use x402_types::scheme::ExtensionKey;
 
-use crate::facilitator_client::FacilitatorClient;
+use crate::discovery::{PricedRoute, PricedRoutes};
+use crate::facilitator_client::{
+    FacilitatorClient, FacilitatorRetries, with_idempotency_key, with_payment_id,
+};
+use crate::meter::{Meter, MeterSink};
 use crate::paygate::{
-    DynamicPriceTags, Paygate, PaygateProtocol, PriceTagSource, ResourceInfoBuilder,
-    StaticPriceTags,
+    AnyPaymentRequirements, AnyPriceTag, DynamicPriceTags, Paygate, PaygateProtocol,
+    PaymentRequired, PaymentRequiredResponder, PriceTagSource, ResourceInfoBuilder,
+    StaticPriceTags, VerificationError,
 };
+use crate::paywall::{Paywall, PaywallContext};
+use crate::quota::{FREE_QUOTA_EXTRA_KEY, FreeQuota};
+use crate::replay::{ReplayPolicy, ReplayProtection};
+use crate::stats::PaymentStats;
 
 /// The main X402 middleware instance for enforcing x402 payments on routes.
 ///
@@ -95,6 +152,17 @@ pub struct X402Middleware<F> {
     base_url: Option<Url>,
     settle_before_execution: bool,
     extensions: ExtensionsJson,
+    paywall: Option<Paywall>,
+    responder: Option<PaymentRequiredResponder>,
+    timing_headers: bool,
+    expose_payment_response: bool,
+    quota: Option<FreeQuota>,
+    replay: Option<ReplayProtection>,
+    priced_routes: PricedRoutes,
+    route_prefix: String,
+    stats: Arc<PaymentStats>,
+    stats_path: Option<String>,
+    meter: Option<Meter>,
 }
 
 impl<F> X402Middleware<F> {
@@ -115,6 +183,17 @@ impl<F> X402Middleware<F> {
             base_url: None,
             settle_before_execution: false,
             extensions: ExtensionsJson::default(),
+            paywall: Some(Paywall::default()),
+            responder: None,
+            timing_headers: false,
+            expose_payment_response: true,
+            quota: None,
+            replay: None,
+            priced_routes: PricedRoutes::global().clone(),
+            route_prefix: String::new(),
+            stats: Arc::default(),
+            stats_path: None,
+            meter: None,
         }
     }
 
@@ -136,6 +215,17 @@ impl X402Middleware<Arc<FacilitatorClient>> {
             base_url: None,
             settle_before_execution: false,
             extensions: ExtensionsJson::default(),
+            paywall: Some(Paywall::default()),
+            responder: None,
+            timing_headers: false,
+            expose_payment_response: true,
+            quota: None,
+            replay: None,
+            priced_routes: PricedRoutes::global().clone(),
+            route_prefix: String::new(),
+            stats: Arc::default(),
+            stats_path: None,
+            meter: None,
         }
     }
 
@@ -147,6 +237,17 @@ impl X402Middleware<Arc<FacilitatorClient>> {
             base_url: None,
             settle_before_execution: false,
             extensions: ExtensionsJson::default(),
+            paywall: Some(Paywall::default()),
+            responder: None,
+            timing_headers: false,
+            expose_payment_response: true,
+            quota: None,
+            replay: None,
+            priced_routes: PricedRoutes::global().clone(),
+            route_prefix: String::new(),
+            stats: Arc::default(),
+            stats_path: None,
+            meter: None,
         })
     }
 
@@ -166,6 +267,139 @@ impl X402Middleware<Arc<FacilitatorClient>> {
             base_url: self.base_url.clone(),
             settle_before_execution: self.settle_before_execution,
             extensions: self.extensions.clone(),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            stats: self.stats.clone(),
+            stats_path: self.stats_path.clone(),
+            meter: self.meter.clone(),
+        }
+    }
+}
+
+impl X402Middleware<Arc<FacilitatorClient>> {
+    /// Retries facilitator `/verify` and `/settle` calls on transient failures.
+    ///
+    /// See [`FacilitatorClient::with_retry_policy`]. The number of retries made for a
+    /// request is available via
+    /// [`FacilitatorRetryCountExt::facilitator_retry_count`](crate::facilitator_client::FacilitatorRetryCountExt::facilitator_retry_count)
+    /// on both the request and the response.
+    pub fn with_retry_policy(
+        &self,
+        max_attempts: u8,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        let facilitator = Arc::new(self.facilitator.with_retry_policy(
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+        ));
+        Self {
+            facilitator,
+            base_url: self.base_url.clone(),
+            settle_before_execution: self.settle_before_execution,
+            extensions: self.extensions.clone(),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            stats: self.stats.clone(),
+            stats_path: self.stats_path.clone(),
+            meter: self.meter.clone(),
+        }
+    }
+
+    /// Adds a facilitator to fail over to when the ones already configured fail.
+    ///
+    /// See [`FacilitatorClient::with_fallback_url`] and the
+    /// [failover rules](crate::facilitator_client#failover).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the facilitator URL is invalid.
+    pub fn with_fallback_facilitator(&self, url: &str) -> Self {
+        let facilitator = Arc::new(
+            self.facilitator
+                .with_fallback_url(url)
+                .expect("Invalid facilitator URL"),
+        );
+        Self {
+            facilitator,
+            base_url: self.base_url.clone(),
+            settle_before_execution: self.settle_before_execution,
+            extensions: self.extensions.clone(),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            stats: self.stats.clone(),
+            stats_path: self.stats_path.clone(),
+            meter: self.meter.clone(),
+        }
+    }
+
+    /// Bounds each facilitator call attempt to `timeout`.
+    ///
+    /// See [`FacilitatorClient::with_timeout`].
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        let facilitator = Arc::new(self.facilitator.with_timeout(timeout));
+        Self {
+            facilitator,
+            base_url: self.base_url.clone(),
+            settle_before_execution: self.settle_before_execution,
+            extensions: self.extensions.clone(),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            stats: self.stats.clone(),
+            stats_path: self.stats_path.clone(),
+            meter: self.meter.clone(),
+        }
+    }
+
+    /// Fails facilitator calls fast while the facilitator is down.
+    ///
+    /// See [`FacilitatorClient::with_circuit_breaker`].
+    pub fn with_circuit_breaker(&self, failure_threshold: u32, cooldown: Duration) -> Self {
+        let facilitator = Arc::new(
+            self.facilitator
+                .with_circuit_breaker(failure_threshold, cooldown),
+        );
+        Self {
+            facilitator,
+            base_url: self.base_url.clone(),
+            settle_before_execution: self.settle_before_execution,
+            extensions: self.extensions.clone(),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            stats: self.stats.clone(),
+            stats_path: self.stats_path.clone(),
+            meter: self.meter.clone(),
         }
     }
 }
@@ -208,6 +442,24 @@ impl TryFrom<String> for X402Middleware<Arc<FacilitatorClient>> {
     }
 }
 
+/// Creates middleware from a primary facilitator URL followed by fallbacks.
+///
+/// See [`X402Middleware::with_fallback_facilitator`].
+impl<const N: usize> TryFrom<[&str; N]> for X402Middleware<Arc<FacilitatorClient>> {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(urls: [&str; N]) -> Result<Self, Self::Error> {
+        let [primary, fallbacks @ ..] = urls.as_slice() else {
+            return Err("At least one facilitator URL is required".into());
+        };
+        let mut facilitator = FacilitatorClient::try_from(*primary)?;
+        for url in fallbacks {
+            facilitator = facilitator.with_fallback_url(url)?;
+        }
+        Ok(Self::from_facilitator(Arc::new(facilitator)))
+    }
+}
+
 impl<F> X402Middleware<F>
 where
     F: Clone,
@@ -242,6 +494,172 @@ where
         this.settle_before_execution = false;
         this
     }
+
+    /// Sets the HTML template of the paywall page shown to browsers.
+    ///
+    /// See [`crate::paywall`] for the available placeholders. Applies to every layer
+    /// builder created from this middleware.
+    pub fn with_paywall_html(&self, template: impl Into<String>) -> Self {
+        let mut this = self.clone();
+        this.paywall = Some(Paywall::Template(Arc::from(template.into())));
+        this
+    }
+
+    /// Renders the paywall shown to browsers with a custom handler.
+    ///
+    /// Applies to every layer builder created from this middleware.
+    pub fn with_paywall_handler<H>(&self, handler: H) -> Self
+    where
+        H: Fn(&PaywallContext) -> Response + Send + Sync + 'static,
+    {
+        let mut this = self.clone();
+        this.paywall = Some(Paywall::Handler(Arc::new(handler)));
+        this
+    }
+
+    /// Disables the paywall page: browsers receive the same 402 response as API clients.
+    pub fn without_paywall(&self) -> Self {
+        let mut this = self.clone();
+        this.paywall = None;
+        this
+    }
+
+    /// Builds payment-required responses with a custom callback.
+    ///
+    /// The callback receives the route's [`PaymentRequired`] challenge and the parts of the
+    /// incoming request, and returns the full response. It runs whenever payment is required,
+    /// including when a submitted payment fails verification. Browser paywall pages take
+    /// precedence when enabled.
+    ///
+    /// The machine-readable challenge is guaranteed to reach the client: a V2 challenge is
+    /// added in the `Payment-Required` header if the response lacks it, and a V1-only
+    /// challenge must appear somewhere in the JSON body, or the default response is sent.
+    ///
+    /// ```rust,ignore
+    /// x402.with_payment_required_responder(|payment_required, _parts| {
+    ///     let body = json!({ "error": { "code": "payment_required", "x402": payment_required.to_json() } });
+    ///     (payment_required.status, Json(body)).into_response()
+    /// })
+    /// ```
+    pub fn with_payment_required_responder<R>(&self, responder: R) -> Self
+    where
+        R: Fn(PaymentRequired, &Parts) -> Response + Send + Sync + 'static,
+    {
+        let mut this = self.clone();
+        this.responder = Some(PaymentRequiredResponder::new(responder));
+        this
+    }
+
+    /// Adds `X-Payment-Verified-Ms` and `X-Payment-Total-Ms` headers to paid responses.
+    ///
+    /// `X-Payment-Verified-Ms` is the time spent in facilitator verify and settle calls,
+    /// `X-Payment-Total-Ms` the whole request including the handler, both in milliseconds.
+    /// Disabled by default.
+    #[cfg(feature = "timing-headers")]
+    pub fn with_timing_headers(&self, enabled: bool) -> Self {
+        let mut this = self.clone();
+        this.timing_headers = enabled;
+        this
+    }
+
+    /// Sets whether the payment response header (`Payment-Response`, or `X-Payment-Response`
+    /// for V1) is sent to the client. Enabled by default.
+    ///
+    /// Disabled, settlement results stay internal: the header is left out, but the typed
+    /// [`PaymentResponse`](x402_types::proto::PaymentResponse) and
+    /// [`SettleResponse`](x402_types::proto::SettleResponse) are still inserted into the
+    /// response extensions for outer layers. See the module documentation.
+    pub fn expose_payment_response(&self, expose: bool) -> Self {
+        let mut this = self.clone();
+        this.expose_payment_response = expose;
+        this
+    }
+
+    /// Serves a number of free requests per client before requiring payment.
+    ///
+    /// Only successful requests use up quota. Once it is exhausted, 402 challenges carry
+    /// the quota state in the `freeQuota` entry of each requirement's `extra`.
+    /// See [`crate::quota`].
+    pub fn with_free_quota(&self, quota: FreeQuota) -> Self {
+        let mut this = self.clone();
+        this.quota = Some(quota);
+        this
+    }
+
+    /// Rejects payments presented more than once with `409 Conflict`, before they reach
+    /// the facilitator.
+    ///
+    /// Share one [`ReplayProtection`] between routes, or give it a shared store when
+    /// running several instances. See [`crate::replay`].
+    pub fn with_replay_protection(&self, replay: ReplayProtection) -> Self {
+        let mut this = self.clone();
+        this.replay = Some(replay);
+        this
+    }
+
+    /// Sets the registry that layers declaring their route register into.
+    ///
+    /// Defaults to [`PricedRoutes::global`], served by [`crate::discovery_router`].
+    /// See [`crate::discovery`].
+    pub fn with_priced_routes(&self, priced_routes: PricedRoutes) -> Self {
+        let mut this = self.clone();
+        this.priced_routes = priced_routes;
+        this
+    }
+
+    /// Sets the path [`Self::stats_router`] serves the payment statistics of this
+    /// middleware at, as JSON. See [`crate::stats`].
+    pub fn with_stats_endpoint(&self, path: &str) -> Self {
+        let mut this = self.clone();
+        this.stats_path = Some(path.to_string());
+        this
+    }
+
+    /// Returns the payment counters of this middleware instance, shared by the routes
+    /// built from it and its clones.
+    pub fn stats(&self) -> &Arc<PaymentStats> {
+        &self.stats
+    }
+
+    /// Returns a router serving `GET` requests to the path set with
+    /// [`Self::with_stats_endpoint`] with a snapshot of [`Self::stats`], to be merged into
+    /// the application router. Empty if no path was set.
+    pub fn stats_router<S>(&self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let Some(path) = &self.stats_path else {
+            return Router::new();
+        };
+        let stats = self.stats.clone();
+        Router::new().route(
+            path,
+            get(move || {
+                let stats = stats.clone();
+                async move { axum::Json(stats.snapshot()) }
+            }),
+        )
+    }
+
+    /// Reports each request carrying a payment to `sink`, once its settlement is resolved.
+    ///
+    /// Events are handed to the sink by a background task, off the request path.
+    /// See [`crate::meter`].
+    pub fn with_meter(&self, sink: impl MeterSink) -> Self {
+        let mut this = self.clone();
+        this.meter = Some(Meter::new(sink));
+        this
+    }
+
+    /// Sets the prefix under which the router of the protected routes is nested.
+    ///
+    /// Prepended to the patterns declared with [`X402LayerBuilder::with_route`], so the
+    /// discovery index lists public paths. Prefixes of nested routers add up.
+    pub fn with_route_prefix(&self, prefix: &str) -> Self {
+        let mut this = self.clone();
+        this.route_prefix.push_str(prefix.trim_end_matches('/'));
+        this
+    }
 }
 
 impl<TFacilitator> X402Middleware<TFacilitator>
@@ -262,14 +680,31 @@ where
             base_url: self.base_url.clone().map(Arc::new),
             resource: Arc::new(ResourceInfoBuilder::default()),
             extensions: Arc::new(self.extensions.clone()),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
             settle_before_execution: self.settle_before_execution,
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            route: None,
+            stats: self.stats.clone(),
+            meter: self.meter.clone(),
         }
     }
 
     /// Sets a dynamic price source for the protected route.
     ///
     /// The `callback` receives request headers, URI, and base URL, and returns
-    /// a vector of price tags.
+    /// a vector of price tags. It runs for each request, so the price can depend on the
+    /// path, query or headers, e.g. the requested resolution or data size.
+    ///
+    /// A payment is checked against the price computed for the request it is sent with:
+    /// a payment made for the price advertised to another request, e.g. a cheaper one, is
+    /// rejected unless that price also applies to this request. Returning no price tags, or
+    /// a price tag with a zero amount, lets the request through without payment.
     ///
     /// # Example
     ///
@@ -306,7 +741,18 @@ where
             base_url: self.base_url.clone().map(Arc::new),
             resource: Arc::new(ResourceInfoBuilder::default()),
             extensions: Arc::new(self.extensions.clone()),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
             settle_before_execution: self.settle_before_execution,
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            priced_routes: self.priced_routes.clone(),
+            route_prefix: self.route_prefix.clone(),
+            route: None,
+            stats: self.stats.clone(),
+            meter: self.meter.clone(),
         }
     }
 }
@@ -323,6 +769,17 @@ pub struct X402LayerBuilder<TSource, TFacilitator> {
     price_source: TSource,
     resource: Arc<ResourceInfoBuilder>,
     extensions: Arc<ExtensionsJson>,
+    paywall: Option<Paywall>,
+    responder: Option<PaymentRequiredResponder>,
+    timing_headers: bool,
+    expose_payment_response: bool,
+    quota: Option<FreeQuota>,
+    replay: Option<ReplayProtection>,
+    priced_routes: PricedRoutes,
+    route_prefix: String,
+    route: Option<(Method, String)>,
+    stats: Arc<PaymentStats>,
+    meter: Option<Meter>,
 }
 
 impl<TPriceTag, TFacilitator> X402LayerBuilder<StaticPriceTags<TPriceTag>, TFacilitator>
@@ -338,6 +795,46 @@ where
         self.price_source = self.price_source.with_price_tag(price_tag);
         self
     }
+
+    /// Adds an alternative payment option, possibly of another protocol version.
+    ///
+    /// Unlike [`Self::with_price_tag`], the new option does not need to share the type
+    /// of the existing ones, so V1 and V2 price tags can be offered on the same route.
+    /// Options are advertised in the 402 challenge in insertion order, and a submitted
+    /// payment is verified against the option it was made for.
+    ///
+    /// ```rust,ignore
+    /// x402.with_price_tag(V1Eip155Exact::price_tag(pay_to, USDC::base().parse("0.01")?))
+    ///     .or_price_tag(V2SolanaExact::price_tag(pay_to_solana, USDC::solana().amount(10000)))
+    /// ```
+    pub fn or_price_tag<TOther>(
+        self,
+        price_tag: TOther,
+    ) -> X402LayerBuilder<StaticPriceTags<AnyPriceTag>, TFacilitator>
+    where
+        TPriceTag: Into<AnyPriceTag>,
+        TOther: Into<AnyPriceTag>,
+    {
+        X402LayerBuilder {
+            facilitator: self.facilitator,
+            settle_before_execution: self.settle_before_execution,
+            base_url: self.base_url,
+            price_source: self.price_source.or_price_tag(price_tag),
+            resource: self.resource,
+            extensions: self.extensions,
+            paywall: self.paywall,
+            responder: self.responder,
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota,
+            replay: self.replay,
+            priced_routes: self.priced_routes,
+            route_prefix: self.route_prefix,
+            route: self.route,
+            stats: self.stats,
+            meter: self.meter,
+        }
+    }
 }
 
 impl<TSource, TFacilitator> X402LayerBuilder<TSource, TFacilitator> {
@@ -361,6 +858,28 @@ impl<TSource, TFacilitator> X402LayerBuilder<TSource, TFacilitator> {
         self
     }
 
+    /// Sets the JSON description of the request inputs, for discovery.
+    ///
+    /// Fields such as `queryParams`, `bodyType`, `body` or `headers` describe how to call
+    /// the resource. Together with [`Self::with_output_schema`], it is advertised as the
+    /// `bazaar` extension in V2 402 responses and as `outputSchema` in V1 ones.
+    pub fn with_input_schema(mut self, schema: serde_json::Value) -> Self {
+        let mut new_resource = (*self.resource).clone();
+        new_resource.input_schema = Some(schema);
+        self.resource = Arc::new(new_resource);
+        self
+    }
+
+    /// Sets the JSON Schema of the response returned once paid, for discovery.
+    ///
+    /// See [`Self::with_input_schema`].
+    pub fn with_output_schema(mut self, schema: serde_json::Value) -> Self {
+        let mut new_resource = (*self.resource).clone();
+        new_resource.output_schema = Some(schema);
+        self.resource = Arc::new(new_resource);
+        self
+    }
+
     /// Sets the full URL of the protected resource.
     ///
     /// When set, this URL is used directly instead of constructing it from the base URL
@@ -389,6 +908,96 @@ impl<TSource, TFacilitator> X402LayerBuilder<TSource, TFacilitator> {
         self.extensions = Arc::new(extensions);
         self
     }
+
+    /// Sets the HTML template of the paywall page shown to browsers on this route.
+    ///
+    /// Browsers are detected by an `Accept` header preferring `text/html` over
+    /// `application/json`. See [`crate::paywall`] for the available placeholders.
+    pub fn with_paywall_html(mut self, template: impl Into<String>) -> Self {
+        self.paywall = Some(Paywall::Template(Arc::from(template.into())));
+        self
+    }
+
+    /// Renders the paywall shown to browsers on this route with a custom handler.
+    ///
+    /// The handler receives the [`PaywallContext`] and builds the full response.
+    pub fn with_paywall_handler<H>(mut self, handler: H) -> Self
+    where
+        H: Fn(&PaywallContext) -> Response + Send + Sync + 'static,
+    {
+        self.paywall = Some(Paywall::Handler(Arc::new(handler)));
+        self
+    }
+
+    /// Disables the paywall page on this route.
+    pub fn without_paywall(mut self) -> Self {
+        self.paywall = None;
+        self
+    }
+
+    /// Builds payment-required responses on this route with a custom callback.
+    ///
+    /// See [`X402Middleware::with_payment_required_responder`].
+    pub fn with_payment_required_responder<R>(mut self, responder: R) -> Self
+    where
+        R: Fn(PaymentRequired, &Parts) -> Response + Send + Sync + 'static,
+    {
+        self.responder = Some(PaymentRequiredResponder::new(responder));
+        self
+    }
+
+    /// Adds payment timing headers to paid responses on this route.
+    ///
+    /// See [`X402Middleware::with_timing_headers`].
+    #[cfg(feature = "timing-headers")]
+    pub fn with_timing_headers(mut self, enabled: bool) -> Self {
+        self.timing_headers = enabled;
+        self
+    }
+
+    /// Sets whether the payment response header is sent to the client on this route.
+    ///
+    /// See [`X402Middleware::expose_payment_response`].
+    pub fn expose_payment_response(mut self, expose: bool) -> Self {
+        self.expose_payment_response = expose;
+        self
+    }
+
+    /// Serves a number of free requests per client on this route before requiring payment.
+    ///
+    /// See [`X402Middleware::with_free_quota`].
+    pub fn with_free_quota(mut self, quota: FreeQuota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Rejects payments presented more than once on this route.
+    ///
+    /// See [`X402Middleware::with_replay_protection`].
+    pub fn with_replay_protection(mut self, replay: ReplayProtection) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+
+    /// Sets what happens to a payment presented again on this route, e.g.
+    /// [`ReplayPolicy::ReuseSettlement`] for idempotent routes whose clients retry.
+    ///
+    /// Enables replay protection with a fresh in-memory store if none was set.
+    pub fn on_replay(mut self, policy: ReplayPolicy) -> Self {
+        self.replay = Some(self.replay.unwrap_or_default().on_replay(policy));
+        self
+    }
+
+    /// Declares the method and route pattern of this protected route, listing it in the
+    /// discovery index.
+    ///
+    /// `path` is the pattern passed to axum's `Router::route`, e.g. `/weather/{city}`.
+    /// It is prefixed by [`X402Middleware::with_route_prefix`]. The route is registered
+    /// when the layer is applied; see [`crate::discovery`].
+    pub fn with_route(mut self, method: Method, path: &str) -> Self {
+        self.route = Some((method, format!("{}{}", self.route_prefix, path)));
+        self
+    }
 }
 
 impl<S, TSource, TFacilitator> Layer<S> for X402LayerBuilder<TSource, TFacilitator>
@@ -401,6 +1010,7 @@ where
     type Service = X402MiddlewareService<TSource, TFacilitator>;
 
     fn layer(&self, inner: S) -> Self::Service {
+        self.register_route();
         X402MiddlewareService {
             facilitator: self.facilitator.clone(),
             settle_before_execution: self.settle_before_execution,
@@ -408,11 +1018,133 @@ where
             price_source: self.price_source.clone(),
             resource: self.resource.clone(),
             extensions: self.extensions.clone(),
+            paywall: self.paywall.clone(),
+            responder: self.responder.clone(),
+            timing_headers: self.timing_headers,
+            expose_payment_response: self.expose_payment_response,
+            quota: self.quota.clone(),
+            replay: self.replay.clone(),
+            stats: self.stats.clone(),
+            meter: self.meter.clone(),
             inner: BoxCloneSyncService::new(inner),
         }
     }
 }
 
+impl<TSource, TFacilitator> X402LayerBuilder<TSource, TFacilitator>
+where
+    TSource: PriceTagSource,
+{
+    /// Registers the declared route in the discovery index, if its price is static.
+    fn register_route(&self) {
+        let Some((method, path)) = &self.route else {
+            return;
+        };
+        let Some(accepts) = self.price_source.static_price_tags() else {
+            return;
+        };
+        let resource = self
+            .resource
+            .as_route_resource_info(self.base_url.as_deref(), path);
+        let payment_required = self.render_payment_required(accepts, &resource, method);
+        self.priced_routes.register(PricedRoute {
+            method: method.clone(),
+            path: path.clone(),
+            resource,
+            payment_required,
+        });
+    }
+
+    /// Builds the challenge answered to an unpaid request for the given price tags.
+    fn render_payment_required(
+        &self,
+        accepts: &[TSource::PriceTag],
+        resource: &v2::ResourceInfo,
+        method: &Method,
+    ) -> PaymentRequired {
+        let extensions = self
+            .resource
+            .with_discovery(self.extensions.clone(), method);
+        TSource::PriceTag::payment_required(
+            &VerificationError::PaymentHeaderRequired(TSource::PriceTag::PAYMENT_HEADER_NAME),
+            accepts,
+            resource,
+            &extensions,
+        )
+    }
+}
+
+impl<TSource, TFacilitator> X402LayerBuilder<TSource, TFacilitator>
+where
+    TSource: PriceTagSource,
+    TFacilitator: Facilitator,
+{
+    /// Renders the challenge this route answers to an unpaid request, without making one.
+    ///
+    /// Price tags are resolved for the parts of `request`, or, if `None`, for a request
+    /// without headers to the route declared with [`Self::with_route`] (`GET /` if none).
+    /// As in the middleware, they are enriched with the facilitator's capabilities, and
+    /// left as configured if the facilitator cannot be reached. Free quota metadata is
+    /// that of a client which has not been served yet.
+    ///
+    /// If no price tag resolves, the route is free and the challenge accepts no payment.
+    pub async fn payment_required(&self, request: Option<&Parts>) -> PaymentRequired {
+        let (method, path) = self
+            .route
+            .clone()
+            .unwrap_or_else(|| (Method::GET, "/".to_string()));
+        let (method, resource, mut accepts) = match request {
+            Some(parts) => {
+                let req = Request::from_parts(parts.clone(), Body::empty());
+                let resource = self
+                    .resource
+                    .as_resource_info(self.base_url.as_deref(), &req);
+                let accepts = self
+                    .price_source
+                    .resolve(&parts.headers, &parts.uri, self.base_url.as_deref())
+                    .await;
+                (parts.method.clone(), resource, accepts)
+            }
+            None => {
+                let resource = self
+                    .resource
+                    .as_route_resource_info(self.base_url.as_deref(), &path);
+                // Route patterns such as `/weather/{city}` are not valid URIs
+                let uri = path.parse().unwrap_or_default();
+                let accepts = self
+                    .price_source
+                    .resolve(&HeaderMap::new(), &uri, self.base_url.as_deref())
+                    .await;
+                (method, resource, accepts)
+            }
+        };
+        let capabilities = self.facilitator.supported().await.unwrap_or_default();
+        let quota_extra = self.quota.as_ref().map(|quota| quota.extra(None));
+        for price_tag in &mut accepts {
+            price_tag.enrich_with_capabilities(&capabilities);
+            if let Some(extra) = &quota_extra {
+                price_tag.insert_extra(FREE_QUOTA_EXTRA_KEY, extra.clone());
+            }
+        }
+        self.render_payment_required(&accepts, &resource, &method)
+    }
+
+    /// Renders the payment requirements this route advertises to an unpaid request.
+    ///
+    /// These are the `accepts` entries of the challenge returned by
+    /// [`Self::payment_required`], V1 ones first. Useful to check the prices of a route in
+    /// unit tests, without making a request and decoding the 402 response.
+    ///
+    /// ```rust,ignore
+    /// let layer = x402.with_price_tag(V2Eip155Exact::price_tag(pay_to, USDC::base().amount(10000)));
+    /// let requirements = layer.requirements(None).await;
+    /// assert_eq!(requirements[0].as_v2().unwrap().amount, "10000");
+    /// ```
+    pub async fn requirements(&self, request: Option<&Parts>) -> Vec<AnyPaymentRequirements> {
+        self.payment_required(request).await.requirements()
+    }
+}
+
 /// Axum service that enforces x402 payments on incoming requests.
 ///
 /// Generic over `TSource` which implements [`PriceTagSource`] to support
@@ -431,6 +1163,22 @@ pub struct X402MiddlewareService<TSource, TFacilitator> {
     resource: Arc<ResourceInfoBuilder>,
     /// Protocol extensions declared by the protected endpoint
     extensions: Arc<ExtensionsJson>,
+    /// Paywall page rendered for browsers, if enabled
+    paywall: Option<Paywall>,
+    /// Custom builder of payment-required responses, if set
+    responder: Option<PaymentRequiredResponder>,
+    /// Whether to add payment timing headers to paid responses
+    timing_headers: bool,
+    /// Whether to send the payment response header to the client
+    expose_payment_response: bool,
+    /// Free requests allowed per client before payment is required, if set
+    quota: Option<FreeQuota>,
+    /// Rejection of payments presented more than once, if enabled
+    replay: Option<ReplayProtection>,
+    /// Payment counters of the middleware instance
+    stats: Arc<PaymentStats>,
+    /// Usage metering of paid requests, if enabled
+    meter: Option<Meter>,
     /// The inner Axum service being wrapped
     inner: BoxCloneSyncService<Request, Response, Infallible>,
 }
@@ -457,6 +1205,14 @@ where
         let base_url = self.base_url.clone();
         let resource_builder = self.resource.clone();
         let extensions = self.extensions.clone();
+        let paywall = self.paywall.clone();
+        let responder = self.responder.clone();
+        let timing_headers = self.timing_headers;
+        let expose_payment_response = self.expose_payment_response;
+        let quota = self.quota.clone();
+        let replay = self.replay.clone();
+        let stats = self.stats.clone();
+        let meter = self.meter.clone();
         let settle_before_execution = self.settle_before_execution;
         let mut inner = self.inner.clone();
 
@@ -466,12 +1222,13 @@ where
                 .resolve(req.headers(), req.uri(), base_url.as_deref())
                 .await;
 
-            // If no price tags are configured, bypass payment enforcement
-            if accepts.is_empty() {
+            // If no price tags are configured, or the request is free, bypass payment enforcement
+            if accepts.is_empty() || accepts.iter().any(PaygateProtocol::is_free) {
                 return inner.call(req).await;
             }
 
             let resource = resource_builder.as_resource_info(base_url.as_deref(), &req);
+            let extensions = resource_builder.with_discovery(extensions, req.method());
 
             let gate = {
                 let mut gate = Paygate {
@@ -480,11 +1237,158 @@ where
                     accepts: Arc::new(accepts),
                     resource,
                     extensions,
+                    paywall,
+                    responder,
+                    timing_headers,
+                    expose_payment_response,
+                    quota,
+                    replay,
+                    stats: Some(stats),
+                    meter,
                 };
                 gate.enrich_accepts().await;
                 gate
             };
-            gate.handle_request(inner, req).await
+            // Track facilitator retries for this request
+            let retries = FacilitatorRetries::default();
+            let payment_id = req
+                .headers()
+                .get(PaymentId::HEADER_NAME)
+                .and_then(|header| PaymentId::from_header(header.as_bytes()));
+            let mut req = req;
+            req.extensions_mut().insert(retries.clone());
+            if let Some(payment_id) = &payment_id {
+                req.extensions_mut().insert(payment_id.clone());
+            }
+            // The payment ID doubles as the idempotency key of settlement retries
+            let idempotency_key = payment_id.as_ref().map(|id| id.as_str().to_owned());
+            let handled = with_payment_id(
+                payment_id.clone(),
+                with_idempotency_key(idempotency_key, gate.handle_request(inner, req)),
+            );
+            let mut response = retries.scope(handled).await?;
+            response.extensions_mut().insert(retries);
+            if let Some(payment_id) = payment_id {
+                // Valid payment IDs are visible ASCII, hence valid header values
+                if let Ok(header) = HeaderValue::from_str(payment_id.as_str()) {
+                    response
+                        .headers_mut()
+                        .insert(PaymentId::HEADER_NAME, header);
+                }
+                response.extensions_mut().insert(payment_id);
+            }
+            Ok(response)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+    use x402_types::proto::v1;
+
+    fn v1_price_tag() -> v1::PriceTag {
+        v1::PriceTag {
+            scheme: "exact".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            network: "base-sepolia".into(),
+            amount: "10000".into(),
+            max_timeout_seconds: 300,
+            extra: None,
+            enricher: None,
+        }
+    }
+
+    fn v2_price_tag(amount: &str) -> v2::PriceTag {
+        v2::PriceTag {
+            requirements: v2::PaymentRequirements {
+                scheme: "exact".into(),
+                network: "eip155:84532".parse().unwrap(),
+                amount: amount.into(),
+                pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+                max_timeout_seconds: 300,
+                asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+                extra: None,
+            },
+            enricher: None,
+        }
+    }
+
+    fn x402() -> X402Middleware<Arc<FacilitatorClient>> {
+        X402Middleware::new("http://127.0.0.1:1")
+            .with_base_url("https://api.example.com/".parse().unwrap())
+    }
+
+    #[test]
+    fn try_from_urls_adds_fallbacks() {
+        let x402 = X402Middleware::try_from(["http://primary/", "http://fallback/"]).unwrap();
+        assert_eq!(x402.facilitator_url().as_str(), "http://primary/");
+        let fallbacks = x402.facilitator().fallbacks();
+        assert_eq!(fallbacks.len(), 1);
+        assert_eq!(fallbacks[0].base_url().as_str(), "http://fallback/");
+
+        assert!(X402Middleware::try_from([]).is_err());
+        assert!(X402Middleware::try_from(["http://primary/", "not a url"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn requirements_render_static_price_tags() {
+        let layer = x402()
+            .with_price_tag(v1_price_tag())
+            .or_price_tag(v2_price_tag("100"))
+            .with_price_tag(v2_price_tag("200").into())
+            .with_description("Weather".to_string())
+            .with_route(Method::GET, "/weather/{city}");
+
+        let requirements = layer.requirements(None).await;
+        assert_eq!(requirements.len(), 3);
+        let v1 = requirements[0].as_v1().unwrap();
+        assert_eq!(v1.max_amount_required, "10000");
+        assert_eq!(v1.resource, "https://api.example.com/weather/{city}");
+        assert_eq!(v1.description, "Weather");
+        assert_eq!(requirements[1].as_v2().unwrap().amount, "100");
+        assert_eq!(requirements[2].as_v2().unwrap().amount, "200");
+
+        let payment_required = layer.payment_required(None).await;
+        assert_eq!(payment_required.status, StatusCode::PAYMENT_REQUIRED);
+        let v2 = payment_required.v2.unwrap();
+        assert_eq!(
+            v2.resource.unwrap().url,
+            "https://api.example.com/weather/{city}"
+        );
+        assert_eq!(v2.accepts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn requirements_resolve_dynamic_price_for_request() {
+        let layer = x402()
+            .with_dynamic_price(|headers, _uri, _base_url| {
+                let premium = headers.contains_key("X-Premium");
+                async move { vec![v2_price_tag(if premium { "50" } else { "100" })] }
+            })
+            .with_free_quota(FreeQuota::new(2, Duration::from_secs(60)));
+
+        let requirements = layer.requirements(None).await;
+        let requirements = requirements[0].as_v2().unwrap();
+        assert_eq!(requirements.amount, "100");
+        assert_eq!(
+            requirements.extra.as_ref().unwrap()[FREE_QUOTA_EXTRA_KEY]["limit"],
+            2
+        );
+
+        let (parts, _) = http::Request::get("/report?day=1")
+            .header("X-Premium", "1")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let payment_required = layer.payment_required(Some(&parts)).await;
+        let v2 = payment_required.v2.unwrap();
+        assert_eq!(v2.accepts[0].amount, "50");
+        assert_eq!(
+            v2.resource.unwrap().url,
+            "https://api.example.com/report?day=1"
+        );
+    }
+}
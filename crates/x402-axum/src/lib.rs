@@ -94,17 +94,44 @@
 //! ## Configuration Notes
 //!
 //! - **[`X402Middleware::with_price_tag`]** sets the assets and amounts accepted for payment (static pricing).
+//! - **[`X402LayerBuilder::or_price_tag`]** adds an alternative payment option, which may use
+//!   another network or protocol version (e.g. V1 on Base alongside V2 on Solana).
 //! - **[`X402Middleware::with_dynamic_price`]** sets a callback for dynamic pricing based on request context.
 //! - **[`X402Middleware::with_base_url`]** sets the base URL for computing full resource URLs.
 //!   If not set, defaults to `http://localhost/` (avoid in production).
 //! - **[`X402Middleware::with_supported_cache_ttl`]** configures the TTL for caching facilitator capabilities.
+//! - **[`X402Middleware::with_retry_policy`]** retries facilitator calls on `5xx` and network errors
+//!   with exponential backoff.
 //! - **[`X402LayerBuilder::with_description`]** is optional but helps the payer understand what is being paid for.
 //! - **[`X402LayerBuilder::with_mime_type`]** sets the MIME type of the protected resource (default: `application/json`).
 //! - **[`X402LayerBuilder::with_resource`]** explicitly sets the full URI of the protected resource.
+//! - **[`X402LayerBuilder::with_paywall_html`]** sets the HTML paywall shown to browsers, see [`paywall`].
+//! - **[`X402LayerBuilder::with_route`]** lists the route in the discovery index served by
+//!   [`discovery_router`] at `/.well-known/x402`, see [`discovery`].
+//! - **[`X402Middleware::with_stats_endpoint`]** serves the payment counters of the middleware
+//!   through [`X402Middleware::stats_router`], see [`stats`].
+//! - **[`X402Middleware::with_meter`]** reports each paid request to a usage metering sink,
+//!   see [`meter`].
+//! - **[`X402LayerBuilder::into_tower`]** enforces payments on any `tower` service, e.g. in a
+//!   hyper gateway without axum; see [`mod@tower`].
 
+pub mod discovery;
 pub mod facilitator_client;
 pub mod layer;
+pub mod meter;
 pub mod paygate;
+pub mod paywall;
+pub mod quota;
+pub mod replay;
+pub mod stats;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod tower;
 
+pub use discovery::{PricedRoutes, discovery_router};
 pub use layer::{X402LayerBuilder, X402Middleware};
-pub use paygate::{DynamicPriceTags, PaygateProtocol, PriceTagSource, StaticPriceTags};
+pub use paygate::{
+    AnyPaymentRequirements, AnyPriceTag, DynamicPriceTags, PaygateProtocol, PaymentRequired,
+    PriceTagSource, StaticPriceTags,
+};
+pub use stats::{PaymentStats, PaymentStatsSnapshot};
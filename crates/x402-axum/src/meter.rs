@@ -0,0 +1,283 @@
+//! Usage metering of paid requests.
+//!
+//! [`X402Middleware::with_meter`](crate::X402Middleware::with_meter) hands a [`PaymentEvent`]
+//! to a [`MeterSink`] for every request carrying a payment, once its settlement is resolved.
+//! Requests without a payment, including the ones served on free quota, are not metered.
+//!
+//! Events go through a bounded channel drained by a background task, so a slow sink does
+//! not delay responses. When the channel is full, new events are dropped. The task is
+//! spawned on the first event, and stops once every clone of the [`Meter`] is dropped.
+//!
+//! [`TracingMeterSink`] logs the events (with the `telemetry` feature), and
+//! [`BatchMeterSink`] groups them for sinks writing to an external store.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let x402 = X402Middleware::new("https://facilitator.x402.rs").with_meter(
+//!     BatchMeterSink::new(100, |events: Vec<PaymentEvent>| analytics.ingest(events)),
+//! );
+//! ```
+
+use http::StatusCode;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How a metered payment was resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentOutcome {
+    /// The payment was settled, or a previous settlement of it was reused.
+    Settled,
+    /// The payment failed verification.
+    VerificationFailed,
+    /// The payment failed to settle.
+    SettlementFailed,
+    /// The request was answered without settling the payment, e.g. because the handler
+    /// failed or the payment was replayed.
+    NotSettled,
+}
+
+/// A request carrying a payment, as reported to a [`MeterSink`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentEvent {
+    /// How the payment was resolved.
+    pub outcome: PaymentOutcome,
+    /// The payer, if settled.
+    pub payer: Option<String>,
+    /// The matched route pattern, or the request path outside of a router.
+    pub route: String,
+    /// The required amount, in token base units.
+    pub amount: Option<String>,
+    /// The asset of the payment requirements.
+    pub asset: Option<String>,
+    /// The network of the payment requirements: a network name in V1, a CAIP-2 chain ID in V2.
+    pub network: Option<String>,
+    /// The settlement transaction, if settled.
+    pub transaction: Option<String>,
+    /// Time from the start of payment handling to the response.
+    pub latency: Duration,
+    /// Status of the response.
+    pub status: StatusCode,
+}
+
+/// Receiver of [`PaymentEvent`]s.
+///
+/// Called from a background task, one event at a time. Sinks doing blocking I/O should
+/// hand it off, e.g. with `tokio::task::spawn_blocking`. Implemented for closures taking
+/// a [`PaymentEvent`].
+pub trait MeterSink: Send + Sync + 'static {
+    /// Records an event.
+    fn record(&self, event: PaymentEvent);
+
+    /// Writes out buffered events, if any. Called when the meter is idle for
+    /// [`Meter::FLUSH_INTERVAL`] and when it stops.
+    fn flush(&self) {}
+}
+
+impl<F> MeterSink for F
+where
+    F: Fn(PaymentEvent) + Send + Sync + 'static,
+{
+    fn record(&self, event: PaymentEvent) {
+        self(event)
+    }
+}
+
+/// Handle sending [`PaymentEvent`]s to a [`MeterSink`] through a background task.
+///
+/// Clones share the same channel and sink.
+#[derive(Clone)]
+pub struct Meter {
+    inner: Arc<MeterInner>,
+}
+
+struct MeterInner {
+    sink: Arc<dyn MeterSink>,
+    capacity: usize,
+    sender: OnceLock<mpsc::Sender<PaymentEvent>>,
+}
+
+impl Meter {
+    /// Default number of events waiting for the sink before new ones are dropped.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+    /// Idle time after which the sink is flushed.
+    pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Creates a meter buffering up to [`Self::DEFAULT_CAPACITY`] events.
+    pub fn new(sink: impl MeterSink) -> Self {
+        Self::with_capacity(sink, Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a meter buffering up to `capacity` events.
+    pub fn with_capacity(sink: impl MeterSink, capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(MeterInner {
+                sink: Arc::new(sink),
+                capacity: capacity.max(1),
+                sender: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Queues `event` for the sink, or drops it if the queue is full.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn record(&self, event: PaymentEvent) {
+        let sender = self.inner.sender.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel(self.inner.capacity);
+            tokio::spawn(drain(receiver, self.inner.sink.clone()));
+            sender
+        });
+        if sender.try_send(event).is_err() {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!("Meter queue is full, dropping payment event");
+        }
+    }
+}
+
+impl Debug for Meter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Meter")
+            .field("capacity", &self.inner.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Hands queued events to `sink` until every sender is dropped.
+async fn drain(mut receiver: mpsc::Receiver<PaymentEvent>, sink: Arc<dyn MeterSink>) {
+    loop {
+        match tokio::time::timeout(Meter::FLUSH_INTERVAL, receiver.recv()).await {
+            Ok(Some(event)) => sink.record(event),
+            Ok(None) => break,
+            Err(_) => sink.flush(),
+        }
+    }
+    sink.flush();
+}
+
+/// Sink logging each event at `INFO` level, under the `x402_axum::meter` target.
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingMeterSink;
+
+#[cfg(feature = "telemetry")]
+impl MeterSink for TracingMeterSink {
+    fn record(&self, event: PaymentEvent) {
+        tracing::info!(
+            target: "x402_axum::meter",
+            outcome = ?event.outcome,
+            payer = event.payer,
+            route = event.route,
+            amount = event.amount,
+            asset = event.asset,
+            network = event.network,
+            transaction = event.transaction,
+            latency_ms = event.latency.as_secs_f64() * 1000.0,
+            status = event.status.as_u16(),
+            "Payment metered"
+        );
+    }
+}
+
+/// Sink buffering events and handing them to a closure in batches.
+///
+/// A batch is handed over once it holds `batch_size` events, and otherwise when the sink
+/// is [flushed](MeterSink::flush).
+pub struct BatchMeterSink<F> {
+    batch_size: usize,
+    batch: Mutex<Vec<PaymentEvent>>,
+    on_batch: F,
+}
+
+impl<F> BatchMeterSink<F>
+where
+    F: Fn(Vec<PaymentEvent>) + Send + Sync + 'static,
+{
+    /// Creates a sink handing batches of up to `batch_size` events to `on_batch`.
+    pub fn new(batch_size: usize, on_batch: F) -> Self {
+        let batch_size = batch_size.max(1);
+        Self {
+            batch_size,
+            batch: Mutex::new(Vec::with_capacity(batch_size)),
+            on_batch,
+        }
+    }
+
+    fn take_batch(&self, full_only: bool) -> Option<Vec<PaymentEvent>> {
+        let mut batch = self.batch.lock().expect("meter batch lock poisoned");
+        if batch.is_empty() || (full_only && batch.len() < self.batch_size) {
+            return None;
+        }
+        Some(std::mem::replace(
+            &mut *batch,
+            Vec::with_capacity(self.batch_size),
+        ))
+    }
+}
+
+impl<F> MeterSink for BatchMeterSink<F>
+where
+    F: Fn(Vec<PaymentEvent>) + Send + Sync + 'static,
+{
+    fn record(&self, event: PaymentEvent) {
+        self.batch
+            .lock()
+            .expect("meter batch lock poisoned")
+            .push(event);
+        if let Some(batch) = self.take_batch(true) {
+            (self.on_batch)(batch);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(batch) = self.take_batch(false) {
+            (self.on_batch)(batch);
+        }
+    }
+}
+
+impl<F> Debug for BatchMeterSink<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchMeterSink")
+            .field("batch_size", &self.batch_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(route: &str) -> PaymentEvent {
+        PaymentEvent {
+            outcome: PaymentOutcome::Settled,
+            payer: None,
+            route: route.to_string(),
+            amount: None,
+            asset: None,
+            network: None,
+            transaction: None,
+            latency: Duration::ZERO,
+            status: StatusCode::OK,
+        }
+    }
+
+    #[tokio::test]
+    async fn batches_are_handed_over_when_full_or_flushed() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let meter = Meter::new(BatchMeterSink::new(2, move |batch: Vec<PaymentEvent>| {
+            let routes: Vec<_> = batch.into_iter().map(|event| event.route).collect();
+            sender.send(routes).unwrap();
+        }));
+        for route in ["/a", "/b", "/c"] {
+            meter.record(event(route));
+        }
+        assert_eq!(receiver.recv().await.unwrap(), vec!["/a", "/b"]);
+
+        // The partial batch is flushed when the meter stops
+        drop(meter);
+        assert_eq!(receiver.recv().await.unwrap(), vec!["/c"]);
+    }
+}
@@ -23,6 +23,15 @@
 //!     settle_before_execution: false,
 //!     accepts: Arc::new(price_tags),
 //!     resource: ResourceInfoBuilder::default().as_resource_info(&base_url, &uri),
+//!     extensions: Arc::new(ExtensionsJson::default()),
+//!     paywall: Some(Paywall::default()),
+//!     responder: None,
+//!     timing_headers: false,
+//!     expose_payment_response: true,
+//!     quota: None,
+//!     replay: None,
+//!     stats: None,
+//!     meter: None,
 //! };
 //!
 //! // Handle a request
@@ -32,16 +41,19 @@
 use axum_core::body::Body;
 use axum_core::extract::Request;
 use axum_core::response::{IntoResponse, Response};
-use http::{HeaderMap, HeaderValue, StatusCode, Uri};
+use http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use http_body_util::BodyExt;
 use serde_json::json;
 use std::convert::Infallible;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tower::Service;
 use url::Url;
 use x402_types::facilitator::Facilitator;
 use x402_types::proto;
+use x402_types::proto::bazaar::Bazaar;
 use x402_types::proto::{SupportedResponse, v1, v2};
 
 #[cfg(feature = "telemetry")]
@@ -51,6 +63,14 @@ use tracing::instrument;
 use x402_types::proto::v2::ExtensionsJson;
 use x402_types::util::Base64Bytes;
 
+use crate::meter::{Meter, PaymentEvent, PaymentOutcome};
+use crate::paywall::{self, Paywall, PaywallContext};
+use crate::quota::{
+    self, FREE_QUOTA_EXTRA_KEY, FREE_QUOTA_REMAINING_HEADER, FreeQuota, QuotaIdentity, QuotaUsage,
+};
+use crate::replay::{self, ReplayProtection};
+use crate::stats::PaymentStats;
+
 // ============================================================================
 // Common Types
 // ============================================================================
@@ -64,6 +84,10 @@ pub struct ResourceInfoBuilder {
     pub mime_type: Option<String>,
     /// Optional explicit URL of the protected resource
     pub url: Option<String>,
+    /// Input parameters of the endpoint, advertised for discovery
+    pub input_schema: Option<serde_json::Value>,
+    /// Output format of the endpoint, advertised for discovery
+    pub output_schema: Option<serde_json::Value>,
 }
 
 impl ResourceInfoBuilder {
@@ -94,6 +118,51 @@ impl ResourceInfoBuilder {
             url,
         }
     }
+
+    /// Determines the resource URL of a route pattern, for the discovery index.
+    ///
+    /// If `url` is set, returns it directly. Otherwise, appends the pattern to the origin
+    /// of the base URL, or of `http://localhost` if none is set.
+    pub fn as_route_resource_info(&self, base_url: Option<&Url>, path: &str) -> v2::ResourceInfo {
+        let url = self.url.clone().unwrap_or_else(|| {
+            let origin = base_url
+                .map(|url| url.origin().ascii_serialization())
+                .unwrap_or_else(|| "http://localhost".to_string());
+            format!("{origin}{path}")
+        });
+        v2::ResourceInfo {
+            description: self.description.clone(),
+            mime_type: self.mime_type.clone(),
+            url,
+        }
+    }
+
+    /// Adds the `bazaar` discovery extension built from the input and output schemas.
+    ///
+    /// Extensions are returned unchanged if neither schema is set, or if a `bazaar`
+    /// extension is already declared.
+    pub fn with_discovery(
+        &self,
+        extensions: Arc<ExtensionsJson>,
+        method: &Method,
+    ) -> Arc<ExtensionsJson> {
+        if self.input_schema.is_none() && self.output_schema.is_none() {
+            return extensions;
+        }
+        if extensions.get::<Bazaar>().is_some() {
+            return extensions;
+        }
+        let bazaar = Bazaar::http(
+            method.as_str(),
+            self.input_schema.as_ref(),
+            self.output_schema.as_ref(),
+        );
+        let mut extensions = (*extensions).clone();
+        extensions
+            .insert(bazaar)
+            .expect("failed to serialize bazaar extension");
+        Arc::new(extensions)
+    }
 }
 
 // ============================================================================
@@ -101,7 +170,7 @@ impl ResourceInfoBuilder {
 // ============================================================================
 
 /// Common verification errors shared between protocol versions.
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum VerificationError {
     #[error("{0} header is required")]
     PaymentHeaderRequired(&'static str),
@@ -116,7 +185,7 @@ pub enum VerificationError {
 }
 
 /// Paygate error type that wraps verification and settlement errors.
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum PaygateError {
     #[error(transparent)]
     Verification(#[from] VerificationError),
@@ -124,6 +193,180 @@ pub enum PaygateError {
     Settlement(String),
 }
 
+// ============================================================================
+// Payment-Required Challenge
+// ============================================================================
+
+/// The payment-required challenge of a protected route.
+///
+/// Holds the V1 and/or V2 challenge, depending on the protocol versions of the
+/// route's price tags, and the status code of the default response.
+#[derive(Debug, Clone)]
+pub struct PaymentRequired {
+    /// Status code of the default response: `402`, or `412` for failed V2 preconditions.
+    pub status: StatusCode,
+    /// The V1 challenge, sent as the JSON response body.
+    pub v1: Option<v1::PaymentRequired>,
+    /// The V2 challenge, sent base64-encoded in the `Payment-Required` header.
+    pub v2: Option<v2::PaymentRequired>,
+}
+
+impl PaymentRequired {
+    /// Returns the challenge as JSON.
+    ///
+    /// For routes mixing protocol versions, this is an array holding the V1 and V2 challenges.
+    pub fn to_json(&self) -> serde_json::Value {
+        let v1 = self.v1.as_ref().map(|v1| json!(v1));
+        let v2 = self.v2.as_ref().map(|v2| json!(v2));
+        match (v1, v2) {
+            (Some(v1), Some(v2)) => json!([v1, v2]),
+            (Some(challenge), None) | (None, Some(challenge)) => challenge,
+            (None, None) => serde_json::Value::Null,
+        }
+    }
+
+    /// Returns the accepted payment requirements, V1 ones first.
+    ///
+    /// Within each version, the order is that of the challenge's `accepts` array.
+    pub fn requirements(&self) -> Vec<AnyPaymentRequirements> {
+        let v1 = self.v1.iter().flat_map(|v1| v1.accepts.iter().cloned());
+        let v2 = self.v2.iter().flat_map(|v2| v2.accepts.iter().cloned());
+        v1.map(AnyPaymentRequirements::V1)
+            .chain(v2.map(AnyPaymentRequirements::V2))
+            .collect()
+    }
+
+    /// Returns the `Payment-Required` header value carrying the V2 challenge, if any.
+    pub fn v2_header(&self) -> Option<HeaderValue> {
+        let v2 = self.v2.as_ref()?;
+        let bytes = serde_json::to_vec(v2).expect("serialization failed");
+        let header = Base64Bytes::encode(&bytes);
+        Some(HeaderValue::from_bytes(header.as_ref()).expect("Failed to create header value"))
+    }
+
+    /// Returns the V1 challenge base64-encoded like the `Payment-Required` header, if any.
+    ///
+    /// Only sent to WebSocket upgrade requests, whose clients may not be able to read the
+    /// body of a rejected upgrade.
+    pub fn v1_header(&self) -> Option<HeaderValue> {
+        let v1 = self.v1.as_ref()?;
+        let bytes = serde_json::to_vec(v1).expect("serialization failed");
+        let header = Base64Bytes::encode(&bytes);
+        Some(HeaderValue::from_bytes(header.as_ref()).expect("Failed to create header value"))
+    }
+}
+
+/// Payment requirements of either protocol version, as advertised in a challenge.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(untagged)]
+pub enum AnyPaymentRequirements {
+    /// V1 requirements, advertised in the JSON response body.
+    V1(v1::PaymentRequirements),
+    /// V2 requirements, advertised in the `Payment-Required` header.
+    V2(v2::PaymentRequirements),
+}
+
+impl AnyPaymentRequirements {
+    /// Returns the V1 requirements, if this is one.
+    pub fn as_v1(&self) -> Option<&v1::PaymentRequirements> {
+        match self {
+            AnyPaymentRequirements::V1(requirements) => Some(requirements),
+            AnyPaymentRequirements::V2(_) => None,
+        }
+    }
+
+    /// Returns the V2 requirements, if this is one.
+    pub fn as_v2(&self) -> Option<&v2::PaymentRequirements> {
+        match self {
+            AnyPaymentRequirements::V1(_) => None,
+            AnyPaymentRequirements::V2(requirements) => Some(requirements),
+        }
+    }
+}
+
+/// Callback building the response to a payment-required challenge.
+///
+/// Receives the challenge and the parts of the incoming request. See
+/// [`X402Middleware::with_payment_required_responder`](crate::X402Middleware::with_payment_required_responder).
+#[derive(Clone)]
+pub struct PaymentRequiredResponder(Arc<ResponderFn>);
+
+type ResponderFn = dyn Fn(PaymentRequired, &http::request::Parts) -> Response + Send + Sync;
+
+impl PaymentRequiredResponder {
+    /// Wraps a responder callback.
+    pub fn new<F>(responder: F) -> Self
+    where
+        F: Fn(PaymentRequired, &http::request::Parts) -> Response + Send + Sync + 'static,
+    {
+        Self(Arc::new(responder))
+    }
+
+    /// Builds the response to `payment_required`.
+    ///
+    /// Guarantees the machine-readable challenge is present: the V2 challenge is added
+    /// in the `Payment-Required` header if the callback left it out. A V1-only challenge
+    /// travels in the body, so if the callback's JSON body does not contain it anywhere,
+    /// `fallback` is returned instead.
+    pub async fn respond(
+        &self,
+        payment_required: PaymentRequired,
+        parts: &http::request::Parts,
+        fallback: Response,
+    ) -> Response {
+        let v2_header = payment_required.v2_header();
+        let v1_json = payment_required.v1.as_ref().map(|v1| json!(v1));
+        let mut response = (self.0)(payment_required, parts);
+        if let Some(v2_header) = v2_header {
+            if !response.headers().contains_key(PAYMENT_REQUIRED_HEADER) {
+                response
+                    .headers_mut()
+                    .insert(PAYMENT_REQUIRED_HEADER, v2_header);
+            }
+            return response;
+        }
+        let Some(v1_json) = v1_json else {
+            return response;
+        };
+        let (parts, body) = response.into_parts();
+        let Ok(bytes) = body.collect().await.map(|body| body.to_bytes()) else {
+            return fallback;
+        };
+        let contains_challenge = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .is_ok_and(|body| contains_json(&body, &v1_json));
+        if contains_challenge {
+            Response::from_parts(parts, Body::from(bytes))
+        } else {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!(
+                "Payment-required responder omitted the V1 challenge, using default response"
+            );
+            fallback
+        }
+    }
+}
+
+impl std::fmt::Debug for PaymentRequiredResponder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PaymentRequiredResponder")
+            .field(&"<callback>")
+            .finish()
+    }
+}
+
+/// Header carrying the base64-encoded V2 challenge.
+const PAYMENT_REQUIRED_HEADER: &str = "Payment-Required";
+
+/// Returns whether `needle` equals `haystack` or any value nested in it.
+fn contains_json(haystack: &serde_json::Value, needle: &serde_json::Value) -> bool {
+    haystack == needle
+        || match haystack {
+            serde_json::Value::Array(values) => values.iter().any(|v| contains_json(v, needle)),
+            serde_json::Value::Object(map) => map.values().any(|v| contains_json(v, needle)),
+            _ => false,
+        }
+}
+
 // ============================================================================
 // PaygateProtocol Trait
 // ============================================================================
@@ -140,14 +383,40 @@ pub trait PaygateProtocol: Clone + Send + Sync + 'static {
     /// The HTTP header name for the payment payload.
     const PAYMENT_HEADER_NAME: &'static str;
 
+    /// The HTTP header name for the [`proto::PaymentResponse`] sent back after settlement.
+    const PAYMENT_RESPONSE_HEADER_NAME: &'static str = proto::PaymentResponse::V2_HEADER_NAME;
+
+    /// Returns the header name for the payment response to a given payload.
+    ///
+    /// Defaults to [`Self::PAYMENT_RESPONSE_HEADER_NAME`].
+    fn payment_response_header_name(_payload: &Self::PaymentPayload) -> &'static str {
+        Self::PAYMENT_RESPONSE_HEADER_NAME
+    }
+
+    /// Extracts the payment payload from the request headers.
+    ///
+    /// The default implementation decodes the base64 JSON payload found in
+    /// [`Self::PAYMENT_HEADER_NAME`].
+    fn payment_payload_from_headers(
+        headers: &HeaderMap,
+    ) -> Result<Self::PaymentPayload, VerificationError> {
+        let header = extract_payment_header(headers, Self::PAYMENT_HEADER_NAME).ok_or(
+            VerificationError::PaymentHeaderRequired(Self::PAYMENT_HEADER_NAME),
+        )?;
+        extract_payment_payload::<Self::PaymentPayload>(header)
+            .ok_or(VerificationError::InvalidPaymentHeader)
+    }
+
     /// Constructs a verify request from the payment payload and accepted requirements.
     ///
-    /// The `resource` parameter provides resource information that may be needed
-    /// for protocol-specific requirements (e.g., V1 includes resource info in PaymentRequirements).
+    /// The `resource` and `extensions` parameters provide information that may be needed
+    /// for protocol-specific requirements (e.g., V1 includes resource info and the discovery
+    /// output schema in PaymentRequirements).
     fn make_verify_request(
         payload: Self::PaymentPayload,
         accepts: &[Self],
         resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
     ) -> Result<proto::VerifyRequest, VerificationError>;
 
     /// Converts an error into an HTTP response with appropriate format.
@@ -158,6 +427,14 @@ pub trait PaygateProtocol: Clone + Send + Sync + 'static {
         extensions: &ExtensionsJson,
     ) -> Response;
 
+    /// Builds the payment-required challenge handed to paywalls and custom responders.
+    fn payment_required(
+        err: &VerificationError,
+        accepts: &[Self],
+        resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
+    ) -> PaymentRequired;
+
     /// Converts the verify response to the protocol-specific format and validates it.
     fn validate_verify_response(
         verify_response: proto::VerifyResponse,
@@ -168,6 +445,23 @@ pub trait PaygateProtocol: Clone + Send + Sync + 'static {
     /// Called by middleware when building 402 response to add extra information like fee payer
     /// from the facilitator's supported endpoints.
     fn enrich_with_capabilities(&mut self, capabilities: &SupportedResponse);
+
+    /// Inserts an entry into the `extra` object of the payment requirements.
+    ///
+    /// Used to advertise free quota metadata in 402 responses.
+    fn insert_extra(&mut self, key: &str, value: serde_json::Value);
+
+    /// Returns whether the price is zero, in which case no payment is required.
+    ///
+    /// Defaults to `false`.
+    fn is_free(&self) -> bool {
+        false
+    }
+}
+
+/// Returns whether `amount`, in atomic token units, is zero.
+fn is_zero_amount(amount: &str) -> bool {
+    !amount.is_empty() && amount.bytes().all(|digit| digit == b'0')
 }
 
 // ============================================================================
@@ -179,10 +473,13 @@ impl PaygateProtocol for v1::PriceTag {
 
     const PAYMENT_HEADER_NAME: &'static str = "X-PAYMENT";
 
+    const PAYMENT_RESPONSE_HEADER_NAME: &'static str = proto::PaymentResponse::V1_HEADER_NAME;
+
     fn make_verify_request(
         payment_payload: Self::PaymentPayload,
         accepts: &[Self],
         resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
     ) -> Result<proto::VerifyRequest, VerificationError> {
         let selected = accepts
             .iter()
@@ -195,7 +492,9 @@ impl PaygateProtocol for v1::PriceTag {
         let verify_request = v1::VerifyRequest {
             x402_version: v1::X402Version1,
             payment_payload,
-            payment_requirements: price_tag_to_v1_requirements_with_resource(selected, resource),
+            payment_requirements: price_tag_to_v1_requirements_with_resource(
+                selected, resource, extensions,
+            ),
         };
 
         verify_request
@@ -207,18 +506,12 @@ impl PaygateProtocol for v1::PriceTag {
         err: PaygateError,
         accepts: &[Self],
         resource: &v2::ResourceInfo,
-        _extensions: &ExtensionsJson,
+        extensions: &ExtensionsJson,
     ) -> Response {
         match err {
             PaygateError::Verification(err) => {
-                let payment_required_response = v1::PaymentRequired {
-                    error: Some(err.to_string()),
-                    accepts: accepts
-                        .iter()
-                        .map(|pt| price_tag_to_v1_requirements_with_resource(pt, resource))
-                        .collect(),
-                    x402_version: v1::X402Version1,
-                };
+                let payment_required_response =
+                    v1_payment_required(&err, accepts, resource, extensions);
                 let payment_required_response_bytes =
                     serde_json::to_vec(&payment_required_response).expect("serialization failed");
                 let body = Body::from(payment_required_response_bytes);
@@ -245,6 +538,19 @@ impl PaygateProtocol for v1::PriceTag {
         }
     }
 
+    fn payment_required(
+        err: &VerificationError,
+        accepts: &[Self],
+        resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
+    ) -> PaymentRequired {
+        PaymentRequired {
+            status: StatusCode::PAYMENT_REQUIRED,
+            v1: Some(v1_payment_required(err, accepts, resource, extensions)),
+            v2: None,
+        }
+    }
+
     fn validate_verify_response(
         verify_response: proto::VerifyResponse,
     ) -> Result<(), VerificationError> {
@@ -263,12 +569,40 @@ impl PaygateProtocol for v1::PriceTag {
     fn enrich_with_capabilities(&mut self, capabilities: &SupportedResponse) {
         self.enrich(capabilities);
     }
+
+    fn insert_extra(&mut self, key: &str, value: serde_json::Value) {
+        quota::insert_extra(&mut self.extra, key, value);
+    }
+
+    fn is_free(&self) -> bool {
+        is_zero_amount(&self.amount)
+    }
+}
+
+/// Builds the V1 payment-required challenge.
+fn v1_payment_required(
+    err: &VerificationError,
+    accepts: &[v1::PriceTag],
+    resource: &v2::ResourceInfo,
+    extensions: &ExtensionsJson,
+) -> v1::PaymentRequired {
+    v1::PaymentRequired {
+        error: Some(err.to_string()),
+        accepts: accepts
+            .iter()
+            .map(|pt| price_tag_to_v1_requirements_with_resource(pt, resource, extensions))
+            .collect(),
+        x402_version: v1::X402Version1,
+    }
 }
 
 /// Helper function to convert V1PriceTag to v1::PaymentRequirements with resource info.
+///
+/// A declared `bazaar` extension is carried over as the V1 `outputSchema`.
 fn price_tag_to_v1_requirements_with_resource(
     price_tag: &v1::PriceTag,
     resource: &v2::ResourceInfo,
+    extensions: &ExtensionsJson,
 ) -> v1::PaymentRequirements {
     v1::PaymentRequirements {
         scheme: price_tag.scheme.clone(),
@@ -277,7 +611,9 @@ fn price_tag_to_v1_requirements_with_resource(
         resource: resource.url.clone(),
         description: resource.description.clone().unwrap_or_default(),
         mime_type: resource.mime_type.clone(),
-        output_schema: None,
+        output_schema: extensions
+            .get::<Bazaar>()
+            .map(|bazaar| bazaar.to_v1_output_schema()),
         pay_to: price_tag.pay_to.clone(),
         max_timeout_seconds: price_tag.max_timeout_seconds,
         asset: price_tag.asset.clone(),
@@ -294,10 +630,13 @@ impl PaygateProtocol for v2::PriceTag {
 
     const PAYMENT_HEADER_NAME: &'static str = "Payment-Signature";
 
+    const PAYMENT_RESPONSE_HEADER_NAME: &'static str = proto::PaymentResponse::V2_HEADER_NAME;
+
     fn make_verify_request(
         payment_payload: Self::PaymentPayload,
         accepts: &[Self],
         _resource: &v2::ResourceInfo,
+        _extensions: &ExtensionsJson,
     ) -> Result<proto::VerifyRequest, VerificationError> {
         // In V2, the accepted requirements are embedded in the payload
         // Resource info is already included in the payment payload from the client
@@ -305,17 +644,24 @@ impl PaygateProtocol for v2::PriceTag {
 
         // Find matching requirements from our accepts list
         // According to V2 spec, the accepted requirements must exactly match
-        // one of the requirements we offered in PaymentRequired.accepts
-        let selected = accepts
+        // one of the requirements we offered in PaymentRequired.accepts,
+        // except for the free quota metadata which changes between challenges
+        let accepted_without_quota = quota::without_quota_extra(accepted);
+        accepts
             .iter()
-            .find(|price_tag| **price_tag == *accepted)
+            .find(|price_tag| {
+                **price_tag == *accepted
+                    || quota::without_quota_extra(&price_tag.requirements) == accepted_without_quota
+            })
             .ok_or(VerificationError::NoPaymentMatching)?;
 
-        // Build the V2 verify request
+        // Facilitators check the accepted requirements against the ones to verify,
+        // so send them as the client echoed them
+        let payment_requirements = accepted.clone();
         let verify_request = v2::VerifyRequest {
             x402_version: v2::X402Version2,
             payment_payload,
-            payment_requirements: selected.requirements.clone(),
+            payment_requirements,
         };
 
         let raw = serde_json::to_value(&verify_request)
@@ -333,18 +679,9 @@ impl PaygateProtocol for v2::PriceTag {
     ) -> Response {
         match err {
             PaygateError::Verification(err) => {
-                let status_code = if let VerificationError::PreconditionFailed(_) = &err {
-                    StatusCode::PRECONDITION_FAILED
-                } else {
-                    StatusCode::PAYMENT_REQUIRED
-                };
-                let payment_required_response = v2::PaymentRequired {
-                    error: Some(err.to_string()),
-                    accepts: accepts.iter().map(|pt| pt.requirements.clone()).collect(),
-                    x402_version: v2::X402Version2,
-                    resource: Some(resource.clone()),
-                    extensions: extensions.clone(),
-                };
+                let status_code = v2_status_code(&err);
+                let payment_required_response =
+                    v2_payment_required(&err, accepts, resource, extensions);
                 // V2 sends payment required in the "Payment-Required" header (base64 encoded)
                 let payment_required_bytes =
                     serde_json::to_vec(&payment_required_response).expect("serialization failed");
@@ -375,6 +712,19 @@ impl PaygateProtocol for v2::PriceTag {
         }
     }
 
+    fn payment_required(
+        err: &VerificationError,
+        accepts: &[Self],
+        resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
+    ) -> PaymentRequired {
+        PaymentRequired {
+            status: v2_status_code(err),
+            v1: None,
+            v2: Some(v2_payment_required(err, accepts, resource, extensions)),
+        }
+    }
+
     fn validate_verify_response(
         verify_response: proto::VerifyResponse,
     ) -> Result<(), VerificationError> {
@@ -397,6 +747,268 @@ impl PaygateProtocol for v2::PriceTag {
     fn enrich_with_capabilities(&mut self, capabilities: &SupportedResponse) {
         self.enrich(capabilities);
     }
+
+    fn insert_extra(&mut self, key: &str, value: serde_json::Value) {
+        quota::insert_extra(&mut self.requirements.extra, key, value);
+    }
+
+    fn is_free(&self) -> bool {
+        is_zero_amount(&self.requirements.amount)
+    }
+}
+
+/// Status code of a V2 challenge: `412` for failed preconditions, `402` otherwise.
+fn v2_status_code(err: &VerificationError) -> StatusCode {
+    if let VerificationError::PreconditionFailed(_) = err {
+        StatusCode::PRECONDITION_FAILED
+    } else {
+        StatusCode::PAYMENT_REQUIRED
+    }
+}
+
+/// Builds the V2 payment-required challenge.
+fn v2_payment_required(
+    err: &VerificationError,
+    accepts: &[v2::PriceTag],
+    resource: &v2::ResourceInfo,
+    extensions: &ExtensionsJson,
+) -> v2::PaymentRequired {
+    v2::PaymentRequired {
+        error: Some(err.to_string()),
+        accepts: accepts.iter().map(|pt| pt.requirements.clone()).collect(),
+        x402_version: v2::X402Version2,
+        resource: Some(resource.clone()),
+        extensions: extensions.clone(),
+    }
+}
+
+// ============================================================================
+// Mixed-version Protocol Implementation (on AnyPriceTag)
+// ============================================================================
+
+/// A price tag of either protocol version.
+///
+/// Lets a single route offer V1 and V2 payment options side by side, e.g. USDC on
+/// Base via V1 and USDC on Solana via V2. Built implicitly by
+/// [`X402LayerBuilder::or_price_tag`](crate::X402LayerBuilder::or_price_tag), or
+/// returned from a dynamic pricing callback.
+///
+/// The 402 challenge carries both versions: V1 requirements go into the JSON body,
+/// V2 requirements into the `Payment-Required` header. Within each version, the
+/// order of the accepts list follows insertion order.
+#[derive(Clone, Debug)]
+pub enum AnyPriceTag {
+    /// A V1 price tag, paid via the `X-PAYMENT` header.
+    V1(v1::PriceTag),
+    /// A V2 price tag, paid via the `Payment-Signature` header.
+    V2(v2::PriceTag),
+}
+
+impl AnyPriceTag {
+    /// Returns the V1 price tag, if this is one.
+    pub fn as_v1(&self) -> Option<&v1::PriceTag> {
+        match self {
+            AnyPriceTag::V1(price_tag) => Some(price_tag),
+            AnyPriceTag::V2(_) => None,
+        }
+    }
+
+    /// Returns the V2 price tag, if this is one.
+    pub fn as_v2(&self) -> Option<&v2::PriceTag> {
+        match self {
+            AnyPriceTag::V1(_) => None,
+            AnyPriceTag::V2(price_tag) => Some(price_tag),
+        }
+    }
+}
+
+impl From<v1::PriceTag> for AnyPriceTag {
+    fn from(price_tag: v1::PriceTag) -> Self {
+        AnyPriceTag::V1(price_tag)
+    }
+}
+
+impl From<v2::PriceTag> for AnyPriceTag {
+    fn from(price_tag: v2::PriceTag) -> Self {
+        AnyPriceTag::V2(price_tag)
+    }
+}
+
+/// Payment payload submitted against a list of [`AnyPriceTag`]s.
+///
+/// The variant is selected by the payload's `x402Version` field.
+#[derive(Debug)]
+pub enum AnyPaymentPayload {
+    V1(<v1::PriceTag as PaygateProtocol>::PaymentPayload),
+    V2(Box<<v2::PriceTag as PaygateProtocol>::PaymentPayload>),
+}
+
+impl<'de> serde::Deserialize<'de> for AnyPaymentPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Version {
+            x402_version: u8,
+        }
+
+        use serde::de::Error;
+        let raw = Box::<serde_json::value::RawValue>::deserialize(deserializer)?;
+        let version: Version = serde_json::from_str(raw.get()).map_err(D::Error::custom)?;
+        match version.x402_version {
+            v1::X402Version1::VALUE => serde_json::from_str(raw.get())
+                .map(AnyPaymentPayload::V1)
+                .map_err(D::Error::custom),
+            v2::X402Version2::VALUE => serde_json::from_str(raw.get())
+                .map(|payload| AnyPaymentPayload::V2(Box::new(payload)))
+                .map_err(D::Error::custom),
+            other => Err(D::Error::custom(format!("unsupported x402Version {other}"))),
+        }
+    }
+}
+
+/// Splits mixed price tags into V1 and V2 lists, preserving insertion order.
+fn split_price_tags(accepts: &[AnyPriceTag]) -> (Vec<v1::PriceTag>, Vec<v2::PriceTag>) {
+    let v1_accepts = accepts
+        .iter()
+        .filter_map(AnyPriceTag::as_v1)
+        .cloned()
+        .collect();
+    let v2_accepts = accepts
+        .iter()
+        .filter_map(AnyPriceTag::as_v2)
+        .cloned()
+        .collect();
+    (v1_accepts, v2_accepts)
+}
+
+impl PaygateProtocol for AnyPriceTag {
+    type PaymentPayload = AnyPaymentPayload;
+
+    const PAYMENT_HEADER_NAME: &'static str =
+        <v2::PriceTag as PaygateProtocol>::PAYMENT_HEADER_NAME;
+
+    /// Reads the V2 `Payment-Signature` header, falling back to the V1 `X-PAYMENT` header.
+    fn payment_payload_from_headers(
+        headers: &HeaderMap,
+    ) -> Result<Self::PaymentPayload, VerificationError> {
+        if headers.contains_key(<v2::PriceTag as PaygateProtocol>::PAYMENT_HEADER_NAME) {
+            v2::PriceTag::payment_payload_from_headers(headers)
+                .map(|payload| AnyPaymentPayload::V2(Box::new(payload)))
+        } else if headers.contains_key(<v1::PriceTag as PaygateProtocol>::PAYMENT_HEADER_NAME) {
+            v1::PriceTag::payment_payload_from_headers(headers).map(AnyPaymentPayload::V1)
+        } else {
+            Err(VerificationError::PaymentHeaderRequired(
+                Self::PAYMENT_HEADER_NAME,
+            ))
+        }
+    }
+
+    fn payment_response_header_name(payload: &Self::PaymentPayload) -> &'static str {
+        match payload {
+            AnyPaymentPayload::V1(_) => {
+                <v1::PriceTag as PaygateProtocol>::PAYMENT_RESPONSE_HEADER_NAME
+            }
+            AnyPaymentPayload::V2(_) => {
+                <v2::PriceTag as PaygateProtocol>::PAYMENT_RESPONSE_HEADER_NAME
+            }
+        }
+    }
+
+    fn make_verify_request(
+        payload: Self::PaymentPayload,
+        accepts: &[Self],
+        resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
+    ) -> Result<proto::VerifyRequest, VerificationError> {
+        let (v1_accepts, v2_accepts) = split_price_tags(accepts);
+        match payload {
+            AnyPaymentPayload::V1(payload) => {
+                v1::PriceTag::make_verify_request(payload, &v1_accepts, resource, extensions)
+            }
+            AnyPaymentPayload::V2(payload) => {
+                v2::PriceTag::make_verify_request(*payload, &v2_accepts, resource, extensions)
+            }
+        }
+    }
+
+    fn error_into_response(
+        err: PaygateError,
+        accepts: &[Self],
+        resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
+    ) -> Response {
+        let (v1_accepts, v2_accepts) = split_price_tags(accepts);
+        if v2_accepts.is_empty() {
+            return v1::PriceTag::error_into_response(err, &v1_accepts, resource, extensions);
+        }
+        if v1_accepts.is_empty() {
+            return v2::PriceTag::error_into_response(err, &v2_accepts, resource, extensions);
+        }
+        // Both versions offered: status and `Payment-Required` header come from V2,
+        // the JSON body carries the V1 challenge.
+        let v1_response =
+            v1::PriceTag::error_into_response(err.clone(), &v1_accepts, resource, extensions);
+        let v2_response = v2::PriceTag::error_into_response(err, &v2_accepts, resource, extensions);
+        let (mut parts, _) = v2_response.into_parts();
+        let (v1_parts, body) = v1_response.into_parts();
+        if let Some(content_type) = v1_parts.headers.get(http::header::CONTENT_TYPE) {
+            parts
+                .headers
+                .insert(http::header::CONTENT_TYPE, content_type.clone());
+        }
+        Response::from_parts(parts, body)
+    }
+
+    fn payment_required(
+        err: &VerificationError,
+        accepts: &[Self],
+        resource: &v2::ResourceInfo,
+        extensions: &ExtensionsJson,
+    ) -> PaymentRequired {
+        let (v1_accepts, v2_accepts) = split_price_tags(accepts);
+        let v1 = (!v1_accepts.is_empty())
+            .then(|| v1_payment_required(err, &v1_accepts, resource, extensions));
+        let v2 = (!v2_accepts.is_empty())
+            .then(|| v2_payment_required(err, &v2_accepts, resource, extensions));
+        // Status follows the V2 challenge when one is present, as in `error_into_response`.
+        let status = if v2.is_some() {
+            v2_status_code(err)
+        } else {
+            StatusCode::PAYMENT_REQUIRED
+        };
+        PaymentRequired { status, v1, v2 }
+    }
+
+    fn validate_verify_response(
+        verify_response: proto::VerifyResponse,
+    ) -> Result<(), VerificationError> {
+        // V1 and V2 share the verify response format.
+        v2::PriceTag::validate_verify_response(verify_response)
+    }
+
+    fn enrich_with_capabilities(&mut self, capabilities: &SupportedResponse) {
+        match self {
+            AnyPriceTag::V1(price_tag) => price_tag.enrich_with_capabilities(capabilities),
+            AnyPriceTag::V2(price_tag) => price_tag.enrich_with_capabilities(capabilities),
+        }
+    }
+
+    fn insert_extra(&mut self, key: &str, value: serde_json::Value) {
+        match self {
+            AnyPriceTag::V1(price_tag) => price_tag.insert_extra(key, value),
+            AnyPriceTag::V2(price_tag) => price_tag.insert_extra(key, value),
+        }
+    }
+
+    fn is_free(&self) -> bool {
+        match self {
+            AnyPriceTag::V1(price_tag) => price_tag.is_free(),
+            AnyPriceTag::V2(price_tag) => price_tag.is_free(),
+        }
+    }
 }
 
 // ============================================================================
@@ -411,7 +1023,8 @@ impl PaygateProtocol for v2::PriceTag {
 pub struct Paygate<TPriceTag, TFacilitator> {
     /// The facilitator for verifying and settling payments
     pub facilitator: TFacilitator,
-    /// Whether to settle before or after request execution
+    /// Whether to settle before or after request execution. WebSocket upgrade requests
+    /// are always settled before execution, see [`is_websocket_upgrade`].
     pub settle_before_execution: bool,
     /// Accepted payment requirements
     pub accepts: Arc<Vec<TPriceTag>>,
@@ -419,8 +1032,71 @@ pub struct Paygate<TPriceTag, TFacilitator> {
     pub resource: v2::ResourceInfo,
     /// Protocol extensions declared by the protected endpoint
     pub extensions: Arc<ExtensionsJson>,
+    /// Paywall page rendered for browsers, if enabled
+    pub paywall: Option<Paywall>,
+    /// Custom builder of payment-required responses, if set
+    pub responder: Option<PaymentRequiredResponder>,
+    /// Whether to add `X-Payment-Verified-Ms` and `X-Payment-Total-Ms` headers to paid
+    /// responses. Only takes effect with the `timing-headers` feature.
+    pub timing_headers: bool,
+    /// Whether to send the payment response header to the client. The settlement is
+    /// inserted into the response extensions either way.
+    pub expose_payment_response: bool,
+    /// Free requests allowed per client before payment is required, if set
+    pub quota: Option<FreeQuota>,
+    /// Rejection of payments presented more than once, if enabled
+    pub replay: Option<ReplayProtection>,
+    /// Payment counters, if collected
+    pub stats: Option<Arc<PaymentStats>>,
+    /// Usage metering of requests carrying a payment, if enabled
+    pub meter: Option<Meter>,
+}
+
+/// Phase boundaries of a paid request, sampled for the timing headers.
+struct PaymentTiming {
+    #[cfg_attr(not(feature = "timing-headers"), allow(dead_code))]
+    started: Instant,
+    facilitator: Duration,
+}
+
+impl PaymentTiming {
+    fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            facilitator: Duration::ZERO,
+        }
+    }
+
+    /// Awaits a facilitator call, adding its duration to the facilitator phase.
+    async fn facilitator<T>(&mut self, call: impl Future<Output = T>) -> T {
+        let phase = Instant::now();
+        let output = call.await;
+        self.facilitator += phase.elapsed();
+        output
+    }
+
+    /// Adds the facilitator (verify + settle) and total durations in milliseconds.
+    #[cfg(feature = "timing-headers")]
+    fn with_headers(&self, mut response: Response) -> Response {
+        fn millis(duration: Duration) -> HeaderValue {
+            let millis = format!("{:.3}", duration.as_secs_f64() * 1000.0);
+            HeaderValue::from_str(&millis).expect("formatted number is a valid header value")
+        }
+        let headers = response.headers_mut();
+        headers.insert(VERIFIED_MS_HEADER, millis(self.facilitator));
+        headers.insert(TOTAL_MS_HEADER, millis(self.started.elapsed()));
+        response
+    }
 }
 
+/// Response header carrying the time spent in facilitator verify and settle calls.
+#[cfg(feature = "timing-headers")]
+pub const VERIFIED_MS_HEADER: &str = "X-Payment-Verified-Ms";
+
+/// Response header carrying the total request duration, including the handler.
+#[cfg(feature = "timing-headers")]
+pub const TOTAL_MS_HEADER: &str = "X-Payment-Total-Ms";
+
 impl<TPriceTag, TFacilitator> Paygate<TPriceTag, TFacilitator> {
     /// Calls the inner service with proper telemetry instrumentation.
     async fn call_inner<
@@ -469,48 +1145,336 @@ where
         self,
         inner: S,
         req: http::Request<ReqBody>,
-    ) -> Result<Response, Infallible>
+    ) -> Result<Response, Infallible>
+    where
+        S::Response: IntoResponse,
+        S::Error: IntoResponse,
+        S::Future: Send,
+    {
+        if let Some(stats) = &self.stats {
+            stats.record_request();
+        }
+        // Requests without a payment may be served on free quota
+        let mut quota_usage = None;
+        if let Some(quota) = &self.quota
+            && TPriceTag::payment_payload_from_headers(req.headers()).is_err()
+            && let Some(key) = quota.identity().request_key(&req)
+            && let Some(usage) = quota.acquire(&key).await
+        {
+            if usage.granted {
+                return Ok(self.serve_free(quota, &key, usage, inner, req).await);
+            }
+            quota_usage = Some(usage);
+        }
+        let upgrade = is_websocket_upgrade(req.headers());
+        let paywall = self
+            .paywall
+            .clone()
+            .filter(|_| !upgrade && paywall::prefers_html(req.headers()));
+        // Custom responders see the request parts, keep a copy before the request is consumed
+        let responder = self.responder.clone().filter(|_| paywall.is_none());
+        let (req, request_parts) = match responder {
+            Some(_) => {
+                let (parts, body) = req.into_parts();
+                let request_parts = parts.clone();
+                (http::Request::from_parts(parts, body), Some(request_parts))
+            }
+            None => (req, None),
+        };
+        let started = Instant::now();
+        let route = self.meter.as_ref().map(|_| metered_route(&req));
+        let mut verify_request = None;
+        let err = match self
+            .handle_payment(inner, req, route.as_ref().map(|_| &mut verify_request))
+            .await
+        {
+            Ok(response) => {
+                if let (Some(meter), Some(route)) = (&self.meter, route) {
+                    meter.record(payment_event(
+                        &response,
+                        None,
+                        route,
+                        verify_request.as_ref(),
+                        started,
+                    ));
+                }
+                return Ok(response);
+            }
+            Err(err) => err,
+        };
+        if let Some(stats) = &self.stats {
+            match &err {
+                PaygateError::Verification(VerificationError::PaymentHeaderRequired(_)) => {
+                    stats.record_refused()
+                }
+                _ => stats.record_failure(),
+            }
+        }
+        let outcome = match &err {
+            PaygateError::Verification(VerificationError::PaymentHeaderRequired(_)) => None,
+            PaygateError::Verification(_) => Some(PaymentOutcome::VerificationFailed),
+            PaygateError::Settlement(_) => Some(PaymentOutcome::SettlementFailed),
+        };
+        let meter = self.meter.clone();
+        let response = self
+            .payment_required_response(err, quota_usage, upgrade, paywall, responder, request_parts)
+            .await;
+        if let (Some(meter), Some(route), Some(outcome)) = (meter, route, outcome) {
+            meter.record(payment_event(
+                &response,
+                Some(outcome),
+                route,
+                verify_request.as_ref(),
+                started,
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Builds the response to a request whose payment is missing or failed: the 402
+    /// challenge, the browser paywall, or the response of the custom responder.
+    async fn payment_required_response(
+        mut self,
+        err: PaygateError,
+        quota_usage: Option<QuotaUsage>,
+        upgrade: bool,
+        paywall: Option<Paywall>,
+        responder: Option<PaymentRequiredResponder>,
+        request_parts: Option<http::request::Parts>,
+    ) -> Response {
+        if let Some(quota) = &self.quota {
+            let extra = quota.extra(quota_usage.as_ref());
+            let accepts = self
+                .accepts
+                .iter()
+                .map(|price_tag| {
+                    let mut price_tag = price_tag.clone();
+                    price_tag.insert_extra(FREE_QUOTA_EXTRA_KEY, extra.clone());
+                    price_tag
+                })
+                .collect();
+            self.accepts = Arc::new(accepts);
+        }
+        // Get enriched accepts for 402 response
+        let mut default_response = TPriceTag::error_into_response(
+            err.clone(),
+            &self.accepts,
+            &self.resource,
+            &self.extensions,
+        );
+        let PaygateError::Verification(err) = err else {
+            return default_response;
+        };
+        let payment_required =
+            || TPriceTag::payment_required(&err, &self.accepts, &self.resource, &self.extensions);
+        // The V1 challenge travels in the body, which WebSocket clients rarely expose
+        if upgrade
+            && !default_response
+                .headers()
+                .contains_key(PAYMENT_REQUIRED_HEADER)
+            && let Some(v1_header) = payment_required().v1_header()
+        {
+            default_response
+                .headers_mut()
+                .insert(PAYMENT_REQUIRED_HEADER, v1_header);
+        }
+        if let Some(paywall) = paywall {
+            let context = PaywallContext::new(
+                self.resource.clone(),
+                err.to_string(),
+                payment_required().to_json(),
+            );
+            return paywall.render(&context, default_response);
+        }
+        if let (Some(responder), Some(request_parts)) = (responder, request_parts) {
+            return responder
+                .respond(payment_required(), &request_parts, default_response)
+                .await;
+        }
+        default_response
+    }
+
+    /// Gets enriched price tags with facilitator capabilities.
+    pub async fn enrich_accepts(&mut self) {
+        // Try to get capabilities, use empty if fails
+        let capabilities = self.facilitator.supported().await.unwrap_or_default();
+
+        let accepts = self
+            .accepts
+            .iter()
+            .map(|pt| {
+                let mut pt_clone = pt.clone();
+                pt_clone.enrich_with_capabilities(&capabilities);
+                pt_clone
+            })
+            .collect::<Vec<_>>();
+        self.accepts = Arc::new(accepts);
+    }
+
+    /// Handles an incoming request, returning errors as `PaygateError`.
+    ///
+    /// This is the fallible version of `handle_request` that returns an actual error
+    /// instead of turning it into 402 Payment Required response. With replay protection,
+    /// a payment presented again is answered with `409 Conflict` without reaching the
+    /// facilitator, or served again with its original settlement, see
+    /// [`ReplayPolicy`](crate::replay::ReplayPolicy).
+    pub async fn handle_request_fallible<
+        ReqBody,
+        ResBody,
+        S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    >(
+        &self,
+        inner: S,
+        req: http::Request<ReqBody>,
+    ) -> Result<Response, PaygateError>
+    where
+        S::Response: IntoResponse,
+        S::Error: IntoResponse,
+        S::Future: Send,
+    {
+        self.handle_payment(inner, req, None).await
+    }
+
+    /// Implements [`Self::handle_request_fallible`], keeping a copy of the verify request
+    /// in `metered` if given.
+    async fn handle_payment<
+        ReqBody,
+        ResBody,
+        S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    >(
+        &self,
+        inner: S,
+        req: http::Request<ReqBody>,
+        metered: Option<&mut Option<proto::VerifyRequest>>,
+    ) -> Result<Response, PaygateError>
+    where
+        S::Response: IntoResponse,
+        S::Error: IntoResponse,
+        S::Future: Send,
+    {
+        let timing = PaymentTiming::start();
+
+        // Extract payment payload from headers
+        let payment_payload = TPriceTag::payment_payload_from_headers(req.headers())?;
+        let response_header_name = TPriceTag::payment_response_header_name(&payment_payload);
+
+        let verify_request = TPriceTag::make_verify_request(
+            payment_payload,
+            &self.accepts,
+            &self.resource,
+            &self.extensions,
+        )?;
+        if let Some(metered) = metered {
+            *metered = Some(verify_request.clone());
+        }
+
+        // Payments already presented are rejected before reaching the facilitator
+        let replay_claim = match (&self.replay, replay::replay_key(&verify_request)) {
+            (Some(replay), Some(key)) => {
+                if !replay.claim(&key, &verify_request).await {
+                    if let Some(header_value) =
+                        replay.reusable_settlement(&key, &verify_request).await
+                    {
+                        #[cfg(feature = "telemetry")]
+                        tracing::info!("Serving retried payment with its original settlement");
+                        if let Some(stats) = &self.stats {
+                            stats.record_paid(None);
+                        }
+                        return Ok(self
+                            .serve_settled(inner, req, response_header_name, header_value)
+                            .await);
+                    }
+                    #[cfg(feature = "telemetry")]
+                    tracing::info!("Rejecting replayed payment");
+                    if let Some(stats) = &self.stats {
+                        stats.record_refused();
+                    }
+                    return Ok(replay::replayed_response());
+                }
+                Some((replay, key))
+            }
+            _ => None,
+        };
+        let result = self
+            .serve_paid(inner, req, &verify_request, response_header_name, timing)
+            .await;
+        if let Some(stats) = &self.stats
+            && let Ok(response) = &result
+            && response.extensions().get::<proto::PaymentResponse>().is_some()
+        {
+            let amount = required_amount(&verify_request).and_then(|amount| amount.parse().ok());
+            stats.record_paid(amount);
+        }
+        // The buyer may retry a payment that was not settled
+        if let Some((replay, key)) = replay_claim {
+            match &result {
+                Ok(response) => match response
+                    .extensions()
+                    .get::<proto::PaymentResponse>()
+                    .and_then(|payment_response| encode_payment_response(payment_response).ok())
+                {
+                    Some(header_value) => {
+                        replay
+                            .record_settlement(&key, &verify_request, &header_value)
+                            .await
+                    }
+                    None => replay.release(&key).await,
+                },
+                // A failed settlement may still land on chain
+                Err(PaygateError::Settlement(_)) => {}
+                Err(PaygateError::Verification(_)) => replay.release(&key).await,
+            }
+        }
+        result
+    }
+
+    /// Serves a retried payment that was already settled, attaching the payment response
+    /// of the original response.
+    async fn serve_settled<
+        ReqBody,
+        ResBody,
+        S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    >(
+        &self,
+        inner: S,
+        req: http::Request<ReqBody>,
+        response_header_name: &'static str,
+        header_value: HeaderValue,
+    ) -> Response
     where
         S::Response: IntoResponse,
         S::Error: IntoResponse,
         S::Future: Send,
     {
-        match self.handle_request_fallible(inner, req).await {
-            Ok(response) => Ok(response),
-            Err(err) => {
-                // Get enriched accepts for 402 response
-                Ok(TPriceTag::error_into_response(
-                    err,
-                    &self.accepts,
-                    &self.resource,
-                    &self.extensions,
-                ))
+        let payment_response = proto::PaymentResponse::from_header(header_value.as_bytes()).ok();
+        let settlement = payment_response
+            .as_ref()
+            .and_then(|payment_response| serde_json::to_value(payment_response).ok())
+            .map(proto::SettleResponse);
+        let (mut parts, body) = req.into_parts();
+        parts.extensions.insert(settlement.clone());
+        let req = Request::from_parts(parts, body);
+        match Self::call_inner(inner, req).await {
+            Ok(response) => {
+                let mut response = response.into_response();
+                if let (Some(settlement), Some(payment_response)) = (settlement, payment_response)
+                {
+                    response.extensions_mut().insert(settlement);
+                    response.extensions_mut().insert(payment_response);
+                }
+                if self.expose_payment_response {
+                    response
+                        .headers_mut()
+                        .insert(response_header_name, header_value);
+                }
+                response
             }
+            Err(err) => err.into_response(),
         }
     }
 
-    /// Gets enriched price tags with facilitator capabilities.
-    pub async fn enrich_accepts(&mut self) {
-        // Try to get capabilities, use empty if fails
-        let capabilities = self.facilitator.supported().await.unwrap_or_default();
-
-        let accepts = self
-            .accepts
-            .iter()
-            .map(|pt| {
-                let mut pt_clone = pt.clone();
-                pt_clone.enrich_with_capabilities(&capabilities);
-                pt_clone
-            })
-            .collect::<Vec<_>>();
-        self.accepts = Arc::new(accepts);
-    }
-
-    /// Handles an incoming request, returning errors as `PaygateError`.
-    ///
-    /// This is the fallible version of `handle_request` that returns an actual error
-    /// instead of turning it into 402 Payment Required response.
-    pub async fn handle_request_fallible<
+    /// Verifies and settles a payment, serving the request on success.
+    async fn serve_paid<
         ReqBody,
         ResBody,
         S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
@@ -518,35 +1482,60 @@ where
         &self,
         inner: S,
         req: http::Request<ReqBody>,
+        verify_request: &proto::VerifyRequest,
+        response_header_name: &'static str,
+        mut timing: PaymentTiming,
     ) -> Result<Response, PaygateError>
     where
         S::Response: IntoResponse,
         S::Error: IntoResponse,
         S::Future: Send,
     {
-        // Extract payment payload from headers
-        let header = extract_payment_header(req.headers(), TPriceTag::PAYMENT_HEADER_NAME).ok_or(
-            VerificationError::PaymentHeaderRequired(TPriceTag::PAYMENT_HEADER_NAME),
-        )?;
-        let payment_payload = extract_payment_payload::<TPriceTag::PaymentPayload>(header)
-            .ok_or(VerificationError::InvalidPaymentHeader)?;
-
-        let verify_request =
-            TPriceTag::make_verify_request(payment_payload, &self.accepts, &self.resource)?;
+        // Payers with free quota left are served without settlement
+        let mut verified = false;
+        if let Some(quota) = self
+            .quota
+            .as_ref()
+            .filter(|quota| *quota.identity() == QuotaIdentity::Payer)
+        {
+            let verify_response = timing
+                .facilitator(self.verify_payment(verify_request))
+                .await?;
+            let payer = verify_response
+                .0
+                .get("payer")
+                .and_then(|payer| payer.as_str())
+                .map(QuotaIdentity::payer_key);
+            TPriceTag::validate_verify_response(verify_response)?;
+            verified = true;
+            if let Some(key) = payer
+                && let Some(usage) = quota.acquire(&key).await
+                && usage.granted
+            {
+                return Ok(self.serve_free(quota, &key, usage, inner, req).await);
+            }
+        }
 
-        if self.settle_before_execution {
+        // A switched protocol leaves no way to report a failed settlement, and the socket
+        // handler needs the settlement
+        let settle_before_execution =
+            self.settle_before_execution || is_websocket_upgrade(req.headers());
+        let res = if settle_before_execution {
             // Settlement before execution: settle payment first, then call inner handler
             #[cfg(feature = "telemetry")]
             tracing::debug!("Settling payment before request execution");
 
-            let settlement = self.settle_payment(&verify_request).await?;
+            let settlement = timing
+                .facilitator(self.settle_payment(verify_request))
+                .await?;
             validate_settlement(&settlement)?;
 
-            let header_value = settlement_to_header(settlement.clone())?;
+            let payment_response = settled_payment_response(&settlement, verify_request)?;
+            let header_value = encode_payment_response(&payment_response)?;
 
             // Settlement succeeded, add it as an extension and execute the request
             let (mut parts, body) = req.into_parts();
-            parts.extensions.insert(Some(settlement));
+            parts.extensions.insert(Some(settlement.clone()));
             let req = Request::from_parts(parts, body);
 
             let response = match Self::call_inner(inner, req).await {
@@ -554,18 +1543,25 @@ where
                 Err(err) => return Ok(err.into_response()),
             };
 
-            // Add payment response header
-            let mut res = response;
-            res.headers_mut().insert("Payment-Response", header_value);
-            Ok(res.into_response())
+            self.attach_settlement(
+                response.into_response(),
+                settlement,
+                payment_response,
+                response_header_name,
+                header_value,
+            )
         } else {
             // Settlement after execution (default): call inner handler first, then settle
             #[cfg(feature = "telemetry")]
             tracing::debug!("Settling payment after request execution");
 
-            let verify_response = self.verify_payment(&verify_request).await?;
+            if !verified {
+                let verify_response = timing
+                    .facilitator(self.verify_payment(verify_request))
+                    .await?;
 
-            TPriceTag::validate_verify_response(verify_response)?;
+                TPriceTag::validate_verify_response(verify_response)?;
+            }
 
             // Add None to extensions since we haven't settled yet
             let (mut parts, body) = req.into_parts();
@@ -581,15 +1577,86 @@ where
                 return Ok(response.into_response());
             }
 
-            let settlement = self.settle_payment(&verify_request).await?;
+            let settlement = timing
+                .facilitator(self.settle_payment(verify_request))
+                .await?;
             validate_settlement(&settlement)?;
 
-            let header_value = settlement_to_header(settlement)?;
+            let payment_response = settled_payment_response(&settlement, verify_request)?;
+            let header_value = encode_payment_response(&payment_response)?;
+
+            self.attach_settlement(
+                response.into_response(),
+                settlement,
+                payment_response,
+                response_header_name,
+                header_value,
+            )
+        };
+        #[cfg(feature = "timing-headers")]
+        if self.timing_headers {
+            return Ok(timing.with_headers(res));
+        }
+        Ok(res)
+    }
+
+    /// Inserts a settled payment into the response extensions, and sends its payment
+    /// response header unless disabled.
+    ///
+    /// Both happen before the response leaves the middleware, so outer layers see the
+    /// extensions whether or not the header is sent.
+    fn attach_settlement(
+        &self,
+        mut response: Response,
+        settlement: proto::SettleResponse,
+        payment_response: proto::PaymentResponse,
+        header_name: &'static str,
+        header_value: HeaderValue,
+    ) -> Response {
+        response.extensions_mut().insert(settlement);
+        response.extensions_mut().insert(payment_response);
+        if self.expose_payment_response {
+            response.headers_mut().insert(header_name, header_value);
+        }
+        response
+    }
 
-            let mut res = response;
-            res.headers_mut().insert("Payment-Response", header_value);
-            Ok(res.into_response())
+    /// Serves a request on free quota, giving the unit back if the handler fails.
+    async fn serve_free<
+        ReqBody,
+        ResBody,
+        S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    >(
+        &self,
+        quota: &FreeQuota,
+        key: &str,
+        usage: QuotaUsage,
+        inner: S,
+        req: http::Request<ReqBody>,
+    ) -> Response
+    where
+        S::Response: IntoResponse,
+        S::Error: IntoResponse,
+        S::Future: Send,
+    {
+        // Nothing is settled for free requests
+        let (mut parts, body) = req.into_parts();
+        parts.extensions.insert(None::<proto::SettleResponse>);
+        let req = http::Request::from_parts(parts, body);
+
+        let mut response = match Self::call_inner(inner, req).await {
+            Ok(response) => response.into_response(),
+            Err(err) => err.into_response(),
+        };
+        if response.status().is_client_error() || response.status().is_server_error() {
+            quota.release(key).await;
+            return response;
         }
+        response.headers_mut().insert(
+            FREE_QUOTA_REMAINING_HEADER,
+            HeaderValue::from(usage.remaining),
+        );
+        response
     }
 
     /// Verifies a payment with the facilitator.
@@ -623,17 +1690,36 @@ where
 // Helper Functions
 // ============================================================================
 
+/// Returns whether `headers` are those of an HTTP/1.1 WebSocket upgrade request.
+///
+/// The paygate settles these before calling the handler, whatever
+/// [`Paygate::settle_before_execution`] says, so that the payment is settled before the
+/// connection switches protocols, and the handler finds the settlement in its extensions.
+pub fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    let has_token = |name: http::header::HeaderName, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(token))
+    };
+    has_token(http::header::CONNECTION, "upgrade") && has_token(http::header::UPGRADE, "websocket")
+}
+
 /// Extracts the payment header value from the header map.
 fn extract_payment_header<'a>(header_map: &'a HeaderMap, header_name: &'a str) -> Option<&'a [u8]> {
     header_map.get(header_name).map(|h| h.as_bytes())
 }
 
 /// Extracts and deserializes the payment payload from base64-encoded header bytes.
+///
+/// Both the standard and the URL-safe base64 alphabets are accepted.
 fn extract_payment_payload<T>(header_bytes: &[u8]) -> Option<T>
 where
     T: serde::de::DeserializeOwned,
 {
-    let base64 = Base64Bytes::from(header_bytes).decode().ok()?;
+    let base64 = Base64Bytes::from(header_bytes).auto_decode().ok()?;
     let value = serde_json::from_slice(base64.as_ref()).ok()?;
     Some(value)
 }
@@ -670,17 +1756,106 @@ fn validate_settlement(settlement: &proto::SettleResponse) -> Result<(), Paygate
     }
 }
 
-/// Converts a [`proto::SettleResponse`] into an HTTP header value.
+/// Builds the [`proto::PaymentResponse`] of a successful settlement.
 ///
-/// Returns an error response if conversion fails.
-fn settlement_to_header(settlement: proto::SettleResponse) -> Result<HeaderValue, PaygateError> {
-    let json =
-        serde_json::to_vec(&settlement).map_err(|err| PaygateError::Settlement(err.to_string()))?;
-    let payment_header = Base64Bytes::encode(json);
+/// If the facilitator does not report the settled amount, it is taken from the
+/// payment requirements the payment was verified against.
+fn settled_payment_response(
+    settlement: &proto::SettleResponse,
+    verify_request: &proto::VerifyRequest,
+) -> Result<proto::PaymentResponse, PaygateError> {
+    let mut payment_response = proto::PaymentResponse::try_from(settlement)
+        .map_err(|err| PaygateError::Settlement(err.to_string()))?;
+    if payment_response.amount.is_none() {
+        payment_response.amount = required_amount(verify_request);
+    }
+    Ok(payment_response)
+}
+
+/// Encodes a payment response as the value of the payment response header.
+fn encode_payment_response(
+    payment_response: &proto::PaymentResponse,
+) -> Result<HeaderValue, PaygateError> {
+    let payment_header = payment_response
+        .to_header()
+        .map_err(|err| PaygateError::Settlement(err.to_string()))?;
     HeaderValue::from_bytes(payment_header.as_ref())
         .map_err(|err| PaygateError::Settlement(err.to_string()))
 }
 
+/// Extracts the required amount from the requirements in a verify request.
+///
+/// V2 requirements carry `amount`, V1 requirements carry `maxAmountRequired`.
+fn required_amount(verify_request: &proto::VerifyRequest) -> Option<String> {
+    amount_of(&payment_requirements(verify_request)?)
+}
+
+/// The payment requirements a verify request was made against.
+fn payment_requirements(verify_request: &proto::VerifyRequest) -> Option<serde_json::Value> {
+    let mut request: serde_json::Value = serde_json::from_str(verify_request.as_str()).ok()?;
+    Some(request.get_mut("paymentRequirements")?.take())
+}
+
+/// The required amount: `amount` in V2, `maxAmountRequired` in V1.
+fn amount_of(requirements: &serde_json::Value) -> Option<String> {
+    requirements
+        .get("amount")
+        .or_else(|| requirements.get("maxAmountRequired"))
+        .and_then(|amount| amount.as_str())
+        .map(str::to_owned)
+}
+
+/// The route of a metered request: the matched route pattern, or the request path
+/// outside of a router.
+fn metered_route<ReqBody>(req: &http::Request<ReqBody>) -> String {
+    req.extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| req.uri().path().to_owned())
+}
+
+/// Describes the resolution of a request carrying a payment.
+///
+/// `outcome` is `None` for a served request, which was settled if the response carries
+/// a successful payment response.
+fn payment_event(
+    response: &Response,
+    outcome: Option<PaymentOutcome>,
+    route: String,
+    verify_request: Option<&proto::VerifyRequest>,
+    started: Instant,
+) -> PaymentEvent {
+    let settlement = response
+        .extensions()
+        .get::<proto::PaymentResponse>()
+        .filter(|payment_response| payment_response.success);
+    let outcome = outcome.unwrap_or(match settlement {
+        Some(_) => PaymentOutcome::Settled,
+        None => PaymentOutcome::NotSettled,
+    });
+    let requirements = verify_request.and_then(payment_requirements);
+    let requirement = |name: &str| {
+        requirements
+            .as_ref()
+            .and_then(|requirements| requirements.get(name))
+            .and_then(|value| value.as_str())
+            .map(str::to_owned)
+    };
+    PaymentEvent {
+        outcome,
+        payer: settlement.and_then(|settlement| settlement.payer.clone()),
+        route,
+        amount: requirements.as_ref().and_then(amount_of),
+        asset: requirement("asset"),
+        network: requirement("network"),
+        transaction: settlement
+            .map(|settlement| settlement.transaction.clone())
+            .filter(|transaction| !transaction.is_empty()),
+        latency: started.elapsed(),
+        status: response.status(),
+    }
+}
+
 // ============================================================================
 // PriceTagSource Trait and Implementations
 // ============================================================================
@@ -709,13 +1884,21 @@ pub trait PriceTagSource {
 
     /// Resolves price tags for the given request context.
     ///
-    /// This method is infallible - it must always return a non-empty vector of price tags.
+    /// This method is infallible. Returning no price tags, or a price tag with a zero
+    /// amount, lets the request through without payment.
     fn resolve(
         &self,
         headers: &HeaderMap,
         uri: &Uri,
         base_url: Option<&Url>,
     ) -> impl Future<Output = Vec<Self::PriceTag>> + Send;
+
+    /// Returns the price tags if they do not depend on the request.
+    ///
+    /// Used to list the route in the discovery index, see [`crate::discovery`].
+    fn static_price_tags(&self) -> Option<&[Self::PriceTag]> {
+        None
+    }
 }
 
 // ============================================================================
@@ -764,6 +1947,24 @@ where
         self.tags = Arc::new(tags);
         self
     }
+
+    /// Adds an alternative price tag, possibly of another protocol version.
+    ///
+    /// Converts the source into [`AnyPriceTag`]s, preserving insertion order.
+    pub fn or_price_tag<TOther>(self, tag: TOther) -> StaticPriceTags<AnyPriceTag>
+    where
+        TPriceTag: Into<AnyPriceTag>,
+        TOther: Into<AnyPriceTag>,
+    {
+        let tags = self
+            .tags
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .chain(std::iter::once(tag.into()))
+            .collect();
+        StaticPriceTags::new(tags)
+    }
 }
 
 impl<TPriceTag> PriceTagSource for StaticPriceTags<TPriceTag>
@@ -781,6 +1982,10 @@ where
         // Simply clone the static tags
         (*self.tags).clone()
     }
+
+    fn static_price_tags(&self) -> Option<&[Self::PriceTag]> {
+        Some(&self.tags)
+    }
 }
 
 // ============================================================================
@@ -899,6 +2104,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::replay::ReplayPolicy;
     use serde_json::json;
 
     fn settle_response(value: serde_json::Value) -> proto::SettleResponse {
@@ -957,4 +2163,918 @@ mod tests {
         let err = validate_settlement(&resp).unwrap_err();
         assert!(err.to_string().contains("missing boolean"));
     }
+
+    /// Facilitator that accepts every payment and records verify requests.
+    #[derive(Default)]
+    struct RecordingFacilitator {
+        verified: std::sync::Mutex<Vec<serde_json::Value>>,
+        settled: std::sync::atomic::AtomicUsize,
+        latency: Duration,
+    }
+
+    impl Facilitator for RecordingFacilitator {
+        type Error = Infallible;
+
+        async fn verify(
+            &self,
+            request: &proto::VerifyRequest,
+        ) -> Result<proto::VerifyResponse, Self::Error> {
+            let request = serde_json::from_str(request.as_str()).unwrap();
+            self.verified.lock().unwrap().push(request);
+            tokio::time::sleep(self.latency).await;
+            Ok(v1::VerifyResponse::valid("payer".into()).into())
+        }
+
+        async fn settle(
+            &self,
+            _request: &proto::SettleRequest,
+        ) -> Result<proto::SettleResponse, Self::Error> {
+            self.settled
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(settle_response(json!({
+                "success": true,
+                "transaction": "0xabc",
+                "network": "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1",
+                "payer": "payer",
+            })))
+        }
+
+        async fn supported(&self) -> Result<SupportedResponse, Self::Error> {
+            Ok(SupportedResponse::default())
+        }
+    }
+
+    fn v1_price_tag() -> v1::PriceTag {
+        v1::PriceTag {
+            scheme: "exact".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            network: "base-sepolia".into(),
+            amount: "10000".into(),
+            max_timeout_seconds: 300,
+            extra: None,
+            enricher: None,
+        }
+    }
+
+    fn v2_price_tag() -> v2::PriceTag {
+        v2::PriceTag {
+            requirements: v2::PaymentRequirements {
+                scheme: "exact".into(),
+                network: "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1".parse().unwrap(),
+                amount: "100".into(),
+                pay_to: "EGBQqKn968sVv5cQh5Cr72pSTHfxsuzq7o7asqYB5uEV".into(),
+                max_timeout_seconds: 300,
+                asset: "4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU".into(),
+                extra: None,
+            },
+            enricher: None,
+        }
+    }
+
+    fn mixed_paygate(
+        facilitator: Arc<RecordingFacilitator>,
+    ) -> Paygate<AnyPriceTag, Arc<RecordingFacilitator>> {
+        let accepts = StaticPriceTags::new(vec![AnyPriceTag::from(v1_price_tag())])
+            .or_price_tag(v2_price_tag())
+            .tags()
+            .to_vec();
+        Paygate {
+            facilitator,
+            settle_before_execution: false,
+            accepts: Arc::new(accepts),
+            resource: v2::ResourceInfo {
+                url: "http://localhost/protected".into(),
+                description: None,
+                mime_type: None,
+            },
+            extensions: Arc::new(ExtensionsJson::default()),
+            paywall: Some(Paywall::default()),
+            responder: None,
+            timing_headers: false,
+            expose_payment_response: true,
+            quota: None,
+            replay: None,
+            stats: None,
+            meter: None,
+        }
+    }
+
+    fn ok_service() -> impl Service<
+        Request,
+        Response = Response,
+        Error = Infallible,
+        Future = impl Future<Output = Result<Response, Infallible>> + Send,
+    > + Clone {
+        tower::service_fn(|_req: Request| async { Ok(Response::new(Body::empty())) })
+    }
+
+    fn encode_header(value: serde_json::Value) -> HeaderValue {
+        let encoded = Base64Bytes::encode(serde_json::to_vec(&value).unwrap());
+        HeaderValue::from_bytes(encoded.as_ref()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn mixed_challenge_lists_options_in_insertion_order() {
+        let paygate = mixed_paygate(Arc::default());
+        let response = paygate
+            .handle_request(ok_service(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+
+        let header = response.headers().get("Payment-Required").unwrap();
+        let header = Base64Bytes::from(header.as_bytes()).decode().unwrap();
+        let v2_challenge: serde_json::Value = serde_json::from_slice(&header).unwrap();
+        assert_eq!(v2_challenge["accepts"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            v2_challenge["accepts"][0]["network"],
+            "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let v1_challenge: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(v1_challenge["accepts"].as_array().unwrap().len(), 1);
+        assert_eq!(v1_challenge["accepts"][0]["network"], "base-sepolia");
+    }
+
+    #[tokio::test]
+    async fn discovery_schemas_reach_both_challenges() {
+        let resource = ResourceInfoBuilder {
+            input_schema: Some(json!({ "queryParams": { "city": { "type": "string" } } })),
+            output_schema: Some(json!({ "type": "object" })),
+            ..Default::default()
+        };
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.extensions = resource.with_discovery(paygate.extensions, &Method::GET);
+        let response = paygate
+            .handle_request(ok_service(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+
+        let header = response.headers().get("Payment-Required").unwrap();
+        let header = Base64Bytes::from(header.as_bytes()).decode().unwrap();
+        let v2_challenge: serde_json::Value = serde_json::from_slice(&header).unwrap();
+        assert_eq!(
+            v2_challenge["extensions"]["bazaar"]["info"],
+            json!({
+                "input": {
+                    "type": "http",
+                    "method": "GET",
+                    "queryParams": { "city": { "type": "string" } }
+                },
+                "output": { "type": "object" }
+            })
+        );
+
+        let v1_challenge = json_body(response).await;
+        assert_eq!(
+            v1_challenge["accepts"][0]["outputSchema"],
+            json!({
+                "input": {
+                    "type": "http",
+                    "method": "GET",
+                    "discoverable": true,
+                    "queryParams": { "city": { "type": "string" } }
+                },
+                "output": { "type": "object" }
+            })
+        );
+    }
+
+    fn browser_request() -> Request {
+        let mut request = Request::new(Body::empty());
+        request.headers_mut().insert(
+            http::header::ACCEPT,
+            HeaderValue::from_static("text/html,application/xhtml+xml,*/*;q=0.8"),
+        );
+        request
+    }
+
+    #[tokio::test]
+    async fn browser_gets_paywall_page() {
+        let paygate = mixed_paygate(Arc::default());
+        let response = paygate
+            .handle_request(ok_service(), browser_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+        assert!(response.headers().contains_key("Payment-Required"));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("<code>base-sepolia</code>"));
+        assert!(body.contains("<code>solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1</code>"));
+        assert!(body.contains("id=\"x402-payment-required\""));
+    }
+
+    #[tokio::test]
+    async fn paywall_handler_builds_response() {
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.paywall = Some(Paywall::Handler(Arc::new(|context| {
+            Response::new(Body::from(format!("{} options", context.options.len())))
+        })));
+        let response = paygate
+            .handle_request(ok_service(), browser_request())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"2 options");
+    }
+
+    #[tokio::test]
+    async fn disabled_paywall_serves_challenge_to_browsers() {
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.paywall = None;
+        let response = paygate
+            .handle_request(ok_service(), browser_request())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+    }
+
+    fn envelope_responder() -> PaymentRequiredResponder {
+        PaymentRequiredResponder::new(|payment_required, parts| {
+            let body = json!({
+                "error": {
+                    "code": "payment_required",
+                    "path": parts.uri.path(),
+                    "x402": payment_required.v1.as_ref().map(|v1| json!(v1)),
+                }
+            });
+            Response::builder()
+                .status(payment_required.status)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        })
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn responder_wraps_challenge_in_envelope() {
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.responder = Some(envelope_responder());
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/protected".parse().unwrap();
+        let response = paygate.handle_request(ok_service(), request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        // The V2 challenge is added even though the responder left it out
+        assert!(response.headers().contains_key("Payment-Required"));
+
+        let body = json_body(response).await;
+        assert_eq!(body["error"]["code"], "payment_required");
+        assert_eq!(body["error"]["path"], "/protected");
+        assert_eq!(
+            body["error"]["x402"]["accepts"][0]["network"],
+            "base-sepolia"
+        );
+    }
+
+    #[tokio::test]
+    async fn responder_applies_to_invalid_payment() {
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.responder = Some(envelope_responder());
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("Payment-Signature", HeaderValue::from_static("not-base64!"));
+        let response = paygate.handle_request(ok_service(), request).await.unwrap();
+        let body = json_body(response).await;
+        assert_eq!(
+            body["error"]["x402"]["error"],
+            "Invalid or malformed payment header"
+        );
+    }
+
+    #[tokio::test]
+    async fn v1_responder_must_include_challenge() {
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.accepts = Arc::new(vec![AnyPriceTag::V1(v1_price_tag())]);
+        paygate.responder = Some(PaymentRequiredResponder::new(|_, _| {
+            Response::new(Body::from(r#"{"error":{"code":"payment_required"}}"#))
+        }));
+        let response = paygate
+            .handle_request(ok_service(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        // Falls back to the default response carrying the challenge in the body
+        let body = json_body(response).await;
+        assert_eq!(body["accepts"][0]["network"], "base-sepolia");
+
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.accepts = Arc::new(vec![AnyPriceTag::V1(v1_price_tag())]);
+        paygate.responder = Some(envelope_responder());
+        let response = paygate
+            .handle_request(ok_service(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let body = json_body(response).await;
+        assert_eq!(
+            body["error"]["x402"]["accepts"][0]["network"],
+            "base-sepolia"
+        );
+    }
+
+    #[tokio::test]
+    async fn mixed_accepts_payment_for_second_option() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let paygate = mixed_paygate(facilitator.clone());
+        let payload = json!({
+            "x402Version": 2,
+            "accepted": v2_price_tag().requirements,
+            "payload": { "transaction": "AQID" },
+        });
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("Payment-Signature", encode_header(payload));
+
+        let response = paygate.handle_request(ok_service(), request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let payment_response = response.headers().get("Payment-Response").unwrap();
+        let payment_response = proto::PaymentResponse::from_header(payment_response.as_bytes())
+            .expect("typed payment response");
+        assert!(payment_response.success);
+        assert_eq!(payment_response.transaction, "0xabc");
+        assert_eq!(payment_response.payer.as_deref(), Some("payer"));
+        assert_eq!(payment_response.amount.as_deref(), Some("100"));
+        assert!(!response.headers().contains_key("X-Payment-Response"));
+
+        let verified = facilitator.verified.lock().unwrap();
+        assert_eq!(verified.len(), 1);
+        assert_eq!(verified[0]["x402Version"], 2);
+        assert_eq!(
+            verified[0]["paymentRequirements"],
+            serde_json::to_value(&v2_price_tag().requirements).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn settlement_is_inserted_into_response_extensions() {
+        let paid_request = || {
+            let payload = json!({
+                "x402Version": 2,
+                "accepted": v2_price_tag().requirements,
+                "payload": { "transaction": "AQID" },
+            });
+            let mut request = Request::new(Body::empty());
+            request
+                .headers_mut()
+                .insert("Payment-Signature", encode_header(payload));
+            request
+        };
+
+        let paygate = mixed_paygate(Arc::default());
+        let response = paygate
+            .handle_request(ok_service(), paid_request())
+            .await
+            .unwrap();
+        let payment_response = response
+            .extensions()
+            .get::<proto::PaymentResponse>()
+            .unwrap();
+        assert_eq!(payment_response.transaction, "0xabc");
+        let header = response.headers().get("Payment-Response").unwrap();
+        assert_eq!(
+            &proto::PaymentResponse::from_header(header.as_bytes()).unwrap(),
+            payment_response
+        );
+        let settlement = response.extensions().get::<proto::SettleResponse>();
+        assert_eq!(settlement.unwrap().0["transaction"], "0xabc");
+
+        // Hidden from the client, still available to outer layers
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.expose_payment_response = false;
+        paygate.settle_before_execution = true;
+        let response = paygate
+            .handle_request(ok_service(), paid_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key("Payment-Response"));
+        let payment_response = response.extensions().get::<proto::PaymentResponse>();
+        assert_eq!(payment_response.unwrap().transaction, "0xabc");
+        assert!(
+            response
+                .extensions()
+                .get::<proto::SettleResponse>()
+                .is_some()
+        );
+
+        // Nothing is inserted without a settlement
+        let response = mixed_paygate(Arc::default())
+            .handle_request(ok_service(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert!(
+            response
+                .extensions()
+                .get::<proto::PaymentResponse>()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn v1_payment_gets_x_payment_response_header() {
+        let paygate = mixed_paygate(Arc::default());
+        let payload = json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "payload": {},
+        });
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("X-PAYMENT", encode_header(payload));
+
+        let response = paygate.handle_request(ok_service(), request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let payment_response = response.headers().get("X-Payment-Response").unwrap();
+        let payment_response =
+            proto::PaymentResponse::from_header(payment_response.as_bytes()).unwrap();
+        assert_eq!(payment_response.amount.as_deref(), Some("10000"));
+        assert!(!response.headers().contains_key("Payment-Response"));
+    }
+
+    #[tokio::test]
+    async fn accepts_url_safe_payment_header() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let paygate = mixed_paygate(facilitator.clone());
+        let payload = json!({
+            "x402Version": 2,
+            "accepted": v2_price_tag().requirements,
+            "payload": { "transaction": "???>>>" },
+        });
+        let bytes = serde_json::to_vec(&payload).unwrap();
+        let standard = Base64Bytes::encode(&bytes).to_string();
+        assert!(standard.contains(['+', '/']), "got: {standard}");
+        let url_safe = Base64Bytes::encode_url_safe(&bytes);
+        let mut request = Request::new(Body::empty());
+        request.headers_mut().insert(
+            "Payment-Signature",
+            HeaderValue::from_bytes(url_safe.as_ref()).unwrap(),
+        );
+
+        let response = paygate.handle_request(ok_service(), request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let verified = facilitator.verified.lock().unwrap();
+        assert_eq!(
+            verified[0]["paymentPayload"]["payload"]["transaction"],
+            "???>>>"
+        );
+    }
+
+    #[tokio::test]
+    async fn mixed_rejects_payment_for_unoffered_option() {
+        let paygate = mixed_paygate(Arc::default());
+        let payload = json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base",
+            "payload": {},
+        });
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("X-PAYMENT", encode_header(payload));
+
+        let err = paygate
+            .handle_request_fallible(ok_service(), request)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PaygateError::Verification(VerificationError::NoPaymentMatching)
+        ));
+    }
+
+    #[cfg(feature = "timing-headers")]
+    fn v1_payment_request() -> Request {
+        let payload = json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "payload": {},
+        });
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("X-PAYMENT", encode_header(payload));
+        request
+    }
+
+    #[cfg(feature = "timing-headers")]
+    #[tokio::test]
+    async fn timing_headers_report_facilitator_and_total_time() {
+        let facilitator = Arc::new(RecordingFacilitator {
+            latency: Duration::from_millis(5),
+            ..Default::default()
+        });
+        let mut paygate = mixed_paygate(facilitator);
+        paygate.timing_headers = true;
+        let response = paygate
+            .handle_request(ok_service(), v1_payment_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let millis =
+            |name: &str| -> f64 { response.headers()[name].to_str().unwrap().parse().unwrap() };
+        let verified = millis(VERIFIED_MS_HEADER);
+        let total = millis(TOTAL_MS_HEADER);
+        assert!(verified > 0.0, "verified: {verified}");
+        assert!(total >= verified, "total: {total}, verified: {verified}");
+    }
+
+    #[cfg(feature = "timing-headers")]
+    #[tokio::test]
+    async fn timing_headers_omitted_when_disabled() {
+        let paygate = mixed_paygate(Arc::default());
+        let response = paygate
+            .handle_request(ok_service(), v1_payment_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(VERIFIED_MS_HEADER));
+        assert!(!response.headers().contains_key(TOTAL_MS_HEADER));
+    }
+
+    /// A paygate allowing 2 free requests per day. Paygates built with the same `quota`
+    /// share its store.
+    fn quota_paygate(
+        facilitator: &Arc<RecordingFacilitator>,
+        quota: &FreeQuota,
+    ) -> Paygate<AnyPriceTag, Arc<RecordingFacilitator>> {
+        let mut paygate = mixed_paygate(facilitator.clone());
+        paygate.quota = Some(quota.clone());
+        paygate
+    }
+
+    fn api_key_request(payment: Option<serde_json::Value>) -> Request {
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("x-api-key", HeaderValue::from_static("key-1"));
+        if let Some(payment) = payment {
+            request
+                .headers_mut()
+                .insert("Payment-Signature", encode_header(payment));
+        }
+        request
+    }
+
+    fn api_key_identity() -> QuotaIdentity {
+        QuotaIdentity::Header(http::HeaderName::from_static("x-api-key"))
+    }
+
+    #[tokio::test]
+    async fn free_quota_then_payment() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let quota = FreeQuota::per_day(2).with_identity(api_key_identity());
+
+        for remaining in ["1", "0"] {
+            let response = quota_paygate(&facilitator, &quota)
+                .handle_request(ok_service(), api_key_request(None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[FREE_QUOTA_REMAINING_HEADER], remaining);
+        }
+        assert!(facilitator.verified.lock().unwrap().is_empty());
+
+        let response = quota_paygate(&facilitator, &quota)
+            .handle_request(ok_service(), api_key_request(None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        let header = response.headers().get("Payment-Required").unwrap();
+        let header = Base64Bytes::from(header.as_bytes()).decode().unwrap();
+        let v2_challenge: serde_json::Value = serde_json::from_slice(&header).unwrap();
+        let accepted = v2_challenge["accepts"][0].clone();
+        let free_quota = &accepted["extra"][FREE_QUOTA_EXTRA_KEY];
+        assert_eq!(free_quota["limit"], 2);
+        assert_eq!(free_quota["periodSeconds"], 86400);
+        assert_eq!(free_quota["remaining"], 0);
+        assert!(free_quota["resetSeconds"].as_u64().unwrap() <= 86400);
+        let v1_challenge = json_body(response).await;
+        assert_eq!(
+            v1_challenge["accepts"][0]["extra"][FREE_QUOTA_EXTRA_KEY]["remaining"],
+            0
+        );
+
+        // Paying with the requirements as advertised, quota metadata included
+        let payment = json!({
+            "x402Version": 2,
+            "accepted": accepted,
+            "payload": { "transaction": "AQID" },
+        });
+        let response = quota_paygate(&facilitator, &quota)
+            .handle_request(ok_service(), api_key_request(Some(payment)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("Payment-Response"));
+        assert_eq!(
+            facilitator
+                .settled
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_free_request_keeps_quota() {
+        let facilitator = Arc::default();
+        let quota = FreeQuota::per_day(2).with_identity(api_key_identity());
+        let failing = tower::service_fn(|_req: Request| async {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            Ok::<_, Infallible>(response)
+        });
+        for _ in 0..3 {
+            let response = quota_paygate(&facilitator, &quota)
+                .handle_request(failing, api_key_request(None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        let response = quota_paygate(&facilitator, &quota)
+            .handle_request(ok_service(), api_key_request(None))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[FREE_QUOTA_REMAINING_HEADER], "1");
+    }
+
+    #[tokio::test]
+    async fn unidentified_client_must_pay() {
+        let facilitator = Arc::default();
+        let quota = FreeQuota::per_day(2).with_identity(api_key_identity());
+        let response = quota_paygate(&facilitator, &quota)
+            .handle_request(ok_service(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        let free_quota = &json_body(response).await["accepts"][0]["extra"][FREE_QUOTA_EXTRA_KEY];
+        assert_eq!(free_quota["limit"], 2);
+        assert!(free_quota.get("remaining").is_none());
+    }
+
+    #[tokio::test]
+    async fn payer_quota_skips_settlement() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let quota = FreeQuota::per_day(2).with_identity(QuotaIdentity::Payer);
+        let payment = json!({
+            "x402Version": 2,
+            "accepted": v2_price_tag().requirements,
+            "payload": { "transaction": "AQID" },
+        });
+
+        let mut headers = vec![];
+        for _ in 0..3 {
+            let response = quota_paygate(&facilitator, &quota)
+                .handle_request(ok_service(), api_key_request(Some(payment.clone())))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            headers.push(response.headers().clone());
+        }
+        assert_eq!(headers[0][FREE_QUOTA_REMAINING_HEADER], "1");
+        assert_eq!(headers[1][FREE_QUOTA_REMAINING_HEADER], "0");
+        assert!(!headers[1].contains_key("Payment-Response"));
+        assert!(headers[2].contains_key("Payment-Response"));
+        assert_eq!(facilitator.verified.lock().unwrap().len(), 3);
+        assert_eq!(
+            facilitator
+                .settled
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+    }
+
+    fn replay_paygate(
+        facilitator: &Arc<RecordingFacilitator>,
+        replay: &ReplayProtection,
+    ) -> Paygate<AnyPriceTag, Arc<RecordingFacilitator>> {
+        let mut paygate = mixed_paygate(facilitator.clone());
+        paygate.replay = Some(replay.clone());
+        paygate
+    }
+
+    fn paid_request() -> Request {
+        let payment = json!({
+            "x402Version": 2,
+            "accepted": v2_price_tag().requirements,
+            "payload": { "transaction": "AQID" },
+        });
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("Payment-Signature", encode_header(payment));
+        request
+    }
+
+    #[tokio::test]
+    async fn replayed_payment_is_rejected_locally() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let replay = ReplayProtection::new();
+
+        let response = replay_paygate(&facilitator, &replay)
+            .handle_request(ok_service(), paid_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("Payment-Response"));
+
+        let response = replay_paygate(&facilitator, &replay)
+            .handle_request(ok_service(), paid_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(json_body(response).await["problem"]["code"], "nonce_used");
+        assert_eq!(facilitator.verified.lock().unwrap().len(), 1);
+        assert_eq!(
+            facilitator
+                .settled
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn unsettled_payment_can_be_retried() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let replay = ReplayProtection::new();
+        let failing = tower::service_fn(|_req: Request| async {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            Ok::<_, Infallible>(response)
+        });
+
+        let response = replay_paygate(&facilitator, &replay)
+            .handle_request(failing, paid_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let response = replay_paygate(&facilitator, &replay)
+            .handle_request(ok_service(), paid_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(facilitator.verified.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retried_payment_reuses_settlement() {
+        for settle_before_execution in [false, true] {
+            let facilitator = Arc::new(RecordingFacilitator::default());
+            let replay = ReplayProtection::new().on_replay(ReplayPolicy::ReuseSettlement);
+            let paygate = || {
+                let mut paygate = replay_paygate(&facilitator, &replay);
+                paygate.settle_before_execution = settle_before_execution;
+                paygate
+            };
+
+            let response = paygate()
+                .handle_request(ok_service(), paid_request())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let payment_response = response.headers()["Payment-Response"].clone();
+            let verified = facilitator.verified.lock().unwrap().len();
+
+            let sees_settlement = tower::service_fn(|req: Request| async move {
+                let settlement = req.extensions().get::<Option<proto::SettleResponse>>();
+                assert!(matches!(settlement, Some(Some(_))));
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            });
+            let response = paygate()
+                .handle_request(sees_settlement, paid_request())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["Payment-Response"], payment_response);
+            assert_eq!(facilitator.verified.lock().unwrap().len(), verified);
+            assert_eq!(
+                facilitator
+                    .settled
+                    .load(std::sync::atomic::Ordering::SeqCst),
+                1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn payment_in_flight_is_not_reused() {
+        let facilitator = Arc::new(RecordingFacilitator {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        });
+        let replay = ReplayProtection::new().on_replay(ReplayPolicy::ReuseSettlement);
+
+        let (first, retry) = tokio::join!(
+            replay_paygate(&facilitator, &replay).handle_request(ok_service(), paid_request()),
+            async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                replay_paygate(&facilitator, &replay)
+                    .handle_request(ok_service(), paid_request())
+                    .await
+            }
+        );
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(retry.unwrap().status(), StatusCode::CONFLICT);
+        assert_eq!(facilitator.verified.lock().unwrap().len(), 1);
+    }
+
+    fn upgrade_request(mut request: Request) -> Request {
+        let headers = request.headers_mut();
+        headers.insert(
+            "Connection",
+            HeaderValue::from_static("keep-alive, Upgrade"),
+        );
+        headers.insert("Upgrade", HeaderValue::from_static("websocket"));
+        request
+    }
+
+    #[test]
+    fn websocket_upgrades_are_detected() {
+        assert!(is_websocket_upgrade(
+            upgrade_request(Request::new(Body::empty())).headers()
+        ));
+        let mut headers = HeaderMap::new();
+        headers.insert("Upgrade", HeaderValue::from_static("websocket"));
+        assert!(!is_websocket_upgrade(&headers));
+        headers.insert("Connection", HeaderValue::from_static("Upgrade"));
+        assert!(is_websocket_upgrade(&headers));
+        headers.insert("Upgrade", HeaderValue::from_static("h2c"));
+        assert!(!is_websocket_upgrade(&headers));
+    }
+
+    #[tokio::test]
+    async fn websocket_upgrade_is_settled_before_execution() {
+        let facilitator = Arc::new(RecordingFacilitator::default());
+        let paygate = mixed_paygate(facilitator.clone());
+        assert!(!paygate.settle_before_execution);
+        let switching = tower::service_fn(|req: Request| async move {
+            let settlement = req.extensions().get::<Option<proto::SettleResponse>>();
+            assert!(matches!(settlement, Some(Some(_))));
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+            Ok::<_, Infallible>(response)
+        });
+
+        let response = paygate
+            .handle_request(switching, upgrade_request(paid_request()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert!(response.headers().contains_key("Payment-Response"));
+        assert_eq!(
+            facilitator
+                .settled
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn websocket_upgrade_challenge_is_in_headers() {
+        let mut paygate = mixed_paygate(Arc::default());
+        paygate.accepts = Arc::new(vec![AnyPriceTag::from(v1_price_tag())]);
+        let mut request = upgrade_request(Request::new(Body::empty()));
+        request.headers_mut().insert(
+            "Accept",
+            HeaderValue::from_static("text/html,application/xhtml+xml,*/*;q=0.8"),
+        );
+
+        let response = paygate.handle_request(ok_service(), request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        // Not the paywall page
+        assert_eq!(response.headers()["Content-Type"], "application/json");
+        let header = response.headers()["Payment-Required"].as_bytes();
+        let challenge: serde_json::Value =
+            serde_json::from_slice(&Base64Bytes::from(header).decode().unwrap()).unwrap();
+        assert_eq!(challenge["x402Version"], 1);
+        assert_eq!(challenge["accepts"][0]["network"], "base-sepolia");
+    }
 }
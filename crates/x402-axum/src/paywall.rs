@@ -0,0 +1,372 @@
+//! Browser paywall for x402-protected routes.
+//!
+//! Browsers opening a protected route would otherwise receive a raw JSON or header-only
+//! `402 Payment Required` response. When the request's `Accept` header prefers `text/html`
+//! over `application/json`, the middleware renders a [`Paywall`] page instead. All other
+//! clients keep receiving the machine-readable challenge.
+//!
+//! ## Templates
+//!
+//! [`Paywall::Template`] renders an HTML template with the following placeholders:
+//!
+//! - `{{description}}` - description of the protected resource
+//! - `{{resource_url}}` - URL of the protected resource
+//! - `{{options}}` - one `<li>` per accepted payment option (amount, asset, network, recipient)
+//! - `{{error}}` - the reason payment is required
+//! - `{{payment_required_json}}` - the payment-required challenge as JSON, safe to embed
+//!   in a `<script type="application/json">` element
+//!
+//! Values are escaped before substitution. Unknown placeholders are left untouched.
+//! [`DEFAULT_PAYWALL_TEMPLATE`] is used unless another template is configured.
+//!
+//! ## Custom Handlers
+//!
+//! [`Paywall::Handler`] hands a [`PaywallContext`] to a callback that builds the whole
+//! response, for full control over status, headers and body.
+
+use axum_core::body::Body;
+use axum_core::response::Response;
+use http::header::{ACCEPT, CONTENT_TYPE};
+use http::{HeaderMap, HeaderValue};
+use std::fmt::Write;
+use std::sync::Arc;
+use x402_types::proto::v2;
+
+/// Minimal paywall page used when no template is configured.
+pub const DEFAULT_PAYWALL_TEMPLATE: &str = include_str!("paywall.html");
+
+/// Callback rendering a paywall response.
+pub type PaywallHandler = Arc<dyn Fn(&PaywallContext) -> Response + Send + Sync>;
+
+/// How the paywall page is rendered for browsers.
+#[derive(Clone)]
+pub enum Paywall {
+    /// Renders an HTML template, see the [module docs](self) for placeholders.
+    ///
+    /// The response keeps the status and headers of the regular 402 response.
+    Template(Arc<str>),
+    /// Builds the full response from the paywall context.
+    Handler(PaywallHandler),
+}
+
+impl Default for Paywall {
+    fn default() -> Self {
+        Paywall::Template(Arc::from(DEFAULT_PAYWALL_TEMPLATE))
+    }
+}
+
+impl std::fmt::Debug for Paywall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Paywall::Template(template) => f.debug_tuple("Template").field(template).finish(),
+            Paywall::Handler(_) => f.debug_tuple("Handler").field(&"<callback>").finish(),
+        }
+    }
+}
+
+impl Paywall {
+    /// Renders the paywall page.
+    ///
+    /// `payment_required` is the regular 402 response, whose status and headers are kept
+    /// for template pages.
+    pub fn render(&self, context: &PaywallContext, payment_required: Response) -> Response {
+        match self {
+            Paywall::Template(template) => {
+                let (mut parts, _) = payment_required.into_parts();
+                parts.headers.insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("text/html; charset=utf-8"),
+                );
+                Response::from_parts(parts, Body::from(render_template(template, context)))
+            }
+            Paywall::Handler(handler) => handler(context),
+        }
+    }
+}
+
+/// A payment option shown on the paywall page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaywallOption {
+    /// Payment scheme, e.g. `exact`.
+    pub scheme: String,
+    /// Network name (V1) or CAIP-2 chain ID (V2).
+    pub network: String,
+    /// Amount in the asset's smallest unit.
+    pub amount: String,
+    /// Token asset address.
+    pub asset: String,
+    /// Token name, if advertised in the requirements' `extra.name`.
+    pub asset_name: Option<String>,
+    /// Recipient address.
+    pub pay_to: String,
+}
+
+/// Data available to paywall templates and handlers.
+#[derive(Debug, Clone)]
+pub struct PaywallContext {
+    /// The protected resource.
+    pub resource: v2::ResourceInfo,
+    /// The reason payment is required.
+    pub error: String,
+    /// Accepted payment options, in the order they are advertised.
+    pub options: Vec<PaywallOption>,
+    /// The payment-required challenge.
+    ///
+    /// For routes mixing protocol versions, this is an array holding the V1 and
+    /// V2 challenges.
+    pub payment_required: serde_json::Value,
+}
+
+impl PaywallContext {
+    /// Builds the context from the payment-required challenge.
+    pub fn new(
+        resource: v2::ResourceInfo,
+        error: String,
+        payment_required: serde_json::Value,
+    ) -> Self {
+        let challenges = match &payment_required {
+            serde_json::Value::Array(challenges) => challenges.iter().collect(),
+            challenge => vec![challenge],
+        };
+        let options = challenges
+            .into_iter()
+            .filter_map(|challenge| challenge.get("accepts")?.as_array())
+            .flatten()
+            .filter_map(paywall_option)
+            .collect();
+        PaywallContext {
+            resource,
+            error,
+            options,
+            payment_required,
+        }
+    }
+}
+
+/// Reads a payment option from V1 or V2 payment requirements JSON.
+fn paywall_option(requirements: &serde_json::Value) -> Option<PaywallOption> {
+    let field = |name: &str| requirements.get(name)?.as_str().map(str::to_owned);
+    Some(PaywallOption {
+        scheme: field("scheme")?,
+        network: field("network")?,
+        amount: field("amount").or_else(|| field("maxAmountRequired"))?,
+        asset: field("asset")?,
+        asset_name: requirements
+            .get("extra")
+            .and_then(|extra| extra.get("name"))
+            .and_then(|name| name.as_str())
+            .map(str::to_owned),
+        pay_to: field("payTo")?,
+    })
+}
+
+/// Returns whether the `Accept` header prefers `text/html` over `application/json`.
+///
+/// `text/html` must be listed explicitly (or as `text/*`); a bare `*/*` counts towards
+/// JSON, so API clients sending `*/*` keep receiving the JSON challenge. Ties go to JSON.
+pub fn prefers_html(headers: &HeaderMap) -> bool {
+    let mut html = MediaRangeQuality::default();
+    let mut json = MediaRangeQuality::default();
+    let ranges = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+    for range in ranges {
+        let mut params = range.split(';');
+        let media_type = params
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let quality = params
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        match media_type.as_str() {
+            "text/html" => html.exact = Some(quality),
+            "text/*" => html.subtype_wildcard = Some(quality),
+            "application/json" => json.exact = Some(quality),
+            "application/*" => json.subtype_wildcard = Some(quality),
+            "*/*" => json.wildcard = Some(quality),
+            _ => {}
+        }
+    }
+    let html = html.quality();
+    html > 0.0 && html > json.quality()
+}
+
+/// Quality values of the media ranges matching a media type, by specificity.
+#[derive(Default)]
+struct MediaRangeQuality {
+    exact: Option<f32>,
+    subtype_wildcard: Option<f32>,
+    wildcard: Option<f32>,
+}
+
+impl MediaRangeQuality {
+    /// The quality of the most specific matching range.
+    fn quality(&self) -> f32 {
+        self.exact
+            .or(self.subtype_wildcard)
+            .or(self.wildcard)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Substitutes the placeholders of `template` with escaped values from `context`.
+pub fn render_template(template: &str, context: &PaywallContext) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = after[..end].trim();
+        match placeholder(name, context) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn placeholder(name: &str, context: &PaywallContext) -> Option<String> {
+    let value = match name {
+        "description" => {
+            let description = context
+                .resource
+                .description
+                .as_deref()
+                .filter(|description| !description.is_empty())
+                .unwrap_or("This resource requires payment.");
+            escape_html(description)
+        }
+        "resource_url" => escape_html(&context.resource.url),
+        "error" => escape_html(&context.error),
+        "options" => {
+            let mut options = String::new();
+            for option in &context.options {
+                let asset = option.asset_name.as_deref().unwrap_or(&option.asset);
+                let _ = write!(
+                    options,
+                    "<li><strong>{}</strong> units of <code>{}</code> on <code>{}</code> ({}), paid to <code>{}</code></li>",
+                    escape_html(&option.amount),
+                    escape_html(asset),
+                    escape_html(&option.network),
+                    escape_html(&option.scheme),
+                    escape_html(&option.pay_to),
+                );
+            }
+            options
+        }
+        "payment_required_json" => escape_script_json(&context.payment_required),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Escapes text for use in HTML element content and attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Serializes JSON so it cannot terminate the `<script>` element it is embedded in.
+fn escape_script_json(value: &serde_json::Value) -> String {
+    value
+        .to_string()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn context() -> PaywallContext {
+        PaywallContext::new(
+            v2::ResourceInfo {
+                description: Some("<b>Premium</b> article".to_string()),
+                mime_type: Some("text/html".to_string()),
+                url: "https://example.com/article?a=1&b=2".to_string(),
+            },
+            "Payment-Signature header is required".to_string(),
+            json!({
+                "x402Version": 2,
+                "accepts": [{
+                    "scheme": "exact",
+                    "network": "eip155:84532",
+                    "amount": "10000",
+                    "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+                    "payTo": "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+                    "maxTimeoutSeconds": 300,
+                    "extra": { "name": "USDC", "version": "2" }
+                }],
+                "error": "</script><script>alert(1)</script>"
+            }),
+        )
+    }
+
+    #[test]
+    fn browser_accept_prefers_html() {
+        assert!(prefers_html(&accept(
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        )));
+        assert!(prefers_html(&accept("application/json;q=0.5, text/html")));
+    }
+
+    #[test]
+    fn api_accept_prefers_json() {
+        assert!(!prefers_html(&HeaderMap::new()));
+        assert!(!prefers_html(&accept("*/*")));
+        assert!(!prefers_html(&accept("application/json")));
+        assert!(!prefers_html(&accept("text/html;q=0.5, application/json")));
+        assert!(!prefers_html(&accept("text/html, application/json")));
+        assert!(!prefers_html(&accept("text/html;q=0")));
+    }
+
+    #[test]
+    fn template_substitutes_escaped_values() {
+        let rendered = render_template(
+            "<h1>{{ description }}</h1><a href=\"{{resource_url}}\"></a><ul>{{options}}</ul>{{unknown}}",
+            &context(),
+        );
+        assert!(rendered.contains("<h1>&lt;b&gt;Premium&lt;/b&gt; article</h1>"));
+        assert!(rendered.contains("href=\"https://example.com/article?a=1&amp;b=2\""));
+        assert!(rendered.contains("<strong>10000</strong> units of <code>USDC</code>"));
+        assert!(rendered.ends_with("{{unknown}}"));
+    }
+
+    #[test]
+    fn embedded_json_cannot_close_script() {
+        let rendered = render_template(DEFAULT_PAYWALL_TEMPLATE, &context());
+        assert_eq!(rendered.matches("</script>").count(), 1);
+        assert!(rendered.contains("\\u003c/script\\u003e"));
+    }
+}
@@ -0,0 +1,562 @@
+//! Free quota: a number of unpaid requests per client before payment is enforced.
+//!
+//! A [`FreeQuota`] serves, say, the first 10 requests per day of each client for free, then
+//! falls back to the regular x402 payment flow. Clients are told apart by a
+//! [`QuotaIdentity`], and usage is counted in a [`QuotaStore`]: in memory by default
+//! ([`MemoryQuotaStore`]), or in Redis with the `redis` feature (`RedisQuotaStore`).
+//!
+//! Only successful requests use up quota. A unit is taken before the handler runs, so
+//! concurrent requests never exceed the limit, and is given back if the handler answers
+//! with a client or server error.
+//!
+//! Once the quota is exhausted, every payment requirement of the 402 challenge carries a
+//! `freeQuota` entry in its `extra`:
+//!
+//! ```json
+//! { "limit": 10, "periodSeconds": 86400, "remaining": 0, "resetSeconds": 5400 }
+//! ```
+//!
+//! `remaining` and `resetSeconds` are omitted when the client could not be identified.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use http::HeaderName;
+//! use x402_axum::quota::{FreeQuota, QuotaIdentity};
+//!
+//! let quota = FreeQuota::per_day(10)
+//!     .with_identity(QuotaIdentity::Header(HeaderName::from_static("x-api-key")));
+//! let app = Router::new().route(
+//!     "/weather",
+//!     get(handler).layer(x402.with_price_tag(price_tag).with_free_quota(quota)),
+//! );
+//! ```
+
+use async_trait::async_trait;
+use axum::extract::ConnectInfo;
+use http::HeaderName;
+use lru::LruCache;
+use serde_json::json;
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use x402_types::proto::v2;
+
+/// Key of the quota metadata in the `extra` of payment requirements.
+pub const FREE_QUOTA_EXTRA_KEY: &str = "freeQuota";
+
+/// Response header carrying the free requests left after a request served on free quota.
+pub const FREE_QUOTA_REMAINING_HEADER: &str = "X-Free-Quota-Remaining";
+
+/// Errors returned by a [`QuotaStore`].
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    /// The store could not be reached or failed to update the counter.
+    #[error("Quota store error: {0}")]
+    Store(String),
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for QuotaError {
+    fn from(err: redis::RedisError) -> Self {
+        Self::Store(err.to_string())
+    }
+}
+
+/// Outcome of a [`QuotaStore::acquire`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    /// Whether a unit of quota was taken.
+    pub granted: bool,
+    /// Units left in the current period, after this one.
+    pub remaining: u64,
+    /// Time until the current period ends and the quota is replenished.
+    pub reset_after: Duration,
+}
+
+/// Counts quota usage per client key.
+///
+/// Implementations must make [`Self::acquire`] atomic: concurrent calls for one key may
+/// never grant more than `limit` units per period.
+#[async_trait]
+pub trait QuotaStore: Send + Sync {
+    /// Takes one unit of quota for `key`, if any is left in the current period.
+    ///
+    /// A period of length `period` starts with the first unit taken for the key.
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: u64,
+        period: Duration,
+    ) -> Result<QuotaUsage, QuotaError>;
+
+    /// Gives back a unit taken by [`Self::acquire`], e.g. because the request failed.
+    async fn release(&self, key: &str) -> Result<(), QuotaError>;
+}
+
+/// In-memory [`QuotaStore`] holding the most recently seen clients.
+///
+/// Once `capacity` clients are tracked, the least recently seen one is forgotten, and
+/// gets a fresh quota on its next request. Counters are local to the process.
+#[derive(Debug)]
+pub struct MemoryQuotaStore {
+    periods: Mutex<LruCache<String, QuotaPeriod>>,
+}
+
+#[derive(Debug)]
+struct QuotaPeriod {
+    started: Instant,
+    used: u64,
+}
+
+impl MemoryQuotaStore {
+    /// Number of clients tracked by [`MemoryQuotaStore::default`].
+    pub const DEFAULT_CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
+    /// Creates a store tracking up to `capacity` clients.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            periods: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+impl Default for MemoryQuotaStore {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl QuotaStore for MemoryQuotaStore {
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: u64,
+        period: Duration,
+    ) -> Result<QuotaUsage, QuotaError> {
+        let now = Instant::now();
+        let mut periods = self.periods.lock().expect("quota store lock poisoned");
+        let current = periods.get_or_insert_mut(key.to_owned(), || QuotaPeriod {
+            started: now,
+            used: 0,
+        });
+        if now.duration_since(current.started) >= period {
+            *current = QuotaPeriod {
+                started: now,
+                used: 0,
+            };
+        }
+        let granted = current.used < limit;
+        if granted {
+            current.used += 1;
+        }
+        Ok(QuotaUsage {
+            granted,
+            remaining: limit.saturating_sub(current.used),
+            reset_after: period.saturating_sub(now.duration_since(current.started)),
+        })
+    }
+
+    async fn release(&self, key: &str) -> Result<(), QuotaError> {
+        let mut periods = self.periods.lock().expect("quota store lock poisoned");
+        if let Some(current) = periods.get_mut(key) {
+            current.used = current.used.saturating_sub(1);
+        }
+        Ok(())
+    }
+}
+
+/// Redis-backed [`QuotaStore`], sharing quota across server instances.
+///
+/// Each client is one counter key, expiring at the end of its period.
+#[cfg(feature = "redis")]
+#[derive(Clone)]
+pub struct RedisQuotaStore {
+    connection: redis::aio::ConnectionManager,
+    prefix: String,
+}
+
+/// Increments the counter unless it reached the limit. Returns `{granted, remaining, ttl_ms}`.
+#[cfg(feature = "redis")]
+const ACQUIRE_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local used = redis.call('INCR', KEYS[1])
+local ttl = redis.call('PTTL', KEYS[1])
+if ttl < 0 then
+  redis.call('PEXPIRE', KEYS[1], ARGV[2])
+  ttl = tonumber(ARGV[2])
+end
+if used > limit then
+  redis.call('DECR', KEYS[1])
+  return {0, 0, ttl}
+end
+return {1, limit - used, ttl}
+"#;
+
+/// Decrements the counter if it still exists, keeping its expiry.
+#[cfg(feature = "redis")]
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 1 and tonumber(redis.call('GET', KEYS[1])) > 0 then
+  redis.call('DECR', KEYS[1])
+end
+return 0
+"#;
+
+#[cfg(feature = "redis")]
+impl RedisQuotaStore {
+    /// Default prefix of the counter keys.
+    pub const DEFAULT_PREFIX: &'static str = "x402:quota:";
+
+    /// Connects to the Redis server at `url`, e.g. `redis://127.0.0.1:6379`.
+    pub async fn connect(url: &str) -> Result<Self, QuotaError> {
+        let client = redis::Client::open(url)?;
+        let connection = redis::aio::ConnectionManager::new(client).await?;
+        Ok(Self::new(connection))
+    }
+
+    /// Creates a store on an existing connection.
+    pub fn new(connection: redis::aio::ConnectionManager) -> Self {
+        Self {
+            connection,
+            prefix: Self::DEFAULT_PREFIX.to_string(),
+        }
+    }
+
+    /// Sets the prefix of the counter keys (default: `x402:quota:`).
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Debug for RedisQuotaStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisQuotaStore")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl QuotaStore for RedisQuotaStore {
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: u64,
+        period: Duration,
+    ) -> Result<QuotaUsage, QuotaError> {
+        let mut connection = self.connection.clone();
+        let period_ms = u64::try_from(period.as_millis()).unwrap_or(u64::MAX).max(1);
+        let (granted, remaining, ttl_ms): (u8, u64, u64) = redis::Script::new(ACQUIRE_SCRIPT)
+            .key(format!("{}{key}", self.prefix))
+            .arg(limit)
+            .arg(period_ms)
+            .invoke_async(&mut connection)
+            .await?;
+        Ok(QuotaUsage {
+            granted: granted == 1,
+            remaining,
+            reset_after: Duration::from_millis(ttl_ms),
+        })
+    }
+
+    async fn release(&self, key: &str) -> Result<(), QuotaError> {
+        let mut connection = self.connection.clone();
+        let _: i64 = redis::Script::new(RELEASE_SCRIPT)
+            .key(format!("{}{key}", self.prefix))
+            .invoke_async(&mut connection)
+            .await?;
+        Ok(())
+    }
+}
+
+/// How clients are told apart for quota accounting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaIdentity {
+    /// The IP address of the connected peer, from axum's [`ConnectInfo`].
+    ///
+    /// Requires serving the app with
+    /// `into_make_service_with_connect_info::<SocketAddr>()`. Behind a reverse proxy this
+    /// is the proxy's address; use [`QuotaIdentity::Header`] with a header the proxy sets instead.
+    ClientIp,
+    /// The value of a request header, such as an API key.
+    Header(HeaderName),
+    /// The payer of a payment, as verified by the facilitator.
+    ///
+    /// Clients pay as usual, but while the payer has quota left the payment is only
+    /// verified, never settled, so nothing is charged.
+    Payer,
+}
+
+impl QuotaIdentity {
+    /// The quota key of an unpaid request, if the client can be identified from it.
+    ///
+    /// Always `None` for [`QuotaIdentity::Payer`], which needs a verified payment.
+    pub fn request_key<B>(&self, req: &http::Request<B>) -> Option<String> {
+        match self {
+            QuotaIdentity::ClientIp => req
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| format!("ip:{}", addr.ip())),
+            QuotaIdentity::Header(name) => req
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| format!("header:{name}:{value}")),
+            QuotaIdentity::Payer => None,
+        }
+    }
+
+    /// The quota key of a verified payer.
+    pub fn payer_key(payer: &str) -> String {
+        format!("payer:{payer}")
+    }
+}
+
+/// A number of free requests per client and period, before payment is required.
+///
+/// Routes sharing one `FreeQuota` (or one store) share the quota of each client.
+#[derive(Clone)]
+pub struct FreeQuota {
+    limit: u64,
+    period: Duration,
+    identity: QuotaIdentity,
+    store: Arc<dyn QuotaStore>,
+}
+
+impl Debug for FreeQuota {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FreeQuota")
+            .field("limit", &self.limit)
+            .field("period", &self.period)
+            .field("identity", &self.identity)
+            .field("store", &"<store>")
+            .finish()
+    }
+}
+
+impl FreeQuota {
+    /// Allows `limit` free requests per client every `period`.
+    ///
+    /// Clients are identified by IP address and counted in a [`MemoryQuotaStore`].
+    pub fn new(limit: u64, period: Duration) -> Self {
+        Self {
+            limit,
+            period,
+            identity: QuotaIdentity::ClientIp,
+            store: Arc::new(MemoryQuotaStore::default()),
+        }
+    }
+
+    /// Allows `limit` free requests per client and day.
+    pub fn per_day(limit: u64) -> Self {
+        Self::new(limit, Duration::from_secs(24 * 60 * 60))
+    }
+
+    /// Sets how clients are identified (default: [`QuotaIdentity::ClientIp`]).
+    pub fn with_identity(mut self, identity: QuotaIdentity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Sets where usage is counted (default: a [`MemoryQuotaStore`]).
+    pub fn with_store<S: QuotaStore + 'static>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Free requests per client and period.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Length of a quota period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// How clients are identified.
+    pub fn identity(&self) -> &QuotaIdentity {
+        &self.identity
+    }
+
+    /// Takes a unit of quota for `key`.
+    ///
+    /// Store errors are treated as "no quota information", so the request goes through
+    /// the payment flow.
+    pub async fn acquire(&self, key: &str) -> Option<QuotaUsage> {
+        self.store
+            .acquire(key, self.limit, self.period)
+            .await
+            .inspect_err(|_err| {
+                #[cfg(feature = "telemetry")]
+                tracing::warn!(error = %_err, "Free quota unavailable, requiring payment");
+            })
+            .ok()
+    }
+
+    /// Gives back a unit of quota taken for `key`.
+    pub async fn release(&self, key: &str) {
+        if let Err(_err) = self.store.release(key).await {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!(error = %_err, "Failed to release free quota");
+        }
+    }
+
+    /// The `freeQuota` metadata added to payment requirements of 402 challenges.
+    pub fn extra(&self, usage: Option<&QuotaUsage>) -> serde_json::Value {
+        let mut extra = json!({
+            "limit": self.limit,
+            "periodSeconds": self.period.as_secs(),
+        });
+        if let Some(usage) = usage {
+            extra["remaining"] = json!(usage.remaining);
+            extra["resetSeconds"] = json!(usage.reset_after.as_secs());
+        }
+        extra
+    }
+}
+
+/// Inserts `value` under `key` in an `extra` object, creating the object if needed.
+///
+/// Non-object `extra` values are left untouched.
+pub(crate) fn insert_extra(
+    extra: &mut Option<serde_json::Value>,
+    key: &str,
+    value: serde_json::Value,
+) {
+    match extra {
+        Some(serde_json::Value::Object(fields)) => {
+            fields.insert(key.to_owned(), value);
+        }
+        Some(_) => {}
+        None => *extra = Some(json!({ key: value })),
+    }
+}
+
+/// Returns `requirements` without the `freeQuota` entry of `extra`.
+///
+/// The entry changes between challenges, so it is left out when matching the requirements
+/// a client accepted against the offered ones. An `extra` left empty is dropped.
+pub(crate) fn without_quota_extra(
+    requirements: &v2::PaymentRequirements,
+) -> v2::PaymentRequirements {
+    let mut requirements = requirements.clone();
+    if let Some(serde_json::Value::Object(fields)) = &mut requirements.extra {
+        fields.remove(FREE_QUOTA_EXTRA_KEY);
+        if fields.is_empty() {
+            requirements.extra = None;
+        }
+    }
+    requirements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[tokio::test]
+    async fn memory_store_grants_up_to_limit() {
+        let store = MemoryQuotaStore::default();
+        for remaining in [1, 0] {
+            let usage = store.acquire("ip:1.2.3.4", 2, DAY).await.unwrap();
+            assert!(usage.granted);
+            assert_eq!(usage.remaining, remaining);
+        }
+        let usage = store.acquire("ip:1.2.3.4", 2, DAY).await.unwrap();
+        assert!(!usage.granted);
+        assert_eq!(usage.remaining, 0);
+        assert!(usage.reset_after <= DAY);
+
+        // Other clients keep their own quota
+        assert!(store.acquire("ip:5.6.7.8", 2, DAY).await.unwrap().granted);
+    }
+
+    #[tokio::test]
+    async fn memory_store_release_and_reset() {
+        let store = MemoryQuotaStore::default();
+        assert!(store.acquire("key", 1, DAY).await.unwrap().granted);
+        store.release("key").await.unwrap();
+        assert!(store.acquire("key", 1, DAY).await.unwrap().granted);
+        assert!(!store.acquire("key", 1, DAY).await.unwrap().granted);
+
+        let period = Duration::from_millis(20);
+        assert!(store.acquire("short", 1, period).await.unwrap().granted);
+        assert!(!store.acquire("short", 1, period).await.unwrap().granted);
+        tokio::time::sleep(period).await;
+        assert!(store.acquire("short", 1, period).await.unwrap().granted);
+    }
+
+    #[tokio::test]
+    async fn memory_store_forgets_least_recent_client() {
+        let store = MemoryQuotaStore::new(NonZeroUsize::new(1).unwrap());
+        assert!(store.acquire("a", 1, DAY).await.unwrap().granted);
+        assert!(store.acquire("b", 1, DAY).await.unwrap().granted);
+        assert!(store.acquire("a", 1, DAY).await.unwrap().granted);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_acquires_never_exceed_limit() {
+        let store = Arc::new(MemoryQuotaStore::default());
+        let tasks = (0..64)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move { store.acquire("key", 10, DAY).await.unwrap() })
+            })
+            .collect::<Vec<_>>();
+        let mut granted = 0;
+        for task in tasks {
+            granted += u64::from(task.await.unwrap().granted);
+        }
+        assert_eq!(granted, 10);
+    }
+
+    #[test]
+    fn identity_keys() {
+        let mut req = http::Request::new(());
+        req.headers_mut()
+            .insert("x-api-key", "secret".parse().unwrap());
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4242))));
+        assert_eq!(
+            QuotaIdentity::ClientIp.request_key(&req).as_deref(),
+            Some("ip:10.0.0.1")
+        );
+        assert_eq!(
+            QuotaIdentity::Header(HeaderName::from_static("x-api-key"))
+                .request_key(&req)
+                .as_deref(),
+            Some("header:x-api-key:secret")
+        );
+        assert_eq!(QuotaIdentity::Payer.request_key(&req), None);
+        assert_eq!(
+            QuotaIdentity::ClientIp.request_key(&http::Request::new(())),
+            None
+        );
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn redis_store_grants_up_to_limit() {
+        let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".into());
+        let store = RedisQuotaStore::connect(&url)
+            .await
+            .unwrap()
+            .with_prefix(format!("x402:test:{}:", std::process::id()));
+        assert!(store.acquire("key", 2, DAY).await.unwrap().granted);
+        let usage = store.acquire("key", 2, DAY).await.unwrap();
+        assert!(usage.granted);
+        assert_eq!(usage.remaining, 0);
+        assert!(!store.acquire("key", 2, DAY).await.unwrap().granted);
+        store.release("key").await.unwrap();
+        assert!(store.acquire("key", 2, DAY).await.unwrap().granted);
+    }
+}
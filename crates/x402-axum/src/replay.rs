@@ -0,0 +1,694 @@
+//! Replay protection: rejecting a payment header presented more than once.
+//!
+//! Until its authorization is consumed on chain, a signed payment keeps passing facilitator
+//! verification. A buyer, or a misbehaving proxy, resending the same payment header to a
+//! non-idempotent route would get it served twice; with settlement after execution the
+//! window spans the whole handler run. With [`ReplayProtection`] enabled, each payment is
+//! claimed in a [`ReplayStore`] before it reaches the facilitator, and a payment already
+//! claimed is answered with `409 Conflict`:
+//!
+//! ```json
+//! { "error": "Payment already used", "problem": { "code": "nonce_used", "message": "..." } }
+//! ```
+//!
+//! Payments are identified by a digest of the asset and the payer and nonce of their
+//! authorization, or of the whole payload for transaction-based schemes. A claim lasts
+//! until the authorization expires (`validBefore`), or for a fallback TTL when the payload
+//! has no expiry. It is given back when the payment is rejected or the handler fails
+//! before settlement, so the buyer can retry; it is kept once the payment is settled.
+//!
+//! Claims live in memory by default ([`MemoryReplayStore`]). Sellers running several
+//! instances share them in Redis with the `redis` feature (`RedisReplayStore`).
+//!
+//! # Reusing settlements
+//!
+//! A buyer timing out while the handler runs retries with the same payment header, which
+//! would be rejected. On idempotent routes, [`ReplayPolicy::ReuseSettlement`] serves such a
+//! retry again: a payment settled on the same route within the
+//! [reuse window](ReplayProtection::with_reuse_window) goes straight to the handler,
+//! without verification or settlement, and the response carries the original payment
+//! response header. A retry arriving before the first request settled is still rejected.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_axum::replay::{ReplayPolicy, ReplayProtection};
+//!
+//! let replay = ReplayProtection::new();
+//! let x402 = X402Middleware::new("https://facilitator.x402.rs")
+//!     .with_replay_protection(replay.clone());
+//!
+//! // An idempotent route sharing the claims of the others
+//! let report = x402
+//!     .with_price_tag(price_tag)
+//!     .with_replay_protection(replay.on_replay(ReplayPolicy::ReuseSettlement));
+//! ```
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::response::Response;
+use http::{HeaderValue, StatusCode};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fmt::{Debug, Formatter};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use x402_types::proto;
+use x402_types::proto::{PaymentProblemBody, PaymentProblemCode};
+use x402_types::timestamp::UnixTimestamp;
+
+/// Errors returned by a [`ReplayStore`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    /// The store could not be reached or failed to record the claim.
+    #[error("Replay store error: {0}")]
+    Store(String),
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for ReplayError {
+    fn from(err: redis::RedisError) -> Self {
+        Self::Store(err.to_string())
+    }
+}
+
+/// Records the payments already presented, by replay key.
+///
+/// Implementations must make [`Self::claim`] atomic: of concurrent calls for one key,
+/// only one may succeed.
+#[async_trait]
+pub trait ReplayStore: Send + Sync {
+    /// Claims `key` for `ttl`. Returns `false` if it is already claimed.
+    async fn claim(&self, key: &str, ttl: Duration) -> Result<bool, ReplayError>;
+
+    /// Gives back a claim taken by [`Self::claim`], e.g. because the payment was rejected.
+    async fn release(&self, key: &str) -> Result<(), ReplayError>;
+
+    /// Attaches the settlement of a claimed payment to its claim, which now lasts `ttl`.
+    ///
+    /// The default does not record it, so that settlements are never reused.
+    async fn record_settlement(
+        &self,
+        _key: &str,
+        _settlement: &str,
+        _ttl: Duration,
+    ) -> Result<(), ReplayError> {
+        Ok(())
+    }
+
+    /// The settlement recorded for `key` by [`Self::record_settlement`], if any.
+    async fn settlement(&self, _key: &str) -> Result<Option<String>, ReplayError> {
+        Ok(None)
+    }
+}
+
+/// In-memory [`ReplayStore`] holding the most recent claims.
+///
+/// Once `capacity` claims are held, the least recent one is forgotten before it expires.
+/// Claims are local to the process.
+#[derive(Debug)]
+pub struct MemoryReplayStore {
+    claims: Mutex<LruCache<String, Claim>>,
+}
+
+#[derive(Debug)]
+struct Claim {
+    expires_at: Instant,
+    settlement: Option<String>,
+}
+
+impl MemoryReplayStore {
+    /// Number of claims held by [`MemoryReplayStore::default`].
+    pub const DEFAULT_CAPACITY: NonZeroUsize = NonZeroUsize::new(100_000).unwrap();
+
+    /// Creates a store holding up to `capacity` claims.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            claims: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+impl Default for MemoryReplayStore {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl ReplayStore for MemoryReplayStore {
+    async fn claim(&self, key: &str, ttl: Duration) -> Result<bool, ReplayError> {
+        let now = Instant::now();
+        let mut claims = self.claims.lock().expect("replay store lock poisoned");
+        if claims.get(key).is_some_and(|claim| claim.expires_at > now) {
+            return Ok(false);
+        }
+        let claim = Claim {
+            expires_at: now + ttl,
+            settlement: None,
+        };
+        claims.put(key.to_owned(), claim);
+        Ok(true)
+    }
+
+    async fn release(&self, key: &str) -> Result<(), ReplayError> {
+        self.claims
+            .lock()
+            .expect("replay store lock poisoned")
+            .pop(key);
+        Ok(())
+    }
+
+    async fn record_settlement(
+        &self,
+        key: &str,
+        settlement: &str,
+        ttl: Duration,
+    ) -> Result<(), ReplayError> {
+        let claim = Claim {
+            expires_at: Instant::now() + ttl,
+            settlement: Some(settlement.to_owned()),
+        };
+        self.claims
+            .lock()
+            .expect("replay store lock poisoned")
+            .put(key.to_owned(), claim);
+        Ok(())
+    }
+
+    async fn settlement(&self, key: &str) -> Result<Option<String>, ReplayError> {
+        let mut claims = self.claims.lock().expect("replay store lock poisoned");
+        let settlement = claims
+            .get(key)
+            .filter(|claim| claim.expires_at > Instant::now())
+            .and_then(|claim| claim.settlement.clone());
+        Ok(settlement)
+    }
+}
+
+/// Redis-backed [`ReplayStore`], sharing claims across server instances.
+///
+/// Each claim is one key, set if absent and expiring with the claim. Its value is empty
+/// until a settlement is recorded.
+#[cfg(feature = "redis")]
+#[derive(Clone)]
+pub struct RedisReplayStore {
+    connection: redis::aio::ConnectionManager,
+    prefix: String,
+}
+
+#[cfg(feature = "redis")]
+impl RedisReplayStore {
+    /// Default prefix of the claim keys.
+    pub const DEFAULT_PREFIX: &'static str = "x402:replay:";
+
+    /// Connects to the Redis server at `url`, e.g. `redis://127.0.0.1:6379`.
+    pub async fn connect(url: &str) -> Result<Self, ReplayError> {
+        let client = redis::Client::open(url)?;
+        let connection = redis::aio::ConnectionManager::new(client).await?;
+        Ok(Self::new(connection))
+    }
+
+    /// Creates a store on an existing connection.
+    pub fn new(connection: redis::aio::ConnectionManager) -> Self {
+        Self {
+            connection,
+            prefix: Self::DEFAULT_PREFIX.to_string(),
+        }
+    }
+
+    /// Sets the prefix of the claim keys (default: `x402:replay:`).
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Debug for RedisReplayStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisReplayStore")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl ReplayStore for RedisReplayStore {
+    async fn claim(&self, key: &str, ttl: Duration) -> Result<bool, ReplayError> {
+        let mut connection = self.connection.clone();
+        let ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+        let set: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{key}", self.prefix))
+            .arg("")
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_ms)
+            .query_async(&mut connection)
+            .await?;
+        Ok(set.is_some())
+    }
+
+    async fn release(&self, key: &str) -> Result<(), ReplayError> {
+        let mut connection = self.connection.clone();
+        let _: i64 = redis::cmd("DEL")
+            .arg(format!("{}{key}", self.prefix))
+            .query_async(&mut connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn record_settlement(
+        &self,
+        key: &str,
+        settlement: &str,
+        ttl: Duration,
+    ) -> Result<(), ReplayError> {
+        let mut connection = self.connection.clone();
+        let ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+        let _: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{key}", self.prefix))
+            .arg(settlement)
+            .arg("PX")
+            .arg(ttl_ms)
+            .query_async(&mut connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn settlement(&self, key: &str) -> Result<Option<String>, ReplayError> {
+        let mut connection = self.connection.clone();
+        let value: Option<String> = redis::cmd("GET")
+            .arg(format!("{}{key}", self.prefix))
+            .query_async(&mut connection)
+            .await?;
+        Ok(value.filter(|value| !value.is_empty()))
+    }
+}
+
+/// What happens to a payment presented again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplayPolicy {
+    /// Answer `409 Conflict` (default).
+    #[default]
+    Reject,
+    /// Serve the request again if the payment was settled on this route within the reuse
+    /// window, attaching the original payment response header. Otherwise answer `409 Conflict`.
+    ReuseSettlement,
+}
+
+/// Settlement of a payment as recorded in the [`ReplayStore`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedSettlement {
+    /// The payment response header sent with the original response.
+    header: String,
+    /// Digest of the payment requirements the payment was settled against.
+    requirements: String,
+    /// When the payment was settled.
+    settled_at: u64,
+}
+
+/// Rejects payments presented more than once.
+///
+/// Routes sharing one `ReplayProtection` (or one store) share their claims.
+#[derive(Clone)]
+pub struct ReplayProtection {
+    store: Arc<dyn ReplayStore>,
+    fallback_ttl: Duration,
+    policy: ReplayPolicy,
+    reuse_window: Duration,
+}
+
+impl Debug for ReplayProtection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayProtection")
+            .field("store", &"<store>")
+            .field("fallback_ttl", &self.fallback_ttl)
+            .field("policy", &self.policy)
+            .field("reuse_window", &self.reuse_window)
+            .finish()
+    }
+}
+
+impl Default for ReplayProtection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplayProtection {
+    /// Default claim duration of payments without an expiry (5 minutes).
+    pub const DEFAULT_FALLBACK_TTL: Duration = Duration::from_secs(5 * 60);
+    /// Default time during which a settlement may be reused (5 minutes).
+    pub const DEFAULT_REUSE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+    /// Creates replay protection with claims held in a [`MemoryReplayStore`].
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(MemoryReplayStore::default()),
+            fallback_ttl: Self::DEFAULT_FALLBACK_TTL,
+            policy: ReplayPolicy::default(),
+            reuse_window: Self::DEFAULT_REUSE_WINDOW,
+        }
+    }
+
+    /// Sets where claims are held (default: a [`MemoryReplayStore`]).
+    pub fn with_store<S: ReplayStore + 'static>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Sets how long payments without an expiry are claimed (default: 5 minutes).
+    pub fn with_fallback_ttl(mut self, fallback_ttl: Duration) -> Self {
+        self.fallback_ttl = fallback_ttl;
+        self
+    }
+
+    /// How long payments without an expiry are claimed.
+    pub fn fallback_ttl(&self) -> Duration {
+        self.fallback_ttl
+    }
+
+    /// Sets what happens to a payment presented again (default: [`ReplayPolicy::Reject`]).
+    ///
+    /// Clones share their store, so routes may set their own policy on a clone.
+    pub fn on_replay(mut self, policy: ReplayPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// What happens to a payment presented again.
+    pub fn policy(&self) -> ReplayPolicy {
+        self.policy
+    }
+
+    /// Sets how long after settlement a payment may be served again under
+    /// [`ReplayPolicy::ReuseSettlement`] (default: 5 minutes).
+    pub fn with_reuse_window(mut self, reuse_window: Duration) -> Self {
+        self.reuse_window = reuse_window;
+        self
+    }
+
+    /// How long after settlement a payment may be served again.
+    pub fn reuse_window(&self) -> Duration {
+        self.reuse_window
+    }
+
+    /// How long the payment of `request` is claimed: until its authorization expires, at
+    /// least a second, or the fallback TTL if it has no expiry.
+    pub fn ttl(&self, request: &proto::VerifyRequest) -> Duration {
+        match request.valid_before() {
+            Some(valid_before) => Duration::from_secs(
+                valid_before
+                    .as_secs()
+                    .saturating_sub(UnixTimestamp::now().as_secs())
+                    .max(1),
+            ),
+            None => self.fallback_ttl,
+        }
+    }
+
+    /// Claims the payment of `request`. Returns `false` if it was already presented.
+    ///
+    /// Store errors are treated as "not presented before", so the payment goes on to the
+    /// facilitator, which remains the authority on used authorizations.
+    pub async fn claim(&self, key: &str, request: &proto::VerifyRequest) -> bool {
+        self.store
+            .claim(key, self.ttl(request))
+            .await
+            .inspect_err(|_err| {
+                #[cfg(feature = "telemetry")]
+                tracing::warn!(error = %_err, "Replay store unavailable, skipping replay check");
+            })
+            .unwrap_or(true)
+    }
+
+    /// Gives back the claim of a payment that was not settled.
+    pub async fn release(&self, key: &str) {
+        if let Err(_err) = self.store.release(key).await {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!(error = %_err, "Failed to release replay claim");
+        }
+    }
+
+    /// Records that the payment of `request` was settled with the payment response `header`,
+    /// for reuse under [`ReplayPolicy::ReuseSettlement`]. Does nothing under other policies.
+    ///
+    /// The claim then lasts until the payment expires, and at least for the reuse window.
+    pub async fn record_settlement(
+        &self,
+        key: &str,
+        request: &proto::VerifyRequest,
+        header: &HeaderValue,
+    ) {
+        if self.policy != ReplayPolicy::ReuseSettlement {
+            return;
+        }
+        let (Ok(header), Some(requirements)) = (header.to_str(), requirements_digest(request))
+        else {
+            return;
+        };
+        let settlement = RecordedSettlement {
+            header: header.to_string(),
+            requirements,
+            settled_at: UnixTimestamp::now().as_secs(),
+        };
+        let settlement = serde_json::to_string(&settlement).expect("settlement is serializable");
+        let ttl = self.ttl(request).max(self.reuse_window);
+        if let Err(_err) = self.store.record_settlement(key, &settlement, ttl).await {
+            #[cfg(feature = "telemetry")]
+            tracing::warn!(error = %_err, "Failed to record settlement for reuse");
+        }
+    }
+
+    /// The payment response header of a settlement of `request` that may be reused: under
+    /// [`ReplayPolicy::ReuseSettlement`], settled against the same requirements within the
+    /// reuse window.
+    pub async fn reusable_settlement(
+        &self,
+        key: &str,
+        request: &proto::VerifyRequest,
+    ) -> Option<HeaderValue> {
+        if self.policy != ReplayPolicy::ReuseSettlement {
+            return None;
+        }
+        let settlement = self
+            .store
+            .settlement(key)
+            .await
+            .inspect_err(|_err| {
+                #[cfg(feature = "telemetry")]
+                tracing::warn!(error = %_err, "Replay store unavailable, not reusing settlement");
+            })
+            .ok()??;
+        let settlement: RecordedSettlement = serde_json::from_str(&settlement).ok()?;
+        let settled_for = UnixTimestamp::now()
+            .as_secs()
+            .saturating_sub(settlement.settled_at);
+        let reusable = settled_for <= self.reuse_window.as_secs()
+            && Some(&settlement.requirements) == requirements_digest(request).as_ref();
+        reusable
+            .then(|| HeaderValue::from_str(&settlement.header).ok())
+            .flatten()
+    }
+}
+
+/// Hex SHA-256 digest of the payment requirements of `request`.
+fn requirements_digest(request: &proto::VerifyRequest) -> Option<String> {
+    let request: serde_json::Value = serde_json::from_str(request.as_str()).ok()?;
+    let requirements = request.get("paymentRequirements")?;
+    Some(hex_digest(requirements.to_string().as_bytes()))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The replay key of the payment in `request`: a hex SHA-256 digest of the asset, payer and
+/// nonce of its authorization, or of the asset and whole payload if it has no authorization.
+///
+/// `None` if the request carries no payload.
+pub fn replay_key(request: &proto::VerifyRequest) -> Option<String> {
+    let request_json: serde_json::Value = serde_json::from_str(request.as_str()).ok()?;
+    let payload = request_json.get("paymentPayload")?.get("payload")?;
+    let asset = request.asset().unwrap_or_default();
+    let authorization = payload
+        .get("authorization")
+        .or_else(|| payload.get("permit2Authorization"));
+    let from = authorization.and_then(|authorization| authorization.get("from")?.as_str());
+    let nonce = authorization.and_then(|authorization| authorization.get("nonce")?.as_str());
+    // Hex fields are lowercased, so that re-encoding them does not make a new payment
+    let material = match (from, nonce) {
+        (Some(from), Some(nonce)) => json!([
+            asset.to_ascii_lowercase(),
+            from.to_ascii_lowercase(),
+            nonce.to_ascii_lowercase()
+        ]),
+        _ => json!([asset, payload]),
+    };
+    Some(hex_digest(material.to_string().as_bytes()))
+}
+
+/// The `409 Conflict` answered to a payment presented again.
+pub(crate) fn replayed_response() -> Response {
+    let problem = PaymentProblemBody {
+        code: PaymentProblemCode::NonceUsed,
+        message: "This payment was already presented".to_string(),
+    };
+    let body = json!({
+        "error": "Payment already used",
+        "problem": problem,
+    });
+    Response::builder()
+        .status(StatusCode::CONFLICT)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("Fail to construct response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(payload: serde_json::Value) -> proto::VerifyRequest {
+        serde_json::from_value(json!({
+            "x402Version": 2,
+            "paymentPayload": { "payload": payload },
+            "paymentRequirements": { "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn replay_key_identifies_authorizations() {
+        let authorization = |from: &str, nonce: &str, value: &str| {
+            request(json!({
+                "signature": "0x01",
+                "authorization": {
+                    "from": from,
+                    "nonce": nonce,
+                    "value": value,
+                    "validBefore": "1700000300",
+                },
+            }))
+        };
+        let key = replay_key(&authorization("0xAbC", "0x01", "100")).unwrap();
+        assert_eq!(key.len(), 64);
+        // Same payer and nonce, whatever the casing or the other fields
+        assert_eq!(
+            replay_key(&authorization("0xabc", "0x01", "200")).unwrap(),
+            key
+        );
+        assert_ne!(
+            replay_key(&authorization("0xabc", "0x02", "100")).unwrap(),
+            key
+        );
+
+        let transaction = replay_key(&request(json!({ "transaction": "AQID" }))).unwrap();
+        assert_ne!(transaction, key);
+        assert_ne!(
+            replay_key(&request(json!({ "transaction": "AQIE" }))).unwrap(),
+            transaction
+        );
+    }
+
+    #[test]
+    fn ttl_follows_authorization_expiry() {
+        let replay = ReplayProtection::new();
+        let valid_before = UnixTimestamp::now().as_secs() + 60;
+        let ttl = replay.ttl(&request(
+            json!({ "authorization": { "validBefore": valid_before.to_string() } }),
+        ));
+        assert!(ttl <= Duration::from_secs(60) && ttl >= Duration::from_secs(59));
+        let expired = replay.ttl(&request(
+            json!({ "authorization": { "validBefore": "1700000300" } }),
+        ));
+        assert_eq!(expired, Duration::from_secs(1));
+        let transaction = replay.ttl(&request(json!({ "transaction": "AQID" })));
+        assert_eq!(transaction, ReplayProtection::DEFAULT_FALLBACK_TTL);
+    }
+
+    #[tokio::test]
+    async fn memory_store_claims_until_expiry() {
+        let store = MemoryReplayStore::default();
+        let ttl = Duration::from_millis(20);
+        assert!(store.claim("key", ttl).await.unwrap());
+        assert!(!store.claim("key", ttl).await.unwrap());
+        assert!(store.claim("other", ttl).await.unwrap());
+
+        store.release("key").await.unwrap();
+        assert!(store.claim("key", ttl).await.unwrap());
+
+        tokio::time::sleep(ttl).await;
+        assert!(store.claim("key", ttl).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn settlements_are_reused_within_window_and_route() {
+        let replay = ReplayProtection::new().on_replay(ReplayPolicy::ReuseSettlement);
+        let request = request(json!({ "transaction": "AQID" }));
+        let key = replay_key(&request).unwrap();
+        let header = HeaderValue::from_static("eyJzdWNjZXNzIjp0cnVlfQ==");
+        assert!(replay.claim(&key, &request).await);
+        // Claimed but not settled yet
+        assert_eq!(replay.reusable_settlement(&key, &request).await, None);
+
+        replay.record_settlement(&key, &request, &header).await;
+        assert_eq!(
+            replay.reusable_settlement(&key, &request).await,
+            Some(header.clone())
+        );
+        // Recording a settlement keeps the claim
+        assert!(!replay.claim(&key, &request).await);
+
+        let other_route: proto::VerifyRequest = serde_json::from_value(json!({
+            "x402Version": 2,
+            "paymentPayload": { "payload": { "transaction": "AQID" } },
+            "paymentRequirements": { "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e", "amount": "1" },
+        }))
+        .unwrap();
+        assert_eq!(replay.reusable_settlement(&key, &other_route).await, None);
+
+        let stale = serde_json::to_string(&RecordedSettlement {
+            header: header.to_str().unwrap().to_string(),
+            requirements: requirements_digest(&request).unwrap(),
+            settled_at: UnixTimestamp::now().as_secs() - 10,
+        })
+        .unwrap();
+        let replay = replay.with_reuse_window(Duration::from_secs(5));
+        replay
+            .store
+            .record_settlement(&key, &stale, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(replay.reusable_settlement(&key, &request).await, None);
+
+        let rejecting = ReplayProtection::new();
+        rejecting.record_settlement(&key, &request, &header).await;
+        assert_eq!(rejecting.reusable_settlement(&key, &request).await, None);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn redis_store_claims_once() {
+        let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".into());
+        let store = RedisReplayStore::connect(&url)
+            .await
+            .unwrap()
+            .with_prefix(format!("x402:test:{}:", std::process::id()));
+        let ttl = Duration::from_secs(60);
+        assert!(store.claim("key", ttl).await.unwrap());
+        assert!(!store.claim("key", ttl).await.unwrap());
+        store.release("key").await.unwrap();
+        assert!(store.claim("key", ttl).await.unwrap());
+    }
+}
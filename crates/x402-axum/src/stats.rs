@@ -0,0 +1,160 @@
+//! Payment statistics of the routes protected by an [`X402Middleware`](crate::X402Middleware).
+//!
+//! Each middleware instance counts the requests reaching its payment gates in a shared
+//! [`PaymentStats`], available from [`X402Middleware::stats`](crate::X402Middleware::stats).
+//! Routes built from clones of the instance, such as the ones returned by its `with_*`
+//! methods, share the same counters. [`X402Middleware::with_stats_endpoint`](crate::X402Middleware::with_stats_endpoint)
+//! sets the path [`X402Middleware::stats_router`](crate::X402Middleware::stats_router)
+//! serves them at.
+//!
+//! # Counters
+//!
+//! - `total`: requests to a priced route. Routes whose dynamic price resolves to no price
+//!   tag are free and not counted.
+//! - `paid`: requests served with a settled payment, including retries served with the
+//!   original settlement under [`ReplayPolicy::ReuseSettlement`](crate::replay::ReplayPolicy::ReuseSettlement).
+//! - `refused`: requests answered with a challenge because they carried no payment, and
+//!   payments rejected as replayed.
+//! - `failed`: payments that failed verification or settlement.
+//! - `total_amount_raw`: sum of the settled amounts, in token base units. Amounts of
+//!   different assets add up, so the sum is only meaningful for routes priced in one asset.
+//!
+//! Requests served on free quota, and paid requests whose handler failed before
+//! settlement, only count towards `total`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let x402 = X402Middleware::new("https://facilitator.x402.rs").with_stats_endpoint("/x402/stats");
+//! let app = Router::new()
+//!     .route("/weather", get(weather).layer(x402.with_price_tag(price_tag)))
+//!     .merge(x402.stats_router());
+//! // GET /x402/stats
+//! // { "total": 12, "paid": 9, "refused": 2, "failed": 1, "total_amount_raw": "90000" }
+//! ```
+
+use serde::{Serialize, Serializer};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Payment counters of an [`X402Middleware`](crate::X402Middleware) instance.
+///
+/// The total amount is kept behind a mutex, as there is no stable 128-bit atomic.
+#[derive(Debug, Default)]
+pub struct PaymentStats {
+    total_requests: AtomicU64,
+    paid_requests: AtomicU64,
+    refused_requests: AtomicU64,
+    verification_failures: AtomicU64,
+    total_amount: Mutex<u128>,
+}
+
+impl PaymentStats {
+    /// Creates zeroed counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a request to a priced route.
+    pub fn record_request(&self) {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a request served with a settled payment of `amount` base units, if known.
+    pub fn record_paid(&self, amount: Option<u128>) {
+        self.paid_requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(amount) = amount {
+            let mut total_amount = self.total_amount.lock().expect("stats lock poisoned");
+            *total_amount = total_amount.saturating_add(amount);
+        }
+    }
+
+    /// Counts a request refused for want of a payment.
+    pub fn record_refused(&self) {
+        self.refused_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a payment that failed verification or settlement.
+    pub fn record_failure(&self) {
+        self.verification_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Resets all counters to zero.
+    pub fn reset(&self) {
+        self.total_requests.store(0, Ordering::Relaxed);
+        self.paid_requests.store(0, Ordering::Relaxed);
+        self.refused_requests.store(0, Ordering::Relaxed);
+        self.verification_failures.store(0, Ordering::Relaxed);
+        *self.total_amount.lock().expect("stats lock poisoned") = 0;
+    }
+
+    /// Returns the current values of the counters.
+    ///
+    /// Counters are read one by one, so a snapshot taken under load may be off by the
+    /// requests completing meanwhile.
+    pub fn snapshot(&self) -> PaymentStatsSnapshot {
+        PaymentStatsSnapshot {
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            paid_requests: self.paid_requests.load(Ordering::Relaxed),
+            refused_requests: self.refused_requests.load(Ordering::Relaxed),
+            verification_failures: self.verification_failures.load(Ordering::Relaxed),
+            total_amount: *self.total_amount.lock().expect("stats lock poisoned"),
+        }
+    }
+}
+
+/// Values of [`PaymentStats`] at a point in time.
+///
+/// Serializes as served by the stats endpoint, with the amount as a decimal string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PaymentStatsSnapshot {
+    /// Requests to a priced route.
+    #[serde(rename = "total")]
+    pub total_requests: u64,
+    /// Requests served with a settled payment.
+    #[serde(rename = "paid")]
+    pub paid_requests: u64,
+    /// Requests refused for want of a payment.
+    #[serde(rename = "refused")]
+    pub refused_requests: u64,
+    /// Payments that failed verification or settlement.
+    #[serde(rename = "failed")]
+    pub verification_failures: u64,
+    /// Sum of the settled amounts, in token base units.
+    #[serde(rename = "total_amount_raw", serialize_with = "serialize_u128")]
+    pub total_amount: u128,
+}
+
+fn serialize_u128<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn snapshot_serializes_as_served() {
+        let stats = PaymentStats::new();
+        stats.record_request();
+        stats.record_request();
+        stats.record_paid(Some(u128::from(u64::MAX) + 1));
+        stats.record_paid(None);
+        stats.record_refused();
+        stats.record_failure();
+        assert_eq!(
+            serde_json::to_value(stats.snapshot()).unwrap(),
+            json!({
+                "total": 2,
+                "paid": 2,
+                "refused": 1,
+                "failed": 1,
+                "total_amount_raw": "18446744073709551616",
+            })
+        );
+
+        stats.reset();
+        assert_eq!(stats.snapshot(), PaymentStatsSnapshot::default());
+    }
+}
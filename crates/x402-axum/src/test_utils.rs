@@ -0,0 +1,460 @@
+//! Test utilities for applications protected by [`X402Middleware`](crate::X402Middleware).
+//!
+//! [`MockFacilitator`] serves the facilitator HTTP surface (`/verify`, `/settle` and
+//! `/supported`) on a local port, with programmable behavior, and records the requests it
+//! receives. [`payment_header`] and [`payment_header_v1`] build validly-structured, but
+//! unsigned, payment headers for an accepted payment option, so that handler tests can
+//! exercise the paid path without a wallet.
+//!
+//! Requires the `test-utils` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use axum::{Router, routing::get};
+//! use x402_axum::test_utils::{MockBehavior, MockFacilitator, payment_header};
+//! use x402_types::proto::v2;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let facilitator = MockFacilitator::start().await;
+//! let price_tag = v2::PriceTag {
+//!     requirements: v2::PaymentRequirements {
+//!         scheme: "exact".into(),
+//!         network: "eip155:84532".parse().unwrap(),
+//!         amount: "10000".into(),
+//!         pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+//!         max_timeout_seconds: 300,
+//!         asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+//!         extra: None,
+//!     },
+//!     enricher: None,
+//! };
+//! let (name, value) = payment_header(&price_tag.requirements);
+//!
+//! let x402 = facilitator.middleware();
+//! let app: Router = Router::new().route(
+//!     "/protected",
+//!     get(|| async { "VIP content" }).layer(x402.with_price_tag(price_tag)),
+//! );
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+//! let addr = listener.local_addr().unwrap();
+//! tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+//!
+//! let url = format!("http://{addr}/protected");
+//! let client = reqwest::Client::new();
+//! let response = client.get(&url).header(&name, &value).send().await.unwrap();
+//! assert_eq!(response.status(), 200);
+//! assert_eq!(facilitator.settle_requests().len(), 1);
+//!
+//! facilitator.set_behavior(MockBehavior::Reject("insufficient_funds".into()));
+//! let response = client.get(&url).header(name, value).send().await.unwrap();
+//! assert_eq!(response.status(), 402);
+//! # }
+//! ```
+
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use http::{HeaderName, HeaderValue};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use url::Url;
+use x402_types::proto::{SupportedResponse, v1, v2};
+use x402_types::util::Base64Bytes;
+
+use crate::X402Middleware;
+use crate::facilitator_client::FacilitatorClient;
+use crate::paygate::PaygateProtocol;
+
+/// Payer reported by [`MockFacilitator`] unless set with [`MockFacilitator::set_payer`].
+pub const MOCK_PAYER: &str = "0x0000000000000000000000000000000000000001";
+
+/// Transaction hash reported by [`MockFacilitator`] for successful settlements.
+pub const MOCK_TRANSACTION: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+/// How a [`MockFacilitator`] answers verify and settle requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MockBehavior {
+    /// Every payment is valid and settles.
+    #[default]
+    AcceptAll,
+    /// Every payment is invalid, with the given reason.
+    Reject(String),
+    /// Every payment is valid, but settlement fails with the given reason.
+    FailSettlement(String),
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    behavior: MockBehavior,
+    delay: Duration,
+    payer: Option<String>,
+    supported: SupportedResponse,
+    verify_requests: Vec<Value>,
+    settle_requests: Vec<Value>,
+}
+
+/// Aborts the server task once the last clone of the facilitator is dropped.
+#[derive(Debug)]
+struct ServerGuard(AbortHandle);
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A facilitator served on a local port, for integration tests.
+///
+/// Cloning is cheap and clones share the same server, behavior and recorded requests.
+/// The server stops when the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct MockFacilitator {
+    url: Url,
+    state: Arc<Mutex<MockState>>,
+    _server: Arc<ServerGuard>,
+}
+
+impl MockFacilitator {
+    /// Starts a facilitator accepting every payment on a random local port.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no local port can be bound.
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let app = Router::new()
+            .route("/verify", post(verify))
+            .route("/settle", post(settle))
+            .route("/supported", get(supported))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind mock facilitator");
+        let addr = listener
+            .local_addr()
+            .expect("mock facilitator has no local address");
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .await
+                .expect("mock facilitator failed")
+        });
+        Self {
+            url: format!("http://{addr}/").parse().expect("valid local URL"),
+            state,
+            _server: Arc::new(ServerGuard(server.abort_handle())),
+        }
+    }
+
+    /// Base URL of the facilitator.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns middleware using this facilitator.
+    pub fn middleware(&self) -> X402Middleware<Arc<FacilitatorClient>> {
+        X402Middleware::new(self.url.as_str())
+    }
+
+    /// Sets how verify and settle requests are answered.
+    pub fn set_behavior(&self, behavior: MockBehavior) {
+        self.lock().behavior = behavior;
+    }
+
+    /// Delays every response by `delay`.
+    pub fn set_delay(&self, delay: Duration) {
+        self.lock().delay = delay;
+    }
+
+    /// Sets the payer reported in verify and settle responses.
+    pub fn set_payer(&self, payer: impl Into<String>) {
+        self.lock().payer = Some(payer.into());
+    }
+
+    /// Sets the response of `/supported`, empty by default.
+    pub fn set_supported(&self, supported: SupportedResponse) {
+        self.lock().supported = supported;
+    }
+
+    /// Returns the bodies of the verify requests received so far.
+    pub fn verify_requests(&self) -> Vec<Value> {
+        self.lock().verify_requests.clone()
+    }
+
+    /// Returns the bodies of the settle requests received so far.
+    pub fn settle_requests(&self) -> Vec<Value> {
+        self.lock().settle_requests.clone()
+    }
+
+    /// Forgets the requests received so far.
+    pub fn clear_requests(&self) {
+        let mut state = self.lock();
+        state.verify_requests.clear();
+        state.settle_requests.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().expect("mock facilitator lock poisoned")
+    }
+}
+
+type SharedState = State<Arc<Mutex<MockState>>>;
+
+/// Records a request and returns the behavior, payer and delay to answer it with.
+fn record(
+    state: &Mutex<MockState>,
+    request: Value,
+    settle: bool,
+) -> (MockBehavior, String, Duration) {
+    let mut state = state.lock().expect("mock facilitator lock poisoned");
+    if settle {
+        state.settle_requests.push(request);
+    } else {
+        state.verify_requests.push(request);
+    }
+    let payer = state
+        .payer
+        .clone()
+        .unwrap_or_else(|| MOCK_PAYER.to_string());
+    (state.behavior.clone(), payer, state.delay)
+}
+
+async fn verify(State(state): SharedState, Json(request): Json<Value>) -> Json<Value> {
+    let (behavior, payer, delay) = record(&state, request, false);
+    tokio::time::sleep(delay).await;
+    let response = match behavior {
+        MockBehavior::Reject(reason) => v1::VerifyResponse::invalid(Some(payer), reason),
+        MockBehavior::AcceptAll | MockBehavior::FailSettlement(_) => {
+            v1::VerifyResponse::valid(payer)
+        }
+    };
+    Json(json!(response))
+}
+
+async fn settle(State(state): SharedState, Json(request): Json<Value>) -> Json<Value> {
+    let network = request["paymentRequirements"]["network"].clone();
+    let (behavior, payer, delay) = record(&state, request, true);
+    tokio::time::sleep(delay).await;
+    let response = match behavior {
+        MockBehavior::AcceptAll => json!({
+            "success": true,
+            "transaction": MOCK_TRANSACTION,
+            "network": network,
+            "payer": payer,
+        }),
+        MockBehavior::Reject(reason) | MockBehavior::FailSettlement(reason) => json!({
+            "success": false,
+            "errorReason": reason,
+            "network": network,
+        }),
+    };
+    Json(response)
+}
+
+async fn supported(State(state): SharedState) -> Json<SupportedResponse> {
+    let (supported, delay) = {
+        let state = state.lock().expect("mock facilitator lock poisoned");
+        (state.supported.clone(), state.delay)
+    };
+    tokio::time::sleep(delay).await;
+    Json(supported)
+}
+
+/// Returns an unsigned V2 `Payment-Signature` header paying for `accept`.
+///
+/// The payload is empty, so the header only passes a facilitator that does not check
+/// signatures, such as [`MockFacilitator`].
+pub fn payment_header(accept: &v2::PaymentRequirements) -> (HeaderName, HeaderValue) {
+    let payload = json!({
+        "x402Version": 2,
+        "accepted": accept,
+        "payload": {},
+    });
+    encode(v2::PriceTag::PAYMENT_HEADER_NAME, &payload)
+}
+
+/// Returns an unsigned V1 `X-PAYMENT` header paying for `accept`.
+///
+/// The payload is empty, so the header only passes a facilitator that does not check
+/// signatures, such as [`MockFacilitator`].
+pub fn payment_header_v1(accept: &v1::PaymentRequirements) -> (HeaderName, HeaderValue) {
+    let payload = json!({
+        "x402Version": 1,
+        "scheme": accept.scheme,
+        "network": accept.network,
+        "payload": {},
+    });
+    encode(v1::PriceTag::PAYMENT_HEADER_NAME, &payload)
+}
+
+fn encode(name: &'static str, payload: &Value) -> (HeaderName, HeaderValue) {
+    let encoded = Base64Bytes::encode(serde_json::to_vec(payload).expect("serializable payload"));
+    let value = HeaderValue::from_bytes(encoded.as_ref()).expect("base64 is a valid header");
+    let name = HeaderName::from_bytes(name.as_bytes()).expect("valid header name");
+    (name, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use x402_types::proto;
+
+    fn v1_price_tag() -> v1::PriceTag {
+        v1::PriceTag {
+            scheme: "exact".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            network: "base-sepolia".into(),
+            amount: "10000".into(),
+            max_timeout_seconds: 300,
+            extra: None,
+            enricher: None,
+        }
+    }
+
+    fn v2_price_tag() -> v2::PriceTag {
+        v2::PriceTag {
+            requirements: v2::PaymentRequirements {
+                scheme: "exact".into(),
+                network: "eip155:84532".parse().unwrap(),
+                amount: "10000".into(),
+                pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+                max_timeout_seconds: 300,
+                asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+                extra: None,
+            },
+            enricher: None,
+        }
+    }
+
+    /// Serves `app` on a local port and returns the URL of `/protected`.
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/protected")
+    }
+
+    async fn v1_challenge(url: &str) -> v1::PaymentRequired {
+        let response = reqwest::get(url).await.unwrap();
+        assert_eq!(response.status(), 402);
+        response.json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepted_payment_is_verified_and_settled() {
+        let facilitator = MockFacilitator::start().await;
+        facilitator.set_payer("0xpayer");
+        let x402 = facilitator.middleware();
+        let app = Router::new().route(
+            "/protected",
+            get(|| async { "paid" }).layer(x402.with_price_tag(v2_price_tag())),
+        );
+        let url = serve(app).await;
+
+        let (name, value) = payment_header(&v2_price_tag().requirements);
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header(name, value)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let payment_response = response.headers().get("Payment-Response").unwrap();
+        let payment_response =
+            proto::PaymentResponse::from_header(payment_response.as_bytes()).unwrap();
+        assert!(payment_response.success);
+        assert_eq!(payment_response.transaction, MOCK_TRANSACTION);
+        assert_eq!(payment_response.payer.as_deref(), Some("0xpayer"));
+        assert_eq!(response.text().await.unwrap(), "paid");
+
+        let verified = facilitator.verify_requests();
+        assert_eq!(verified.len(), 1);
+        assert_eq!(
+            verified[0]["paymentRequirements"],
+            json!(v2_price_tag().requirements)
+        );
+        assert_eq!(facilitator.settle_requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_payment_gets_challenge_with_reason() {
+        let facilitator = MockFacilitator::start().await;
+        facilitator.set_behavior(MockBehavior::Reject("insufficient_funds".into()));
+        let x402 = facilitator.middleware();
+        let app = Router::new().route(
+            "/protected",
+            get(|| async { "paid" }).layer(x402.with_price_tag(v1_price_tag())),
+        );
+        let url = serve(app).await;
+
+        let challenge = v1_challenge(&url).await;
+        let (name, value) = payment_header_v1(&challenge.accepts[0]);
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header(name, value)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 402);
+        let challenge: v1::PaymentRequired = response.json().await.unwrap();
+        assert!(challenge.error.unwrap().contains("insufficient_funds"));
+        assert_eq!(facilitator.verify_requests().len(), 1);
+        assert!(facilitator.settle_requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_settlement_is_not_served() {
+        let facilitator = MockFacilitator::start().await;
+        facilitator.set_behavior(MockBehavior::FailSettlement("nonce_used".into()));
+        let x402 = facilitator.middleware();
+        let app = Router::new().route(
+            "/protected",
+            get(|| async { "paid" }).layer(x402.with_price_tag(v1_price_tag())),
+        );
+        let url = serve(app).await;
+
+        let challenge = v1_challenge(&url).await;
+        let (name, value) = payment_header_v1(&challenge.accepts[0]);
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header(name, value)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 402);
+        assert!(response.text().await.unwrap().contains("nonce_used"));
+        assert_eq!(facilitator.settle_requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn slow_facilitator_times_out_verification() {
+        let facilitator = MockFacilitator::start().await;
+        facilitator.set_delay(Duration::from_secs(5));
+        let client = FacilitatorClient::try_new(facilitator.url().clone())
+            .unwrap()
+            .with_timeout(Duration::from_millis(50));
+        let x402 = X402Middleware::from_facilitator(Arc::new(client));
+        let app = Router::new().route(
+            "/protected",
+            get(|| async { "paid" }).layer(x402.with_price_tag(v2_price_tag())),
+        );
+        let url = serve(app).await;
+
+        let (name, value) = payment_header(&v2_price_tag().requirements);
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header(name, value)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 402);
+        assert_eq!(facilitator.verify_requests().len(), 1);
+        assert!(facilitator.settle_requests().is_empty());
+    }
+}
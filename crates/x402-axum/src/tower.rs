@@ -0,0 +1,152 @@
+//! Payment enforcement for plain [`tower`](::tower) and [hyper](https://hyper.rs) services.
+//!
+//! The payment gate itself does not depend on axum: it reads the payment header, builds the
+//! `402 Payment Required` challenge, and calls the facilitator to verify and settle, on
+//! [`http`] requests and responses. [`X402LayerBuilder::into_tower`] exposes it as an
+//! [`X402TowerLayer`] that wraps any service taking `http::Request<Body>` and returning
+//! `http::Response<B>` for any body `B`, e.g. a hyper gateway's handler.
+//!
+//! The resulting [`X402TowerService`] accepts requests with any body and answers with the
+//! type-erased [`Body`], which hyper serves as is. Errors of the inner service are
+//! answered with `500 Internal Server Error`, so the payment is not settled.
+//!
+//! Everything configured on the [`X402Middleware`](crate::X402Middleware) and the route
+//! layer applies, except for what relies on axum's request extensions: the route reported
+//! to the [meter](crate::meter) is the request path, and [`QuotaIdentity::ClientIp`](crate::quota::QuotaIdentity::ClientIp)
+//! quotas need the peer address inserted in the request extensions as axum's `ConnectInfo`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let x402 = X402Middleware::new("https://facilitator.x402.rs");
+//! let service = ServiceBuilder::new()
+//!     .layer(x402.with_price_tag(price_tag).into_tower())
+//!     .service_fn(|_req: http::Request<Body>| async {
+//!         Ok::<_, Infallible>(http::Response::new("paid content".to_string()))
+//!     });
+//! let response = service.oneshot(request).await?;
+//! ```
+
+use ::tower::util::ServiceExt;
+use ::tower::{BoxError, Layer, Service};
+use axum_core::extract::Request;
+use axum_core::response::{IntoResponse, Response};
+use bytes::Bytes;
+use http::StatusCode;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use x402_types::facilitator::Facilitator;
+
+use crate::layer::{X402LayerBuilder, X402MiddlewareService};
+use crate::paygate::{PaygateProtocol, PriceTagSource};
+
+pub use axum_core::body::Body;
+
+impl<TSource, TFacilitator> X402LayerBuilder<TSource, TFacilitator> {
+    /// Turns this route layer into one wrapping any `tower` service, not only axum handlers.
+    pub fn into_tower(self) -> X402TowerLayer<TSource, TFacilitator> {
+        X402TowerLayer { builder: self }
+    }
+}
+
+/// Layer enforcing x402 payments on a service taking `http::Request<Body>`.
+///
+/// Built with [`X402LayerBuilder::into_tower`].
+#[derive(Clone)]
+pub struct X402TowerLayer<TSource, TFacilitator> {
+    builder: X402LayerBuilder<TSource, TFacilitator>,
+}
+
+impl<S, ResBody, TSource, TFacilitator> Layer<S> for X402TowerLayer<TSource, TFacilitator>
+where
+    S: Service<http::Request<Body>, Response = http::Response<ResBody>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+    TFacilitator: Facilitator + Clone,
+    TSource: PriceTagSource + Clone,
+{
+    type Service = X402TowerService<TSource, TFacilitator>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        X402TowerService {
+            inner: self.builder.layer(HttpService { inner }),
+        }
+    }
+}
+
+/// Service enforcing x402 payments, built by [`X402TowerLayer`].
+///
+/// Accepts requests with any body, and answers with a [`Body`].
+#[derive(Clone)]
+pub struct X402TowerService<TSource, TFacilitator> {
+    inner: X402MiddlewareService<TSource, TFacilitator>,
+}
+
+impl<B, TSource, TFacilitator> Service<http::Request<B>> for X402TowerService<TSource, TFacilitator>
+where
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+    TSource: PriceTagSource + Clone + Send + 'static,
+    TSource::PriceTag: PaygateProtocol,
+    TFacilitator: Facilitator + Clone + Send + Sync + 'static,
+{
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        self.inner.call(req.map(Body::new))
+    }
+}
+
+/// Adapts a service with any response body and error type to the axum service shape the
+/// payment gate wraps.
+#[derive(Clone)]
+struct HttpService<S> {
+    inner: S,
+}
+
+impl<S, ResBody> Service<Request> for HttpService<S>
+where
+    S: Service<Request, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    /// The inner service is driven to readiness in [`Self::call`].
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            match inner.oneshot(req).await {
+                Ok(response) => Ok(response.map(Body::new)),
+                Err(error) => {
+                    let _error: BoxError = error.into();
+                    #[cfg(feature = "telemetry")]
+                    tracing::error!(error = %_error, "Inner service failed");
+                    Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+                }
+            }
+        })
+    }
+}
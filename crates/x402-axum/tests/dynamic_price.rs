@@ -0,0 +1,115 @@
+//! Routes with a dynamic price charge the price computed for each request.
+
+use axum::Router;
+use axum::routing::get;
+use x402_axum::test_utils::{MockFacilitator, payment_header};
+use x402_types::proto::v2;
+
+/// Price in atomic units of an image at the requested resolution: free thumbnails,
+/// and 4K images cost more than the default.
+fn price(resolution: Option<&str>) -> &'static str {
+    match resolution {
+        Some("thumbnail") => "0",
+        Some("4k") => "40000",
+        _ => "10000",
+    }
+}
+
+fn price_tag(amount: &str) -> v2::PriceTag {
+    v2::PriceTag {
+        requirements: v2::PaymentRequirements {
+            scheme: "exact".into(),
+            network: "eip155:84532".parse().unwrap(),
+            amount: amount.into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            max_timeout_seconds: 300,
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            extra: None,
+        },
+        enricher: None,
+    }
+}
+
+/// Serves images priced by the `resolution` query parameter, returning their base URL.
+async fn serve(facilitator: &MockFacilitator) -> String {
+    let x402 = facilitator.middleware();
+    let app = Router::new().route(
+        "/image",
+        get(|| async { "image" }).layer(x402.with_dynamic_price(|_headers, uri, _base_url| {
+            let resolution = uri
+                .query()
+                .and_then(|query| query.strip_prefix("resolution="))
+                .map(str::to_owned);
+            async move { vec![price_tag(price(resolution.as_deref()))] }
+        })),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}/image")
+}
+
+#[tokio::test]
+async fn each_request_is_charged_its_own_price() {
+    let facilitator = MockFacilitator::start().await;
+    let url = serve(&facilitator).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{url}?resolution=4k"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 402);
+    let header = response.headers()["Payment-Required"].as_bytes();
+    let payment_required: serde_json::Value = serde_json::from_slice(
+        &x402_types::util::Base64Bytes::from(header)
+            .decode()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(payment_required["accepts"][0]["amount"], "40000");
+
+    let (name, value) = payment_header(&price_tag("40000").requirements);
+    let response = client
+        .get(format!("{url}?resolution=4k"))
+        .header(name, value)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "image");
+    assert_eq!(facilitator.settle_requests().len(), 1);
+}
+
+#[tokio::test]
+async fn payments_for_another_price_are_rejected() {
+    let facilitator = MockFacilitator::start().await;
+    let url = serve(&facilitator).await;
+
+    // Paying the default price for a 4K image
+    let (name, value) = payment_header(&price_tag("10000").requirements);
+    let response = reqwest::Client::new()
+        .get(format!("{url}?resolution=4k"))
+        .header(name, value)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 402);
+    assert!(facilitator.verify_requests().is_empty());
+    assert!(facilitator.settle_requests().is_empty());
+}
+
+#[tokio::test]
+async fn zero_prices_require_no_payment() {
+    let facilitator = MockFacilitator::start().await;
+    let url = serve(&facilitator).await;
+
+    let response = reqwest::get(format!("{url}?resolution=thumbnail"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(!response.headers().contains_key("Payment-Response"));
+    assert_eq!(response.text().await.unwrap(), "image");
+    assert!(facilitator.verify_requests().is_empty());
+}
@@ -0,0 +1,91 @@
+//! Each request carrying a payment is reported to the meter sink once its settlement is
+//! resolved.
+
+use axum::Router;
+use axum::routing::get;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use x402_axum::meter::{PaymentEvent, PaymentOutcome};
+use x402_axum::test_utils::{MOCK_TRANSACTION, MockBehavior, MockFacilitator, payment_header};
+use x402_types::proto::v2;
+
+const ASSET: &str = "0x036CbD53842c5426634e7929541eC2318f3dCF7e";
+
+fn price_tag() -> v2::PriceTag {
+    v2::PriceTag {
+        requirements: v2::PaymentRequirements {
+            scheme: "exact".into(),
+            network: "eip155:84532".parse().unwrap(),
+            amount: "10000".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            max_timeout_seconds: 300,
+            asset: ASSET.into(),
+            extra: None,
+        },
+        enricher: None,
+    }
+}
+
+async fn next_event(events: &mut mpsc::UnboundedReceiver<PaymentEvent>) -> PaymentEvent {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("no event received")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn payments_are_metered() {
+    let facilitator = MockFacilitator::start().await;
+    facilitator.set_payer("0xpayer");
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let x402 = facilitator
+        .middleware()
+        .with_meter(move |event: PaymentEvent| sender.send(event).unwrap());
+    let app = Router::new().route(
+        "/items/{id}",
+        get(|| async { "paid" }).layer(x402.with_price_tag(price_tag())),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let url = format!("http://{addr}/items/42");
+    let client = reqwest::Client::new();
+    let get_paid = || {
+        let (name, value) = payment_header(&price_tag().requirements);
+        client.get(&url).header(name, value).send()
+    };
+
+    // Requests without a payment are not metered
+    assert_eq!(client.get(&url).send().await.unwrap().status(), 402);
+
+    assert_eq!(get_paid().await.unwrap().status(), 200);
+    let event = next_event(&mut events).await;
+    assert_eq!(event.outcome, PaymentOutcome::Settled);
+    assert_eq!(event.payer.as_deref(), Some("0xpayer"));
+    assert_eq!(event.route, "/items/{id}");
+    assert_eq!(event.amount.as_deref(), Some("10000"));
+    assert_eq!(event.asset.as_deref(), Some(ASSET));
+    assert_eq!(event.network.as_deref(), Some("eip155:84532"));
+    assert_eq!(event.transaction.as_deref(), Some(MOCK_TRANSACTION));
+    assert_eq!(event.status, 200);
+
+    facilitator.set_behavior(MockBehavior::Reject("insufficient_funds".into()));
+    assert_eq!(get_paid().await.unwrap().status(), 402);
+    let event = next_event(&mut events).await;
+    assert_eq!(event.outcome, PaymentOutcome::VerificationFailed);
+    assert_eq!(event.payer, None);
+    assert_eq!(event.route, "/items/{id}");
+    assert_eq!(event.amount.as_deref(), Some("10000"));
+    assert_eq!(event.transaction, None);
+    assert_eq!(event.status, 402);
+
+    facilitator.set_behavior(MockBehavior::FailSettlement("nonce_used".into()));
+    assert_eq!(get_paid().await.unwrap().status(), 402);
+    let event = next_event(&mut events).await;
+    assert_eq!(event.outcome, PaymentOutcome::SettlementFailed);
+    assert_eq!(event.asset.as_deref(), Some(ASSET));
+    assert_eq!(event.transaction, None);
+    assert_eq!(event.status, 402);
+
+    assert!(events.try_recv().is_err());
+}
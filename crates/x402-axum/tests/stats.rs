@@ -0,0 +1,89 @@
+//! Payment statistics count the outcome of every request to the priced routes of a
+//! middleware, and are served as JSON by its stats router.
+
+use axum::Router;
+use axum::routing::get;
+use serde_json::{Value, json};
+use x402_axum::PaymentStatsSnapshot;
+use x402_axum::test_utils::{MockBehavior, MockFacilitator, payment_header};
+use x402_types::proto::v2;
+
+fn price_tag() -> v2::PriceTag {
+    v2::PriceTag {
+        requirements: v2::PaymentRequirements {
+            scheme: "exact".into(),
+            network: "eip155:84532".parse().unwrap(),
+            amount: "10000".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            max_timeout_seconds: 300,
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            extra: None,
+        },
+        enricher: None,
+    }
+}
+
+async fn get_paid(client: &reqwest::Client, url: &str) -> reqwest::StatusCode {
+    let (name, value) = payment_header(&price_tag().requirements);
+    let response = client.get(url).header(name, value).send().await.unwrap();
+    response.status()
+}
+
+#[tokio::test]
+async fn outcomes_are_counted_and_served() {
+    let facilitator = MockFacilitator::start().await;
+    let x402 = facilitator.middleware().with_stats_endpoint("/x402/stats");
+    let app = Router::new()
+        .route(
+            "/protected",
+            get(|| async { "paid" }).layer(x402.with_price_tag(price_tag())),
+        )
+        .route("/free", get(|| async { "free" }))
+        .merge(x402.stats_router());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let url = format!("http://{addr}/protected");
+    let client = reqwest::Client::new();
+
+    assert_eq!(get_paid(&client, &url).await, 200);
+    assert_eq!(get_paid(&client, &url).await, 200);
+    assert_eq!(client.get(&url).send().await.unwrap().status(), 402);
+    facilitator.set_behavior(MockBehavior::Reject("insufficient_funds".into()));
+    assert_eq!(get_paid(&client, &url).await, 402);
+    client
+        .get(format!("http://{addr}/free"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        x402.stats().snapshot(),
+        PaymentStatsSnapshot {
+            total_requests: 4,
+            paid_requests: 2,
+            refused_requests: 1,
+            verification_failures: 1,
+            total_amount: 20000,
+        }
+    );
+    let response = client
+        .get(format!("http://{addr}/x402/stats"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.json::<Value>().await.unwrap(),
+        json!({
+            "total": 4,
+            "paid": 2,
+            "refused": 1,
+            "failed": 1,
+            "total_amount_raw": "20000",
+        })
+    );
+
+    x402.stats().reset();
+    assert_eq!(x402.stats().snapshot(), PaymentStatsSnapshot::default());
+}
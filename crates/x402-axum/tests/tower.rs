@@ -0,0 +1,83 @@
+//! The tower layer enforces payments on plain services, with any request and response body.
+
+use http::StatusCode;
+use http_body_util::BodyExt;
+use std::convert::Infallible;
+use tower::{Layer, ServiceExt};
+use x402_axum::test_utils::{MockBehavior, MockFacilitator, payment_header};
+use x402_axum::tower::Body;
+use x402_types::proto::v2;
+
+fn price_tag() -> v2::PriceTag {
+    v2::PriceTag {
+        requirements: v2::PaymentRequirements {
+            scheme: "exact".into(),
+            network: "eip155:84532".parse().unwrap(),
+            amount: "10000".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            max_timeout_seconds: 300,
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            extra: None,
+        },
+        enricher: None,
+    }
+}
+
+fn request(paid: bool) -> http::Request<String> {
+    let mut request = http::Request::get("/protected");
+    if paid {
+        let (name, value) = payment_header(&price_tag().requirements);
+        request = request.header(name, value);
+    }
+    request.body("request body".to_string()).unwrap()
+}
+
+async fn body_text(response: http::Response<Body>) -> String {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn payments_are_enforced_on_plain_services() {
+    let facilitator = MockFacilitator::start().await;
+    let layer = facilitator
+        .middleware()
+        .with_price_tag(price_tag())
+        .into_tower();
+    let service = layer.layer(tower::service_fn(|req: http::Request<Body>| async move {
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let echo = format!("paid: {}", String::from_utf8_lossy(&body));
+        Ok::<_, Infallible>(http::Response::new(echo))
+    }));
+
+    let response = service.clone().oneshot(request(false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    assert!(response.headers().contains_key("Payment-Required"));
+
+    let response = service.clone().oneshot(request(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("Payment-Response"));
+    assert_eq!(body_text(response).await, "paid: request body");
+    assert_eq!(facilitator.settle_requests().len(), 1);
+
+    facilitator.set_behavior(MockBehavior::Reject("insufficient_funds".into()));
+    let response = service.oneshot(request(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    assert_eq!(facilitator.settle_requests().len(), 1);
+}
+
+#[tokio::test]
+async fn inner_errors_are_not_settled() {
+    let facilitator = MockFacilitator::start().await;
+    let service = facilitator
+        .middleware()
+        .with_price_tag(price_tag())
+        .into_tower()
+        .layer(tower::service_fn(|_req: http::Request<Body>| async {
+            Err::<http::Response<String>, _>(std::io::Error::other("upstream down"))
+        }));
+
+    let response = service.oneshot(request(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(facilitator.settle_requests().len(), 0);
+}
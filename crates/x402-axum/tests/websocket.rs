@@ -0,0 +1,90 @@
+//! A paid WebSocket route: the upgrade is refused with a 402 challenge until it carries a
+//! payment, which is settled before the socket handler runs.
+
+use axum::Router;
+use axum::extract::Extension;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use axum::routing::get;
+use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use x402_axum::test_utils::{MOCK_TRANSACTION, MockFacilitator, payment_header};
+use x402_types::proto::{self, v2};
+use x402_types::util::Base64Bytes;
+
+fn price_tag() -> v2::PriceTag {
+    v2::PriceTag {
+        requirements: v2::PaymentRequirements {
+            scheme: "exact".into(),
+            network: "eip155:84532".parse().unwrap(),
+            amount: "10000".into(),
+            pay_to: "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".into(),
+            max_timeout_seconds: 300,
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            extra: None,
+        },
+        enricher: None,
+    }
+}
+
+/// Greets the client with the settlement transaction, then echoes its messages.
+async fn echo(
+    ws: WebSocketUpgrade,
+    Extension(settlement): Extension<Option<proto::SettleResponse>>,
+) -> Response {
+    ws.on_upgrade(move |mut socket: WebSocket| async move {
+        let transaction = settlement
+            .and_then(|settlement| settlement.0["transaction"].as_str().map(str::to_string))
+            .unwrap_or_default();
+        socket.send(Message::text(transaction)).await.unwrap();
+        while let Some(Ok(message)) = socket.recv().await {
+            if socket.send(message).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+#[tokio::test]
+async fn paid_websocket_upgrade() {
+    let facilitator = MockFacilitator::start().await;
+    let app: Router = Router::new().route(
+        "/ws",
+        get(echo).layer(facilitator.middleware().with_price_tag(price_tag())),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let url = format!("ws://{addr}/ws");
+
+    // Without payment, the upgrade is refused with the requirements in a header
+    let err = tokio_tungstenite::connect_async(&url).await.unwrap_err();
+    let tungstenite::Error::Http(response) = err else {
+        panic!("expected an HTTP error, got {err}");
+    };
+    assert_eq!(response.status(), 402);
+    let header = response.headers()["Payment-Required"].as_bytes();
+    let challenge: v2::PaymentRequired =
+        serde_json::from_slice(&Base64Bytes::from(header).decode().unwrap()).unwrap();
+    assert_eq!(challenge.accepts, vec![price_tag().requirements]);
+    assert!(facilitator.settle_requests().is_empty());
+
+    // Paying for the upgrade settles before the socket handler runs
+    let mut request = url.into_client_request().unwrap();
+    let (name, value) = payment_header(&challenge.accepts[0]);
+    request.headers_mut().insert(name, value);
+    let (mut socket, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+    assert_eq!(response.status(), 101);
+    assert!(response.headers().contains_key("Payment-Response"));
+    assert_eq!(facilitator.settle_requests().len(), 1);
+
+    let greeting = socket.next().await.unwrap().unwrap();
+    assert_eq!(greeting.into_text().unwrap().as_str(), MOCK_TRANSACTION);
+    socket
+        .send(tungstenite::Message::text("ping"))
+        .await
+        .unwrap();
+    let echoed = socket.next().await.unwrap().unwrap();
+    assert_eq!(echoed.into_text().unwrap().as_str(), "ping");
+}
@@ -0,0 +1,197 @@
+//! Reading 402 challenges and payments.
+//!
+//! A challenge file holds the requirements of a `402 Payment Required` response: the JSON
+//! body of a V1 response, or the `Payment-Required` header of a V2 response, either as
+//! copied (base64) or decoded (JSON).
+
+use reqwest::Url;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::path::Path;
+use x402_types::proto;
+use x402_types::proto::{OriginalJson, v1, v2};
+use x402_types::util::Base64Bytes;
+
+/// Reads the payment requirements in the challenge file at `path`.
+pub fn read_payment_required(path: &Path) -> Result<proto::PaymentRequired, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Can not read {}: {e}", path.display()))?;
+    parse_payment_required(contents.trim())
+}
+
+/// Parses payment requirements, as JSON or base64 encoded JSON.
+pub fn parse_payment_required(contents: &str) -> Result<proto::PaymentRequired, Box<dyn Error>> {
+    let json = if contents.starts_with('{') {
+        contents.as_bytes().to_vec()
+    } else {
+        Base64Bytes::from(contents.as_bytes())
+            .auto_decode()
+            .map_err(|e| format!("Payment requirements are neither JSON nor base64: {e}"))?
+    };
+    let version = serde_json::from_slice::<Value>(&json)?
+        .get("x402Version")
+        .and_then(Value::as_u64);
+    let payment_required = match version {
+        Some(1) => proto::PaymentRequired::V1(serde_json::from_slice::<
+            v1::PaymentRequired<OriginalJson>,
+        >(&json)?),
+        Some(2) => proto::PaymentRequired::V2(serde_json::from_slice::<
+            v2::PaymentRequired<OriginalJson>,
+        >(&json)?),
+        _ => return Err("Payment requirements have no supported x402Version".into()),
+    };
+    Ok(payment_required)
+}
+
+/// The URL of the resource the requirements are for, if they name one.
+pub fn resource_url(payment_required: &proto::PaymentRequired) -> Option<Url> {
+    let url = match payment_required {
+        proto::PaymentRequired::V1(payment_required) => payment_required
+            .accepts
+            .iter()
+            .filter_map(|accepts| accepts.try_into().ok())
+            .map(|requirements: v1::PaymentRequirements| requirements.resource)
+            .next(),
+        proto::PaymentRequired::V2(payment_required) => payment_required
+            .resource
+            .as_ref()
+            .map(|resource| resource.url.clone()),
+    };
+    url.and_then(|url| url.parse().ok())
+}
+
+/// Decodes a payment header value into its payment payload.
+pub fn decode_payment(header: &str) -> Result<Value, Box<dyn Error>> {
+    let json = Base64Bytes::from(header.trim().as_bytes())
+        .auto_decode()
+        .map_err(|e| format!("Payment is not base64: {e}"))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Payment is not JSON: {e}").into())
+}
+
+/// The requirements of `payment_required` paid for by `payment`.
+///
+/// A V2 payment names the requirements it accepted, a V1 payment its scheme and network.
+pub fn requirements_for(
+    payment_required: &proto::PaymentRequired,
+    payment: &Value,
+) -> Result<Value, Box<dyn Error>> {
+    let accepts = match payment_required {
+        proto::PaymentRequired::V1(payment_required) => &payment_required.accepts,
+        proto::PaymentRequired::V2(payment_required) => &payment_required.accepts,
+    };
+    let paid = |requirements: &Value| match payment_required {
+        proto::PaymentRequired::V1(_) => {
+            requirements.get("scheme") == payment.get("scheme")
+                && requirements.get("network") == payment.get("network")
+        }
+        proto::PaymentRequired::V2(_) => Some(requirements) == payment.get("accepted"),
+    };
+    accepts
+        .iter()
+        .filter_map(|accepts| serde_json::from_str::<Value>(accepts.0.get()).ok())
+        .find(paid)
+        .ok_or_else(|| "Payment does not match any of the payment requirements".into())
+}
+
+/// A request to verify or settle `payment` against `requirements`.
+pub fn verify_request(payment: Value, requirements: Value) -> proto::VerifyRequest {
+    let request = json!({
+        "x402Version": payment.get("x402Version").cloned().unwrap_or(Value::Null),
+        "paymentPayload": payment,
+        "paymentRequirements": requirements,
+    });
+    let raw = serde_json::value::to_raw_value(&request).expect("verify request is valid JSON");
+    proto::VerifyRequest::from(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> proto::PaymentRequired {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        read_payment_required(&path).unwrap()
+    }
+
+    fn accepts(payment_required: &proto::PaymentRequired, index: usize) -> Value {
+        let accepts = match payment_required {
+            proto::PaymentRequired::V1(payment_required) => &payment_required.accepts,
+            proto::PaymentRequired::V2(payment_required) => &payment_required.accepts,
+        };
+        serde_json::from_str(accepts[index].0.get()).unwrap()
+    }
+
+    fn encode(payment: &Value) -> String {
+        Base64Bytes::encode(serde_json::to_vec(payment).unwrap()).to_string()
+    }
+
+    #[test]
+    fn reads_a_v1_body() {
+        let payment_required = fixture("ts-server-v1-payment-required.json");
+        assert!(matches!(payment_required, proto::PaymentRequired::V1(_)));
+        assert_eq!(
+            resource_url(&payment_required).unwrap().as_str(),
+            "http://localhost:4021/weather"
+        );
+    }
+
+    #[test]
+    fn reads_a_base64_v2_header() {
+        let payment_required = fixture("ts-server-v2-payment-required.txt");
+        assert!(matches!(payment_required, proto::PaymentRequired::V2(_)));
+        assert_eq!(
+            resource_url(&payment_required).unwrap().as_str(),
+            "http://localhost:4021/static-price-v2"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        assert!(parse_payment_required(r#"{"x402Version":3,"accepts":[]}"#).is_err());
+        assert!(parse_payment_required("not a challenge").is_err());
+    }
+
+    #[test]
+    fn builds_the_verify_request_of_a_v2_payment() {
+        let payment_required = fixture("ts-server-v2-payment-required.txt");
+        let solana = accepts(&payment_required, 1);
+        let payment = json!({
+            "x402Version": 2,
+            "accepted": solana,
+            "payload": { "transaction": "AQID" },
+        });
+
+        let payment = decode_payment(&encode(&payment)).unwrap();
+        let requirements = requirements_for(&payment_required, &payment).unwrap();
+        assert_eq!(requirements, solana);
+
+        let request = verify_request(payment.clone(), requirements);
+        let request: Value = serde_json::from_str(request.as_str()).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "x402Version": 2,
+                "paymentPayload": payment,
+                "paymentRequirements": solana,
+            })
+        );
+    }
+
+    #[test]
+    fn finds_the_requirements_of_a_v1_payment() {
+        let payment_required = fixture("ts-server-v1-payment-required.json");
+        let payment = json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "payload": {},
+        });
+        let requirements = requirements_for(&payment_required, &payment).unwrap();
+        assert_eq!(requirements, accepts(&payment_required, 0));
+
+        let payment = json!({ "x402Version": 1, "scheme": "exact", "network": "base" });
+        assert!(requirements_for(&payment_required, &payment).is_err());
+    }
+}
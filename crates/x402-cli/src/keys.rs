@@ -0,0 +1,85 @@
+//! Loading signers, and the payment client using them.
+
+use alloy_signer_local::PrivateKeySigner;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_keypair::Keypair;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use x402_chain_eip155::{V1Eip155ExactClient, V2Eip155ExactClient};
+use x402_chain_solana::{V1SolanaExactClient, V2SolanaExactClient};
+use x402_reqwest::X402Client;
+use x402_types::scheme::client::FirstMatch;
+
+/// Parses a hex-encoded EVM private key.
+pub fn evm_signer(key: &str) -> Result<Arc<PrivateKeySigner>, Box<dyn Error>> {
+    let signer = key
+        .trim()
+        .parse::<PrivateKeySigner>()
+        .map_err(|e| format!("Invalid EVM private key: {e}"))?;
+    Ok(Arc::new(signer))
+}
+
+/// Reads a Solana keypair file, as written by `solana-keygen`: a JSON array of bytes.
+pub fn solana_keypair(path: &Path) -> Result<Arc<Keypair>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Can not read {}: {e}", path.display()))?;
+    let bytes: Vec<u8> = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid Solana keypair file {}: {e}", path.display()))?;
+    let keypair = Keypair::try_from(bytes.as_slice())
+        .map_err(|e| format!("Invalid Solana keypair file {}: {e}", path.display()))?;
+    Ok(Arc::new(keypair))
+}
+
+/// A payment client for the `exact` scheme, V1 and V2, with the given signers.
+pub fn x402_client(
+    evm_signer: Option<Arc<PrivateKeySigner>>,
+    solana: Option<(Arc<Keypair>, String)>,
+) -> X402Client<FirstMatch> {
+    let mut x402_client = X402Client::new();
+    if let Some(signer) = evm_signer {
+        eprintln!("Using EVM signer address: {}", signer.address());
+        x402_client = x402_client
+            .register(V1Eip155ExactClient::new(signer.clone()))
+            .register(V2Eip155ExactClient::new(signer));
+    }
+    if let Some((keypair, rpc_url)) = solana {
+        let rpc_client = Arc::new(RpcClient::new(rpc_url));
+        x402_client = x402_client
+            .register(V1SolanaExactClient::new(
+                keypair.clone(),
+                rpc_client.clone(),
+            ))
+            .register(V2SolanaExactClient::new(keypair, rpc_client));
+    }
+    x402_client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn reads_a_solana_keygen_file() {
+        let path = fixture("solana-keypair.json");
+        let keypair = solana_keypair(&path).unwrap();
+        let bytes: Vec<u8> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(keypair.to_bytes().as_slice(), bytes.as_slice());
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_keypairs() {
+        let error = solana_keypair(&fixture("ts-server-v1-payment-required.json")).unwrap_err();
+        assert!(
+            error.to_string().starts_with("Invalid Solana keypair file"),
+            "{error}"
+        );
+    }
+}
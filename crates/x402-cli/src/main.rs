@@ -0,0 +1,105 @@
+//! `x402` command-line tool.
+//!
+//! Signs payments for 402 challenges, verifies them with a facilitator, makes paid requests,
+//! and runs a smoke test battery against a facilitator, for debugging x402 integrations.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Print the payment header value for a 402 challenge saved to a file
+//! x402 sign --evm-key 0x... --payment-required payment-required.json
+//!
+//! # Ask a facilitator whether a payment is valid, without settling it
+//! x402 verify --facilitator https://facilitator.x402.rs --payment-required payment-required.json --payment eyJ4...
+//!
+//! # Make a paid GET request, and print the response
+//! x402 request https://api.example.com/paid --evm-key 0x...
+//!
+//! # Run the smoke test battery against a facilitator, on Base Sepolia
+//! x402 test-facilitator https://facilitator.x402.rs --evm-key 0x...
+//! ```
+//!
+//! Keys can also be given with the `X402_EVM_KEY` environment variable, and the Solana RPC
+//! with `SOLANA_RPC_URL`.
+//!
+//! # Architecture
+//!
+//! - [`challenge`](crate::challenge) - Reading 402 challenges and payments
+//! - [`keys`](crate::keys) - Loading signers, and the payment client using them
+//! - [`sign`](crate::sign), [`verify`](crate::verify), [`request`](crate::request) and
+//!   [`smoke`](crate::smoke) - The commands
+
+mod challenge;
+mod keys;
+mod request;
+mod sign;
+mod smoke;
+mod verify;
+
+use clap::{Parser, Subcommand};
+use std::process;
+
+/// Sign x402 payments, make paid requests, and test facilitators.
+#[derive(Debug, Parser)]
+#[command(name = "x402", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Sign a payment for a 402 challenge, and print the payment header value
+    Sign(sign::SignArgs),
+    /// Verify a payment with a facilitator, without settling it
+    Verify(verify::VerifyArgs),
+    /// Make a paid GET request, and print the response
+    Request(request::RequestArgs),
+    /// Run a smoke test battery against a facilitator
+    TestFacilitator(smoke::TestFacilitatorArgs),
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Sign(args) => sign::run(args).await,
+        Command::Verify(args) => verify::run(args).await,
+        Command::Request(args) => request::run(args).await,
+        Command::TestFacilitator(args) => smoke::run(args).await,
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        process::exit(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_the_sign_command() {
+        let cli = Cli::try_parse_from([
+            "x402",
+            "sign",
+            "--evm-key",
+            "0x01",
+            "--payment-required",
+            "payment-required.json",
+        ])
+        .unwrap();
+        assert!(matches!(cli.command, Command::Sign(_)));
+    }
+
+    #[test]
+    fn rejects_unknown_commands() {
+        assert!(Cli::try_parse_from(["x402", "settle"]).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+//! `x402 request`: makes a paid GET request.
+
+use clap::Args;
+use reqwest::{Client, Url};
+use std::error::Error;
+use std::path::PathBuf;
+use x402_reqwest::{ReqwestWithPayments, ReqwestWithPaymentsBuild, ResponseExt};
+
+use crate::keys;
+
+#[derive(Debug, Args)]
+pub struct RequestArgs {
+    /// URL to request
+    url: Url,
+    /// Hex-encoded EVM private key to pay with
+    #[arg(long, env = "X402_EVM_KEY", hide_env_values = true)]
+    evm_key: Option<String>,
+    /// Solana keypair file to pay with, as written by `solana-keygen`
+    #[arg(long, requires = "solana_rpc_url")]
+    solana_keypair: Option<PathBuf>,
+    /// Solana RPC URL, to build Solana payments with
+    #[arg(long, env = "SOLANA_RPC_URL")]
+    solana_rpc_url: Option<String>,
+}
+
+/// Prints the status and payment on stderr, and the body on stdout.
+pub async fn run(args: RequestArgs) -> Result<(), Box<dyn Error>> {
+    let evm_signer = args.evm_key.as_deref().map(keys::evm_signer).transpose()?;
+    let solana = match (args.solana_keypair, args.solana_rpc_url) {
+        (Some(path), Some(rpc_url)) => Some((keys::solana_keypair(&path)?, rpc_url)),
+        _ => None,
+    };
+    let http_client = Client::new()
+        .with_payments(keys::x402_client(evm_signer, solana))
+        .build();
+
+    let response = http_client.get(args.url).send().await?;
+    eprintln!("Status: {}", response.status());
+    if let Some(payment_id) = response.payment_id() {
+        eprintln!("Payment ID: {payment_id}");
+    }
+    if let Some(receipt) = response.payment_receipt() {
+        eprintln!(
+            "Paid {} of {} on {} in transaction {}",
+            receipt.amount, receipt.asset, receipt.chain_id, receipt.transaction
+        );
+    } else if let Some(payment_response) = response.payment_response() {
+        eprintln!(
+            "Payment not settled: {}",
+            payment_response
+                .error_reason
+                .as_deref()
+                .unwrap_or("no reason given")
+        );
+    }
+    println!("{}", response.text().await?);
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! `x402 sign`: signs a payment for a 402 challenge.
+
+use clap::Args;
+use reqwest::Url;
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::{challenge, keys};
+
+#[derive(Debug, Args)]
+pub struct SignArgs {
+    /// Hex-encoded EVM private key to sign with
+    #[arg(long, env = "X402_EVM_KEY", hide_env_values = true)]
+    evm_key: String,
+    /// File with the 402 challenge: a V1 body, or a V2 `Payment-Required` header
+    #[arg(long)]
+    payment_required: PathBuf,
+    /// URL of the paid resource, by default the one named by the challenge
+    #[arg(long)]
+    resource: Option<Url>,
+}
+
+/// Prints the payment header value on stdout, and its name on stderr.
+pub async fn run(args: SignArgs) -> Result<(), Box<dyn Error>> {
+    let payment_required = challenge::read_payment_required(&args.payment_required)?;
+    let resource = args
+        .resource
+        .or_else(|| challenge::resource_url(&payment_required))
+        .ok_or("The challenge names no resource, set it with --resource")?;
+    let x402_client = keys::x402_client(Some(keys::evm_signer(&args.evm_key)?), None);
+    let prepared = x402_client
+        .prepare_payment(&payment_required, &resource)
+        .await?;
+    eprintln!(
+        "Paying {} {} of {} on {} to {}",
+        prepared.payment.scheme,
+        prepared.payment.amount,
+        prepared.payment.asset,
+        prepared.payment.chain_id,
+        prepared.payment.pay_to
+    );
+    eprintln!("Header: {}", prepared.header_name.as_str());
+    println!("{}", prepared.header_value.to_str()?);
+    Ok(())
+}
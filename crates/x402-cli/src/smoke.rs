@@ -0,0 +1,257 @@
+//! `x402 test-facilitator`: a smoke test battery against a facilitator.
+//!
+//! Each check signs a fresh V2 `exact` payment of a token on an EVM testnet, Base Sepolia
+//! USDC by default, and sends it to the facilitator:
+//!
+//! - A valid payment is verified.
+//! - A settled payment is rejected when settled again.
+//! - An expired payment is rejected.
+//! - A payment for another chain than the requirements is rejected.
+//!
+//! The payer needs a balance of the token: 1 base unit by default, paid to the payer itself
+//! unless `--pay-to` says otherwise. Settling costs the facilitator gas.
+
+use clap::Args;
+use reqwest::Url;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::time::Duration;
+use x402_axum::facilitator_client::{FacilitatorClient, FacilitatorClientError};
+use x402_types::chain::ChainId;
+use x402_types::proto;
+use x402_types::proto::{OriginalJson, v2};
+
+use crate::{challenge, keys};
+
+/// Base Sepolia.
+const TESTNET: &str = "eip155:84532";
+/// USDC on Base Sepolia.
+const TESTNET_USDC: &str = "0x036CbD53842c5426634e7929541eC2318f3dCF7e";
+/// A chain other than [`TESTNET`], for payments of the wrong chain: Base.
+const OTHER_CHAIN: &str = "eip155:8453";
+/// The resource the test payments are for.
+const RESOURCE: &str = "http://localhost/x402-cli/test-facilitator";
+
+#[derive(Debug, Args)]
+pub struct TestFacilitatorArgs {
+    /// Base URL of the facilitator
+    facilitator: Url,
+    /// Hex-encoded EVM private key of the payer, holding the token on the network
+    #[arg(long, env = "X402_EVM_KEY", hide_env_values = true)]
+    evm_key: String,
+    /// Chain to pay on
+    #[arg(long, default_value = TESTNET)]
+    network: ChainId,
+    /// Token to pay with, an EIP-3009 token
+    #[arg(long, default_value = TESTNET_USDC)]
+    asset: String,
+    /// EIP-712 domain name of the token
+    #[arg(long, default_value = "USDC")]
+    asset_name: String,
+    /// EIP-712 domain version of the token
+    #[arg(long, default_value = "2")]
+    asset_version: String,
+    /// Amount to pay, in base units of the token
+    #[arg(long, default_value = "1")]
+    amount: String,
+    /// Recipient of the payments, by default the payer
+    #[arg(long)]
+    pay_to: Option<String>,
+}
+
+/// A check failed, with the reason.
+type Failure = String;
+
+/// Runs the checks, failing if any does.
+pub async fn run(args: TestFacilitatorArgs) -> Result<(), Box<dyn Error>> {
+    let facilitator = FacilitatorClient::try_from(args.facilitator.as_str())?;
+    let battery = Battery::new(&args)?;
+
+    let checks = [
+        (
+            "valid payment is verified",
+            battery.valid_verify(&facilitator).await,
+        ),
+        (
+            "settled payment cannot be settled again",
+            battery.double_settle(&facilitator).await,
+        ),
+        (
+            "expired payment is rejected",
+            battery.expired(&facilitator).await,
+        ),
+        (
+            "chain ID mismatch is rejected",
+            battery.chain_mismatch(&facilitator).await,
+        ),
+    ];
+    let mut failed = 0;
+    for (name, result) in &checks {
+        match result {
+            Ok(()) => println!("PASS {name}"),
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL {name}: {reason}");
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{failed} of {} checks failed", checks.len()).into());
+    }
+    Ok(())
+}
+
+struct Battery {
+    evm_key: String,
+    requirements: Value,
+}
+
+impl Battery {
+    fn new(args: &TestFacilitatorArgs) -> Result<Self, Box<dyn Error>> {
+        let pay_to = match &args.pay_to {
+            Some(pay_to) => pay_to.clone(),
+            None => keys::evm_signer(&args.evm_key)?.address().to_string(),
+        };
+        let requirements = json!({
+            "scheme": "exact",
+            "network": args.network,
+            "amount": args.amount,
+            "payTo": pay_to,
+            "maxTimeoutSeconds": 300,
+            "asset": args.asset,
+            "extra": { "name": args.asset_name, "version": args.asset_version },
+        });
+        Ok(Self {
+            evm_key: args.evm_key.clone(),
+            requirements,
+        })
+    }
+
+    /// Signs a fresh payment of the requirements, valid for `validity_window` if set.
+    async fn sign(&self, validity_window: Option<Duration>) -> Result<Value, Failure> {
+        let payment_required = serde_json::from_value::<v2::PaymentRequired<OriginalJson>>(json!({
+            "x402Version": 2,
+            "resource": { "url": RESOURCE },
+            "accepts": [self.requirements],
+        }))
+        .map_err(|e| e.to_string())?;
+        let signer = keys::evm_signer(&self.evm_key).map_err(|e| e.to_string())?;
+        let mut x402_client = keys::x402_client(Some(signer), None);
+        if let Some(window) = validity_window {
+            x402_client = x402_client.with_validity_window(window);
+        }
+        let resource = RESOURCE.parse().expect("resource is a valid URL");
+        let prepared = x402_client
+            .prepare_payment(&proto::PaymentRequired::V2(payment_required), &resource)
+            .await
+            .map_err(|e| format!("can not sign: {e}"))?;
+        let header = prepared.header_value.to_str().map_err(|e| e.to_string())?;
+        challenge::decode_payment(header).map_err(|e| e.to_string())
+    }
+
+    async fn valid_verify(&self, facilitator: &FacilitatorClient) -> Result<(), Failure> {
+        let payment = self.sign(None).await?;
+        let request = challenge::verify_request(payment, self.requirements.clone());
+        match verify(facilitator, &request).await? {
+            Answer::Accepted(_) => Ok(()),
+            Answer::Rejected(reason) => Err(format!("rejected: {reason}")),
+        }
+    }
+
+    async fn double_settle(&self, facilitator: &FacilitatorClient) -> Result<(), Failure> {
+        let payment = self.sign(None).await?;
+        let request = challenge::verify_request(payment, self.requirements.clone());
+        if let Answer::Rejected(reason) = settle(facilitator, &request).await? {
+            return Err(format!("first settlement rejected: {reason}"));
+        }
+        match settle(facilitator, &request).await? {
+            Answer::Accepted(transaction) => Err(format!(
+                "settled again in transaction {}",
+                transaction.unwrap_or_default()
+            )),
+            Answer::Rejected(_) => Ok(()),
+        }
+    }
+
+    async fn expired(&self, facilitator: &FacilitatorClient) -> Result<(), Failure> {
+        let payment = self.sign(Some(Duration::ZERO)).await?;
+        let request = challenge::verify_request(payment, self.requirements.clone());
+        verify(facilitator, &request).await?.rejected()
+    }
+
+    async fn chain_mismatch(&self, facilitator: &FacilitatorClient) -> Result<(), Failure> {
+        let payment = self.sign(None).await?;
+        let mut requirements = self.requirements.clone();
+        let other_chain = if requirements["network"] == OTHER_CHAIN {
+            TESTNET
+        } else {
+            OTHER_CHAIN
+        };
+        requirements["network"] = other_chain.into();
+        let request = challenge::verify_request(payment, requirements);
+        verify(facilitator, &request).await?.rejected()
+    }
+}
+
+/// What the facilitator answered a payment with.
+enum Answer {
+    /// The payment was accepted, and settled in the transaction, if any.
+    Accepted(Option<String>),
+    /// The payment was rejected, for the reason.
+    Rejected(String),
+}
+
+impl Answer {
+    /// Passes if the payment was rejected.
+    fn rejected(self) -> Result<(), Failure> {
+        match self {
+            Answer::Accepted(_) => Err("accepted".to_string()),
+            Answer::Rejected(_) => Ok(()),
+        }
+    }
+
+    /// Reads a facilitator response, accepted if its `accepted` field is true, and rejected
+    /// with the reason in its `reason` field otherwise.
+    ///
+    /// Client error statuses reject the payment, other errors fail the check.
+    fn read(
+        result: Result<Value, FacilitatorClientError>,
+        accepted: &str,
+        reason: &str,
+    ) -> Result<Self, Failure> {
+        match result {
+            Ok(response) if response.get(accepted).and_then(Value::as_bool) == Some(true) => {
+                let transaction = response.get("transaction").and_then(Value::as_str);
+                Ok(Answer::Accepted(transaction.map(str::to_string)))
+            }
+            Ok(response) => {
+                let reason = response.get(reason).and_then(Value::as_str);
+                Ok(Answer::Rejected(
+                    reason.unwrap_or("no reason given").to_string(),
+                ))
+            }
+            Err(FacilitatorClientError::HttpStatus { status, body, .. })
+                if status.is_client_error() =>
+            {
+                Ok(Answer::Rejected(format!("{status}: {body}")))
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+async fn verify(
+    facilitator: &FacilitatorClient,
+    request: &proto::VerifyRequest,
+) -> Result<Answer, Failure> {
+    let result = facilitator.verify(request).await.map(|response| response.0);
+    Answer::read(result, "isValid", "invalidReason")
+}
+
+async fn settle(
+    facilitator: &FacilitatorClient,
+    request: &proto::SettleRequest,
+) -> Result<Answer, Failure> {
+    let result = facilitator.settle(request).await.map(|response| response.0);
+    Answer::read(result, "success", "errorReason")
+}
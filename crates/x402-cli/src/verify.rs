@@ -0,0 +1,38 @@
+//! `x402 verify`: verifies a payment with a facilitator.
+
+use clap::Args;
+use reqwest::Url;
+use std::error::Error;
+use std::path::PathBuf;
+use x402_axum::facilitator_client::FacilitatorClient;
+
+use crate::challenge;
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// Base URL of the facilitator
+    #[arg(long)]
+    facilitator: Url,
+    /// File with the 402 challenge the payment answers
+    #[arg(long)]
+    payment_required: PathBuf,
+    /// The payment header value, as printed by `x402 sign`
+    #[arg(long)]
+    payment: String,
+}
+
+/// Prints the verify response of the facilitator, failing if the payment is not valid.
+pub async fn run(args: VerifyArgs) -> Result<(), Box<dyn Error>> {
+    let payment_required = challenge::read_payment_required(&args.payment_required)?;
+    let payment = challenge::decode_payment(&args.payment)?;
+    let requirements = challenge::requirements_for(&payment_required, &payment)?;
+    let request = challenge::verify_request(payment, requirements);
+
+    let facilitator = FacilitatorClient::try_from(args.facilitator.as_str())?;
+    let response = facilitator.verify(&request).await?;
+    println!("{}", serde_json::to_string_pretty(&response.0)?);
+    if response.0.get("isValid").and_then(|valid| valid.as_bool()) != Some(true) {
+        return Err("Payment is not valid".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Correlation of the requests making up a payment.
+//!
+//! Verifying and settling a payment are independent HTTP requests. A resource server
+//! sending the same [`CORRELATION_ID_HEADER`] with both lets the two be found together in
+//! traces. [`propagate_correlation_id`], installed by [`routes`](crate::handlers::routes),
+//! takes the ID from the request or generates one, makes it available to the facilitator
+//! through [`CorrelationId::current`], and echoes it in the response.
+
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use x402_types::facilitator::CORRELATION_ID_HEADER;
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+/// Identifier shared by the requests of a single payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(Arc<str>);
+
+impl CorrelationId {
+    /// Maximum length of a client-supplied ID.
+    pub const MAX_LEN: usize = 128;
+
+    /// Generates a random (UUID v4) ID.
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string().into())
+    }
+
+    /// Parses a client-supplied header value.
+    ///
+    /// Returns `None` unless the value is 1 to [`Self::MAX_LEN`] printable ASCII characters.
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        let valid = !value.is_empty()
+            && value.len() <= Self::MAX_LEN
+            && value.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(value.into()))
+    }
+
+    /// The ID of the request being handled, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `future` with `self` as the [current](Self::current) ID.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// The ID as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Axum middleware propagating the [`CORRELATION_ID_HEADER`] of a request.
+///
+/// A missing or malformed header is replaced with a [generated](CorrelationId::generate) ID.
+/// The ID is recorded as `correlation_id` on the current span, stored in the request
+/// extensions, set as [current](CorrelationId::current) while the request is handled, and
+/// returned in the response header.
+pub async fn propagate_correlation_id(mut request: Request, next: Next) -> Response {
+    let correlation_id = request
+        .headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(CorrelationId::from_header)
+        .unwrap_or_else(CorrelationId::generate);
+    #[cfg(feature = "telemetry")]
+    tracing::Span::current().record("correlation_id", correlation_id.as_str());
+    request.extensions_mut().insert(correlation_id.clone());
+    let mut response = correlation_id.clone().scope(next.run(request)).await;
+    let header = HeaderValue::from_str(correlation_id.as_str())
+        .expect("correlation IDs are valid header values");
+    response.headers_mut().insert(CORRELATION_ID_HEADER, header);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|| async { CorrelationId::current().unwrap().to_string() }),
+            )
+            .layer(axum::middleware::from_fn(propagate_correlation_id))
+    }
+
+    async fn call(header: Option<&str>) -> (String, String) {
+        let mut request = Request::builder().uri("/");
+        if let Some(header) = header {
+            request = request.header(CORRELATION_ID_HEADER, header);
+        }
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let echoed = response.headers()[CORRELATION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (echoed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn supplied_id_is_propagated_and_echoed() {
+        let (echoed, current) = call(Some("payment-42")).await;
+        assert_eq!(echoed, "payment-42");
+        assert_eq!(current, "payment-42");
+    }
+
+    #[tokio::test]
+    async fn missing_or_malformed_id_is_generated() {
+        let (echoed, current) = call(None).await;
+        assert!(uuid::Uuid::parse_str(&echoed).is_ok());
+        assert_eq!(current, echoed);
+
+        let (echoed, _) = call(Some(&"x".repeat(CorrelationId::MAX_LEN + 1))).await;
+        assert!(uuid::Uuid::parse_str(&echoed).is_ok());
+    }
+
+    #[test]
+    fn header_values_are_validated() {
+        let parse =
+            |value: &str| CorrelationId::from_header(&HeaderValue::from_str(value).unwrap());
+        assert_eq!(parse(" abc-123 ").unwrap().as_str(), "abc-123");
+        assert!(parse("").is_none());
+        assert!(parse("two words").is_none());
+        assert!(CorrelationId::current().is_none());
+    }
+}
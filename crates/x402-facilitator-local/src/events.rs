@@ -0,0 +1,238 @@
+//! Real-time payment lifecycle events.
+//!
+//! [`FacilitatorLocal`](crate::FacilitatorLocal) configured with
+//! [`with_payment_events`](crate::FacilitatorLocal::with_payment_events) publishes a
+//! [`PaymentEvent`] for every verification and settlement. Subscribers, such as the
+//! `GET /events` Server-Sent Events stream (see [`event_routes`](crate::handlers::event_routes)),
+//! receive the events published while they are connected. A subscriber falling more than
+//! [`PaymentEvents::CHANNEL_CAPACITY`] events behind misses the oldest ones.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, broadcast};
+use x402_types::chain::ChainId;
+use x402_types::proto;
+
+/// Kind of a [`PaymentEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentEventType {
+    /// A payment was verified.
+    VerifyOk,
+    /// A payment failed verification.
+    VerifyFail,
+    /// A payment was settled.
+    SettleOk,
+    /// A payment failed to settle.
+    SettleFail,
+}
+
+/// Outcome of a verification or settlement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentEvent {
+    /// What happened.
+    #[serde(rename = "type")]
+    pub event_type: PaymentEventType,
+    /// The payer, when reported by the scheme handler.
+    pub payer: Option<String>,
+    /// The chain of the payment.
+    pub chain: Option<ChainId>,
+    /// The required amount, in token units.
+    pub amount: Option<String>,
+    /// When the outcome was known, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// Why the payment failed, `None` on success.
+    pub error: Option<String>,
+}
+
+impl PaymentEvent {
+    /// Describes the outcome of verifying `request`.
+    pub fn verified<E: Display>(
+        request: &proto::VerifyRequest,
+        result: &Result<proto::VerifyResponse, E>,
+    ) -> Self {
+        let outcome = result.as_ref().map(|response| &response.0);
+        Self::new(
+            request,
+            outcome,
+            "isValid",
+            "invalidReason",
+            PaymentEventType::VerifyOk,
+            PaymentEventType::VerifyFail,
+        )
+    }
+
+    /// Describes the outcome of settling `request`.
+    pub fn settled<E: Display>(
+        request: &proto::SettleRequest,
+        result: &Result<proto::SettleResponse, E>,
+    ) -> Self {
+        let outcome = result.as_ref().map(|response| &response.0);
+        Self::new(
+            request,
+            outcome,
+            "success",
+            "errorReason",
+            PaymentEventType::SettleOk,
+            PaymentEventType::SettleFail,
+        )
+    }
+
+    /// A response is successful if its `success_field` is `true`, otherwise its
+    /// `reason_field` (or the error) is reported.
+    fn new<E: Display>(
+        request: &proto::VerifyRequest,
+        outcome: Result<&serde_json::Value, &E>,
+        success_field: &str,
+        reason_field: &str,
+        ok: PaymentEventType,
+        fail: PaymentEventType,
+    ) -> Self {
+        let (event_type, payer, error) = match outcome {
+            Ok(response) => {
+                let payer = response
+                    .get("payer")
+                    .and_then(|payer| payer.as_str())
+                    .filter(|payer| !payer.is_empty())
+                    .map(str::to_string);
+                if response.get(success_field) == Some(&serde_json::Value::Bool(true)) {
+                    (ok, payer, None)
+                } else {
+                    let reason = response
+                        .get(reason_field)
+                        .map(|reason| match reason.as_str() {
+                            Some(reason) => reason.to_string(),
+                            None => reason.to_string(),
+                        })
+                        .unwrap_or_else(|| "unknown".to_string());
+                    (fail, payer, Some(reason))
+                }
+            }
+            Err(error) => (fail, None, Some(error.to_string())),
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        PaymentEvent {
+            event_type,
+            payer,
+            chain: request.scheme_handler_slug().map(|slug| slug.chain_id),
+            amount: request.amount(),
+            timestamp_ms,
+            error,
+        }
+    }
+}
+
+/// Broadcast channel of [`PaymentEvent`]s, with a limit on concurrent subscribers.
+///
+/// Clones share the same channel.
+#[derive(Debug, Clone)]
+pub struct PaymentEvents {
+    sender: broadcast::Sender<PaymentEvent>,
+    subscribers: Arc<Semaphore>,
+}
+
+impl PaymentEvents {
+    /// Number of events buffered for each subscriber.
+    pub const CHANNEL_CAPACITY: usize = 1000;
+    /// Default limit of concurrent subscribers.
+    pub const DEFAULT_MAX_SUBSCRIBERS: usize = 100;
+    /// Interval of the keep-alive comments of the `/events` stream.
+    pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// Creates a channel accepting up to `max_subscribers` concurrent subscribers.
+    pub fn new(max_subscribers: usize) -> Self {
+        let (sender, _) = broadcast::channel(Self::CHANNEL_CAPACITY);
+        Self {
+            sender,
+            subscribers: Arc::new(Semaphore::new(max_subscribers)),
+        }
+    }
+
+    /// Sends `event` to the current subscribers, if any.
+    pub fn publish(&self, event: PaymentEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events published from now on.
+    ///
+    /// Returns `None` if the subscriber limit is reached. The subscription counts towards
+    /// the limit until the returned permit is dropped.
+    pub fn subscribe(&self) -> Option<(broadcast::Receiver<PaymentEvent>, OwnedSemaphorePermit)> {
+        let permit = self.subscribers.clone().try_acquire_owned().ok()?;
+        Some((self.sender.subscribe(), permit))
+    }
+}
+
+impl Default for PaymentEvents {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_SUBSCRIBERS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request() -> proto::VerifyRequest {
+        serde_json::from_value(json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": { "scheme": "exact", "network": "eip155:8453" },
+                "payload": {},
+            },
+            "paymentRequirements": { "scheme": "exact", "network": "eip155:8453", "amount": "10000" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn events_describe_outcomes() {
+        let ok: Result<_, String> = Ok(proto::SettleResponse(json!({
+            "success": true,
+            "payer": "0xpayer",
+            "transaction": "0xtx",
+            "network": "eip155:8453",
+        })));
+        let event = PaymentEvent::settled(&request(), &ok);
+        assert_eq!(event.event_type, PaymentEventType::SettleOk);
+        assert_eq!(event.payer.as_deref(), Some("0xpayer"));
+        assert_eq!(event.chain, Some(ChainId::new("eip155", "8453")));
+        assert_eq!(event.amount.as_deref(), Some("10000"));
+        assert_eq!(event.error, None);
+
+        let invalid: Result<_, String> = Ok(proto::VerifyResponse(json!({
+            "isValid": false,
+            "invalidReason": "insufficient_funds",
+            "payer": "0xpayer",
+        })));
+        let event = PaymentEvent::verified(&request(), &invalid);
+        assert_eq!(event.event_type, PaymentEventType::VerifyFail);
+        assert_eq!(event.error.as_deref(), Some("insufficient_funds"));
+
+        let failed: Result<proto::VerifyResponse, _> = Err("Unsupported scheme");
+        let event = PaymentEvent::verified(&request(), &failed);
+        assert_eq!(event.event_type, PaymentEventType::VerifyFail);
+        assert_eq!(event.payer, None);
+        assert_eq!(event.error.as_deref(), Some("Unsupported scheme"));
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "verify_fail");
+        assert_eq!(json["chain"], "eip155:8453");
+    }
+
+    #[test]
+    fn subscribers_are_limited() {
+        let events = PaymentEvents::new(1);
+        let subscription = events.subscribe().unwrap();
+        assert!(events.subscribe().is_none());
+        drop(subscription);
+        assert!(events.subscribe().is_some());
+    }
+}
@@ -31,12 +31,85 @@
 //!
 //! If no matching handler is found, the request returns an error with
 //! [`PaymentVerificationError::UnsupportedScheme`](x402_types::proto::PaymentVerificationError::UnsupportedScheme).
+//!
+//! # Settlement Receipts
+//!
+//! With a [`ReceiptSigner`] configured via [`FacilitatorLocal::with_receipt_signer`],
+//! every successful settle response carries a signed
+//! [`SettlementReceipt`](x402_types::receipt::SettlementReceipt) under the `receipt` field.
+//!
+//! # Batch Settlement
+//!
+//! [`Facilitator::settle_batch`](x402_types::facilitator::Facilitator::settle_batch) groups
+//! payments by scheme handler, so that handlers able to do so (e.g. EVM `exact` with
+//! EIP-3009 authorizations) settle a whole group in one transaction.
+//!
+//! # Verification Caching
+//!
+//! With a per-chain TTL configured via [`FacilitatorLocal::with_verify_cache_ttl`],
+//! successful `/verify` responses carry an `X-Verify-TTL` header telling resource servers
+//! how long they may reuse the result. The TTL never extends past the authorization's
+//! `validBefore`, minus a grace buffer (see [`FacilitatorLocal::with_verify_grace_buffer`]).
+//! The TTL is counted from the time of a [`Clock`], the system clock unless set with
+//! [`FacilitatorLocal::with_clock`].
+//!
+//! # Asset Allowlist
+//!
+//! With assets allowed on a chain via [`FacilitatorLocal::with_allowed_assets`], payments
+//! on that chain whose requirements name another asset are rejected with
+//! [`PaymentVerificationError::AssetNotAllowed`](x402_types::proto::PaymentVerificationError::AssetNotAllowed)
+//! before reaching the scheme handler, on `/verify` and `/settle` alike. Chains without an
+//! allowlist accept any asset.
+//!
+//! # Minimum Payment Amounts
+//!
+//! With minimums set on a chain via [`FacilitatorLocal::with_min_payment_amounts`], payments
+//! whose required amount is below the minimum for their asset are rejected with
+//! [`PaymentVerificationError::AmountBelowMinimum`](x402_types::proto::PaymentVerificationError::AmountBelowMinimum),
+//! again before reaching the scheme handler. This keeps dust payments from costing the
+//! facilitator RPC calls and simulations on `/verify`, which is free to call. Assets without
+//! a minimum accept any amount.
+//!
+//! # Payment Events
+//!
+//! With [`PaymentEvents`] configured via [`FacilitatorLocal::with_payment_events`], every
+//! verification and settlement publishes a [`PaymentEvent`] describing its outcome, see
+//! [`crate::events`].
+//!
+//! # Typed Lifecycle
+//!
+//! [`FacilitatorLocal::verify_lifecycle`] and [`FacilitatorLocal::settle_lifecycle`] run a
+//! [`PaymentLifecycle`] through verification and settlement. As settlement takes a
+//! [`PaymentLifecycle<Verified>`], settling a payment that was not verified first does not
+//! compile. The [`Facilitator`] methods, used by the `/verify` and `/settle` endpoints,
+//! keep accepting a bare request, since the protocol allows settling without a prior
+//! `/verify` call.
+//!
+//! # Correlation
+//!
+//! When called from the [HTTP handlers](crate::handlers), verification and settlement run
+//! in spans carrying the request's [`CorrelationId`](crate::correlation::CorrelationId) as `correlation_id`, so that the
+//! `/verify` and `/settle` requests of a payment can be found together in traces.
 
+use alloy_primitives::U256;
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use x402_types::chain::ChainId;
 use x402_types::facilitator::Facilitator;
+use x402_types::lifecycle::{Failed, PaymentLifecycle, Pending, Settled, Verified};
 use x402_types::proto;
 use x402_types::proto::PaymentVerificationError;
-use x402_types::scheme::{SchemeRegistry, X402SchemeFacilitatorError};
+use x402_types::receipt::ReceiptSigner;
+use x402_types::scheme::{SchemeHandlerSlug, SchemeRegistry, X402SchemeFacilitatorError};
+use x402_types::timestamp::{Clock, SystemClock};
+
+#[cfg(feature = "telemetry")]
+use tracing::instrument;
+
+#[cfg(feature = "telemetry")]
+use crate::correlation::CorrelationId;
+use crate::events::{PaymentEvent, PaymentEvents};
+use crate::util::SettlementInflightCounter;
 
 /// A local [`Facilitator`](x402_types::facilitator::Facilitator) implementation that delegates to scheme handlers.
 ///
@@ -62,9 +135,20 @@ use x402_types::scheme::{SchemeRegistry, X402SchemeFacilitatorError};
 /// ```
 pub struct FacilitatorLocal<A> {
     handlers: A,
+    receipt_signer: Option<ReceiptSigner>,
+    verify_cache_ttl: HashMap<ChainId, Duration>,
+    verify_grace_buffer: Duration,
+    allowed_assets: HashMap<ChainId, HashSet<String>>,
+    min_payment_amounts: HashMap<ChainId, HashMap<String, U256>>,
+    payment_events: Option<PaymentEvents>,
+    settlements: SettlementInflightCounter,
+    clock: Box<dyn Clock>,
 }
 
 impl<A> FacilitatorLocal<A> {
+    /// Default margin kept between a cached verification and the authorization expiry (6 seconds).
+    pub const DEFAULT_VERIFY_GRACE_BUFFER: Duration = Duration::from_secs(6);
+
     /// Creates a new [`FacilitatorLocal`] with the given scheme handler registry.
     ///
     /// # Arguments
@@ -81,17 +165,187 @@ impl<A> FacilitatorLocal<A> {
     /// let facilitator = FacilitatorLocal::new(scheme_registry);
     /// ```
     pub fn new(handlers: A) -> Self {
-        FacilitatorLocal { handlers }
+        FacilitatorLocal {
+            handlers,
+            receipt_signer: None,
+            verify_cache_ttl: HashMap::new(),
+            verify_grace_buffer: Self::DEFAULT_VERIFY_GRACE_BUFFER,
+            allowed_assets: HashMap::new(),
+            min_payment_amounts: HashMap::new(),
+            payment_events: None,
+            settlements: SettlementInflightCounter::new(),
+            clock: Box::new(SystemClock),
+        }
+    }
+
+    /// Signs a receipt for every successful settlement with the given key.
+    ///
+    /// The receipt is attached to the settle response under the `receipt` field.
+    pub fn with_receipt_signer(mut self, receipt_signer: ReceiptSigner) -> Self {
+        self.receipt_signer = Some(receipt_signer);
+        self
+    }
+
+    /// Lets resource servers cache successful verifications on `chain_id` for up to `secs` seconds.
+    ///
+    /// `0`, the default, disables caching for the chain.
+    pub fn with_verify_cache_ttl(mut self, chain_id: ChainId, secs: u64) -> Self {
+        if secs == 0 {
+            self.verify_cache_ttl.remove(&chain_id);
+        } else {
+            self.verify_cache_ttl
+                .insert(chain_id, Duration::from_secs(secs));
+        }
+        self
+    }
+
+    /// Sets the margin kept between a cached verification and the authorization expiry
+    /// (default: 6 seconds).
+    pub fn with_verify_grace_buffer(mut self, grace_buffer: Duration) -> Self {
+        self.verify_grace_buffer = grace_buffer;
+        self
+    }
+
+    /// Restricts payments on `chain_id` to the given asset addresses.
+    ///
+    /// An empty list, the default, allows any asset on the chain. Hex addresses (EVM, Aptos)
+    /// are compared case-insensitively, base58 addresses (Solana, TRON) exactly.
+    pub fn with_allowed_assets<I, S>(mut self, chain_id: ChainId, assets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let assets: HashSet<String> = assets
+            .into_iter()
+            .map(|asset| normalize_asset(&chain_id, asset.as_ref()))
+            .collect();
+        if assets.is_empty() {
+            self.allowed_assets.remove(&chain_id);
+        } else {
+            self.allowed_assets.insert(chain_id, assets);
+        }
+        self
+    }
+
+    /// Sets the smallest amount, in base units, accepted for each asset on `chain_id`, to turn
+    /// away dust payments before any on-chain check.
+    ///
+    /// Assets are compared like in [`FacilitatorLocal::with_allowed_assets`]. Assets without
+    /// a minimum, by default all of them, accept any amount.
+    pub fn with_min_payment_amounts<I, S>(mut self, chain_id: ChainId, minimums: I) -> Self
+    where
+        I: IntoIterator<Item = (S, U256)>,
+        S: AsRef<str>,
+    {
+        let minimums: HashMap<String, U256> = minimums
+            .into_iter()
+            .map(|(asset, minimum)| (normalize_asset(&chain_id, asset.as_ref()), minimum))
+            .collect();
+        if minimums.is_empty() {
+            self.min_payment_amounts.remove(&chain_id);
+        } else {
+            self.min_payment_amounts.insert(chain_id, minimums);
+        }
+        self
+    }
+
+    /// Tells time with `clock` instead of the system clock, e.g. to test verification caching
+    /// at exact instants.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Publishes a [`PaymentEvent`] for every verification and settlement to `payment_events`.
+    pub fn with_payment_events(mut self, payment_events: PaymentEvents) -> Self {
+        self.payment_events = Some(payment_events);
+        self
+    }
+
+    /// Returns the counter of settle calls in progress, to drain them on shutdown.
+    pub fn settlement_counter(&self) -> &SettlementInflightCounter {
+        &self.settlements
+    }
+
+    /// Publishes the event built by `event`, if payment events are enabled.
+    fn publish(&self, event: impl FnOnce() -> PaymentEvent) {
+        if let Some(payment_events) = &self.payment_events {
+            payment_events.publish(event());
+        }
+    }
+
+    /// Rejects the request if its chain has an allowlist that does not contain its asset.
+    fn check_allowed_asset(
+        &self,
+        request: &proto::VerifyRequest,
+    ) -> Result<(), PaymentVerificationError> {
+        let Some(chain_id) = request.scheme_handler_slug().map(|slug| slug.chain_id) else {
+            return Ok(());
+        };
+        let Some(allowed_assets) = self.allowed_assets.get(&chain_id) else {
+            return Ok(());
+        };
+        match request.asset() {
+            Some(asset) if allowed_assets.contains(&normalize_asset(&chain_id, &asset)) => Ok(()),
+            _ => Err(PaymentVerificationError::AssetNotAllowed),
+        }
+    }
+
+    /// Rejects the request if its required amount is below the minimum for its asset.
+    ///
+    /// Amounts that are not decimal integers are left to the scheme handler to reject.
+    fn check_min_payment_amount(
+        &self,
+        request: &proto::VerifyRequest,
+    ) -> Result<(), PaymentVerificationError> {
+        let Some(chain_id) = request.scheme_handler_slug().map(|slug| slug.chain_id) else {
+            return Ok(());
+        };
+        let Some(minimums) = self.min_payment_amounts.get(&chain_id) else {
+            return Ok(());
+        };
+        let Some(minimum) = request
+            .asset()
+            .and_then(|asset| minimums.get(&normalize_asset(&chain_id, &asset)))
+        else {
+            return Ok(());
+        };
+        match request
+            .amount()
+            .and_then(|amount| U256::from_str_radix(&amount, 10).ok())
+        {
+            Some(amount) if amount < *minimum => {
+                Err(PaymentVerificationError::AmountBelowMinimum { minimum: *minimum })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the facilitator's asset allowlists and minimum amounts to the request.
+    fn check_policies(
+        &self,
+        request: &proto::VerifyRequest,
+    ) -> Result<(), PaymentVerificationError> {
+        self.check_allowed_asset(request)?;
+        self.check_min_payment_amount(request)
     }
 }
 
-impl Facilitator for FacilitatorLocal<SchemeRegistry> {
-    type Error = FacilitatorLocalError;
+/// Lowercases hex addresses, which may be checksum-cased.
+fn normalize_asset(chain_id: &ChainId, asset: &str) -> String {
+    match chain_id.namespace() {
+        "eip155" | "aptos" => asset.to_ascii_lowercase(),
+        _ => asset.to_string(),
+    }
+}
 
-    async fn verify(
+impl FacilitatorLocal<SchemeRegistry> {
+    async fn verify_with_handler(
         &self,
         request: &proto::VerifyRequest,
-    ) -> Result<proto::VerifyResponse, Self::Error> {
+    ) -> Result<proto::VerifyResponse, FacilitatorLocalError> {
+        self.check_policies(request)
+            .map_err(|e| FacilitatorLocalError::Verification(e.into()))?;
         let handler = request
             .scheme_handler_slug()
             .and_then(|slug| self.handlers.by_slug(&slug))
@@ -105,22 +359,157 @@ impl Facilitator for FacilitatorLocal<SchemeRegistry> {
         Ok(response)
     }
 
-    async fn settle(
+    async fn settle_with_handler(
         &self,
         request: &proto::SettleRequest,
-    ) -> Result<proto::SettleResponse, Self::Error> {
+    ) -> Result<proto::SettleResponse, FacilitatorLocalError> {
+        self.check_policies(request)
+            .map_err(|e| FacilitatorLocalError::Settlement(e.into()))?;
         let handler = request
             .scheme_handler_slug()
             .and_then(|slug| self.handlers.by_slug(&slug))
             .ok_or(FacilitatorLocalError::Settlement(
                 PaymentVerificationError::UnsupportedScheme.into(),
             ))?;
-        let response = handler
+        let mut response = handler
             .settle(request)
             .await
             .map_err(FacilitatorLocalError::Settlement)?;
+        if let Some(receipt_signer) = &self.receipt_signer {
+            receipt_signer.attach(request, &mut response);
+        }
         Ok(response)
     }
+}
+
+impl FacilitatorLocal<SchemeRegistry> {
+    /// Verifies the payment of `lifecycle`, like [`Facilitator::verify`].
+    ///
+    /// Returns the verified lifecycle, or the failed one with the invalid reason or error.
+    pub async fn verify_lifecycle(
+        &self,
+        lifecycle: PaymentLifecycle<Pending>,
+    ) -> Result<PaymentLifecycle<Verified>, PaymentLifecycle<Failed>> {
+        match self.verify(lifecycle.request()).await {
+            Ok(response) => lifecycle.verified_by(&response),
+            Err(e) => Err(lifecycle.fail(e.to_string())),
+        }
+    }
+
+    /// Settles the payment of a verified `lifecycle`, like [`Facilitator::settle`].
+    ///
+    /// Returns the settled lifecycle, or the failed one with the error reason or error.
+    pub async fn settle_lifecycle(
+        &self,
+        lifecycle: PaymentLifecycle<Verified>,
+    ) -> Result<PaymentLifecycle<Settled>, PaymentLifecycle<Failed>> {
+        match self.settle(lifecycle.request()).await {
+            Ok(response) => lifecycle.settled_by(&response),
+            Err(e) => Err(lifecycle.fail(e.to_string())),
+        }
+    }
+}
+
+impl Facilitator for FacilitatorLocal<SchemeRegistry> {
+    type Error = FacilitatorLocalError;
+
+    #[cfg_attr(
+        feature = "telemetry",
+        instrument(skip_all, fields(correlation_id = CorrelationId::current().map(tracing::field::display)))
+    )]
+    async fn verify(
+        &self,
+        request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, Self::Error> {
+        let result = self.verify_with_handler(request).await;
+        self.publish(|| PaymentEvent::verified(request, &result));
+        result
+    }
+
+    #[cfg_attr(
+        feature = "telemetry",
+        instrument(skip_all, fields(correlation_id = CorrelationId::current().map(tracing::field::display)))
+    )]
+    async fn settle(
+        &self,
+        request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, Self::Error> {
+        let _inflight = self.settlements.track();
+        let result = self.settle_with_handler(request).await;
+        self.publish(|| PaymentEvent::settled(request, &result));
+        result
+    }
+
+    /// Groups the payments by scheme handler and settles each group with
+    /// [`X402SchemeFacilitator::settle_batch`](x402_types::scheme::X402SchemeFacilitator::settle_batch), so that a handler may combine
+    /// payments on its chain into a single transaction.
+    #[cfg_attr(
+        feature = "telemetry",
+        instrument(skip_all, fields(payments = requests.len(), correlation_id = CorrelationId::current().map(tracing::field::display)))
+    )]
+    async fn settle_batch(
+        &self,
+        requests: &[proto::SettleRequest],
+    ) -> Vec<Result<proto::SettleResponse, Self::Error>> {
+        let _inflight = self.settlements.track();
+        let mut results: Vec<Option<Result<proto::SettleResponse, Self::Error>>> =
+            requests.iter().map(|_| None).collect();
+        let mut groups: HashMap<SchemeHandlerSlug, Vec<usize>> = HashMap::new();
+        for (index, request) in requests.iter().enumerate() {
+            if let Err(e) = self.check_policies(request) {
+                results[index] = Some(Err(FacilitatorLocalError::Settlement(e.into())));
+                continue;
+            }
+            match request
+                .scheme_handler_slug()
+                .filter(|slug| self.handlers.by_slug(slug).is_some())
+            {
+                Some(slug) => groups.entry(slug).or_default().push(index),
+                None => {
+                    results[index] = Some(Err(FacilitatorLocalError::Settlement(
+                        PaymentVerificationError::UnsupportedScheme.into(),
+                    )))
+                }
+            }
+        }
+        for (slug, indices) in groups {
+            let Some(handler) = self.handlers.by_slug(&slug) else {
+                continue;
+            };
+            let batch: Vec<proto::SettleRequest> = indices
+                .iter()
+                .map(|&index| requests[index].clone())
+                .collect();
+            let responses = handler.settle_batch(&batch).await;
+            for (index, response) in indices.into_iter().zip(responses) {
+                let response = response
+                    .map(|mut response| {
+                        if let Some(receipt_signer) = &self.receipt_signer {
+                            receipt_signer.attach(&requests[index], &mut response);
+                        }
+                        response
+                    })
+                    .map_err(FacilitatorLocalError::Settlement);
+                results[index] = Some(response);
+            }
+        }
+        let results: Vec<Result<proto::SettleResponse, Self::Error>> = results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(FacilitatorLocalError::Settlement(
+                        X402SchemeFacilitatorError::OnchainFailure(
+                            "Scheme handler returned no result".to_string(),
+                        ),
+                    ))
+                })
+            })
+            .collect();
+        for (request, result) in requests.iter().zip(&results) {
+            self.publish(|| PaymentEvent::settled(request, result));
+        }
+        results
+    }
 
     async fn supported(&self) -> Result<proto::SupportedResponse, Self::Error> {
         let mut kinds = vec![];
@@ -142,6 +531,21 @@ impl Facilitator for FacilitatorLocal<SchemeRegistry> {
             signers,
         })
     }
+
+    /// The configured TTL of the request's chain, cut to expire `verify_grace_buffer`
+    /// before the authorization does. `None` if the payload has no `validBefore`.
+    fn verify_cache_ttl(&self, request: &proto::VerifyRequest) -> Option<Duration> {
+        let chain_id = request.scheme_handler_slug()?.chain_id;
+        let max_ttl = *self.verify_cache_ttl.get(&chain_id)?;
+        let valid_for = request
+            .valid_before()?
+            .as_secs()
+            .checked_sub(self.clock.now().as_secs())
+            .map(Duration::from_secs)?
+            .checked_sub(self.verify_grace_buffer)?;
+        let ttl = valid_for.min(max_ttl);
+        (ttl.as_secs() > 0).then_some(ttl)
+    }
 }
 
 /// Errors that can occur during local facilitator operations.
@@ -164,3 +568,181 @@ pub enum FacilitatorLocalError {
     #[error(transparent)]
     Settlement(X402SchemeFacilitatorError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x402_types::proto::PaymentVerificationError;
+    use x402_types::timestamp::FixedClock;
+
+    fn request(network: &str, asset: &str) -> proto::VerifyRequest {
+        request_for(network, asset, "10000")
+    }
+
+    fn request_for(network: &str, asset: &str, amount: &str) -> proto::VerifyRequest {
+        serde_json::from_value(serde_json::json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": { "scheme": "exact", "network": network },
+                "payload": {},
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": network,
+                "asset": asset,
+                "amount": amount,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn verification_error(
+        result: Result<proto::VerifyResponse, FacilitatorLocalError>,
+    ) -> PaymentVerificationError {
+        match result {
+            Err(FacilitatorLocalError::Verification(
+                X402SchemeFacilitatorError::PaymentVerification(e),
+            )) => e,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn allowed_assets_are_checked_per_chain() {
+        let base = ChainId::new("eip155", "8453");
+        let facilitator = FacilitatorLocal::new(SchemeRegistry::default())
+            .with_allowed_assets(base, ["0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"]);
+
+        // Allowed assets, case-insensitively, and other chains reach the (missing) handler.
+        for request in [
+            request("eip155:8453", "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"),
+            request("eip155:84532", "0x036CbD53842c5426634e7929541eC2318f3dCF7e"),
+        ] {
+            assert!(matches!(
+                verification_error(facilitator.verify(&request).await),
+                PaymentVerificationError::UnsupportedScheme
+            ));
+        }
+
+        let other_token = request("eip155:8453", "0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+        assert!(matches!(
+            verification_error(facilitator.verify(&other_token).await),
+            PaymentVerificationError::AssetNotAllowed
+        ));
+        assert!(matches!(
+            facilitator.settle(&other_token).await,
+            Err(FacilitatorLocalError::Settlement(
+                X402SchemeFacilitatorError::PaymentVerification(
+                    PaymentVerificationError::AssetNotAllowed
+                )
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn lifecycle_fails_on_unsupported_scheme() {
+        let facilitator = FacilitatorLocal::new(SchemeRegistry::default());
+        let lifecycle = PaymentLifecycle::new(request(
+            "eip155:8453",
+            "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        ));
+        let failed = facilitator.verify_lifecycle(lifecycle).await.unwrap_err();
+        assert_eq!(
+            failed.reason(),
+            PaymentVerificationError::UnsupportedScheme.to_string()
+        );
+
+        // A payment verified elsewhere still goes through settlement
+        let verified = PaymentLifecycle::new(request(
+            "eip155:8453",
+            "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        ))
+        .verify("0xpayer");
+        let event = facilitator
+            .settle_lifecycle(verified)
+            .await
+            .unwrap_err()
+            .into_audit_event();
+        assert_eq!(event.payer.as_deref(), Some("0xpayer"));
+        assert!(event.reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn min_payment_amounts_are_checked_per_asset() {
+        let usdc = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
+        let facilitator = FacilitatorLocal::new(SchemeRegistry::default())
+            .with_min_payment_amounts(ChainId::new("eip155", "8453"), [(usdc, U256::from(1000))]);
+
+        let dust = request_for("eip155:8453", &usdc.to_lowercase(), "999");
+        assert!(matches!(
+            verification_error(facilitator.verify(&dust).await),
+            PaymentVerificationError::AmountBelowMinimum { minimum } if minimum == U256::from(1000)
+        ));
+        assert!(matches!(
+            facilitator.settle(&dust).await,
+            Err(FacilitatorLocalError::Settlement(
+                X402SchemeFacilitatorError::PaymentVerification(
+                    PaymentVerificationError::AmountBelowMinimum { .. }
+                )
+            ))
+        ));
+
+        // The minimum itself, other assets and other chains reach the (missing) handler.
+        for request in [
+            request_for("eip155:8453", usdc, "1000"),
+            request_for(
+                "eip155:8453",
+                "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+                "1",
+            ),
+            request_for("eip155:84532", usdc, "1"),
+        ] {
+            assert!(matches!(
+                verification_error(facilitator.verify(&request).await),
+                PaymentVerificationError::UnsupportedScheme
+            ));
+        }
+    }
+
+    #[test]
+    fn verify_cache_ttl_ends_a_grace_buffer_before_expiry() {
+        const VALID_BEFORE: u64 = 1_700_000_060;
+        let grace = FacilitatorLocal::<SchemeRegistry>::DEFAULT_VERIFY_GRACE_BUFFER.as_secs();
+        let request: proto::VerifyRequest = serde_json::from_value(serde_json::json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": { "scheme": "exact", "network": "eip155:8453" },
+                "payload": { "authorization": { "validBefore": VALID_BEFORE.to_string() } },
+            },
+            "paymentRequirements": { "scheme": "exact", "network": "eip155:8453" },
+        }))
+        .unwrap();
+        let ttl_at = |now: u64| {
+            FacilitatorLocal::new(SchemeRegistry::default())
+                .with_verify_cache_ttl(ChainId::new("eip155", "8453"), 30)
+                .with_clock(FixedClock(now))
+                .verify_cache_ttl(&request)
+        };
+
+        assert_eq!(
+            ttl_at(VALID_BEFORE - grace - 1),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(ttl_at(VALID_BEFORE - grace), None);
+        assert_eq!(ttl_at(VALID_BEFORE - grace + 1), None);
+        // Capped by the configured TTL
+        assert_eq!(ttl_at(VALID_BEFORE - 60), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn empty_allowlist_allows_any_asset() {
+        let base = ChainId::new("eip155", "8453");
+        let facilitator = FacilitatorLocal::new(SchemeRegistry::default())
+            .with_allowed_assets(base.clone(), ["0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"])
+            .with_allowed_assets(base, Vec::<String>::new());
+        let request = request("eip155:8453", "0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+        assert!(facilitator.check_allowed_asset(&request).is_ok());
+    }
+}
@@ -8,25 +8,75 @@
 //!
 //! Each endpoint consumes or produces structured JSON payloads defined in `x402-rs`,
 //! and is compatible with official x402 client SDKs.
+//!
+//! # Errors
+//!
+//! Error responses are [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) problem details,
+//! served as [`PROBLEM_JSON_CONTENT_TYPE`]. Failed verifications and settlements keep the
+//! x402 fields of the protocol, e.g. `isValid` and `invalidReason`, next to the `type`,
+//! `title`, `status`, `detail` and `instance` members. See
+//! [`PaymentProblem::from_facilitator_error`].
+//!
+//! # Tracing
+//!
+//! With the `telemetry` feature, [`trace_parent`] extracts the W3C trace context (`traceparent`,
+//! `tracestate`) and baggage sent by the caller, so that the spans of a request continue the
+//! caller's trace. [`Telemetry::http_tracing`](crate::util::Telemetry::http_tracing) sets it as
+//! the parent of each request span.
 
-use axum::extract::State;
-use axum::http::StatusCode;
+use axum::extract::{Query, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware;
 use axum::response::Response;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::routing::{get, post};
 use axum::{Json, Router, response::IntoResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use x402_types::facilitator::Facilitator;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::BroadcastStream;
+use x402_types::chain::ChainId;
+use x402_types::facilitator::{Facilitator, VERIFY_TTL_HEADER};
 use x402_types::proto;
-use x402_types::proto::{AsPaymentProblem, ErrorReason, PaymentVerificationError};
-use x402_types::scheme::X402SchemeFacilitatorError;
+use x402_types::proto::{ErrorReason, PaymentProblem, PaymentProblemBody, ProblemDetails};
 
+#[cfg(feature = "telemetry")]
+use axum::http::HeaderMap;
 #[cfg(feature = "telemetry")]
 use tracing::instrument;
 
+use crate::correlation::propagate_correlation_id;
+use crate::events::PaymentEvents;
 use crate::facilitator_local::FacilitatorLocalError;
 use crate::util::AsJsonValue;
 
+/// Extracts the trace context of the caller from the `headers` of a request, using the
+/// global text map propagator.
+///
+/// The returned context is empty unless a propagator is registered, as done by
+/// [`Telemetry::register`](crate::util::Telemetry::register), and the caller sent one.
+#[cfg(feature = "telemetry")]
+pub fn trace_parent(headers: &HeaderMap) -> opentelemetry::Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    })
+}
+
+/// Reads propagated context from HTTP headers.
+#[cfg(feature = "telemetry")]
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+#[cfg(feature = "telemetry")]
+impl opentelemetry::propagation::Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
 /// `GET /verify`: Returns a machine-readable description of the `/verify` endpoint.
 ///
 /// This is served by the facilitator to help clients understand how to construct
@@ -63,6 +113,25 @@ pub async fn get_settle_info() -> impl IntoResponse {
     }))
 }
 
+/// `GET /schema/payment-requirements`: Returns the JSON Schema of V2 payment requirements.
+///
+/// See [`x402_types::schema`].
+#[cfg(feature = "json-schema")]
+#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+pub async fn get_payment_requirements_schema() -> impl IntoResponse {
+    Json(x402_types::schema::generate_payment_requirements_schema())
+}
+
+/// `GET /schema/verify-request`: Returns the JSON Schema of the body of V2 `/verify` requests,
+/// the same as `/settle` requests.
+///
+/// See [`x402_types::schema`].
+#[cfg(feature = "json-schema")]
+#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+pub async fn get_verify_request_schema() -> impl IntoResponse {
+    Json(x402_types::schema::generate_verify_request_schema())
+}
+
 /// Creates the Axum router with all x402 facilitator endpoints.
 ///
 /// The router includes the following routes:
@@ -71,8 +140,16 @@ pub async fn get_settle_info() -> impl IntoResponse {
 /// - `POST /verify` - Verify a payment payload
 /// - `GET /settle` - Schema information for settle endpoint
 /// - `POST /settle` - Settle a verified payment on-chain
+/// - `POST /settle/batch` - Settle several payments, combining same-chain payments where possible
 /// - `GET /health` - Health check (delegates to `/supported`)
 /// - `GET /supported` - List supported payment schemes and networks
+/// - `POST /supported` - List supported payment schemes and networks a client can pay with
+/// - `GET /schema/payment-requirements` - JSON Schema of V2 payment requirements
+///   (with the `json-schema` feature)
+/// - `GET /schema/verify-request` - JSON Schema of V2 `/verify` requests
+///   (with the `json-schema` feature)
+///
+/// Every route propagates the `X-Correlation-Id` header, see [`propagate_correlation_id`].
 ///
 /// # Type Parameters
 ///
@@ -92,16 +169,48 @@ pub async fn get_settle_info() -> impl IntoResponse {
 pub fn routes<A>() -> Router<A>
 where
     A: Facilitator + Clone + Send + Sync + 'static,
-    A::Error: IntoResponse,
+    A::Error: IntoResponse + AsJsonValue + Send,
 {
-    Router::new()
+    let router = Router::new()
         .route("/", get(get_root))
         .route("/verify", get(get_verify_info))
         .route("/verify", post(post_verify::<A>))
         .route("/settle", get(get_settle_info))
         .route("/settle", post(post_settle::<A>))
+        .route("/settle/batch", post(post_settle_batch::<A>))
         .route("/health", get(get_health::<A>))
         .route("/supported", get(get_supported::<A>))
+        .route("/supported", post(post_supported::<A>));
+    #[cfg(feature = "json-schema")]
+    let router = router
+        .route(
+            "/schema/payment-requirements",
+            get(get_payment_requirements_schema),
+        )
+        .route("/schema/verify-request", get(get_verify_request_schema));
+    router.layer(middleware::from_fn(propagate_correlation_id))
+}
+
+/// Creates the Axum router serving `GET /events` from `payment_events`.
+///
+/// Merge it with [`routes`] and pass the same [`PaymentEvents`] to
+/// [`FacilitatorLocal::with_payment_events`](crate::FacilitatorLocal::with_payment_events):
+///
+/// ```ignore
+/// let payment_events = PaymentEvents::new(100);
+/// let facilitator = FacilitatorLocal::new(scheme_registry)
+///     .with_payment_events(payment_events.clone());
+/// let app = axum::Router::new()
+///     .merge(handlers::routes().with_state(Arc::new(facilitator)))
+///     .merge(handlers::event_routes(payment_events));
+/// ```
+pub fn event_routes<S>(payment_events: PaymentEvents) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/events", get(get_events))
+        .with_state(payment_events)
 }
 
 /// `GET /`: Returns a simple greeting message from the facilitator.
@@ -127,6 +236,32 @@ where
     }
 }
 
+/// `POST /supported`: Lists the x402 payment schemes and networks supported by this
+/// facilitator that a client can pay with.
+///
+/// Takes the [`ClientCapabilities`](x402_types::proto::ClientCapabilities) of the client, and
+/// responds with the `GET /supported` response narrowed to them, see
+/// [`SupportedResponse::for_client`](x402_types::proto::SupportedResponse::for_client).
+/// Clients use it to skip payment options they could not pay with anyway.
+#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+pub async fn post_supported<A>(
+    State(facilitator): State<A>,
+    Json(capabilities): Json<proto::ClientCapabilities>,
+) -> impl IntoResponse
+where
+    A: Facilitator,
+    A::Error: IntoResponse,
+{
+    match facilitator.supported().await {
+        Ok(supported) => (
+            StatusCode::OK,
+            Json(json!(supported.for_client(&capabilities))),
+        )
+            .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
 /// `GET /health`: Health check endpoint.
 ///
 /// Returns the same response as `/supported`, making it useful for load balancers
@@ -148,6 +283,9 @@ where
 ///
 /// Responds with a [`VerifyResponse`](x402_types::proto::VerifyResponse) indicating whether the payment can be accepted.
 ///
+/// A valid response carries `X-Verify-TTL` and `Cache-Control: max-age` headers when the
+/// facilitator allows caching it, see [`Facilitator::verify_cache_ttl`].
+///
 /// # Errors
 ///
 /// Returns `400 Bad Request` if the payment verification fails (e.g., invalid signature,
@@ -163,7 +301,24 @@ where
     A::Error: IntoResponse,
 {
     match facilitator.verify(&body).await {
-        Ok(valid_response) => (StatusCode::OK, Json(valid_response)).into_response(),
+        Ok(valid_response) => {
+            let is_valid = valid_response.0.get("isValid") == Some(&serde_json::Value::Bool(true));
+            let ttl = is_valid
+                .then(|| facilitator.verify_cache_ttl(&body))
+                .flatten();
+            let mut response = (StatusCode::OK, Json(valid_response)).into_response();
+            if let Some(ttl) = ttl {
+                let secs = HeaderValue::from(ttl.as_secs());
+                let headers = response.headers_mut();
+                headers.insert(
+                    header::CACHE_CONTROL,
+                    HeaderValue::from_str(&format!("max-age={}", ttl.as_secs()))
+                        .expect("max-age is a valid header value"),
+                );
+                headers.insert(VERIFY_TTL_HEADER, secs);
+            }
+            response
+        }
         Err(error) => {
             #[cfg(feature = "telemetry")]
             tracing::warn!(
@@ -211,16 +366,135 @@ where
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// Query parameters of `GET /events`.
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Only stream events of this chain.
+    pub filter_chain: Option<ChainId>,
+}
+
+/// `GET /events`: Server-Sent Events stream of [`PaymentEvent`](crate::events::PaymentEvent)s.
+///
+/// Each verification and settlement is sent as a JSON-encoded `data` field, optionally
+/// restricted to one chain with `?filter_chain=eip155:8453`. A keep-alive comment is sent
+/// every [`PaymentEvents::KEEP_ALIVE_INTERVAL`] so that proxies keep idle streams open.
+///
+/// # Errors
+///
+/// Returns `503 Service Unavailable` if the maximum number of streams is already open.
+#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+pub async fn get_events(
+    State(payment_events): State<PaymentEvents>,
+    Query(query): Query<EventsQuery>,
+) -> Response {
+    let Some((receiver, permit)) = payment_events.subscribe() else {
+        return plain_problem_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many event streams".to_string(),
+            "/events",
+        );
+    };
+    let stream = BroadcastStream::new(receiver).filter_map(move |event| {
+        // The stream counts towards the limit until the client disconnects.
+        let _permit = &permit;
+        // Events missed by a lagging stream are skipped.
+        let event = event.ok()?;
+        match &query.filter_chain {
+            Some(chain) if event.chain.as_ref() != Some(chain) => None,
+            _ => Some(Event::default().json_data(event)),
+        }
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::new().interval(PaymentEvents::KEEP_ALIVE_INTERVAL))
+        .into_response()
+}
+
+/// Maximum number of payments accepted by `POST /settle/batch`.
+pub const MAX_SETTLE_BATCH_SIZE: usize = 100;
+
+/// `POST /settle/batch`: Facilitator-side execution of several x402 payments.
+///
+/// Takes a [`SettleBatchRequest`](x402_types::proto::SettleBatchRequest) and responds with a
+/// [`SettleBatchResponse`](x402_types::proto::SettleBatchResponse) holding one settle response
+/// per payment, in request order. Same-chain payments may be settled in a single transaction,
+/// in which case they share its hash. A failed payment does not fail the others, and its entry
+/// has the same shape as a failed `/settle` response.
+///
+/// # Errors
+///
+/// Returns `400 Bad Request` if the batch is empty or holds more than [`MAX_SETTLE_BATCH_SIZE`] payments.
+#[cfg_attr(feature = "telemetry", instrument(skip_all, fields(payments = body.payments.len())))]
+pub async fn post_settle_batch<A>(
+    State(facilitator): State<A>,
+    Json(body): Json<proto::SettleBatchRequest>,
+) -> impl IntoResponse
+where
+    A: Facilitator + Sync,
+    A::Error: AsJsonValue + Send,
+{
+    if body.payments.is_empty() || body.payments.len() > MAX_SETTLE_BATCH_SIZE {
+        let detail = format!("Batch must hold between 1 and {MAX_SETTLE_BATCH_SIZE} payments");
+        return plain_problem_response(StatusCode::BAD_REQUEST, detail, "/settle/batch");
+    }
+    let results = facilitator
+        .settle_batch(&body.payments)
+        .await
+        .into_iter()
+        .map(|result| match result {
+            Ok(response) => response,
+            Err(error) => {
+                #[cfg(feature = "telemetry")]
+                tracing::warn!(error = ?error, "Batch settlement of a payment failed");
+                let response = error
+                    .as_json_value()
+                    .unwrap_or_else(|_| json!({ "success": false }));
+                proto::SettleResponse(response)
+            }
+        })
+        .collect();
+    (StatusCode::OK, Json(proto::SettleBatchResponse { results })).into_response()
+}
+
+/// Content type of error responses, [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807)
+/// problem details.
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Answers with a problem details JSON `body`.
+fn problem_response(status: StatusCode, body: impl Serialize) -> Response {
+    let mut response = (status, Json(body)).into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(PROBLEM_JSON_CONTENT_TYPE),
+    );
+    response
+}
+
+/// Answers with problem details of the generic `about:blank` type, for errors that are
+/// not about a payment.
+fn plain_problem_response(status: StatusCode, detail: String, instance: &str) -> Response {
+    let details = ProblemDetails {
+        type_uri: "about:blank".to_string(),
+        title: status.canonical_reason().unwrap_or_default().to_string(),
+        status: status.as_u16(),
+        detail,
+        instance: Some(instance.to_string()),
+    };
+    problem_response(status, details)
+}
+
+#[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct VerificationErrorResponse<'a> {
     is_valid: bool,
     invalid_reason: ErrorReason,
     invalid_reason_details: &'a str,
     payer: &'a str,
+    problem: PaymentProblemBody,
+    #[serde(flatten)]
+    details: ProblemDetails,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct SettlementErrorResponse<'a> {
     success: bool,
@@ -229,23 +503,33 @@ struct SettlementErrorResponse<'a> {
     error_reason: ErrorReason,
     error_message: &'a str,
     payer: &'a str,
+    problem: PaymentProblemBody,
+    #[serde(flatten)]
+    details: ProblemDetails,
 }
 
+/// Error responses carry the x402 fields clients rely on, e.g. `invalidReason`, along with
+/// the [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) problem details members: `type`,
+/// `title`, `status`, `detail` and `instance`.
 impl AsJsonValue for FacilitatorLocalError {
     fn as_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
         match self {
             FacilitatorLocalError::Verification(scheme_handler_error) => {
-                let problem = scheme_handler_error.as_payment_problem();
+                let (status, problem) =
+                    PaymentProblem::from_facilitator_error(scheme_handler_error, "/verify");
                 let verification_error_response = VerificationErrorResponse {
                     is_valid: false,
                     invalid_reason: problem.reason(),
                     invalid_reason_details: problem.details(),
                     payer: "",
+                    problem: problem.to_body(),
+                    details: problem.to_problem_details(status.as_u16()),
                 };
                 serde_json::to_value(verification_error_response)
             }
             FacilitatorLocalError::Settlement(scheme_handler_error) => {
-                let problem = scheme_handler_error.as_payment_problem();
+                let (status, problem) =
+                    PaymentProblem::from_facilitator_error(scheme_handler_error, "/settle");
                 let settlement_error_response = SettlementErrorResponse {
                     success: false,
                     network: "",
@@ -253,6 +537,8 @@ impl AsJsonValue for FacilitatorLocalError {
                     error_reason: problem.reason(),
                     error_message: problem.details(),
                     payer: "",
+                    problem: problem.to_body(),
+                    details: problem.to_problem_details(status.as_u16()),
                 };
                 serde_json::to_value(settlement_error_response)
             }
@@ -260,46 +546,18 @@ impl AsJsonValue for FacilitatorLocalError {
     }
 }
 
+/// Answers with the [`AsJsonValue`] body, as `application/problem+json`.
 impl IntoResponse for FacilitatorLocalError {
     fn into_response(self) -> Response {
-        match self {
-            FacilitatorLocalError::Verification(scheme_handler_error) => {
-                let problem = scheme_handler_error.as_payment_problem();
-                let verification_error_response = VerificationErrorResponse {
-                    is_valid: false,
-                    invalid_reason: problem.reason(),
-                    invalid_reason_details: problem.details(),
-                    payer: "",
-                };
-                let status_code = scheme_error_to_status_code(&scheme_handler_error);
-                (status_code, Json(verification_error_response)).into_response()
-            }
-            FacilitatorLocalError::Settlement(scheme_handler_error) => {
-                let problem = scheme_handler_error.as_payment_problem();
-                let settlement_error_response = SettlementErrorResponse {
-                    success: false,
-                    network: "",
-                    transaction: "",
-                    error_reason: problem.reason(),
-                    error_message: problem.details(),
-                    payer: "",
-                };
-                let status_code = scheme_error_to_status_code(&scheme_handler_error);
-                (status_code, Json(settlement_error_response)).into_response()
-            }
-        }
-    }
-}
-
-fn scheme_error_to_status_code(error: &X402SchemeFacilitatorError) -> StatusCode {
-    match error {
-        X402SchemeFacilitatorError::PaymentVerification(e) => {
-            if let PaymentVerificationError::InsufficientAllowance = e {
-                StatusCode::PRECONDITION_FAILED
-            } else {
-                StatusCode::BAD_REQUEST
+        let status = match &self {
+            FacilitatorLocalError::Verification(scheme_handler_error)
+            | FacilitatorLocalError::Settlement(scheme_handler_error) => {
+                scheme_handler_error.status_code()
             }
+        };
+        match self.as_json_value() {
+            Ok(body) => problem_response(status, body),
+            Err(_) => status.into_response(),
         }
-        X402SchemeFacilitatorError::OnchainFailure(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
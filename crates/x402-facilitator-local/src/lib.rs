@@ -16,6 +16,8 @@
 //! # Modules
 //!
 //! - [`facilitator_local`] - Core facilitator implementation
+//! - [`correlation`] - Correlation IDs tying together the requests of a payment
+//! - [`events`] - Real-time payment lifecycle events
 //! - [`handlers`] - HTTP endpoints for the x402 protocol
 //! - [`util`] - Utilities for graceful shutdown and telemetry
 //!
@@ -61,9 +63,13 @@
 //! }
 //! ```
 
+pub mod correlation;
+pub mod events;
 pub mod facilitator_local;
 pub mod handlers;
 pub mod util;
 
+pub use correlation::*;
+pub use events::*;
 pub use facilitator_local::*;
 pub use handlers::*;
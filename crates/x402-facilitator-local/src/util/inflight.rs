@@ -0,0 +1,111 @@
+//! Tracking of in-flight settlements, to drain them on shutdown.
+//!
+//! A settlement interrupted mid-way may have submitted its transaction without the
+//! facilitator waiting for, or reporting, its outcome. [`SettlementInflightCounter`] counts
+//! the settle calls in progress, so that shutdown can wait for them to complete after the
+//! listener stopped accepting requests.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//!
+//! let settlements = facilitator.settlement_counter().clone();
+//! // ... serve until a shutdown signal, then stop accepting requests
+//! if let Err(remaining) = settlements.drain(Duration::from_secs(30)).await {
+//!     tracing::warn!(remaining, "Shutting down with settlements in flight");
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Counter of the settle calls in progress.
+///
+/// Clones share the count.
+#[derive(Debug, Clone, Default)]
+pub struct SettlementInflightCounter {
+    inner: Arc<Inflight>,
+}
+
+#[derive(Debug, Default)]
+struct Inflight {
+    count: AtomicUsize,
+    changed: Notify,
+}
+
+impl SettlementInflightCounter {
+    /// Creates a counter with no settlement in flight.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of settle calls in progress.
+    pub fn count(&self) -> usize {
+        self.inner.count.load(Ordering::SeqCst)
+    }
+
+    /// Counts a settle call until the returned guard is dropped.
+    pub fn track(&self) -> InflightGuard {
+        self.inner.count.fetch_add(1, Ordering::SeqCst);
+        self.inner.changed.notify_waiters();
+        InflightGuard {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Waits until no settle call is in progress.
+    pub async fn wait_until_zero(&self) {
+        self.wait_while(|count| count > 0).await;
+    }
+
+    /// Waits until no settle call is in progress, for at most `timeout`.
+    ///
+    /// Logs the number of settlements left as it decreases. Returns the number still in
+    /// flight if the timeout elapses first.
+    pub async fn drain(&self, timeout: Duration) -> Result<(), usize> {
+        let drained = async {
+            loop {
+                let count = self.count();
+                if count == 0 {
+                    break;
+                }
+                #[cfg(feature = "telemetry")]
+                tracing::info!(inflight = count, "Draining in-flight settlements");
+                self.wait_while(|current| current == count).await;
+            }
+        };
+        tokio::time::timeout(timeout, drained)
+            .await
+            .map_err(|_| self.count())
+    }
+
+    /// Waits until `condition` no longer holds for the count.
+    async fn wait_while(&self, condition: impl Fn(usize) -> bool) {
+        loop {
+            let changed = self.inner.changed.notified();
+            tokio::pin!(changed);
+            // Register before reading the count, so that no change is missed
+            changed.as_mut().enable();
+            if !condition(self.count()) {
+                return;
+            }
+            changed.await;
+        }
+    }
+}
+
+/// Keeps a settle call counted by a [`SettlementInflightCounter`] until dropped.
+#[derive(Debug)]
+pub struct InflightGuard {
+    inner: Arc<Inflight>,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.inner.count.fetch_sub(1, Ordering::SeqCst);
+        self.inner.changed.notify_waiters();
+    }
+}
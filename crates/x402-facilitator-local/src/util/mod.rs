@@ -4,6 +4,7 @@
 //!
 //! | Module | Description | Feature |
 //! |--------|-------------|---------|
+//! | [`inflight`] | Draining in-flight settlements on shutdown | - |
 //! | [`sig_down`] | Graceful shutdown signal handling | - |
 //! | [`telemetry`] | OpenTelemetry tracing and metrics setup | `telemetry` |
 //!
@@ -16,10 +17,12 @@
 //! let token = sig_down.cancellation_token();
 //! ```
 
+pub mod inflight;
 pub mod sig_down;
 #[cfg(feature = "telemetry")]
 pub mod telemetry;
 
+pub use inflight::*;
 pub use sig_down::*;
 #[cfg(feature = "telemetry")]
 pub use telemetry::*;
@@ -45,8 +45,11 @@
 //! - Automatic graceful shutdown of exporters via [`TelemetryProviders`]
 
 use axum::http::{Request, Response};
+use opentelemetry::baggage::BaggageExt;
 use opentelemetry::trace::{Status, TracerProvider};
 use opentelemetry::{KeyValue, Value, global};
+use opentelemetry::propagation::TextMapCompositePropagator;
+use opentelemetry_sdk::propagation::{BaggagePropagator, TraceContextPropagator};
 use opentelemetry_sdk::{
     Resource,
     metrics::{MeterProviderBuilder, PeriodicReader, SdkMeterProvider},
@@ -60,6 +63,7 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use std::time::Duration;
 use tower_http::trace::{MakeSpan, OnResponse, TraceLayer};
+use x402_types::facilitator::PAYMENT_ID_BAGGAGE_KEY;
 use tracing::{Level, Span};
 use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer, OpenTelemetrySpanExt};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
@@ -337,6 +341,10 @@ impl Telemetry {
                 let tracer_provider = self.init_tracer_provider(&telemetry_protocol);
                 let meter_provider = self.init_meter_provider(&telemetry_protocol);
                 let tracer = tracer_provider.tracer("tracing-otel-subscriber");
+                global::set_text_map_propagator(TextMapCompositePropagator::new(vec![
+                    Box::new(TraceContextPropagator::new()),
+                    Box::new(BaggagePropagator::new()),
+                ]));
 
                 // Register tracing subscriber with OpenTelemetry layers
                 tracing_subscriber::registry()
@@ -454,20 +462,34 @@ impl TelemetryProviders {
 /// Custom span maker for HTTP requests.
 ///
 /// Creates OpenTelemetry-compatible spans with relevant HTTP attributes
-/// including method, URI, and version.
+/// including method, URI, and version. The `correlation_id` field is recorded by
+/// [`propagate_correlation_id`](crate::correlation::propagate_correlation_id).
+///
+/// The span continues the trace of the caller, as extracted by
+/// [`trace_parent`](crate::handlers::trace_parent), and records the `payment_id` sent as
+/// [`PAYMENT_ID_BAGGAGE_KEY`] baggage.
 #[derive(Clone, Debug)]
 pub struct FacilitatorHttpMakeSpan;
 
 impl<A> MakeSpan<A> for FacilitatorHttpMakeSpan {
     fn make_span(&mut self, request: &Request<A>) -> Span {
-        tracing::info_span!(
+        let span = tracing::info_span!(
             "http_request",
             otel.kind = "server",
             otel.name = %format!("{} {}", request.method(), request.uri()),
             method = %request.method(),
             uri = %request.uri(),
             version = ?request.version(),
-        )
+            correlation_id = tracing::field::Empty,
+            payment_id = tracing::field::Empty,
+        );
+        let parent = crate::handlers::trace_parent(request.headers());
+        if let Some(payment_id) = parent.baggage().get(PAYMENT_ID_BAGGAGE_KEY) {
+            span.record("payment_id", payment_id.as_str());
+        }
+        // Fails without an OpenTelemetry layer or for a filtered-out span, which are not exported
+        let _ = span.set_parent(parent);
+        span
     }
 }
 
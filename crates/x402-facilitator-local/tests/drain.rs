@@ -0,0 +1,140 @@
+//! Shutdown waits for in-flight settlements to complete, up to the drain timeout.
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use x402_facilitator_local::FacilitatorLocal;
+use x402_types::chain::{ChainId, ChainProviderOps, ChainRegistry};
+use x402_types::facilitator::Facilitator;
+use x402_types::proto;
+use x402_types::scheme::{
+    SchemeBlueprints, SchemeConfig, SchemeRegistry, X402SchemeFacilitator,
+    X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError, X402SchemeId,
+};
+
+const SETTLEMENT_TIME: Duration = Duration::from_millis(300);
+
+struct MockChain(ChainId);
+
+impl ChainProviderOps for MockChain {
+    fn signer_addresses(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.0.clone()
+    }
+}
+
+/// The `mock` scheme, taking [`SETTLEMENT_TIME`] to settle every payment.
+struct SlowScheme;
+
+impl X402SchemeId for SlowScheme {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "mock"
+    }
+}
+
+impl X402SchemeFacilitatorBuilder<&MockChain> for SlowScheme {
+    fn build(
+        &self,
+        provider: &MockChain,
+        _config: Option<serde_json::Value>,
+    ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        Ok(Box::new(SlowSettler(provider.0.clone())))
+    }
+}
+
+struct SlowSettler(ChainId);
+
+#[async_trait::async_trait]
+impl X402SchemeFacilitator for SlowSettler {
+    async fn verify(
+        &self,
+        _request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+        Ok(proto::VerifyResponse(json!({ "isValid": true })))
+    }
+
+    async fn settle(
+        &self,
+        _request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+        tokio::time::sleep(SETTLEMENT_TIME).await;
+        Ok(proto::SettleResponse(json!({
+            "success": true,
+            "transaction": "0xabc",
+            "network": self.0.to_string(),
+        })))
+    }
+
+    async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SupportedResponse::default())
+    }
+}
+
+fn facilitator() -> Arc<FacilitatorLocal<SchemeRegistry>> {
+    let chain_id: ChainId = "eip155:84532".parse().unwrap();
+    let chains = HashMap::from([(chain_id.clone(), MockChain(chain_id))]);
+    let schemes: Vec<SchemeConfig> =
+        serde_json::from_value(json!([{ "id": "v2-eip155-mock", "chains": "eip155:*" }])).unwrap();
+    let scheme_registry = SchemeRegistry::build(
+        ChainRegistry::new(chains),
+        SchemeBlueprints::new().and_register(SlowScheme),
+        &schemes,
+    );
+    Arc::new(FacilitatorLocal::new(scheme_registry))
+}
+
+/// Starts a settlement and waits until it is counted in flight.
+async fn start_settlement(
+    facilitator: &Arc<FacilitatorLocal<SchemeRegistry>>,
+) -> tokio::task::JoinHandle<bool> {
+    let request: proto::SettleRequest = serde_json::from_value(json!({
+        "x402Version": 2,
+        "paymentPayload": {
+            "x402Version": 2,
+            "accepted": { "scheme": "mock", "network": "eip155:84532" },
+            "payload": {},
+        },
+        "paymentRequirements": { "scheme": "mock", "network": "eip155:84532", "amount": "10000" },
+    }))
+    .unwrap();
+    let settlement = tokio::spawn({
+        let facilitator = facilitator.clone();
+        async move { facilitator.settle(&request).await.is_ok() }
+    });
+    while facilitator.settlement_counter().count() == 0 {
+        tokio::task::yield_now().await;
+    }
+    settlement
+}
+
+#[tokio::test]
+async fn slow_settlements_complete_before_the_drain_timeout() {
+    let facilitator = facilitator();
+    let settlement = start_settlement(&facilitator).await;
+
+    let counter = facilitator.settlement_counter();
+    assert_eq!(counter.count(), 1);
+    assert_eq!(counter.drain(Duration::from_secs(5)).await, Ok(()));
+    assert_eq!(counter.count(), 0);
+    assert!(settlement.is_finished());
+    assert!(settlement.await.unwrap());
+}
+
+#[tokio::test]
+async fn drain_gives_up_after_the_timeout() {
+    let facilitator = facilitator();
+    let settlement = start_settlement(&facilitator).await;
+
+    let counter = facilitator.settlement_counter();
+    assert_eq!(counter.drain(Duration::from_millis(10)).await, Err(1));
+    assert!(settlement.await.unwrap());
+    assert_eq!(counter.count(), 0);
+}
@@ -0,0 +1,182 @@
+//! `GET /events` streams the outcome of settlements made through the facilitator.
+
+use axum::Router;
+use axum::body::{Body, BodyDataStream};
+use axum::http::{Request, StatusCode, header};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tower::ServiceExt;
+use x402_facilitator_local::{
+    FacilitatorLocal, PaymentEvent, PaymentEventType, PaymentEvents, handlers,
+};
+use x402_types::chain::{ChainId, ChainProviderOps, ChainRegistry};
+use x402_types::proto;
+use x402_types::scheme::{
+    SchemeBlueprints, SchemeConfig, SchemeRegistry, X402SchemeFacilitator,
+    X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError, X402SchemeId,
+};
+
+const PAYER: &str = "0x857b06519E91e3A54538791bDbb0E22373e36b66";
+
+struct MockChain(ChainId);
+
+impl ChainProviderOps for MockChain {
+    fn signer_addresses(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.0.clone()
+    }
+}
+
+/// The `mock` scheme, settling every payment without touching a chain.
+struct MockScheme;
+
+impl X402SchemeId for MockScheme {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "mock"
+    }
+}
+
+impl X402SchemeFacilitatorBuilder<&MockChain> for MockScheme {
+    fn build(
+        &self,
+        provider: &MockChain,
+        _config: Option<serde_json::Value>,
+    ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        Ok(Box::new(MockSettler(provider.0.clone())))
+    }
+}
+
+struct MockSettler(ChainId);
+
+#[async_trait::async_trait]
+impl X402SchemeFacilitator for MockSettler {
+    async fn verify(
+        &self,
+        _request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+        Ok(proto::VerifyResponse(
+            json!({ "isValid": true, "payer": PAYER }),
+        ))
+    }
+
+    async fn settle(
+        &self,
+        _request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SettleResponse(json!({
+            "success": true,
+            "payer": PAYER,
+            "transaction": "0xabc",
+            "network": self.0.to_string(),
+        })))
+    }
+
+    async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SupportedResponse::default())
+    }
+}
+
+fn app(payment_events: PaymentEvents) -> Router {
+    let chains = ["eip155:8453", "eip155:84532"]
+        .map(|chain| chain.parse::<ChainId>().unwrap())
+        .map(|chain_id| (chain_id.clone(), MockChain(chain_id)));
+    let schemes: Vec<SchemeConfig> =
+        serde_json::from_value(json!([{ "id": "v2-eip155-mock", "chains": "eip155:*" }])).unwrap();
+    let scheme_registry = SchemeRegistry::build(
+        ChainRegistry::new(HashMap::from(chains)),
+        SchemeBlueprints::new().and_register(MockScheme),
+        &schemes,
+    );
+    let facilitator =
+        FacilitatorLocal::new(scheme_registry).with_payment_events(payment_events.clone());
+    Router::new()
+        .merge(handlers::routes().with_state(Arc::new(facilitator)))
+        .merge(handlers::event_routes(payment_events))
+}
+
+async fn settle(app: &Router, network: &str) {
+    let body = json!({
+        "x402Version": 2,
+        "paymentPayload": {
+            "x402Version": 2,
+            "accepted": { "scheme": "mock", "network": network },
+            "payload": {},
+        },
+        "paymentRequirements": { "scheme": "mock", "network": network, "amount": "10000" },
+    });
+    let request = Request::post("/settle")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn connect(app: &Router, uri: &str) -> (StatusCode, BodyDataStream) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    (response.status(), response.into_body().into_data_stream())
+}
+
+/// Reads the next `data` field of the stream.
+async fn next_event(stream: &mut BodyDataStream) -> PaymentEvent {
+    let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("no event received")
+        .unwrap()
+        .unwrap();
+    let text = String::from_utf8(chunk.to_vec()).unwrap();
+    let data = text
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .expect("not a data event");
+    serde_json::from_str(data).unwrap()
+}
+
+#[tokio::test]
+async fn settlements_are_streamed() {
+    let app = app(PaymentEvents::default());
+    let (status, mut all) = connect(&app, "/events").await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, mut base) = connect(&app, "/events?filter_chain=eip155:8453").await;
+
+    settle(&app, "eip155:84532").await;
+    settle(&app, "eip155:8453").await;
+
+    let event = next_event(&mut all).await;
+    assert_eq!(event.event_type, PaymentEventType::SettleOk);
+    assert_eq!(event.chain, Some("eip155:84532".parse().unwrap()));
+    assert_eq!(event.payer.as_deref(), Some(PAYER));
+    assert_eq!(event.amount.as_deref(), Some("10000"));
+    assert_eq!(event.error, None);
+    let event = next_event(&mut all).await;
+    assert_eq!(event.chain, Some("eip155:8453".parse().unwrap()));
+
+    // The filtered stream skips the payment on Base Sepolia.
+    let event = next_event(&mut base).await;
+    assert_eq!(event.event_type, PaymentEventType::SettleOk);
+    assert_eq!(event.chain, Some("eip155:8453".parse().unwrap()));
+}
+
+#[tokio::test]
+async fn event_streams_are_limited() {
+    let app = app(PaymentEvents::new(1));
+    let (status, stream) = connect(&app, "/events").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = connect(&app, "/events").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    drop(stream);
+    let (status, _) = connect(&app, "/events").await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,104 @@
+//! Facilitator error responses are RFC 7807 problem details, along with the x402 fields.
+
+use alloy_primitives::U256;
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+use tower::ServiceExt;
+use x402_facilitator_local::{
+    FacilitatorLocalError, PROBLEM_JSON_CONTENT_TYPE, PaymentEvents, handlers,
+};
+use x402_types::proto::PaymentVerificationError;
+use x402_types::scheme::X402SchemeFacilitatorError;
+
+async fn problem(response: Response) -> (StatusCode, Value) {
+    let status = response.status();
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        PROBLEM_JSON_CONTENT_TYPE
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+fn assert_well_formed(body: &Value, status: StatusCode, instance: &str) {
+    let type_uri = body["type"].as_str().unwrap();
+    assert!(type_uri == "about:blank" || type_uri.starts_with("https://x402.org/errors/"));
+    assert!(!body["title"].as_str().unwrap().is_empty());
+    assert_eq!(body["status"], status.as_u16());
+    assert!(!body["detail"].as_str().unwrap().is_empty());
+    assert_eq!(body["instance"], instance);
+}
+
+#[tokio::test]
+async fn all_errors_are_problem_details() {
+    let verification_errors = [
+        PaymentVerificationError::InvalidFormat("missing payload".into()),
+        PaymentVerificationError::InvalidPaymentAmount,
+        PaymentVerificationError::AmountBelowMinimum {
+            minimum: U256::from(1000),
+        },
+        PaymentVerificationError::Early,
+        PaymentVerificationError::Expired,
+        PaymentVerificationError::ChainIdMismatch,
+        PaymentVerificationError::RecipientMismatch,
+        PaymentVerificationError::AssetMismatch,
+        PaymentVerificationError::AssetNotAllowed,
+        PaymentVerificationError::DecimalsMismatch {
+            expected: 6,
+            actual: 18,
+        },
+        PaymentVerificationError::InsufficientFunds,
+        PaymentVerificationError::InsufficientAllowance,
+        PaymentVerificationError::InvalidSignature("signature mismatch".into()),
+        PaymentVerificationError::TransactionSimulation("reverted".into()),
+        PaymentVerificationError::RecipientRejected("not a receiver".into()),
+        PaymentVerificationError::UnsupportedChain,
+        PaymentVerificationError::UnsupportedScheme,
+        PaymentVerificationError::AcceptedRequirementsMismatch,
+        PaymentVerificationError::NonceUsed,
+        PaymentVerificationError::BlockhashExpired,
+    ];
+    for error in verification_errors {
+        let code = error.code();
+        let error = X402SchemeFacilitatorError::PaymentVerification(error);
+        let expected_status = error.status_code();
+        let type_uri = error.error_type_uri();
+
+        let response = FacilitatorLocalError::Verification(error).into_response();
+        let (status, body) = problem(response).await;
+        assert_eq!(status, expected_status);
+        assert_well_formed(&body, status, "/verify");
+        assert_eq!(body["type"], type_uri);
+        assert_eq!(body["title"], code.title());
+        assert_eq!(body["isValid"], false);
+        assert_eq!(body["problem"]["code"], code.as_str());
+        assert_eq!(body["detail"], body["invalidReasonDetails"]);
+    }
+
+    let error = X402SchemeFacilitatorError::OnchainFailure("transaction reverted".into());
+    let response = FacilitatorLocalError::Settlement(error).into_response();
+    let (status, body) = problem(response).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_well_formed(&body, status, "/settle");
+    assert_eq!(body["type"], "https://x402.org/errors/onchain_failure");
+    assert_eq!(body["success"], false);
+    assert_eq!(body["detail"], body["errorMessage"]);
+}
+
+#[tokio::test]
+async fn request_errors_are_problem_details() {
+    // No event stream may be opened
+    let app: Router = handlers::event_routes(PaymentEvents::new(0));
+    let request = Request::get("/events").body(Body::empty()).unwrap();
+
+    let (status, body) = problem(app.oneshot(request).await.unwrap()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_well_formed(&body, status, "/events");
+    assert_eq!(body["type"], "about:blank");
+    assert_eq!(body["title"], "Service Unavailable");
+}
@@ -0,0 +1,33 @@
+//! The facilitator serves the JSON Schemas of the V2 protocol messages.
+
+use axum::response::IntoResponse;
+use serde_json::Value;
+use x402_facilitator_local::handlers;
+
+async fn json(response: impl IntoResponse) -> Value {
+    let response = response.into_response();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn serves_the_payment_requirements_schema() {
+    let schema = json(handlers::get_payment_requirements_schema().await).await;
+    assert_eq!(schema["title"], "PaymentRequirements");
+    assert_eq!(
+        schema,
+        x402_types::schema::generate_payment_requirements_schema()
+    );
+}
+
+#[tokio::test]
+async fn serves_the_verify_request_schema() {
+    let schema = json(handlers::get_verify_request_schema().await).await;
+    assert_eq!(schema["title"], "VerifyRequest");
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&"paymentPayload".into()));
+    assert!(required.contains(&"paymentRequirements".into()));
+}
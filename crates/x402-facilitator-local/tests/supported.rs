@@ -0,0 +1,157 @@
+//! `POST /supported` lists the payment kinds a client can pay with.
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
+use x402_facilitator_local::{FacilitatorLocal, handlers};
+use x402_types::chain::{ChainId, ChainProviderOps, ChainRegistry};
+use x402_types::proto;
+use x402_types::scheme::{
+    SchemeBlueprints, SchemeConfig, SchemeRegistry, X402SchemeFacilitator,
+    X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError, X402SchemeId,
+};
+
+const SIGNER: &str = "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07";
+
+struct MockChain(ChainId);
+
+impl ChainProviderOps for MockChain {
+    fn signer_addresses(&self) -> Vec<String> {
+        vec![SIGNER.to_string()]
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.0.clone()
+    }
+}
+
+/// The `mock` scheme, supported on the chain it is built for.
+struct MockScheme;
+
+impl X402SchemeId for MockScheme {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "mock"
+    }
+}
+
+impl X402SchemeFacilitatorBuilder<&MockChain> for MockScheme {
+    fn build(
+        &self,
+        provider: &MockChain,
+        _config: Option<serde_json::Value>,
+    ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        Ok(Box::new(MockHandler(provider.0.clone())))
+    }
+}
+
+struct MockHandler(ChainId);
+
+#[async_trait::async_trait]
+impl X402SchemeFacilitator for MockHandler {
+    async fn verify(
+        &self,
+        _request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+        Err(X402SchemeFacilitatorError::OnchainFailure(
+            "not verified".into(),
+        ))
+    }
+
+    async fn settle(
+        &self,
+        _request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+        Err(X402SchemeFacilitatorError::OnchainFailure(
+            "not settled".into(),
+        ))
+    }
+
+    async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SupportedResponse {
+            kinds: vec![proto::SupportedPaymentKind {
+                x402_version: 2,
+                scheme: "mock".into(),
+                network: self.0.to_string(),
+                extra: None,
+            }],
+            extensions: vec![],
+            signers: HashMap::from([(self.0.clone(), vec![SIGNER.to_string()])]),
+        })
+    }
+}
+
+fn app() -> Router {
+    let chains = ["eip155:8453", "eip155:137"]
+        .map(|chain| chain.parse::<ChainId>().unwrap())
+        .map(|chain_id| (chain_id.clone(), MockChain(chain_id)));
+    let schemes: Vec<SchemeConfig> =
+        serde_json::from_value(json!([{ "id": "v2-eip155-mock", "chains": "eip155:*" }])).unwrap();
+    let scheme_registry = SchemeRegistry::build(
+        ChainRegistry::new(HashMap::from(chains)),
+        SchemeBlueprints::new().and_register(MockScheme),
+        &schemes,
+    );
+    handlers::routes().with_state(Arc::new(FacilitatorLocal::new(scheme_registry)))
+}
+
+async fn post_supported(capabilities: serde_json::Value) -> (StatusCode, serde_json::Value) {
+    let request = Request::post("/supported")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(capabilities.to_string()))
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn networks(supported: &serde_json::Value) -> Vec<&str> {
+    let mut networks: Vec<&str> = supported["kinds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|kind| kind["network"].as_str().unwrap())
+        .collect();
+    networks.sort();
+    networks
+}
+
+#[tokio::test]
+async fn supported_kinds_are_narrowed_to_the_client() {
+    let (status, supported) = post_supported(json!({
+        "supportedSchemes": ["mock"],
+        "supportedChains": ["eip155:8453"],
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(networks(&supported), vec!["eip155:8453"]);
+    assert_eq!(supported["signers"], json!({ "eip155:8453": [SIGNER] }));
+}
+
+#[tokio::test]
+async fn unsupported_schemes_leave_nothing() {
+    let (status, supported) = post_supported(json!({ "supportedSchemes": ["exact"] })).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(networks(&supported).is_empty());
+    assert_eq!(supported["signers"], json!({}));
+}
+
+#[tokio::test]
+async fn empty_capabilities_list_everything() {
+    let (status, supported) = post_supported(json!({})).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(networks(&supported), vec!["eip155:137", "eip155:8453"]);
+}
@@ -0,0 +1,191 @@
+//! The facilitator continues the trace of the resource server calling it.
+
+use axum::Router;
+use opentelemetry::propagation::TextMapCompositePropagator;
+use opentelemetry::trace::{SpanKind, TraceContextExt, TraceId, TracerProvider};
+use opentelemetry::{Value, global};
+use opentelemetry_sdk::propagation::{BaggagePropagator, TraceContextPropagator};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::trace::TraceLayer;
+use tracing::Instrument;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::layer::SubscriberExt;
+use x402_axum::facilitator_client::{FacilitatorClient, with_payment_id};
+use x402_facilitator_local::util::FacilitatorHttpMakeSpan;
+use x402_facilitator_local::{FacilitatorLocal, handlers};
+use x402_types::chain::{ChainId, ChainProviderOps, ChainRegistry};
+use x402_types::facilitator::Facilitator;
+use x402_types::proto;
+use x402_types::scheme::{
+    SchemeBlueprints, SchemeConfig, SchemeRegistry, X402SchemeFacilitator,
+    X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError, X402SchemeId,
+};
+
+struct MockChain(ChainId);
+
+impl ChainProviderOps for MockChain {
+    fn signer_addresses(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.0.clone()
+    }
+}
+
+struct MockScheme;
+
+impl X402SchemeId for MockScheme {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "mock"
+    }
+}
+
+impl X402SchemeFacilitatorBuilder<&MockChain> for MockScheme {
+    fn build(
+        &self,
+        provider: &MockChain,
+        _config: Option<serde_json::Value>,
+    ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        Ok(Box::new(MockSettler(provider.0.clone())))
+    }
+}
+
+struct MockSettler(ChainId);
+
+#[async_trait::async_trait]
+impl X402SchemeFacilitator for MockSettler {
+    async fn verify(
+        &self,
+        _request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+        Ok(proto::VerifyResponse(json!({
+            "isValid": true,
+            "payer": "0x857b06519E91e3A54538791bDbb0E22373e36b66",
+        })))
+    }
+
+    async fn settle(
+        &self,
+        _request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SettleResponse(json!({
+            "success": true,
+            "payer": "0x857b06519E91e3A54538791bDbb0E22373e36b66",
+            "transaction": "0xabc",
+            "network": self.0.to_string(),
+        })))
+    }
+
+    async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SupportedResponse::default())
+    }
+}
+
+/// Serves the facilitator, traced as by the facilitator binary, on a random local port.
+async fn serve_facilitator() -> String {
+    let chain_id: ChainId = "eip155:84532".parse().unwrap();
+    let chains = HashMap::from([(chain_id.clone(), MockChain(chain_id))]);
+    let schemes: Vec<SchemeConfig> =
+        serde_json::from_value(json!([{ "id": "v2-eip155-mock", "chains": "eip155:*" }])).unwrap();
+    let scheme_registry = SchemeRegistry::build(
+        ChainRegistry::new(chains),
+        SchemeBlueprints::new().and_register(MockScheme),
+        &schemes,
+    );
+    let app = Router::new()
+        .merge(handlers::routes().with_state(Arc::new(FacilitatorLocal::new(scheme_registry))))
+        .layer(TraceLayer::new_for_http().make_span_with(FacilitatorHttpMakeSpan));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}/")
+}
+
+fn payment() -> serde_json::Value {
+    json!({
+        "x402Version": 2,
+        "paymentPayload": {
+            "x402Version": 2,
+            "accepted": { "scheme": "mock", "network": "eip155:84532" },
+            "payload": {},
+        },
+        "paymentRequirements": { "scheme": "mock", "network": "eip155:84532", "amount": "10000" },
+    })
+}
+
+/// Waits for the facilitator to export the spans of its `count` requests, which end
+/// once their responses are sent.
+async fn server_spans(exporter: &InMemorySpanExporter, count: usize) -> Vec<SpanData> {
+    for _ in 0..100 {
+        let spans: Vec<SpanData> = exporter
+            .get_finished_spans()
+            .unwrap()
+            .into_iter()
+            .filter(|span| span.span_kind == SpanKind::Server)
+            .collect();
+        if spans.len() >= count {
+            return spans;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the facilitator did not export {count} request spans");
+}
+
+#[tokio::test]
+async fn facilitator_spans_continue_the_trace_of_the_resource_server() {
+    let exporter = InMemorySpanExporter::default();
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(OpenTelemetryLayer::new(tracer_provider.tracer("trace-context-test")));
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+    global::set_text_map_propagator(TextMapCompositePropagator::new(vec![
+        Box::new(TraceContextPropagator::new()),
+        Box::new(BaggagePropagator::new()),
+    ]));
+
+    let facilitator = FacilitatorClient::try_from(serve_facilitator().await).unwrap();
+    let verify_request: proto::VerifyRequest = serde_json::from_value(payment()).unwrap();
+    let settle_request: proto::SettleRequest = serde_json::from_value(payment()).unwrap();
+
+    // The payment cycle of a resource server, calling the facilitator as the payment
+    // middleware does: verify, then settle, in a single trace
+    let payment_span = tracing::info_span!("payment");
+    let trace_id: TraceId = payment_span.context().span().span_context().trace_id();
+    let payment_id = "payment-42".parse().unwrap();
+    with_payment_id(
+        Some(payment_id),
+        async {
+            Facilitator::verify(&facilitator, &verify_request).await.unwrap();
+            Facilitator::settle(&facilitator, &settle_request).await.unwrap();
+        }
+        .instrument(payment_span),
+    )
+    .await;
+
+    let server_spans = server_spans(&exporter, 2).await;
+    assert_eq!(server_spans.len(), 2);
+    let client_spans = exporter.get_finished_spans().unwrap();
+    for server_span in &server_spans {
+        assert_eq!(server_span.span_context.trace_id(), trace_id);
+        assert!(client_spans.iter().any(|client_span| {
+            client_span.span_context.span_id() == server_span.parent_span_id
+                && client_span.name.starts_with("x402.facilitator_client.")
+        }));
+        assert!(server_span.attributes.iter().any(|attribute| {
+            attribute.key.as_str() == "payment_id"
+                && attribute.value == Value::from("payment-42")
+        }));
+    }
+}
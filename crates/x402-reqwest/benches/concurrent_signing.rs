@@ -0,0 +1,163 @@
+//! Signs 1,000 payments concurrently, with signing run on the executor and on blocking threads.
+//!
+//! Each payment is signed by a signer spinning for a fixed time, standing in for CPU-bound
+//! EIP-712 or Solana transaction signing. For both [`SignerKind`]s, the benchmark reports
+//! the time to sign all payments and the worst delay of a heartbeat task ticking every
+//! millisecond on the same runtime, i.e. how long the executor was kept from other work.
+//!
+//! ```text
+//! cargo bench -p x402-reqwest --features non-blocking-sign --bench concurrent_signing
+//! ```
+
+use alloy_primitives::U256;
+use serde_json::json;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use x402_reqwest::X402Client;
+use x402_types::proto;
+use x402_types::scheme::X402SchemeId;
+use x402_types::scheme::client::{
+    PaymentCandidate, PaymentCandidateSigner, SignerKind, X402Error, X402SchemeClient,
+};
+use x402_types::util::Base64Bytes;
+
+const PAYMENTS: usize = 1_000;
+const WORKER_THREADS: usize = 4;
+const SIGNING_TIME: Duration = Duration::from_millis(2);
+
+/// Scheme client that accepts any requirement and signs with a [`SpinningSigner`].
+struct SpinningSchemeClient {
+    kind: SignerKind,
+}
+
+impl X402SchemeId for SpinningSchemeClient {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "exact"
+    }
+}
+
+impl X402SchemeClient for SpinningSchemeClient {
+    fn accept(&self, _payment_required: &proto::PaymentRequired) -> Vec<PaymentCandidate> {
+        vec![PaymentCandidate {
+            chain_id: "eip155:84532".parse().unwrap(),
+            asset: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".into(),
+            amount: U256::from(100),
+            scheme: "exact".into(),
+            x402_version: 2,
+            pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+            signer: Box::new(SpinningSigner { kind: self.kind }),
+        }]
+    }
+}
+
+/// Signer keeping its thread busy for [`SIGNING_TIME`].
+struct SpinningSigner {
+    kind: SignerKind,
+}
+
+#[async_trait::async_trait]
+impl PaymentCandidateSigner for SpinningSigner {
+    async fn sign_payment(&self) -> Result<String, X402Error> {
+        let start = Instant::now();
+        let mut state = 0u64;
+        while start.elapsed() < SIGNING_TIME {
+            state = black_box(state.wrapping_mul(6364136223846793005).wrapping_add(1));
+        }
+        Ok(Base64Bytes::encode(state.to_be_bytes()).to_string())
+    }
+
+    fn kind(&self) -> SignerKind {
+        self.kind
+    }
+}
+
+fn payment_required_response() -> reqwest::Response {
+    let payment_required = json!({
+        "x402Version": 2,
+        "resource": { "url": "http://localhost/protected" },
+        "accepts": [{
+            "scheme": "exact",
+            "network": "eip155:84532",
+            "amount": "100",
+            "payTo": "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+            "maxTimeoutSeconds": 300,
+            "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+            "extra": null,
+        }],
+    });
+    let header = Base64Bytes::encode(serde_json::to_vec(&payment_required).unwrap());
+    http::Response::builder()
+        .status(402)
+        .header("Payment-Required", header.to_string())
+        .body(Vec::<u8>::new())
+        .unwrap()
+        .into()
+}
+
+/// Signs [`PAYMENTS`] payments concurrently, returning the total time and the worst
+/// heartbeat delay.
+async fn run(kind: SignerKind) -> (Duration, Duration) {
+    let client = Arc::new(X402Client::new().register(SpinningSchemeClient { kind }));
+
+    let done = Arc::new(AtomicBool::new(false));
+    let heartbeat = tokio::spawn({
+        let done = done.clone();
+        async move {
+            let mut worst = Duration::ZERO;
+            while !done.load(Ordering::Relaxed) {
+                let start = Instant::now();
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                worst = worst.max(start.elapsed().saturating_sub(Duration::from_millis(1)));
+            }
+            worst
+        }
+    });
+
+    let start = Instant::now();
+    let payments: Vec<_> = (0..PAYMENTS)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                client
+                    .make_payment_headers(payment_required_response())
+                    .await
+                    .unwrap()
+            })
+        })
+        .collect();
+    for payment in payments {
+        payment.await.unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    done.store(true, Ordering::Relaxed);
+    (elapsed, heartbeat.await.unwrap())
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(WORKER_THREADS)
+        .enable_time()
+        .build()
+        .unwrap();
+
+    println!(
+        "{PAYMENTS} payments, {WORKER_THREADS} worker threads, {SIGNING_TIME:?} per signature"
+    );
+    for (label, kind) in [
+        ("on the executor", SignerKind::Async),
+        ("on blocking threads", SignerKind::Sync),
+    ] {
+        let (elapsed, worst_delay) = runtime.block_on(run(kind));
+        println!(
+            "signing {label:<20} total {elapsed:>10.2?}  {:>8.0} payments/s  worst heartbeat delay {worst_delay:>10.2?}",
+            PAYMENTS as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Approval of payments before they are signed.
+//!
+//! Human-in-the-loop agents confirm each payment ("this request wants 0.25 USDC on Base,
+//! approve?") before the client signs it. [`X402Client::with_approval`](crate::X402Client::with_approval)
+//! takes a [`PaymentApprover`], usually an async closure, that is shown the selected payment
+//! as a [`PendingPayment`] and answers with an [`Approval`]:
+//!
+//! - [`Approval::Approve`] signs this payment.
+//! - [`Approval::Deny`] fails the request with [`X402Error::PaymentDenied`] instead of
+//!   returning the `402` response.
+//! - [`Approval::ApproveWithCap`] signs this payment, and approves later payments in the
+//!   same token without asking again, until they add up to the cap.
+//!
+//! The approver is awaited without holding any lock, so it can wait for user input while
+//! other requests of the same client proceed. Without an approver, every payment is
+//! approved, as with [`AutoApprove`].
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::X402Client;
+//! use x402_reqwest::approval::{Approval, PendingPayment};
+//!
+//! let client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_approval(|payment: PendingPayment| async move {
+//!         let network = payment.network().unwrap_or("unknown network");
+//!         if ask_user(format!("{} wants {} on {network}, approve?", payment.resource_url, payment.amount)).await {
+//!             Approval::Approve
+//!         } else {
+//!             Approval::Deny
+//!         }
+//!     });
+//! ```
+//!
+//! [`X402Error::PaymentDenied`]: x402_types::scheme::client::X402Error::PaymentDenied
+
+use alloy_primitives::U256;
+use async_trait::async_trait;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use x402_types::chain::ChainId;
+use x402_types::scheme::client::PaymentCandidate;
+
+/// A payment awaiting approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingPayment {
+    /// The URL of the paid resource.
+    pub resource_url: Url,
+    /// The chain where payment will be made.
+    pub chain_id: ChainId,
+    /// The token asset address.
+    pub asset: String,
+    /// The payment amount in token units.
+    pub amount: U256,
+    /// The seller address receiving the payment.
+    pub pay_to: String,
+    /// The payment scheme name.
+    pub scheme: String,
+    /// The x402 protocol version.
+    pub x402_version: u8,
+}
+
+impl PendingPayment {
+    /// Describes `candidate`, a payment for `resource_url`.
+    pub fn new(candidate: &PaymentCandidate, resource_url: Url) -> Self {
+        Self {
+            resource_url,
+            chain_id: candidate.chain_id.clone(),
+            asset: candidate.asset.clone(),
+            amount: candidate.amount,
+            pay_to: candidate.pay_to.clone(),
+            scheme: candidate.scheme.clone(),
+            x402_version: candidate.x402_version,
+        }
+    }
+
+    /// Returns the name of the network (e.g. "base"), if it is a known one.
+    pub fn network(&self) -> Option<&'static str> {
+        self.chain_id.as_network_name()
+    }
+}
+
+/// The decision on a [`PendingPayment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approval {
+    /// Sign the payment.
+    Approve,
+    /// Refuse the payment, failing the request.
+    Deny,
+    /// Sign the payment, and approve later payments in the same token on the same chain
+    /// while the approved amounts, this payment included, add up to at most the cap, in
+    /// token units. A payment above the cap is denied.
+    ApproveWithCap(U256),
+}
+
+/// Decides whether a payment is signed.
+#[async_trait]
+pub trait PaymentApprover: Send + Sync {
+    /// Approves or denies `payment`.
+    async fn approve(&self, payment: PendingPayment) -> Approval;
+}
+
+#[async_trait]
+impl<F, Fut> PaymentApprover for F
+where
+    F: Fn(PendingPayment) -> Fut + Send + Sync,
+    Fut: Future<Output = Approval> + Send,
+{
+    async fn approve(&self, payment: PendingPayment) -> Approval {
+        self(payment).await
+    }
+}
+
+/// Non-interactive approver approving every payment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AutoApprove;
+
+#[async_trait]
+impl PaymentApprover for AutoApprove {
+    async fn approve(&self, _payment: PendingPayment) -> Approval {
+        Approval::Approve
+    }
+}
+
+/// A [`PaymentApprover`] with the caps granted by [`Approval::ApproveWithCap`].
+pub(crate) struct Approvals {
+    approver: Box<dyn PaymentApprover>,
+    /// Amount left under the cap, per chain and asset.
+    caps: Mutex<HashMap<(ChainId, String), U256>>,
+}
+
+impl Approvals {
+    pub(crate) fn new<A: PaymentApprover + 'static>(approver: A) -> Self {
+        Self {
+            approver: Box::new(approver),
+            caps: Default::default(),
+        }
+    }
+
+    /// Returns whether `payment` is approved, by a cap granted earlier or by the approver.
+    pub(crate) async fn approve(&self, payment: PendingPayment) -> bool {
+        let key = (payment.chain_id.clone(), payment.asset.clone());
+        if self.take_from_cap(&key, payment.amount) {
+            return true;
+        }
+        let amount = payment.amount;
+        match self.approver.approve(payment).await {
+            Approval::Approve => true,
+            Approval::Deny => false,
+            Approval::ApproveWithCap(cap) => match cap.checked_sub(amount) {
+                Some(left) => {
+                    self.caps
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(key, left);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Deducts `amount` from the cap of `key` if it covers it.
+    fn take_from_cap(&self, key: &(ChainId, String), amount: U256) -> bool {
+        let mut caps = self.caps.lock().unwrap_or_else(PoisonError::into_inner);
+        match caps.get_mut(key) {
+            Some(left) if *left >= amount => {
+                *left -= amount;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,705 @@
+//! Spending budget across all payments made by a client.
+//!
+//! A [`MaxAmount`](x402_types::scheme::client::MaxAmount) selector caps each payment, but
+//! many capped payments can still drain a wallet. A [`Budget`] caps the cumulative spend:
+//! per period, e.g. 5 USDC per hour, and in total, e.g. 50 USDC over the client's lifetime.
+//! Once a limit is reached, the [`X402Client`](crate::X402Client) refuses to pay, with
+//! [`X402Error::BudgetExhausted`](x402_types::scheme::client::X402Error::BudgetExhausted).
+//!
+//! Limits are expressed in token units, e.g. `5.00 USDC`. Each token the client may pay
+//! with is registered as a [`BudgetToken`], with its symbol, decimals and deployments.
+//! Payments in tokens not registered are refused. Spend is tracked per token deployment,
+//! or per symbol, e.g. USDC on every chain together, with [`Budget::pooled_by_symbol`].
+//!
+//! A period starts with the first payment counted in it, and its spend is reset once it
+//! elapses. A payment is counted as soon as it is attached to a request, so concurrent
+//! requests never exceed the budget, and given back if signing fails or the paid request
+//! could not be sent.
+//!
+//! Spend is kept in memory. With the `ledger` feature, [`Budget::with_ledger`] records
+//! payments in a [`SpendLedger`](crate::ledger::SpendLedger), from which the spend of each
+//! period is restored after a restart.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::budget::{Budget, BudgetToken};
+//! use x402_types::chain::ChainId;
+//!
+//! let usdc = BudgetToken::new("USDC", 6)
+//!     .on(ChainId::new("eip155", "8453"), "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913");
+//! let budget = Budget::per_hour("5.00 USDC".parse()?)
+//!     .and_total("50.00 USDC".parse()?)
+//!     .with_token(usdc);
+//! let x402_client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_budget(budget);
+//! ```
+
+use alloy_primitives::U256;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use x402_types::chain::ChainId;
+use x402_types::scheme::client::PaymentCandidate;
+use x402_types::timestamp::UnixTimestamp;
+use x402_types::util::money_amount::{MoneyAmount, MoneyAmountParseError};
+
+#[cfg(feature = "ledger")]
+use crate::ledger::{SpendLedger, SpendLedgerError, SpendRecord};
+
+/// An amount of a token, by symbol, e.g. `5.00 USDC`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetAmount {
+    amount: MoneyAmount,
+    symbol: String,
+}
+
+impl BudgetAmount {
+    /// Creates an amount of the token with the given symbol.
+    pub fn new(amount: MoneyAmount, symbol: impl Into<String>) -> Self {
+        Self {
+            amount,
+            symbol: symbol.into(),
+        }
+    }
+
+    /// The amount, in whole tokens.
+    pub fn amount(&self) -> &MoneyAmount {
+        &self.amount
+    }
+
+    /// The token symbol.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Converts the amount to atomic units of a token with `decimals` decimals, rounding
+    /// down digits beyond the token's precision.
+    pub(crate) fn to_atomic(&self, decimals: u8) -> U256 {
+        let mantissa = U256::from(self.amount.mantissa());
+        let scale = self.amount.scale();
+        let decimals = u32::from(decimals);
+        if scale <= decimals {
+            mantissa * U256::from(10).pow(U256::from(decimals - scale))
+        } else {
+            mantissa / U256::from(10).pow(U256::from(scale - decimals))
+        }
+    }
+}
+
+/// Errors parsing a [`BudgetAmount`].
+#[derive(Debug, thiserror::Error)]
+pub enum BudgetAmountParseError {
+    /// The input is not an amount followed by a symbol.
+    #[error("Expected an amount followed by a token symbol, e.g. `5.00 USDC`")]
+    InvalidFormat,
+    /// The amount could not be parsed.
+    #[error(transparent)]
+    Amount(#[from] MoneyAmountParseError),
+}
+
+impl FromStr for BudgetAmount {
+    type Err = BudgetAmountParseError;
+
+    /// Parses an amount followed by a token symbol, e.g. `5.00 USDC`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let (Some(amount), Some(symbol), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(BudgetAmountParseError::InvalidFormat);
+        };
+        Ok(Self::new(MoneyAmount::parse(amount)?, symbol))
+    }
+}
+
+/// A token the client may pay with, and its deployments.
+#[derive(Debug, Clone)]
+pub struct BudgetToken {
+    symbol: String,
+    decimals: u8,
+    deployments: Vec<(ChainId, String)>,
+}
+
+impl BudgetToken {
+    /// Creates a token with the given symbol and decimals, with no deployment yet.
+    pub fn new(symbol: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals,
+            deployments: Vec::new(),
+        }
+    }
+
+    /// Adds the deployment of the token at `asset` on `chain_id`.
+    ///
+    /// Addresses are compared case-insensitively.
+    pub fn on(mut self, chain_id: ChainId, asset: impl Into<String>) -> Self {
+        self.deployments.push((chain_id, asset.into()));
+        self
+    }
+
+    /// The token symbol.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// The token decimals.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Whether the token is deployed at `asset` on `chain_id`.
+    pub fn is_deployed(&self, chain_id: &ChainId, asset: &str) -> bool {
+        self.deployments
+            .iter()
+            .any(|(deployment_chain_id, deployment_asset)| {
+                deployment_chain_id == chain_id && deployment_asset.eq_ignore_ascii_case(asset)
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BudgetLimit {
+    amount: BudgetAmount,
+    /// `None` for a total limit.
+    period: Option<Duration>,
+}
+
+/// Spend counted against one limit of one account.
+#[derive(Debug)]
+struct Spending {
+    started: UnixTimestamp,
+    amount: U256,
+}
+
+impl Spending {
+    /// Returns the spend of `key` in the period of `limit` current at `now`, restarting
+    /// the period if it elapsed.
+    fn current<'a>(
+        spent: &'a mut HashMap<SpendingKey, Spending>,
+        key: SpendingKey,
+        limit: &BudgetLimit,
+        now: UnixTimestamp,
+    ) -> &'a mut Spending {
+        let spending = spent.entry(key).or_insert(Spending {
+            started: now,
+            amount: U256::ZERO,
+        });
+        let period_end = limit
+            .period
+            .map(|period| spending.started + period.as_secs());
+        if period_end.is_some_and(|end| now >= end) {
+            *spending = Spending {
+                started: now,
+                amount: U256::ZERO,
+            };
+        }
+        spending
+    }
+}
+
+/// Account and limit index a spend is counted against.
+type SpendingKey = (String, usize);
+
+type Clock = Arc<dyn Fn() -> UnixTimestamp + Send + Sync>;
+
+/// Cumulative spending limits of a client. See the [module docs](self).
+///
+/// Clones share the spend counted so far, so one budget can cap several clients.
+#[derive(Clone)]
+pub struct Budget {
+    limits: Vec<BudgetLimit>,
+    tokens: Vec<BudgetToken>,
+    pooled: bool,
+    clock: Clock,
+    spent: Arc<Mutex<HashMap<SpendingKey, Spending>>>,
+    #[cfg(feature = "ledger")]
+    ledger: Option<Arc<dyn SpendLedger>>,
+    /// Set once the spend recorded in the ledger is restored.
+    #[cfg(feature = "ledger")]
+    restored: Arc<tokio::sync::OnceCell<()>>,
+}
+
+impl Debug for Budget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Budget")
+            .field("limits", &self.limits)
+            .field("tokens", &self.tokens)
+            .field("pooled", &self.pooled)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Budget {
+    fn new(limit: BudgetLimit) -> Self {
+        Self {
+            limits: vec![limit],
+            tokens: Vec::new(),
+            pooled: false,
+            clock: Arc::new(UnixTimestamp::now),
+            spent: Arc::default(),
+            #[cfg(feature = "ledger")]
+            ledger: None,
+            #[cfg(feature = "ledger")]
+            restored: Arc::default(),
+        }
+    }
+
+    /// Creates a budget of `limit` per `period`.
+    pub fn per(period: Duration, limit: BudgetAmount) -> Self {
+        Self::new(BudgetLimit {
+            amount: limit,
+            period: Some(period),
+        })
+    }
+
+    /// Creates a budget of `limit` per hour.
+    pub fn per_hour(limit: BudgetAmount) -> Self {
+        Self::per(Duration::from_secs(60 * 60), limit)
+    }
+
+    /// Creates a budget of `limit` per day.
+    pub fn per_day(limit: BudgetAmount) -> Self {
+        Self::per(Duration::from_secs(24 * 60 * 60), limit)
+    }
+
+    /// Creates a budget of `limit` in total.
+    pub fn total(limit: BudgetAmount) -> Self {
+        Self::new(BudgetLimit {
+            amount: limit,
+            period: None,
+        })
+    }
+
+    /// Adds a limit of `limit` per `period`.
+    pub fn and_per(mut self, period: Duration, limit: BudgetAmount) -> Self {
+        self.limits.push(BudgetLimit {
+            amount: limit,
+            period: Some(period),
+        });
+        self
+    }
+
+    /// Adds a limit of `limit` per hour.
+    pub fn and_per_hour(self, limit: BudgetAmount) -> Self {
+        self.and_per(Duration::from_secs(60 * 60), limit)
+    }
+
+    /// Adds a limit of `limit` per day.
+    pub fn and_per_day(self, limit: BudgetAmount) -> Self {
+        self.and_per(Duration::from_secs(24 * 60 * 60), limit)
+    }
+
+    /// Adds a limit of `limit` in total.
+    pub fn and_total(mut self, limit: BudgetAmount) -> Self {
+        self.limits.push(BudgetLimit {
+            amount: limit,
+            period: None,
+        });
+        self
+    }
+
+    /// Registers a token the client may pay with.
+    ///
+    /// Payments in tokens with no limit for their symbol are not capped.
+    pub fn with_token(mut self, token: BudgetToken) -> Self {
+        self.tokens.push(token);
+        self
+    }
+
+    /// Counts the spend of all tokens with the same symbol together, e.g. USDC on every
+    /// chain, rather than per deployment.
+    pub fn pooled_by_symbol(mut self) -> Self {
+        self.pooled = true;
+        self
+    }
+
+    /// Reads the current time from `clock` rather than the system clock.
+    pub fn with_clock<F>(mut self, clock: F) -> Self
+    where
+        F: Fn() -> UnixTimestamp + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Records payments in `ledger`, and restores the spend recorded in it before the first
+    /// payment. See the [`ledger`](crate::ledger) module.
+    #[cfg(feature = "ledger")]
+    pub fn with_ledger<L: SpendLedger + 'static>(mut self, ledger: L) -> Self {
+        self.ledger = Some(Arc::new(ledger));
+        self.restored = Arc::default();
+        self
+    }
+
+    /// Counts the payments recorded in the ledger, once, as if they were made by this
+    /// process. Periods start and restart as they did when the payments were made.
+    #[cfg(feature = "ledger")]
+    pub(crate) async fn restore(&self) -> Result<(), SpendLedgerError> {
+        let Some(ledger) = &self.ledger else {
+            return Ok(());
+        };
+        self.restored
+            .get_or_try_init(|| async {
+                let records = ledger.records_since(UnixTimestamp::from_secs(0)).await?;
+                let mut spent = self.spent.lock().expect("budget lock poisoned");
+                for record in &records {
+                    let Some(token) = self.token(&record.chain_id, &record.asset) else {
+                        continue;
+                    };
+                    let account = self.account(token, &record.chain_id, &record.asset);
+                    for (index, limit) in self.limits_of(token) {
+                        let key = (account.clone(), index);
+                        let spending = Spending::current(&mut spent, key, limit, record.timestamp);
+                        spending.amount = spending.amount.saturating_add(record.amount);
+                    }
+                }
+                Ok(())
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Records the payment counted by `reservation` in the ledger, if any.
+    #[cfg(feature = "ledger")]
+    pub(crate) async fn record(
+        &self,
+        reservation: &BudgetReservation,
+    ) -> Result<(), SpendLedgerError> {
+        match (&self.ledger, &reservation.record) {
+            (Some(ledger), Some(record)) => ledger.record(record).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// The registered token deployed at `asset` on `chain_id`.
+    fn token(&self, chain_id: &ChainId, asset: &str) -> Option<&BudgetToken> {
+        self.tokens
+            .iter()
+            .find(|token| token.is_deployed(chain_id, asset))
+    }
+
+    /// The account spend of `token` at `asset` on `chain_id` is counted in.
+    fn account(&self, token: &BudgetToken, chain_id: &ChainId, asset: &str) -> String {
+        if self.pooled {
+            token.symbol.to_ascii_lowercase()
+        } else {
+            format!("{}/{}", chain_id, asset.to_ascii_lowercase())
+        }
+    }
+
+    /// The limits on `token`, with their index.
+    fn limits_of<'a>(
+        &'a self,
+        token: &'a BudgetToken,
+    ) -> impl Iterator<Item = (usize, &'a BudgetLimit)> {
+        self.limits
+            .iter()
+            .enumerate()
+            .filter(|(_, limit)| limit.amount.symbol.eq_ignore_ascii_case(&token.symbol))
+    }
+
+    /// Removes the candidates the budget does not cover.
+    ///
+    /// Returns `Some` with when the budget resets if a candidate was removed because a
+    /// limit is reached, as [`Self::charge`] does.
+    pub(crate) fn retain_affordable(
+        &self,
+        candidates: &mut Vec<PaymentCandidate>,
+    ) -> Option<Option<UnixTimestamp>> {
+        let mut exhausted: Option<Option<UnixTimestamp>> = None;
+        candidates.retain(|candidate| match self.charge(candidate, false) {
+            Ok(_) => true,
+            Err(BudgetRefusal::Untracked) => false,
+            Err(BudgetRefusal::Exhausted { resets_at }) => {
+                exhausted = Some(match exhausted {
+                    None => resets_at,
+                    Some(earliest) => earliest_reset(earliest, resets_at),
+                });
+                false
+            }
+        });
+        exhausted
+    }
+
+    /// Counts the payment of `candidate` against the budget.
+    ///
+    /// The spend is given back when the returned reservation is dropped without being
+    /// [committed](BudgetReservation::commit).
+    pub(crate) fn reserve(
+        &self,
+        candidate: &PaymentCandidate,
+    ) -> Result<BudgetReservation, BudgetRefusal> {
+        self.charge(candidate, true)
+    }
+
+    fn charge(
+        &self,
+        candidate: &PaymentCandidate,
+        count: bool,
+    ) -> Result<BudgetReservation, BudgetRefusal> {
+        let token = self
+            .token(&candidate.chain_id, &candidate.asset)
+            .ok_or(BudgetRefusal::Untracked)?;
+        let account = self.account(token, &candidate.chain_id, &candidate.asset);
+        let now = (self.clock)();
+
+        let mut spent = self.spent.lock().expect("budget lock poisoned");
+        let mut exhausted = false;
+        let mut resets_at = Some(now);
+        let mut charges = Vec::new();
+        for (index, limit) in self.limits_of(token) {
+            let key = (account.clone(), index);
+            let spending = Spending::current(&mut spent, key.clone(), limit, now);
+            let available = limit.amount.to_atomic(token.decimals);
+            if spending.amount.saturating_add(candidate.amount) > available {
+                exhausted = true;
+                let period_end = limit
+                    .period
+                    .map(|period| spending.started + period.as_secs());
+                resets_at = match (resets_at, period_end) {
+                    (Some(latest), Some(end)) => Some(latest.max(end)),
+                    _ => None,
+                };
+            }
+            charges.push((key, spending.started));
+        }
+        if exhausted {
+            return Err(BudgetRefusal::Exhausted { resets_at });
+        }
+        if count {
+            for (key, _) in &charges {
+                if let Some(spending) = spent.get_mut(key) {
+                    spending.amount = spending.amount.saturating_add(candidate.amount);
+                }
+            }
+        }
+        Ok(BudgetReservation {
+            spent: self.spent.clone(),
+            charges: if count { charges } else { Vec::new() },
+            amount: candidate.amount,
+            #[cfg(feature = "ledger")]
+            record: count.then(|| SpendRecord::new(candidate, now)),
+        })
+    }
+}
+
+/// Returns the earlier of two reset times, `None` meaning never.
+fn earliest_reset(a: Option<UnixTimestamp>, b: Option<UnixTimestamp>) -> Option<UnixTimestamp> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Why a [`Budget`] does not cover a payment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BudgetRefusal {
+    /// The payment token is not registered with the budget.
+    Untracked,
+    /// A limit is reached. `resets_at` is when every limit reached restarts its period,
+    /// or `None` if a total limit is reached.
+    Exhausted { resets_at: Option<UnixTimestamp> },
+}
+
+/// A payment counted against a [`Budget`], given back on drop unless committed.
+#[must_use]
+pub(crate) struct BudgetReservation {
+    spent: Arc<Mutex<HashMap<SpendingKey, Spending>>>,
+    /// Spend entries charged, with the start of their period when charged.
+    charges: Vec<(SpendingKey, UnixTimestamp)>,
+    amount: U256,
+    /// The payment, as recorded in the ledger.
+    #[cfg(feature = "ledger")]
+    record: Option<SpendRecord>,
+}
+
+impl BudgetReservation {
+    /// Keeps the payment counted.
+    pub(crate) fn commit(mut self) {
+        self.charges.clear();
+    }
+}
+
+impl Drop for BudgetReservation {
+    fn drop(&mut self) {
+        if self.charges.is_empty() {
+            return;
+        }
+        let mut spent = self.spent.lock().expect("budget lock poisoned");
+        for (key, started) in &self.charges {
+            // A period that restarted since no longer holds this payment
+            if let Some(spending) = spent.get_mut(key).filter(|s| s.started == *started) {
+                spending.amount = spending.amount.saturating_sub(self.amount);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    const BASE: &str = "eip155:8453";
+    const POLYGON: &str = "eip155:137";
+    const USDC_BASE: &str = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
+    const USDC_POLYGON: &str = "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359";
+
+    struct NoSigner;
+
+    #[async_trait::async_trait]
+    impl x402_types::scheme::client::PaymentCandidateSigner for NoSigner {
+        async fn sign_payment(&self) -> Result<String, x402_types::scheme::client::X402Error> {
+            unreachable!()
+        }
+    }
+
+    /// A payment of `amount` USDC, in atomic units.
+    fn payment(chain_id: &str, asset: &str, amount: u64) -> PaymentCandidate {
+        PaymentCandidate {
+            chain_id: chain_id.parse().unwrap(),
+            asset: asset.into(),
+            amount: U256::from(amount),
+            scheme: "exact".into(),
+            x402_version: 2,
+            pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+            signer: Box::new(NoSigner),
+        }
+    }
+
+    fn usdc() -> BudgetToken {
+        BudgetToken::new("USDC", 6)
+            .on(BASE.parse().unwrap(), USDC_BASE.to_lowercase())
+            .on(POLYGON.parse().unwrap(), USDC_POLYGON)
+    }
+
+    /// Returns a budget of 5 USDC per hour and 12 USDC in total, on a clock set by the
+    /// returned seconds.
+    fn hourly_budget() -> (Budget, Arc<AtomicU64>) {
+        let now = Arc::new(AtomicU64::new(1_000_000));
+        let clock = now.clone();
+        let budget = Budget::per_hour("5.00 USDC".parse().unwrap())
+            .and_total("12 USDC".parse().unwrap())
+            .with_token(usdc())
+            .with_clock(move || UnixTimestamp::from_secs(clock.load(Ordering::SeqCst)));
+        (budget, now)
+    }
+
+    #[test]
+    fn parses_amounts() {
+        let amount: BudgetAmount = "5.25 USDC".parse().unwrap();
+        assert_eq!(amount.symbol(), "USDC");
+        assert_eq!(amount.to_atomic(6), U256::from(5_250_000));
+        assert_eq!(amount.to_atomic(1), U256::from(52));
+        assert!("5.25".parse::<BudgetAmount>().is_err());
+        assert!("five USDC".parse::<BudgetAmount>().is_err());
+    }
+
+    #[test]
+    fn limits_spend_per_period() {
+        let (budget, now) = hourly_budget();
+        let started = now.load(Ordering::SeqCst);
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 3_000_000))
+            .unwrap()
+            .commit();
+        now.fetch_add(30 * 60, Ordering::SeqCst);
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 2_000_000))
+            .unwrap()
+            .commit();
+        assert_eq!(
+            budget.reserve(&payment(BASE, USDC_BASE, 1)).err(),
+            Some(BudgetRefusal::Exhausted {
+                resets_at: Some(UnixTimestamp::from_secs(started + 60 * 60))
+            })
+        );
+
+        // The hour elapsed since the first payment
+        now.fetch_add(30 * 60, Ordering::SeqCst);
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 5_000_000))
+            .unwrap()
+            .commit();
+
+        // 10 of 12 USDC spent in total
+        now.fetch_add(60 * 60, Ordering::SeqCst);
+        assert_eq!(
+            budget.reserve(&payment(BASE, USDC_BASE, 3_000_000)).err(),
+            Some(BudgetRefusal::Exhausted { resets_at: None })
+        );
+        assert!(budget.reserve(&payment(BASE, USDC_BASE, 2_000_000)).is_ok());
+    }
+
+    #[test]
+    fn dropped_reservations_are_given_back() {
+        let (budget, _now) = hourly_budget();
+        let reservation = budget
+            .reserve(&payment(BASE, USDC_BASE, 5_000_000))
+            .unwrap();
+        assert!(budget.reserve(&payment(BASE, USDC_BASE, 1)).is_err());
+        drop(reservation);
+        assert!(budget.reserve(&payment(BASE, USDC_BASE, 5_000_000)).is_ok());
+    }
+
+    #[test]
+    fn restarted_periods_are_not_given_back() {
+        let (budget, now) = hourly_budget();
+        let reservation = budget
+            .reserve(&payment(BASE, USDC_BASE, 4_000_000))
+            .unwrap();
+        now.fetch_add(60 * 60, Ordering::SeqCst);
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 5_000_000))
+            .unwrap()
+            .commit();
+        drop(reservation);
+        assert!(budget.reserve(&payment(BASE, USDC_BASE, 1)).is_err());
+    }
+
+    #[test]
+    fn tracks_spend_per_deployment_or_symbol() {
+        let (budget, _now) = hourly_budget();
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 5_000_000))
+            .unwrap()
+            .commit();
+        assert!(
+            budget
+                .reserve(&payment(POLYGON, USDC_POLYGON, 5_000_000))
+                .is_ok()
+        );
+
+        let (budget, _now) = hourly_budget();
+        let budget = budget.pooled_by_symbol();
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 5_000_000))
+            .unwrap()
+            .commit();
+        assert!(budget.reserve(&payment(POLYGON, USDC_POLYGON, 1)).is_err());
+    }
+
+    #[test]
+    fn filters_unaffordable_candidates() {
+        let (budget, now) = hourly_budget();
+        let started = now.load(Ordering::SeqCst);
+        budget
+            .reserve(&payment(BASE, USDC_BASE, 4_000_000))
+            .unwrap()
+            .commit();
+        let mut candidates = vec![
+            payment(BASE, USDC_BASE, 2_000_000),
+            payment(BASE, "0x036CbD53842c5426634e7929541eC2318f3dCF7e", 1),
+            payment(POLYGON, USDC_POLYGON, 2_000_000),
+        ];
+        assert_eq!(
+            budget.retain_affordable(&mut candidates),
+            Some(Some(UnixTimestamp::from_secs(started + 60 * 60)))
+        );
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].asset, USDC_POLYGON);
+    }
+}
@@ -3,19 +3,39 @@
 //! This module provides the [`X402Client`] which orchestrates scheme clients
 //! and payment selection for automatic payment handling.
 
-use http::{Extensions, HeaderMap, StatusCode};
-use reqwest::{Request, Response};
+use http::{Extensions, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use reqwest::{Request, Response, Url};
 use reqwest_middleware as rqm;
 use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use x402_types::chain::ChainId;
 use x402_types::proto;
-use x402_types::proto::{OriginalJson, v1, v2};
+use x402_types::proto::{OriginalJson, PaymentId, v1, v2};
+#[cfg(all(feature = "non-blocking-sign", not(target_arch = "wasm32")))]
+use x402_types::scheme::client::SignerKind;
 use x402_types::scheme::client::{
-    FirstMatch, PaymentCandidate, PaymentSelector, X402Error, X402SchemeClient,
+    FailedPayment, FirstMatch, PaymentCandidate, PaymentSelector, SigningOptions, X402Error,
+    X402SchemeClient,
 };
+use x402_types::timestamp::UnixTimestamp;
 use x402_types::util::Base64Bytes;
 
+use crate::approval::{Approvals, PaymentApprover, PendingPayment};
+use crate::budget::{Budget, BudgetRefusal, BudgetReservation, BudgetToken};
+#[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+use crate::coalesce::{Coalescer, Coalescing, Outcome, Role};
+#[cfg(feature = "events")]
+use crate::events::{PaymentEvent, PaymentEventKind, PaymentEvents};
+use crate::facilitator::FacilitatorSupport;
+use crate::hosts::{HostPattern, HostPolicies, HostPolicy};
+use crate::prepared::PreparedPayment;
+use crate::rate_limit::RateLimit;
+use crate::receipt::{OnPayment, PaymentReceipt};
+use crate::replay::{self, BodyReplay};
+
 #[cfg(feature = "telemetry")]
-use tracing::{debug, info, instrument, trace};
+use tracing::{debug, info, instrument, trace, warn};
 
 /// The main x402 client that orchestrates scheme clients and selection.
 ///
@@ -53,6 +73,22 @@ use tracing::{debug, info, instrument, trace};
 pub struct X402Client<TSelector> {
     schemes: ClientSchemes,
     selector: TSelector,
+    standard_base64: bool,
+    signing_options: SigningOptions,
+    clock_calibration: bool,
+    budget: Option<Budget>,
+    approvals: Option<Approvals>,
+    facilitator: Option<FacilitatorSupport>,
+    on_payment: Option<OnPayment>,
+    max_attempts: usize,
+    max_challenges: usize,
+    body_replay: BodyReplay,
+    hosts: HostPolicies,
+    rate_limit: Option<RateLimit>,
+    #[cfg(feature = "events")]
+    events: PaymentEvents,
+    #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+    coalescer: Option<Coalescer>,
 }
 
 impl X402Client<FirstMatch> {
@@ -70,6 +106,22 @@ impl Default for X402Client<FirstMatch> {
         Self {
             schemes: ClientSchemes::default(),
             selector: FirstMatch,
+            standard_base64: false,
+            signing_options: SigningOptions::default(),
+            clock_calibration: false,
+            budget: None,
+            approvals: None,
+            facilitator: None,
+            on_payment: None,
+            max_attempts: 1,
+            max_challenges: 2,
+            body_replay: BodyReplay::default(),
+            hosts: HostPolicies::default(),
+            rate_limit: None,
+            #[cfg(feature = "events")]
+            events: PaymentEvents::default(),
+            #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+            coalescer: None,
         }
     }
 }
@@ -127,6 +179,264 @@ impl<TSelector> X402Client<TSelector> {
         X402Client {
             selector,
             schemes: self.schemes,
+            standard_base64: self.standard_base64,
+            signing_options: self.signing_options,
+            clock_calibration: self.clock_calibration,
+            budget: self.budget,
+            approvals: self.approvals,
+            facilitator: self.facilitator,
+            on_payment: self.on_payment,
+            max_attempts: self.max_attempts,
+            max_challenges: self.max_challenges,
+            body_replay: self.body_replay,
+            hosts: self.hosts,
+            rate_limit: self.rate_limit,
+            #[cfg(feature = "events")]
+            events: self.events,
+            #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+            coalescer: self.coalescer,
+        }
+    }
+
+    /// Sends payment headers in the standard base64 alphabet.
+    ///
+    /// By default, payment headers use the URL-safe alphabet (`-` and `_` instead of
+    /// `+` and `/`), which HTTP parsers and logging systems handle better. Use this for
+    /// servers that only accept the standard alphabet.
+    pub fn with_standard_base64(mut self) -> Self {
+        self.standard_base64 = true;
+        self
+    }
+
+    /// Sets how long signed payment authorizations stay valid.
+    ///
+    /// By default, an authorization is valid for the `maxTimeoutSeconds` of the server's
+    /// payment requirements. A window too short for the facilitator to settle within makes
+    /// payments expire before settlement: EVM facilitators reject authorizations that expire
+    /// within the next few seconds. A long window leaves a signed, unsettled authorization
+    /// usable for longer should it leak. Solana payments are bound to a recent blockhash
+    /// instead, and ignore the window.
+    ///
+    /// Whole seconds are used; see [`SigningOptions`].
+    pub fn with_validity_window(mut self, window: Duration) -> Self {
+        self.signing_options.validity_window = Some(window);
+        self
+    }
+
+    /// Signs payments on the server's clock, as told by the `Date` header of its 402
+    /// response, rather than on the local one.
+    ///
+    /// EVM authorizations are valid from `validAfter`, set a little before the time of
+    /// signing, see the `with_clock_skew` method of the scheme clients. A local clock running
+    /// further ahead of the facilitator's makes payments fail as not yet valid, and one
+    /// running behind shortens their validity window. With calibration, the offset between
+    /// the `Date` header and the local clock is applied when signing; responses without a
+    /// valid `Date` header are paid on the local clock. The header has a one second
+    /// resolution.
+    pub fn with_clock_calibration(mut self) -> Self {
+        self.clock_calibration = true;
+        self
+    }
+
+    /// Caps the cumulative spend of this client, see [`Budget`].
+    ///
+    /// Payment options the budget does not cover are not considered for selection. When
+    /// none is left because a limit is reached, payment fails with
+    /// [`X402Error::BudgetExhausted`].
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Caps how often this client pays, see [`RateLimit`].
+    ///
+    /// Payments beyond the limit fail with [`X402Error::RateLimited`], or wait with
+    /// `RateLimit::waiting`.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Asks `approver` to approve each payment before it is signed, see [`approval`](crate::approval).
+    ///
+    /// A denied payment fails with [`X402Error::PaymentDenied`]. The approver may take its
+    /// time, e.g. to ask a user: other requests are not held up meanwhile.
+    pub fn with_approval<A: PaymentApprover + 'static>(mut self, approver: A) -> Self {
+        self.approvals = Some(Approvals::new(approver));
+        self
+    }
+
+    /// Only pays with options the facilitator of the sellers supports, see
+    /// [`facilitator`](crate::facilitator).
+    ///
+    /// Options the facilitator does not list in its `/supported` payment kinds are not
+    /// considered for selection, so that the next best option is paid with instead.
+    pub fn with_facilitator(mut self, facilitator: FacilitatorSupport) -> Self {
+        self.facilitator = Some(facilitator);
+        self
+    }
+
+    /// Calls `callback` with the receipt of each settled payment, see [`receipt`](crate::receipt).
+    ///
+    /// The callback runs on the request task before the paid response is returned: hand
+    /// slow work, e.g. writes to a database, off to another task.
+    pub fn on_payment<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(PaymentReceipt) + Send + Sync + 'static,
+    {
+        self.on_payment = Some(Arc::new(callback));
+        self
+    }
+
+    /// Pays with up to `attempts` payment options per request, retrying when a payment fails.
+    ///
+    /// By default, a single payment is made, and a payment that fails to settle is returned
+    /// as the server's response. With more attempts, a payment is failed when the server
+    /// answers it with another 402, or a `Payment-Response` reporting a failed settlement,
+    /// e.g. because the facilitator could not reach the chain. The failed option is then
+    /// excluded for this request, and a fresh payment is signed with the next option the
+    /// budget, approver and facilitator allow. Authorizations are never reused.
+    ///
+    /// When every attempt fails, or no option is left, the request fails with
+    /// [`X402Error::PaymentFailed`], listing each failed payment and its reason.
+    pub fn with_max_attempts(mut self, attempts: usize) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Pays again up to `challenges` times per request when the server answers a payment
+    /// with a fresh 402 carrying different requirements, e.g. a higher amount after a price
+    /// change. Defaults to 2, and 0 returns such a 402 as the server's response.
+    ///
+    /// Each new challenge goes through selection, the budget, the approver and the
+    /// facilitator again, and is paid with a freshly signed payment. A server answering with
+    /// requirements it challenged with before is not paid again: the 402 is handled as a
+    /// failed payment instead, see [`X402Client::with_max_attempts`]. Re-challenges do not
+    /// count as attempts.
+    pub fn with_max_challenges(mut self, challenges: usize) -> Self {
+        self.max_challenges = challenges;
+        self
+    }
+
+    /// Sets the payment settings of the hosts matching `pattern`, see [`hosts`](crate::hosts).
+    ///
+    /// `pattern` is a host, e.g. `api.example.com`, or a suffix wildcard, e.g.
+    /// `*.example.com`. Settings `configure` leaves unset are taken from
+    /// [`X402Client::with_host_defaults`].
+    ///
+    /// ```rust,ignore
+    /// let client = X402Client::new()
+    ///     .register(V2Eip155ExactClient::new(signer))
+    ///     .with_token(usdc)
+    ///     .for_host("api.example.com", |host| host.max("5.00 USDC".parse().unwrap()));
+    /// ```
+    pub fn for_host<F>(mut self, pattern: &str, configure: F) -> Self
+    where
+        F: FnOnce(HostPolicy) -> HostPolicy,
+    {
+        self.hosts
+            .insert(HostPattern::new(pattern), configure(HostPolicy::default()));
+        self
+    }
+
+    /// Sets the payment settings of every host, overridden by [`X402Client::for_host`].
+    pub fn with_host_defaults<F>(mut self, configure: F) -> Self
+    where
+        F: FnOnce(HostPolicy) -> HostPolicy,
+    {
+        self.hosts.set_defaults(configure(HostPolicy::default()));
+        self
+    }
+
+    /// Never pays hosts matching no [`X402Client::for_host`] pattern.
+    ///
+    /// Their payments fail with [`X402Error::HostNotAllowed`].
+    pub fn deny_unknown_hosts(mut self) -> Self {
+        self.hosts.deny_unknown();
+        self
+    }
+
+    /// Registers a token the client may pay with, for the caps of [`HostPolicy::max`].
+    pub fn with_token(mut self, token: BudgetToken) -> Self {
+        self.hosts.add_token(token);
+        self
+    }
+
+    /// The host policies of the client, e.g. to inspect the settings a host is paid with
+    /// using [`HostPolicies::resolve`].
+    pub fn hosts(&self) -> &HostPolicies {
+        &self.hosts
+    }
+
+    /// Sets how streaming request bodies are replayed with the payment, see [`replay`].
+    ///
+    /// By default, streaming bodies of up to [`BodyReplay::DEFAULT_LIMIT`] bytes are read
+    /// into memory before the request is sent. A 402 answering a larger body fails with
+    /// [`X402Error::BodyTooLarge`].
+    pub fn with_body_replay(mut self, body_replay: BodyReplay) -> Self {
+        self.body_replay = body_replay;
+        self
+    }
+
+    /// Publishes the payment events of this client on `events`, see
+    /// [`events`](crate::events).
+    ///
+    /// By default, each client has its own channel of [`PaymentEvents::DEFAULT_CAPACITY`].
+    /// Share one to follow the payments of several clients.
+    #[cfg(feature = "events")]
+    pub fn with_events(mut self, events: PaymentEvents) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Subscribes to the payment events of this client, see [`events`](crate::events).
+    ///
+    /// Only events published from now on are received: subscribe before sending requests.
+    #[cfg(feature = "events")]
+    pub fn events(&self) -> tokio::sync::broadcast::Receiver<PaymentEvent> {
+        self.events.subscribe()
+    }
+
+    /// Has concurrent `GET` and `HEAD` requests to the same URL share the challenge, and the
+    /// paid response if cacheable, see [`coalesce`](crate::coalesce).
+    ///
+    /// Not available on `wasm32`.
+    #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+    pub fn with_coalescing(mut self, coalescing: Coalescing) -> Self {
+        self.coalescer = Some(Coalescer::new(coalescing));
+        self
+    }
+
+    /// Publishes a payment event of `kind` for `url`.
+    #[cfg(feature = "events")]
+    fn publish(&self, url: &Url, payment_id: Option<&PaymentId>, kind: PaymentEventKind) {
+        self.events
+            .publish(PaymentEvent::new(url.clone(), payment_id.cloned(), kind));
+    }
+
+    /// Fails the payment cycle for `url` with `error`, publishing it as a
+    /// [`PaymentEventKind::PaymentFailed`] event with the `events` feature.
+    fn fail(&self, _url: &Url, _payment_id: Option<&PaymentId>, error: X402Error) -> rqm::Error {
+        #[cfg(feature = "events")]
+        self.publish(
+            _url,
+            _payment_id,
+            PaymentEventKind::PaymentFailed {
+                payment: None,
+                reason: error.to_string(),
+            },
+        );
+        rqm::Error::Middleware(error.into())
+    }
+
+    /// Returns the options to sign a payment answering `res` with, on the server's clock if
+    /// [`Self::with_clock_calibration`] is set.
+    fn signing_options_for(&self, res: &Response) -> SigningOptions {
+        match server_date(res.headers()).filter(|_| self.clock_calibration) {
+            Some(server_time) => self
+                .signing_options
+                .calibrated(server_time, UnixTimestamp::now()),
+            None => self.signing_options,
         }
     }
 }
@@ -153,22 +463,141 @@ where
     ///
     /// Returns [`X402Error::ParseError`] if the response cannot be parsed.
     /// Returns [`X402Error::NoMatchingPaymentOption`] if no registered scheme
-    /// can handle the payment requirements.
+    /// can handle the payment requirements, or the facilitator set with
+    /// [`X402Client::with_facilitator`] supports none of them.
+    /// Returns [`X402Error::BudgetExhausted`] if the [`Budget`] does not cover any option.
+    /// Returns [`X402Error::HostNotAllowed`] if the host is not paid, see
+    /// [`X402Client::deny_unknown_hosts`].
+    /// Returns [`X402Error::PaymentDenied`] if the approver set with
+    /// [`X402Client::with_approval`] denies the payment.
+    /// Returns [`X402Error::RateLimited`] if the [`RateLimit`] allows no payment for now.
+    /// The payment is counted against the budget once the headers are returned.
     #[cfg_attr(
         feature = "telemetry",
         instrument(name = "x402.reqwest.make_payment_headers", skip_all, err)
     )]
     pub async fn make_payment_headers(&self, res: Response) -> Result<HeaderMap, X402Error> {
-        let payment_required = parse_payment_required(res)
+        let resource_url = res.url().clone();
+        let signing_options = self.signing_options_for(&res);
+        let Some(payment_required) = parse_payment_required(res).await else {
+            let error = X402Error::ParseError("Invalid 402 response".to_string());
+            #[cfg(feature = "events")]
+            self.publish(
+                &resource_url,
+                None,
+                PaymentEventKind::PaymentFailed {
+                    payment: None,
+                    reason: error.to_string(),
+                },
+            );
+            return Err(error);
+        };
+        let prepared = self
+            .prepare_signed_payment(&payment_required, &resource_url, &signing_options)
+            .await?;
+        Ok(prepared.to_headers())
+    }
+
+    /// Prepares a payment for `resource`, challenged with `payment_required`, without any
+    /// HTTP I/O, see the [`prepared`](crate::prepared) module.
+    ///
+    /// The payment is selected, approved, signed and counted against the budget as the
+    /// middleware does, and returned with the header to send it in.
+    ///
+    /// # Errors
+    ///
+    /// As [`Self::make_payment_headers`], except for [`X402Error::ParseError`].
+    pub async fn prepare_payment(
+        &self,
+        payment_required: &proto::PaymentRequired,
+        resource: &Url,
+    ) -> Result<PreparedPayment, X402Error> {
+        self.prepare_signed_payment(payment_required, resource, &self.signing_options)
             .await
-            .ok_or(X402Error::ParseError("Invalid 402 response".to_string()))?;
-        let candidates = self.schemes.candidates(&payment_required);
+    }
+
+    /// Prepares a payment as [`Self::prepare_payment`], signed with `signing_options`.
+    #[cfg_attr(
+        feature = "telemetry",
+        instrument(name = "x402.reqwest.prepare_payment", skip_all, fields(resource = %resource), err)
+    )]
+    async fn prepare_signed_payment(
+        &self,
+        payment_required: &proto::PaymentRequired,
+        resource: &Url,
+        signing_options: &SigningOptions,
+    ) -> Result<PreparedPayment, X402Error> {
+        #[cfg(feature = "events")]
+        self.publish(resource, None, payment_required_event(payment_required));
+        let result = self
+            .pay(
+                resource.clone(),
+                None,
+                payment_required,
+                signing_options,
+                &[],
+            )
+            .await;
+        #[cfg(feature = "events")]
+        if let Err(e) = &result {
+            self.publish(
+                resource,
+                None,
+                PaymentEventKind::PaymentFailed {
+                    payment: None,
+                    reason: e.to_string(),
+                },
+            );
+        }
+        let (prepared, reservation) = result?;
+        if let Some(reservation) = reservation {
+            reservation.commit();
+        }
+        Ok(prepared)
+    }
+
+    /// Prepares a payment for `payment_required`, as [`Self::prepare_payment`], signed with
+    /// `signing_options` and skipping the options of payments that already `failed` for this
+    /// request, along with the payment counted against the budget, given back if dropped
+    /// uncommitted.
+    ///
+    /// `payment_id` is the correlation ID of the payment cycle, for its events.
+    async fn pay(
+        &self,
+        resource_url: Url,
+        _payment_id: Option<&PaymentId>,
+        payment_required: &proto::PaymentRequired,
+        signing_options: &SigningOptions,
+        failed: &[FailedPayment],
+    ) -> Result<(PreparedPayment, Option<BudgetReservation>), X402Error> {
+        let mut candidates = self
+            .schemes
+            .candidates_with_options(payment_required, signing_options);
+        candidates.retain(|candidate| !failed.iter().any(|failure| failure.is_for(candidate)));
+        self.hosts
+            .apply(resource_url.host_str().unwrap_or_default(), &mut candidates)?;
+        if let Some(facilitator) = &self.facilitator {
+            facilitator.retain_supported(&mut candidates).await;
+        }
+
+        #[cfg(feature = "ledger")]
+        if let Some(budget) = &self.budget {
+            budget
+                .restore()
+                .await
+                .map_err(|e| X402Error::SpendLedger(e.to_string()))?;
+        }
+
+        let exhausted = self
+            .budget
+            .as_ref()
+            .and_then(|budget| budget.retain_affordable(&mut candidates));
 
         // Select the best candidate
-        let selected = self
-            .selector
-            .select(&candidates)
-            .ok_or(X402Error::NoMatchingPaymentOption)?;
+        let selected = self.selector.select(&candidates).ok_or(match exhausted {
+            Some(resets_at) => X402Error::BudgetExhausted { resets_at },
+            None => X402Error::NoMatchingPaymentOption,
+        })?;
 
         #[cfg(feature = "telemetry")]
         debug!(
@@ -177,18 +606,362 @@ where
             "Selected payment scheme"
         );
 
-        let signed_payload = selected.sign().await?;
-        let header_name = match &payment_required {
-            proto::PaymentRequired::V1(_) => "X-Payment",
-            proto::PaymentRequired::V2(_) => "Payment-Signature",
+        let index = candidates
+            .iter()
+            .position(|candidate| std::ptr::eq(candidate, selected))
+            .ok_or(X402Error::NoMatchingPaymentOption)?;
+        let selected = candidates.swap_remove(index);
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.acquire().await?;
+        }
+        let payment = PendingPayment::new(&selected, resource_url);
+        #[cfg(feature = "events")]
+        self.publish(
+            &payment.resource_url,
+            _payment_id,
+            PaymentEventKind::CandidateSelected(payment.clone()),
+        );
+        if let Some(approvals) = &self.approvals
+            && !approvals.approve(payment.clone()).await
+        {
+            #[cfg(feature = "telemetry")]
+            info!(
+                chain_id = %selected.chain_id,
+                amount = %selected.amount,
+                "Payment denied"
+            );
+            return Err(X402Error::PaymentDenied);
+        }
+        let reservation = match &self.budget {
+            Some(budget) => Some(budget.reserve(&selected).map_err(|refusal| match refusal {
+                BudgetRefusal::Exhausted { resets_at } => X402Error::BudgetExhausted { resets_at },
+                BudgetRefusal::Untracked => X402Error::NoMatchingPaymentOption,
+            })?),
+            None => None,
+        };
+        let validity_seconds = match max_timeout_seconds(payment_required, &selected) {
+            Some(max_timeout_seconds) => {
+                Some(self.signing_options.validity_seconds(max_timeout_seconds))
+            }
+            None => self
+                .signing_options
+                .validity_window
+                .map(|window| window.as_secs()),
         };
-        let headers = {
-            let mut headers = HeaderMap::new();
-            headers.insert(header_name, signed_payload.parse().unwrap());
-            headers
+        let expires_at = validity_seconds.map(|seconds| UnixTimestamp::now() + seconds);
+        let signed_payload = sign_candidate(selected).await?;
+        // Recorded before the payment can be sent, so that a crash does not lose it
+        #[cfg(feature = "ledger")]
+        if let (Some(budget), Some(reservation)) = (&self.budget, &reservation) {
+            budget
+                .record(reservation)
+                .await
+                .map_err(|e| X402Error::SpendLedger(e.to_string()))?;
+        }
+        #[cfg(feature = "events")]
+        self.publish(
+            &payment.resource_url,
+            _payment_id,
+            PaymentEventKind::PaymentSigned(payment.clone()),
+        );
+        let signed_payload = if self.standard_base64 {
+            signed_payload
+        } else {
+            to_url_safe(signed_payload)
+        };
+        let header_name = match payment_required {
+            proto::PaymentRequired::V1(_) => HeaderName::from_static("x-payment"),
+            proto::PaymentRequired::V2(_) => HeaderName::from_static("payment-signature"),
+        };
+        let header_value = HeaderValue::try_from(signed_payload)
+            .map_err(|e| X402Error::SigningError(e.to_string()))?;
+        let prepared = PreparedPayment {
+            header_name,
+            header_value,
+            payment,
+            expires_at,
         };
 
-        Ok(headers)
+        Ok((prepared, reservation))
+    }
+
+    /// Pays for `resource_url` with `payment_required`, sending `retry_req` again with the
+    /// payment, and paying again with another option on failure, up to
+    /// [`X402Client::with_max_attempts`].
+    ///
+    /// `unbuffered` is the buffer limit of a streaming body too large to be buffered, which
+    /// cannot be sent again.
+    #[allow(clippy::too_many_arguments)]
+    async fn pay_and_retry(
+        &self,
+        mut retry_req: Option<Request>,
+        unbuffered: Option<usize>,
+        resource_url: Url,
+        payment_id: PaymentId,
+        mut payment_required: proto::PaymentRequired,
+        signing_options: SigningOptions,
+        next: rqm::Next<'_>,
+        extensions: &mut Extensions,
+    ) -> rqm::Result<Response> {
+        let payment_id_header =
+            HeaderValue::from_str(payment_id.as_str()).expect("payment ID is a valid header");
+        let mut failed = Vec::new();
+        // The requirements paid for so far, to tell re-challenges from loops
+        let mut challenges = vec![payment_required.clone()];
+        loop {
+            // The request to retry with payment, taken before signing so that no payment
+            // is made for a request that cannot be sent again
+            let mut retry = match retry_req.as_ref().and_then(Request::try_clone) {
+                Some(retry) => retry,
+                // A probed streaming body is sent once, as is
+                None => match retry_req.take() {
+                    Some(retry) => retry,
+                    None => {
+                        let error = match unbuffered {
+                            Some(limit) => X402Error::BodyTooLarge { limit },
+                            None => X402Error::RequestNotCloneable,
+                        };
+                        return Err(self.fail(&resource_url, Some(&payment_id), error));
+                    }
+                },
+            };
+            let (prepared, reservation) = match self
+                .pay(
+                    resource_url.clone(),
+                    Some(&payment_id),
+                    &payment_required,
+                    &signing_options,
+                    &failed,
+                )
+                .await
+            {
+                Ok(payment) => payment,
+                // No option is left to retry with, the last failure was published
+                Err(X402Error::NoMatchingPaymentOption) if !failed.is_empty() => {
+                    return Err(rqm::Error::Middleware(
+                        X402Error::PaymentFailed(failed).into(),
+                    ));
+                }
+                Err(e) => return Err(self.fail(&resource_url, Some(&payment_id), e)),
+            };
+
+            let sent_payment_header = prepared
+                .header_value
+                .to_str()
+                .ok()
+                .map(|header| SentPaymentHeader(header.to_owned()));
+            let payment = prepared.payment;
+            retry
+                .headers_mut()
+                .insert(prepared.header_name, prepared.header_value);
+            retry
+                .headers_mut()
+                .insert(PaymentId::HEADER_NAME, payment_id_header.clone());
+
+            #[cfg(feature = "telemetry")]
+            trace!(url = ?retry.url(), %payment_id, "Retrying request with payment headers");
+
+            #[cfg(feature = "events")]
+            self.publish(
+                &resource_url,
+                Some(&payment_id),
+                PaymentEventKind::RetrySent {
+                    payment: payment.clone(),
+                    attempt: failed.len() + 1,
+                },
+            );
+
+            let res = run_next(next.clone(), retry, extensions).await;
+            // A paid request that was not sent gives its payment back to the budget
+            let not_sent =
+                matches!(&res, Err(rqm::Error::Reqwest(e)) if e.is_connect() || e.is_builder());
+            if let Some(reservation) = reservation.filter(|_| !not_sent) {
+                reservation.commit();
+            }
+            #[cfg(feature = "events")]
+            if let Err(e) = &res {
+                self.publish(
+                    &resource_url,
+                    Some(&payment_id),
+                    PaymentEventKind::PaymentFailed {
+                        payment: Some(payment.clone()),
+                        reason: e.to_string(),
+                    },
+                );
+            }
+            let mut res = res?;
+            let payment_response = match decode_payment_response(&res) {
+                Some(Ok(payment_response)) => {
+                    #[cfg(feature = "telemetry")]
+                    debug!(
+                        success = payment_response.success,
+                        transaction = %payment_response.transaction,
+                        "Received payment response"
+                    );
+                    Some(payment_response)
+                }
+                Some(Err(_e)) => {
+                    #[cfg(feature = "telemetry")]
+                    warn!(error = %_e, %payment_id, "Ignoring malformed payment response header");
+                    None
+                }
+                None => None,
+            };
+
+            let rejected = res.status() == StatusCode::PAYMENT_REQUIRED;
+            let unsettled = payment_response.as_ref().is_some_and(|p| !p.success);
+            // A rejected payment comes with the requirements to pay again with
+            let mut next_required = None;
+            if rejected {
+                let (challenge, requirements) = read_challenge(res).await?;
+                res = challenge;
+                next_required = requirements;
+            }
+
+            // New requirements are paid again, requirements seen before make a loop
+            if let Some(next_required) = next_required.take_if(|next_required| {
+                challenges.len() <= self.max_challenges
+                    && !challenges
+                        .iter()
+                        .any(|seen| same_requirements(seen, next_required))
+            }) {
+                #[cfg(feature = "telemetry")]
+                info!(%payment_id, challenge = challenges.len() + 1, "Payment re-challenged");
+                #[cfg(feature = "events")]
+                {
+                    self.publish(
+                        &resource_url,
+                        Some(&payment_id),
+                        PaymentEventKind::Rechallenged {
+                            payment: payment.clone(),
+                            challenge: challenges.len() + 1,
+                            reason: payment_required_error(&next_required),
+                        },
+                    );
+                    self.publish(
+                        &resource_url,
+                        Some(&payment_id),
+                        payment_required_event(&next_required),
+                    );
+                }
+                challenges.push(next_required.clone());
+                payment_required = next_required;
+                continue;
+            }
+
+            if self.max_attempts > 1 && (rejected || unsettled) {
+                let mut reason = payment_response.and_then(|p| p.error_reason);
+                let next_required = match next_required {
+                    Some(next_required) => Some(next_required),
+                    None if rejected => parse_payment_required(res).await,
+                    None => None,
+                };
+                if let Some(next_required) = next_required {
+                    reason = reason.or_else(|| payment_required_error(&next_required));
+                    payment_required = next_required;
+                }
+                let reason = reason.unwrap_or_else(|| "payment not settled".to_string());
+                #[cfg(feature = "events")]
+                self.publish(
+                    &resource_url,
+                    Some(&payment_id),
+                    PaymentEventKind::PaymentFailed {
+                        payment: Some(payment.clone()),
+                        reason: reason.clone(),
+                    },
+                );
+                let failure = failed_payment(payment, reason);
+                #[cfg(feature = "telemetry")]
+                warn!(%failure, %payment_id, "Payment failed");
+                failed.push(failure);
+                if failed.len() >= self.max_attempts {
+                    return Err(rqm::Error::Middleware(
+                        X402Error::PaymentFailed(failed).into(),
+                    ));
+                }
+                continue;
+            }
+
+            #[cfg(feature = "events")]
+            self.publish(
+                &resource_url,
+                Some(&payment_id),
+                payment_outcome_event(&payment, rejected, payment_response.as_ref()),
+            );
+            if let Some(payment_response) = payment_response {
+                if let Some(receipt) = PaymentReceipt::new(payment, &payment_response) {
+                    if let Some(on_payment) = &self.on_payment {
+                        on_payment(receipt.clone());
+                    }
+                    res.extensions_mut().insert(receipt);
+                }
+                res.extensions_mut().insert(payment_response);
+            }
+            if let Some(sent_payment_header) = sent_payment_header {
+                res.extensions_mut().insert(sent_payment_header);
+            }
+            res.extensions_mut().insert(payment_id);
+            return Ok(res);
+        }
+    }
+}
+
+/// Signs the selected payment candidate.
+///
+/// With the `non-blocking-sign` feature, [`SignerKind::Sync`] signers are driven on a
+/// blocking thread with [`tokio::task::spawn_blocking`], so that CPU-bound signing does
+/// not stall the executor under many concurrent payments. Other signers are awaited in place,
+/// as are all signers on `wasm32`, which has no blocking threads.
+async fn sign_candidate(candidate: PaymentCandidate) -> Result<String, X402Error> {
+    #[cfg(all(feature = "non-blocking-sign", not(target_arch = "wasm32")))]
+    if candidate.signer_kind() == SignerKind::Sync {
+        let handle = tokio::runtime::Handle::current();
+        return tokio::task::spawn_blocking(move || handle.block_on(candidate.sign()))
+            .await
+            .map_err(|e| X402Error::SigningError(e.to_string()))?;
+    }
+    candidate.sign().await
+}
+
+/// The `maxTimeoutSeconds` of the requirements `candidate` was made from, if found.
+fn max_timeout_seconds(
+    payment_required: &proto::PaymentRequired,
+    candidate: &PaymentCandidate,
+) -> Option<u64> {
+    let matches = |scheme: &str, chain_id: Option<ChainId>, asset: &str, pay_to: &str| {
+        scheme == candidate.scheme
+            && chain_id.as_ref() == Some(&candidate.chain_id)
+            && asset.eq_ignore_ascii_case(&candidate.asset)
+            && pay_to.eq_ignore_ascii_case(&candidate.pay_to)
+    };
+    match payment_required {
+        proto::PaymentRequired::V1(payment_required) => payment_required
+            .accepts
+            .iter()
+            .filter_map(|accepts| accepts.try_into().ok())
+            .find(|r: &v1::PaymentRequirements| {
+                let chain_id = ChainId::from_network_name(&r.network);
+                matches(&r.scheme, chain_id, &r.asset, &r.pay_to)
+            })
+            .map(|r| r.max_timeout_seconds),
+        proto::PaymentRequired::V2(payment_required) => payment_required
+            .accepts
+            .iter()
+            .filter_map(|accepts| accepts.try_into().ok())
+            .find(|r: &v2::PaymentRequirements| {
+                matches(&r.scheme, Some(r.network.clone()), &r.asset, &r.pay_to)
+            })
+            .map(|r| r.max_timeout_seconds),
+    }
+}
+
+/// Re-encodes a base64 payment payload in the URL-safe alphabet.
+///
+/// Payloads that are not valid base64 are returned unchanged.
+fn to_url_safe(payload: String) -> String {
+    match Base64Bytes::from(payload.as_bytes()).auto_decode() {
+        Ok(bytes) => Base64Bytes::encode_url_safe(bytes).to_string(),
+        Err(_) => payload,
     }
 }
 
@@ -204,9 +977,19 @@ impl ClientSchemes {
 
     /// Finds all payment candidates that can handle the given payment requirements.
     pub fn candidates(&self, payment_required: &proto::PaymentRequired) -> Vec<PaymentCandidate> {
+        self.candidates_with_options(payment_required, &SigningOptions::default())
+    }
+
+    /// Finds all payment candidates that can handle the given payment requirements,
+    /// to be signed with the given options.
+    pub fn candidates_with_options(
+        &self,
+        payment_required: &proto::PaymentRequired,
+        options: &SigningOptions,
+    ) -> Vec<PaymentCandidate> {
         let mut candidates = vec![];
         for client in self.0.iter() {
-            let accepted = client.accept(payment_required);
+            let accepted = client.accept_with_options(payment_required, options);
             candidates.extend(accepted);
         }
         candidates
@@ -226,7 +1009,8 @@ async fn run_next(
     next.run(req, extensions).await
 }
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<TSelector> rqm::Middleware for X402Client<TSelector>
 where
     TSelector: PaymentSelector + Send + Sync + 'static,
@@ -236,7 +1020,14 @@ where
     /// When a 402 response is received, this middleware:
     /// 1. Extracts payment requirements from the response
     /// 2. Signs a payment using registered scheme clients
-    /// 3. Retries the request with the payment header
+    /// 3. Retries the request with the payment header and an `X-Payment-Id`
+    ///    correlation ID, reusing the one set on the request if any
+    /// 4. With [`X402Client::with_max_attempts`], pays again with another option if the
+    ///    payment fails
+    ///
+    /// Streaming bodies are made replayable beforehand, see [`X402Client::with_body_replay`].
+    /// With the `coalescing` feature, concurrent requests to the same resource may wait for
+    /// each other first, see `X402Client::with_coalescing`.
     #[cfg_attr(
         feature = "telemetry",
         instrument(name = "x402.reqwest.handle", skip_all, err)
@@ -247,8 +1038,76 @@ where
         extensions: &mut Extensions,
         next: rqm::Next<'_>,
     ) -> rqm::Result<Response> {
-        let retry_req = req.try_clone();
-        let res = run_next(next.clone(), req, extensions).await?;
+        let mut req = req;
+        #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+        let mut leader = None;
+        #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+        if let Some(coalescer) = &self.coalescer
+            && Coalescer::coalesces(&req)
+        {
+            match coalescer.join(&req).await {
+                Role::Leader(joined) => leader = Some(joined),
+                Role::Follower(Outcome::Paid {
+                    response: Some(response),
+                    ..
+                }) => {
+                    #[cfg(feature = "telemetry")]
+                    trace!(url = ?req.url(), "Reusing the response paid for by a concurrent request");
+                    return Ok(response.to_response());
+                }
+                Role::Follower(Outcome::Paid {
+                    resource_url,
+                    payment_required,
+                    response: None,
+                }) => {
+                    #[cfg(feature = "telemetry")]
+                    trace!(url = ?req.url(), "Paying with the requirements of a concurrent request");
+                    let payment_id = request_payment_id(Some(&req));
+                    // Signed on the local clock, the leader's 402 response is not shared
+                    return self
+                        .pay_and_retry(
+                            Some(req),
+                            None,
+                            resource_url,
+                            payment_id,
+                            (*payment_required).clone(),
+                            self.signing_options,
+                            next,
+                            extensions,
+                        )
+                        .await;
+                }
+                Role::Follower(Outcome::Unpaid) => {}
+            }
+        }
+        // The buffer limit of a streaming body too large to be buffered
+        let mut unbuffered = None;
+        let mut probing = false;
+        if replay::is_streaming(&req) {
+            match self.body_replay {
+                BodyReplay::Buffer { limit } => {
+                    if !replay::buffer(&mut req, limit).await? {
+                        unbuffered = Some(limit);
+                    }
+                }
+                BodyReplay::Probe => probing = true,
+            }
+        }
+        let mut retry_req = req.try_clone();
+        let res = if probing {
+            let res = run_next(next.clone(), replay::probe(&req), extensions).await?;
+            if res.status() == StatusCode::PAYMENT_REQUIRED {
+                // The body is sent once, with the payment
+                retry_req = Some(req);
+                res
+            } else {
+                #[cfg(feature = "telemetry")]
+                trace!(status = ?res.status(), "No payment required by probe, sending request");
+                run_next(next.clone(), req, extensions).await?
+            }
+        } else {
+            run_next(next.clone(), req, extensions).await?
+        };
 
         if res.status() != StatusCode::PAYMENT_REQUIRED {
             #[cfg(feature = "telemetry")]
@@ -259,24 +1118,213 @@ where
         #[cfg(feature = "telemetry")]
         info!(url = ?res.url(), "Received 402 Payment Required, processing payment");
 
-        let headers = self
-            .make_payment_headers(res)
-            .await
-            .map_err(|e| rqm::Error::Middleware(e.into()))?;
+        let resource_url = res.url().clone();
+        // Correlate this payment cycle across client, seller and facilitator
+        let payment_id = request_payment_id(retry_req.as_ref());
+        let signing_options = self.signing_options_for(&res);
 
-        // Retry with payment
-        let mut retry = retry_req.ok_or(rqm::Error::Middleware(
-            X402Error::RequestNotCloneable.into(),
-        ))?;
-        retry.headers_mut().extend(headers);
+        let payment_required = match parse_payment_required(res).await {
+            Some(payment_required) => payment_required,
+            None => {
+                let error = X402Error::ParseError("Invalid 402 response".to_string());
+                return Err(self.fail(&resource_url, Some(&payment_id), error));
+            }
+        };
+        #[cfg(feature = "events")]
+        self.publish(
+            &resource_url,
+            Some(&payment_id),
+            payment_required_event(&payment_required),
+        );
 
-        #[cfg(feature = "telemetry")]
-        trace!(url = ?retry.url(), "Retrying request with payment headers");
+        #[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+        if let Some(leader) = leader {
+            let shared = payment_required.clone();
+            let res = self
+                .pay_and_retry(
+                    retry_req,
+                    unbuffered,
+                    resource_url.clone(),
+                    payment_id,
+                    payment_required,
+                    signing_options,
+                    next,
+                    extensions,
+                )
+                .await;
+            return leader.share(resource_url, shared, res).await;
+        }
+        self.pay_and_retry(
+            retry_req,
+            unbuffered,
+            resource_url,
+            payment_id,
+            payment_required,
+            signing_options,
+            next,
+            extensions,
+        )
+        .await
+    }
+}
+
+/// The time of the `Date` header in `headers`, if valid.
+fn server_date(headers: &HeaderMap) -> Option<UnixTimestamp> {
+    let date = headers.get(http::header::DATE)?.to_str().ok()?;
+    let date = httpdate::parse_http_date(date).ok()?;
+    let secs = date.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs();
+    Some(UnixTimestamp::from_secs(secs))
+}
 
-        run_next(next, retry, extensions).await
+/// The `X-Payment-Id` set on `req`, or a new random one.
+fn request_payment_id(req: Option<&Request>) -> PaymentId {
+    req.and_then(|req| req.headers().get(PaymentId::HEADER_NAME))
+        .and_then(|header| PaymentId::from_header(header.as_bytes()))
+        .unwrap_or_else(|| {
+            Uuid::new_v4()
+                .to_string()
+                .parse()
+                .expect("UUID is a valid payment ID")
+        })
+}
+
+/// Describes `payment`, which failed for `reason`.
+fn failed_payment(payment: PendingPayment, reason: String) -> FailedPayment {
+    FailedPayment {
+        chain_id: payment.chain_id,
+        scheme: payment.scheme,
+        asset: payment.asset,
+        amount: payment.amount,
+        pay_to: payment.pay_to,
+        reason,
     }
 }
 
+/// The event of the server answering with `payment_required`.
+#[cfg(feature = "events")]
+fn payment_required_event(payment_required: &proto::PaymentRequired) -> PaymentEventKind {
+    let (x402_version, options) = match payment_required {
+        proto::PaymentRequired::V1(payment_required) => (1, payment_required.accepts.len()),
+        proto::PaymentRequired::V2(payment_required) => (2, payment_required.accepts.len()),
+    };
+    PaymentEventKind::PaymentRequired {
+        x402_version,
+        options,
+    }
+}
+
+/// The event of the server answering `payment`, with another 402 if `rejected`, and the
+/// settlement result in `payment_response`, if any.
+#[cfg(feature = "events")]
+fn payment_outcome_event(
+    payment: &PendingPayment,
+    rejected: bool,
+    payment_response: Option<&proto::PaymentResponse>,
+) -> PaymentEventKind {
+    let unsettled = payment_response.is_some_and(|p| !p.success);
+    if rejected || unsettled {
+        let reason = payment_response
+            .and_then(|p| p.error_reason.clone())
+            .unwrap_or_else(|| "payment not settled".to_string());
+        PaymentEventKind::PaymentFailed {
+            payment: Some(payment.clone()),
+            reason,
+        }
+    } else {
+        PaymentEventKind::PaymentSucceeded {
+            payment: payment.clone(),
+            transaction: payment_response
+                .map(|p| p.transaction.clone())
+                .filter(|transaction| !transaction.is_empty()),
+        }
+    }
+}
+
+/// Returns the error a server gave along with its payment requirements, if any.
+fn payment_required_error(payment_required: &proto::PaymentRequired) -> Option<String> {
+    match payment_required {
+        proto::PaymentRequired::V1(payment_required) => payment_required.error.clone(),
+        proto::PaymentRequired::V2(payment_required) => payment_required.error.clone(),
+    }
+}
+
+/// Exposes x402 payment metadata stored on a [`Response`] by the [`X402Client`] middleware.
+pub trait ResponseExt {
+    /// The `X-Payment-Id` correlation ID of the payment made for this response.
+    ///
+    /// Returns `None` if no payment was made.
+    fn payment_id(&self) -> Option<&str>;
+
+    /// The settlement result of the payment made for this response, if any.
+    fn payment_response(&self) -> Option<&proto::PaymentResponse>;
+
+    /// The receipt of the payment made for this response, if it was settled.
+    fn payment_receipt(&self) -> Option<&PaymentReceipt>;
+
+    /// The `Payment-Signature` (V2) or `X-Payment` (V1) header value sent to pay for this
+    /// response, e.g. to audit the exact payment made.
+    ///
+    /// Returns `None` if no payment was made.
+    fn sent_payment_header(&self) -> Option<&str>;
+}
+
+/// The payment header value sent with a paid request, stored in the response extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentPaymentHeader(pub String);
+
+impl ResponseExt for Response {
+    fn payment_id(&self) -> Option<&str> {
+        self.extensions().get::<PaymentId>().map(PaymentId::as_str)
+    }
+
+    fn payment_response(&self) -> Option<&proto::PaymentResponse> {
+        self.extensions().get::<proto::PaymentResponse>()
+    }
+
+    fn payment_receipt(&self) -> Option<&PaymentReceipt> {
+        self.extensions().get::<PaymentReceipt>()
+    }
+
+    fn sent_payment_header(&self) -> Option<&str> {
+        self.extensions()
+            .get::<SentPaymentHeader>()
+            .map(|header| header.0.as_str())
+    }
+}
+
+/// Reads the settlement result from a paid response.
+///
+/// Accepts both the V2 `Payment-Response` and the V1 `X-Payment-Response` header.
+/// Returns `None` if neither is present or the header cannot be decoded.
+///
+/// The middleware also stores the decoded value in the response extensions, so it
+/// can be read with `response.extensions().get::<proto::PaymentResponse>()`.
+pub fn parse_payment_response(response: &Response) -> Option<proto::PaymentResponse> {
+    decode_payment_response(response)?.ok()
+}
+
+/// Decodes the settlement result of a paid response, as [`parse_payment_response`], keeping
+/// the decoding error if no header could be decoded.
+fn decode_payment_response(
+    response: &Response,
+) -> Option<Result<proto::PaymentResponse, proto::PaymentResponseDecodeError>> {
+    let headers = response.headers();
+    let mut error = None;
+    for header in [
+        proto::PaymentResponse::V2_HEADER_NAME,
+        proto::PaymentResponse::V1_HEADER_NAME,
+    ]
+    .into_iter()
+    .filter_map(|name| headers.get(name))
+    {
+        match proto::PaymentResponse::from_header(header.as_bytes()) {
+            Ok(payment_response) => return Some(Ok(payment_response)),
+            Err(e) => error = Some(e),
+        }
+    }
+    error.map(Err)
+}
+
 /// Parses a 402 Payment Required response into a [`proto::PaymentRequired`].
 ///
 /// Supports both V1 (JSON body) and V2 (base64-encoded header) formats.
@@ -286,31 +1334,100 @@ where
 )]
 pub async fn parse_payment_required(response: Response) -> Option<proto::PaymentRequired> {
     // Try V2 format first (header-based)
-    let headers = response.headers();
-    let v2_payment_required = headers
-        .get("Payment-Required")
-        .and_then(|h| Base64Bytes::from(h.as_bytes()).decode().ok())
-        .and_then(|b| serde_json::from_slice::<v2::PaymentRequired<OriginalJson>>(&b).ok());
-    if let Some(v2_payment_required) = v2_payment_required {
-        #[cfg(feature = "telemetry")]
-        debug!("Parsed V2 payment required from header");
-        return Some(proto::PaymentRequired::V2(v2_payment_required));
+    if let Some(payment_required) = payment_required_from_header(response.headers()) {
+        return Some(payment_required);
     }
 
     // Fall back to V1 format (body-based)
-    let v1_payment_required = response
-        .bytes()
-        .await
-        .ok()
-        .and_then(|b| serde_json::from_slice::<v1::PaymentRequired<OriginalJson>>(&b).ok());
-    if let Some(v1_payment_required) = v1_payment_required {
-        #[cfg(feature = "telemetry")]
-        debug!("Parsed V1 payment required from body");
-        return Some(proto::PaymentRequired::V1(v1_payment_required));
+    let body = response.bytes().await.ok().unwrap_or_default();
+    let payment_required = payment_required_from_body(&body);
+
+    #[cfg(feature = "telemetry")]
+    if payment_required.is_none() {
+        debug!("Could not parse payment required from response");
+    }
+
+    payment_required
+}
+
+/// Reads the requirements a paid request was answered with in the 402 `res`, along with the
+/// response to hand on should they not be paid.
+///
+/// V1 requirements are read from the body, which is buffered for the response handed on.
+/// On `wasm32`, where responses cannot be rebuilt, only the V2 header is read.
+async fn read_challenge(
+    res: Response,
+) -> reqwest::Result<(Response, Option<proto::PaymentRequired>)> {
+    if let Some(payment_required) = payment_required_from_header(res.headers()) {
+        return Ok((res, Some(payment_required)));
+    }
+    #[cfg(target_arch = "wasm32")]
+    return Ok((res, None));
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use reqwest::ResponseBuilderExt;
+
+        let status = res.status();
+        let version = res.version();
+        let headers = res.headers().clone();
+        let url = res.url().clone();
+        let body = res.bytes().await?;
+        let payment_required = payment_required_from_body(&body);
+        let mut res = http::Response::builder()
+            .status(status)
+            .version(version)
+            .url(url)
+            .body(body)
+            .expect("buffered response is valid");
+        *res.headers_mut() = headers;
+        Ok((Response::from(res), payment_required))
     }
+}
+
+/// Whether `a` and `b` offer the same payment options, to tell a server challenging with
+/// new requirements from one answering every payment with the same 402.
+fn same_requirements(a: &proto::PaymentRequired, b: &proto::PaymentRequired) -> bool {
+    fn options(accepts: &[OriginalJson]) -> Vec<serde_json::Value> {
+        accepts
+            .iter()
+            .map(|accepts| serde_json::from_str(accepts.0.get()).unwrap_or_default())
+            .collect()
+    }
+    match (a, b) {
+        (proto::PaymentRequired::V1(a), proto::PaymentRequired::V1(b)) => {
+            options(&a.accepts) == options(&b.accepts)
+        }
+        (proto::PaymentRequired::V2(a), proto::PaymentRequired::V2(b)) => {
+            options(&a.accepts) == options(&b.accepts)
+        }
+        _ => false,
+    }
+}
 
+/// Decodes the requirements of a 402 Payment Required response, from its `headers` and
+/// `body`, as [`parse_payment_required`], for responses received by other HTTP stacks.
+///
+/// See the [`prepared`](crate::prepared) module.
+pub fn decode_payment_required(headers: &HeaderMap, body: &[u8]) -> Option<proto::PaymentRequired> {
+    payment_required_from_header(headers).or_else(|| payment_required_from_body(body))
+}
+
+/// Decodes V2 requirements from the base64 `Payment-Required` header.
+fn payment_required_from_header(headers: &HeaderMap) -> Option<proto::PaymentRequired> {
+    let v2_payment_required = headers
+        .get("Payment-Required")
+        .and_then(|h| Base64Bytes::from(h.as_bytes()).decode().ok())
+        .and_then(|b| serde_json::from_slice::<v2::PaymentRequired<OriginalJson>>(&b).ok())?;
     #[cfg(feature = "telemetry")]
-    debug!("Could not parse payment required from response");
+    debug!("Parsed V2 payment required from header");
+    Some(proto::PaymentRequired::V2(v2_payment_required))
+}
 
-    None
+/// Decodes V1 requirements from a JSON body.
+fn payment_required_from_body(body: &[u8]) -> Option<proto::PaymentRequired> {
+    let v1_payment_required =
+        serde_json::from_slice::<v1::PaymentRequired<OriginalJson>>(body).ok()?;
+    #[cfg(feature = "telemetry")]
+    debug!("Parsed V1 payment required from body");
+    Some(proto::PaymentRequired::V1(v1_payment_required))
 }
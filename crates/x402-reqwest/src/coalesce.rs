@@ -0,0 +1,324 @@
+//! Coalescing concurrent payments for the same resource.
+//!
+//! Without coalescing, `N` concurrent requests to the same paid resource each fetch the
+//! `402 Payment Required` challenge, and each pay for it. With
+//! [`X402Client::with_coalescing`](crate::X402Client::with_coalescing), concurrent `GET` and
+//! `HEAD` requests to the same URL are grouped: the first one, the leader, is sent as usual,
+//! and the others, the followers, wait for its outcome instead of being sent:
+//!
+//! - If the leader was paid for and its response is cacheable, followers get a copy of that
+//!   response, and pay nothing. A response is cacheable if it is successful, its payment
+//!   did not fail, its `Cache-Control` header allows shared caches to store it, with
+//!   `public`, `max-age` or `s-maxage`, and its `Content-Length` is at most
+//!   [`Coalescing::max_shared_body`].
+//! - If the leader was paid for but its response is not cacheable, followers skip the
+//!   challenge and pay with the requirements fetched by the leader. Each follower signs its
+//!   own payment, with its own `X-Payment-Id`: a signed authorization is never sent with
+//!   more than one request.
+//! - If the leader needed no payment, or failed before getting the requirements, followers
+//!   are sent as usual.
+//!
+//! Followers wait for the whole paid request of the leader, so coalescing pays off for
+//! resources fetched concurrently many times, e.g. a price feed polled by many tasks.
+//!
+//! Responses shared with followers carry no payment metadata: their
+//! [`ResponseExt`](crate::ResponseExt) accessors return `None`, as no payment was made for
+//! them. Followers paying with the leader's requirements publish no `PaymentRequired`
+//! [event](crate::events), as they got no challenge.
+//!
+//! Only available with the `coalescing` feature, and not on `wasm32`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::X402Client;
+//! use x402_reqwest::coalesce::Coalescing;
+//!
+//! let client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_coalescing(Coalescing::default());
+//! ```
+
+use bytes::Bytes;
+use http::{HeaderMap, Method, StatusCode, Version, header};
+use reqwest::{Request, Response, ResponseBuilderExt, Url};
+use reqwest_middleware as rqm;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use x402_types::proto;
+
+use crate::ResponseExt;
+
+/// Settings of request coalescing, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coalescing {
+    /// Whether followers get a copy of a cacheable paid response, instead of paying.
+    pub share_responses: bool,
+    /// The largest response body shared with followers, in bytes.
+    pub max_shared_body: usize,
+}
+
+impl Coalescing {
+    /// Response bodies shared by default, 1 MiB.
+    pub const DEFAULT_MAX_SHARED_BODY: usize = 1024 * 1024;
+}
+
+impl Default for Coalescing {
+    fn default() -> Self {
+        Self {
+            share_responses: true,
+            max_shared_body: Self::DEFAULT_MAX_SHARED_BODY,
+        }
+    }
+}
+
+/// Requests grouped by method and URL.
+type Key = (Method, Url);
+
+/// Tracks the requests in flight, for followers to wait on.
+#[derive(Clone)]
+pub(crate) struct Coalescer {
+    coalescing: Coalescing,
+    in_flight: Arc<Mutex<HashMap<Key, watch::Receiver<Option<Outcome>>>>>,
+}
+
+/// What a leader left its followers with.
+#[derive(Clone)]
+pub(crate) enum Outcome {
+    /// No requirements were fetched, followers are sent as usual.
+    Unpaid,
+    /// The leader got the requirements, and paid with them.
+    Paid {
+        /// The URL of the paid resource, after redirects.
+        resource_url: Url,
+        /// The requirements the leader was challenged with.
+        payment_required: Arc<proto::PaymentRequired>,
+        /// The paid response, if cacheable.
+        response: Option<Arc<SharedResponse>>,
+    },
+}
+
+/// The part a request plays in its group.
+pub(crate) enum Role {
+    /// The request is sent, and its outcome given to followers.
+    Leader(Leader),
+    /// The request waited for the outcome of a leader.
+    Follower(Outcome),
+}
+
+impl Coalescer {
+    pub(crate) fn new(coalescing: Coalescing) -> Self {
+        Self {
+            coalescing,
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Whether `req` can be grouped with others: a `GET` or `HEAD` request, which can be
+    /// sent again.
+    pub(crate) fn coalesces(req: &Request) -> bool {
+        matches!(*req.method(), Method::GET | Method::HEAD) && req.try_clone().is_some()
+    }
+
+    /// Makes `req` the leader of its group, or waits for the outcome of the current leader.
+    ///
+    /// A leader dropped before giving an outcome leaves its followers [`Outcome::Unpaid`].
+    pub(crate) async fn join(&self, req: &Request) -> Role {
+        let key = (req.method().clone(), req.url().clone());
+        let mut outcome = {
+            let mut in_flight = self.in_flight.lock().expect("in-flight requests poisoned");
+            match in_flight.get(&key) {
+                Some(outcome) => outcome.clone(),
+                None => {
+                    let (sender, outcome) = watch::channel(None);
+                    in_flight.insert(key.clone(), outcome);
+                    return Role::Leader(Leader {
+                        key,
+                        sender: Some(sender),
+                        coalescer: self.clone(),
+                    });
+                }
+            }
+        };
+        match outcome.wait_for(Option::is_some).await {
+            Ok(outcome) => Role::Follower(outcome.clone().unwrap_or(Outcome::Unpaid)),
+            Err(_) => Role::Follower(Outcome::Unpaid),
+        }
+    }
+}
+
+/// The request sent on behalf of its group.
+pub(crate) struct Leader {
+    key: Key,
+    sender: Option<watch::Sender<Option<Outcome>>>,
+    coalescer: Coalescer,
+}
+
+impl Leader {
+    /// Gives the outcome of the paid request `res` to the followers, sharing the response if
+    /// it is cacheable, and returns it.
+    ///
+    /// The followers pay with `payment_required` if the request failed.
+    pub(crate) async fn share(
+        mut self,
+        resource_url: Url,
+        payment_required: proto::PaymentRequired,
+        res: rqm::Result<Response>,
+    ) -> rqm::Result<Response> {
+        let coalescing = self.coalescer.coalescing;
+        let (res, response) = match res {
+            Ok(mut res) if coalescing.share_responses && is_shareable(&res, coalescing) => {
+                let extensions = std::mem::take(res.extensions_mut());
+                match SharedResponse::read(res).await {
+                    Ok(shared) => {
+                        let mut res = shared.to_response();
+                        *res.extensions_mut() = extensions;
+                        (Ok(res), Some(Arc::new(shared)))
+                    }
+                    Err(e) => (Err(rqm::Error::Reqwest(e)), None),
+                }
+            }
+            res => (res, None),
+        };
+        self.publish(Outcome::Paid {
+            resource_url,
+            payment_required: Arc::new(payment_required),
+            response,
+        });
+        res
+    }
+
+    /// Leaves the group, then gives `outcome` to its followers.
+    fn publish(&mut self, outcome: Outcome) {
+        if let Some(sender) = self.sender.take() {
+            self.leave();
+            sender.send_replace(Some(outcome));
+        }
+    }
+
+    /// Removes the group, for the next request to lead a new one.
+    fn leave(&self) {
+        self.coalescer
+            .in_flight
+            .lock()
+            .expect("in-flight requests poisoned")
+            .remove(&self.key);
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        if self.sender.is_some() {
+            self.leave();
+        }
+    }
+}
+
+/// A paid response, held in memory to be given to followers.
+pub(crate) struct SharedResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    url: Url,
+    body: Bytes,
+}
+
+impl SharedResponse {
+    /// Reads the body of `res` into memory.
+    async fn read(res: Response) -> reqwest::Result<Self> {
+        let status = res.status();
+        let version = res.version();
+        let headers = res.headers().clone();
+        let url = res.url().clone();
+        let body = res.bytes().await?;
+        Ok(Self {
+            status,
+            version,
+            headers,
+            url,
+            body,
+        })
+    }
+
+    /// A copy of the response, without extensions.
+    pub(crate) fn to_response(&self) -> Response {
+        let mut response = http::Response::builder()
+            .status(self.status)
+            .version(self.version)
+            .url(self.url.clone())
+            .body(self.body.clone())
+            .expect("shared response is valid");
+        *response.headers_mut() = self.headers.clone();
+        Response::from(response)
+    }
+}
+
+/// Whether `res` is a successful paid response that shared caches may store, with a body
+/// small enough to be shared.
+fn is_shareable(res: &Response, coalescing: Coalescing) -> bool {
+    let settled = res.payment_response().is_none_or(|p| p.success);
+    let small = res
+        .content_length()
+        .is_some_and(|length| length <= coalescing.max_shared_body as u64);
+    res.status().is_success() && settled && small && is_cacheable(res.headers())
+}
+
+/// Whether the `Cache-Control` header allows shared caches to store the response.
+fn is_cacheable(headers: &HeaderMap) -> bool {
+    let directives: Vec<String> = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .collect();
+    let denied = directives.iter().any(|directive| {
+        matches!(directive.as_str(), "no-store" | "no-cache" | "private")
+            || directive.starts_with("no-cache=")
+            || directive.starts_with("private=")
+    });
+    let allowed = directives.iter().any(|directive| {
+        directive == "public"
+            || ["max-age=", "s-maxage="].iter().any(|prefix| {
+                directive
+                    .strip_prefix(prefix)
+                    .and_then(|seconds| seconds.trim_matches('"').parse::<u64>().ok())
+                    .is_some_and(|seconds| seconds > 0)
+            })
+    });
+    allowed && !denied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_control(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(header::CACHE_CONTROL, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn cacheable_responses() {
+        assert!(is_cacheable(&cache_control(&["public"])));
+        assert!(is_cacheable(&cache_control(&["max-age=60"])));
+        assert!(is_cacheable(&cache_control(&["Public, S-MaxAge=\"30\""])));
+        assert!(is_cacheable(&cache_control(&["no-transform", "max-age=5"])));
+    }
+
+    #[test]
+    fn uncacheable_responses() {
+        assert!(!is_cacheable(&cache_control(&[])));
+        assert!(!is_cacheable(&cache_control(&["max-age=0"])));
+        assert!(!is_cacheable(&cache_control(&["public, no-store"])));
+        assert!(!is_cacheable(&cache_control(&["max-age=60", "private"])));
+        assert!(!is_cacheable(&cache_control(&[
+            "public, no-cache=\"Set-Cookie\""
+        ])));
+        assert!(!is_cacheable(&cache_control(&["max-age=soon"])));
+    }
+}
@@ -0,0 +1,174 @@
+//! Structured events of the payment middleware.
+//!
+//! With the `events` feature, the [`X402Client`](crate::X402Client) publishes a
+//! [`PaymentEvent`] at each step of paying for a request, e.g. to feed metrics or an audit
+//! log. Subscribe with [`X402Client::events`](crate::X402Client::events) before handing the
+//! client to reqwest, or share a [`PaymentEvents`] channel between clients with
+//! [`X402Client::with_events`](crate::X402Client::with_events).
+//!
+//! Each event has a [`timestamp_ms`](PaymentEvent::timestamp_ms), the `url` of the paid
+//! resource, the [`payment_id`](PaymentEvent::payment_id) correlating the payment cycle, and
+//! a [`PaymentEventKind`]:
+//!
+//! | Kind                | Published when                                           |
+//! |---------------------|----------------------------------------------------------|
+//! | `PaymentRequired`   | The server answered with `402 Payment Required`          |
+//! | `CandidateSelected` | A payment option was selected, before approval           |
+//! | `PaymentSigned`     | The selected payment was approved and signed             |
+//! | `RetrySent`         | The request was sent again with the payment attached     |
+//! | `Rechallenged`      | The server answered the payment with new requirements    |
+//! | `PaymentSucceeded`  | The server accepted the payment                          |
+//! | `PaymentFailed`     | A payment, or the payment cycle, failed                  |
+//!
+//! A successful payment publishes `PaymentRequired`, `CandidateSelected`, `PaymentSigned`,
+//! `RetrySent` and `PaymentSucceeded`, in this order. A payment cycle ends with
+//! `PaymentSucceeded` or `PaymentFailed`. With
+//! [`X402Client::with_max_attempts`](crate::X402Client::with_max_attempts), a failed payment
+//! may be followed by another `CandidateSelected`. A re-challenge, see
+//! [`X402Client::with_max_challenges`](crate::X402Client::with_max_challenges), publishes
+//! `Rechallenged` and a `PaymentRequired` for the new requirements, followed by the events
+//! of paying them.
+//!
+//! Publishing never waits for subscribers: a subscriber falling more than the channel
+//! capacity behind misses the oldest events, and receives
+//! [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) instead.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::X402Client;
+//! use x402_reqwest::events::PaymentEventKind;
+//!
+//! let x402_client = X402Client::new().register(V2Eip155ExactClient::new(signer));
+//! let mut events = x402_client.events();
+//! tokio::spawn(async move {
+//!     while let Ok(event) = events.recv().await {
+//!         if let PaymentEventKind::PaymentFailed { reason, .. } = &event.kind {
+//!             eprintln!("Payment for {} failed: {reason}", event.url);
+//!         }
+//!     }
+//! });
+//! ```
+
+use reqwest::Url;
+use tokio::sync::broadcast;
+use web_time::{SystemTime, UNIX_EPOCH};
+use x402_types::proto::PaymentId;
+
+use crate::approval::PendingPayment;
+
+/// A step of paying for a request, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentEvent {
+    /// When the event occurred, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// The URL of the paid resource.
+    pub url: Url,
+    /// The `X-Payment-Id` correlation ID of the payment cycle, `None` for payments made with
+    /// [`X402Client::make_payment_headers`](crate::X402Client::make_payment_headers) or
+    /// [`X402Client::prepare_payment`](crate::X402Client::prepare_payment).
+    pub payment_id: Option<PaymentId>,
+    /// What happened.
+    pub kind: PaymentEventKind,
+}
+
+impl PaymentEvent {
+    /// An event of `kind` occurring now.
+    pub fn new(url: Url, payment_id: Option<PaymentId>, kind: PaymentEventKind) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self {
+            timestamp_ms,
+            url,
+            payment_id,
+            kind,
+        }
+    }
+}
+
+/// Kind of a [`PaymentEvent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentEventKind {
+    /// The server answered with `402 Payment Required`.
+    PaymentRequired {
+        /// The x402 protocol version of the payment requirements.
+        x402_version: u8,
+        /// Number of payment options offered by the server.
+        options: usize,
+    },
+    /// A payment option was selected, before approval.
+    CandidateSelected(PendingPayment),
+    /// The selected payment was approved and signed.
+    PaymentSigned(PendingPayment),
+    /// The request was sent again with the payment attached.
+    RetrySent {
+        /// The payment sent.
+        payment: PendingPayment,
+        /// The attempt this is, starting at 1, see
+        /// [`X402Client::with_max_attempts`](crate::X402Client::with_max_attempts).
+        attempt: usize,
+    },
+    /// The server answered the payment with a fresh 402 carrying new requirements, which
+    /// are paid again, see
+    /// [`X402Client::with_max_challenges`](crate::X402Client::with_max_challenges).
+    Rechallenged {
+        /// The payment the server answered.
+        payment: PendingPayment,
+        /// The challenge this is, 2 for the first re-challenge.
+        challenge: usize,
+        /// Why the server challenged again, if it said.
+        reason: Option<String>,
+    },
+    /// The server accepted the payment.
+    PaymentSucceeded {
+        /// The payment accepted.
+        payment: PendingPayment,
+        /// The settlement transaction, if the server reported one.
+        transaction: Option<String>,
+    },
+    /// A payment, or the payment cycle, failed.
+    PaymentFailed {
+        /// The payment that failed, `None` if the cycle failed before a payment was signed,
+        /// e.g. because the budget is exhausted.
+        payment: Option<PendingPayment>,
+        /// Why it failed.
+        reason: String,
+    },
+}
+
+/// Broadcast channel of [`PaymentEvent`]s.
+///
+/// Clones share the same channel.
+#[derive(Debug, Clone)]
+pub struct PaymentEvents {
+    sender: broadcast::Sender<PaymentEvent>,
+}
+
+impl PaymentEvents {
+    /// Default number of events buffered for each subscriber.
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    /// Creates a channel buffering up to `capacity` events for each subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Sends `event` to the current subscribers, if any, without waiting.
+    pub fn publish(&self, event: PaymentEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<PaymentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for PaymentEvents {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
@@ -0,0 +1,220 @@
+//! Selection of payments a facilitator can settle.
+//!
+//! A seller accepting several payment options may hand them to a facilitator that does not
+//! support all of them. Paying with such an option fails verification after a wasted round
+//! trip. [`X402Client::with_facilitator`](crate::X402Client::with_facilitator) takes a
+//! [`FacilitatorSupport`] for the facilitator of the sellers, which fetches its `/supported`
+//! payment kinds, and only options the facilitator supports are considered for selection:
+//!
+//! - The facilitator lists a kind with the same x402 version, scheme and network.
+//! - On Solana, where the facilitator pays the transaction fees, it also lists a fee payer
+//!   among its signers for the network.
+//!
+//! With [`FacilitatorSupport::with_capabilities`], the client sends its
+//! [`ClientCapabilities`] to `POST /supported` instead, and the facilitator only lists the
+//! payment kinds the client can pay with, e.g. leaving out chains where it has no balance.
+//! Facilitators without that endpoint are asked with `GET /supported`.
+//!
+//! The `/supported` response is cached for a [TTL](FacilitatorSupport::with_ttl). If
+//! fetching it fails, the last response is used, even past its TTL. Until one is fetched,
+//! options are not filtered.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::X402Client;
+//! use x402_reqwest::facilitator::FacilitatorSupport;
+//!
+//! let client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .register(V2SolanaExactClient::new(keypair, rpc))
+//!     .with_facilitator(FacilitatorSupport::try_new("https://facilitator.x402.rs".parse()?)?);
+//! ```
+
+use http::StatusCode;
+use reqwest::Url;
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
+use web_time::Instant;
+use x402_types::chain::ChainId;
+use x402_types::proto::{ClientCapabilities, SupportedPaymentKind, SupportedResponse};
+use x402_types::scheme::client::PaymentCandidate;
+
+#[cfg(feature = "telemetry")]
+use tracing::debug;
+
+/// Error returned by [`FacilitatorSupport::try_new`] for a URL that has no `/supported`
+/// endpoint.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid facilitator URL: {0}")]
+pub struct InvalidFacilitatorUrl(String);
+
+/// A `/supported` response, and when it was fetched.
+#[derive(Debug)]
+struct CachedSupported {
+    response: SupportedResponse,
+    fetched_at: Instant,
+}
+
+/// The payment kinds supported by a facilitator, fetched from its `/supported` endpoint.
+#[derive(Debug)]
+pub struct FacilitatorSupport {
+    supported_url: Url,
+    http_client: reqwest::Client,
+    ttl: Duration,
+    capabilities: Option<ClientCapabilities>,
+    cached: RwLock<Option<CachedSupported>>,
+}
+
+impl FacilitatorSupport {
+    /// Default TTL of the cached `/supported` response (10 minutes).
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(600);
+
+    /// Creates the support of the facilitator at `base_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidFacilitatorUrl`] if `base_url` cannot be a base URL.
+    pub fn try_new(base_url: Url) -> Result<Self, InvalidFacilitatorUrl> {
+        let mut base_url = base_url;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        let supported_url = base_url
+            .join("./supported")
+            .map_err(|e| InvalidFacilitatorUrl(e.to_string()))?;
+        Ok(Self {
+            supported_url,
+            http_client: reqwest::Client::new(),
+            ttl: Self::DEFAULT_TTL,
+            capabilities: None,
+            cached: RwLock::new(None),
+        })
+    }
+
+    /// Sets how long a fetched `/supported` response is used before fetching it again.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the HTTP client fetching `/supported`.
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    /// Sends `capabilities` to `POST /supported`, for the facilitator to only list the
+    /// payment kinds this client can pay with.
+    pub fn with_capabilities(mut self, capabilities: ClientCapabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Returns the URL of the `/supported` endpoint.
+    pub fn supported_url(&self) -> &Url {
+        &self.supported_url
+    }
+
+    /// Returns the payment kinds supported by the facilitator.
+    ///
+    /// Returns the cached response within its TTL. Past it, fetches the response again, and
+    /// returns the stale one if that fails. Returns `None` if no response could be fetched
+    /// yet.
+    pub async fn supported(&self) -> Option<SupportedResponse> {
+        let stale = {
+            let cached = self.cached.read().unwrap_or_else(PoisonError::into_inner);
+            match cached.as_ref() {
+                Some(cached) if cached.fetched_at.elapsed() < self.ttl => {
+                    return Some(cached.response.clone());
+                }
+                cached => cached.map(|cached| cached.response.clone()),
+            }
+        };
+        match self.fetch().await {
+            Ok(response) => {
+                *self.cached.write().unwrap_or_else(PoisonError::into_inner) =
+                    Some(CachedSupported {
+                        response: response.clone(),
+                        fetched_at: Instant::now(),
+                    });
+                Some(response)
+            }
+            Err(_e) => {
+                #[cfg(feature = "telemetry")]
+                debug!(url = %self.supported_url, error = %_e, stale = stale.is_some(), "Could not fetch facilitator support");
+                stale
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<SupportedResponse, reqwest::Error> {
+        if let Some(capabilities) = &self.capabilities {
+            let response = self
+                .http_client
+                .post(self.supported_url.clone())
+                .json(capabilities)
+                .send()
+                .await?;
+            // Facilitators that predate `POST /supported` list everything they support
+            if !matches!(
+                response.status(),
+                StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+            ) {
+                return response.error_for_status()?.json().await;
+            }
+        }
+        self.http_client
+            .get(self.supported_url.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Keeps the candidates the facilitator supports, in order.
+    ///
+    /// Candidates are kept as they are if the support is unknown.
+    pub(crate) async fn retain_supported(&self, candidates: &mut Vec<PaymentCandidate>) {
+        if candidates.is_empty() {
+            return;
+        }
+        if let Some(supported) = self.supported().await {
+            candidates.retain(|candidate| is_supported(&supported, candidate));
+        }
+    }
+}
+
+/// Returns whether the facilitator can settle `candidate`.
+fn is_supported(supported: &SupportedResponse, candidate: &PaymentCandidate) -> bool {
+    let kind_listed = supported
+        .kinds
+        .iter()
+        .any(|kind| kind_matches(kind, candidate));
+    // Solana facilitators pay the fees with one of their signers
+    let fee_payer_listed = candidate.chain_id.namespace != "solana"
+        || supported
+            .signers
+            .get(&candidate.chain_id)
+            .is_some_and(|signers| !signers.is_empty());
+    #[cfg(feature = "telemetry")]
+    if !(kind_listed && fee_payer_listed) {
+        debug!(
+            scheme = %candidate.scheme,
+            chain_id = %candidate.chain_id,
+            "Facilitator does not support payment option"
+        );
+    }
+    kind_listed && fee_payer_listed
+}
+
+/// Returns whether `kind` describes the payment of `candidate`.
+///
+/// The network of a kind is a CAIP-2 chain ID in V2, and a network name in V1.
+fn kind_matches(kind: &SupportedPaymentKind, candidate: &PaymentCandidate) -> bool {
+    kind.x402_version == candidate.x402_version
+        && kind.scheme == candidate.scheme
+        && (kind.network.parse::<ChainId>().ok().as_ref() == Some(&candidate.chain_id)
+            || candidate.chain_id.as_network_name() == Some(kind.network.as_str()))
+}
@@ -0,0 +1,380 @@
+//! Payment settings per host.
+//!
+//! A client talking to several paid APIs may trust some more than others, e.g. pay up to
+//! 5 USDC per call to a known API but no more than 0.05 USDC to any other. Host policies,
+//! set with [`X402Client::for_host`](crate::X402Client::for_host), narrow the payment
+//! options of requests to matching hosts before the selector picks one:
+//!
+//! - [`HostPolicy::max`] caps each payment, in token units, e.g. `5.00 USDC`. Options in
+//!   other tokens, or tokens not registered with
+//!   [`X402Client::with_token`](crate::X402Client::with_token), are not paid.
+//! - [`HostPolicy::prefer`] orders the options by chain, so that
+//!   [`FirstMatch`](x402_types::scheme::client::FirstMatch) pays with the most preferred.
+//!
+//! Patterns are either an exact host, `api.example.com`, or a suffix wildcard,
+//! `*.example.com`, matching any subdomain of `example.com` but not `example.com` itself.
+//! The exact host takes precedence, then the longest matching suffix.
+//!
+//! Each setting is resolved from the matching host policy, then from the global policy set
+//! with [`X402Client::with_host_defaults`](crate::X402Client::with_host_defaults). With
+//! [`X402Client::deny_unknown_hosts`](crate::X402Client::deny_unknown_hosts), hosts matching
+//! no pattern are never paid, and their payments fail with
+//! [`X402Error::HostNotAllowed`](x402_types::scheme::client::X402Error::HostNotAllowed).
+//!
+//! [`HostPolicies::resolve`] returns the settings a host gets, e.g. for debugging, and the
+//! `Debug` output of [`HostPolicies`] lists every policy.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::budget::BudgetToken;
+//!
+//! let usdc = BudgetToken::new("USDC", 6)
+//!     .on(ChainId::new("eip155", "8453"), "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913");
+//! let x402_client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_token(usdc)
+//!     .for_host("api.example.com", |host| host.max("5.00 USDC".parse().unwrap()))
+//!     .with_host_defaults(|host| host.max("0.05 USDC".parse().unwrap()));
+//! ```
+
+use std::fmt::{Display, Formatter};
+use x402_types::chain::ChainIdPattern;
+use x402_types::scheme::client::{PaymentCandidate, X402Error};
+
+use crate::budget::{BudgetAmount, BudgetToken};
+
+/// Hosts a [`HostPolicy`] applies to, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostPattern {
+    /// A host, e.g. `api.example.com`.
+    Exact(String),
+    /// Subdomains of a domain, e.g. `example.com` for `*.example.com`.
+    Subdomains(String),
+}
+
+impl HostPattern {
+    /// Parses an exact host, or a suffix wildcard starting with `*.`.
+    ///
+    /// Hosts are compared case-insensitively.
+    pub fn new(pattern: &str) -> Self {
+        let pattern = normalize(pattern);
+        match pattern.strip_prefix("*.") {
+            Some(domain) => HostPattern::Subdomains(domain.to_string()),
+            None => HostPattern::Exact(pattern),
+        }
+    }
+
+    /// Whether `host` matches the pattern.
+    pub fn matches(&self, host: &str) -> bool {
+        let host = normalize(host);
+        match self {
+            HostPattern::Exact(exact) => host == *exact,
+            HostPattern::Subdomains(domain) => host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+        }
+    }
+
+    /// Precedence among matching patterns: exact hosts first, then longer suffixes.
+    fn specificity(&self) -> (bool, usize) {
+        match self {
+            HostPattern::Exact(host) => (true, host.len()),
+            HostPattern::Subdomains(domain) => (false, domain.len()),
+        }
+    }
+}
+
+impl Display for HostPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostPattern::Exact(host) => f.write_str(host),
+            HostPattern::Subdomains(domain) => write!(f, "*.{domain}"),
+        }
+    }
+}
+
+fn normalize(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Payment settings of a host, or of all hosts. Settings left unset fall back to the
+/// global policy.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostPolicy {
+    preference: Option<Vec<ChainIdPattern>>,
+    caps: Option<Vec<BudgetAmount>>,
+}
+
+impl HostPolicy {
+    /// Orders payment options by chain, in the order of `patterns`.
+    ///
+    /// Options on chains matching no pattern come last.
+    pub fn prefer<P: Into<Vec<ChainIdPattern>>>(mut self, patterns: P) -> Self {
+        self.preference = Some(patterns.into());
+        self
+    }
+
+    /// Caps each payment at `amount`. Call once per token the host may be paid in.
+    pub fn max(mut self, amount: BudgetAmount) -> Self {
+        self.caps.get_or_insert_with(Vec::new).push(amount);
+        self
+    }
+
+    /// The chain preference, if set.
+    pub fn preference(&self) -> Option<&[ChainIdPattern]> {
+        self.preference.as_deref()
+    }
+
+    /// The caps per payment, if set.
+    pub fn caps(&self) -> Option<&[BudgetAmount]> {
+        self.caps.as_deref()
+    }
+
+    /// This policy, with the settings it leaves unset taken from `fallback`.
+    fn or(&self, fallback: &HostPolicy) -> HostPolicy {
+        HostPolicy {
+            preference: self
+                .preference
+                .clone()
+                .or_else(|| fallback.preference.clone()),
+            caps: self.caps.clone().or_else(|| fallback.caps.clone()),
+        }
+    }
+}
+
+/// The host policies of a client, see the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct HostPolicies {
+    hosts: Vec<(HostPattern, HostPolicy)>,
+    defaults: HostPolicy,
+    deny_unknown: bool,
+    tokens: Vec<BudgetToken>,
+}
+
+impl HostPolicies {
+    /// Adds the policy of the hosts matching `pattern`, replacing any previous one.
+    pub(crate) fn insert(&mut self, pattern: HostPattern, policy: HostPolicy) {
+        self.hosts.retain(|(existing, _)| *existing != pattern);
+        self.hosts.push((pattern, policy));
+    }
+
+    pub(crate) fn set_defaults(&mut self, policy: HostPolicy) {
+        self.defaults = policy;
+    }
+
+    pub(crate) fn deny_unknown(&mut self) {
+        self.deny_unknown = true;
+    }
+
+    pub(crate) fn add_token(&mut self, token: BudgetToken) {
+        self.tokens.push(token);
+    }
+
+    /// The most specific host policy matching `host`, with its pattern.
+    fn matching(&self, host: &str) -> Option<&(HostPattern, HostPolicy)> {
+        self.hosts
+            .iter()
+            .filter(|(pattern, _)| pattern.matches(host))
+            .max_by_key(|(pattern, _)| pattern.specificity())
+    }
+
+    /// Returns the pattern of the policy applying to `host`, if any.
+    pub fn pattern_for(&self, host: &str) -> Option<&HostPattern> {
+        self.matching(host).map(|(pattern, _)| pattern)
+    }
+
+    /// Returns the settings `host` is paid with, or `None` if it is never paid.
+    pub fn resolve(&self, host: &str) -> Option<HostPolicy> {
+        match self.matching(host) {
+            Some((_, policy)) => Some(policy.or(&self.defaults)),
+            None if self.deny_unknown => None,
+            None => Some(self.defaults.clone()),
+        }
+    }
+
+    /// Removes the candidates `host` may not be paid with, and orders the others by the
+    /// chain preference of the host.
+    pub(crate) fn apply(
+        &self,
+        host: &str,
+        candidates: &mut Vec<PaymentCandidate>,
+    ) -> Result<(), X402Error> {
+        let policy = self
+            .resolve(host)
+            .ok_or_else(|| X402Error::HostNotAllowed(host.to_string()))?;
+        if let Some(caps) = &policy.caps {
+            candidates.retain(|candidate| self.within(caps, candidate));
+        }
+        if let Some(preference) = &policy.preference {
+            // Stable, so that equally preferred options keep the server's order
+            candidates.sort_by_key(|candidate| {
+                preference
+                    .iter()
+                    .position(|pattern| pattern.matches(&candidate.chain_id))
+                    .unwrap_or(preference.len())
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether `candidate` is in a registered token capped by `caps`, within its cap.
+    fn within(&self, caps: &[BudgetAmount], candidate: &PaymentCandidate) -> bool {
+        let Some(token) = self
+            .tokens
+            .iter()
+            .find(|token| token.is_deployed(&candidate.chain_id, &candidate.asset))
+        else {
+            return false;
+        };
+        caps.iter()
+            .filter(|cap| cap.symbol().eq_ignore_ascii_case(token.symbol()))
+            .any(|cap| candidate.amount <= cap.to_atomic(token.decimals()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+
+    const BASE: &str = "eip155:8453";
+    const POLYGON: &str = "eip155:137";
+    const USDC_BASE: &str = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
+    const USDC_POLYGON: &str = "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359";
+
+    struct NoSigner;
+
+    #[async_trait::async_trait]
+    impl x402_types::scheme::client::PaymentCandidateSigner for NoSigner {
+        async fn sign_payment(&self) -> Result<String, X402Error> {
+            unreachable!()
+        }
+    }
+
+    /// A payment of `amount` USDC, in atomic units.
+    fn payment(chain_id: &str, asset: &str, amount: u64) -> PaymentCandidate {
+        PaymentCandidate {
+            chain_id: chain_id.parse().unwrap(),
+            asset: asset.into(),
+            amount: U256::from(amount),
+            scheme: "exact".into(),
+            x402_version: 2,
+            pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+            signer: Box::new(NoSigner),
+        }
+    }
+
+    fn usdc(amount: &str) -> BudgetAmount {
+        format!("{amount} USDC").parse().unwrap()
+    }
+
+    fn policies() -> HostPolicies {
+        let mut policies = HostPolicies::default();
+        policies.add_token(
+            BudgetToken::new("USDC", 6)
+                .on(BASE.parse().unwrap(), USDC_BASE)
+                .on(POLYGON.parse().unwrap(), USDC_POLYGON),
+        );
+        policies.insert(
+            HostPattern::new("api.example.com"),
+            HostPolicy::default().max(usdc("5.00")),
+        );
+        policies.insert(
+            HostPattern::new("*.example.com"),
+            HostPolicy::default().prefer(vec![POLYGON.parse().unwrap()]),
+        );
+        policies.set_defaults(
+            HostPolicy::default()
+                .max(usdc("0.05"))
+                .prefer(vec![BASE.parse().unwrap()]),
+        );
+        policies
+    }
+
+    #[test]
+    fn matches_exact_hosts_and_subdomains() {
+        let exact = HostPattern::new("API.example.com.");
+        assert!(exact.matches("api.example.com"));
+        assert!(!exact.matches("www.api.example.com"));
+
+        let subdomains = HostPattern::new("*.example.com");
+        assert!(subdomains.matches("api.example.com"));
+        assert!(subdomains.matches("eu.api.Example.com"));
+        assert!(!subdomains.matches("example.com"));
+        assert!(!subdomains.matches("badexample.com"));
+        assert_eq!(subdomains.to_string(), "*.example.com");
+    }
+
+    #[test]
+    fn resolves_host_settings_before_global_ones() {
+        let policies = policies();
+
+        // The exact host wins over the wildcard, and takes the global preference
+        assert_eq!(
+            policies.pattern_for("api.example.com"),
+            Some(&HostPattern::new("api.example.com"))
+        );
+        let api = policies.resolve("api.example.com").unwrap();
+        assert_eq!(api.caps(), Some(&[usdc("5.00")][..]));
+        assert_eq!(api.preference(), Some(&[BASE.parse().unwrap()][..]));
+
+        // Other subdomains take the wildcard preference and the global cap
+        let other = policies.resolve("other.example.com").unwrap();
+        assert_eq!(other.caps(), Some(&[usdc("0.05")][..]));
+        assert_eq!(other.preference(), Some(&[POLYGON.parse().unwrap()][..]));
+
+        // Unknown hosts get the global settings
+        assert_eq!(policies.pattern_for("unknown.org"), None);
+        assert_eq!(policies.resolve("unknown.org").unwrap(), policies.defaults);
+    }
+
+    #[test]
+    fn caps_and_orders_candidates() {
+        let policies = policies();
+        let candidates = || {
+            vec![
+                payment(BASE, USDC_BASE, 1_000_000),
+                payment(POLYGON, USDC_POLYGON, 1_000_000),
+                payment(BASE, USDC_BASE, 10_000),
+                payment(BASE, "0x60a3E35Cc302bFA44Cb288Bc5a4F316Fdb1adb42", 10_000),
+            ]
+        };
+
+        // 1 USDC is within the 5 USDC cap; the unregistered token is never paid
+        let mut api = candidates();
+        policies.apply("api.example.com", &mut api).unwrap();
+        let api: Vec<_> = api
+            .iter()
+            .map(|c| (c.chain_id.to_string(), c.amount))
+            .collect();
+        assert_eq!(
+            api,
+            [
+                (BASE.to_string(), U256::from(1_000_000)),
+                (BASE.to_string(), U256::from(10_000)),
+                (POLYGON.to_string(), U256::from(1_000_000)),
+            ]
+        );
+
+        // Unknown hosts are capped at 0.05 USDC
+        let mut unknown = candidates();
+        policies.apply("unknown.org", &mut unknown).unwrap();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].amount, U256::from(10_000));
+    }
+
+    #[test]
+    fn denies_unknown_hosts() {
+        let mut policies = policies();
+        policies.deny_unknown();
+
+        assert!(policies.resolve("api.example.com").is_some());
+        assert!(policies.resolve("other.example.com").is_some());
+        assert_eq!(policies.resolve("unknown.org"), None);
+
+        let mut candidates = vec![payment(BASE, USDC_BASE, 10_000)];
+        let error = policies.apply("unknown.org", &mut candidates).unwrap_err();
+        assert!(matches!(error, X402Error::HostNotAllowed(host) if host == "unknown.org"));
+    }
+}
@@ -0,0 +1,64 @@
+//! reqwest client settings for AWS Lambda.
+//!
+//! A Lambda execution environment is frozen between invocations, which silently breaks
+//! pooled keep-alive connections: the next invocation picks a socket the peer closed long
+//! ago and fails. Lambda clients therefore open a fresh connection per request, give up
+//! connecting quickly so that a retry fits in the function timeout, and trust the CA bundle
+//! shipped with the Lambda runtime in addition to the built-in roots.
+
+use reqwest::{Certificate, Client, ClientBuilder};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::builder::{ReqwestWithPayments, ReqwestWithPaymentsBuilder};
+use crate::client::X402Client;
+
+/// Connect timeout of clients built by [`lambda_client_builder`].
+pub const LAMBDA_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// CA bundle of the Amazon Linux images Lambda runtimes are based on.
+pub const LAMBDA_CA_BUNDLE: &str = "/etc/pki/tls/certs/ca-bundle.crt";
+
+/// Returns a [`ClientBuilder`] configured for AWS Lambda.
+///
+/// - Connection pooling is disabled (`pool_max_idle_per_host(0)`).
+/// - Connecting times out after [`LAMBDA_CONNECT_TIMEOUT`].
+/// - Certificates of the CA bundle named by `SSL_CERT_FILE`, or of [`LAMBDA_CA_BUNDLE`],
+///   are trusted in addition to the built-in roots. A missing or unreadable bundle is ignored.
+pub fn lambda_client_builder() -> ClientBuilder {
+    Client::builder()
+        .pool_max_idle_per_host(0)
+        .connect_timeout(LAMBDA_CONNECT_TIMEOUT)
+        .tls_certs_merge(lambda_root_certificates())
+}
+
+/// Reads the certificates of the Lambda CA bundle, if any.
+fn lambda_root_certificates() -> Vec<Certificate> {
+    let path = std::env::var_os("SSL_CERT_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(LAMBDA_CA_BUNDLE));
+    let certificates = std::fs::read(&path)
+        .ok()
+        .and_then(|pem| Certificate::from_pem_bundle(&pem).ok())
+        .unwrap_or_default();
+    #[cfg(feature = "telemetry")]
+    tracing::debug!(
+        path = %path.display(),
+        count = certificates.len(),
+        "Loaded Lambda CA bundle"
+    );
+    certificates
+}
+
+impl<S> ReqwestWithPaymentsBuilder<ClientBuilder, S> {
+    /// Starts a client with x402 payments from [`lambda_client_builder`].
+    ///
+    /// ```rust,ignore
+    /// use x402_reqwest::{ReqwestWithPaymentsBuild, ReqwestWithPaymentsBuilder};
+    ///
+    /// let http_client = ReqwestWithPaymentsBuilder::for_lambda(x402_client).build()?;
+    /// ```
+    pub fn for_lambda(x402_client: X402Client<S>) -> Self {
+        lambda_client_builder().with_payments(x402_client)
+    }
+}
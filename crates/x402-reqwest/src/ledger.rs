@@ -0,0 +1,544 @@
+//! Persistent record of the payments made by a client.
+//!
+//! A [`Budget`](crate::budget::Budget) counts spend in memory, so a restarted process would
+//! start its budget over. Given a [`SpendLedger`] with
+//! [`Budget::with_ledger`](crate::budget::Budget::with_ledger), the budget records every
+//! payment in it, and restores the spend of its periods from it before its first payment.
+//!
+//! A ledger also answers what a client spent, independently of any budget, see
+//! [`spent_within`].
+//!
+//! Two ledgers are provided:
+//!
+//! - [`JsonLinesLedger`]: one JSON object per line, appended to a file.
+//! - `SqliteLedger`: a table of an SQLite database, with the `sqlite` feature.
+//!
+//! Payments are recorded before the paid request is sent, so that a crash never loses one.
+//! A payment whose request could not be sent stays recorded. The [`FsyncPolicy`] decides
+//! whether each record is flushed to disk before the request proceeds.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//! use x402_reqwest::budget::{Budget, BudgetToken};
+//! use x402_reqwest::ledger::{FsyncPolicy, JsonLinesLedger, spent_within};
+//!
+//! let ledger = JsonLinesLedger::open("payments.jsonl", FsyncPolicy::Always).await?;
+//! let spent_today = spent_within(&ledger, &usdc, Duration::from_secs(24 * 60 * 60)).await?;
+//! println!("Spent {spent_today} today");
+//!
+//! let budget = Budget::per_day("5.00 USDC".parse()?)
+//!     .with_token(usdc)
+//!     .with_ledger(ledger);
+//! ```
+
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use x402_types::chain::ChainId;
+use x402_types::scheme::client::PaymentCandidate;
+use x402_types::timestamp::UnixTimestamp;
+
+use crate::budget::BudgetToken;
+
+/// A payment made by a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendRecord {
+    /// When the payment was made.
+    pub timestamp: UnixTimestamp,
+    /// Chain the payment is made on.
+    pub chain_id: ChainId,
+    /// Token contract or mint address.
+    pub asset: String,
+    /// Amount paid, in atomic units of the token.
+    pub amount: U256,
+    /// Recipient of the payment.
+    pub pay_to: String,
+    /// Payment scheme, e.g. `exact`.
+    pub scheme: String,
+}
+
+impl SpendRecord {
+    /// Records the payment of `candidate` at `timestamp`.
+    pub fn new(candidate: &PaymentCandidate, timestamp: UnixTimestamp) -> Self {
+        Self {
+            timestamp,
+            chain_id: candidate.chain_id.clone(),
+            asset: candidate.asset.clone(),
+            amount: candidate.amount,
+            pay_to: candidate.pay_to.clone(),
+            scheme: candidate.scheme.clone(),
+        }
+    }
+}
+
+/// Errors reading or writing a [`SpendLedger`].
+#[derive(Debug, thiserror::Error)]
+pub enum SpendLedgerError {
+    /// The ledger storage could not be accessed.
+    #[error("Spend ledger I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A stored record could not be read.
+    #[error("Invalid spend record #{index}: {reason}")]
+    InvalidRecord {
+        /// Position of the record in the ledger, starting at 1.
+        index: usize,
+        /// Why the record is invalid.
+        reason: String,
+    },
+    /// The SQLite database returned an error.
+    #[cfg(feature = "sqlite")]
+    #[error("Spend ledger database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// Persistent storage of the payments made by a client.
+#[async_trait::async_trait]
+pub trait SpendLedger: Send + Sync {
+    /// Appends `payment` to the ledger.
+    async fn record(&self, payment: &SpendRecord) -> Result<(), SpendLedgerError>;
+
+    /// Returns the payments made at or after `since`, oldest first.
+    async fn records_since(
+        &self,
+        since: UnixTimestamp,
+    ) -> Result<Vec<SpendRecord>, SpendLedgerError>;
+
+    /// Returns the amount of `token` paid at or after `since`, in atomic units, over all
+    /// its deployments.
+    async fn sum_since(
+        &self,
+        token: &BudgetToken,
+        since: UnixTimestamp,
+    ) -> Result<U256, SpendLedgerError> {
+        let records = self.records_since(since).await?;
+        Ok(records
+            .iter()
+            .filter(|record| token.is_deployed(&record.chain_id, &record.asset))
+            .fold(U256::ZERO, |sum, record| sum.saturating_add(record.amount)))
+    }
+}
+
+/// Amount of a token paid, as returned by [`spent_within`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendTotal {
+    /// Token symbol.
+    pub symbol: String,
+    /// Token decimals.
+    pub decimals: u8,
+    /// Amount paid, in atomic units.
+    pub amount: U256,
+}
+
+impl Display for SpendTotal {
+    /// Formats the amount in whole tokens, e.g. `5.25 USDC`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let digits = self.amount.to_string();
+        let decimals = usize::from(self.decimals);
+        let digits = format!("{digits:0>width$}", width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{whole} {}", self.symbol)
+        } else {
+            write!(f, "{whole}.{fraction} {}", self.symbol)
+        }
+    }
+}
+
+/// Returns the amount of `token` paid over the last `window`, according to `ledger`.
+pub async fn spent_within<L: SpendLedger + ?Sized>(
+    ledger: &L,
+    token: &BudgetToken,
+    window: Duration,
+) -> Result<SpendTotal, SpendLedgerError> {
+    let since = UnixTimestamp::from_secs(
+        UnixTimestamp::now()
+            .as_secs()
+            .saturating_sub(window.as_secs()),
+    );
+    Ok(SpendTotal {
+        symbol: token.symbol().to_owned(),
+        decimals: token.decimals(),
+        amount: ledger.sum_since(token, since).await?,
+    })
+}
+
+/// When a [`SpendLedger`] flushes records to disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Flush every record to disk before the payment proceeds. Survives power loss.
+    #[default]
+    Always,
+    /// Leave flushing to the operating system. Survives a crash of the process, but not
+    /// of the machine.
+    Never,
+}
+
+/// Line of a [`JsonLinesLedger`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordLine {
+    timestamp: u64,
+    chain_id: ChainId,
+    asset: String,
+    /// Decimal, for the file to be readable as is.
+    amount: String,
+    pay_to: String,
+    scheme: String,
+}
+
+impl From<&SpendRecord> for RecordLine {
+    fn from(record: &SpendRecord) -> Self {
+        Self {
+            timestamp: record.timestamp.as_secs(),
+            chain_id: record.chain_id.clone(),
+            asset: record.asset.clone(),
+            amount: record.amount.to_string(),
+            pay_to: record.pay_to.clone(),
+            scheme: record.scheme.clone(),
+        }
+    }
+}
+
+impl RecordLine {
+    fn into_record(self, index: usize) -> Result<SpendRecord, SpendLedgerError> {
+        let amount = U256::from_str_radix(&self.amount, 10).map_err(|err| {
+            SpendLedgerError::InvalidRecord {
+                index,
+                reason: err.to_string(),
+            }
+        })?;
+        Ok(SpendRecord {
+            timestamp: UnixTimestamp::from_secs(self.timestamp),
+            chain_id: self.chain_id,
+            asset: self.asset,
+            amount,
+            pay_to: self.pay_to,
+            scheme: self.scheme,
+        })
+    }
+}
+
+/// [`SpendLedger`] appending one JSON object per line to a file.
+///
+/// A record is written with a single append, so a crash can only leave the last line
+/// incomplete. That line, never acknowledged, is discarded when the ledger is opened.
+#[derive(Debug)]
+pub struct JsonLinesLedger {
+    path: PathBuf,
+    file: tokio::sync::Mutex<tokio::fs::File>,
+    fsync: FsyncPolicy,
+}
+
+impl JsonLinesLedger {
+    /// Opens the ledger at `path`, creating the file if it does not exist.
+    pub async fn open(
+        path: impl AsRef<Path>,
+        fsync: FsyncPolicy,
+    ) -> Result<Self, SpendLedgerError> {
+        let path = path.as_ref().to_path_buf();
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        // Drop the incomplete line a crash may have left
+        let contents = tokio::fs::read(&path).await?;
+        if contents.last().is_some_and(|byte| *byte != b'\n') {
+            let complete = contents
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |newline| newline + 1);
+            file.set_len(complete as u64).await?;
+            file.sync_all().await?;
+        }
+        Ok(Self {
+            path,
+            file: tokio::sync::Mutex::new(file),
+            fsync,
+        })
+    }
+
+    /// Path of the ledger file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait::async_trait]
+impl SpendLedger for JsonLinesLedger {
+    async fn record(&self, payment: &SpendRecord) -> Result<(), SpendLedgerError> {
+        let mut line = serde_json::to_vec(&RecordLine::from(payment))
+            .expect("spend records serialize to JSON");
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        if self.fsync == FsyncPolicy::Always {
+            file.sync_data().await?;
+        }
+        Ok(())
+    }
+
+    async fn records_since(
+        &self,
+        since: UnixTimestamp,
+    ) -> Result<Vec<SpendRecord>, SpendLedgerError> {
+        // Holding the file keeps appends from being read half-written
+        let _file = self.file.lock().await;
+        let contents = tokio::fs::read_to_string(&self.path).await?;
+        let mut records = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line: RecordLine =
+                serde_json::from_str(line).map_err(|err| SpendLedgerError::InvalidRecord {
+                    index: index + 1,
+                    reason: err.to_string(),
+                })?;
+            let record = line.into_record(index + 1)?;
+            if record.timestamp >= since {
+                records.push(record);
+            }
+        }
+        records.sort_by_key(|record| record.timestamp);
+        Ok(records)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteLedger;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{Connection, params};
+    use std::sync::{Arc, Mutex};
+
+    /// [`SpendLedger`] storing payments in a table of an SQLite database.
+    ///
+    /// Queries run on blocking threads. The database is in WAL mode, with its `synchronous`
+    /// setting following the [`FsyncPolicy`].
+    #[derive(Debug, Clone)]
+    pub struct SqliteLedger {
+        connection: Arc<Mutex<Connection>>,
+    }
+
+    impl SqliteLedger {
+        /// Opens the ledger in the database at `path`, creating it if it does not exist.
+        pub async fn open(
+            path: impl AsRef<Path>,
+            fsync: FsyncPolicy,
+        ) -> Result<Self, SpendLedgerError> {
+            let path = path.as_ref().to_path_buf();
+            let connection = blocking(move || {
+                let connection = Connection::open(path)?;
+                let synchronous = match fsync {
+                    FsyncPolicy::Always => "FULL",
+                    FsyncPolicy::Never => "OFF",
+                };
+                connection.execute_batch(&format!(
+                    "PRAGMA journal_mode = WAL;
+                     PRAGMA synchronous = {synchronous};
+                     CREATE TABLE IF NOT EXISTS x402_payments (
+                         id INTEGER PRIMARY KEY AUTOINCREMENT,
+                         timestamp INTEGER NOT NULL,
+                         chain_id TEXT NOT NULL,
+                         asset TEXT NOT NULL,
+                         amount TEXT NOT NULL,
+                         pay_to TEXT NOT NULL,
+                         scheme TEXT NOT NULL
+                     );
+                     CREATE INDEX IF NOT EXISTS x402_payments_timestamp
+                         ON x402_payments (timestamp);"
+                ))?;
+                Ok(connection)
+            })
+            .await?;
+            Ok(Self {
+                connection: Arc::new(Mutex::new(connection)),
+            })
+        }
+    }
+
+    /// Runs `f` on a blocking thread.
+    async fn blocking<T, F>(f: F) -> Result<T, SpendLedgerError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, SpendLedgerError> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|err| SpendLedgerError::Io(std::io::Error::other(err)))?
+    }
+
+    #[async_trait::async_trait]
+    impl SpendLedger for SqliteLedger {
+        async fn record(&self, payment: &SpendRecord) -> Result<(), SpendLedgerError> {
+            let connection = self.connection.clone();
+            let payment = payment.clone();
+            blocking(move || {
+                let connection = connection.lock().expect("spend ledger lock poisoned");
+                connection.execute(
+                    "INSERT INTO x402_payments (timestamp, chain_id, asset, amount, pay_to, scheme)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        payment.timestamp.as_secs() as i64,
+                        payment.chain_id.to_string(),
+                        payment.asset,
+                        payment.amount.to_string(),
+                        payment.pay_to,
+                        payment.scheme,
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+        }
+
+        async fn records_since(
+            &self,
+            since: UnixTimestamp,
+        ) -> Result<Vec<SpendRecord>, SpendLedgerError> {
+            let connection = self.connection.clone();
+            blocking(move || {
+                let connection = connection.lock().expect("spend ledger lock poisoned");
+                let mut statement = connection.prepare(
+                    "SELECT id, timestamp, chain_id, asset, amount, pay_to, scheme
+                     FROM x402_payments WHERE timestamp >= ?1 ORDER BY timestamp, id",
+                )?;
+                let rows = statement.query_map(params![since.as_secs() as i64], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, String>(5)?,
+                        row.get::<_, String>(6)?,
+                    ))
+                })?;
+                let mut records = Vec::new();
+                for row in rows {
+                    let (id, timestamp, chain_id, asset, amount, pay_to, scheme) = row?;
+                    let invalid = |reason: String| SpendLedgerError::InvalidRecord {
+                        index: id as usize,
+                        reason,
+                    };
+                    records.push(SpendRecord {
+                        timestamp: UnixTimestamp::from_secs(timestamp as u64),
+                        chain_id: chain_id
+                            .parse::<ChainId>()
+                            .map_err(|err| invalid(err.to_string()))?,
+                        asset,
+                        amount: U256::from_str_radix(&amount, 10)
+                            .map_err(|err| invalid(err.to_string()))?,
+                        pay_to,
+                        scheme,
+                    });
+                }
+                Ok(records)
+            })
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: u64, amount: u64) -> SpendRecord {
+        SpendRecord {
+            timestamp: UnixTimestamp::from_secs(timestamp),
+            chain_id: "eip155:8453".parse().unwrap(),
+            asset: "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913".into(),
+            amount: U256::from(amount),
+            pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+            scheme: "exact".into(),
+        }
+    }
+
+    fn ledger_path() -> PathBuf {
+        std::env::temp_dir().join(format!("x402-ledger-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn json_lines_ledger_reads_back_its_records() {
+        let path = ledger_path();
+        let ledger = JsonLinesLedger::open(&path, FsyncPolicy::Always)
+            .await
+            .unwrap();
+        ledger.record(&record(100, 1_000_000)).await.unwrap();
+        ledger.record(&record(200, 2_500_000)).await.unwrap();
+
+        let usdc = BudgetToken::new("USDC", 6).on(
+            "eip155:8453".parse().unwrap(),
+            "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913",
+        );
+        let reopened = JsonLinesLedger::open(&path, FsyncPolicy::Never)
+            .await
+            .unwrap();
+        assert_eq!(
+            reopened
+                .records_since(UnixTimestamp::from_secs(0))
+                .await
+                .unwrap(),
+            vec![record(100, 1_000_000), record(200, 2_500_000)]
+        );
+        assert_eq!(
+            reopened
+                .sum_since(&usdc, UnixTimestamp::from_secs(150))
+                .await
+                .unwrap(),
+            U256::from(2_500_000)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn incomplete_last_line_is_discarded() {
+        let path = ledger_path();
+        let ledger = JsonLinesLedger::open(&path, FsyncPolicy::Always)
+            .await
+            .unwrap();
+        ledger.record(&record(100, 1)).await.unwrap();
+        drop(ledger);
+        // A crash in the middle of an append
+        let mut contents = std::fs::read(&path).unwrap();
+        contents.extend_from_slice(br#"{"timestamp":200,"chainId":"eip1"#);
+        std::fs::write(&path, contents).unwrap();
+
+        let ledger = JsonLinesLedger::open(&path, FsyncPolicy::Always)
+            .await
+            .unwrap();
+        ledger.record(&record(300, 3)).await.unwrap();
+        assert_eq!(
+            ledger
+                .records_since(UnixTimestamp::from_secs(0))
+                .await
+                .unwrap(),
+            vec![record(100, 1), record(300, 3)]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn totals_are_formatted_in_whole_tokens() {
+        let total = |amount: u64| SpendTotal {
+            symbol: "USDC".into(),
+            decimals: 6,
+            amount: U256::from(amount),
+        };
+        assert_eq!(total(5_250_000).to_string(), "5.25 USDC");
+        assert_eq!(total(3).to_string(), "0.000003 USDC");
+        assert_eq!(total(0).to_string(), "0 USDC");
+        assert_eq!(total(12_000_000).to_string(), "12 USDC");
+    }
+}
@@ -50,10 +50,121 @@
 //! to choose the best option. By default, it uses [`FirstMatch`] which selects the first
 //! matching scheme. You can implement custom selection logic by providing your own selector.
 //!
-//! See [`X402Client::with_selector`] for custom payment selection.
+//! See [`X402Client::with_selector`] for custom payment selection. The
+//! [`Cheapest`](selection::Cheapest) selector pays with the option of lowest value, given
+//! token valuations. See the [`selection`] module.
+//!
+//! ## Reading the Settlement Result
+//!
+//! After a paid retry, the server's `Payment-Response` (V2) or `X-Payment-Response` (V1)
+//! header is decoded into a [`PaymentResponse`](x402_types::proto::PaymentResponse) and
+//! stored in the response extensions:
+//!
+//! ```rust,ignore
+//! use x402_types::proto::PaymentResponse;
+//!
+//! let response = http_client.get(url).send().await?;
+//! if let Some(payment) = response.extensions().get::<PaymentResponse>() {
+//!     println!("Paid in transaction {}", payment.transaction);
+//! }
+//! ```
+//!
+//! See [`parse_payment_response`] to decode the header yourself.
+//!
+//! Settled payments also get a [`PaymentReceipt`](receipt::PaymentReceipt), with the paid
+//! URL, amount, token, network, payer and transaction, read with
+//! [`ResponseExt::payment_receipt`]. [`X402Client::on_payment`] calls a callback with each
+//! receipt, e.g. for accounting. See the [`receipt`] module.
+//!
+//! ## Spending Budget
+//!
+//! [`X402Client::with_budget`] caps the cumulative spend across payments, per period and
+//! in total. See the [`budget`] module.
+//!
+//! With the `ledger` feature, payments are recorded in a persistent
+//! [`SpendLedger`](ledger::SpendLedger), so that budgets survive restarts and spend can be
+//! audited. See the [`ledger`] module.
+//!
+//! ## Host Policies
+//!
+//! [`X402Client::for_host`] sets per-host caps and chain preferences, e.g. up to 5 USDC per
+//! call to a trusted API and 0.05 USDC to any other, and [`X402Client::deny_unknown_hosts`]
+//! only pays configured hosts. See the [`hosts`] module.
+//!
+//! ## Rate Limit
+//!
+//! [`X402Client::with_rate_limit`] caps how often the client pays, e.g. for at most 10
+//! requests per minute, against a runaway loop. See the [`rate_limit`] module.
+//!
+//! ## Payment Approval
+//!
+//! [`X402Client::with_approval`] asks a hook to approve each payment before it is signed,
+//! e.g. to confirm it with a user. See the [`approval`] module.
+//!
+//! ## Payment Events
+//!
+//! With the `events` feature, [`X402Client::events`] subscribes to a stream of structured
+//! events, from the 402 to the payment succeeding or failing, e.g. for observability.
+//! Publishing never waits for subscribers. See the [`events`] module.
+//!
+//! ## Request Coalescing
+//!
+//! With the `coalescing` feature, [`X402Client::with_coalescing`] has concurrent requests to
+//! the same paid resource share a single challenge, and a single payment when the response
+//! is cacheable. See the [`coalesce`] module.
+//!
+//! ## Other HTTP Stacks
+//!
+//! [`X402Client::prepare_payment`] prepares the payment header for a decoded 402 challenge
+//! without any HTTP I/O, for HTTP stacks other than reqwest. The middleware pays the same
+//! way. See the [`prepared`] module.
+//!
+//! ## Facilitator Support
+//!
+//! [`X402Client::with_facilitator`] only pays with options the facilitator of the sellers
+//! supports, as listed by its `/supported` endpoint. See the [`facilitator`] module.
+//!
+//! ## Streaming Bodies
+//!
+//! A paid request is sent again with the payment attached. Streaming bodies, e.g. multipart
+//! uploads, are read into memory up to a limit so that they can be sent twice, or sent once
+//! after fetching the 402 with a `HEAD` request, see [`X402Client::with_body_replay`] and the
+//! [`replay`] module.
+//!
+//! ## AWS Lambda
+//!
+//! With the `lambda` feature, [`ReqwestWithPaymentsBuilder::for_lambda`] builds the client
+//! from [`lambda_client_builder`]: no connection pooling across frozen invocations, a short
+//! connect timeout, and the CA bundle of the Lambda runtime.
+//!
+//! ## WebAssembly
+//!
+//! The client builds for `wasm32-unknown-unknown`, on reqwest's browser backend: requests
+//! go through `fetch`, clocks read `Date.now()` and payment IDs use the browser crypto API.
+//! The `lambda`, `ledger`, `sqlite` and `coalescing` features are not available there, and
+//! `non-blocking-sign` has no effect, as browsers have no blocking threads.
 
+pub mod approval;
+pub mod budget;
 mod builder;
 mod client;
+#[cfg(all(feature = "coalescing", not(target_arch = "wasm32")))]
+pub mod coalesce;
+#[cfg(feature = "events")]
+pub mod events;
+pub mod facilitator;
+pub mod hosts;
+#[cfg(all(feature = "lambda", not(target_arch = "wasm32")))]
+mod lambda;
+#[cfg(feature = "ledger")]
+pub mod ledger;
+pub mod prepared;
+pub mod rate_limit;
+pub mod receipt;
+pub mod replay;
+pub mod selection;
 
 pub use builder::*;
 pub use client::*;
+#[cfg(all(feature = "lambda", not(target_arch = "wasm32")))]
+pub use lambda::*;
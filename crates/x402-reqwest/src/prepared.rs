@@ -0,0 +1,64 @@
+//! Payments prepared without sending the request.
+//!
+//! The [`X402Client`](crate::X402Client) middleware fetches the `402 Payment Required`
+//! challenge, pays, and sends the request again, all over reqwest. Other HTTP stacks, e.g. a
+//! gRPC-web gateway, can reuse the payment logic with
+//! [`X402Client::prepare_payment`](crate::X402Client::prepare_payment), which does no HTTP
+//! I/O:
+//!
+//! 1. Send the request with the HTTP stack, and get a `402` response.
+//! 2. Decode its requirements with [`decode_payment_required`](crate::decode_payment_required),
+//!    from the `Payment-Required` header (V2) or the body (V1).
+//! 3. Pass them to `prepare_payment`, along with the URL of the resource. It selects an
+//!    option, and approves, signs and counts the payment as the middleware does.
+//! 4. Send the request again with the header of the returned [`PreparedPayment`].
+//!
+//! The middleware prepares its payments the same way.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::{X402Client, decode_payment_required};
+//!
+//! let x402_client = X402Client::new().register(V2Eip155ExactClient::new(signer));
+//!
+//! let (headers, body) = gateway.call(&url, None).await?;
+//! let payment_required = decode_payment_required(&headers, &body).ok_or("not a 402")?;
+//! let prepared = x402_client.prepare_payment(&payment_required, &url).await?;
+//! let (name, value) = prepared.header();
+//! let (headers, body) = gateway.call(&url, Some((name, value))).await?;
+//! ```
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+use x402_types::timestamp::UnixTimestamp;
+
+use crate::approval::PendingPayment;
+
+/// A signed payment, ready to be attached to a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedPayment {
+    /// The header carrying the payment: `Payment-Signature` (V2) or `X-Payment` (V1).
+    pub header_name: HeaderName,
+    /// The signed payment payload.
+    pub header_value: HeaderValue,
+    /// The selected payment option.
+    pub payment: PendingPayment,
+    /// When the signed authorization expires, per the validity window, if known.
+    ///
+    /// Solana payments are bound to a recent blockhash, and usually expire sooner.
+    pub expires_at: Option<UnixTimestamp>,
+}
+
+impl PreparedPayment {
+    /// The payment header, as a name and value pair.
+    pub fn header(&self) -> (&HeaderName, &HeaderValue) {
+        (&self.header_name, &self.header_value)
+    }
+
+    /// The payment header, in a [`HeaderMap`] of its own.
+    pub fn to_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(self.header_name.clone(), self.header_value.clone());
+        headers
+    }
+}
@@ -0,0 +1,184 @@
+//! Rate limit on the payments made by a client.
+//!
+//! A [`Budget`](crate::budget::Budget) caps how much a client spends, but a runaway loop can
+//! still spend it in seconds. A [`RateLimit`] caps how often the
+//! [`X402Client`](crate::X402Client) pays, e.g. for at most 10 requests per minute, whatever
+//! they cost.
+//!
+//! The limit is a token bucket: it holds up to `max_paid_requests` payments, and refills
+//! evenly over the period, one payment every `per / max_paid_requests`. A full bucket allows
+//! a burst of `max_paid_requests` payments. Each payment takes one from the bucket once its
+//! option is selected, before it is approved and signed; with
+//! [`X402Client::with_max_attempts`](crate::X402Client::with_max_attempts), so does each
+//! attempt.
+//!
+//! When the bucket is empty, the payment fails with
+//! [`X402Error::RateLimited`](x402_types::scheme::client::X402Error::RateLimited), telling
+//! when the next payment is allowed. With the `rate-limit-wait` feature, `RateLimit::waiting`
+//! makes the payment wait for it instead.
+//!
+//! Clones of a [`RateLimit`] share their bucket: give clones to several clients to limit
+//! their payments together.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//! use x402_reqwest::rate_limit::RateLimit;
+//!
+//! let x402_client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_rate_limit(RateLimit::new(10, Duration::from_secs(60)));
+//! ```
+
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use web_time::Instant;
+use x402_types::scheme::client::X402Error;
+
+/// Caps the number of payments per period, see the [module documentation](self).
+#[derive(Clone)]
+pub struct RateLimit {
+    max_paid_requests: u32,
+    per: Duration,
+    waiting: bool,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+/// Payments left in the bucket, as of `updated`.
+struct Bucket {
+    available: f64,
+    updated: Instant,
+}
+
+impl RateLimit {
+    /// Allows up to `max_paid_requests` payments `per` period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_paid_requests` or `per` is zero.
+    pub fn new(max_paid_requests: u32, per: Duration) -> Self {
+        assert!(max_paid_requests > 0, "rate limit allows no payment");
+        assert!(!per.is_zero(), "rate limit period is zero");
+        Self {
+            max_paid_requests,
+            per,
+            waiting: false,
+            bucket: Arc::new(Mutex::new(Bucket {
+                available: f64::from(max_paid_requests),
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    /// Makes payments beyond the limit wait until they are allowed, instead of failing with
+    /// [`X402Error::RateLimited`].
+    ///
+    /// Waiting payments are not served in order.
+    #[cfg(all(feature = "rate-limit-wait", not(target_arch = "wasm32")))]
+    pub fn waiting(mut self) -> Self {
+        self.waiting = true;
+        self
+    }
+
+    /// The largest number of payments per period.
+    pub fn max_paid_requests(&self) -> u32 {
+        self.max_paid_requests
+    }
+
+    /// The period.
+    pub fn per(&self) -> Duration {
+        self.per
+    }
+
+    /// Takes a payment from the bucket, waiting for one if [`Self::waiting`].
+    pub(crate) async fn acquire(&self) -> Result<(), X402Error> {
+        #[cfg(all(feature = "rate-limit-wait", not(target_arch = "wasm32")))]
+        if self.waiting {
+            while let Err(retry_after) = self.take_at(Instant::now()) {
+                tokio::time::sleep(retry_after).await;
+            }
+            return Ok(());
+        }
+        self.take_at(Instant::now())
+            .map_err(|retry_after| X402Error::RateLimited { retry_after })
+    }
+
+    /// Takes a payment from the bucket as of `now`, or returns how long until one is
+    /// available.
+    fn take_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().expect("rate limit poisoned");
+        let max = f64::from(self.max_paid_requests);
+        let refill = now.saturating_duration_since(bucket.updated).as_secs_f64() * max
+            / self.per.as_secs_f64();
+        bucket.available = (bucket.available + refill).min(max);
+        bucket.updated = now;
+        if bucket.available >= 1.0 {
+            bucket.available -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - bucket.available;
+            Err(Duration::from_secs_f64(
+                missing * self.per.as_secs_f64() / max,
+            ))
+        }
+    }
+}
+
+impl Debug for RateLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("max_paid_requests", &self.max_paid_requests)
+            .field("per", &self.per)
+            .field("waiting", &self.waiting)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_up_to_the_limit() {
+        let rate_limit = RateLimit::new(3, Duration::from_secs(60));
+        let start = Instant::now();
+        for _ in 0..3 {
+            rate_limit.take_at(start).unwrap();
+        }
+        let retry_after = rate_limit.take_at(start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(20));
+    }
+
+    #[test]
+    fn refills_over_the_period() {
+        let rate_limit = RateLimit::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        rate_limit.take_at(start).unwrap();
+        rate_limit.take_at(start).unwrap();
+
+        let later = start + Duration::from_secs(15);
+        assert_eq!(
+            rate_limit.take_at(later).unwrap_err(),
+            Duration::from_secs(15)
+        );
+        rate_limit.take_at(later + Duration::from_secs(15)).unwrap();
+        assert!(rate_limit.take_at(later + Duration::from_secs(15)).is_err());
+
+        // An idle period refills the bucket, but no more than the limit
+        let idle = later + Duration::from_secs(600);
+        rate_limit.take_at(idle).unwrap();
+        rate_limit.take_at(idle).unwrap();
+        assert!(rate_limit.take_at(idle).is_err());
+    }
+
+    #[test]
+    fn clones_share_the_bucket() {
+        let rate_limit = RateLimit::new(1, Duration::from_secs(60));
+        let clone = rate_limit.clone();
+        let start = Instant::now();
+        rate_limit.take_at(start).unwrap();
+        assert!(clone.take_at(start).is_err());
+    }
+}
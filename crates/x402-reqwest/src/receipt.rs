@@ -0,0 +1,90 @@
+//! Receipts of the payments made by the client.
+//!
+//! When a paid request settles, the server answers with a `Payment-Response` (V2) or
+//! `X-Payment-Response` (V1) header carrying the settlement result. The [`X402Client`](crate::X402Client)
+//! middleware decodes it, and combines it with the payment it signed into a
+//! [`PaymentReceipt`], stored in the response extensions and read with
+//! [`ResponseExt::payment_receipt`](crate::ResponseExt::payment_receipt).
+//!
+//! [`X402Client::on_payment`](crate::X402Client::on_payment) registers a callback called with
+//! each receipt, e.g. for logging or accounting. A receipt is only made for a settled payment:
+//! a failed settlement, or a header that cannot be decoded, yields none. A malformed header is
+//! logged as a warning with the `telemetry` feature, and does not fail the request.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::receipt::PaymentReceipt;
+//! use x402_reqwest::{ResponseExt, X402Client};
+//!
+//! let client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .on_payment(|receipt: PaymentReceipt| {
+//!         println!("Paid {} for {} in {}", receipt.amount, receipt.resource_url, receipt.transaction);
+//!     });
+//!
+//! let response = http_client.get(url).send().await?;
+//! if let Some(receipt) = response.payment_receipt() {
+//!     println!("Transaction: {}", receipt.transaction);
+//! }
+//! ```
+
+use alloy_primitives::U256;
+use reqwest::Url;
+use std::sync::Arc;
+use x402_types::chain::ChainId;
+use x402_types::proto;
+
+use crate::approval::PendingPayment;
+
+/// A settled payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentReceipt {
+    /// The URL of the paid resource.
+    pub resource_url: Url,
+    /// The chain the payment was made on.
+    pub chain_id: ChainId,
+    /// The token asset address.
+    pub asset: String,
+    /// The settled amount in token units: the amount reported by the server, or the signed
+    /// amount if it reports none.
+    pub amount: U256,
+    /// The seller address receiving the payment.
+    pub pay_to: String,
+    /// The payer address, if reported by the server.
+    pub payer: Option<String>,
+    /// The settlement transaction.
+    pub transaction: String,
+}
+
+impl PaymentReceipt {
+    /// Describes `payment` as settled by `payment_response`, or returns `None` if the
+    /// settlement failed.
+    pub fn new(payment: PendingPayment, payment_response: &proto::PaymentResponse) -> Option<Self> {
+        if !payment_response.success {
+            return None;
+        }
+        let amount = payment_response
+            .amount
+            .as_deref()
+            .and_then(|amount| amount.parse().ok())
+            .unwrap_or(payment.amount);
+        Some(Self {
+            resource_url: payment.resource_url,
+            chain_id: payment.chain_id,
+            asset: payment.asset,
+            amount,
+            pay_to: payment.pay_to,
+            payer: payment_response.payer.clone(),
+            transaction: payment_response.transaction.clone(),
+        })
+    }
+
+    /// Returns the name of the network (e.g. "base"), if it is a known one.
+    pub fn network(&self) -> Option<&'static str> {
+        self.chain_id.as_network_name()
+    }
+}
+
+/// Callback called with each [`PaymentReceipt`].
+pub(crate) type OnPayment = Arc<dyn Fn(PaymentReceipt) + Send + Sync>;
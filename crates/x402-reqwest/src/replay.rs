@@ -0,0 +1,131 @@
+//! Replaying request bodies after a 402.
+//!
+//! A paid request is sent twice: once to get the `402 Payment Required` challenge, and once
+//! more with the payment attached. Bodies held in memory are simply sent again, but a
+//! streaming body, e.g. from [`Body::wrap_stream`](reqwest::Body::wrap_stream) or a
+//! multipart form, is consumed by the first send. [`BodyReplay`], set with
+//! [`X402Client::with_body_replay`](crate::X402Client::with_body_replay), picks how such
+//! bodies are made to survive the 402:
+//!
+//! - [`BodyReplay::Buffer`], the default, reads the stream into memory before the first send,
+//!   up to a limit. A larger body is sent as it streams, and a 402 answering it fails with
+//!   [`X402Error::BodyTooLarge`](x402_types::scheme::client::X402Error::BodyTooLarge).
+//! - [`BodyReplay::Probe`] first sends a `HEAD` request to the same URL to fetch the
+//!   challenge, then sends the body once, with the payment attached. The server must answer
+//!   `HEAD` with the 402, with the requirements in the `Payment-Required` header of x402 V2.
+//!   A body answered with another 402 cannot be paid again, and fails with
+//!   [`X402Error::RequestNotCloneable`](x402_types::scheme::client::X402Error::RequestNotCloneable).
+//!
+//! On `wasm32`, request bodies are always held in memory, and need neither.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::X402Client;
+//! use x402_reqwest::replay::BodyReplay;
+//!
+//! // Upload large files without holding them in memory
+//! let client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_body_replay(BodyReplay::Probe);
+//! ```
+
+use reqwest::{Method, Request};
+
+/// How request bodies that cannot be cloned are replayed after a 402, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyReplay {
+    /// Reads streaming bodies of up to `limit` bytes into memory before sending them.
+    Buffer {
+        /// The largest body buffered, in bytes.
+        limit: usize,
+    },
+    /// Fetches the 402 challenge with a `HEAD` request, then sends the body once.
+    Probe,
+}
+
+impl BodyReplay {
+    /// Bodies buffered by default, 1 MiB.
+    pub const DEFAULT_LIMIT: usize = 1024 * 1024;
+}
+
+impl Default for BodyReplay {
+    fn default() -> Self {
+        BodyReplay::Buffer {
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+}
+
+/// Whether `req` has a streaming body, which cannot be sent twice.
+pub(crate) fn is_streaming(req: &Request) -> bool {
+    req.try_clone().is_none()
+}
+
+/// A `HEAD` request to the URL of `req`, with its headers, to fetch the 402 challenge.
+pub(crate) fn probe(req: &Request) -> Request {
+    let mut probe = Request::new(Method::HEAD, req.url().clone());
+    *probe.headers_mut() = req.headers().clone();
+    probe.headers_mut().remove(http::header::CONTENT_LENGTH);
+    probe.headers_mut().remove(http::header::CONTENT_TYPE);
+    *probe.timeout_mut() = req.timeout().copied();
+    probe
+}
+
+/// Reads the streaming body of `req` into memory if it holds no more than `limit` bytes.
+///
+/// Returns whether the body was buffered. A larger body is put back to be sent as it
+/// streams, starting with the chunks already read.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn buffer(req: &mut Request, limit: usize) -> Result<bool, reqwest::Error> {
+    use http_body_util::BodyExt;
+
+    let Some(mut body) = req.body_mut().take() else {
+        return Ok(true);
+    };
+    let mut chunks = Vec::new();
+    let mut len = 0;
+    while let Some(frame) = body.frame().await {
+        if let Ok(chunk) = frame?.into_data() {
+            len += chunk.len();
+            chunks.push(chunk);
+        }
+        if len > limit {
+            *req.body_mut() = Some(reqwest::Body::wrap(Resumed { chunks, rest: body }));
+            return Ok(false);
+        }
+    }
+    *req.body_mut() = Some(chunks.concat().into());
+    Ok(true)
+}
+
+/// Request bodies are held in memory on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn buffer(_req: &mut Request, _limit: usize) -> Result<bool, reqwest::Error> {
+    Ok(true)
+}
+
+/// A streaming body resumed after `chunks` were read from it.
+#[cfg(not(target_arch = "wasm32"))]
+struct Resumed {
+    chunks: Vec<bytes::Bytes>,
+    rest: reqwest::Body,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl http_body::Body for Resumed {
+    type Data = bytes::Bytes;
+    type Error = reqwest::Error;
+
+    fn poll_frame(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        if !self.chunks.is_empty() {
+            let chunk = self.chunks.remove(0);
+            return std::task::Poll::Ready(Some(Ok(http_body::Frame::data(chunk))));
+        }
+        std::pin::Pin::new(&mut self.rest).poll_frame(cx)
+    }
+}
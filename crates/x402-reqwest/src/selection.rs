@@ -0,0 +1,288 @@
+//! Selection of the cheapest payment option.
+//!
+//! Servers may accept several payment options at different prices, e.g. 0.30 USDC on Base,
+//! 0.28 USDC on Solana or 0.29 EURC. The default [`FirstMatch`](x402_types::scheme::client::FirstMatch)
+//! selector follows the server's order, and [`PreferChain`](x402_types::scheme::client::PreferChain)
+//! a preference order. [`Cheapest`] instead pays with the option of lowest value.
+//!
+//! Each token the client may pay with is registered as a [`BudgetToken`], giving its decimals
+//! and deployments, and valued in USD with [`Cheapest::with_token_values`]. USD stablecoins
+//! (USDC, USDT, DAI, PYUSD) are valued at 1.0 by default. The value of an option is its
+//! amount in whole tokens times the token value.
+//!
+//! Options in a token that is not registered, or has no value, cannot be compared: they are
+//! only selected if no valued option is left, in preference order. With a cap set by
+//! [`Cheapest::max`], they are never selected.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use x402_reqwest::budget::BudgetToken;
+//! use x402_reqwest::selection::Cheapest;
+//!
+//! let usdc = BudgetToken::new("USDC", 6)
+//!     .on(ChainId::new("eip155", "8453"), "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913");
+//! let eurc = BudgetToken::new("EURC", 6)
+//!     .on(ChainId::new("eip155", "8453"), "0x60a3E35Cc302bFA44Cb288Bc5a4F316Fdb1adb42");
+//! let selector = Cheapest::new()
+//!     .with_token(usdc)
+//!     .with_token(eurc)
+//!     .with_token_values([("EURC", 1.08)])
+//!     .max(1.0);
+//! let x402_client = X402Client::new()
+//!     .register(V2Eip155ExactClient::new(signer))
+//!     .with_selector(selector);
+//! ```
+
+use alloy_primitives::U256;
+use std::collections::HashMap;
+use x402_types::chain::ChainIdPattern;
+use x402_types::scheme::client::{PaymentCandidate, PaymentSelector};
+
+use crate::budget::BudgetToken;
+
+/// Symbols of the USD stablecoins valued at 1.0 by default.
+const USD_STABLECOINS: [&str; 4] = ["USDC", "USDT", "DAI", "PYUSD"];
+
+/// Selector paying with the option of lowest USD value. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct Cheapest {
+    tokens: Vec<BudgetToken>,
+    values: HashMap<String, f64>,
+    preference: Vec<ChainIdPattern>,
+    max: Option<f64>,
+}
+
+impl Default for Cheapest {
+    fn default() -> Self {
+        Self {
+            tokens: Vec::new(),
+            values: USD_STABLECOINS
+                .iter()
+                .map(|symbol| (symbol.to_string(), 1.0))
+                .collect(),
+            preference: Vec::new(),
+            max: None,
+        }
+    }
+}
+
+impl Cheapest {
+    /// Creates a selector with no token registered yet, valuing USD stablecoins at 1.0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a token the client may pay with.
+    pub fn with_token(mut self, token: BudgetToken) -> Self {
+        self.tokens.push(token);
+        self
+    }
+
+    /// Sets the USD value of one whole token, by symbol, replacing any previous value.
+    pub fn with_token_values<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = (S, f64)>,
+        S: Into<String>,
+    {
+        self.values.extend(
+            values
+                .into_iter()
+                .map(|(symbol, value)| (symbol.into(), value)),
+        );
+        self
+    }
+
+    /// Breaks ties between options of equal value by chain, in the order of `patterns`.
+    ///
+    /// Options on chains matching no pattern come last. Remaining ties follow the order of
+    /// the options.
+    pub fn prefer<P: Into<Vec<ChainIdPattern>>>(mut self, patterns: P) -> Self {
+        self.preference = patterns.into();
+        self
+    }
+
+    /// Only selects options worth at most `value` USD.
+    pub fn max(mut self, value: f64) -> Self {
+        self.max = Some(value);
+        self
+    }
+
+    /// Returns the USD value of `candidate`, or `None` if its token is not registered or
+    /// has no value.
+    pub fn value_of(&self, candidate: &PaymentCandidate) -> Option<f64> {
+        let token = self
+            .tokens
+            .iter()
+            .find(|token| token.is_deployed(&candidate.chain_id, &candidate.asset))?;
+        let value = self.values.get(token.symbol())?;
+        Some(whole_tokens(candidate.amount, token.decimals()) * value)
+    }
+
+    /// Returns the position of the first preferred chain pattern matching `candidate`.
+    fn preference_of(&self, candidate: &PaymentCandidate) -> usize {
+        self.preference
+            .iter()
+            .position(|pattern| pattern.matches(&candidate.chain_id))
+            .unwrap_or(self.preference.len())
+    }
+}
+
+impl PaymentSelector for Cheapest {
+    fn select<'a>(&self, candidates: &'a [PaymentCandidate]) -> Option<&'a PaymentCandidate> {
+        let valued = candidates
+            .iter()
+            .map(|candidate| (self.value_of(candidate), candidate))
+            .filter(|(value, _)| match self.max {
+                Some(max) => value.is_some_and(|value| value <= max),
+                None => true,
+            });
+        // Valued options first, by value, then by chain preference; `min_by` keeps the
+        // first of equal options
+        valued
+            .min_by(|(a_value, a), (b_value, b)| {
+                let by_value = match (a_value, b_value) {
+                    (Some(a_value), Some(b_value)) => a_value.total_cmp(b_value),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                };
+                by_value.then_with(|| self.preference_of(a).cmp(&self.preference_of(b)))
+            })
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+/// Converts `amount` in atomic units of a token with `decimals` decimals to whole tokens.
+fn whole_tokens(amount: U256, decimals: u8) -> f64 {
+    let amount: f64 = amount.to_string().parse().unwrap_or(f64::INFINITY);
+    amount / 10f64.powi(i32::from(decimals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "eip155:8453";
+    const POLYGON: &str = "eip155:137";
+    const SOLANA: &str = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+    const USDC_BASE: &str = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
+    const USDC_POLYGON: &str = "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359";
+    const USDC_SOLANA: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const EURC_BASE: &str = "0x60a3E35Cc302bFA44Cb288Bc5a4F316Fdb1adb42";
+    const DAI_BASE: &str = "0x50c5725949A6F0c72E6C4a641F24049A917DB0Cb";
+
+    struct NoSigner;
+
+    #[async_trait::async_trait]
+    impl x402_types::scheme::client::PaymentCandidateSigner for NoSigner {
+        async fn sign_payment(&self) -> Result<String, x402_types::scheme::client::X402Error> {
+            unreachable!()
+        }
+    }
+
+    /// A payment of `amount` atomic units of `asset`.
+    fn payment(chain_id: &str, asset: &str, amount: u128) -> PaymentCandidate {
+        PaymentCandidate {
+            chain_id: chain_id.parse().unwrap(),
+            asset: asset.into(),
+            amount: U256::from(amount),
+            scheme: "exact".into(),
+            x402_version: 2,
+            pay_to: "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into(),
+            signer: Box::new(NoSigner),
+        }
+    }
+
+    fn selector() -> Cheapest {
+        Cheapest::new()
+            .with_token(
+                BudgetToken::new("USDC", 6)
+                    .on(BASE.parse().unwrap(), USDC_BASE)
+                    .on(POLYGON.parse().unwrap(), USDC_POLYGON)
+                    .on(SOLANA.parse().unwrap(), USDC_SOLANA),
+            )
+            .with_token(BudgetToken::new("EURC", 6).on(BASE.parse().unwrap(), EURC_BASE))
+            .with_token(BudgetToken::new("DAI", 18).on(BASE.parse().unwrap(), DAI_BASE))
+            .with_token_values([("EURC", 1.08)])
+    }
+
+    fn selected(selector: &Cheapest, candidates: &[PaymentCandidate]) -> Option<usize> {
+        let selected = selector.select(candidates)?;
+        candidates
+            .iter()
+            .position(|candidate| std::ptr::eq(candidate, selected))
+    }
+
+    #[test]
+    fn selects_the_cheapest_option() {
+        let candidates = [
+            payment(BASE, USDC_BASE, 300_000),
+            payment(SOLANA, USDC_SOLANA, 280_000),
+            payment(BASE, EURC_BASE, 290_000),
+        ];
+        assert_eq!(selected(&selector(), &candidates), Some(1));
+
+        // 0.29 EURC is worth more than 0.30 USDC
+        let value = selector().value_of(&candidates[2]).unwrap();
+        assert!((value - 0.3132).abs() < 1e-9);
+    }
+
+    #[test]
+    fn compares_tokens_of_different_decimals() {
+        let candidates = [
+            payment(BASE, USDC_BASE, 300_000),
+            // 0.27 DAI, with 18 decimals
+            payment(BASE, DAI_BASE, 270_000_000_000_000_000),
+        ];
+        assert_eq!(selected(&selector(), &candidates), Some(1));
+
+        let candidates = [
+            payment(BASE, DAI_BASE, 300_000_000_000_000_000),
+            payment(BASE, USDC_BASE, 300_000),
+        ];
+        assert_eq!(selector().value_of(&candidates[0]), Some(0.3));
+        assert_eq!(selector().value_of(&candidates[1]), Some(0.3));
+        // Equal values keep the order of the options
+        assert_eq!(selected(&selector(), &candidates), Some(0));
+    }
+
+    #[test]
+    fn breaks_ties_by_chain_preference() {
+        let candidates = [
+            payment(BASE, USDC_BASE, 100_000),
+            payment(POLYGON, USDC_POLYGON, 100_000),
+            payment(SOLANA, USDC_SOLANA, 200_000),
+        ];
+        let selector =
+            selector().prefer(vec!["solana:*".parse().unwrap(), POLYGON.parse().unwrap()]);
+        assert_eq!(selected(&selector, &candidates), Some(1));
+    }
+
+    #[test]
+    fn ranks_options_without_a_value_last() {
+        let unknown = "0x0000000000000000000000000000000000000001";
+        let candidates = [payment(BASE, unknown, 1), payment(BASE, USDC_BASE, 300_000)];
+        assert_eq!(selector().value_of(&candidates[0]), None);
+        assert_eq!(selected(&selector(), &candidates), Some(1));
+
+        // Registered, but not valued
+        let selector =
+            selector().with_token(BudgetToken::new("WETH", 18).on(BASE.parse().unwrap(), unknown));
+        assert_eq!(selector.value_of(&candidates[0]), None);
+        assert_eq!(selected(&selector, &candidates[..1]), Some(0));
+    }
+
+    #[test]
+    fn respects_the_max_cap() {
+        let candidates = [
+            payment(BASE, USDC_BASE, 3_000_000),
+            payment(BASE, EURC_BASE, 2_000_000),
+            payment(BASE, "0x0000000000000000000000000000000000000001", 1),
+        ];
+        let selector = selector().max(2.5);
+        assert_eq!(selected(&selector, &candidates), Some(1));
+        let selector = selector.max(2.0);
+        assert_eq!(selected(&selector, &candidates), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Payments are signed only once approved.
+
+mod common;
+
+use alloy_primitives::U256;
+use common::{BASE, FixedSchemeClient, PAY_TO, USDC_BASE, http_client, paid, seller};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+use x402_reqwest::X402Client;
+use x402_reqwest::approval::{Approval, AutoApprove, PendingPayment};
+use x402_types::scheme::client::X402Error;
+
+/// Scheme client paying 0.25 USDC on Base.
+fn scheme_client() -> FixedSchemeClient {
+    FixedSchemeClient::new().paying(BASE, USDC_BASE, 250_000)
+}
+
+fn x402_error(error: reqwest_middleware::Error) -> X402Error {
+    match error {
+        reqwest_middleware::Error::Middleware(error) => error.downcast().unwrap(),
+        other => panic!("expected a middleware error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn approved_payments_are_sent() {
+    let seller = seller(paid()).await;
+    let asked = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let client = http_client(X402Client::new().register(scheme_client()).with_approval({
+        let asked = asked.clone();
+        move |payment: PendingPayment| {
+            asked.lock().unwrap().push(payment);
+            async { Approval::Approve }
+        }
+    }));
+
+    let url = format!("{}/report", seller.uri());
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let asked = asked.lock().unwrap();
+    assert_eq!(asked.len(), 1);
+    assert_eq!(asked[0].resource_url.as_str(), url);
+    assert_eq!(asked[0].network(), Some("base"));
+    assert_eq!(asked[0].amount, U256::from(250_000));
+    assert_eq!(asked[0].pay_to, PAY_TO);
+}
+
+#[tokio::test]
+async fn denied_payments_fail_the_request() {
+    let seller = seller(paid()).await;
+    let client = http_client(
+        X402Client::new()
+            .register(scheme_client())
+            .with_approval(|_payment: PendingPayment| async { Approval::Deny }),
+    );
+
+    let error = client.get(seller.uri()).send().await.unwrap_err();
+    assert!(matches!(x402_error(error), X402Error::PaymentDenied));
+    let requests = seller.received_requests().await.unwrap();
+    assert!(
+        requests
+            .iter()
+            .all(|request| !request.headers.contains_key("Payment-Signature"))
+    );
+}
+
+#[tokio::test]
+async fn capped_approvals_cover_later_payments() {
+    let seller = seller(paid()).await;
+    let asked = Arc::new(AtomicUsize::new(0));
+    let client = http_client(X402Client::new().register(scheme_client()).with_approval({
+        let asked = asked.clone();
+        move |_payment: PendingPayment| {
+            let first = asked.fetch_add(1, Ordering::SeqCst) == 0;
+            async move {
+                if first {
+                    // Two payments of 0.25 USDC
+                    Approval::ApproveWithCap(U256::from(500_000))
+                } else {
+                    Approval::Deny
+                }
+            }
+        }
+    }));
+
+    for _ in 0..2 {
+        let response = client.get(seller.uri()).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    assert_eq!(asked.load(Ordering::SeqCst), 1);
+
+    // The cap is spent: the approver is asked again
+    let error = client.get(seller.uri()).send().await.unwrap_err();
+    assert!(matches!(x402_error(error), X402Error::PaymentDenied));
+    assert_eq!(asked.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn caps_below_the_amount_deny_the_payment() {
+    let seller = seller(paid()).await;
+    let client = http_client(X402Client::new().register(scheme_client()).with_approval(
+        |_payment: PendingPayment| async { Approval::ApproveWithCap(U256::from(100_000)) },
+    ));
+
+    let error = client.get(seller.uri()).send().await.unwrap_err();
+    assert!(matches!(x402_error(error), X402Error::PaymentDenied));
+}
+
+#[tokio::test]
+async fn auto_approve_pays_without_asking() {
+    let seller = seller(paid()).await;
+    let client = http_client(
+        X402Client::new()
+            .register(scheme_client())
+            .with_approval(AutoApprove),
+    );
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn slow_approvals_do_not_hold_up_other_requests() {
+    let seller = seller(paid()).await;
+    let answer = Arc::new(Notify::new());
+    let client = http_client(X402Client::new().register(scheme_client()).with_approval({
+        let answer = answer.clone();
+        move |payment: PendingPayment| {
+            let answer = answer.clone();
+            async move {
+                // Payments for `/slow` wait for the user to answer
+                if payment.resource_url.path() == "/slow" {
+                    answer.notified().await;
+                }
+                Approval::Approve
+            }
+        }
+    }));
+
+    let slow = tokio::spawn({
+        let client = client.clone();
+        let url = format!("{}/slow", seller.uri());
+        async move { client.get(&url).send().await.unwrap().status() }
+    });
+    let fast = client.get(format!("{}/fast", seller.uri())).send();
+    let fast = tokio::time::timeout(Duration::from_secs(5), fast)
+        .await
+        .expect("a pending approval held up another request")
+        .unwrap();
+    assert_eq!(fast.status(), 200);
+    assert!(!slow.is_finished());
+
+    answer.notify_one();
+    assert_eq!(slow.await.unwrap(), 200);
+}
@@ -0,0 +1,188 @@
+//! Streaming request bodies are sent in full with the payment.
+
+mod common;
+
+use common::{FixedSchemeClient, http_client, paid, seller};
+use reqwest::multipart::{Form, Part};
+use reqwest_middleware::ClientWithMiddleware;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_reqwest::X402Client;
+use x402_reqwest::replay::BodyReplay;
+use x402_types::scheme::client::X402Error;
+
+fn replaying_client(body_replay: BodyReplay) -> ClientWithMiddleware {
+    http_client(
+        X402Client::new()
+            .register(FixedSchemeClient::new().with_payload("signed payment"))
+            .with_body_replay(body_replay),
+    )
+}
+
+fn streaming_body() -> reqwest::Body {
+    let chunks = ["first chunk, ", "second chunk, ", "last chunk"];
+    reqwest::Body::wrap_stream(futures_util::stream::iter(
+        chunks.map(Ok::<_, std::io::Error>),
+    ))
+}
+
+fn multipart_form() -> Form {
+    let file = Part::stream(streaming_body()).file_name("upload.txt");
+    Form::new()
+        .text("title", "quarterly report")
+        .part("file", file)
+}
+
+/// Returns the methods and bodies of the requests received by `seller`, paid or not.
+async fn received(seller: &MockServer) -> Vec<(String, bool, String)> {
+    let requests = seller.received_requests().await.unwrap();
+    requests
+        .iter()
+        .map(|request| {
+            (
+                request.method.to_string(),
+                request.headers.contains_key("Payment-Signature"),
+                String::from_utf8(request.body.clone()).unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn buffered_streaming_bodies_are_sent_again_in_full() {
+    let seller = seller(paid()).await;
+    let response = replaying_client(BodyReplay::default())
+        .post(format!("{}/upload", seller.uri()))
+        .body(streaming_body())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body = "first chunk, second chunk, last chunk".to_string();
+    assert_eq!(
+        received(&seller).await,
+        vec![
+            ("POST".to_string(), false, body.clone()),
+            ("POST".to_string(), true, body),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn buffered_multipart_bodies_are_sent_again_in_full() {
+    let seller = seller(paid()).await;
+    let response = replaying_client(BodyReplay::default())
+        .post(format!("{}/upload", seller.uri()))
+        .multipart(multipart_form())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let received = received(&seller).await;
+    assert_eq!(received.len(), 2);
+    let (_, paid, body) = &received[1];
+    assert!(paid);
+    assert_eq!(body, &received[0].2);
+    assert!(body.contains("quarterly report"));
+    assert!(body.contains("first chunk, second chunk, last chunk"));
+}
+
+#[tokio::test]
+async fn bodies_over_the_buffer_limit_are_not_paid_for() {
+    let seller = seller(paid()).await;
+    let error = replaying_client(BodyReplay::Buffer { limit: 16 })
+        .post(format!("{}/upload", seller.uri()))
+        .body(streaming_body())
+        .send()
+        .await
+        .unwrap_err();
+    let error: X402Error = match error {
+        reqwest_middleware::Error::Middleware(error) => error.downcast().unwrap(),
+        error => panic!("unexpected error: {error}"),
+    };
+    assert!(matches!(error, X402Error::BodyTooLarge { limit: 16 }));
+
+    // The body was still sent in full, once
+    assert_eq!(
+        received(&seller).await,
+        vec![(
+            "POST".to_string(),
+            false,
+            "first chunk, second chunk, last chunk".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn probed_streaming_bodies_are_sent_once_with_the_payment() {
+    let seller = seller(paid()).await;
+    let response = replaying_client(BodyReplay::Probe)
+        .post(format!("{}/upload", seller.uri()))
+        .body(streaming_body())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    assert_eq!(
+        received(&seller).await,
+        vec![
+            ("HEAD".to_string(), false, String::new()),
+            (
+                "POST".to_string(),
+                true,
+                "first chunk, second chunk, last chunk".to_string()
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn probed_multipart_bodies_are_sent_once_with_the_payment() {
+    let seller = seller(paid()).await;
+    let response = replaying_client(BodyReplay::Probe)
+        .post(format!("{}/upload", seller.uri()))
+        .multipart(multipart_form())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let received = received(&seller).await;
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0], ("HEAD".to_string(), false, String::new()));
+    let (method, paid, body) = &received[1];
+    assert_eq!(method, "POST");
+    assert!(paid);
+    assert!(body.contains("quarterly report"));
+    assert!(body.contains("first chunk, second chunk, last chunk"));
+}
+
+#[tokio::test]
+async fn probed_requests_to_free_routes_are_sent_once() {
+    let seller = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(200).set_body_string("free content"))
+        .mount(&seller)
+        .await;
+    let response = replaying_client(BodyReplay::Probe)
+        .post(format!("{}/upload", seller.uri()))
+        .body(streaming_body())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    assert_eq!(
+        received(&seller).await,
+        vec![
+            ("HEAD".to_string(), false, String::new()),
+            (
+                "POST".to_string(),
+                false,
+                "first chunk, second chunk, last chunk".to_string()
+            ),
+        ]
+    );
+}
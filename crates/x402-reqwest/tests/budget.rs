@@ -0,0 +1,50 @@
+//! A client with a budget refuses to pay once it is spent.
+
+mod common;
+
+use common::{BASE_SEPOLIA, FixedSchemeClient, USDC_BASE_SEPOLIA, payment_required_response};
+use x402_reqwest::X402Client;
+use x402_reqwest::budget::{Budget, BudgetToken};
+use x402_types::scheme::client::X402Error;
+use x402_types::timestamp::UnixTimestamp;
+
+#[tokio::test]
+async fn payments_stop_when_the_budget_is_spent() {
+    let usdc = BudgetToken::new("USDC", 6).on(BASE_SEPOLIA.parse().unwrap(), USDC_BASE_SEPOLIA);
+    // Two payments of 100 atomic units fit
+    let budget = Budget::per_hour("0.0002 USDC".parse().unwrap())
+        .with_token(usdc)
+        .with_clock(|| UnixTimestamp::from_secs(1_000_000));
+    let client = X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_budget(budget);
+
+    for _ in 0..2 {
+        client
+            .make_payment_headers(payment_required_response())
+            .await
+            .unwrap();
+    }
+    let error = client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        X402Error::BudgetExhausted { resets_at: Some(resets_at) }
+            if resets_at == UnixTimestamp::from_secs(1_000_000 + 60 * 60)
+    ));
+}
+
+#[tokio::test]
+async fn payments_in_unregistered_tokens_are_refused() {
+    let budget = Budget::total("1 USDC".parse().unwrap());
+    let client = X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_budget(budget);
+    let error = client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, X402Error::NoMatchingPaymentOption));
+}
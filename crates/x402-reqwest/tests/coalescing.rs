@@ -0,0 +1,139 @@
+//! Concurrent requests to the same resource share the challenge, and cacheable responses.
+
+mod common;
+
+use common::{BASE, SchemeClientFn, USDC_BASE, candidate, http_client, paid, seller};
+use futures_util::future::join_all;
+use reqwest_middleware::ClientWithMiddleware;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use wiremock::{MockServer, ResponseTemplate};
+use x402_reqwest::coalesce::Coalescing;
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::scheme::client::{PaymentCandidateSigner, X402Error};
+use x402_types::util::Base64Bytes;
+
+const CONCURRENT: usize = 5;
+
+/// Signer signing a distinct payload each time.
+struct CountingSigner;
+
+#[async_trait::async_trait]
+impl PaymentCandidateSigner for CountingSigner {
+    async fn sign_payment(&self) -> Result<String, X402Error> {
+        static SIGNED: AtomicUsize = AtomicUsize::new(0);
+        let nonce = SIGNED.fetch_add(1, Ordering::Relaxed);
+        Ok(Base64Bytes::encode(format!("signed-{nonce}")).to_string())
+    }
+}
+
+/// A paid response, slow enough for concurrent requests to wait on each other, with
+/// `cache_control` if any.
+fn slowly_paid(cache_control: Option<&str>) -> ResponseTemplate {
+    let paid = paid().set_delay(Duration::from_millis(100));
+    match cache_control {
+        Some(cache_control) => paid.insert_header("Cache-Control", cache_control),
+        None => paid,
+    }
+}
+
+/// The number of challenges the seller answered, and the payments it received.
+async fn received(seller: &MockServer) -> (usize, Vec<String>) {
+    let requests = seller.received_requests().await.unwrap();
+    let payments: Vec<String> = requests
+        .iter()
+        .filter_map(|request| request.headers.get("Payment-Signature"))
+        .map(|header| header.to_str().unwrap().to_owned())
+        .collect();
+    (requests.len() - payments.len(), payments)
+}
+
+fn coalescing_client() -> ClientWithMiddleware {
+    http_client(
+        X402Client::new()
+            .register(SchemeClientFn::new(|_, _| {
+                vec![candidate(BASE, USDC_BASE, 250_000, CountingSigner)]
+            }))
+            .with_coalescing(Coalescing::default()),
+    )
+}
+
+#[tokio::test]
+async fn cacheable_responses_are_paid_once() {
+    let seller = seller(slowly_paid(Some("public, max-age=60"))).await;
+    let client = coalescing_client();
+
+    let url = format!("{}/feed", seller.uri());
+    let responses = join_all((0..CONCURRENT).map(|_| client.get(&url).send())).await;
+
+    let mut paid = 0;
+    for response in responses {
+        let response = response.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["cache-control"], "public, max-age=60");
+        if response.payment_id().is_some() {
+            paid += 1;
+        }
+        assert_eq!(response.text().await.unwrap(), "paid content");
+    }
+    assert_eq!(paid, 1);
+    let (challenges, payments) = received(&seller).await;
+    assert_eq!(challenges, 1);
+    assert_eq!(payments.len(), 1);
+}
+
+#[tokio::test]
+async fn uncacheable_responses_share_only_the_challenge() {
+    let seller = seller(slowly_paid(None)).await;
+    let client = coalescing_client();
+
+    let url = format!("{}/feed", seller.uri());
+    let responses = join_all((0..CONCURRENT).map(|_| client.get(&url).send())).await;
+
+    let mut payment_ids = HashSet::new();
+    for response in responses {
+        let response = response.unwrap();
+        assert_eq!(response.status(), 200);
+        payment_ids.insert(response.payment_id().unwrap().to_owned());
+    }
+    assert_eq!(payment_ids.len(), CONCURRENT);
+    let (challenges, payments) = received(&seller).await;
+    assert_eq!(challenges, 1);
+    // Each request was settled with its own authorization
+    assert_eq!(payments.iter().collect::<HashSet<_>>().len(), CONCURRENT);
+}
+
+#[tokio::test]
+async fn no_store_responses_are_not_shared() {
+    let seller = seller(slowly_paid(Some("public, max-age=60, no-store"))).await;
+    let client = coalescing_client();
+
+    let url = format!("{}/feed", seller.uri());
+    let responses = join_all((0..CONCURRENT).map(|_| client.get(&url).send())).await;
+
+    assert!(
+        responses
+            .iter()
+            .all(|response| response.as_ref().unwrap().status() == 200)
+    );
+    let (challenges, payments) = received(&seller).await;
+    assert_eq!(challenges, 1);
+    assert_eq!(payments.len(), CONCURRENT);
+}
+
+#[tokio::test]
+async fn sequential_requests_are_not_coalesced() {
+    let seller = seller(slowly_paid(Some("public, max-age=60"))).await;
+    let client = coalescing_client();
+
+    let url = format!("{}/feed", seller.uri());
+    for _ in 0..2 {
+        let response = client.get(&url).send().await.unwrap();
+        assert!(response.payment_id().is_some());
+    }
+
+    let (challenges, payments) = received(&seller).await;
+    assert_eq!(challenges, 2);
+    assert_eq!(payments.len(), 2);
+}
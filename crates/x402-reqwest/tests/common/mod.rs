@@ -0,0 +1,227 @@
+//! Fixtures shared by the integration tests: scheme clients signing canned payloads, and the
+//! 402 challenges of a seller.
+
+#![allow(dead_code)] // Each test uses some of the fixtures only.
+
+use alloy_primitives::U256;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use serde_json::{Value, json};
+use wiremock::matchers::{any, header_exists};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_reqwest::X402Client;
+use x402_types::proto;
+use x402_types::scheme::X402SchemeId;
+use x402_types::scheme::client::{
+    FirstMatch, PaymentCandidate, PaymentCandidateSigner, SigningOptions, X402Error,
+    X402SchemeClient,
+};
+use x402_types::util::Base64Bytes;
+
+pub const BASE_SEPOLIA: &str = "eip155:84532";
+pub const USDC_BASE_SEPOLIA: &str = "0x036CbD53842c5426634e7929541eC2318f3dCF7e";
+pub const BASE: &str = "eip155:8453";
+pub const USDC_BASE: &str = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
+pub const PAY_TO: &str = "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07";
+
+/// Scheme client that accepts any requirement and signs a fixed payload.
+///
+/// Pays 100 units of USDC on Base Sepolia with a V2 payment signing `signed` by default.
+pub struct FixedSchemeClient {
+    chain_id: &'static str,
+    asset: &'static str,
+    amount: u64,
+    x402_version: u8,
+    payload: Vec<u8>,
+}
+
+impl FixedSchemeClient {
+    pub fn new() -> Self {
+        Self {
+            chain_id: BASE_SEPOLIA,
+            asset: USDC_BASE_SEPOLIA,
+            amount: 100,
+            x402_version: 2,
+            payload: b"signed".to_vec(),
+        }
+    }
+
+    /// Pays `amount` of `asset` on `chain_id` instead.
+    pub fn paying(mut self, chain_id: &'static str, asset: &'static str, amount: u64) -> Self {
+        self.chain_id = chain_id;
+        self.asset = asset;
+        self.amount = amount;
+        self
+    }
+
+    pub fn with_x402_version(mut self, x402_version: u8) -> Self {
+        self.x402_version = x402_version;
+        self
+    }
+
+    pub fn with_payload(mut self, payload: impl AsRef<[u8]>) -> Self {
+        self.payload = payload.as_ref().to_vec();
+        self
+    }
+}
+
+impl X402SchemeId for FixedSchemeClient {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "exact"
+    }
+}
+
+impl X402SchemeClient for FixedSchemeClient {
+    fn accept(&self, _payment_required: &proto::PaymentRequired) -> Vec<PaymentCandidate> {
+        let mut candidate = candidate(
+            self.chain_id,
+            self.asset,
+            self.amount,
+            FixedSigner(self.payload.clone()),
+        );
+        candidate.x402_version = self.x402_version;
+        vec![candidate]
+    }
+}
+
+/// EVM `exact` scheme client offering the candidates its closure returns.
+pub struct SchemeClientFn<F>(F);
+
+impl<F> SchemeClientFn<F>
+where
+    F: Fn(&proto::PaymentRequired, &SigningOptions) -> Vec<PaymentCandidate> + Send + Sync,
+{
+    pub fn new(accept: F) -> Self {
+        Self(accept)
+    }
+}
+
+impl<F> X402SchemeId for SchemeClientFn<F> {
+    fn namespace(&self) -> &str {
+        "eip155"
+    }
+
+    fn scheme(&self) -> &str {
+        "exact"
+    }
+}
+
+impl<F> X402SchemeClient for SchemeClientFn<F>
+where
+    F: Fn(&proto::PaymentRequired, &SigningOptions) -> Vec<PaymentCandidate> + Send + Sync,
+{
+    fn accept(&self, payment_required: &proto::PaymentRequired) -> Vec<PaymentCandidate> {
+        self.accept_with_options(payment_required, &SigningOptions::default())
+    }
+
+    fn accept_with_options(
+        &self,
+        payment_required: &proto::PaymentRequired,
+        options: &SigningOptions,
+    ) -> Vec<PaymentCandidate> {
+        (self.0)(payment_required, options)
+    }
+}
+
+/// Signer signing a fixed payload.
+pub struct FixedSigner(pub Vec<u8>);
+
+#[async_trait::async_trait]
+impl PaymentCandidateSigner for FixedSigner {
+    async fn sign_payment(&self) -> Result<String, X402Error> {
+        Ok(Base64Bytes::encode(&self.0).to_string())
+    }
+}
+
+/// A V2 `exact` candidate paying `amount` of `asset` on `chain_id` to [`PAY_TO`].
+pub fn candidate(
+    chain_id: &str,
+    asset: &str,
+    amount: u64,
+    signer: impl PaymentCandidateSigner + Send + Sync + 'static,
+) -> PaymentCandidate {
+    PaymentCandidate {
+        chain_id: chain_id.parse().unwrap(),
+        asset: asset.into(),
+        amount: U256::from(amount),
+        scheme: "exact".into(),
+        x402_version: 2,
+        pay_to: PAY_TO.into(),
+        signer: Box::new(signer),
+    }
+}
+
+/// V2 `exact` requirements asking for `amount` of `asset` on `network`, paid to [`PAY_TO`].
+pub fn requirements(network: &str, asset: &str, amount: u64) -> Value {
+    json!({
+        "scheme": "exact",
+        "network": network,
+        "amount": amount.to_string(),
+        "payTo": PAY_TO,
+        "maxTimeoutSeconds": 300,
+        "asset": asset,
+        "extra": null,
+    })
+}
+
+/// A V2 challenge for `http://localhost/protected` accepting `accepts`.
+pub fn payment_required(accepts: Vec<Value>) -> Value {
+    json!({
+        "x402Version": 2,
+        "resource": { "url": "http://localhost/protected" },
+        "accepts": accepts,
+    })
+}
+
+/// Encodes `value` as a payment header.
+pub fn encode(value: &Value) -> String {
+    Base64Bytes::encode(serde_json::to_vec(value).unwrap()).to_string()
+}
+
+/// A 402 response with `payment_required` in its `Payment-Required` header.
+pub fn challenge(payment_required: &Value) -> ResponseTemplate {
+    ResponseTemplate::new(402).insert_header("Payment-Required", encode(payment_required))
+}
+
+/// A 200 response serving the paid content.
+pub fn paid() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_string("paid content")
+}
+
+/// A seller asking for 0.25 USDC on Base, and answering paid requests with `paid`.
+pub async fn seller(paid: ResponseTemplate) -> MockServer {
+    let payment_required = payment_required(vec![requirements(BASE, USDC_BASE, 250_000)]);
+    let seller = MockServer::start().await;
+    Mock::given(header_exists("Payment-Signature"))
+        .respond_with(paid)
+        .with_priority(1)
+        .mount(&seller)
+        .await;
+    Mock::given(any())
+        .respond_with(challenge(&payment_required))
+        .mount(&seller)
+        .await;
+    seller
+}
+
+/// An HTTP client paying with `x402`.
+pub fn http_client(x402: X402Client<FirstMatch>) -> ClientWithMiddleware {
+    ClientBuilder::new(reqwest::Client::new())
+        .with(x402)
+        .build()
+}
+
+/// A 402 response asking for 100 units of USDC on Base Sepolia.
+pub fn payment_required_response() -> reqwest::Response {
+    let payment_required =
+        payment_required(vec![requirements(BASE_SEPOLIA, USDC_BASE_SEPOLIA, 100)]);
+    http::Response::builder()
+        .status(402)
+        .header("Payment-Required", encode(&payment_required))
+        .body(Vec::<u8>::new())
+        .unwrap()
+        .into()
+}
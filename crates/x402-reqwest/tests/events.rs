@@ -0,0 +1,179 @@
+//! Each step of paying for a request is published as a payment event.
+
+mod common;
+
+use alloy_primitives::U256;
+use common::{BASE, FixedSchemeClient, PAY_TO, USDC_BASE, http_client, paid, seller};
+use reqwest_middleware::ClientWithMiddleware;
+use tokio::sync::broadcast;
+use wiremock::ResponseTemplate;
+use x402_reqwest::budget::{Budget, BudgetToken};
+use x402_reqwest::events::{PaymentEvent, PaymentEventKind, PaymentEvents};
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::proto;
+use x402_types::scheme::client::{FirstMatch, X402Error};
+
+const TRANSACTION: &str = "0x9c9a59f9e3b1e4d9c0bcbd3c8e9fd6a3e25fc8f6f1a2c4b9d3e7f0a1b2c3d4e5";
+
+/// Scheme client paying 0.25 USDC on Base.
+fn scheme_client() -> FixedSchemeClient {
+    FixedSchemeClient::new().paying(BASE, USDC_BASE, 250_000)
+}
+
+/// A paid response settling the payment.
+fn settled() -> ResponseTemplate {
+    let settled = proto::PaymentResponse {
+        success: true,
+        error_reason: None,
+        payer: None,
+        transaction: TRANSACTION.into(),
+        network: BASE.into(),
+        amount: None,
+        extensions: Default::default(),
+    };
+    paid().insert_header(
+        proto::PaymentResponse::V2_HEADER_NAME,
+        settled.to_header().unwrap().to_string(),
+    )
+}
+
+/// An HTTP client paying with `x402`, along with a subscription to its events.
+fn subscribed(
+    x402: X402Client<FirstMatch>,
+) -> (ClientWithMiddleware, broadcast::Receiver<PaymentEvent>) {
+    let events = x402.events();
+    (http_client(x402), events)
+}
+
+/// The events received so far.
+fn received(events: &mut broadcast::Receiver<PaymentEvent>) -> Vec<PaymentEvent> {
+    std::iter::from_fn(|| events.try_recv().ok()).collect()
+}
+
+#[tokio::test]
+async fn successful_payments_publish_each_step() {
+    let seller = seller(settled()).await;
+    let (client, mut events) = subscribed(X402Client::new().register(scheme_client()));
+
+    let url = format!("{}/report", seller.uri());
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let events = received(&mut events);
+    let kinds: Vec<_> = events.iter().map(|event| event.kind.clone()).collect();
+    let payment = match &kinds[1] {
+        PaymentEventKind::CandidateSelected(payment) => payment.clone(),
+        kind => panic!("unexpected event: {kind:?}"),
+    };
+    assert_eq!(payment.resource_url.as_str(), url);
+    assert_eq!(payment.asset, USDC_BASE);
+    assert_eq!(payment.amount, U256::from(250_000));
+    assert_eq!(payment.pay_to, PAY_TO);
+    assert_eq!(
+        kinds,
+        vec![
+            PaymentEventKind::PaymentRequired {
+                x402_version: 2,
+                options: 1,
+            },
+            PaymentEventKind::CandidateSelected(payment.clone()),
+            PaymentEventKind::PaymentSigned(payment.clone()),
+            PaymentEventKind::RetrySent {
+                payment: payment.clone(),
+                attempt: 1,
+            },
+            PaymentEventKind::PaymentSucceeded {
+                payment,
+                transaction: Some(TRANSACTION.into()),
+            },
+        ]
+    );
+
+    for event in &events {
+        assert_eq!(event.url.as_str(), url);
+        assert_eq!(
+            event.payment_id.as_ref().map(|id| id.as_str()),
+            response.payment_id()
+        );
+    }
+    assert!(
+        events
+            .windows(2)
+            .all(|pair| pair[0].timestamp_ms <= pair[1].timestamp_ms)
+    );
+}
+
+#[tokio::test]
+async fn budget_rejected_payments_publish_the_failure() {
+    let seller = seller(settled()).await;
+    let usdc = BudgetToken::new("USDC", 6).on(BASE.parse().unwrap(), USDC_BASE);
+    let budget = Budget::total("0.10 USDC".parse().unwrap()).with_token(usdc);
+    let (client, mut events) = subscribed(
+        X402Client::new()
+            .register(scheme_client())
+            .with_budget(budget),
+    );
+
+    client.get(seller.uri()).send().await.unwrap_err();
+
+    let kinds: Vec<_> = received(&mut events)
+        .into_iter()
+        .map(|event| event.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            PaymentEventKind::PaymentRequired {
+                x402_version: 2,
+                options: 1,
+            },
+            PaymentEventKind::PaymentFailed {
+                payment: None,
+                reason: X402Error::BudgetExhausted { resets_at: None }.to_string(),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn clients_can_share_a_channel() {
+    let seller = seller(settled()).await;
+    let events = PaymentEvents::new(16);
+    let mut subscription = events.subscribe();
+    let client = http_client(
+        X402Client::new()
+            .register(scheme_client())
+            .with_events(events.clone()),
+    );
+
+    client.get(seller.uri()).send().await.unwrap();
+
+    assert_eq!(received(&mut subscription).len(), 5);
+}
+
+#[tokio::test]
+async fn lagging_subscribers_do_not_hold_up_payments() {
+    let seller = seller(settled()).await;
+    let events = PaymentEvents::new(1);
+    let mut subscription = events.subscribe();
+    let client = http_client(
+        X402Client::new()
+            .register(scheme_client())
+            .with_events(events),
+    );
+
+    for _ in 0..2 {
+        let response = client.get(seller.uri()).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    // Only the last event was kept
+    assert!(matches!(
+        subscription.try_recv(),
+        Err(broadcast::error::TryRecvError::Lagged(9))
+    ));
+    assert!(matches!(
+        subscription.try_recv().unwrap().kind,
+        PaymentEventKind::PaymentSucceeded { .. }
+    ));
+}
@@ -0,0 +1,255 @@
+//! Payments are made with options the facilitator supports.
+
+mod common;
+
+use common::{FixedSigner, SchemeClientFn, USDC_BASE, candidate, http_client, paid, seller};
+use serde_json::{Value, json};
+use std::time::Duration;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_reqwest::X402Client;
+use x402_reqwest::facilitator::FacilitatorSupport;
+use x402_types::proto;
+use x402_types::scheme::client::{X402Error, X402SchemeClient};
+use x402_types::util::Base64Bytes;
+
+/// Scheme client offering to pay on each of `chains`, in order, signing the chain ID.
+fn chains_client(chains: &'static [&'static str]) -> impl X402SchemeClient {
+    SchemeClientFn::new(move |_, _| {
+        chains
+            .iter()
+            .map(|chain| {
+                candidate(
+                    chain,
+                    USDC_BASE,
+                    250_000,
+                    FixedSigner(chain.as_bytes().to_vec()),
+                )
+            })
+            .collect()
+    })
+}
+
+fn supported(kinds: &[&str], signers: Value) -> Value {
+    let kinds: Vec<Value> = kinds
+        .iter()
+        .map(|network| json!({ "x402Version": 2, "scheme": "exact", "network": network }))
+        .collect();
+    json!({ "kinds": kinds, "extensions": [], "signers": signers })
+}
+
+/// A facilitator answering `/supported` with `response`.
+async fn facilitator(response: Value) -> MockServer {
+    let facilitator = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/supported"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response))
+        .mount(&facilitator)
+        .await;
+    facilitator
+}
+
+fn support(facilitator: &MockServer) -> FacilitatorSupport {
+    FacilitatorSupport::try_new(facilitator.uri().parse().unwrap()).unwrap()
+}
+
+/// Returns the chain the seller was paid on.
+async fn paid_chain(seller: &MockServer) -> String {
+    let requests = seller.received_requests().await.unwrap();
+    let header = requests
+        .iter()
+        .rev()
+        .find_map(|request| request.headers.get("Payment-Signature"))
+        .expect("a paid request");
+    let bytes = Base64Bytes::from(header.as_bytes()).auto_decode().unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn unsupported_preferred_option_falls_back_to_the_next() {
+    let seller = seller(paid()).await;
+    let facilitator = facilitator(supported(&["eip155:137"], json!({}))).await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator)),
+    );
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(paid_chain(&seller).await, "eip155:137");
+}
+
+#[tokio::test]
+async fn no_supported_option_fails_without_paying() {
+    let seller = seller(paid()).await;
+    let facilitator = facilitator(supported(&["eip155:10"], json!({}))).await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator)),
+    );
+
+    let error = client.get(seller.uri()).send().await.unwrap_err();
+    match error {
+        reqwest_middleware::Error::Middleware(error) => assert!(matches!(
+            error.downcast().unwrap(),
+            X402Error::NoMatchingPaymentOption
+        )),
+        other => panic!("expected a middleware error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn solana_options_need_a_fee_payer() {
+    const SOLANA: &str = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+    let seller = seller(paid()).await;
+    let client_for = |signers| async move {
+        let facilitator = facilitator(supported(&[SOLANA, "eip155:8453"], signers)).await;
+        let client = http_client(
+            X402Client::new()
+                .register(chains_client(&[SOLANA, "eip155:8453"]))
+                .with_facilitator(support(&facilitator)),
+        );
+        (client, facilitator)
+    };
+
+    let (client, _facilitator) = client_for(json!({ SOLANA: [] })).await;
+    client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(paid_chain(&seller).await, "eip155:8453");
+
+    let fee_payer = "2wKupLR9q6wXYppw8Gr2NvWxKBUqm4PPJKkQfoxHDBg4";
+    let (client, _facilitator) = client_for(json!({ SOLANA: [fee_payer] })).await;
+    client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(paid_chain(&seller).await, SOLANA);
+}
+
+#[tokio::test]
+async fn supported_kinds_are_cached() {
+    let seller = seller(paid()).await;
+    let facilitator = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/supported"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(supported(&["eip155:137"], json!({}))),
+        )
+        .expect(1)
+        .mount(&facilitator)
+        .await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator)),
+    );
+
+    for _ in 0..3 {
+        let response = client.get(seller.uri()).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    facilitator.verify().await;
+}
+
+#[tokio::test]
+async fn stale_kinds_are_used_when_the_facilitator_is_down() {
+    let seller = seller(paid()).await;
+    let facilitator = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/supported"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(supported(&["eip155:137"], json!({}))),
+        )
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&facilitator)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/supported"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&facilitator)
+        .await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator).with_ttl(Duration::ZERO)),
+    );
+
+    for _ in 0..2 {
+        client.get(seller.uri()).send().await.unwrap();
+        assert_eq!(paid_chain(&seller).await, "eip155:137");
+    }
+    let fetches = facilitator.received_requests().await.unwrap().len();
+    assert_eq!(fetches, 2);
+}
+
+#[tokio::test]
+async fn unknown_support_keeps_every_option() {
+    let seller = seller(paid()).await;
+    let facilitator = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&facilitator)
+        .await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator)),
+    );
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(paid_chain(&seller).await, "eip155:8453");
+}
+
+#[tokio::test]
+async fn capabilities_are_sent_to_the_facilitator() {
+    let seller = seller(paid()).await;
+    // The client has no balance on Base, which the facilitator leaves out
+    let capabilities = proto::ClientCapabilities {
+        supported_schemes: vec!["exact".into()],
+        supported_chains: vec!["eip155:*".into()],
+        available_balances: [("eip155:8453".to_string(), "0".to_string())].into(),
+    };
+    let facilitator = facilitator(supported(&["eip155:8453", "eip155:137"], json!({}))).await;
+    Mock::given(method("POST"))
+        .and(path("/supported"))
+        .and(body_json(&capabilities))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(supported(&["eip155:137"], json!({}))),
+        )
+        .expect(1)
+        .mount(&facilitator)
+        .await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator).with_capabilities(capabilities)),
+    );
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(paid_chain(&seller).await, "eip155:137");
+}
+
+#[tokio::test]
+async fn facilitators_without_capabilities_are_asked_for_everything() {
+    let seller = seller(paid()).await;
+    // Only `GET /supported` is served
+    let facilitator = facilitator(supported(&["eip155:137"], json!({}))).await;
+    let client = http_client(
+        X402Client::new()
+            .register(chains_client(&["eip155:8453", "eip155:137"]))
+            .with_facilitator(support(&facilitator).with_capabilities(Default::default())),
+    );
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(paid_chain(&seller).await, "eip155:137");
+    let methods: Vec<String> = facilitator
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.method.to_string())
+        .collect();
+    assert_eq!(methods, vec!["POST", "GET"]);
+}
@@ -0,0 +1,37 @@
+//! Payment headers use the URL-safe base64 alphabet unless the standard one is requested.
+
+mod common;
+
+use common::{FixedSchemeClient, payment_required_response};
+use x402_reqwest::X402Client;
+use x402_types::util::Base64Bytes;
+
+/// Payload whose standard base64 encoding contains `+` and `/`.
+const PAYLOAD: [u8; 6] = [0xfb, 0xff, 0xfe, 0xfb, 0xef, 0xbf];
+
+#[tokio::test]
+async fn payment_header_is_url_safe_by_default() {
+    let client = X402Client::new().register(FixedSchemeClient::new().with_payload(PAYLOAD));
+    let headers = client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap();
+    let header = headers["Payment-Signature"].to_str().unwrap();
+    assert_eq!(header, "-__----_");
+    assert_eq!(
+        Base64Bytes::from(header.as_bytes()).auto_decode().unwrap(),
+        PAYLOAD
+    );
+}
+
+#[tokio::test]
+async fn payment_header_can_use_standard_alphabet() {
+    let client = X402Client::new()
+        .register(FixedSchemeClient::new().with_payload(PAYLOAD))
+        .with_standard_base64();
+    let headers = client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap();
+    assert_eq!(headers["Payment-Signature"], "+//++++/");
+}
@@ -0,0 +1,57 @@
+//! Clients built for AWS Lambda do not reuse connections and connect with a short timeout.
+#![cfg(feature = "lambda")]
+
+use axum::Router;
+use axum::extract::ConnectInfo;
+use axum::routing::get;
+use std::net::SocketAddr;
+use x402_reqwest::{LAMBDA_CONNECT_TIMEOUT, lambda_client_builder};
+
+/// Serves `/peer`, answering with the port the client connected from.
+async fn peer_port_server() -> SocketAddr {
+    let app = Router::new().route(
+        "/peer",
+        get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.port().to_string() }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+    addr
+}
+
+async fn peer_ports(client: &reqwest::Client, addr: SocketAddr) -> (String, String) {
+    let url = format!("http://{addr}/peer");
+    let first = client.get(&url).send().await.unwrap().text().await.unwrap();
+    let second = client.get(&url).send().await.unwrap().text().await.unwrap();
+    (first, second)
+}
+
+#[test]
+fn lambda_client_uses_short_connect_timeout() {
+    let builder = lambda_client_builder();
+    assert_eq!(LAMBDA_CONNECT_TIMEOUT.as_secs(), 2);
+    assert!(format!("{builder:?}").contains("connect_timeout: 2s"));
+    assert!(builder.build().is_ok());
+}
+
+#[tokio::test]
+async fn lambda_client_does_not_pool_connections() {
+    let addr = peer_port_server().await;
+
+    let (first, second) = peer_ports(&reqwest::Client::new(), addr).await;
+    assert_eq!(first, second, "default client reuses its connection");
+
+    let client = lambda_client_builder().build().unwrap();
+    let (first, second) = peer_ports(&client, addr).await;
+    assert_ne!(
+        first, second,
+        "lambda client opens a connection per request"
+    );
+}
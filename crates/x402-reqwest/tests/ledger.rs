@@ -0,0 +1,89 @@
+//! A budget with a spend ledger picks up where it left off after a restart.
+
+mod common;
+
+use alloy_primitives::U256;
+use common::{BASE_SEPOLIA, FixedSchemeClient, USDC_BASE_SEPOLIA, payment_required_response};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use x402_reqwest::X402Client;
+use x402_reqwest::budget::{Budget, BudgetToken};
+use x402_reqwest::ledger::{FsyncPolicy, JsonLinesLedger, SpendLedger};
+use x402_types::scheme::client::{PaymentSelector, X402Error};
+use x402_types::timestamp::UnixTimestamp;
+
+const STARTED: u64 = 1_000_000;
+
+fn usdc() -> BudgetToken {
+    BudgetToken::new("USDC", 6).on(BASE_SEPOLIA.parse().unwrap(), USDC_BASE_SEPOLIA)
+}
+
+/// Starts a client with a budget of two payments per hour, recorded in the ledger at
+/// `path`, on a clock set by `now`.
+async fn start_client(path: &Path, now: &Arc<AtomicU64>) -> X402Client<impl PaymentSelector> {
+    let ledger = JsonLinesLedger::open(path, FsyncPolicy::Always)
+        .await
+        .unwrap();
+    let clock = now.clone();
+    let budget = Budget::per_hour("0.0002 USDC".parse().unwrap())
+        .with_token(usdc())
+        .with_clock(move || UnixTimestamp::from_secs(clock.load(Ordering::SeqCst)))
+        .with_ledger(ledger);
+    X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_budget(budget)
+}
+
+fn ledger_path() -> PathBuf {
+    std::env::temp_dir().join(format!("x402-ledger-{}.jsonl", uuid::Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn budget_windows_survive_restarts() {
+    let path = ledger_path();
+    let now = Arc::new(AtomicU64::new(STARTED));
+
+    let client = start_client(&path, &now).await;
+    for _ in 0..2 {
+        client
+            .make_payment_headers(payment_required_response())
+            .await
+            .unwrap();
+    }
+    drop(client);
+
+    // Restarted within the hour: the window started by the first payment is still spent
+    now.store(STARTED + 30 * 60, Ordering::SeqCst);
+    let client = start_client(&path, &now).await;
+    let error = client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        X402Error::BudgetExhausted { resets_at: Some(resets_at) }
+            if resets_at == UnixTimestamp::from_secs(STARTED + 60 * 60)
+    ));
+    drop(client);
+
+    // Restarted after the hour: a new window starts
+    now.store(STARTED + 60 * 60, Ordering::SeqCst);
+    let client = start_client(&path, &now).await;
+    client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap();
+
+    let ledger = JsonLinesLedger::open(&path, FsyncPolicy::Never)
+        .await
+        .unwrap();
+    assert_eq!(
+        ledger
+            .sum_since(&usdc(), UnixTimestamp::from_secs(0))
+            .await
+            .unwrap(),
+        U256::from(300)
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,162 @@
+//! End-to-end check that one `X-Payment-Id` correlates the client, the resource
+//! server and the facilitator.
+
+mod common;
+
+use alloy_primitives::U256;
+use axum::Router;
+use axum::routing::get;
+use common::{BASE_SEPOLIA, PAY_TO, SchemeClientFn, USDC_BASE_SEPOLIA};
+use reqwest_middleware::ClientBuilder;
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_axum::X402Middleware;
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::proto;
+use x402_types::proto::{PaymentId, v2};
+use x402_types::scheme::client::{
+    PaymentCandidate, PaymentCandidateSigner, X402Error, X402SchemeClient,
+};
+use x402_types::util::Base64Bytes;
+
+/// Scheme client that accepts any V2 requirement and signs an empty payload.
+fn echo_client() -> impl X402SchemeClient {
+    SchemeClientFn::new(|payment_required, _| {
+        let proto::PaymentRequired::V2(payment_required) = payment_required else {
+            return vec![];
+        };
+        payment_required
+            .accepts
+            .iter()
+            .map(|accepted| {
+                let requirements: v2::PaymentRequirements =
+                    serde_json::from_str(accepted.0.get()).unwrap();
+                PaymentCandidate {
+                    chain_id: requirements.network.clone(),
+                    asset: requirements.asset.clone(),
+                    amount: requirements.amount.parse().unwrap_or(U256::ZERO),
+                    scheme: requirements.scheme.clone(),
+                    x402_version: 2,
+                    pay_to: requirements.pay_to.clone(),
+                    signer: Box::new(EchoSigner(requirements)),
+                }
+            })
+            .collect()
+    })
+}
+
+struct EchoSigner(v2::PaymentRequirements);
+
+#[async_trait::async_trait]
+impl PaymentCandidateSigner for EchoSigner {
+    async fn sign_payment(&self) -> Result<String, X402Error> {
+        let payload = json!({
+            "x402Version": 2,
+            "accepted": self.0,
+            "payload": { "signature": "0x" },
+        });
+        let encoded = Base64Bytes::encode(serde_json::to_vec(&payload).unwrap());
+        Ok(String::from_utf8(encoded.as_ref().to_vec()).unwrap())
+    }
+}
+
+async fn facilitator() -> MockServer {
+    let facilitator = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/supported"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "kinds": [] })))
+        .mount(&facilitator)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/verify"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "isValid": true, "payer": "0xpayer" })),
+        )
+        .mount(&facilitator)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/settle"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "payer": "0xpayer",
+            "transaction": "0xtx",
+            "network": "eip155:84532",
+        })))
+        .mount(&facilitator)
+        .await;
+    facilitator
+}
+
+async fn seller(facilitator_url: &str) -> String {
+    let price_tag = v2::PriceTag {
+        requirements: v2::PaymentRequirements {
+            scheme: "exact".into(),
+            network: BASE_SEPOLIA.parse().unwrap(),
+            amount: "100".into(),
+            pay_to: PAY_TO.into(),
+            max_timeout_seconds: 300,
+            asset: USDC_BASE_SEPOLIA.into(),
+            extra: None,
+        },
+        enricher: None,
+    };
+    let x402 = X402Middleware::new(facilitator_url);
+    let app = Router::new().route(
+        "/protected",
+        get(|| async { "paid content" }).layer(x402.with_price_tag(price_tag)),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}/protected")
+}
+
+#[tokio::test]
+async fn payment_id_reaches_facilitator_and_response() {
+    let facilitator = facilitator().await;
+    let url = seller(&facilitator.uri()).await;
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(X402Client::new().register(echo_client()))
+        .build();
+
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let payment_id = response.payment_id().unwrap().to_owned();
+    assert!(response.payment_response().unwrap().success);
+    assert_eq!(
+        response.headers()[PaymentId::HEADER_NAME].to_str().unwrap(),
+        payment_id
+    );
+
+    let requests = facilitator.received_requests().await.unwrap();
+    for endpoint in ["/verify", "/settle"] {
+        let request = requests
+            .iter()
+            .find(|request| request.url.path() == endpoint)
+            .unwrap_or_else(|| panic!("no {endpoint} request"));
+        assert_eq!(
+            request.headers[PaymentId::HEADER_NAME].to_str().unwrap(),
+            payment_id,
+            "{endpoint}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn caller_supplied_payment_id_is_kept() {
+    let facilitator = facilitator().await;
+    let url = seller(&facilitator.uri()).await;
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(X402Client::new().register(echo_client()))
+        .build();
+
+    let response = client
+        .get(&url)
+        .header(PaymentId::HEADER_NAME, "order-42")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.payment_id(), Some("order-42"));
+}
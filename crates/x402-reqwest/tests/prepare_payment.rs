@@ -0,0 +1,155 @@
+//! Payments are prepared from decoded 402 challenges, without any HTTP I/O.
+//!
+//! The fixtures are the 402 responses of the TypeScript server: the `Payment-Required`
+//! header of `@x402/hono` (V2), and the JSON body of `x402-express` (V1).
+
+mod common;
+
+use alloy_primitives::U256;
+use common::{FixedSigner, SchemeClientFn};
+use http::{HeaderMap, HeaderValue};
+use reqwest::Url;
+use std::time::Duration;
+use x402_reqwest::X402Client;
+use x402_reqwest::decode_payment_required;
+use x402_types::chain::ChainId;
+use x402_types::proto::{self, v1, v2};
+use x402_types::scheme::client::{PaymentCandidate, X402Error, X402SchemeClient};
+use x402_types::timestamp::UnixTimestamp;
+use x402_types::util::Base64Bytes;
+
+const V2_HEADER: &str = include_str!("fixtures/ts-server-v2-payment-required.txt");
+const V1_BODY: &str = include_str!("fixtures/ts-server-v1-payment-required.json");
+
+/// Scheme client that accepts the EVM requirements as they are.
+fn evm_client() -> impl X402SchemeClient {
+    SchemeClientFn::new(|payment_required, _| {
+        let candidate =
+            |x402_version, chain_id: ChainId, amount: &str, asset, pay_to| PaymentCandidate {
+                chain_id,
+                asset,
+                amount: amount.parse::<U256>().unwrap(),
+                scheme: "exact".into(),
+                x402_version,
+                pay_to,
+                signer: Box::new(FixedSigner(b"signed payment".to_vec())),
+            };
+        match payment_required {
+            proto::PaymentRequired::V1(payment_required) => payment_required
+                .accepts
+                .iter()
+                .filter_map(|accepts| accepts.try_into().ok())
+                .filter_map(|r: v1::PaymentRequirements| {
+                    let chain_id = ChainId::from_network_name(&r.network)?;
+                    let amount = r.max_amount_required;
+                    Some(candidate(1, chain_id, &amount, r.asset, r.pay_to))
+                })
+                .collect(),
+            proto::PaymentRequired::V2(payment_required) => payment_required
+                .accepts
+                .iter()
+                .filter_map(|accepts| accepts.try_into().ok())
+                .filter(|r: &v2::PaymentRequirements| r.network.namespace() == "eip155")
+                .map(|r| candidate(2, r.network, &r.amount, r.asset, r.pay_to))
+                .collect(),
+        }
+    })
+}
+
+fn v2_challenge() -> proto::PaymentRequired {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Payment-Required",
+        HeaderValue::from_str(V2_HEADER.trim()).unwrap(),
+    );
+    decode_payment_required(&headers, b"{}").unwrap()
+}
+
+fn v1_challenge() -> proto::PaymentRequired {
+    decode_payment_required(&HeaderMap::new(), V1_BODY.as_bytes()).unwrap()
+}
+
+/// Asserts that `expires_at` is `seconds` from now, give or take a second.
+fn assert_expires_in(expires_at: Option<UnixTimestamp>, seconds: u64) {
+    let expires_at = expires_at.unwrap().as_secs();
+    let now = UnixTimestamp::now().as_secs();
+    assert!(
+        (now + seconds - 1..=now + seconds + 1).contains(&expires_at),
+        "expires at {expires_at}, expected {seconds}s after {now}"
+    );
+}
+
+#[tokio::test]
+async fn prepares_a_v2_payment_signature() {
+    let client = X402Client::new().register(evm_client());
+    let resource: Url = "http://localhost:4021/static-price-v2".parse().unwrap();
+
+    let prepared = client
+        .prepare_payment(&v2_challenge(), &resource)
+        .await
+        .unwrap();
+
+    assert_eq!(prepared.header_name, "payment-signature");
+    let payload = Base64Bytes::from(prepared.header_value.as_bytes())
+        .auto_decode()
+        .unwrap();
+    assert_eq!(&payload[..], b"signed payment");
+    assert_eq!(prepared.payment.resource_url, resource);
+    assert_eq!(prepared.payment.chain_id, "eip155:84532".parse().unwrap());
+    assert_eq!(prepared.payment.amount, U256::from(1000));
+    assert_eq!(
+        prepared.payment.pay_to,
+        "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"
+    );
+    assert_eq!(prepared.payment.x402_version, 2);
+    assert_expires_in(prepared.expires_at, 300);
+    assert_eq!(prepared.to_headers().len(), 1);
+}
+
+#[tokio::test]
+async fn prepares_a_v1_x_payment() {
+    let client = X402Client::new().register(evm_client());
+    let resource: Url = "http://localhost:4021/weather".parse().unwrap();
+
+    let prepared = client
+        .prepare_payment(&v1_challenge(), &resource)
+        .await
+        .unwrap();
+
+    let (name, _) = prepared.header();
+    assert_eq!(name, "x-payment");
+    assert_eq!(prepared.payment.network(), Some("base-sepolia"));
+    assert_eq!(prepared.payment.x402_version, 1);
+    assert_expires_in(prepared.expires_at, 60);
+}
+
+#[tokio::test]
+async fn expiry_follows_the_validity_window() {
+    let client = X402Client::new()
+        .register(evm_client())
+        .with_validity_window(Duration::from_secs(30));
+    let resource: Url = "http://localhost:4021/static-price-v2".parse().unwrap();
+
+    let prepared = client
+        .prepare_payment(&v2_challenge(), &resource)
+        .await
+        .unwrap();
+
+    assert_expires_in(prepared.expires_at, 30);
+}
+
+#[tokio::test]
+async fn client_policies_apply() {
+    let client = X402Client::new()
+        .register(evm_client())
+        .deny_unknown_hosts()
+        .for_host("localhost", |policy| policy);
+    let resource: Url = "http://example.com/static-price-v2".parse().unwrap();
+
+    let error = client
+        .prepare_payment(&v2_challenge(), &resource)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, X402Error::HostNotAllowed(_)), "{error}");
+}
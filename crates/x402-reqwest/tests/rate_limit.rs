@@ -0,0 +1,72 @@
+//! A client with a rate limit refuses to pay, or waits, once it has paid too often.
+
+mod common;
+
+use common::{FixedSchemeClient, payment_required_response};
+use std::time::Duration;
+use x402_reqwest::X402Client;
+use x402_reqwest::rate_limit::RateLimit;
+use x402_types::scheme::client::X402Error;
+
+#[tokio::test]
+async fn payments_stop_at_the_rate_limit() {
+    let client = X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_rate_limit(RateLimit::new(2, Duration::from_secs(60 * 60)));
+
+    for _ in 0..2 {
+        client
+            .make_payment_headers(payment_required_response())
+            .await
+            .unwrap();
+    }
+    let error = client
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap_err();
+    // The next payment is allowed half an hour after the burst
+    assert!(matches!(
+        error,
+        X402Error::RateLimited { retry_after }
+            if retry_after > Duration::from_secs(29 * 60)
+                && retry_after <= Duration::from_secs(30 * 60)
+    ));
+}
+
+#[tokio::test]
+async fn clients_can_share_a_rate_limit() {
+    let rate_limit = RateLimit::new(1, Duration::from_secs(60 * 60));
+    let first = X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_rate_limit(rate_limit.clone());
+    let second = X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_rate_limit(rate_limit);
+
+    first
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap();
+    let error = second
+        .make_payment_headers(payment_required_response())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, X402Error::RateLimited { .. }));
+}
+
+#[cfg(feature = "rate-limit-wait")]
+#[tokio::test]
+async fn waiting_payments_are_made_once_allowed() {
+    let client = X402Client::new()
+        .register(FixedSchemeClient::new())
+        .with_rate_limit(RateLimit::new(1, Duration::from_millis(200)).waiting());
+
+    let start = std::time::Instant::now();
+    for _ in 0..2 {
+        client
+            .make_payment_headers(payment_required_response())
+            .await
+            .unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(190));
+}
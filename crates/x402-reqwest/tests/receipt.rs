@@ -0,0 +1,112 @@
+//! Settled payments are reported with a receipt.
+
+mod common;
+
+use alloy_primitives::U256;
+use common::{BASE, FixedSchemeClient, PAY_TO, USDC_BASE, http_client, paid, seller};
+use reqwest_middleware::ClientWithMiddleware;
+use std::sync::{Arc, Mutex};
+use wiremock::ResponseTemplate;
+use x402_reqwest::receipt::PaymentReceipt;
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::proto;
+
+const PAYER: &str = "0x857b06519E91e3A54538791bDbb0E22373e36b66";
+const TRANSACTION: &str = "0x9c9a59f9e3b1e4d9c0bcbd3c8e9fd6a3e25fc8f6f1a2c4b9d3e7f0a1b2c3d4e5";
+
+/// A paid response with `payment_response` in the `Payment-Response` header.
+fn reported(payment_response: &str) -> ResponseTemplate {
+    paid().insert_header(proto::PaymentResponse::V2_HEADER_NAME, payment_response)
+}
+
+fn settled(amount: Option<&str>) -> String {
+    let payment_response = proto::PaymentResponse {
+        success: true,
+        error_reason: None,
+        payer: Some(PAYER.into()),
+        transaction: TRANSACTION.into(),
+        network: BASE.into(),
+        amount: amount.map(Into::into),
+        extensions: Default::default(),
+    };
+    payment_response.to_header().unwrap().to_string()
+}
+
+/// A client collecting the receipts it is called back with.
+fn recording_client() -> (ClientWithMiddleware, Arc<Mutex<Vec<PaymentReceipt>>>) {
+    let receipts = Arc::new(Mutex::new(Vec::new()));
+    let client = http_client(
+        X402Client::new()
+            .register(FixedSchemeClient::new().paying(BASE, USDC_BASE, 250_000))
+            .on_payment({
+                let receipts = receipts.clone();
+                move |receipt| receipts.lock().unwrap().push(receipt)
+            }),
+    );
+    (client, receipts)
+}
+
+#[tokio::test]
+async fn settled_payments_come_with_a_receipt() {
+    let seller = seller(reported(&settled(None))).await;
+    let (client, receipts) = recording_client();
+
+    let url = format!("{}/report", seller.uri());
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let receipt = response.payment_receipt().unwrap().clone();
+    assert_eq!(receipt.resource_url.as_str(), url);
+    assert_eq!(receipt.network(), Some("base"));
+    assert_eq!(receipt.asset, USDC_BASE);
+    assert_eq!(receipt.amount, U256::from(250_000));
+    assert_eq!(receipt.pay_to, PAY_TO);
+    assert_eq!(receipt.payer.as_deref(), Some(PAYER));
+    assert_eq!(receipt.transaction, TRANSACTION);
+    assert_eq!(*receipts.lock().unwrap(), vec![receipt]);
+}
+
+#[tokio::test]
+async fn receipts_have_the_settled_amount() {
+    let seller = seller(reported(&settled(Some("120000")))).await;
+    let (client, _receipts) = recording_client();
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(
+        response.payment_receipt().unwrap().amount,
+        U256::from(120_000)
+    );
+}
+
+#[tokio::test]
+async fn failed_settlements_have_no_receipt() {
+    let payment_response = proto::PaymentResponse {
+        success: false,
+        error_reason: Some("insufficient_funds".into()),
+        payer: Some(PAYER.into()),
+        transaction: String::new(),
+        network: BASE.into(),
+        amount: None,
+        extensions: Default::default(),
+    };
+    let seller = seller(reported(&payment_response.to_header().unwrap().to_string())).await;
+    let (client, receipts) = recording_client();
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert!(!response.payment_response().unwrap().success);
+    assert!(response.payment_receipt().is_none());
+    assert!(receipts.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn malformed_headers_do_not_fail_the_request() {
+    let seller = seller(reported("not a payment response")).await;
+    let (client, receipts) = recording_client();
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.payment_response().is_none());
+    assert!(response.payment_receipt().is_none());
+    assert!(receipts.lock().unwrap().is_empty());
+    assert_eq!(response.text().await.unwrap(), "paid content");
+}
@@ -0,0 +1,235 @@
+//! A server answering a payment with new requirements is paid again, a bounded number of
+//! times.
+
+mod common;
+
+use alloy_primitives::U256;
+use common::{BASE, SchemeClientFn, USDC_BASE, requirements};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use tokio::sync::broadcast;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+use x402_reqwest::events::{PaymentEvent, PaymentEventKind};
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::proto::{self, v2};
+use x402_types::scheme::client::{
+    FirstMatch, PaymentCandidate, PaymentCandidateSigner, X402Error, X402SchemeClient,
+};
+use x402_types::util::Base64Bytes;
+
+/// Scheme client paying the amount the requirements ask for.
+fn amount_client() -> impl X402SchemeClient {
+    SchemeClientFn::new(|payment_required, _| {
+        let proto::PaymentRequired::V2(payment_required) = payment_required else {
+            return Vec::new();
+        };
+        payment_required
+            .accepts
+            .iter()
+            .filter_map(|accepts| accepts.try_into().ok())
+            .map(|requirements: v2::PaymentRequirements| PaymentCandidate {
+                chain_id: requirements.network,
+                asset: requirements.asset,
+                amount: requirements.amount.parse().unwrap(),
+                scheme: "exact".into(),
+                x402_version: 2,
+                pay_to: requirements.pay_to,
+                signer: Box::new(AmountSigner(requirements.amount)),
+            })
+            .collect()
+    })
+}
+
+/// Signer signing `paid <amount>`.
+struct AmountSigner(String);
+
+#[async_trait::async_trait]
+impl PaymentCandidateSigner for AmountSigner {
+    async fn sign_payment(&self) -> Result<String, X402Error> {
+        Ok(Base64Bytes::encode(format!("paid {}", self.0)).to_string())
+    }
+}
+
+/// A 402 asking for `amount`, because of `error` if set.
+fn challenge(amount: u64, error: Option<&str>) -> ResponseTemplate {
+    let mut payment_required =
+        common::payment_required(vec![requirements(BASE, USDC_BASE, amount)]);
+    payment_required["error"] = error.into();
+    common::challenge(&payment_required)
+}
+
+/// The amount paid for `request`, if paid.
+fn paid_amount(request: &Request) -> Option<u64> {
+    let header = request.headers.get("Payment-Signature")?;
+    let signature = Base64Bytes::from(header.as_bytes()).auto_decode().ok()?;
+    String::from_utf8(signature)
+        .ok()?
+        .strip_prefix("paid ")?
+        .parse()
+        .ok()
+}
+
+/// A seller answering each request as `respond` says for the amount paid, if any.
+async fn seller(
+    respond: impl Fn(Option<u64>) -> ResponseTemplate + Send + Sync + 'static,
+) -> MockServer {
+    let seller = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(move |request: &Request| respond(paid_amount(request)))
+        .mount(&seller)
+        .await;
+    seller
+}
+
+fn paid(amount: u64) -> ResponseTemplate {
+    let payment_response = proto::PaymentResponse {
+        success: true,
+        error_reason: None,
+        payer: None,
+        transaction: "0x01".into(),
+        network: "eip155:8453".into(),
+        amount: Some(amount.to_string()),
+        extensions: Default::default(),
+    };
+    ResponseTemplate::new(200)
+        .insert_header(
+            proto::PaymentResponse::V2_HEADER_NAME,
+            payment_response.to_header().unwrap().to_string(),
+        )
+        .set_body_string("paid content")
+}
+
+fn http_client(
+    configure: impl FnOnce(X402Client<FirstMatch>) -> X402Client<FirstMatch>,
+) -> (ClientWithMiddleware, broadcast::Receiver<PaymentEvent>) {
+    let x402 = configure(X402Client::new().register(amount_client()));
+    let events = x402.events();
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(x402)
+        .build();
+    (client, events)
+}
+
+async fn request_count(seller: &MockServer) -> usize {
+    seller.received_requests().await.unwrap().len()
+}
+
+/// The kinds of the events published so far.
+fn event_kinds(events: &mut broadcast::Receiver<PaymentEvent>) -> Vec<PaymentEventKind> {
+    std::iter::from_fn(|| events.try_recv().ok())
+        .map(|event| event.kind)
+        .collect()
+}
+
+#[tokio::test]
+async fn price_bump_is_paid_again() {
+    let seller = seller(|amount_paid| match amount_paid {
+        None => challenge(100, None),
+        Some(100) => challenge(150, Some("price changed")),
+        Some(amount) => paid(amount),
+    })
+    .await;
+    let (client, mut events) = http_client(|x402| x402);
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.payment_receipt().unwrap().amount, U256::from(150));
+    assert_eq!(request_count(&seller).await, 3);
+
+    // Each challenge is published, and followed by the payment answering it
+    let kinds = event_kinds(&mut events);
+    let names = kinds
+        .iter()
+        .map(|kind| match kind {
+            PaymentEventKind::PaymentRequired { .. } => "PaymentRequired",
+            PaymentEventKind::CandidateSelected(_) => "CandidateSelected",
+            PaymentEventKind::PaymentSigned(_) => "PaymentSigned",
+            PaymentEventKind::RetrySent { .. } => "RetrySent",
+            PaymentEventKind::Rechallenged { .. } => "Rechallenged",
+            PaymentEventKind::PaymentSucceeded { .. } => "PaymentSucceeded",
+            PaymentEventKind::PaymentFailed { .. } => "PaymentFailed",
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "PaymentRequired",
+            "CandidateSelected",
+            "PaymentSigned",
+            "RetrySent",
+            "Rechallenged",
+            "PaymentRequired",
+            "CandidateSelected",
+            "PaymentSigned",
+            "RetrySent",
+            "PaymentSucceeded",
+        ]
+    );
+    let rechallenged = kinds
+        .iter()
+        .find_map(|kind| match kind {
+            PaymentEventKind::Rechallenged {
+                payment,
+                challenge,
+                reason,
+            } => Some((payment.amount, *challenge, reason.clone())),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        rechallenged,
+        (U256::from(100), 2, Some("price changed".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn endless_challenges_are_cut_off() {
+    // Every payment is answered with a price one higher
+    let seller = seller(|amount_paid| challenge(amount_paid.unwrap_or(100) + 1, None)).await;
+    let (client, _events) = http_client(|x402| x402.with_max_challenges(2));
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 402);
+    // The unpaid request, the first payment, and two re-challenges
+    assert_eq!(request_count(&seller).await, 4);
+}
+
+#[tokio::test]
+async fn unchanged_challenge_is_not_paid_again() {
+    let seller = seller(|_| challenge(100, None)).await;
+    let (client, _events) = http_client(|x402| x402);
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 402);
+    assert_eq!(request_count(&seller).await, 2);
+}
+
+#[tokio::test]
+async fn challenge_seen_before_is_not_paid_again() {
+    // The price alternates between two amounts
+    let seller = seller(|amount_paid| match amount_paid {
+        Some(100) => challenge(150, None),
+        _ => challenge(100, None),
+    })
+    .await;
+    let (client, _events) = http_client(|x402| x402.with_max_challenges(5));
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 402);
+    assert_eq!(request_count(&seller).await, 3);
+}
+
+#[tokio::test]
+async fn re_challenges_can_be_turned_off() {
+    let seller = seller(|amount_paid| match amount_paid {
+        None => challenge(100, None),
+        Some(100) => challenge(150, None),
+        Some(amount) => paid(amount),
+    })
+    .await;
+    let (client, _events) = http_client(|x402| x402.with_max_challenges(0));
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 402);
+    assert_eq!(request_count(&seller).await, 2);
+}
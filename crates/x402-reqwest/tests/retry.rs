@@ -0,0 +1,247 @@
+//! A client with several attempts pays with another option when a payment fails.
+
+mod common;
+
+use common::{BASE, SchemeClientFn, candidate};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::proto;
+use x402_types::scheme::client::{FirstMatch, PaymentCandidateSigner, X402Error};
+use x402_types::util::Base64Bytes;
+
+const SOLANA: &str = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+const POLYGON: &str = "eip155:137";
+
+/// Signer recording each payment it signs, as `<chain>#<count>`, so that no two
+/// signatures are alike.
+struct CountingSigner {
+    chain: &'static str,
+    signed: Arc<Mutex<Vec<String>>>,
+}
+
+static SIGNATURES: AtomicUsize = AtomicUsize::new(0);
+
+#[async_trait::async_trait]
+impl PaymentCandidateSigner for CountingSigner {
+    async fn sign_payment(&self) -> Result<String, X402Error> {
+        let signature = format!(
+            "{}#{}",
+            self.chain,
+            SIGNATURES.fetch_add(1, Ordering::Relaxed)
+        );
+        self.signed.lock().unwrap().push(signature.clone());
+        Ok(Base64Bytes::encode(signature).to_string())
+    }
+}
+
+/// How the seller answers a payment on a chain.
+enum Outcome {
+    /// Another 402, with an error.
+    Rejected(&'static str),
+    /// A failed settlement in the `Payment-Response` header.
+    Unsettled(&'static str),
+    /// The paid content.
+    Paid,
+}
+
+fn payment_response(chain: &str, error_reason: Option<&str>) -> String {
+    let payment_response = proto::PaymentResponse {
+        success: error_reason.is_none(),
+        error_reason: error_reason.map(Into::into),
+        payer: None,
+        transaction: "0x01".into(),
+        network: chain.into(),
+        amount: None,
+        extensions: Default::default(),
+    };
+    payment_response.to_header().unwrap().to_string()
+}
+
+fn payment_required(error: Option<&str>) -> String {
+    let mut payment_required = common::payment_required(vec![]);
+    payment_required["error"] = error.into();
+    common::encode(&payment_required)
+}
+
+/// A seller answering payments on each chain with a scripted outcome.
+async fn seller(outcomes: Vec<(&'static str, Outcome)>) -> MockServer {
+    let seller = MockServer::start().await;
+    for (chain, outcome) in outcomes {
+        let response = match outcome {
+            Outcome::Rejected(error) => ResponseTemplate::new(402)
+                .insert_header("Payment-Required", payment_required(Some(error))),
+            Outcome::Unsettled(reason) => ResponseTemplate::new(402)
+                .insert_header("Payment-Required", payment_required(None))
+                .insert_header(
+                    proto::PaymentResponse::V2_HEADER_NAME,
+                    payment_response(chain, Some(reason)),
+                ),
+            Outcome::Paid => ResponseTemplate::new(200)
+                .insert_header(
+                    proto::PaymentResponse::V2_HEADER_NAME,
+                    payment_response(chain, None),
+                )
+                .set_body_string("paid content"),
+        };
+        Mock::given(method("GET"))
+            .and(move |request: &wiremock::Request| {
+                request
+                    .headers
+                    .get("Payment-Signature")
+                    .and_then(|header| Base64Bytes::from(header.as_bytes()).auto_decode().ok())
+                    .is_some_and(|signature| signature.starts_with(format!("{chain}#").as_bytes()))
+            })
+            .respond_with(response)
+            .with_priority(1)
+            .mount(&seller)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(402).insert_header("Payment-Required", payment_required(None)),
+        )
+        .mount(&seller)
+        .await;
+    seller
+}
+
+fn http_client(
+    chains: Vec<&'static str>,
+    configure: impl FnOnce(X402Client<FirstMatch>) -> X402Client<FirstMatch>,
+) -> (ClientWithMiddleware, Arc<Mutex<Vec<String>>>) {
+    let signed = Arc::new(Mutex::new(Vec::new()));
+    // Offers a payment on each chain, in order
+    let x402 = configure(X402Client::new().register(SchemeClientFn::new({
+        let signed = signed.clone();
+        move |_, _| {
+            chains
+                .iter()
+                .map(|&chain| {
+                    let signer = CountingSigner {
+                        chain,
+                        signed: signed.clone(),
+                    };
+                    candidate(chain, &format!("usdc@{chain}"), 100, signer)
+                })
+                .collect()
+        }
+    })));
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(x402)
+        .build();
+    (client, signed)
+}
+
+fn x402_error(error: reqwest_middleware::Error) -> X402Error {
+    match error {
+        reqwest_middleware::Error::Middleware(error) => error.downcast().unwrap(),
+        other => panic!("expected a middleware error, got {other:?}"),
+    }
+}
+
+fn signed_chains(signed: &Mutex<Vec<String>>) -> Vec<String> {
+    signed
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|signature| signature.split('#').next().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn rejected_payments_are_retried_with_the_next_option() {
+    let seller = seller(vec![
+        (BASE, Outcome::Rejected("facilitator unavailable")),
+        (SOLANA, Outcome::Paid),
+    ])
+    .await;
+    let (client, signed) = http_client(vec![BASE, SOLANA], |x402| x402.with_max_attempts(3));
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.payment_receipt().unwrap().chain_id,
+        SOLANA.parse().unwrap()
+    );
+    assert_eq!(signed_chains(&signed), vec![BASE, SOLANA]);
+    assert_eq!(response.text().await.unwrap(), "paid content");
+}
+
+#[tokio::test]
+async fn unsettled_payments_are_retried_with_the_next_option() {
+    let seller = seller(vec![
+        (BASE, Outcome::Unsettled("unexpected_settle_error")),
+        (SOLANA, Outcome::Paid),
+    ])
+    .await;
+    let (client, signed) = http_client(vec![BASE, SOLANA], |x402| x402.with_max_attempts(2));
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(signed_chains(&signed), vec![BASE, SOLANA]);
+}
+
+#[tokio::test]
+async fn failing_every_option_reports_each_failure() {
+    let seller = seller(vec![
+        (BASE, Outcome::Rejected("facilitator unavailable")),
+        (SOLANA, Outcome::Unsettled("blockhash_expired")),
+    ])
+    .await;
+    let (client, signed) = http_client(vec![BASE, SOLANA], |x402| x402.with_max_attempts(5));
+
+    let error = client.get(seller.uri()).send().await.unwrap_err();
+    let X402Error::PaymentFailed(failures) = x402_error(error) else {
+        panic!("expected the payment to fail");
+    };
+    let failures = failures
+        .iter()
+        .map(|failure| (failure.chain_id.to_string(), failure.reason.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failures,
+        vec![
+            (BASE.to_string(), "facilitator unavailable"),
+            (SOLANA.to_string(), "blockhash_expired"),
+        ]
+    );
+    assert_eq!(signed_chains(&signed), vec![BASE, SOLANA]);
+}
+
+#[tokio::test]
+async fn attempts_stop_at_the_limit() {
+    let seller = seller(vec![
+        (BASE, Outcome::Rejected("facilitator unavailable")),
+        (SOLANA, Outcome::Rejected("facilitator unavailable")),
+        (POLYGON, Outcome::Paid),
+    ])
+    .await;
+    let (client, signed) = http_client(vec![BASE, SOLANA, POLYGON], |x402| {
+        x402.with_max_attempts(2)
+    });
+
+    let error = client.get(seller.uri()).send().await.unwrap_err();
+    let X402Error::PaymentFailed(failures) = x402_error(error) else {
+        panic!("expected the payment to fail");
+    };
+    assert_eq!(failures.len(), 2);
+    assert_eq!(signed_chains(&signed), vec![BASE, SOLANA]);
+}
+
+#[tokio::test]
+async fn failed_payments_are_not_retried_by_default() {
+    let seller = seller(vec![
+        (BASE, Outcome::Rejected("facilitator unavailable")),
+        (SOLANA, Outcome::Paid),
+    ])
+    .await;
+    let (client, signed) = http_client(vec![BASE, SOLANA], |x402| x402);
+
+    let response = client.get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 402);
+    assert_eq!(signed_chains(&signed), vec![BASE]);
+}
@@ -0,0 +1,132 @@
+//! Paid responses keep the payment header sent and the payment response received.
+
+mod common;
+
+use common::{BASE_SEPOLIA, FixedSchemeClient, challenge, payment_required};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use serde_json::json;
+use wiremock::matchers::{header_exists, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_reqwest::{ResponseExt, X402Client};
+use x402_types::proto;
+
+const TRANSACTION: &str = "0x9c9a59f9e3b1e4d9c0bcbd3c8e9fd6a3e25fc8f6f1a2c4b9d3e7f0a1b2c3d4e5";
+
+fn payment_response() -> String {
+    let payment_response = proto::PaymentResponse {
+        success: true,
+        error_reason: None,
+        payer: None,
+        transaction: TRANSACTION.into(),
+        network: BASE_SEPOLIA.into(),
+        amount: None,
+        extensions: Default::default(),
+    };
+    payment_response.to_header().unwrap().to_string()
+}
+
+/// A seller answering payments sent in `payment_header` with a payment response in
+/// `payment_response_header`, and other requests with `payment_required`.
+async fn seller(
+    payment_header: &'static str,
+    payment_response_header: &'static str,
+    payment_required: ResponseTemplate,
+) -> MockServer {
+    let seller = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(header_exists(payment_header))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header(payment_response_header, payment_response())
+                .set_body_string("paid content"),
+        )
+        .with_priority(1)
+        .mount(&seller)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(payment_required)
+        .mount(&seller)
+        .await;
+    seller
+}
+
+fn http_client(x402_version: u8) -> ClientWithMiddleware {
+    ClientBuilder::new(reqwest::Client::new())
+        .with(
+            X402Client::new().register(
+                FixedSchemeClient::new()
+                    .with_x402_version(x402_version)
+                    .with_payload("signed payment"),
+            ),
+        )
+        .build()
+}
+
+/// Returns the value of `header` in the paid request received by `seller`.
+async fn received_header(seller: &MockServer, header: &str) -> String {
+    let requests = seller.received_requests().await.unwrap();
+    let paid = requests
+        .iter()
+        .find_map(|request| request.headers.get(header))
+        .expect("no paid request received");
+    paid.to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn v2_payments_are_kept_on_the_response() {
+    let seller = seller(
+        "Payment-Signature",
+        proto::PaymentResponse::V2_HEADER_NAME,
+        challenge(&payment_required(vec![])),
+    )
+    .await;
+
+    let response = http_client(2).get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.sent_payment_header(),
+        Some(received_header(&seller, "Payment-Signature").await.as_str())
+    );
+    assert_eq!(
+        response.payment_response().unwrap().transaction,
+        TRANSACTION
+    );
+}
+
+#[tokio::test]
+async fn v1_payments_are_kept_on_the_response() {
+    let payment_required = json!({
+        "x402Version": 1,
+        "accepts": [],
+    });
+    let seller = seller(
+        "X-Payment",
+        proto::PaymentResponse::V1_HEADER_NAME,
+        ResponseTemplate::new(402).set_body_json(payment_required),
+    )
+    .await;
+
+    let response = http_client(1).get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.sent_payment_header(),
+        Some(received_header(&seller, "X-Payment").await.as_str())
+    );
+    assert_eq!(
+        response.payment_response().unwrap().transaction,
+        TRANSACTION
+    );
+}
+
+#[tokio::test]
+async fn unpaid_responses_have_no_payment() {
+    let seller = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("free content"))
+        .mount(&seller)
+        .await;
+
+    let response = http_client(2).get(seller.uri()).send().await.unwrap();
+    assert_eq!(response.sent_payment_header(), None);
+    assert!(response.payment_response().is_none());
+}
@@ -0,0 +1,103 @@
+//! The validity window and clock offset of the client reach the scheme clients.
+
+mod common;
+
+use common::{BASE_SEPOLIA, FixedSigner, SchemeClientFn, USDC_BASE_SEPOLIA, candidate};
+use std::time::{Duration, SystemTime};
+use x402_reqwest::X402Client;
+use x402_types::scheme::client::{PaymentSelector, X402SchemeClient};
+use x402_types::util::Base64Bytes;
+
+const MAX_TIMEOUT_SECONDS: u64 = 300;
+
+/// Scheme client signing the validity window it is given, in seconds.
+fn window_client() -> impl X402SchemeClient {
+    SchemeClientFn::new(|_, options| {
+        let validity_seconds = options.validity_seconds(MAX_TIMEOUT_SECONDS);
+        let signer = FixedSigner(validity_seconds.to_string().into_bytes());
+        vec![candidate(BASE_SEPOLIA, USDC_BASE_SEPOLIA, 100, signer)]
+    })
+}
+
+/// Scheme client signing the clock offset it is given, in seconds.
+fn offset_client() -> impl X402SchemeClient {
+    SchemeClientFn::new(|_, options| {
+        let signer = FixedSigner(options.clock_offset_secs.to_string().into_bytes());
+        vec![candidate(BASE_SEPOLIA, USDC_BASE_SEPOLIA, 100, signer)]
+    })
+}
+
+fn payment_required_response() -> reqwest::Response {
+    dated_payment_required_response(None)
+}
+
+/// A 402 response, with a `Date` header if `date` is set.
+fn dated_payment_required_response(date: Option<SystemTime>) -> reqwest::Response {
+    let payment_required = common::payment_required(vec![common::requirements(
+        BASE_SEPOLIA,
+        USDC_BASE_SEPOLIA,
+        100,
+    )]);
+    let mut response = http::Response::builder()
+        .status(402)
+        .header("Payment-Required", common::encode(&payment_required));
+    if let Some(date) = date {
+        response = response.header("Date", httpdate::fmt_http_date(date));
+    }
+    response.body(Vec::<u8>::new()).unwrap().into()
+}
+
+async fn signed_window(client: X402Client<impl PaymentSelector>) -> String {
+    signed(client, payment_required_response()).await
+}
+
+/// Signs a payment answering `response`, returning what the scheme client signed.
+async fn signed(client: X402Client<impl PaymentSelector>, response: reqwest::Response) -> String {
+    let headers = client.make_payment_headers(response).await.unwrap();
+    let header = headers["Payment-Signature"].to_str().unwrap();
+    let bytes = Base64Bytes::from(header.as_bytes()).auto_decode().unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn validity_window_defaults_to_max_timeout() {
+    let client = X402Client::new().register(window_client());
+    assert_eq!(signed_window(client).await, "300");
+}
+
+#[tokio::test]
+async fn validity_window_is_passed_to_scheme_clients() {
+    let client = X402Client::new()
+        .register(window_client())
+        .with_validity_window(Duration::from_secs(60));
+    assert_eq!(signed_window(client).await, "60");
+}
+
+#[tokio::test]
+async fn clock_offset_is_calibrated_from_the_date_header() {
+    // The server's clock is 30 seconds behind the local one
+    let server_time = SystemTime::now() - Duration::from_secs(30);
+    let client = X402Client::new()
+        .register(offset_client())
+        .with_clock_calibration();
+    let offset: i64 = signed(client, dated_payment_required_response(Some(server_time)))
+        .await
+        .parse()
+        .unwrap();
+    // The Date header has a one second resolution
+    assert!((-31..=-29).contains(&offset), "{offset}");
+}
+
+#[tokio::test]
+async fn local_clock_is_trusted_without_calibration() {
+    let server_time = SystemTime::now() - Duration::from_secs(30);
+    let client = X402Client::new().register(offset_client());
+    let response = dated_payment_required_response(Some(server_time));
+    assert_eq!(signed(client, response).await, "0");
+
+    // Responses without a Date header are paid on the local clock
+    let client = X402Client::new()
+        .register(offset_client())
+        .with_clock_calibration();
+    assert_eq!(signed(client, payment_required_response()).await, "0");
+}
@@ -22,6 +22,7 @@
 //! assert_eq!(polygon.reference, "137");
 //! ```
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 use std::collections::HashSet;
 use std::fmt;
@@ -39,7 +40,7 @@ use crate::networks;
 ///
 /// # Serialization
 ///
-/// Serializes to/from a colon-separated string: `"eip155:8453"`
+/// With the `serde` feature, serializes to/from a colon-separated string: `"eip155:8453"`
 ///
 /// # Example
 ///
@@ -47,8 +48,8 @@ use crate::networks;
 /// use x402_types::chain::ChainId;
 ///
 /// let chain = ChainId::new("eip155", "8453");
-/// let json = serde_json::to_string(&chain).unwrap();
-/// assert_eq!(json, "\"eip155:8453\"");
+/// assert_eq!(chain.to_string(), "eip155:8453");
+/// assert_eq!("eip155:8453".parse::<ChainId>().unwrap(), chain);
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ChainId {
@@ -161,6 +162,7 @@ impl FromStr for ChainId {
     }
 }
 
+#[cfg(feature = "serde")]
 impl Serialize for ChainId {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -170,6 +172,22 @@ impl Serialize for ChainId {
     }
 }
 
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for ChainId {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ChainId".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "CAIP-2 chain ID, as `namespace:reference` (e.g., \"eip155:8453\").",
+            "pattern": "^[^:]+:.+$",
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for ChainId {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -211,7 +229,7 @@ impl<'de> Deserialize<'de> for ChainId {
 /// assert!(base_only.matches(&ChainId::new("eip155", "8453")));
 /// assert!(!base_only.matches(&ChainId::new("eip155", "137")));
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ChainIdPattern {
     /// Matches any chain within the specified namespace.
     Wildcard {
@@ -382,6 +400,7 @@ impl FromStr for ChainIdPattern {
     }
 }
 
+#[cfg(feature = "serde")]
 impl Serialize for ChainIdPattern {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -391,6 +410,7 @@ impl Serialize for ChainIdPattern {
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for ChainIdPattern {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -425,6 +445,7 @@ mod tests {
     use crate::networks::{chain_id_by_network_name, network_name_by_chain_id};
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_serialize_eip155() {
         let chain_id = ChainId::new("eip155", "1");
         let serialized = serde_json::to_string(&chain_id).unwrap();
@@ -432,6 +453,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_serialize_solana() {
         let chain_id = ChainId::new("solana", "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp");
         let serialized = serde_json::to_string(&chain_id).unwrap();
@@ -439,6 +461,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_deserialize_eip155() {
         let chain_id: ChainId = serde_json::from_str("\"eip155:1\"").unwrap();
         assert_eq!(chain_id.namespace, "eip155");
@@ -446,6 +469,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_deserialize_solana() {
         let chain_id: ChainId =
             serde_json::from_str("\"solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp\"").unwrap();
@@ -454,6 +478,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_roundtrip_eip155() {
         let original = ChainId::new("eip155", "8453");
         // let original = ChainId::eip155(8453);
@@ -463,6 +488,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_roundtrip_solana() {
         let original = ChainId::new("solana", "devnet");
         let serialized = serde_json::to_string(&original).unwrap();
@@ -471,12 +497,14 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_deserialize_invalid_format() {
         let result: Result<ChainId, _> = serde_json::from_str("\"invalid\"");
         assert!(result.is_err());
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_chain_id_deserialize_unknown_namespace() {
         let result: Result<ChainId, _> = serde_json::from_str("\"unknown:1\"");
         assert!(result.is_ok());
@@ -14,7 +14,9 @@
 //!
 //! # Configuration File Format
 //!
-//! Configuration is loaded from a JSON file (default: `config.json`) with the following structure:
+//! Configuration is loaded from a JSON or TOML file (default: `config.json`). The format is
+//! detected from the file extension: `.toml` files are parsed as TOML, everything else as JSON.
+//! A JSON file has the following structure:
 //!
 //! ```json
 //! {
@@ -23,10 +25,27 @@
 //!   "chains": { /* chain-specific configuration */ },
 //!   "schemes": [
 //!     { "scheme": "v2-eip155-exact", "chains": ["eip155:8453"] }
-//!   ]
+//!   ],
+//!   "max_sse_connections": 100,
+//!   "drain_timeout_secs": 30,
+//!   "network_definitions": "networks.json"
 //! }
 //! ```
 //!
+//! TOML files use the same fields:
+//!
+//! ```toml
+//! port = 8080
+//! host = "0.0.0.0"
+//!
+//! [chains]
+//! # chain-specific configuration
+//!
+//! [[schemes]]
+//! id = "v2-eip155-exact"
+//! chains = "eip155:8453"
+//! ```
+//!
 //! # Environment Variables
 //!
 //! - `CONFIG` - Path to configuration file (default: `config.json`)
@@ -53,19 +72,19 @@
 //!
 //! - `cli` - Enables CLI argument parsing via [`clap`]. When enabled, [`Config::load()`]
 //!   parses command-line arguments to determine the config file path.
+//! - `toml` - Enables loading configuration from `.toml` files via [`Config::load_toml()`].
 
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::fs;
+use std::io::BufReader;
 use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 #[cfg(feature = "cli")]
 use clap::Parser;
-#[cfg(feature = "cli")]
-use std::path::Path;
 
 use crate::scheme::SchemeConfig;
 
@@ -199,7 +218,7 @@ where
 #[cfg_attr(feature = "cli", command(about = "x402 Facilitator HTTP server"))]
 #[allow(dead_code)] // For downstream crates to use
 pub struct CliArgs {
-    /// Path to the JSON configuration file
+    /// Path to the configuration file (`.json` or `.toml`)
     #[cfg_attr(
         feature = "cli",
         arg(long, short, env = "CONFIG", default_value = "config.json")
@@ -211,7 +230,7 @@ pub struct CliArgs {
 ///
 /// Fields use serde defaults that fall back to environment variables,
 /// then to hardcoded defaults.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Config<TChainsConfig> {
     #[serde(default = "config_defaults::default_port")]
     port: u16,
@@ -221,6 +240,14 @@ pub struct Config<TChainsConfig> {
     chains: TChainsConfig,
     #[serde(default)]
     schemes: Vec<SchemeConfig>,
+    #[serde(default)]
+    receipt_signing_key: Option<LiteralOrEnv<String>>,
+    #[serde(default = "config_defaults::default_max_sse_connections")]
+    max_sse_connections: usize,
+    #[serde(default = "config_defaults::default_drain_timeout_secs")]
+    drain_timeout_secs: u64,
+    #[serde(default)]
+    network_definitions: Option<PathBuf>,
 }
 
 impl<TChainsConfig> Default for Config<TChainsConfig>
@@ -233,6 +260,10 @@ where
             host: config_defaults::default_host(),
             chains: TChainsConfig::default(),
             schemes: Vec::new(),
+            receipt_signing_key: None,
+            max_sse_connections: config_defaults::default_max_sse_connections(),
+            drain_timeout_secs: config_defaults::default_drain_timeout_secs(),
+            network_definitions: None,
         }
     }
 }
@@ -243,6 +274,8 @@ pub mod config_defaults {
 
     pub const DEFAULT_PORT: u16 = 8080;
     pub const DEFAULT_HOST: &str = "0.0.0.0";
+    pub const DEFAULT_MAX_SSE_CONNECTIONS: usize = 100;
+    pub const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
 
     /// Returns the default port value with fallback: $PORT env var -> 8080
     pub fn default_port() -> u16 {
@@ -259,6 +292,16 @@ pub mod config_defaults {
             .and_then(|s| s.parse().ok())
             .unwrap_or(IpAddr::V4(DEFAULT_HOST.parse().unwrap()))
     }
+
+    /// Returns the default limit of concurrent `/events` streams: 100
+    pub fn default_max_sse_connections() -> usize {
+        DEFAULT_MAX_SSE_CONNECTIONS
+    }
+
+    /// Returns the default time to wait for in-flight settlements on shutdown: 30 seconds
+    pub fn default_drain_timeout_secs() -> u64 {
+        DEFAULT_DRAIN_TIMEOUT_SECS
+    }
 }
 
 impl<TChainsConfig> Config<TChainsConfig> {
@@ -287,18 +330,44 @@ impl<TChainsConfig> Config<TChainsConfig> {
     pub fn chains(&self) -> &TChainsConfig {
         &self.chains
     }
+
+    /// Get the hex-encoded ed25519 seed used to sign settlement receipts, if configured.
+    ///
+    /// See [`crate::receipt`].
+    pub fn receipt_signing_key(&self) -> Option<&str> {
+        self.receipt_signing_key.as_deref().map(String::as_str)
+    }
+
+    /// Get the maximum number of concurrent `/events` Server-Sent Events streams (default: 100).
+    pub fn max_sse_connections(&self) -> usize {
+        self.max_sse_connections
+    }
+
+    /// Get how long to wait on shutdown for in-flight settlements to complete, in seconds
+    /// (default: 30).
+    pub fn drain_timeout_secs(&self) -> u64 {
+        self.drain_timeout_secs
+    }
+
+    /// Get the path of the file of networks and tokens to register at startup, if configured.
+    ///
+    /// See [`crate::networks::definitions`].
+    pub fn network_definitions(&self) -> Option<&Path> {
+        self.network_definitions.as_deref()
+    }
 }
 
 impl<TChainsConfig> Config<TChainsConfig>
 where
     TChainsConfig: Default + for<'de> Deserialize<'de>,
 {
-    /// Load configuration from CLI arguments and JSON file.
+    /// Load configuration from CLI arguments and a JSON or TOML file.
     ///
     /// The config file path is determined by:
     /// 1. `--config <path>` CLI argument
     /// 2. `./config.json` (if it exists)
     ///
+    /// The file format is detected from the extension, see [`Config::load_from_path`].
     /// Values not present in the config file will be resolved via
     /// environment variables or defaults during deserialization.
     #[cfg(feature = "cli")]
@@ -310,14 +379,60 @@ where
         Self::load_from_path(config_path)
     }
 
-    /// Load configuration from a specific path (or use defaults if None).
+    /// Load configuration from a specific path.
+    ///
+    /// Files with a `.toml` extension are parsed as TOML (requires the `toml` feature).
+    /// Any other extension, or no extension at all, is parsed as JSON.
     pub fn load_from_path(path: PathBuf) -> Result<Self, ConfigError> {
-        let content = fs::read_to_string(&path).map_err(|e| ConfigError::FileRead(path, e))?;
-        let config: Config<TChainsConfig> = serde_json::from_str(&content)?;
+        match ConfigFormat::from_path(&path) {
+            ConfigFormat::Json => Self::load_json(&path),
+            ConfigFormat::Toml => {
+                #[cfg(feature = "toml")]
+                return Self::load_toml(&path);
+                #[cfg(not(feature = "toml"))]
+                return Err(ConfigError::UnsupportedFormat(path));
+            }
+        }
+    }
+
+    /// Load configuration from a JSON file.
+    pub fn load_json(path: &Path) -> Result<Self, ConfigError> {
+        let file = fs::File::open(path).map_err(|e| ConfigError::FileRead(path.into(), e))?;
+        let config: Config<TChainsConfig> = serde_json::from_reader(BufReader::new(file))?;
+        Ok(config)
+    }
+
+    /// Load configuration from a TOML file.
+    #[cfg(feature = "toml")]
+    pub fn load_toml(path: &Path) -> Result<Self, ConfigError> {
+        let content =
+            fs::read_to_string(path).map_err(|e| ConfigError::FileRead(path.into(), e))?;
+        let config: Config<TChainsConfig> = toml::from_str(&content)?;
         Ok(config)
     }
 }
 
+/// Configuration file format, detected from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON format (`.json`, and the fallback for unknown extensions).
+    Json,
+    /// TOML format (`.toml`).
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Detects the configuration format from the file extension.
+    ///
+    /// Falls back to [`ConfigFormat::Json`] when the extension is missing or not recognized.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
 /// Configuration error types.
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -325,4 +440,120 @@ pub enum ConfigError {
     FileRead(PathBuf, std::io::Error),
     #[error("Failed to parse config file: {0}")]
     JsonParse(#[from] serde_json::Error),
+    #[cfg(feature = "toml")]
+    #[error("Failed to parse TOML config file: {0}")]
+    TomlParse(#[from] toml::de::Error),
+    #[cfg(not(feature = "toml"))]
+    #[error("Config file at {0} is TOML, but the `toml` feature is not enabled")]
+    UnsupportedFormat(PathBuf),
+}
+
+#[cfg(all(test, feature = "toml"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type TestConfig = Config<serde_json::Value>;
+
+    /// Writes `content` to a unique temp file with the given extension and loads it back.
+    fn load_as(content: &str, name: &str, extension: &str) -> TestConfig {
+        let path = std::env::temp_dir().join(format!(
+            "x402-config-{}-{name}.{extension}",
+            std::process::id()
+        ));
+        fs::write(&path, content).unwrap();
+        let config = TestConfig::load_from_path(path.clone());
+        fs::remove_file(&path).unwrap();
+        config.unwrap()
+    }
+
+    /// Enumerates logical configs covering every top-level field, with and without defaults.
+    fn logical_configs() -> Vec<serde_json::Value> {
+        let ports = [None, Some(1), Some(3000), Some(u16::MAX)];
+        let hosts = [None, Some("127.0.0.1"), Some("::1")];
+        let chains = [
+            None,
+            Some(json!({})),
+            Some(json!({
+                "eip155:84532": {
+                    "eip1559": true,
+                    "signers": ["$EVM_PRIVATE_KEY"],
+                    "rpc": [{ "http": "https://sepolia.base.org", "rate_limit": 50 }]
+                }
+            })),
+        ];
+        let schemes = [
+            None,
+            Some(json!([])),
+            Some(json!([
+                { "id": "v2-eip155-exact", "chains": "eip155:*" },
+                { "id": "v1-eip155-exact", "chains": "eip155:{1,8453}", "enabled": false },
+                { "id": "v2-solana-exact", "chains": "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp",
+                  "config": { "allow_lighthouse": true, "max_fee": 5000, "programs": ["a", "b"] } }
+            ])),
+        ];
+
+        let mut configs = Vec::new();
+        for port in &ports {
+            for host in &hosts {
+                for chain in &chains {
+                    for scheme in &schemes {
+                        let mut config = serde_json::Map::new();
+                        if let Some(port) = port {
+                            config.insert("port".into(), json!(port));
+                        }
+                        if let Some(host) = host {
+                            config.insert("host".into(), json!(host));
+                        }
+                        if let Some(chain) = chain {
+                            config.insert("chains".into(), chain.clone());
+                        }
+                        if let Some(scheme) = scheme {
+                            config.insert("schemes".into(), scheme.clone());
+                        }
+                        configs.push(serde_json::Value::Object(config));
+                    }
+                }
+            }
+        }
+        configs
+    }
+
+    #[test]
+    fn json_and_toml_produce_identical_config() {
+        for (i, logical) in logical_configs().iter().enumerate() {
+            let json = serde_json::to_string_pretty(logical).unwrap();
+            let toml = toml::to_string(logical).unwrap();
+            let from_json = load_as(&json, &i.to_string(), "json");
+            let from_toml = load_as(&toml, &i.to_string(), "toml");
+            assert_eq!(
+                from_json, from_toml,
+                "config #{i} differs:\n{json}\n---\n{toml}"
+            );
+        }
+    }
+
+    #[test]
+    fn detects_format_from_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.TOML")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.json")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.conf")),
+            ConfigFormat::Json
+        );
+    }
 }
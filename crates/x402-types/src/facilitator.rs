@@ -6,9 +6,22 @@
 use std::fmt::{Debug, Display};
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::proto;
 
+/// Response header of `/verify` carrying how many seconds a successful verification
+/// may be cached, see [`Facilitator::verify_cache_ttl`].
+pub const VERIFY_TTL_HEADER: &str = "X-Verify-TTL";
+
+/// Header correlating the `/verify` and `/settle` requests of a payment. Facilitators
+/// echo it in their responses, generating one if the request has none.
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+/// OpenTelemetry baggage key carrying the payment ID from a resource server to the
+/// facilitator, next to the W3C trace context of its `/verify` and `/settle` requests.
+pub const PAYMENT_ID_BAGGAGE_KEY: &str = "x402.payment_id";
+
 /// Type-level contract that associates the concrete request/response types used
 /// by a [`Facilitator`] implementation.
 ///
@@ -97,6 +110,44 @@ pub trait Facilitator<C: FacilitatorContract = ProtoContract> {
     /// Returns [`Self::Error`] if the facilitator is unable to enumerate its capabilities.
     #[allow(dead_code)] // For some reason clippy believes it is not used.
     fn supported(&self) -> impl Future<Output = Result<C::SupportedResponse, Self::Error>> + Send;
+
+    /// Settles several payments, returning one result per request, in order.
+    ///
+    /// A failed payment does not fail the others. The default settles them one by one
+    /// with [`Facilitator::settle`]; implementations may combine payments on the same
+    /// chain into a single transaction.
+    fn settle_batch(
+        &self,
+        requests: &[C::SettleRequest],
+    ) -> impl Future<Output = Vec<Result<C::SettleResponse, Self::Error>>> + Send
+    where
+        Self::Error: Send,
+        C::SettleRequest: Sync,
+        C::SettleResponse: Send,
+    {
+        // The settle futures are created up front, so that the returned future does not
+        // borrow `self` and is `Send` without requiring `Self: Sync`.
+        let settlements: Vec<_> = requests
+            .iter()
+            .map(|request| self.settle(request))
+            .collect();
+        async move {
+            let mut results = Vec::with_capacity(settlements.len());
+            for settlement in settlements {
+                results.push(settlement.await);
+            }
+            results
+        }
+    }
+
+    /// Returns how long a successful verification of `request` may be cached by the caller.
+    ///
+    /// HTTP facilitators advertise it in the [`VERIFY_TTL_HEADER`] of `/verify` responses.
+    /// Defaults to `None`, meaning verification results must not be cached.
+    fn verify_cache_ttl(&self, request: &C::VerifyRequest) -> Option<Duration> {
+        let _ = request;
+        None
+    }
 }
 
 impl<C, T> Facilitator<C> for Arc<T>
@@ -123,4 +174,83 @@ where
     fn supported(&self) -> impl Future<Output = Result<C::SupportedResponse, Self::Error>> + Send {
         self.as_ref().supported()
     }
+
+    fn settle_batch(
+        &self,
+        requests: &[C::SettleRequest],
+    ) -> impl Future<Output = Vec<Result<C::SettleResponse, Self::Error>>> + Send
+    where
+        Self::Error: Send,
+        C::SettleRequest: Sync,
+        C::SettleResponse: Send,
+    {
+        self.as_ref().settle_batch(requests)
+    }
+
+    fn verify_cache_ttl(&self, request: &C::VerifyRequest) -> Option<Duration> {
+        self.as_ref().verify_cache_ttl(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::future::ready;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    /// Contract settling numbers, so that a test facilitator needs no payment types.
+    struct NumberContract;
+
+    impl FacilitatorContract for NumberContract {
+        type VerifyRequest = u32;
+        type VerifyResponse = u32;
+        type SettleRequest = u32;
+        type SettleResponse = u32;
+        type SupportedResponse = ();
+    }
+
+    /// Facilitator settling odd numbers only. It is not `Sync`.
+    #[derive(Default)]
+    struct OddFacilitator {
+        _not_sync: Cell<()>,
+    }
+
+    impl Facilitator<NumberContract> for OddFacilitator {
+        type Error = String;
+
+        fn verify(&self, request: &u32) -> impl Future<Output = Result<u32, String>> + Send {
+            ready(Ok(*request))
+        }
+
+        fn settle(&self, request: &u32) -> impl Future<Output = Result<u32, String>> + Send {
+            let request = *request;
+            ready(
+                (request % 2 == 1)
+                    .then_some(request)
+                    .ok_or(format!("{request} is even")),
+            )
+        }
+
+        fn supported(&self) -> impl Future<Output = Result<(), String>> + Send {
+            ready(Ok(()))
+        }
+    }
+
+    /// Resolves a future that completes on its first poll.
+    fn resolve<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    #[test]
+    #[allow(clippy::arc_with_non_send_sync)] // The Arc impl must not require `Sync`.
+    fn batches_are_settled_one_by_one_through_an_arc() {
+        let facilitator = Arc::new(OddFacilitator::default());
+        let results = resolve(facilitator.settle_batch(&[1, 2, 3]));
+        assert_eq!(results, vec![Ok(1), Err("2 is even".to_string()), Ok(3)]);
+    }
 }
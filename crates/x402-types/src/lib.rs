@@ -16,11 +16,13 @@
 //! - [`chain`] - Blockchain identifiers and provider abstractions (CAIP-2 chain IDs)
 //! - [`config`] - Server configuration, CLI parsing, RPC config, and environment variable resolution
 //! - [`facilitator`] - Core trait for payment verification and settlement
+//! - [`lifecycle`] - Typed state machine of a payment from verification to settlement
 //! - [`networks`] - Registry of well-known blockchain networks
 //! - [`proto`] - Wire format types for protocol messages (V1 and V2)
+//! - [`receipt`] - Facilitator-signed settlement receipts
 //! - [`scheme`] - Payment scheme system for extensible payment methods
 //! - [`timestamp`] - Unix timestamp utilities for payment authorization windows
-//! - [`util`] - Helper types (base64, string literals, money amounts)
+//! - [`util`] - Helper types (base64, string literals, money amounts, revert reasons)
 //!
 //! # Protocol Versions
 //!
@@ -31,14 +33,38 @@
 //!
 //! # Feature Flags
 //!
+//! - `serde` (default) - Enables serialization of all types, the JSON wire messages in
+//!   [`proto`], and the `config`, `facilitator`, `lifecycle`, `receipt` and `scheme`
+//!   modules built on them.
+//!   Without it, only chain identifiers, networks, timestamps, money amounts and the
+//!   serde-free protocol types of [`proto::types`] are available.
 //! - `cli` - Enables CLI argument parsing via clap for configuration loading
 //! - `telemetry` - Enables tracing instrumentation for debugging and monitoring
+//! - `toml` - Enables loading configuration from `.toml` files
+//! - `receipt` - Enables signing and verifying settlement receipts (ed25519)
+//! - `chrono` - Converts [`timestamp::UnixTimestamp`] to and from `chrono::DateTime<Utc>`,
+//!   and to and from RFC 3339 strings
+//! - `time` - Converts [`timestamp::UnixTimestamp`] to and from `time::OffsetDateTime`,
+//!   and to and from RFC 3339 strings
+//! - `json-schema` - Derives `schemars::JsonSchema` for the V2 protocol messages, and
+//!   generates their JSON Schemas with [`schema`]
+#![doc = ""]
+#![doc = include_str!("../STABILITY.md")]
 
 pub mod chain;
+#[cfg(feature = "serde")]
 pub mod config;
+#[cfg(feature = "serde")]
 pub mod facilitator;
+#[cfg(feature = "serde")]
+pub mod lifecycle;
 pub mod networks;
 pub mod proto;
+#[cfg(feature = "serde")]
+pub mod receipt;
+#[cfg(feature = "serde")]
 pub mod scheme;
+#[cfg(feature = "json-schema")]
+pub mod schema;
 pub mod timestamp;
 pub mod util;
@@ -0,0 +1,398 @@
+//! Typed state machine of a payment going through verification and settlement.
+//!
+//! A [`PaymentLifecycle`] wraps a [`proto::VerifyRequest`] together with the state the
+//! payment has reached. Each state is a distinct type, so that the transitions available
+//! on a lifecycle are checked at compile time:
+//!
+//! ```text
+//! Pending ──verify──▶ Verified ──settle──▶ Settled
+//!    │                   │
+//!    └──────fail─────────┴──────▶ Failed
+//! ```
+//!
+//! In particular, only a [`Verified`] lifecycle can be settled:
+//!
+//! ```compile_fail
+//! # use x402_types::lifecycle::PaymentLifecycle;
+//! # fn f(request: x402_types::proto::VerifyRequest) {
+//! let pending = PaymentLifecycle::new(request);
+//! pending.settle("0xtx"); // no `settle` on `PaymentLifecycle<Pending>`
+//! # }
+//! ```
+//!
+//! and a settled or failed payment cannot go back:
+//!
+//! ```compile_fail
+//! # use x402_types::lifecycle::{PaymentLifecycle, Settled};
+//! # fn f(settled: PaymentLifecycle<Settled>) {
+//! settled.fail("too late"); // no `fail` on `PaymentLifecycle<Settled>`
+//! # }
+//! ```
+//!
+//! [`PaymentLifecycle::into_audit_event`] turns a lifecycle in any state into an
+//! [`AuditEvent`] recording how far the payment got and how long it took.
+
+use serde::Serialize;
+use std::time::Duration;
+use web_time::Instant;
+
+use crate::chain::ChainId;
+use crate::proto;
+
+/// A payment that was neither verified nor failed yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pending;
+
+/// A payment that passed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified {
+    /// The payer reported by the verifier.
+    pub payer: String,
+}
+
+/// A payment that was settled on-chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settled {
+    /// Hash of the settlement transaction.
+    pub tx_hash: String,
+}
+
+/// A payment that failed verification or settlement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failed {
+    /// Why the payment failed.
+    pub reason: String,
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Pending {}
+    impl Sealed for super::Verified {}
+    impl Sealed for super::Settled {}
+    impl Sealed for super::Failed {}
+}
+
+/// State of a [`PaymentLifecycle`]: one of [`Pending`], [`Verified`], [`Settled`] or [`Failed`].
+pub trait LifecycleState: sealed::Sealed {
+    /// The stage reported in audit events.
+    const STAGE: LifecycleStage;
+
+    /// Fills in the state-specific fields of `event`.
+    #[doc(hidden)]
+    fn annotate(&self, event: &mut AuditEvent);
+}
+
+impl LifecycleState for Pending {
+    const STAGE: LifecycleStage = LifecycleStage::Pending;
+
+    fn annotate(&self, _event: &mut AuditEvent) {}
+}
+
+impl LifecycleState for Verified {
+    const STAGE: LifecycleStage = LifecycleStage::Verified;
+
+    fn annotate(&self, _event: &mut AuditEvent) {}
+}
+
+impl LifecycleState for Settled {
+    const STAGE: LifecycleStage = LifecycleStage::Settled;
+
+    fn annotate(&self, event: &mut AuditEvent) {
+        event.transaction = Some(self.tx_hash.clone());
+    }
+}
+
+impl LifecycleState for Failed {
+    const STAGE: LifecycleStage = LifecycleStage::Failed;
+
+    fn annotate(&self, event: &mut AuditEvent) {
+        event.reason = Some(self.reason.clone());
+    }
+}
+
+/// A payment request and the state it has reached.
+#[derive(Debug, Clone)]
+pub struct PaymentLifecycle<S> {
+    request: proto::VerifyRequest,
+    started_at: Instant,
+    payer: Option<String>,
+    state: S,
+}
+
+impl PaymentLifecycle<Pending> {
+    /// Starts the lifecycle of `request`. [`elapsed`](Self::elapsed) counts from now.
+    pub fn new(request: proto::VerifyRequest) -> Self {
+        Self {
+            request,
+            started_at: Instant::now(),
+            payer: None,
+            state: Pending,
+        }
+    }
+
+    /// Marks the payment as verified for `payer`.
+    pub fn verify(self, payer: impl Into<String>) -> PaymentLifecycle<Verified> {
+        let payer = payer.into();
+        self.transition(Some(payer.clone()), Verified { payer })
+    }
+
+    /// Applies a facilitator's verification response: verified if `isValid` is `true`,
+    /// otherwise failed with its `invalidReason`.
+    pub fn verified_by(
+        self,
+        response: &proto::VerifyResponse,
+    ) -> Result<PaymentLifecycle<Verified>, PaymentLifecycle<Failed>> {
+        let response = &response.0;
+        if response.get("isValid") == Some(&serde_json::Value::Bool(true)) {
+            let payer = string_field(response, "payer").unwrap_or_default();
+            Ok(self.verify(payer))
+        } else {
+            let reason = string_field(response, "invalidReason");
+            Err(self.fail(reason.unwrap_or_else(|| "unknown".to_string())))
+        }
+    }
+
+    /// Marks the payment as failed for `reason`.
+    pub fn fail(self, reason: impl Into<String>) -> PaymentLifecycle<Failed> {
+        let reason = reason.into();
+        self.transition(None, Failed { reason })
+    }
+}
+
+impl PaymentLifecycle<Verified> {
+    /// The payer reported by the verifier.
+    pub fn payer(&self) -> &str {
+        &self.state.payer
+    }
+
+    /// Marks the payment as settled in transaction `tx_hash`.
+    pub fn settle(self, tx_hash: impl Into<String>) -> PaymentLifecycle<Settled> {
+        let tx_hash = tx_hash.into();
+        let payer = self.payer.clone();
+        self.transition(payer, Settled { tx_hash })
+    }
+
+    /// Applies a facilitator's settlement response: settled if `success` is `true`,
+    /// otherwise failed with its `errorReason`.
+    pub fn settled_by(
+        self,
+        response: &proto::SettleResponse,
+    ) -> Result<PaymentLifecycle<Settled>, PaymentLifecycle<Failed>> {
+        let response = &response.0;
+        if response.get("success") == Some(&serde_json::Value::Bool(true)) {
+            let tx_hash = string_field(response, "transaction").unwrap_or_default();
+            Ok(self.settle(tx_hash))
+        } else {
+            let reason = string_field(response, "errorReason");
+            Err(self.fail(reason.unwrap_or_else(|| "unknown".to_string())))
+        }
+    }
+
+    /// Marks the payment as failed for `reason`, e.g. when settlement fails.
+    pub fn fail(self, reason: impl Into<String>) -> PaymentLifecycle<Failed> {
+        let reason = reason.into();
+        let payer = self.payer.clone();
+        self.transition(payer, Failed { reason })
+    }
+}
+
+impl PaymentLifecycle<Settled> {
+    /// Hash of the settlement transaction.
+    pub fn tx_hash(&self) -> &str {
+        &self.state.tx_hash
+    }
+}
+
+impl PaymentLifecycle<Failed> {
+    /// Why the payment failed.
+    pub fn reason(&self) -> &str {
+        &self.state.reason
+    }
+}
+
+impl<S> PaymentLifecycle<S> {
+    /// The payment request.
+    pub fn request(&self) -> &proto::VerifyRequest {
+        &self.request
+    }
+
+    /// The current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Time since the lifecycle was started.
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    fn transition<T>(self, payer: Option<String>, state: T) -> PaymentLifecycle<T> {
+        PaymentLifecycle {
+            request: self.request,
+            started_at: self.started_at,
+            payer,
+            state,
+        }
+    }
+}
+
+impl<S: LifecycleState> PaymentLifecycle<S> {
+    /// Describes the payment as it stands, for an audit log.
+    pub fn into_audit_event(self) -> AuditEvent {
+        let mut event = AuditEvent {
+            stage: S::STAGE,
+            payer: self.payer,
+            chain: self.request.scheme_handler_slug().map(|slug| slug.chain_id),
+            amount: self.request.amount(),
+            transaction: None,
+            reason: None,
+            elapsed_ms: self.started_at.elapsed().as_millis() as u64,
+        };
+        self.state.annotate(&mut event);
+        event
+    }
+}
+
+/// Stage reached by a payment, as recorded in an [`AuditEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleStage {
+    /// Neither verified nor failed.
+    Pending,
+    /// Verified, not settled.
+    Verified,
+    /// Settled on-chain.
+    Settled,
+    /// Failed verification or settlement.
+    Failed,
+}
+
+/// Record of a [`PaymentLifecycle`], from [`PaymentLifecycle::into_audit_event`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    /// The stage the payment reached.
+    pub stage: LifecycleStage,
+    /// The payer, once verified.
+    pub payer: Option<String>,
+    /// The chain of the payment.
+    pub chain: Option<ChainId>,
+    /// The required amount, in token units.
+    pub amount: Option<String>,
+    /// The settlement transaction, if settled.
+    pub transaction: Option<String>,
+    /// Why the payment failed, if it did.
+    pub reason: Option<String>,
+    /// Milliseconds from the start of the lifecycle to the event.
+    pub elapsed_ms: u64,
+}
+
+/// A non-empty string field of a JSON response.
+fn string_field(response: &serde_json::Value, field: &str) -> Option<String> {
+    response
+        .get(field)
+        .and_then(|value| value.as_str())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request() -> proto::VerifyRequest {
+        serde_json::from_value(json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": { "scheme": "exact", "network": "eip155:8453" },
+                "payload": {},
+            },
+            "paymentRequirements": { "scheme": "exact", "network": "eip155:8453", "amount": "10000" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn pending_to_verified_to_settled() {
+        let pending = PaymentLifecycle::new(request());
+        assert_eq!(
+            pending.clone().into_audit_event().stage,
+            LifecycleStage::Pending
+        );
+
+        let verified = pending.verify("0xpayer");
+        assert_eq!(verified.payer(), "0xpayer");
+        let settled = verified.settle("0xtx");
+        assert_eq!(settled.tx_hash(), "0xtx");
+        assert!(settled.elapsed() < Duration::from_secs(60));
+
+        let event = settled.into_audit_event();
+        assert_eq!(event.stage, LifecycleStage::Settled);
+        assert_eq!(event.payer.as_deref(), Some("0xpayer"));
+        assert_eq!(event.chain, Some(ChainId::new("eip155", "8453")));
+        assert_eq!(event.amount.as_deref(), Some("10000"));
+        assert_eq!(event.transaction.as_deref(), Some("0xtx"));
+        assert_eq!(event.reason, None);
+    }
+
+    #[test]
+    fn pending_or_verified_to_failed() {
+        let failed = PaymentLifecycle::new(request()).fail("invalid_signature");
+        assert_eq!(failed.reason(), "invalid_signature");
+        let event = failed.into_audit_event();
+        assert_eq!(event.stage, LifecycleStage::Failed);
+        assert_eq!(event.payer, None);
+        assert_eq!(event.reason.as_deref(), Some("invalid_signature"));
+
+        // The payer is kept when settlement fails
+        let event = PaymentLifecycle::new(request())
+            .verify("0xpayer")
+            .fail("nonce_used")
+            .into_audit_event();
+        assert_eq!(event.stage, LifecycleStage::Failed);
+        assert_eq!(event.payer.as_deref(), Some("0xpayer"));
+        assert_eq!(event.transaction, None);
+    }
+
+    #[test]
+    fn transitions_follow_facilitator_responses() {
+        let verified = PaymentLifecycle::new(request())
+            .verified_by(&proto::VerifyResponse(
+                json!({ "isValid": true, "payer": "0xpayer" }),
+            ))
+            .unwrap();
+        assert_eq!(verified.payer(), "0xpayer");
+        let settled = verified
+            .clone()
+            .settled_by(&proto::SettleResponse(
+                json!({ "success": true, "payer": "0xpayer", "transaction": "0xtx", "network": "eip155:8453" }),
+            ))
+            .unwrap();
+        assert_eq!(settled.tx_hash(), "0xtx");
+        let failed = verified
+            .settled_by(&proto::SettleResponse(
+                json!({ "success": false, "errorReason": "insufficient_funds", "transaction": "" }),
+            ))
+            .unwrap_err();
+        assert_eq!(failed.reason(), "insufficient_funds");
+
+        let failed = PaymentLifecycle::new(request())
+            .verified_by(&proto::VerifyResponse(json!({ "isValid": false })))
+            .unwrap_err();
+        assert_eq!(failed.reason(), "unknown");
+    }
+
+    #[test]
+    fn audit_events_serialize() {
+        let event = PaymentLifecycle::new(request())
+            .fail("expired")
+            .into_audit_event();
+        let value = serde_json::to_value(event).unwrap();
+        assert_eq!(value["stage"], "failed");
+        assert_eq!(value["chain"], "eip155:8453");
+        assert_eq!(value["reason"], "expired");
+        assert!(value["elapsedMs"].is_u64());
+    }
+}
@@ -0,0 +1,511 @@
+//! Network and token deployment definitions loaded from a file at startup.
+//!
+//! [`KNOWN_NETWORKS`](super::KNOWN_NETWORKS) and the token deployments of the chain crates
+//! are compiled in, so supporting a new chain would otherwise take a crate release.
+//! [`NetworkDefinitions`] adds networks and tokens at runtime instead: once registered,
+//! a network is resolved by [`ChainId::from_network_name`] and
+//! [`ChainId::as_network_name`] like a built-in one, and its tokens are returned by
+//! [`registered_tokens`], which the chain crates merge into their known token deployments.
+//!
+//! # File Format
+//!
+//! Definitions are loaded from a JSON or TOML file, detected from the extension as for
+//! [`Config::load_from_path`](crate::config::Config::load_from_path):
+//!
+//! ```json
+//! {
+//!   "networks": [
+//!     { "name": "xdc-apothem", "chainId": "eip155:51" }
+//!   ],
+//!   "tokens": [
+//!     {
+//!       "network": "xdc-apothem",
+//!       "symbol": "USDC",
+//!       "address": "0xb5AB69F7bBada22B28e79C8FFAECe55eF1c771D4",
+//!       "decimals": 6,
+//!       "eip712": { "name": "USDC", "version": "2" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! A token refers to its network by name, built-in or defined in the same file, or by
+//! CAIP-2 chain ID. EVM tokens need the `eip712` domain of the token contract, and
+//! default to the `eip3009` transfer method; set `"transferMethod": "permit2"` for
+//! tokens without EIP-3009 support.
+//!
+//! # Validation
+//!
+//! [`NetworkDefinitions::register`] rejects the whole file if any entry is malformed, or
+//! if a network name, chain ID or token is defined twice, including by a built-in network
+//! or by definitions registered earlier.
+//!
+//! # Example
+//!
+//! ```
+//! use x402_types::chain::ChainId;
+//! use x402_types::networks::definitions::{NetworkDefinitions, registered_tokens};
+//!
+//! let definitions: NetworkDefinitions = serde_json::from_value(serde_json::json!({
+//!     "networks": [{ "name": "example-testnet", "chainId": "eip155:7357" }],
+//!     "tokens": [{
+//!         "network": "example-testnet",
+//!         "symbol": "USDC",
+//!         "address": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+//!         "decimals": 6,
+//!         "eip712": { "name": "USDC", "version": "2" }
+//!     }]
+//! }))
+//! .unwrap();
+//! definitions.register().unwrap();
+//!
+//! let chain_id = ChainId::from_network_name("example-testnet").unwrap();
+//! assert_eq!(chain_id, ChainId::new("eip155", "7357"));
+//! assert_eq!(chain_id.as_network_name(), Some("example-testnet"));
+//! assert!(registered_tokens().iter().any(|token| token.chain_id == chain_id));
+//! ```
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{LazyLock, PoisonError, RwLock};
+
+use crate::chain::ChainId;
+use crate::config::ConfigFormat;
+
+use super::{CHAIN_ID_TO_NAME, NAME_TO_CHAIN_ID};
+
+/// Networks and token deployments to add to the built-in ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkDefinitions {
+    /// Networks to resolve by name.
+    #[serde(default)]
+    pub networks: Vec<NetworkDefinition>,
+    /// Token deployments on built-in or defined networks.
+    #[serde(default)]
+    pub tokens: Vec<TokenDefinition>,
+}
+
+/// A network, named for [`ChainId::from_network_name`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NetworkDefinition {
+    /// Human-readable network name (e.g., "xdc-apothem").
+    pub name: String,
+    /// CAIP-2 chain ID of the network (e.g., "eip155:51").
+    pub chain_id: ChainId,
+}
+
+/// A token deployment on a network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TokenDefinition {
+    /// Name or CAIP-2 chain ID of the network the token is deployed on.
+    pub network: String,
+    /// Token symbol (e.g., "USDC").
+    pub symbol: String,
+    /// Token contract address, or mint address on Solana.
+    pub address: String,
+    /// Number of decimal places for the token.
+    pub decimals: u8,
+    /// EIP-712 domain of the token contract, required on EVM networks.
+    #[serde(default)]
+    pub eip712: Option<Eip712Domain>,
+    /// How the token is transferred on EVM networks.
+    #[serde(default)]
+    pub transfer_method: TokenTransferMethod,
+}
+
+/// The `name` and `version` of an EIP-712 domain.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Eip712Domain {
+    /// The token name as specified in the EIP-712 domain.
+    pub name: String,
+    /// The token version as specified in the EIP-712 domain.
+    pub version: String,
+}
+
+/// How an EVM token is transferred.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenTransferMethod {
+    /// `transferWithAuthorization` of EIP-3009.
+    #[default]
+    Eip3009,
+    /// Permit2 signature transfers.
+    Permit2,
+}
+
+/// A token deployment from registered definitions, with its network resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegisteredToken {
+    /// The chain the token is deployed on.
+    pub chain_id: ChainId,
+    /// Token symbol (e.g., "USDC").
+    pub symbol: String,
+    /// Token contract address, or mint address on Solana.
+    pub address: String,
+    /// Number of decimal places for the token.
+    pub decimals: u8,
+    /// EIP-712 domain of the token contract, set on EVM networks.
+    pub eip712: Option<Eip712Domain>,
+    /// How the token is transferred on EVM networks.
+    pub transfer_method: TokenTransferMethod,
+}
+
+/// Error loading or registering [`NetworkDefinitions`].
+#[derive(Debug, thiserror::Error)]
+pub enum NetworkDefinitionsError {
+    #[error("Failed to read network definitions at {0}: {1}")]
+    FileRead(PathBuf, std::io::Error),
+    #[error("Failed to parse network definitions: {0}")]
+    JsonParse(#[from] serde_json::Error),
+    #[cfg(feature = "toml")]
+    #[error("Failed to parse TOML network definitions: {0}")]
+    TomlParse(#[from] toml::de::Error),
+    #[cfg(not(feature = "toml"))]
+    #[error("Network definitions at {0} are TOML, but the `toml` feature is not enabled")]
+    UnsupportedFormat(PathBuf),
+    #[error("Invalid network definition #{index}: {reason}")]
+    InvalidNetwork { index: usize, reason: String },
+    #[error("Invalid token definition #{index}: {reason}")]
+    InvalidToken { index: usize, reason: String },
+    #[error("Network name {0} is defined more than once")]
+    DuplicateName(String),
+    #[error("Chain id {0} is defined more than once")]
+    DuplicateChainId(ChainId),
+    #[error("Token {address} on {chain_id} is defined more than once")]
+    DuplicateToken { chain_id: ChainId, address: String },
+}
+
+/// Networks and tokens registered at runtime. Entries are never removed, so names and
+/// chain IDs are leaked to be looked up as `'static` like the built-in ones.
+#[derive(Default)]
+struct Registry {
+    name_to_chain_id: HashMap<&'static str, &'static ChainId>,
+    chain_id_to_name: HashMap<ChainId, &'static str>,
+    tokens: Vec<RegisteredToken>,
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(Default::default);
+
+impl NetworkDefinitions {
+    /// Load definitions from a file.
+    ///
+    /// Files with a `.toml` extension are parsed as TOML (requires the `toml` feature).
+    /// Any other extension, or no extension at all, is parsed as JSON.
+    pub fn load_from_path(path: &Path) -> Result<Self, NetworkDefinitionsError> {
+        match ConfigFormat::from_path(path) {
+            ConfigFormat::Json => Self::load_json(path),
+            ConfigFormat::Toml => {
+                #[cfg(feature = "toml")]
+                return Self::load_toml(path);
+                #[cfg(not(feature = "toml"))]
+                return Err(NetworkDefinitionsError::UnsupportedFormat(path.into()));
+            }
+        }
+    }
+
+    /// Load definitions from a JSON file.
+    pub fn load_json(path: &Path) -> Result<Self, NetworkDefinitionsError> {
+        let file =
+            fs::File::open(path).map_err(|e| NetworkDefinitionsError::FileRead(path.into(), e))?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// Load definitions from a TOML file.
+    #[cfg(feature = "toml")]
+    pub fn load_toml(path: &Path) -> Result<Self, NetworkDefinitionsError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| NetworkDefinitionsError::FileRead(path.into(), e))?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Checks the definitions against the built-in networks and the ones registered so far,
+    /// without registering them.
+    pub fn validate(&self) -> Result<(), NetworkDefinitionsError> {
+        let registry = REGISTRY.read().unwrap_or_else(PoisonError::into_inner);
+        self.resolve(&registry).map(|_| ())
+    }
+
+    /// Validates the definitions, then registers all of them.
+    ///
+    /// Nothing is registered if any definition is invalid.
+    pub fn register(self) -> Result<(), NetworkDefinitionsError> {
+        let mut registry = REGISTRY.write().unwrap_or_else(PoisonError::into_inner);
+        let tokens = self.resolve(&registry)?;
+        for network in self.networks {
+            let name: &'static str = Box::leak(network.name.into_boxed_str());
+            let chain_id: &'static ChainId = Box::leak(Box::new(network.chain_id));
+            registry.name_to_chain_id.insert(name, chain_id);
+            registry.chain_id_to_name.insert(chain_id.clone(), name);
+        }
+        registry.tokens.extend(tokens);
+        Ok(())
+    }
+
+    /// Validates every definition and resolves the networks of the tokens.
+    fn resolve(
+        &self,
+        registry: &Registry,
+    ) -> Result<Vec<RegisteredToken>, NetworkDefinitionsError> {
+        let mut names = HashMap::new();
+        let mut chain_ids = HashSet::new();
+        for (index, network) in self.networks.iter().enumerate() {
+            let invalid = |reason: &str| NetworkDefinitionsError::InvalidNetwork {
+                index,
+                reason: reason.into(),
+            };
+            let name = network.name.as_str();
+            if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == ':') {
+                return Err(invalid("name must be non-empty, without whitespace or ':'"));
+            }
+            validate_chain_id(&network.chain_id).map_err(invalid)?;
+            if NAME_TO_CHAIN_ID.contains_key(name)
+                || registry.name_to_chain_id.contains_key(name)
+                || names.insert(name, &network.chain_id).is_some()
+            {
+                return Err(NetworkDefinitionsError::DuplicateName(name.into()));
+            }
+            if CHAIN_ID_TO_NAME.contains_key(&network.chain_id)
+                || registry.chain_id_to_name.contains_key(&network.chain_id)
+                || !chain_ids.insert(&network.chain_id)
+            {
+                return Err(NetworkDefinitionsError::DuplicateChainId(
+                    network.chain_id.clone(),
+                ));
+            }
+        }
+
+        let mut tokens: Vec<RegisteredToken> = Vec::with_capacity(self.tokens.len());
+        for (index, token) in self.tokens.iter().enumerate() {
+            let invalid = |reason: String| NetworkDefinitionsError::InvalidToken { index, reason };
+            let chain_id = match names.get(token.network.as_str()) {
+                Some(chain_id) => (*chain_id).clone(),
+                None => match NAME_TO_CHAIN_ID.get(token.network.as_str()).or_else(|| {
+                    registry
+                        .name_to_chain_id
+                        .get(token.network.as_str())
+                        .copied()
+                }) {
+                    Some(chain_id) => chain_id.clone(),
+                    None => ChainId::from_str(&token.network)
+                        .ok()
+                        .filter(|chain_id| validate_chain_id(chain_id).is_ok())
+                        .ok_or_else(|| invalid(format!("unknown network {}", token.network)))?,
+                },
+            };
+            if token.symbol.is_empty() {
+                return Err(invalid("symbol must be non-empty".into()));
+            }
+            if token.address.is_empty() {
+                return Err(invalid("address must be non-empty".into()));
+            }
+            if chain_id.namespace == "eip155" {
+                if Address::from_str(&token.address).is_err() {
+                    return Err(invalid(format!("invalid EVM address {}", token.address)));
+                }
+                if token.eip712.is_none() {
+                    return Err(invalid("EVM tokens need an eip712 domain".into()));
+                }
+            }
+            let duplicate = |other: &RegisteredToken| {
+                other.chain_id == chain_id && other.address.eq_ignore_ascii_case(&token.address)
+            };
+            if tokens.iter().any(duplicate) || registry.tokens.iter().any(duplicate) {
+                return Err(NetworkDefinitionsError::DuplicateToken {
+                    chain_id,
+                    address: token.address.clone(),
+                });
+            }
+            tokens.push(RegisteredToken {
+                chain_id,
+                symbol: token.symbol.clone(),
+                address: token.address.clone(),
+                decimals: token.decimals,
+                eip712: token.eip712.clone(),
+                transfer_method: token.transfer_method,
+            });
+        }
+        Ok(tokens)
+    }
+}
+
+fn validate_chain_id(chain_id: &ChainId) -> Result<(), &'static str> {
+    if chain_id.namespace.is_empty() || chain_id.reference.is_empty() {
+        return Err("chain id must be a CAIP-2 namespace:reference");
+    }
+    if chain_id.namespace == "eip155" && chain_id.reference.parse::<u64>().is_err() {
+        return Err("eip155 chain id reference must be a number");
+    }
+    Ok(())
+}
+
+/// Returns the chain ID of a registered network, see [`NetworkDefinitions::register`].
+pub(super) fn registered_chain_id(name: &str) -> Option<&'static ChainId> {
+    let registry = REGISTRY.read().unwrap_or_else(PoisonError::into_inner);
+    registry.name_to_chain_id.get(name).copied()
+}
+
+/// Returns the name of a registered network, see [`NetworkDefinitions::register`].
+pub(super) fn registered_network_name(chain_id: &ChainId) -> Option<&'static str> {
+    let registry = REGISTRY.read().unwrap_or_else(PoisonError::into_inner);
+    registry.chain_id_to_name.get(chain_id).copied()
+}
+
+/// Returns the token deployments registered so far, see [`NetworkDefinitions::register`].
+pub fn registered_tokens() -> Vec<RegisteredToken> {
+    let registry = REGISTRY.read().unwrap_or_else(PoisonError::into_inner);
+    registry.tokens.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn definitions(value: serde_json::Value) -> NetworkDefinitions {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn registered_networks_resolve_like_built_in_ones() {
+        definitions(json!({
+            "networks": [{ "name": "test-apothem", "chainId": "eip155:900051" }],
+            "tokens": [
+                {
+                    "network": "test-apothem",
+                    "symbol": "USDC",
+                    "address": "0xb5AB69F7bBada22B28e79C8FFAECe55eF1c771D4",
+                    "decimals": 6,
+                    "eip712": { "name": "USDC", "version": "2" }
+                },
+                {
+                    "network": "base",
+                    "symbol": "TEST",
+                    "address": "0x0000000000000000000000000000000000900051",
+                    "decimals": 18,
+                    "eip712": { "name": "Test", "version": "1" },
+                    "transferMethod": "permit2"
+                }
+            ]
+        }))
+        .register()
+        .unwrap();
+
+        let chain_id = ChainId::new("eip155", "900051");
+        assert_eq!(
+            ChainId::from_network_name("test-apothem"),
+            Some(chain_id.clone())
+        );
+        assert_eq!(chain_id.as_network_name(), Some("test-apothem"));
+        assert_eq!(
+            ChainId::from_network_name("base"),
+            Some(ChainId::new("eip155", "8453"))
+        );
+
+        let tokens = registered_tokens();
+        let usdc = tokens
+            .iter()
+            .find(|token| token.chain_id == chain_id)
+            .unwrap();
+        assert_eq!(usdc.decimals, 6);
+        assert_eq!(usdc.transfer_method, TokenTransferMethod::Eip3009);
+        let test = tokens
+            .iter()
+            .find(|token| token.chain_id == ChainId::new("eip155", "8453"))
+            .unwrap();
+        assert_eq!(test.symbol, "TEST");
+        assert_eq!(test.transfer_method, TokenTransferMethod::Permit2);
+    }
+
+    #[test]
+    fn duplicates_are_rejected() {
+        let built_in_name = definitions(json!({
+            "networks": [{ "name": "base", "chainId": "eip155:900052" }]
+        }));
+        assert!(matches!(
+            built_in_name.validate(),
+            Err(NetworkDefinitionsError::DuplicateName(name)) if name == "base"
+        ));
+
+        let built_in_chain_id = definitions(json!({
+            "networks": [{ "name": "test-base", "chainId": "eip155:8453" }]
+        }));
+        assert!(matches!(
+            built_in_chain_id.validate(),
+            Err(NetworkDefinitionsError::DuplicateChainId(_))
+        ));
+
+        let same_file = definitions(json!({
+            "networks": [
+                { "name": "test-duplicate", "chainId": "eip155:900053" },
+                { "name": "test-duplicate", "chainId": "eip155:900054" }
+            ]
+        }));
+        assert!(matches!(
+            same_file.register(),
+            Err(NetworkDefinitionsError::DuplicateName(_))
+        ));
+        assert!(ChainId::from_network_name("test-duplicate").is_none());
+
+        let token = json!({
+            "network": "eip155:900055",
+            "symbol": "USDC",
+            "address": "0xb5AB69F7bBada22B28e79C8FFAECe55eF1c771D4",
+            "decimals": 6,
+            "eip712": { "name": "USDC", "version": "2" }
+        });
+        let mut same_token = token.clone();
+        same_token["address"] = json!("0xb5ab69f7bbada22b28e79c8ffaece55ef1c771d4");
+        assert!(matches!(
+            definitions(json!({ "tokens": [token, same_token] })).validate(),
+            Err(NetworkDefinitionsError::DuplicateToken { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_entries_are_rejected() {
+        let cases = [
+            json!({ "networks": [{ "name": "", "chainId": "eip155:900056" }] }),
+            json!({ "networks": [{ "name": "test net", "chainId": "eip155:900056" }] }),
+            json!({ "networks": [{ "name": "test-net", "chainId": "eip155:" }] }),
+            json!({ "networks": [{ "name": "test-net", "chainId": "eip155:xdc" }] }),
+            json!({ "tokens": [{
+                "network": "unknown-network", "symbol": "USDC", "decimals": 6,
+                "address": "0xb5AB69F7bBada22B28e79C8FFAECe55eF1c771D4",
+                "eip712": { "name": "USDC", "version": "2" }
+            }] }),
+            json!({ "tokens": [{
+                "network": "base", "symbol": "USDC", "decimals": 6, "address": "0x1234",
+                "eip712": { "name": "USDC", "version": "2" }
+            }] }),
+            json!({ "tokens": [{
+                "network": "base", "symbol": "USDC", "decimals": 6,
+                "address": "0xb5AB69F7bBada22B28e79C8FFAECe55eF1c771D4"
+            }] }),
+        ];
+        for case in cases {
+            let error = definitions(case.clone()).validate().unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    NetworkDefinitionsError::InvalidNetwork { .. }
+                        | NetworkDefinitionsError::InvalidToken { .. }
+                ),
+                "{case}: {error}"
+            );
+        }
+
+        let unknown_field = serde_json::from_value::<NetworkDefinitions>(json!({
+            "networks": [{ "name": "test-net", "chain_id": "eip155:900056" }]
+        }));
+        assert!(unknown_field.is_err());
+    }
+}
@@ -55,6 +55,7 @@
 //! - [`chain_id_by_network_name`]: Lookup function to get ChainId by network name
 //! - [`network_name_by_chain_id`]: Reverse lookup function to get network name by ChainId
 //! - [`USDC`] and [`SBC`]: Marker structs used for token deployment implementations
+//! - [`definitions`]: Networks and token deployments loaded from a file at startup
 //!
 //! # Namespace-Specific Traits
 //!
@@ -107,6 +108,9 @@ use std::sync::LazyLock;
 
 use crate::chain::ChainId;
 
+#[cfg(feature = "serde")]
+pub mod definitions;
+
 /// A known network definition with its chain ID and human-readable name.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NetworkInfo {
@@ -323,7 +327,8 @@ pub static CHAIN_ID_TO_NAME: LazyLock<HashMap<ChainId, &'static str>> = LazyLock
 /// Retrieves a ChainId by its network name.
 ///
 /// Performs a lookup in the [`NAME_TO_CHAIN_ID`] hashmap to find the ChainId
-/// corresponding to the given network name. The lookup is case-sensitive.
+/// corresponding to the given network name, then in the networks registered from
+/// [`definitions`]. The lookup is case-sensitive.
 ///
 /// # x402 v1 Protocol Relevance
 ///
@@ -358,13 +363,17 @@ pub static CHAIN_ID_TO_NAME: LazyLock<HashMap<ChainId, &'static str>> = LazyLock
 /// assert!(chain_id_by_network_name("unknown-network").is_none());
 /// ```
 pub fn chain_id_by_network_name(name: &str) -> Option<&ChainId> {
-    NAME_TO_CHAIN_ID.get(name)
+    let chain_id = NAME_TO_CHAIN_ID.get(name);
+    #[cfg(feature = "serde")]
+    let chain_id = chain_id.or_else(|| definitions::registered_chain_id(name));
+    chain_id
 }
 
 /// Retrieves a network name by its ChainId.
 ///
 /// Performs a reverse lookup in the [`CHAIN_ID_TO_NAME`] hashmap to find the
-/// human-readable network name corresponding to the given ChainId.
+/// human-readable network name corresponding to the given ChainId, then in the networks
+/// registered from [`definitions`].
 ///
 /// # x402 v1 Protocol Relevance
 ///
@@ -401,7 +410,10 @@ pub fn chain_id_by_network_name(name: &str) -> Option<&ChainId> {
 /// assert!(network_name_by_chain_id(&unknown).is_none());
 /// ```
 pub fn network_name_by_chain_id(chain_id: &ChainId) -> Option<&'static str> {
-    CHAIN_ID_TO_NAME.get(chain_id).copied()
+    let name = CHAIN_ID_TO_NAME.get(chain_id).copied();
+    #[cfg(feature = "serde")]
+    let name = name.or_else(|| definitions::registered_network_name(chain_id));
+    name
 }
 
 /// Marker struct for USDC token deployment implementations.
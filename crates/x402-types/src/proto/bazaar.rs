@@ -0,0 +1,153 @@
+//! The `bazaar` discovery extension.
+//!
+//! Resource servers describe how to call a paid endpoint (HTTP method, input parameters,
+//! output format) so that facilitators can catalog it. In V2 the description travels in
+//! the `extensions.bazaar` entry of the 402 response; V1 carried the same data in the
+//! `outputSchema` field of the payment requirements.
+//!
+//! See `docs/specs/extensions/bazaar.md`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::scheme::ExtensionKey;
+
+/// The `bazaar` extension declared in a V2 `PaymentRequired` response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bazaar {
+    /// Discovery data: how to call the endpoint and what it returns.
+    pub info: BazaarInfo,
+    /// JSON Schema validating `info`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+}
+
+/// Discovery data of the [`Bazaar`] extension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BazaarInfo {
+    /// Input description, discriminated by its `type` field (`"http"` or `"mcp"`).
+    pub input: Value,
+    /// Output description, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+}
+
+impl ExtensionKey for Bazaar {
+    const EXTENSION_KEY: &'static str = "bazaar";
+}
+
+impl Bazaar {
+    /// Describes an HTTP endpoint.
+    ///
+    /// The fields of `input` (e.g. `queryParams`, `bodyType`, `body`, `headers`) are merged
+    /// into `{ "type": "http", "method": method }`. `output` is used as is.
+    pub fn http(method: &str, input: Option<&Value>, output: Option<&Value>) -> Self {
+        let mut http_input = Map::new();
+        http_input.insert("type".into(), "http".into());
+        http_input.insert("method".into(), method.to_uppercase().into());
+        if let Some(Value::Object(fields)) = input {
+            http_input.extend(fields.clone());
+        }
+        Self {
+            info: BazaarInfo {
+                input: Value::Object(http_input),
+                output: output.cloned(),
+            },
+            schema: Some(json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "input": { "type": "object" },
+                    "output": { "type": "object" },
+                },
+                "required": ["input"],
+            })),
+        }
+    }
+
+    /// The V1 `outputSchema` carrying the same discovery data:
+    /// `{ "input": { …, "discoverable": true }, "output": … }`.
+    pub fn to_v1_output_schema(&self) -> Value {
+        let mut input = self.info.input.clone();
+        if let Value::Object(fields) = &mut input {
+            fields.entry("discoverable").or_insert(Value::Bool(true));
+        }
+        let mut output_schema = Map::new();
+        output_schema.insert("input".into(), input);
+        if let Some(output) = &self.info.output {
+            output_schema.insert("output".into(), output.clone());
+        }
+        Value::Object(output_schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input_schema() -> Value {
+        json!({
+            "queryParams": {
+                "city": { "type": "string", "description": "City name" }
+            }
+        })
+    }
+
+    fn output_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": { "temperature": { "type": "number" } }
+        })
+    }
+
+    #[test]
+    fn http_info_merges_input_fields() {
+        let bazaar = Bazaar::http("get", Some(&input_schema()), Some(&output_schema()));
+        assert_eq!(
+            serde_json::to_value(&bazaar.info).unwrap(),
+            json!({
+                "input": {
+                    "type": "http",
+                    "method": "GET",
+                    "queryParams": {
+                        "city": { "type": "string", "description": "City name" }
+                    }
+                },
+                "output": {
+                    "type": "object",
+                    "properties": { "temperature": { "type": "number" } }
+                }
+            })
+        );
+    }
+
+    /// Matches the `outputSchema` the TypeScript V1 middleware emits for the same
+    /// `inputSchema` and `outputSchema` route config.
+    #[test]
+    fn v1_output_schema_matches_typescript_middleware() {
+        let bazaar = Bazaar::http("GET", Some(&input_schema()), Some(&output_schema()));
+        assert_eq!(
+            bazaar.to_v1_output_schema(),
+            json!({
+                "input": {
+                    "type": "http",
+                    "method": "GET",
+                    "discoverable": true,
+                    "queryParams": {
+                        "city": { "type": "string", "description": "City name" }
+                    }
+                },
+                "output": {
+                    "type": "object",
+                    "properties": { "temperature": { "type": "number" } }
+                }
+            })
+        );
+
+        let bazaar = Bazaar::http("POST", None, None);
+        assert_eq!(
+            bazaar.to_v1_output_schema(),
+            json!({ "input": { "type": "http", "method": "POST", "discoverable": true } })
+        );
+    }
+}
@@ -0,0 +1,711 @@
+//! Protocol types that do not depend on a serialization format.
+//!
+//! These types are available without the `serde` feature, for code that only needs them
+//! for logic. With the feature, they also implement `Serialize` and `Deserialize`; the JSON
+//! messages exchanged with facilitators and clients are in [`super::wire`].
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Trait for types that have both V1 and V2 protocol variants.
+///
+/// This trait enables generic handling of protocol-versioned types through
+/// the [`ProtocolVersioned`] enum.
+pub trait ProtocolV {
+    /// The V1 protocol variant of this type.
+    type V1;
+    /// The V2 protocol variant of this type.
+    type V2;
+}
+
+/// A versioned protocol type that can be either V1 or V2.
+///
+/// This enum wraps protocol-specific types to allow handling both versions
+/// in a unified way.
+pub enum ProtocolVersioned<T>
+where
+    T: ProtocolV,
+{
+    /// Protocol version 1 variant.
+    #[allow(dead_code)]
+    V1(T::V1),
+    /// Protocol version 2 variant.
+    #[allow(dead_code)]
+    V2(T::V2),
+}
+
+impl<T> Clone for ProtocolVersioned<T>
+where
+    T: ProtocolV,
+    T::V1: Clone,
+    T::V2: Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            ProtocolVersioned::V1(v1) => ProtocolVersioned::V1(v1.clone()),
+            ProtocolVersioned::V2(v2) => ProtocolVersioned::V2(v2.clone()),
+        }
+    }
+}
+
+/// Correlation ID of a payment cycle, carried in the `X-Payment-Id` header.
+///
+/// The client generates one per paid request and sends it along with the payment.
+/// The resource server forwards it to the facilitator on `/verify` and `/settle`, so
+/// logs of all three parties can be joined on it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct PaymentId(String);
+
+impl PaymentId {
+    /// HTTP header carrying the payment ID.
+    pub const HEADER_NAME: &'static str = "X-Payment-Id";
+
+    /// Maximum accepted length of a payment ID, in bytes.
+    pub const MAX_LEN: usize = 128;
+
+    /// Reads a payment ID from a header value.
+    ///
+    /// Returns `None` if the value is empty, longer than [`Self::MAX_LEN`],
+    /// or contains anything but visible ASCII characters.
+    pub fn from_header(header: &[u8]) -> Option<Self> {
+        let valid = !header.is_empty()
+            && header.len() <= Self::MAX_LEN
+            && header.iter().all(u8::is_ascii_graphic);
+        if !valid {
+            return None;
+        }
+        std::str::from_utf8(header)
+            .ok()
+            .map(|id| PaymentId(id.to_owned()))
+    }
+
+    /// Returns the payment ID as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for PaymentId {
+    type Err = InvalidPaymentId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_header(s.as_bytes()).ok_or(InvalidPaymentId)
+    }
+}
+
+/// Error returned when parsing an invalid [`PaymentId`].
+#[derive(Debug, thiserror::Error)]
+#[error("Payment ID must be 1 to 128 visible ASCII characters")]
+pub struct InvalidPaymentId;
+
+impl std::fmt::Display for PaymentId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Errors that can occur during payment verification.
+///
+/// These errors are returned when a payment fails validation checks
+/// performed by the facilitator before settlement.
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentVerificationError {
+    /// The payment payload format is invalid or malformed.
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+    /// The payment amount doesn't match the requirements.
+    #[error("Payment amount is invalid with respect to the payment requirements")]
+    InvalidPaymentAmount,
+    /// The payment amount is below the facilitator's minimum for the asset.
+    #[error("Payment amount is below the minimum of {minimum} accepted by this facilitator")]
+    AmountBelowMinimum {
+        /// Smallest amount accepted in the asset, in base units.
+        minimum: alloy_primitives::U256,
+    },
+    /// The payment authorization's `validAfter` timestamp is in the future.
+    #[error("Payment authorization is not yet valid")]
+    Early,
+    /// The payment authorization's `validBefore` timestamp has passed.
+    #[error("Payment authorization is expired")]
+    Expired,
+    /// The payment's chain ID doesn't match the requirements.
+    #[error("Payment chain id is invalid with respect to the payment requirements")]
+    ChainIdMismatch,
+    /// The payment recipient doesn't match the requirements.
+    #[error("Payment recipient is invalid with respect to the payment requirements")]
+    RecipientMismatch,
+    /// The payment asset (token) doesn't match the requirements.
+    #[error("Payment asset is invalid with respect to the payment requirements")]
+    AssetMismatch,
+    /// The payment asset is not on the facilitator's allowlist for the chain.
+    #[error("Payment asset is not allowed by this facilitator")]
+    AssetNotAllowed,
+    /// The token's on-chain decimals differ from the decimals the payment was priced with.
+    #[error("Asset has {actual} decimals, expected {expected}")]
+    DecimalsMismatch {
+        /// Decimals implied by the payment requirements or the asset registry.
+        expected: u8,
+        /// Decimals reported by the token.
+        actual: u8,
+    },
+    /// The payer's on-chain balance is insufficient.
+    #[error("Onchain balance is not enough to cover the payment amount")]
+    InsufficientFunds,
+    #[error("Allowance is not enough to cover the payment amount")]
+    InsufficientAllowance,
+    /// The payment signature is invalid.
+    #[error("{0}")]
+    InvalidSignature(String),
+    /// Transaction simulation failed.
+    #[error("{0}")]
+    TransactionSimulation(String),
+    /// Transaction simulation failed because the recipient rejected the transfer, e.g. a
+    /// `payTo` contract that cannot receive the token.
+    #[error("Payment recipient rejected the transfer: {0}")]
+    RecipientRejected(String),
+    /// The chain is not supported by this facilitator.
+    #[error("Unsupported chain")]
+    UnsupportedChain,
+    /// The payment scheme is not supported by this facilitator.
+    #[error("Unsupported scheme")]
+    UnsupportedScheme,
+    /// The accepted payment details don't match the requirements.
+    #[error("Accepted does not match payment requirements")]
+    AcceptedRequirementsMismatch,
+    /// The payment authorization nonce was already used.
+    #[error("Authorization nonce already used")]
+    NonceUsed,
+    /// The recent blockhash of the payment transaction expired before it landed on chain.
+    #[error("Transaction blockhash expired, sign a new transaction with a recent blockhash")]
+    BlockhashExpired,
+}
+
+impl PaymentVerificationError {
+    pub fn eip2612_gas_sponsoring_not_enabled() -> Self {
+        Self::InvalidSignature(
+            "EIP-2612 gas sponsoring is not enabled by this facilitator".to_string(),
+        )
+    }
+
+    /// Returns the stable code of this error.
+    pub fn code(&self) -> PaymentProblemCode {
+        match self {
+            PaymentVerificationError::InvalidFormat(_) => PaymentProblemCode::InvalidFormat,
+            PaymentVerificationError::InvalidPaymentAmount => PaymentProblemCode::AmountMismatch,
+            PaymentVerificationError::AmountBelowMinimum { .. } => {
+                PaymentProblemCode::AmountBelowMinimum
+            }
+            PaymentVerificationError::Early => PaymentProblemCode::Early,
+            PaymentVerificationError::Expired => PaymentProblemCode::Expired,
+            PaymentVerificationError::ChainIdMismatch => PaymentProblemCode::ChainIdMismatch,
+            PaymentVerificationError::RecipientMismatch => PaymentProblemCode::RecipientMismatch,
+            PaymentVerificationError::AssetMismatch => PaymentProblemCode::AssetMismatch,
+            PaymentVerificationError::AssetNotAllowed => PaymentProblemCode::AssetNotAllowed,
+            PaymentVerificationError::DecimalsMismatch { .. } => {
+                PaymentProblemCode::DecimalsMismatch
+            }
+            PaymentVerificationError::InsufficientFunds => PaymentProblemCode::InsufficientFunds,
+            PaymentVerificationError::InsufficientAllowance => {
+                PaymentProblemCode::InsufficientAllowance
+            }
+            PaymentVerificationError::InvalidSignature(_) => PaymentProblemCode::InvalidSignature,
+            PaymentVerificationError::TransactionSimulation(_) => {
+                PaymentProblemCode::SimulationFailed
+            }
+            PaymentVerificationError::RecipientRejected(_) => PaymentProblemCode::RecipientRejected,
+            PaymentVerificationError::UnsupportedChain => PaymentProblemCode::UnsupportedChain,
+            PaymentVerificationError::UnsupportedScheme => PaymentProblemCode::UnsupportedScheme,
+            PaymentVerificationError::AcceptedRequirementsMismatch => {
+                PaymentProblemCode::AcceptedRequirementsMismatch
+            }
+            PaymentVerificationError::NonceUsed => PaymentProblemCode::NonceUsed,
+            PaymentVerificationError::BlockhashExpired => PaymentProblemCode::BlockhashExpired,
+        }
+    }
+}
+
+/// Verification errors are reported on the wire as their [`ErrorReason`], e.g. in the
+/// `invalidReason` of a verify response.
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for PaymentVerificationError {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "PaymentVerificationError".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        ErrorReason::json_schema(generator)
+    }
+}
+
+impl AsPaymentProblem for PaymentVerificationError {
+    fn as_payment_problem(&self) -> PaymentProblem {
+        let error_reason = match self {
+            PaymentVerificationError::InvalidFormat(_) => ErrorReason::InvalidFormat,
+            PaymentVerificationError::InvalidPaymentAmount => ErrorReason::InvalidPaymentAmount,
+            PaymentVerificationError::AmountBelowMinimum { .. } => {
+                ErrorReason::InvalidPaymentAmount
+            }
+            PaymentVerificationError::InsufficientFunds => ErrorReason::InsufficientFunds,
+            PaymentVerificationError::InsufficientAllowance => {
+                ErrorReason::Permit2AllowanceRequired
+            }
+            PaymentVerificationError::Early => ErrorReason::InvalidPaymentEarly,
+            PaymentVerificationError::Expired => ErrorReason::InvalidPaymentExpired,
+            PaymentVerificationError::ChainIdMismatch => ErrorReason::ChainIdMismatch,
+            PaymentVerificationError::RecipientMismatch => ErrorReason::RecipientMismatch,
+            PaymentVerificationError::AssetMismatch => ErrorReason::AssetMismatch,
+            PaymentVerificationError::AssetNotAllowed => ErrorReason::AssetMismatch,
+            PaymentVerificationError::DecimalsMismatch { .. } => ErrorReason::AssetMismatch,
+            PaymentVerificationError::InvalidSignature(_) => ErrorReason::InvalidSignature,
+            PaymentVerificationError::TransactionSimulation(_) => {
+                ErrorReason::TransactionSimulation
+            }
+            PaymentVerificationError::RecipientRejected(_) => ErrorReason::TransactionSimulation,
+            PaymentVerificationError::UnsupportedChain => ErrorReason::UnsupportedChain,
+            PaymentVerificationError::UnsupportedScheme => ErrorReason::UnsupportedScheme,
+            PaymentVerificationError::AcceptedRequirementsMismatch => {
+                ErrorReason::AcceptedRequirementsMismatch
+            }
+            PaymentVerificationError::NonceUsed => ErrorReason::InvalidSignature,
+            PaymentVerificationError::BlockhashExpired => ErrorReason::InvalidPaymentExpired,
+        };
+        PaymentProblem::new(self.code(), error_reason, self.to_string())
+    }
+}
+
+/// Machine-readable error reason codes for payment failures.
+///
+/// These codes are used in error responses to allow clients to
+/// programmatically handle different failure scenarios.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ErrorReason {
+    /// The payment payload format is invalid.
+    InvalidFormat,
+    /// The payment amount is incorrect.
+    InvalidPaymentAmount,
+    /// The payment authorization is not yet valid.
+    InvalidPaymentEarly,
+    /// The payment authorization has expired.
+    InvalidPaymentExpired,
+    /// The chain ID doesn't match.
+    ChainIdMismatch,
+    /// The recipient address doesn't match.
+    RecipientMismatch,
+    /// The token asset doesn't match.
+    AssetMismatch,
+    /// The accepted details don't match requirements.
+    AcceptedRequirementsMismatch,
+    /// The signature is invalid.
+    InvalidSignature,
+    /// Transaction simulation failed.
+    TransactionSimulation,
+    /// Insufficient on-chain balance.
+    InsufficientFunds,
+    /// Insufficient allowance.
+    Permit2AllowanceRequired,
+    /// The chain is not supported.
+    UnsupportedChain,
+    /// The scheme is not supported.
+    UnsupportedScheme,
+    /// An unexpected error occurred.
+    UnexpectedError,
+}
+
+/// Stable, machine-readable code of a payment failure.
+///
+/// Every [`PaymentVerificationError`] maps to exactly one code, so clients can branch on
+/// the kind of failure instead of matching messages. Codes serialize in `snake_case`
+/// and are the same across x402 SDKs; they are never renamed, but new ones may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PaymentProblemCode {
+    /// The payment payload is malformed.
+    InvalidFormat,
+    /// The payment amount does not match the requirements.
+    AmountMismatch,
+    /// The payment amount is below the facilitator's minimum for the asset.
+    AmountBelowMinimum,
+    /// The payment authorization is not valid yet.
+    Early,
+    /// The payment authorization has expired.
+    Expired,
+    /// The payment targets another chain than required.
+    ChainIdMismatch,
+    /// The payment recipient does not match the requirements.
+    RecipientMismatch,
+    /// The payment asset does not match the requirements.
+    AssetMismatch,
+    /// The payment asset is not allowed by the facilitator.
+    AssetNotAllowed,
+    /// The asset decimals do not match the decimals the payment was priced with.
+    DecimalsMismatch,
+    /// The payer's balance does not cover the payment.
+    InsufficientFunds,
+    /// The payer's token allowance does not cover the payment.
+    InsufficientAllowance,
+    /// The payment signature is invalid.
+    InvalidSignature,
+    /// The payment authorization nonce was already used.
+    NonceUsed,
+    /// The payment transaction's recent blockhash expired; the payer must sign a new
+    /// transaction, resubmitting the same one cannot succeed.
+    BlockhashExpired,
+    /// Simulating the payment transaction failed.
+    SimulationFailed,
+    /// Simulating the payment transaction failed because the recipient rejected the transfer.
+    RecipientRejected,
+    /// The facilitator does not support the chain.
+    UnsupportedChain,
+    /// The facilitator does not support the scheme.
+    UnsupportedScheme,
+    /// The accepted requirements do not match the requirements to verify.
+    AcceptedRequirementsMismatch,
+    /// An on-chain operation, such as settlement, failed.
+    OnchainFailure,
+    /// A code unknown to this version of the library.
+    #[cfg_attr(feature = "serde", serde(other))]
+    Unknown,
+}
+
+impl PaymentProblemCode {
+    /// Returns the wire representation of the code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentProblemCode::InvalidFormat => "invalid_format",
+            PaymentProblemCode::AmountMismatch => "amount_mismatch",
+            PaymentProblemCode::AmountBelowMinimum => "amount_below_minimum",
+            PaymentProblemCode::Early => "early",
+            PaymentProblemCode::Expired => "expired",
+            PaymentProblemCode::ChainIdMismatch => "chain_id_mismatch",
+            PaymentProblemCode::RecipientMismatch => "recipient_mismatch",
+            PaymentProblemCode::AssetMismatch => "asset_mismatch",
+            PaymentProblemCode::AssetNotAllowed => "asset_not_allowed",
+            PaymentProblemCode::DecimalsMismatch => "decimals_mismatch",
+            PaymentProblemCode::InsufficientFunds => "insufficient_funds",
+            PaymentProblemCode::InsufficientAllowance => "insufficient_allowance",
+            PaymentProblemCode::InvalidSignature => "invalid_signature",
+            PaymentProblemCode::NonceUsed => "nonce_used",
+            PaymentProblemCode::BlockhashExpired => "blockhash_expired",
+            PaymentProblemCode::SimulationFailed => "simulation_failed",
+            PaymentProblemCode::RecipientRejected => "recipient_rejected",
+            PaymentProblemCode::UnsupportedChain => "unsupported_chain",
+            PaymentProblemCode::UnsupportedScheme => "unsupported_scheme",
+            PaymentProblemCode::AcceptedRequirementsMismatch => "accepted_requirements_mismatch",
+            PaymentProblemCode::OnchainFailure => "onchain_failure",
+            PaymentProblemCode::Unknown => "unknown",
+        }
+    }
+
+    /// Returns the URI identifying the problem type, the `type` member of
+    /// [`ProblemDetails`]: `https://x402.org/errors/` followed by the wire code.
+    pub fn error_type_uri(&self) -> &'static str {
+        match self {
+            PaymentProblemCode::InvalidFormat => "https://x402.org/errors/invalid_format",
+            PaymentProblemCode::AmountMismatch => "https://x402.org/errors/amount_mismatch",
+            PaymentProblemCode::AmountBelowMinimum => "https://x402.org/errors/amount_below_minimum",
+            PaymentProblemCode::Early => "https://x402.org/errors/early",
+            PaymentProblemCode::Expired => "https://x402.org/errors/expired",
+            PaymentProblemCode::ChainIdMismatch => "https://x402.org/errors/chain_id_mismatch",
+            PaymentProblemCode::RecipientMismatch => "https://x402.org/errors/recipient_mismatch",
+            PaymentProblemCode::AssetMismatch => "https://x402.org/errors/asset_mismatch",
+            PaymentProblemCode::AssetNotAllowed => "https://x402.org/errors/asset_not_allowed",
+            PaymentProblemCode::DecimalsMismatch => "https://x402.org/errors/decimals_mismatch",
+            PaymentProblemCode::InsufficientFunds => "https://x402.org/errors/insufficient_funds",
+            PaymentProblemCode::InsufficientAllowance => "https://x402.org/errors/insufficient_allowance",
+            PaymentProblemCode::InvalidSignature => "https://x402.org/errors/invalid_signature",
+            PaymentProblemCode::NonceUsed => "https://x402.org/errors/nonce_used",
+            PaymentProblemCode::BlockhashExpired => "https://x402.org/errors/blockhash_expired",
+            PaymentProblemCode::SimulationFailed => "https://x402.org/errors/simulation_failed",
+            PaymentProblemCode::RecipientRejected => "https://x402.org/errors/recipient_rejected",
+            PaymentProblemCode::UnsupportedChain => "https://x402.org/errors/unsupported_chain",
+            PaymentProblemCode::UnsupportedScheme => "https://x402.org/errors/unsupported_scheme",
+            PaymentProblemCode::AcceptedRequirementsMismatch => "https://x402.org/errors/accepted_requirements_mismatch",
+            PaymentProblemCode::OnchainFailure => "https://x402.org/errors/onchain_failure",
+            PaymentProblemCode::Unknown => "https://x402.org/errors/unknown",
+        }
+    }
+
+    /// Returns a short summary of the problem type, the `title` member of [`ProblemDetails`].
+    pub fn title(&self) -> &'static str {
+        match self {
+            PaymentProblemCode::InvalidFormat => "Invalid payment format",
+            PaymentProblemCode::AmountMismatch => "Payment amount mismatch",
+            PaymentProblemCode::AmountBelowMinimum => "Payment amount below minimum",
+            PaymentProblemCode::Early => "Payment not yet valid",
+            PaymentProblemCode::Expired => "Payment expired",
+            PaymentProblemCode::ChainIdMismatch => "Chain ID mismatch",
+            PaymentProblemCode::RecipientMismatch => "Recipient mismatch",
+            PaymentProblemCode::AssetMismatch => "Asset mismatch",
+            PaymentProblemCode::AssetNotAllowed => "Asset not allowed",
+            PaymentProblemCode::DecimalsMismatch => "Decimals mismatch",
+            PaymentProblemCode::InsufficientFunds => "Insufficient funds",
+            PaymentProblemCode::InsufficientAllowance => "Insufficient allowance",
+            PaymentProblemCode::InvalidSignature => "Invalid signature",
+            PaymentProblemCode::NonceUsed => "Nonce already used",
+            PaymentProblemCode::BlockhashExpired => "Blockhash expired",
+            PaymentProblemCode::SimulationFailed => "Transaction simulation failed",
+            PaymentProblemCode::RecipientRejected => "Recipient rejected the payment",
+            PaymentProblemCode::UnsupportedChain => "Unsupported chain",
+            PaymentProblemCode::UnsupportedScheme => "Unsupported scheme",
+            PaymentProblemCode::AcceptedRequirementsMismatch => "Accepted requirements mismatch",
+            PaymentProblemCode::OnchainFailure => "On-chain operation failed",
+            PaymentProblemCode::Unknown => "Unknown payment problem",
+        }
+    }
+}
+
+impl std::fmt::Display for PaymentProblemCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Trait for converting errors into structured payment problems.
+pub trait AsPaymentProblem {
+    /// Converts this error into a [`PaymentProblem`].
+    fn as_payment_problem(&self) -> PaymentProblem;
+}
+
+/// A structured payment error with reason code and details.
+///
+/// This type is used to return detailed error information to clients
+/// when a payment fails verification or settlement.
+pub struct PaymentProblem {
+    /// The stable problem code.
+    code: PaymentProblemCode,
+    /// The machine-readable error reason.
+    reason: ErrorReason,
+    /// Human-readable error details.
+    details: String,
+    /// URI of the request the problem occurred on, e.g. `/verify`.
+    instance: Option<String>,
+}
+
+impl PaymentProblem {
+    /// Creates a new payment problem with the given code, reason and details.
+    pub fn new(code: PaymentProblemCode, reason: ErrorReason, details: String) -> Self {
+        Self {
+            code,
+            reason,
+            details,
+            instance: None,
+        }
+    }
+
+    /// Sets the URI of the request the problem occurred on.
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Returns the stable problem code.
+    pub fn code(&self) -> PaymentProblemCode {
+        self.code
+    }
+
+    /// Returns the error reason code.
+    pub fn reason(&self) -> ErrorReason {
+        self.reason
+    }
+
+    /// Returns the human-readable error details.
+    pub fn details(&self) -> &str {
+        &self.details
+    }
+
+    /// Returns the URI of the request the problem occurred on, if set.
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// Returns the problem as [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) problem
+    /// details, answered with the given HTTP status code.
+    pub fn to_problem_details(&self, status: u16) -> ProblemDetails {
+        ProblemDetails {
+            type_uri: self.code.error_type_uri().to_string(),
+            title: self.code.title().to_string(),
+            status,
+            detail: self.details.clone(),
+            instance: self.instance.clone(),
+        }
+    }
+
+    /// Returns the code and message sent to clients.
+    pub fn to_body(&self) -> PaymentProblemBody {
+        PaymentProblemBody {
+            code: self.code,
+            message: self.details.clone(),
+        }
+    }
+}
+
+/// Wire form of a [`PaymentProblem`], carried in the `problem` field of facilitator
+/// error responses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PaymentProblemBody {
+    /// The stable problem code.
+    pub code: PaymentProblemCode,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+/// [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) problem details, the standard members
+/// of facilitator error responses.
+///
+/// Facilitator error responses carry these members alongside the x402 fields clients rely
+/// on, such as `invalidReason` or `errorReason`:
+///
+/// ```json
+/// {
+///   "type": "https://x402.org/errors/invalid_signature",
+///   "title": "Invalid signature",
+///   "status": 400,
+///   "detail": "Invalid signature: signature mismatch",
+///   "instance": "/verify"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProblemDetails {
+    /// URI identifying the problem type, see [`PaymentProblemCode::error_type_uri`].
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_uri: String,
+    /// Short summary of the problem type.
+    pub title: String,
+    /// HTTP status code of the response.
+    pub status: u16,
+    /// Human-readable explanation of this occurrence of the problem.
+    pub detail: String,
+    /// URI of the request the problem occurred on.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub instance: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_id_rejects_invalid_headers() {
+        let id = PaymentId::from_header(b"1b4e28ba-2fa1-41d2-883f-0016d3cca427").unwrap();
+        assert_eq!(id.as_str(), "1b4e28ba-2fa1-41d2-883f-0016d3cca427");
+        assert!(PaymentId::from_header(b"").is_none());
+        assert!(PaymentId::from_header(b"has space").is_none());
+        assert!(PaymentId::from_header(&[b'a'; PaymentId::MAX_LEN + 1]).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn problem_codes_serialize_as_their_wire_names() {
+        let codes = [
+            PaymentProblemCode::InvalidFormat,
+            PaymentProblemCode::AmountMismatch,
+            PaymentProblemCode::AmountBelowMinimum,
+            PaymentProblemCode::Early,
+            PaymentProblemCode::Expired,
+            PaymentProblemCode::ChainIdMismatch,
+            PaymentProblemCode::RecipientMismatch,
+            PaymentProblemCode::AssetMismatch,
+            PaymentProblemCode::AssetNotAllowed,
+            PaymentProblemCode::DecimalsMismatch,
+            PaymentProblemCode::InsufficientFunds,
+            PaymentProblemCode::InsufficientAllowance,
+            PaymentProblemCode::InvalidSignature,
+            PaymentProblemCode::NonceUsed,
+            PaymentProblemCode::BlockhashExpired,
+            PaymentProblemCode::SimulationFailed,
+            PaymentProblemCode::RecipientRejected,
+            PaymentProblemCode::UnsupportedChain,
+            PaymentProblemCode::UnsupportedScheme,
+            PaymentProblemCode::AcceptedRequirementsMismatch,
+            PaymentProblemCode::OnchainFailure,
+            PaymentProblemCode::Unknown,
+        ];
+        for code in codes {
+            let json = serde_json::to_value(code).unwrap();
+            assert_eq!(json, code.as_str());
+            assert_eq!(
+                serde_json::from_value::<PaymentProblemCode>(json).unwrap(),
+                code
+            );
+        }
+        let future: PaymentProblemCode = serde_json::from_str("\"quota_exceeded\"").unwrap();
+        assert_eq!(future, PaymentProblemCode::Unknown);
+        for code in codes {
+            assert_eq!(
+                code.error_type_uri(),
+                format!("https://x402.org/errors/{}", code.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn verification_errors_map_to_codes() {
+        let cases = [
+            (PaymentVerificationError::Expired, "expired"),
+            (PaymentVerificationError::Early, "early"),
+            (
+                PaymentVerificationError::InvalidPaymentAmount,
+                "amount_mismatch",
+            ),
+            (
+                PaymentVerificationError::AmountBelowMinimum {
+                    minimum: alloy_primitives::U256::from(1000),
+                },
+                "amount_below_minimum",
+            ),
+            (PaymentVerificationError::NonceUsed, "nonce_used"),
+            (
+                PaymentVerificationError::BlockhashExpired,
+                "blockhash_expired",
+            ),
+            (
+                PaymentVerificationError::AssetNotAllowed,
+                "asset_not_allowed",
+            ),
+            (
+                PaymentVerificationError::InvalidSignature("bad".into()),
+                "invalid_signature",
+            ),
+            (
+                PaymentVerificationError::TransactionSimulation("reverted".into()),
+                "simulation_failed",
+            ),
+            (
+                PaymentVerificationError::RecipientRejected("not a receiver".into()),
+                "recipient_rejected",
+            ),
+            (
+                PaymentVerificationError::DecimalsMismatch {
+                    expected: 6,
+                    actual: 18,
+                },
+                "decimals_mismatch",
+            ),
+        ];
+        for (error, code) in cases {
+            let problem = error.as_payment_problem();
+            assert_eq!(problem.code().as_str(), code);
+            #[cfg(feature = "serde")]
+            assert_eq!(
+                serde_json::to_value(problem.to_body()).unwrap(),
+                serde_json::json!({ "code": code, "message": error.to_string() })
+            );
+        }
+    }
+}
@@ -61,6 +61,21 @@ impl Serialize for X402Version2 {
     }
 }
 
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for X402Version2 {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "X402Version2".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({ "type": "integer", "const": Self::VALUE })
+    }
+}
+
 impl<'de> Deserialize<'de> for X402Version2 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -99,6 +114,7 @@ pub type SettleResponse = v1::SettleResponse;
 ///
 /// This provides human-readable information about what the buyer is paying for.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceInfo {
     /// URL of the resource.
@@ -115,6 +131,7 @@ pub struct ResourceInfo {
 ///
 /// Contains the payment payload and requirements for verification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct VerifyRequest<TPayload, TRequirements> {
     /// Protocol version (always 2).
@@ -163,6 +180,7 @@ where
 /// - `TAccepted` - The accepted requirements type
 /// - `TPayload` - The scheme-specific payload type
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct PaymentPayload<TPaymentRequirements, TPayload> {
     /// The payment requirements the buyer accepted.
@@ -189,7 +207,8 @@ pub struct PaymentPayload<TPaymentRequirements, TPayload> {
 /// # Serialization
 ///
 /// Serializes to and from a JSON object (e.g. `{ "eip2612GasSponsoring": { … } }`).
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ExtensionsJson(serde_json::value::Map<String, serde_json::Value>);
 
 impl ExtensionsJson {
@@ -289,6 +308,7 @@ impl TryFrom<serde_json::Value> for ExtensionsJson {
 /// - `TAddress` - The address type (default: `String`)
 /// - `TExtra` - Scheme-specific extra data type (default: `Option<serde_json::Value>`)
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct PaymentRequirements<
     TScheme = String,
@@ -312,6 +332,55 @@ pub struct PaymentRequirements<
     pub extra: TExtra,
 }
 
+impl<TScheme, TAmount, TAddress, TExtra> PaymentRequirements<TScheme, TAmount, TAddress, TExtra>
+where
+    TScheme: PartialEq,
+    TAmount: PartialEq,
+    TAddress: PartialEq,
+    TExtra: PartialEq,
+{
+    /// Returns `true` if both requirements ask for the same payment.
+    ///
+    /// Compares `scheme`, `network`, `amount`, `pay_to`, `asset` and `extra`, ignoring
+    /// `max_timeout_seconds`. Clients may echo requirements back with a different timeout
+    /// or with extra fields the scheme does not read; with a typed `TExtra` (such as the
+    /// EIP-712 `name` and `version` of EVM tokens) these fields are dropped on
+    /// deserialization and do not take part in the comparison.
+    pub fn is_functionally_equal(&self, other: &Self) -> bool {
+        self.scheme == other.scheme
+            && self.network == other.network
+            && self.amount == other.amount
+            && self.pay_to == other.pay_to
+            && self.asset == other.asset
+            && self.extra == other.extra
+    }
+
+    /// Checks that `self`, the requirements accepted by the client, are the ones being paid for.
+    ///
+    /// The requirements only need to be [functionally equal](Self::is_functionally_equal),
+    /// unless `strict` is set, in which case every field must match.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PaymentVerificationError::AcceptedRequirementsMismatch` if they differ.
+    pub fn assert_accepted(
+        &self,
+        payment_requirements: &Self,
+        strict: bool,
+    ) -> Result<(), proto::PaymentVerificationError> {
+        let matches = if strict {
+            self == payment_requirements
+        } else {
+            self.is_functionally_equal(payment_requirements)
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(proto::PaymentVerificationError::AcceptedRequirementsMismatch)
+        }
+    }
+}
+
 impl<TScheme, TAmount, TAddress, TExtra> TryFrom<&OriginalJson>
     for PaymentRequirements<TScheme, TAmount, TAddress, TExtra>
 where
@@ -429,3 +498,63 @@ impl PartialEq<PaymentRequirements> for PriceTag {
         a == b
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct TokenExtra {
+        name: String,
+        version: String,
+    }
+
+    fn requirements(
+        extra: serde_json::Value,
+        timeout: u64,
+    ) -> PaymentRequirements<String, String, String, TokenExtra> {
+        serde_json::from_value(json!({
+            "scheme": "exact",
+            "network": "eip155:8453",
+            "amount": "1000",
+            "payTo": "0x1111111111111111111111111111111111111111",
+            "maxTimeoutSeconds": timeout,
+            "asset": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+            "extra": extra,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn functional_equality_ignores_cosmetic_fields() {
+        let required = requirements(json!({"name": "USD Coin", "version": "2"}), 300);
+        let accepted = requirements(
+            json!({"name": "USD Coin", "version": "2", "description": "Premium API"}),
+            60,
+        );
+        assert_ne!(accepted.max_timeout_seconds, required.max_timeout_seconds);
+        assert!(accepted.is_functionally_equal(&required));
+        assert!(accepted.assert_accepted(&required, false).is_ok());
+        assert!(matches!(
+            accepted.assert_accepted(&required, true),
+            Err(proto::PaymentVerificationError::AcceptedRequirementsMismatch)
+        ));
+    }
+
+    #[test]
+    fn functional_equality_compares_payment_terms() {
+        let required = requirements(json!({"name": "USD Coin", "version": "2"}), 300);
+
+        let mut accepted = requirements(json!({"name": "USD Coin", "version": "2"}), 300);
+        accepted.amount = "999".to_string();
+        assert!(!accepted.is_functionally_equal(&required));
+
+        let mut accepted = requirements(json!({"name": "USD Coin", "version": "2"}), 300);
+        accepted.pay_to = "0x2222222222222222222222222222222222222222".to_string();
+        assert!(!accepted.is_functionally_equal(&required));
+
+        let accepted = requirements(json!({"name": "USDC", "version": "2"}), 300);
+        assert!(!accepted.is_functionally_equal(&required));
+    }
+}
@@ -0,0 +1,262 @@
+//! Offline inspection of payment payloads.
+//!
+//! [`validate_payload`] checks the structure of a payment payload, as signed by a client,
+//! and summarizes it, without any network call. It is meant for SDK authors and clients
+//! sanity-checking a payload before sending it: it does not check signatures, balances, or
+//! whether the payload matches the payment requirements, which only a facilitator can do.
+
+use serde_json::Value;
+
+use super::{AsPaymentProblem, PaymentProblem, PaymentVerificationError, VerifyRequest};
+use crate::chain::ChainId;
+
+/// What a payment payload pays, as read by [`validate_payload`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadSummary {
+    /// The x402 protocol version.
+    pub x402_version: u8,
+    /// The payment scheme (e.g., "exact").
+    pub scheme: String,
+    /// The chain the payment is made on.
+    pub chain_id: ChainId,
+    /// The payer address, if the payload names it.
+    ///
+    /// Payloads carrying a signed transaction, as on Solana, do not.
+    pub payer: Option<String>,
+    /// The recipient address, if the payload or the accepted requirements name it.
+    pub recipient: Option<String>,
+    /// The amount in token units, if the payload or the accepted requirements name it.
+    pub amount: Option<String>,
+}
+
+/// Checks the structure of a payment payload and summarizes it.
+///
+/// `json` is either a payment payload, as sent by a client in the `Payment-Signature`
+/// (V2) or `X-Payment` (V1) header once base64-decoded, or a facilitator `/verify` request
+/// wrapping one in `paymentPayload`. The payload is routed as a facilitator would, with
+/// [`VerifyRequest::scheme_handler_slug`].
+///
+/// Payer, recipient and amount are read from an EIP-3009 `authorization` or a
+/// `permit2Authorization`, falling back to the accepted requirements for the recipient
+/// and amount.
+///
+/// # Errors
+///
+/// Returns an `invalid_format` problem if the payload is not JSON, lacks a required
+/// field or is a V2 payload not naming its network with a CAIP-2 chain ID, and an
+/// `unsupported_chain` problem if a V1 payload names an unknown network. V2 payloads naming
+/// a V1 network are rejected even though a facilitator set to `accept_v1_network_names`
+/// accepts them.
+pub fn validate_payload(json: &str) -> Result<PayloadSummary, PaymentProblem> {
+    let value: Value = serde_json::from_str(json).map_err(|e| invalid_format(e.to_string()))?;
+    let payment_payload = value.get("paymentPayload").unwrap_or(&value);
+    let Some(fields) = payment_payload.as_object() else {
+        return Err(invalid_format("payment payload is not a JSON object"));
+    };
+    let x402_version = fields
+        .get("x402Version")
+        .or_else(|| value.get("x402Version"))
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid_format("missing x402Version"))?;
+    let terms = match x402_version {
+        1 => payment_payload,
+        2 => fields
+            .get("accepted")
+            .ok_or_else(|| invalid_format("missing accepted requirements"))?,
+        version => {
+            return Err(invalid_format(format!("unsupported x402Version {version}")));
+        }
+    };
+    for field in ["scheme", "network"] {
+        if terms.get(field).and_then(Value::as_str).is_none() {
+            return Err(invalid_format(format!("missing {field}")));
+        }
+    }
+    if x402_version == 1 {
+        let network = terms["network"].as_str().unwrap_or_default();
+        if ChainId::from_network_name(network).is_none() {
+            return Err(PaymentVerificationError::UnsupportedChain.as_payment_problem());
+        }
+    } else {
+        // Facilitators may route V1 network names, but V2 payloads must use CAIP-2
+        let network = terms["network"].as_str().unwrap_or_default();
+        if network.parse::<ChainId>().is_err() {
+            return Err(invalid_format(match ChainId::from_network_name(network) {
+                Some(chain_id) => {
+                    format!("network '{network}' is a V1 network name, use CAIP-2 '{chain_id}'")
+                }
+                None => format!("invalid network '{network}'"),
+            }));
+        }
+    }
+    let Some(payload) = fields.get("payload").filter(|payload| payload.is_object()) else {
+        return Err(invalid_format("missing payload"));
+    };
+
+    let request = serde_json::json!({
+        "x402Version": x402_version,
+        "paymentPayload": payment_payload,
+    });
+    let request: VerifyRequest =
+        serde_json::from_value(request).map_err(|e| invalid_format(e.to_string()))?;
+    let slug = request
+        .scheme_handler_slug()
+        .ok_or_else(|| invalid_format("invalid network or scheme"))?;
+
+    let authorization = payload.get("authorization");
+    let permit2 = payload.get("permit2Authorization");
+    let payer = authorization
+        .and_then(|authorization| authorization.get("from"))
+        .or_else(|| permit2.and_then(|permit2| permit2.get("from")));
+    let recipient = authorization
+        .and_then(|authorization| authorization.get("to"))
+        .or_else(|| permit2.and_then(|permit2| permit2.get("witness")?.get("to")))
+        .or_else(|| terms.get("payTo"));
+    let amount = authorization
+        .and_then(|authorization| authorization.get("value"))
+        .or_else(|| permit2.and_then(|permit2| permit2.get("permitted")?.get("amount")))
+        .or_else(|| terms.get("amount"))
+        .or_else(|| terms.get("maxAmountRequired"));
+
+    Ok(PayloadSummary {
+        x402_version: slug.x402_version,
+        scheme: slug.name,
+        chain_id: slug.chain_id,
+        payer: payer.and_then(as_string),
+        recipient: recipient.and_then(as_string),
+        amount: amount.and_then(as_string),
+    })
+}
+
+fn invalid_format(details: impl Into<String>) -> PaymentProblem {
+    PaymentVerificationError::InvalidFormat(details.into()).as_payment_problem()
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{ErrorReason, PaymentProblemCode};
+    use serde_json::json;
+
+    const PAYER: &str = "0x857b06519E91e3A54538791bDbb0E22373e36b66";
+    const PAY_TO: &str = "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07";
+
+    fn summary(json: &Value) -> PayloadSummary {
+        validate_payload(&json.to_string())
+            .unwrap_or_else(|problem| panic!("{}", problem.details()))
+    }
+
+    fn eip3009_payload() -> Value {
+        json!({
+            "signature": "0x01",
+            "authorization": {
+                "from": PAYER,
+                "to": PAY_TO,
+                "value": "10000",
+                "validAfter": "0",
+                "validBefore": "1700000300",
+                "nonce": "0x00",
+            },
+        })
+    }
+
+    #[test]
+    fn summarizes_v2_payloads() {
+        let payment_payload = json!({
+            "x402Version": 2,
+            "accepted": {
+                "scheme": "exact",
+                "network": "eip155:8453",
+                "amount": "10000",
+                "payTo": PAY_TO,
+                "asset": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+            },
+            "payload": eip3009_payload(),
+        });
+        let expected = PayloadSummary {
+            x402_version: 2,
+            scheme: "exact".into(),
+            chain_id: "eip155:8453".parse().unwrap(),
+            payer: Some(PAYER.into()),
+            recipient: Some(PAY_TO.into()),
+            amount: Some("10000".into()),
+        };
+        assert_eq!(summary(&payment_payload), expected);
+
+        // Wrapped in a `/verify` request
+        let request = json!({ "x402Version": 2, "paymentPayload": payment_payload });
+        assert_eq!(summary(&request), expected);
+    }
+
+    #[test]
+    fn summarizes_v1_payloads() {
+        let payment_payload = json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "payload": eip3009_payload(),
+        });
+        let summary = summary(&payment_payload);
+        assert_eq!(summary.x402_version, 1);
+        assert_eq!(summary.chain_id, "eip155:84532".parse().unwrap());
+        assert_eq!(summary.payer.as_deref(), Some(PAYER));
+        assert_eq!(summary.amount.as_deref(), Some("10000"));
+    }
+
+    #[test]
+    fn transaction_payloads_fall_back_to_the_accepted_requirements() {
+        let payment_payload = json!({
+            "x402Version": 2,
+            "accepted": {
+                "scheme": "exact",
+                "network": "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp",
+                "amount": "500",
+                "payTo": "EGBQqKn968sVv5cQh5Cr72pSTHfxsuzq7o7asqYB5uEV",
+            },
+            "payload": { "transaction": "AQID" },
+        });
+        let summary = summary(&payment_payload);
+        assert_eq!(summary.payer, None);
+        assert_eq!(
+            summary.recipient.as_deref(),
+            Some("EGBQqKn968sVv5cQh5Cr72pSTHfxsuzq7o7asqYB5uEV")
+        );
+        assert_eq!(summary.amount.as_deref(), Some("500"));
+    }
+
+    #[test]
+    fn malformed_payloads_are_rejected() {
+        let invalid = |json: &str| {
+            let problem = validate_payload(json).unwrap_err();
+            assert_eq!(problem.code(), PaymentProblemCode::InvalidFormat, "{json}");
+            assert_eq!(problem.reason(), ErrorReason::InvalidFormat);
+        };
+        invalid("not json");
+        invalid("[]");
+        invalid(&json!({ "x402Version": 3 }).to_string());
+        invalid(
+            &json!({ "x402Version": 2, "accepted": { "scheme": "exact" }, "payload": {} })
+                .to_string(),
+        );
+        let accepted = json!({ "scheme": "exact", "network": "base" });
+        invalid(&json!({ "x402Version": 2, "accepted": accepted, "payload": {} }).to_string());
+        invalid(&json!({ "x402Version": 1, "scheme": "exact", "network": "base" }).to_string());
+
+        let unknown_network = json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "moon",
+            "payload": {},
+        });
+        let problem = validate_payload(&unknown_network.to_string()).unwrap_err();
+        assert_eq!(problem.code(), PaymentProblemCode::UnsupportedChain);
+    }
+}
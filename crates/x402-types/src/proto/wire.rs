@@ -0,0 +1,718 @@
+//! JSON messages exchanged with facilitators and clients.
+//!
+//! Requires the `serde` feature. Verify and settle messages wrap the raw JSON, whose
+//! structure depends on the protocol version and scheme; see [`super::v1`] and [`super::v2`].
+
+use serde::{Deserialize, Serialize};
+use serde_with::{VecSkipError, serde_as};
+use std::collections::HashMap;
+
+use super::{PaymentVerificationError, ProtocolV, ProtocolVersioned, v1, v2};
+use crate::chain::{ChainId, ChainIdPattern};
+use crate::scheme::SchemeHandlerSlug;
+use crate::timestamp::UnixTimestamp;
+use crate::util::Base64Bytes;
+
+/// Describes a payment method supported by a facilitator.
+///
+/// This type is returned in the [`SupportedResponse`] to indicate what
+/// payment schemes, networks, and protocol versions a facilitator can handle.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "x402Version": 2,
+///   "scheme": "exact",
+///   "network": "eip155:8453"
+/// }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedPaymentKind {
+    /// The x402 protocol version (1 or 2).
+    pub x402_version: u8,
+    /// The payment scheme identifier (e.g., "exact").
+    pub scheme: String,
+    /// The network identifier (CAIP-2 chain ID for V2, network name for V1).
+    pub network: String,
+    /// Optional scheme-specific extra data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+/// Response from a facilitator's `/supported` endpoint.
+///
+/// This response tells clients what payment methods the facilitator supports,
+/// including protocol versions, schemes, networks, and signer addresses.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "kinds": [
+///     { "x402Version": 2, "scheme": "exact", "network": "eip155:8453" }
+///   ],
+///   "extensions": [],
+///   "signers": {
+///     "eip155:8453": ["0x1234..."]
+///   }
+/// }
+/// ```
+#[serde_as]
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)] // Public for consumption by downstream crates.
+pub struct SupportedResponse {
+    /// List of supported payment kinds.
+    #[serde_as(as = "VecSkipError<_>")]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Vec<SupportedPaymentKind>"))]
+    pub kinds: Vec<SupportedPaymentKind>,
+    /// List of supported protocol extensions.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Map of chain IDs to signer addresses for that chain.
+    #[serde(default)]
+    pub signers: HashMap<ChainId, Vec<String>>,
+}
+
+impl SupportedResponse {
+    /// Narrows this response to the payment kinds a client with `capabilities` can pay
+    /// with, as answered by a facilitator's `POST /supported` endpoint.
+    ///
+    /// Signers are kept for the chains of the remaining kinds, and extensions as they are.
+    pub fn for_client(&self, capabilities: &ClientCapabilities) -> SupportedResponse {
+        let kinds: Vec<SupportedPaymentKind> = self
+            .kinds
+            .iter()
+            .filter(|kind| capabilities.accepts(kind))
+            .cloned()
+            .collect();
+        let signers = self
+            .signers
+            .iter()
+            .filter(|(chain_id, _)| {
+                kinds
+                    .iter()
+                    .any(|kind| kind_chain_id(kind).as_ref() == Some(chain_id))
+            })
+            .map(|(chain_id, signers)| (chain_id.clone(), signers.clone()))
+            .collect();
+        SupportedResponse {
+            kinds,
+            extensions: self.extensions.clone(),
+            signers,
+        }
+    }
+}
+
+/// Capabilities of a paying client, sent to a facilitator's `POST /supported` endpoint to
+/// learn which of its payment kinds the client can pay with.
+///
+/// Empty lists do not restrict the payment kinds. See [`SupportedResponse::for_client`].
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "supportedSchemes": ["exact"],
+///   "supportedChains": ["eip155:*", "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp"],
+///   "availableBalances": { "eip155:8453": "2500000", "eip155:137": "0" }
+/// }
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ClientCapabilities {
+    /// Payment schemes the client can sign, e.g. `"exact"`.
+    #[serde(default)]
+    pub supported_schemes: Vec<String>,
+    /// Chains the client can pay on, as CAIP-2 chain IDs or [`ChainIdPattern`]s, e.g.
+    /// `"eip155:*"`.
+    #[serde(default)]
+    pub supported_chains: Vec<String>,
+    /// Balances of the client by CAIP-2 chain ID, in atomic units. Payment kinds on a chain
+    /// with a zero balance are left out; other balances are not compared, as payment kinds
+    /// carry no amount.
+    #[serde(default)]
+    pub available_balances: HashMap<String, String>,
+}
+
+impl ClientCapabilities {
+    /// Returns whether the client can pay with a payment of `kind`.
+    ///
+    /// The network of a kind is a CAIP-2 chain ID in V2, and a network name in V1.
+    pub fn accepts(&self, kind: &SupportedPaymentKind) -> bool {
+        let chain_id = kind_chain_id(kind);
+        let scheme_supported =
+            self.supported_schemes.is_empty() || self.supported_schemes.contains(&kind.scheme);
+        let chain_supported = self.supported_chains.is_empty()
+            || self.supported_chains.iter().any(|chain| {
+                *chain == kind.network
+                    || chain.parse::<ChainIdPattern>().is_ok_and(|pattern| {
+                        chain_id
+                            .as_ref()
+                            .is_some_and(|chain_id| pattern.matches(chain_id))
+                    })
+            });
+        let funded = chain_id
+            .and_then(|chain_id| self.available_balances.get(&chain_id.to_string()))
+            .and_then(|balance| alloy_primitives::U256::from_str_radix(balance, 10).ok())
+            .is_none_or(|balance| !balance.is_zero());
+        scheme_supported && chain_supported && funded
+    }
+}
+
+/// The chain of a payment kind, named by its CAIP-2 chain ID or, in V1, its network name.
+fn kind_chain_id(kind: &SupportedPaymentKind) -> Option<ChainId> {
+    kind.network
+        .parse()
+        .ok()
+        .or_else(|| ChainId::from_network_name(&kind.network))
+}
+
+/// Request to verify a payment before settlement.
+///
+/// This wrapper contains the payment payload and requirements sent by a client
+/// to a facilitator for verification. The facilitator checks that the payment
+/// authorization is valid, properly signed, and matches the requirements.
+///
+/// The inner JSON structure varies by protocol version and scheme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyRequest(pub(super) Box<serde_json::value::RawValue>);
+
+/// Request to settle a verified payment on-chain.
+///
+/// This is the same structure as [`VerifyRequest`], containing the payment
+/// payload that was previously verified.
+pub type SettleRequest = VerifyRequest;
+
+impl From<Box<serde_json::value::RawValue>> for VerifyRequest {
+    fn from(value: Box<serde_json::value::RawValue>) -> Self {
+        Self(value)
+    }
+}
+
+impl VerifyRequest {
+    pub fn as_str(&self) -> &str {
+        self.0.get()
+    }
+
+    /// Extracts the scheme handler slug from the request.
+    ///
+    /// This determines which scheme handler should process this payment
+    /// based on the protocol version, chain ID, and scheme name.
+    ///
+    /// A V2 request naming its network with a V1 network name, e.g. `"base"` instead of
+    /// `"eip155:8453"`, is routed to the handler of that chain, which decides whether to
+    /// accept it.
+    ///
+    /// Returns `None` if the request format is invalid or the scheme is unknown.
+    pub fn scheme_handler_slug(&self) -> Option<SchemeHandlerSlug> {
+        #[derive(Debug, Deserialize, Serialize)]
+        #[serde(untagged)]
+        enum VerifyRequestWire {
+            #[serde(rename_all = "camelCase")]
+            V1 {
+                x402_version: v1::X402Version1,
+                payment_payload: PaymentPayloadV1,
+            },
+            #[serde(rename_all = "camelCase")]
+            V2 {
+                x402_version: v2::X402Version2,
+                payment_payload: PaymentPayloadV2,
+            },
+        }
+
+        #[derive(Debug, Deserialize, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PaymentPayloadV1 {
+            pub network: String,
+            pub scheme: String,
+        }
+
+        #[derive(Debug, Deserialize, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PaymentPayloadV2 {
+            pub accepted: PaymentPayloadV2Accepted,
+        }
+
+        #[derive(Debug, Deserialize, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PaymentPayloadV2Accepted {
+            pub network: String,
+            pub scheme: String,
+        }
+
+        let wire = serde_json::from_str::<VerifyRequestWire>(self.as_str()).ok()?;
+        match wire {
+            VerifyRequestWire::V1 {
+                payment_payload,
+                x402_version,
+            } => {
+                let network_name = payment_payload.network;
+                let chain_id = ChainId::from_network_name(&network_name)?;
+                let scheme = payment_payload.scheme;
+                let slug = SchemeHandlerSlug::new(chain_id, x402_version.into(), scheme);
+                Some(slug)
+            }
+            VerifyRequestWire::V2 {
+                payment_payload,
+                x402_version,
+            } => {
+                let network = payment_payload.accepted.network;
+                let chain_id = network
+                    .parse()
+                    .ok()
+                    .or_else(|| ChainId::from_network_name(&network))?;
+                let scheme = payment_payload.accepted.scheme;
+                let slug = SchemeHandlerSlug::new(chain_id, x402_version.into(), scheme);
+                Some(slug)
+            }
+        }
+    }
+
+    /// Extracts the time after which the payment authorization is no longer valid.
+    ///
+    /// Reads `validBefore` of an EIP-3009 `authorization` or `deadline` of a
+    /// `permit2Authorization` in the payment payload, for both protocol versions.
+    ///
+    /// Returns `None` if the payload carries neither.
+    pub fn valid_before(&self) -> Option<UnixTimestamp> {
+        let request: serde_json::Value = serde_json::from_str(self.as_str()).ok()?;
+        let payload = request.get("paymentPayload")?.get("payload")?;
+        let valid_before = payload
+            .get("authorization")
+            .and_then(|authorization| authorization.get("validBefore"))
+            .or_else(|| {
+                payload
+                    .get("permit2Authorization")
+                    .and_then(|authorization| authorization.get("deadline"))
+            })?;
+        let secs = match valid_before {
+            serde_json::Value::String(secs) => secs.parse().ok()?,
+            serde_json::Value::Number(secs) => secs.as_u64()?,
+            _ => return None,
+        };
+        Some(UnixTimestamp::from_secs(secs))
+    }
+
+    /// Extracts the decimals of the payment asset declared by the resource server.
+    ///
+    /// Reads `decimals` from the `extra` field of the payment requirements, for both
+    /// protocol versions. Facilitators compare it with the token's on-chain decimals.
+    ///
+    /// Returns `None` if the requirements do not declare decimals.
+    pub fn asset_decimals(&self) -> Option<u8> {
+        let request: serde_json::Value = serde_json::from_str(self.as_str()).ok()?;
+        let decimals = request
+            .get("paymentRequirements")?
+            .get("extra")?
+            .get("decimals")?
+            .as_u64()?;
+        u8::try_from(decimals).ok()
+    }
+
+    /// Extracts the recipient, `payTo`, from the payment requirements, for both protocol versions.
+    ///
+    /// Returns `None` if the requirements do not name a recipient.
+    pub fn pay_to(&self) -> Option<String> {
+        let request: serde_json::Value = serde_json::from_str(self.as_str()).ok()?;
+        let pay_to = request.get("paymentRequirements")?.get("payTo")?.as_str()?;
+        Some(pay_to.to_string())
+    }
+
+    /// Extracts the payment asset from the payment requirements, for both protocol versions.
+    ///
+    /// Returns `None` if the requirements do not name an asset.
+    pub fn asset(&self) -> Option<String> {
+        let request: serde_json::Value = serde_json::from_str(self.as_str()).ok()?;
+        let asset = request.get("paymentRequirements")?.get("asset")?.as_str()?;
+        Some(asset.to_string())
+    }
+
+    /// Extracts the required amount from the payment requirements: `amount` in V2,
+    /// `maxAmountRequired` in V1.
+    ///
+    /// Returns `None` if the requirements carry neither.
+    pub fn amount(&self) -> Option<String> {
+        let request: serde_json::Value = serde_json::from_str(self.as_str()).ok()?;
+        let requirements = request.get("paymentRequirements")?;
+        let amount = requirements
+            .get("amount")
+            .or_else(|| requirements.get("maxAmountRequired"))?
+            .as_str()?;
+        Some(amount.to_string())
+    }
+}
+
+/// Response from a payment verification request.
+///
+/// Contains the verification result as JSON. The structure varies by
+/// protocol version and scheme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResponse(pub serde_json::Value);
+
+/// Response from a payment settlement request.
+///
+/// Contains the settlement result as JSON, typically including the
+/// transaction hash if settlement was successful.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettleResponse(pub serde_json::Value);
+
+/// Request to settle several payments at once, sent to a facilitator's `/settle/batch` endpoint.
+///
+/// Each entry has the same structure as a [`SettleRequest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettleBatchRequest {
+    /// The payments to settle.
+    pub payments: Vec<SettleRequest>,
+}
+
+/// Response from a batch settlement request.
+///
+/// Holds one [`SettleResponse`] per payment, in request order. Payments settled in the
+/// same on-chain transaction share its hash; failed payments carry their own error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettleBatchResponse {
+    /// Settlement result of each payment.
+    pub results: Vec<SettleResponse>,
+}
+
+/// Settlement result sent by a resource server back to the client.
+///
+/// Carried as base64-encoded JSON in the [`PaymentResponse::V1_HEADER_NAME`] or
+/// [`PaymentResponse::V2_HEADER_NAME`] header of the paid response. Servers write it
+/// with [`PaymentResponse::to_header`], clients read it with [`PaymentResponse::from_header`].
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "success": true,
+///   "transaction": "0x1234567890abcdef...",
+///   "network": "eip155:84532",
+///   "payer": "0x857b06519E91e3A54538791bDbb0E22373e36b66",
+///   "amount": "10000"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentResponse {
+    /// Whether the payment was settled.
+    pub success: bool,
+    /// Reason for the failure, if settlement failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_reason: Option<String>,
+    /// Address of the payer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+    /// Transaction hash, empty if settlement failed.
+    #[serde(default)]
+    pub transaction: String,
+    /// Network of the settlement: a network name in V1, a CAIP-2 chain ID in V2.
+    #[serde(default)]
+    pub network: String,
+    /// Settled amount in atomic token units.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    /// Protocol extension data.
+    #[serde(default, skip_serializing_if = "v2::ExtensionsJson::is_empty")]
+    pub extensions: v2::ExtensionsJson,
+}
+
+impl PaymentResponse {
+    /// Header carrying the payment response in the V1 protocol.
+    pub const V1_HEADER_NAME: &'static str = "X-Payment-Response";
+    /// Header carrying the payment response in the V2 protocol.
+    pub const V2_HEADER_NAME: &'static str = "Payment-Response";
+
+    /// Encodes the payment response as a base64 header value.
+    pub fn to_header(&self) -> Result<Base64Bytes<'static>, serde_json::Error> {
+        let json = serde_json::to_vec(self)?;
+        Ok(Base64Bytes::encode(json))
+    }
+
+    /// Decodes a payment response from a base64 header value, in the standard or
+    /// the URL-safe alphabet.
+    pub fn from_header(header: &[u8]) -> Result<Self, PaymentResponseDecodeError> {
+        let json = Base64Bytes::from(header).auto_decode()?;
+        let payment_response = serde_json::from_slice(&json)?;
+        Ok(payment_response)
+    }
+}
+
+impl TryFrom<&SettleResponse> for PaymentResponse {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &SettleResponse) -> Result<Self, Self::Error> {
+        Self::deserialize(&value.0)
+    }
+}
+
+/// Errors that can occur while decoding a [`PaymentResponse`] header.
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentResponseDecodeError {
+    #[error("Invalid base64 in payment response header: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Invalid payment response JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<serde_json::Error> for PaymentVerificationError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::InvalidFormat(value.to_string())
+    }
+}
+
+/// Protocol version marker for [`PaymentRequired`] responses.
+pub struct PaymentRequiredV;
+
+impl ProtocolV for PaymentRequiredV {
+    type V1 = v1::PaymentRequired<OriginalJson>;
+    type V2 = v2::PaymentRequired<OriginalJson>;
+}
+
+/// A payment required response that can be either V1 or V2.
+///
+/// This is returned with HTTP 402 status to indicate that payment is required.
+pub type PaymentRequired = ProtocolVersioned<PaymentRequiredV>;
+
+/// Verbatim JSON for PaymentRequirements and other places.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OriginalJson(pub Box<serde_json::value::RawValue>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_request_valid_before() {
+        let request = |payload: serde_json::Value| -> VerifyRequest {
+            let request = serde_json::json!({
+                "x402Version": 2,
+                "paymentPayload": { "payload": payload },
+            });
+            serde_json::from_value(request).unwrap()
+        };
+        let eip3009 =
+            request(serde_json::json!({ "authorization": { "validBefore": "1700000300" } }));
+        assert_eq!(
+            eip3009.valid_before(),
+            Some(UnixTimestamp::from_secs(1700000300))
+        );
+        let permit2 =
+            request(serde_json::json!({ "permit2Authorization": { "deadline": 1700000600 } }));
+        assert_eq!(
+            permit2.valid_before(),
+            Some(UnixTimestamp::from_secs(1700000600))
+        );
+        let transaction = request(serde_json::json!({ "transaction": "AQID" }));
+        assert_eq!(transaction.valid_before(), None);
+    }
+
+    #[test]
+    fn verify_request_slug() {
+        let request = |network: &str| -> VerifyRequest {
+            let request = serde_json::json!({
+                "x402Version": 2,
+                "paymentPayload": {
+                    "x402Version": 2,
+                    "accepted": { "scheme": "exact", "network": network },
+                    "payload": {},
+                },
+            });
+            serde_json::from_value(request).unwrap()
+        };
+        let base = SchemeHandlerSlug::new(ChainId::new("eip155", "8453"), 2, "exact".into());
+        assert_eq!(
+            request("eip155:8453").scheme_handler_slug(),
+            Some(base.clone())
+        );
+        // V1 network names are routed to their chain
+        assert_eq!(request("base").scheme_handler_slug(), Some(base));
+        assert_eq!(request("unknown-network").scheme_handler_slug(), None);
+    }
+
+    #[test]
+    fn verify_request_asset_decimals() {
+        let request = |extra: serde_json::Value| -> VerifyRequest {
+            let request = serde_json::json!({
+                "x402Version": 2,
+                "paymentRequirements": { "extra": extra },
+            });
+            serde_json::from_value(request).unwrap()
+        };
+        let declared = request(serde_json::json!({ "name": "USDC", "decimals": 6 }));
+        assert_eq!(declared.asset_decimals(), Some(6));
+        let undeclared = request(serde_json::json!({ "name": "USDC" }));
+        assert_eq!(undeclared.asset_decimals(), None);
+        let out_of_range = request(serde_json::json!({ "decimals": 300 }));
+        assert_eq!(out_of_range.asset_decimals(), None);
+    }
+
+    #[test]
+    fn verify_request_asset() {
+        let request: VerifyRequest = serde_json::from_value(serde_json::json!({
+            "x402Version": 2,
+            "paymentRequirements": { "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e" },
+        }))
+        .unwrap();
+        assert_eq!(
+            request.asset().as_deref(),
+            Some("0x036CbD53842c5426634e7929541eC2318f3dCF7e")
+        );
+        let request: VerifyRequest =
+            serde_json::from_value(serde_json::json!({ "x402Version": 2 })).unwrap();
+        assert_eq!(request.asset(), None);
+    }
+
+    #[test]
+    fn verify_request_amount() {
+        let request = |requirements: serde_json::Value| -> VerifyRequest {
+            let request = serde_json::json!({
+                "x402Version": 2,
+                "paymentRequirements": requirements,
+            });
+            serde_json::from_value(request).unwrap()
+        };
+        let v2 = request(serde_json::json!({ "amount": "10000" }));
+        assert_eq!(v2.amount().as_deref(), Some("10000"));
+        let v1 = request(serde_json::json!({ "maxAmountRequired": "20000" }));
+        assert_eq!(v1.amount().as_deref(), Some("20000"));
+        assert_eq!(request(serde_json::json!({})).amount(), None);
+    }
+
+    #[test]
+    fn payment_response_decodes_spec_header() {
+        // Example from the V1 HTTP transport spec.
+        let header = b"eyJzdWNjZXNzIjp0cnVlLCJ0cmFuc2FjdGlvbiI6IjB4MTIzNDU2Nzg5MGFiY2RlZjEyMzQ1Njc4OTBhYmNkZWYxMjM0NTY3ODkwYWJjZGVmMTIzNDU2Nzg5MGFiY2RlZiIsIm5ldHdvcmsiOiJiYXNlLXNlcG9saWEiLCJwYXllciI6IjB4ODU3YjA2NTE5RTkxZTNBNTQ1Mzg3OTFiRGJiMEUyMjM3M2UzNmI2NiJ9";
+        let payment_response = PaymentResponse::from_header(header).unwrap();
+        assert!(payment_response.success);
+        assert_eq!(payment_response.network, "base-sepolia");
+        assert_eq!(
+            payment_response.payer.as_deref(),
+            Some("0x857b06519E91e3A54538791bDbb0E22373e36b66")
+        );
+        assert_eq!(payment_response.amount, None);
+    }
+
+    #[test]
+    fn payment_response_header_roundtrip() {
+        let settlement: SettleResponse = v1::SettleResponse::Success {
+            payer: "0x857b06519E91e3A54538791bDbb0E22373e36b66".into(),
+            transaction: "0xabc".into(),
+            network: "eip155:84532".into(),
+        }
+        .into();
+        let mut payment_response = PaymentResponse::try_from(&settlement).unwrap();
+        payment_response.amount = Some("10000".into());
+
+        let header = payment_response.to_header().unwrap();
+        let decoded = PaymentResponse::from_header(header.as_ref()).unwrap();
+        assert_eq!(decoded, payment_response);
+    }
+
+    fn supported() -> SupportedResponse {
+        serde_json::from_value(serde_json::json!({
+            "kinds": [
+                { "x402Version": 2, "scheme": "exact", "network": "eip155:8453" },
+                { "x402Version": 2, "scheme": "upto", "network": "eip155:8453" },
+                { "x402Version": 2, "scheme": "exact", "network": "eip155:137" },
+                { "x402Version": 1, "scheme": "exact", "network": "base" },
+                {
+                    "x402Version": 2,
+                    "scheme": "exact",
+                    "network": "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp",
+                    "extra": { "feePayer": "FeePayer1111111111111111111111111111111111" },
+                },
+            ],
+            "extensions": ["bazaar"],
+            "signers": {
+                "eip155:8453": ["0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"],
+                "eip155:137": ["0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"],
+                "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp": ["FeePayer1111111111111111111111111111111111"],
+            },
+        }))
+        .unwrap()
+    }
+
+    fn kinds(response: &SupportedResponse) -> Vec<(u8, &str, &str)> {
+        response
+            .kinds
+            .iter()
+            .map(|kind| {
+                (
+                    kind.x402_version,
+                    kind.scheme.as_str(),
+                    kind.network.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn client_capabilities_filter_schemes() {
+        let capabilities = ClientCapabilities {
+            supported_schemes: vec!["upto".into()],
+            ..Default::default()
+        };
+        let response = supported().for_client(&capabilities);
+        assert_eq!(kinds(&response), vec![(2, "upto", "eip155:8453")]);
+        assert_eq!(
+            response.signers.keys().collect::<Vec<_>>(),
+            vec![&ChainId::new("eip155", "8453")]
+        );
+        assert_eq!(response.extensions, vec!["bazaar".to_string()]);
+    }
+
+    #[test]
+    fn client_capabilities_filter_chains() {
+        let capabilities = ClientCapabilities {
+            supported_schemes: vec!["exact".into()],
+            supported_chains: vec!["eip155:{8453,10}".into()],
+            ..Default::default()
+        };
+        let response = supported().for_client(&capabilities);
+        // V1 network names are matched by their chain ID
+        assert_eq!(
+            kinds(&response),
+            vec![(2, "exact", "eip155:8453"), (1, "exact", "base")]
+        );
+
+        let capabilities = ClientCapabilities {
+            supported_chains: vec!["solana:*".into(), "base".into()],
+            ..Default::default()
+        };
+        let response = supported().for_client(&capabilities);
+        assert_eq!(
+            kinds(&response),
+            vec![
+                (1, "exact", "base"),
+                (2, "exact", "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp")
+            ]
+        );
+        assert!(response.kinds[1].extra.is_some());
+        assert_eq!(response.signers.len(), 2);
+    }
+
+    #[test]
+    fn client_capabilities_leave_out_unfunded_chains() {
+        let capabilities: ClientCapabilities = serde_json::from_value(serde_json::json!({
+            "supportedChains": ["eip155:*"],
+            "availableBalances": { "eip155:8453": "0", "eip155:137": "2500000" },
+        }))
+        .unwrap();
+        let response = supported().for_client(&capabilities);
+        assert_eq!(kinds(&response), vec![(2, "exact", "eip155:137")]);
+    }
+
+    #[test]
+    fn empty_client_capabilities_keep_everything() {
+        let response = supported().for_client(&ClientCapabilities::default());
+        assert_eq!(kinds(&response), kinds(&supported()));
+        assert_eq!(response.signers, supported().signers);
+    }
+}
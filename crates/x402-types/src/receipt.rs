@@ -0,0 +1,366 @@
+//! Signed settlement receipts.
+//!
+//! A facilitator configured with a receipt signing key attaches a [`SettlementReceipt`]
+//! to every successful settle response, under the `receipt` field. The receipt carries
+//! the settled payment's claims (payer, nonce, amount, transaction hash, network and
+//! timestamp) and an ed25519 signature over them by the facilitator's key.
+//!
+//! Receipts give non-repudiable proof that a facilitator settled a payment, without
+//! looking the transaction up on-chain. The nonce allows downstream services to detect
+//! a receipt being presented twice.
+//!
+//! # Signing and Verification
+//!
+//! Signing and verification require the `receipt` feature:
+//!
+//! ```ignore
+//! use x402_types::receipt::{ReceiptSigner, parse_public_key};
+//!
+//! let signer: ReceiptSigner = "0x…32-byte hex seed…".parse()?;
+//! let receipt = signer.sign(claims);
+//!
+//! // Downstream: validate against the facilitator's published public key
+//! let public_key = parse_public_key("0x…32-byte hex public key…")?;
+//! receipt.verify(&public_key)?;
+//! ```
+//!
+//! # Signed Message
+//!
+//! The signature covers [`RECEIPT_DOMAIN`] followed by the JSON serialization of
+//! [`ReceiptClaims`], with fields in declaration order.
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto;
+use crate::timestamp::UnixTimestamp;
+
+/// Domain separator prepended to the signed receipt claims.
+pub const RECEIPT_DOMAIN: &[u8] = b"x402-settlement-receipt-v1:";
+
+/// The settlement facts attested by a [`SettlementReceipt`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptClaims {
+    /// Address of the payer.
+    pub payer: String,
+    /// Replay-protection nonce of the payment authorization, if the scheme has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Settled amount in atomic token units.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    /// Settlement transaction hash.
+    pub transaction: String,
+    /// Network of the settlement, as reported by the settle response.
+    pub network: String,
+    /// When the receipt was issued.
+    pub timestamp: UnixTimestamp,
+}
+
+impl ReceiptClaims {
+    /// Extracts receipt claims from a settle request and its successful response.
+    ///
+    /// Returns `None` if the settlement was not successful or lacks a payer, transaction
+    /// or network. The amount is taken from the response if present, otherwise from the
+    /// payment requirements. The nonce is read from EIP-3009 (`authorization.nonce`) or
+    /// Permit2 (`permit2Authorization.nonce`) payloads.
+    pub fn from_settlement(
+        request: &proto::SettleRequest,
+        response: &proto::SettleResponse,
+    ) -> Option<Self> {
+        let response = &response.0;
+        if response.get("success")?.as_bool() != Some(true) {
+            return None;
+        }
+        let field = |value: &serde_json::Value, name: &str| value.get(name).and_then(as_string);
+        let request: serde_json::Value = serde_json::from_str(request.as_str()).ok()?;
+        let requirements = request.get("paymentRequirements");
+        let payload = request
+            .get("paymentPayload")
+            .and_then(|payment_payload| payment_payload.get("payload"));
+        let amount = field(response, "amount").or_else(|| {
+            requirements.and_then(|requirements| {
+                field(requirements, "amount").or_else(|| field(requirements, "maxAmountRequired"))
+            })
+        });
+        let nonce = payload.and_then(|payload| {
+            ["authorization", "permit2Authorization"]
+                .into_iter()
+                .filter_map(|name| payload.get(name))
+                .find_map(|authorization| field(authorization, "nonce"))
+        });
+        Some(ReceiptClaims {
+            payer: field(response, "payer")?,
+            nonce,
+            amount,
+            transaction: field(response, "transaction")?,
+            network: field(response, "network")?,
+            timestamp: UnixTimestamp::now(),
+        })
+    }
+
+    /// Returns the bytes covered by the receipt signature.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut message = RECEIPT_DOMAIN.to_vec();
+        let claims = serde_json::to_vec(self).expect("ReceiptClaims serialization failed");
+        message.extend_from_slice(&claims);
+        message
+    }
+}
+
+/// Reads a JSON string or number as a string.
+fn as_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Settlement claims signed by a facilitator.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "payer": "0x857b06519E91e3A54538791bDbb0E22373e36b66",
+///   "nonce": "0xf3746613c2d920b5fdabc0856f2aeb2d4f88ee6037b8cc5d04a71a4462f13480",
+///   "amount": "10000",
+///   "transaction": "0x1234567890abcdef…",
+///   "network": "eip155:84532",
+///   "timestamp": "1699999999",
+///   "signer": "0x…",
+///   "signature": "0x…"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettlementReceipt {
+    /// The attested settlement facts.
+    #[serde(flatten)]
+    pub claims: ReceiptClaims,
+    /// Hex-encoded ed25519 public key of the signing facilitator.
+    pub signer: String,
+    /// Hex-encoded ed25519 signature over [`ReceiptClaims::signing_message`].
+    pub signature: String,
+}
+
+impl SettlementReceipt {
+    /// Field of the settle response carrying the receipt.
+    pub const SETTLE_RESPONSE_FIELD: &'static str = "receipt";
+
+    /// Reads the receipt from a settle response, if present.
+    pub fn from_settle_response(response: &proto::SettleResponse) -> Option<Self> {
+        let receipt = response.0.get(Self::SETTLE_RESPONSE_FIELD)?;
+        serde_json::from_value(receipt.clone()).ok()
+    }
+}
+
+#[cfg(feature = "receipt")]
+pub use signing::*;
+
+#[cfg(feature = "receipt")]
+mod signing {
+    use alloy_primitives::hex;
+    use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+    use std::fmt::{Debug, Formatter};
+    use std::str::FromStr;
+
+    use super::{ReceiptClaims, SettlementReceipt};
+    use crate::proto;
+
+    /// Errors that can occur while handling receipt keys and signatures.
+    #[derive(Debug, thiserror::Error)]
+    pub enum ReceiptError {
+        #[error("Invalid hex: {0}")]
+        Hex(#[from] hex::FromHexError),
+        #[error("Invalid key length: expected 32 bytes, got {0}")]
+        KeyLength(usize),
+        #[error("Invalid key or signature: {0}")]
+        Signature(#[from] ed25519_dalek::SignatureError),
+        #[error("Receipt signed by {actual}, expected {expected}")]
+        SignerMismatch { expected: String, actual: String },
+    }
+
+    /// Parses a hex-encoded 32-byte ed25519 public key.
+    pub fn parse_public_key(public_key: &str) -> Result<VerifyingKey, ReceiptError> {
+        let bytes = decode_32_bytes(public_key)?;
+        Ok(VerifyingKey::from_bytes(&bytes)?)
+    }
+
+    fn decode_32_bytes(s: &str) -> Result<[u8; 32], ReceiptError> {
+        let bytes = hex::decode(s.trim())?;
+        let len = bytes.len();
+        bytes.try_into().map_err(|_| ReceiptError::KeyLength(len))
+    }
+
+    /// Facilitator key that signs settlement receipts.
+    ///
+    /// Parsed from a hex-encoded 32-byte ed25519 seed, with or without `0x` prefix.
+    #[derive(Clone)]
+    pub struct ReceiptSigner {
+        key: SigningKey,
+    }
+
+    impl ReceiptSigner {
+        /// Creates a signer from an ed25519 signing key.
+        pub fn new(key: SigningKey) -> Self {
+            Self { key }
+        }
+
+        /// Returns the public key downstream services verify receipts against.
+        pub fn public_key(&self) -> VerifyingKey {
+            self.key.verifying_key()
+        }
+
+        /// Returns the hex-encoded public key.
+        pub fn public_key_hex(&self) -> String {
+            hex::encode_prefixed(self.public_key().as_bytes())
+        }
+
+        /// Signs the given claims.
+        pub fn sign(&self, claims: ReceiptClaims) -> SettlementReceipt {
+            let signature = self.key.sign(&claims.signing_message());
+            SettlementReceipt {
+                claims,
+                signer: self.public_key_hex(),
+                signature: hex::encode_prefixed(signature.to_bytes()),
+            }
+        }
+
+        /// Attaches a signed receipt to a successful settle response.
+        ///
+        /// Leaves the response untouched if it does not describe a successful settlement.
+        pub fn attach(&self, request: &proto::SettleRequest, response: &mut proto::SettleResponse) {
+            let Some(claims) = ReceiptClaims::from_settlement(request, response) else {
+                return;
+            };
+            let receipt = self.sign(claims);
+            if let Some(object) = response.0.as_object_mut() {
+                let receipt = serde_json::to_value(receipt).expect("receipt serialization failed");
+                object.insert(
+                    SettlementReceipt::SETTLE_RESPONSE_FIELD.to_string(),
+                    receipt,
+                );
+            }
+        }
+    }
+
+    impl Debug for ReceiptSigner {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ReceiptSigner")
+                .field("public_key", &self.public_key_hex())
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl FromStr for ReceiptSigner {
+        type Err = ReceiptError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let seed = decode_32_bytes(s)?;
+            Ok(Self::new(SigningKey::from_bytes(&seed)))
+        }
+    }
+
+    impl SettlementReceipt {
+        /// Verifies the receipt was signed by `public_key` over its claims.
+        pub fn verify(&self, public_key: &VerifyingKey) -> Result<(), ReceiptError> {
+            let expected = hex::encode_prefixed(public_key.as_bytes());
+            if parse_public_key(&self.signer)? != *public_key {
+                return Err(ReceiptError::SignerMismatch {
+                    expected,
+                    actual: self.signer.clone(),
+                });
+            }
+            let signature = hex::decode(&self.signature)?;
+            let signature = Signature::from_slice(&signature)?;
+            public_key.verify_strict(&self.claims.signing_message(), &signature)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "receipt"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SEED: &str = "0x9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+    fn settle_request() -> proto::SettleRequest {
+        let request = json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {
+                    "signature": "0x",
+                    "authorization": { "nonce": "0xabcd" }
+                }
+            },
+            "paymentRequirements": { "maxAmountRequired": "10000" }
+        });
+        serde_json::value::to_raw_value(&request).unwrap().into()
+    }
+
+    fn settle_response() -> proto::SettleResponse {
+        proto::SettleResponse(json!({
+            "success": true,
+            "payer": "0xpayer",
+            "transaction": "0xtx",
+            "network": "base-sepolia"
+        }))
+    }
+
+    #[test]
+    fn attached_receipt_verifies() {
+        let signer: ReceiptSigner = SEED.parse().unwrap();
+        let mut response = settle_response();
+        signer.attach(&settle_request(), &mut response);
+
+        let receipt = SettlementReceipt::from_settle_response(&response).unwrap();
+        assert_eq!(receipt.claims.payer, "0xpayer");
+        assert_eq!(receipt.claims.nonce.as_deref(), Some("0xabcd"));
+        assert_eq!(receipt.claims.amount.as_deref(), Some("10000"));
+        assert_eq!(receipt.claims.transaction, "0xtx");
+
+        let public_key = parse_public_key(&signer.public_key_hex()).unwrap();
+        receipt.verify(&public_key).unwrap();
+    }
+
+    #[test]
+    fn tampered_receipt_fails() {
+        let signer: ReceiptSigner = SEED.parse().unwrap();
+        let mut response = settle_response();
+        signer.attach(&settle_request(), &mut response);
+        let mut receipt = SettlementReceipt::from_settle_response(&response).unwrap();
+        receipt.claims.amount = Some("1".into());
+        assert!(receipt.verify(&signer.public_key()).is_err());
+    }
+
+    #[test]
+    fn receipt_from_other_key_fails() {
+        let signer: ReceiptSigner = SEED.parse().unwrap();
+        let other: ReceiptSigner = format!("0x{}", "11".repeat(32)).parse().unwrap();
+        let receipt = signer
+            .sign(ReceiptClaims::from_settlement(&settle_request(), &settle_response()).unwrap());
+        assert!(matches!(
+            receipt.verify(&other.public_key()),
+            Err(ReceiptError::SignerMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn failed_settlement_gets_no_receipt() {
+        let signer: ReceiptSigner = SEED.parse().unwrap();
+        let mut response = proto::SettleResponse(json!({
+            "success": false,
+            "errorReason": "insufficient_funds",
+            "network": "base-sepolia"
+        }));
+        signer.attach(&settle_request(), &mut response);
+        assert!(response.0.get("receipt").is_none());
+    }
+}
@@ -0,0 +1,185 @@
+//! JSON Schemas of the x402 V2 protocol messages.
+//!
+//! The schemas are generated from the protocol types with [`schemars`], and describe their
+//! wire format, e.g. to validate messages in other languages or to document an API. They
+//! follow JSON Schema draft 2020-12.
+//!
+//! Scheme-specific parts, such as the `payload` of a payment payload or the `extra` of
+//! payment requirements, are left open: their structure depends on the scheme.
+//!
+//! The schemas of this version are committed in the `schemas` directory of this crate;
+//! a test fails when the generated schemas drift from them. Run the tests with
+//! `X402_UPDATE_SCHEMAS=1` to update them.
+
+use schemars::{JsonSchema, schema_for};
+use serde_json::Value;
+
+use crate::proto::{PaymentVerificationError, SupportedResponse, v2};
+
+/// The V2 payment payload, with open scheme-specific payload.
+pub type PaymentPayload = v2::PaymentPayload<v2::PaymentRequirements, Value>;
+
+/// The body of a V2 `/verify` request.
+pub type VerifyRequest = v2::VerifyRequest<PaymentPayload, v2::PaymentRequirements>;
+
+/// The body of a V2 `/settle` request, the same as [`VerifyRequest`].
+pub type SettleRequest = VerifyRequest;
+
+/// Generates the JSON Schema of V2 [`PaymentRequirements`](v2::PaymentRequirements).
+pub fn generate_payment_requirements_schema() -> Value {
+    generate::<v2::PaymentRequirements>()
+}
+
+/// Generates the JSON Schema of the body of a V2 `/verify` request.
+pub fn generate_verify_request_schema() -> Value {
+    generate::<VerifyRequest>()
+}
+
+/// Generates the JSON Schema of the body of a V2 `/settle` request.
+pub fn generate_settle_request_schema() -> Value {
+    let mut schema = generate::<SettleRequest>();
+    schema["title"] = "SettleRequest".into();
+    schema
+}
+
+/// Generates the JSON Schema of a `/supported` response.
+pub fn generate_supported_response_schema() -> Value {
+    generate::<SupportedResponse>()
+}
+
+/// Generates the JSON Schema of the reasons of failed verifications, see
+/// [`PaymentVerificationError`].
+pub fn generate_verification_error_schema() -> Value {
+    generate::<PaymentVerificationError>()
+}
+
+fn generate<T: JsonSchema>() -> Value {
+    schema_for!(T).to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    /// Compares `schema` to the committed `schemas/<name>.json`, updating it instead
+    /// with `X402_UPDATE_SCHEMAS=1`.
+    fn assert_committed(name: &str, schema: Value) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("schemas")
+            .join(format!("{name}.json"));
+        if std::env::var_os("X402_UPDATE_SCHEMAS").is_some() {
+            let json = serde_json::to_string_pretty(&schema).unwrap();
+            std::fs::write(&path, json + "\n").unwrap();
+            return;
+        }
+        let committed = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {e}", path.display()));
+        let committed: Value = serde_json::from_str(&committed).unwrap();
+        assert!(
+            committed == schema,
+            "the schema of {name} drifted from {}, run the tests with X402_UPDATE_SCHEMAS=1 to update it",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn schemas_match_the_committed_ones() {
+        assert_committed(
+            "payment-requirements",
+            generate_payment_requirements_schema(),
+        );
+        assert_committed("verify-request", generate_verify_request_schema());
+        assert_committed("settle-request", generate_settle_request_schema());
+        assert_committed("supported-response", generate_supported_response_schema());
+        assert_committed("verification-error", generate_verification_error_schema());
+    }
+
+    /// Returns the fields of `payload` the object `schema` requires but `payload` lacks,
+    /// and the fields of `payload` it does not define.
+    fn field_errors(schema: &Value, payload: &Value) -> (Vec<String>, Vec<String>) {
+        let required = schema["required"].as_array().unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        let payload = payload.as_object().unwrap();
+        let missing = required
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|field| !payload.contains_key(*field))
+            .map(str::to_string)
+            .collect();
+        let unknown = payload
+            .keys()
+            .filter(|field| !properties.contains_key(*field))
+            .cloned()
+            .collect();
+        (missing, unknown)
+    }
+
+    fn requirements() -> Value {
+        json!({
+            "scheme": "exact",
+            "network": "eip155:8453",
+            "amount": "10000",
+            "payTo": "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+            "maxTimeoutSeconds": 300,
+            "asset": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+            "extra": { "name": "USD Coin", "version": "2" },
+        })
+    }
+
+    #[test]
+    fn payment_requirements_schema_describes_the_wire_format() {
+        let schema = generate_payment_requirements_schema();
+        let valid = requirements();
+        serde_json::from_value::<v2::PaymentRequirements>(valid.clone()).unwrap();
+        assert_eq!(field_errors(&schema, &valid), (vec![], vec![]));
+
+        let mut invalid = valid.as_object().unwrap().clone();
+        invalid.remove("payTo");
+        let invalid = Value::Object(invalid);
+        assert!(serde_json::from_value::<v2::PaymentRequirements>(invalid.clone()).is_err());
+        assert_eq!(field_errors(&schema, &invalid).0, vec!["payTo"]);
+
+        assert_eq!(schema["properties"]["maxTimeoutSeconds"]["type"], "integer");
+        assert_eq!(schema["properties"]["network"]["$ref"], "#/$defs/ChainId");
+        assert_eq!(schema["$defs"]["ChainId"]["type"], "string");
+    }
+
+    #[test]
+    fn verify_request_schema_describes_the_wire_format() {
+        let schema = generate_verify_request_schema();
+        let valid = json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": requirements(),
+                "payload": { "signature": "0x01" },
+            },
+            "paymentRequirements": requirements(),
+        });
+        serde_json::from_value::<VerifyRequest>(valid.clone()).unwrap();
+        assert_eq!(field_errors(&schema, &valid), (vec![], vec![]));
+        assert_eq!(schema["properties"]["x402Version"]["const"], 2);
+
+        let invalid = json!({ "x402Version": 2, "paymentPayload": valid["paymentPayload"] });
+        assert!(serde_json::from_value::<VerifyRequest>(invalid.clone()).is_err());
+        assert_eq!(
+            field_errors(&schema, &invalid).0,
+            vec!["paymentRequirements"]
+        );
+    }
+
+    #[test]
+    fn verification_errors_are_described_by_their_reason() {
+        let schema = generate_verification_error_schema();
+        let reasons = schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| variant["const"].clone())
+            .collect::<Vec<_>>();
+        assert!(reasons.contains(&json!("invalid_format")), "{schema}");
+        assert!(reasons.contains(&json!("invalid_signature")), "{schema}");
+    }
+}
@@ -10,6 +10,12 @@
 //! 3. A [`PaymentSelector`] chooses the best candidate
 //! 4. The candidate is signed and sent back to the server
 //!
+//! # Signing
+//!
+//! A [`PaymentCandidateSigner`] reports its [`SignerKind`]: local keys sign with CPU-bound
+//! work only, which HTTP clients may move off the async executor, while remote signers
+//! and signers querying the chain await I/O.
+//!
 //! # Payment Selection
 //!
 //! Multiple selectors are available:
@@ -19,10 +25,12 @@
 
 use alloy_primitives::U256;
 use async_trait::async_trait;
+use std::time::Duration;
 
 use crate::chain::{ChainId, ChainIdPattern};
 use crate::proto;
 use crate::scheme::X402SchemeId;
+use crate::timestamp::UnixTimestamp;
 
 /// A payment option that can be signed and submitted.
 ///
@@ -66,6 +74,11 @@ impl PaymentCandidate {
     pub async fn sign(&self) -> Result<String, X402Error> {
         self.signer.sign_payment().await
     }
+
+    /// Returns how the signer of this candidate signs.
+    pub fn signer_kind(&self) -> SignerKind {
+        self.signer.kind()
+    }
 }
 
 /// Trait for scheme clients that can process payment requirements.
@@ -77,6 +90,69 @@ impl PaymentCandidate {
 pub trait X402SchemeClient: X402SchemeId + Send + Sync {
     /// Generates payment candidates for the given payment requirements.
     fn accept(&self, payment_required: &proto::PaymentRequired) -> Vec<PaymentCandidate>;
+
+    /// Generates payment candidates signed with the given options.
+    ///
+    /// Defaults to [`Self::accept`], ignoring the options, for schemes whose payments
+    /// have no validity window of their own.
+    fn accept_with_options(
+        &self,
+        payment_required: &proto::PaymentRequired,
+        options: &SigningOptions,
+    ) -> Vec<PaymentCandidate> {
+        let _ = options;
+        self.accept(payment_required)
+    }
+}
+
+/// Client-side options applied when signing payments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SigningOptions {
+    /// How long a signed authorization stays valid, e.g. the span between `validAfter`
+    /// (now) and `validBefore` of an ERC-3009 authorization.
+    ///
+    /// `None` uses the `maxTimeoutSeconds` of the payment requirements.
+    pub validity_window: Option<Duration>,
+    /// How many seconds the server's clock is ahead of the local one, negative if behind.
+    ///
+    /// Added to the local time when signing, so that the validity window is computed on the
+    /// server's clock. `0`, the default, trusts the local clock. See [`Self::calibrated`].
+    pub clock_offset_secs: i64,
+}
+
+impl SigningOptions {
+    /// Returns the validity window in seconds for requirements with the given
+    /// `maxTimeoutSeconds`.
+    pub fn validity_seconds(&self, max_timeout_seconds: u64) -> u64 {
+        self.validity_window
+            .map(|window| window.as_secs())
+            .unwrap_or(max_timeout_seconds)
+    }
+
+    /// Returns the current time on the server's clock, see [`Self::clock_offset_secs`].
+    pub fn now(&self) -> UnixTimestamp {
+        self.server_time(UnixTimestamp::now())
+    }
+
+    /// Returns the server's time when the local clock reads `local_time`.
+    pub fn server_time(&self, local_time: UnixTimestamp) -> UnixTimestamp {
+        UnixTimestamp::from_secs(
+            local_time
+                .as_secs()
+                .saturating_add_signed(self.clock_offset_secs),
+        )
+    }
+
+    /// Returns these options with the clock offset measured from a server reporting
+    /// `server_time` when the local clock read `local_time`, e.g. from the HTTP `Date`
+    /// header of its response.
+    pub fn calibrated(self, server_time: UnixTimestamp, local_time: UnixTimestamp) -> Self {
+        let offset = server_time.as_secs() as i128 - local_time.as_secs() as i128;
+        Self {
+            clock_offset_secs: offset.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            ..self
+        }
+    }
 }
 
 /// Trait for signing payment authorizations.
@@ -85,6 +161,25 @@ pub trait X402SchemeClient: X402SchemeId + Send + Sync {
 pub trait PaymentCandidateSigner {
     /// Signs a payment authorization.
     async fn sign_payment(&self) -> Result<String, X402Error>;
+
+    /// Returns how this signer signs. Defaults to [`SignerKind::Async`].
+    fn kind(&self) -> SignerKind {
+        SignerKind::Async
+    }
+}
+
+/// How a [`PaymentCandidateSigner`] produces its signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignerKind {
+    /// Signs with CPU-bound work only, e.g. EIP-712 signing with a local private key.
+    ///
+    /// The signing future completes without waiting on I/O, so it may be driven on a
+    /// blocking thread rather than on the async executor.
+    Sync,
+    /// Awaits I/O while signing, e.g. a remote signer or an RPC call, and is driven on the
+    /// async executor.
+    #[default]
+    Async,
 }
 
 /// Errors that can occur during client-side payment processing.
@@ -99,6 +194,13 @@ pub enum X402Error {
     #[error("Request is not cloneable (streaming body?)")]
     RequestNotCloneable,
 
+    /// The streaming HTTP request body is larger than the bytes buffered to replay it.
+    #[error("Request body is larger than the {limit} bytes buffered to replay it")]
+    BodyTooLarge {
+        /// The largest body buffered, in bytes.
+        limit: usize,
+    },
+
     /// Failed to parse the 402 response body.
     #[error("Failed to parse 402 response: {0}")]
     ParseError(String),
@@ -110,6 +212,82 @@ pub enum X402Error {
     /// JSON serialization/deserialization error.
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
+
+    /// The client's spending budget does not cover any payment option.
+    #[error("Payment budget exhausted")]
+    BudgetExhausted {
+        /// When the exhausted budget window restarts, or `None` if a total budget is spent.
+        resets_at: Option<UnixTimestamp>,
+    },
+
+    /// The client's spend ledger could not be read or written.
+    #[error("Spend ledger error: {0}")]
+    SpendLedger(String),
+
+    /// Payments to the host are not allowed by the client's host policies.
+    #[error("Payments to {0} are not allowed")]
+    HostNotAllowed(String),
+
+    /// The payment was denied when asked for approval.
+    #[error("Payment denied")]
+    PaymentDenied,
+
+    /// The client made as many payments as its rate limit allows for now.
+    #[error("Payment rate limit exceeded, retry in {}ms", retry_after.as_millis())]
+    RateLimited {
+        /// How long until the rate limit allows another payment.
+        retry_after: Duration,
+    },
+
+    /// Every payment sent for the request failed to settle.
+    #[error("Payment failed: {}", display_failures(.0))]
+    PaymentFailed(Vec<FailedPayment>),
+}
+
+/// A payment that was sent with a request but failed to settle, see
+/// [`X402Error::PaymentFailed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedPayment {
+    /// The chain the payment was made on.
+    pub chain_id: ChainId,
+    /// The payment scheme name.
+    pub scheme: String,
+    /// The token asset address.
+    pub asset: String,
+    /// The payment amount in token units.
+    pub amount: U256,
+    /// The recipient address.
+    pub pay_to: String,
+    /// Why the payment failed, as reported by the server.
+    pub reason: String,
+}
+
+impl FailedPayment {
+    /// Returns whether `candidate` is the payment option that failed.
+    pub fn is_for(&self, candidate: &PaymentCandidate) -> bool {
+        self.chain_id == candidate.chain_id
+            && self.scheme == candidate.scheme
+            && self.asset == candidate.asset
+            && self.pay_to == candidate.pay_to
+    }
+}
+
+impl std::fmt::Display for FailedPayment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} of {} on {}: {}",
+            self.scheme, self.amount, self.asset, self.chain_id, self.reason
+        )
+    }
+}
+
+fn display_failures(failures: &[FailedPayment]) -> String {
+    failures
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 // ============================================================================
@@ -196,3 +374,40 @@ impl PaymentSelector for MaxAmount {
         candidates.iter().find(|c| c.amount <= self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_offset_is_calibrated_from_the_server_time() {
+        let local_time = UnixTimestamp::from_secs(1_700_000_000);
+
+        // The local clock runs 7 seconds fast
+        let options = SigningOptions::default()
+            .calibrated(UnixTimestamp::from_secs(1_699_999_993), local_time);
+        assert_eq!(options.clock_offset_secs, -7);
+        assert_eq!(
+            options.server_time(UnixTimestamp::from_secs(1_700_000_100)),
+            UnixTimestamp::from_secs(1_700_000_093)
+        );
+
+        // The local clock runs 5 seconds slow
+        let options = options.calibrated(UnixTimestamp::from_secs(1_700_000_005), local_time);
+        assert_eq!(options.clock_offset_secs, 5);
+        assert_eq!(
+            options.server_time(local_time),
+            UnixTimestamp::from_secs(1_700_000_005)
+        );
+
+        // The server time does not go before the epoch
+        let options = SigningOptions {
+            clock_offset_secs: -10,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.server_time(UnixTimestamp::from_secs(3)),
+            UnixTimestamp::from_secs(0)
+        );
+    }
+}
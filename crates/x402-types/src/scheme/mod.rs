@@ -16,9 +16,11 @@
 //!
 //! Scheme implementations are provided by chain-specific crates:
 //!
-//! - **EVM chains** (`x402-chain-eip155`): `v1-eip155-exact`, `v2-eip155-exact`
+//! - **EVM chains** (`x402-chain-eip155`): `v1-eip155-exact`, `v2-eip155-exact`,
+//!   `v2-eip155-upto`
 //! - **Solana** (`x402-chain-solana`): `v1-solana-exact`, `v2-solana-exact`
 //! - **Aptos** (`x402-chain-aptos`): `v2-aptos-exact`
+//! - **Tron** (`x402-chain-tron`): `v2-tron-exact`
 //!
 //! # Implementing a Custom Scheme
 //!
@@ -29,13 +31,103 @@
 //! 3. Implement [`X402SchemeFacilitator`] for the actual verification/settlement logic
 //! 4. Register your scheme with [`SchemeBlueprints::register`]
 //!
+//! None of these needs to live in this repository: a downstream crate implements the traits
+//! for its own types and registers them next to the built-in blueprints.
+//!
+//! ```rust
+//! use x402_types::chain::{ChainId, ChainIdPattern, ChainProviderOps, ChainRegistry};
+//! use x402_types::proto;
+//! use x402_types::scheme::{
+//!     SchemeBlueprints, SchemeConfig, SchemeHandlerSlug, SchemeRegistry,
+//!     X402SchemeFacilitator, X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError,
+//!     X402SchemeId,
+//! };
+//! use std::collections::HashMap;
+//!
+//! /// The chain provider of the facilitator.
+//! struct Provider(ChainId);
+//!
+//! impl ChainProviderOps for Provider {
+//!     fn signer_addresses(&self) -> Vec<String> {
+//!         vec![]
+//!     }
+//!
+//!     fn chain_id(&self) -> ChainId {
+//!         self.0.clone()
+//!     }
+//! }
+//!
+//! /// The `v2-xdc-exact` scheme.
+//! struct V2XdcExact;
+//!
+//! impl X402SchemeId for V2XdcExact {
+//!     fn namespace(&self) -> &str {
+//!         "xdc"
+//!     }
+//!
+//!     fn scheme(&self) -> &str {
+//!         "exact"
+//!     }
+//! }
+//!
+//! impl X402SchemeFacilitatorBuilder<&Provider> for V2XdcExact {
+//!     fn build(
+//!         &self,
+//!         _provider: &Provider,
+//!         _config: Option<serde_json::Value>,
+//!     ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+//!         Ok(Box::new(V2XdcExactFacilitator))
+//!     }
+//! }
+//!
+//! struct V2XdcExactFacilitator;
+//!
+//! #[async_trait::async_trait]
+//! impl X402SchemeFacilitator for V2XdcExactFacilitator {
+//!     async fn verify(
+//!         &self,
+//!         _request: &proto::VerifyRequest,
+//!     ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+//!         unimplemented!()
+//!     }
+//!
+//!     async fn settle(
+//!         &self,
+//!         _request: &proto::SettleRequest,
+//!     ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+//!         unimplemented!()
+//!     }
+//!
+//!     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+//!         unimplemented!()
+//!     }
+//! }
+//!
+//! let chain_id = ChainId::new("xdc", "50");
+//! let chains = ChainRegistry::new(HashMap::from([(chain_id.clone(), Provider(chain_id.clone()))]));
+//! let blueprints = SchemeBlueprints::new().and_register(V2XdcExact);
+//! let config = vec![SchemeConfig {
+//!     enabled: true,
+//!     id: "v2-xdc-exact".into(),
+//!     chains: ChainIdPattern::wildcard("xdc"),
+//!     config: None,
+//! }];
+//! let registry = SchemeRegistry::build(chains, blueprints, &config);
+//!
+//! let slug = SchemeHandlerSlug::new(chain_id, 2, "exact".into());
+//! assert!(registry.by_slug(&slug).is_some());
+//! ```
+//!
 //! See the `docs/how-to-write-a-scheme.md` guide in the repository for details.
 
 pub mod client;
 
 use crate::chain::{ChainId, ChainIdPattern, ChainProviderOps, ChainRegistry};
 use crate::proto;
-use crate::proto::{AsPaymentProblem, ErrorReason, PaymentProblem, PaymentVerificationError};
+use crate::proto::{
+    AsPaymentProblem, ErrorReason, PaymentProblem, PaymentProblemCode, PaymentVerificationError,
+};
+use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
@@ -68,6 +160,21 @@ pub trait X402SchemeFacilitator: Send + Sync {
 
     /// Returns the payment methods supported by this handler.
     async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError>;
+
+    /// Settles several payments, returning one result per request, in order.
+    ///
+    /// The default settles them one by one with [`Self::settle`]. Schemes able to
+    /// combine settlements into a single transaction override it.
+    async fn settle_batch(
+        &self,
+        requests: &[proto::SettleRequest],
+    ) -> Vec<Result<proto::SettleResponse, X402SchemeFacilitatorError>> {
+        let mut results = Vec::with_capacity(requests.len());
+        for request in requests {
+            results.push(self.settle(request).await);
+        }
+        results
+    }
 }
 
 /// Marker trait for types that are both identifiable and buildable.
@@ -86,7 +193,13 @@ impl<T, P> X402SchemeBlueprint<P> for T where
 /// Trait for identifying a payment scheme.
 ///
 /// Each scheme has a unique identifier composed of the protocol version,
-/// chain namespace, and scheme name.
+/// chain namespace, and scheme name. [`SchemeBlueprints`] keys blueprints by
+/// [`id`](Self::id), which [`SchemeConfig::id`] refers to, and [`SchemeRegistry`]
+/// routes requests to handlers by version and scheme name on the chains of the
+/// configuration.
+///
+/// A scheme from a downstream crate picks the namespace of its chain, which
+/// need not be one supported by this repository, e.g. `"xdc"`.
 pub trait X402SchemeId {
     /// Returns the x402 protocol version (1 or 2).
     fn x402_version(&self) -> u8 {
@@ -109,8 +222,10 @@ pub trait X402SchemeId {
 
 /// Trait for building scheme handlers from chain providers.
 ///
-/// The type parameter `P` represents the chain provider type.
-/// Implementations use ['FromChainProvider'] trait to get the specific provider they need.
+/// The type parameter `P` represents the chain provider type, borrowed from the
+/// [`ChainRegistry`] the handlers are built from, e.g. `&ChainProvider`. Blueprints
+/// implement it for the provider type of the facilitator they plug into, and pick
+/// out the chain-specific provider they need, failing the build for other chains.
 pub trait X402SchemeFacilitatorBuilder<P> {
     /// Creates a new scheme handler for the given chain provider.
     ///
@@ -136,13 +251,56 @@ pub enum X402SchemeFacilitatorError {
     OnchainFailure(String),
 }
 
+impl X402SchemeFacilitatorError {
+    /// Returns the HTTP status code of facilitator responses reporting this error.
+    ///
+    /// Failed verifications are `400 Bad Request`, except for an insufficient allowance,
+    /// which is `412 Precondition Failed`. On-chain failures are `500 Internal Server Error`.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            X402SchemeFacilitatorError::PaymentVerification(
+                PaymentVerificationError::InsufficientAllowance,
+            ) => StatusCode::PRECONDITION_FAILED,
+            X402SchemeFacilitatorError::PaymentVerification(_) => StatusCode::BAD_REQUEST,
+            X402SchemeFacilitatorError::OnchainFailure(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Returns the URI identifying the problem type of this error, see
+    /// [`PaymentProblemCode::error_type_uri`].
+    pub fn error_type_uri(&self) -> &'static str {
+        match self {
+            X402SchemeFacilitatorError::PaymentVerification(e) => e.code().error_type_uri(),
+            X402SchemeFacilitatorError::OnchainFailure(_) => {
+                PaymentProblemCode::OnchainFailure.error_type_uri()
+            }
+        }
+    }
+}
+
+impl PaymentProblem {
+    /// Returns the HTTP status code and the problem of a facilitator response reporting
+    /// `err`, which occurred on the request to `instance`, e.g. `/verify`.
+    pub fn from_facilitator_error(
+        err: &X402SchemeFacilitatorError,
+        instance: &str,
+    ) -> (StatusCode, PaymentProblem) {
+        (
+            err.status_code(),
+            err.as_payment_problem().with_instance(instance),
+        )
+    }
+}
+
 impl AsPaymentProblem for X402SchemeFacilitatorError {
     fn as_payment_problem(&self) -> PaymentProblem {
         match self {
             X402SchemeFacilitatorError::PaymentVerification(e) => e.as_payment_problem(),
-            X402SchemeFacilitatorError::OnchainFailure(e) => {
-                PaymentProblem::new(ErrorReason::UnexpectedError, e.to_string())
-            }
+            X402SchemeFacilitatorError::OnchainFailure(e) => PaymentProblem::new(
+                PaymentProblemCode::OnchainFailure,
+                ErrorReason::UnexpectedError,
+                e.to_string(),
+            ),
         }
     }
 }
@@ -154,8 +312,8 @@ impl AsPaymentProblem for X402SchemeFacilitatorError {
 ///
 /// # Type Parameters
 ///
-/// - `P` - The chain provider type that blueprints can extract from using [`FromChainProvider`]
-#[derive(Default)]
+/// - `P` - The chain provider type that blueprints build handlers from, see
+///   [`X402SchemeFacilitatorBuilder`]
 pub struct SchemeBlueprints<P>(
     HashMap<String, Box<dyn X402SchemeBlueprint<P>>>,
     PhantomData<P>,
@@ -168,6 +326,12 @@ impl<P> Debug for SchemeBlueprints<P> {
     }
 }
 
+impl<P> Default for SchemeBlueprints<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<P> SchemeBlueprints<P> {
     /// Creates an empty blueprint registry.
     pub fn new() -> Self {
@@ -318,7 +482,7 @@ impl SchemeRegistry {
 /// Configuration for a specific scheme.
 ///
 /// Each scheme entry specifies which scheme to use and which chains it applies to.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SchemeConfig {
     /// Whether this scheme is enabled (defaults to true).
     #[serde(default = "scheme_config_defaults::default_enabled")]
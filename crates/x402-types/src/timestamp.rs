@@ -4,11 +4,24 @@
 //! to represent time-bounded payment authorizations. Timestamps are used in ERC-3009
 //! `transferWithAuthorization` messages and Solana payment instructions to specify
 //! when a payment authorization becomes valid and when it expires.
+//!
+//! A [`ValidityWindow`] pairs the `validAfter` and `validBefore` bounds of an authorization.
+//!
+//! A [`Clock`] tells the current time to time-dependent checks, such as the validity of an
+//! authorization at verification. [`SystemClock`] reads the system time; [`FixedClock`] and
+//! [`AdvancingClock`] let tests run these checks at exact instants, without sleeping.
+//!
+//! With the `chrono` or `time` feature, timestamps convert to and from the datetime types of
+//! these crates, and to and from RFC 3339 strings, e.g. for structured log timestamps.
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
-use std::ops::Add;
-use std::time::SystemTime;
+use std::ops::{Add, Sub};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use web_time::SystemTime;
 
 /// A Unix timestamp representing seconds since the Unix epoch (1970-01-01T00:00:00Z).
 ///
@@ -44,12 +57,14 @@ use std::time::SystemTime;
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq)]
 pub struct UnixTimestamp(u64);
 
+#[cfg(feature = "serde")]
 impl Serialize for UnixTimestamp {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_str(&self.0.to_string())
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for UnixTimestamp {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -63,6 +78,68 @@ impl<'de> Deserialize<'de> for UnixTimestamp {
     }
 }
 
+/// Latest timestamp representable in RFC 3339, `9999-12-31T23:59:59Z`.
+#[cfg(any(feature = "chrono", feature = "time"))]
+const MAX_RFC3339_SECS: u64 = 253_402_300_799;
+
+/// Error returned by [`UnixTimestamp::from_rfc3339`].
+#[cfg(any(feature = "chrono", feature = "time"))]
+#[derive(Debug, thiserror::Error)]
+pub enum TimestampParseError {
+    /// The string is not an RFC 3339 datetime.
+    #[error("Invalid RFC 3339 timestamp: {0}")]
+    Invalid(String),
+    /// The datetime is before the Unix epoch.
+    #[error("Timestamp is before the Unix epoch")]
+    BeforeEpoch,
+}
+
+/// Error converting a [`UnixTimestamp`] beyond the range of a datetime type.
+#[cfg(any(feature = "chrono", feature = "time"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Timestamp {0} is out of the datetime range")]
+pub struct TimestampOutOfRange(pub u64);
+
+/// Truncates to whole seconds. Datetimes before the Unix epoch convert to the epoch.
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for UnixTimestamp {
+    fn from(value: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(u64::try_from(value.timestamp()).unwrap_or(0))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<UnixTimestamp> for chrono::DateTime<chrono::Utc> {
+    type Error = TimestampOutOfRange;
+
+    fn try_from(value: UnixTimestamp) -> Result<Self, Self::Error> {
+        i64::try_from(value.0)
+            .ok()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .ok_or(TimestampOutOfRange(value.0))
+    }
+}
+
+/// Truncates to whole seconds. Datetimes before the Unix epoch convert to the epoch.
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for UnixTimestamp {
+    fn from(value: time::OffsetDateTime) -> Self {
+        Self(u64::try_from(value.unix_timestamp()).unwrap_or(0))
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<UnixTimestamp> for time::OffsetDateTime {
+    type Error = TimestampOutOfRange;
+
+    fn try_from(value: UnixTimestamp) -> Result<Self, Self::Error> {
+        i64::try_from(value.0)
+            .ok()
+            .and_then(|secs| time::OffsetDateTime::from_unix_timestamp(secs).ok())
+            .ok_or(TimestampOutOfRange(value.0))
+    }
+}
+
 impl Display for UnixTimestamp {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -77,6 +154,24 @@ impl Add<u64> for UnixTimestamp {
     }
 }
 
+/// Saturates at the latest representable timestamp.
+impl Add<Duration> for UnixTimestamp {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        UnixTimestamp(self.0.saturating_add(rhs.as_secs()))
+    }
+}
+
+/// Saturates at the Unix epoch.
+impl Sub<Duration> for UnixTimestamp {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        UnixTimestamp(self.0.saturating_sub(rhs.as_secs()))
+    }
+}
+
 impl UnixTimestamp {
     /// Creates a new [`UnixTimestamp`] from a raw seconds value.
     ///
@@ -94,6 +189,8 @@ impl UnixTimestamp {
 
     /// Returns the current system time as a [`UnixTimestamp`].
     ///
+    /// On `wasm32` targets, this reads the browser clock (`Date.now()`).
+    ///
     /// # Panics
     ///
     /// Panics if the system clock is set to a time before the Unix epoch,
@@ -129,4 +226,374 @@ impl UnixTimestamp {
     pub fn as_secs(&self) -> u64 {
         self.0
     }
+    /// Parses an RFC 3339 datetime, such as `2023-11-14T22:13:20Z`.
+    ///
+    /// Fractional seconds are truncated. Requires the `chrono` or `time` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimestampParseError`] if the string is not an RFC 3339 datetime, or is
+    /// before the Unix epoch.
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    pub fn from_rfc3339(s: &str) -> Result<Self, TimestampParseError> {
+        #[cfg(feature = "chrono")]
+        let secs = chrono::DateTime::parse_from_rfc3339(s)
+            .map_err(|e| TimestampParseError::Invalid(e.to_string()))?
+            .timestamp();
+        #[cfg(not(feature = "chrono"))]
+        let secs = time::OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339)
+            .map_err(|e| TimestampParseError::Invalid(e.to_string()))?
+            .unix_timestamp();
+        u64::try_from(secs)
+            .map(Self)
+            .map_err(|_| TimestampParseError::BeforeEpoch)
+    }
+
+    /// Formats the timestamp as an RFC 3339 datetime in UTC, such as `2023-11-14T22:13:20Z`.
+    ///
+    /// Timestamps after `9999-12-31T23:59:59Z`, the latest RFC 3339 datetime, are formatted
+    /// as that datetime. Requires the `chrono` or `time` feature.
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    pub fn to_rfc3339(&self) -> String {
+        let secs = self.0.min(MAX_RFC3339_SECS) as i64;
+        #[cfg(feature = "chrono")]
+        {
+            chrono::DateTime::from_timestamp(secs, 0)
+                .expect("RFC 3339 timestamps are within the chrono range")
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        }
+        #[cfg(not(feature = "chrono"))]
+        {
+            time::OffsetDateTime::from_unix_timestamp(secs)
+                .expect("RFC 3339 timestamps are within the time range")
+                .format(&time::format_description::well_known::Rfc3339)
+                .expect("RFC 3339 timestamps are formattable")
+        }
+    }
+}
+
+/// The time window of a payment authorization: valid from `valid_after` (inclusive) until
+/// `valid_before` (exclusive).
+///
+/// Durations are counted in whole seconds, fractions are truncated.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use x402_types::timestamp::{UnixTimestamp, ValidityWindow};
+///
+/// let window = ValidityWindow::new(UnixTimestamp::from_secs(100), UnixTimestamp::from_secs(160));
+/// assert!(window.is_valid_at(UnixTimestamp::from_secs(100)));
+/// assert!(!window.is_valid_at(UnixTimestamp::from_secs(160)));
+///
+/// let extended = window.extend(Duration::from_secs(60));
+/// assert_eq!(extended.valid_before, UnixTimestamp::from_secs(220));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidityWindow {
+    /// Not valid before this timestamp (inclusive).
+    pub valid_after: UnixTimestamp,
+    /// Not valid at/after this timestamp (exclusive).
+    pub valid_before: UnixTimestamp,
+}
+
+impl ValidityWindow {
+    /// Creates the window from `valid_after` until `valid_before`.
+    pub fn new(valid_after: UnixTimestamp, valid_before: UnixTimestamp) -> Self {
+        Self {
+            valid_after,
+            valid_before,
+        }
+    }
+
+    /// Creates a window valid from now for `duration`.
+    pub fn from_now_for(duration: Duration) -> Self {
+        let now = UnixTimestamp::now();
+        Self::new(now, now + duration)
+    }
+
+    /// Returns `true` if the window contains `t`.
+    pub fn is_valid_at(&self, t: UnixTimestamp) -> bool {
+        self.valid_after <= t && t < self.valid_before
+    }
+
+    /// Returns `true` if the window contains now, and remains valid for at least `grace`.
+    ///
+    /// The grace period leaves time to settle an authorization before it expires.
+    pub fn grace_adjusted_is_valid(&self, grace: Duration) -> bool {
+        self.grace_adjusted_is_valid_at(UnixTimestamp::now(), grace)
+    }
+
+    /// Returns `true` if the window contains `t`, and remains valid for at least `grace`
+    /// after it.
+    pub fn grace_adjusted_is_valid_at(&self, t: UnixTimestamp, grace: Duration) -> bool {
+        self.is_valid_at(t) && self.remaining_at(t).is_some_and(|left| left >= grace)
+    }
+
+    /// Returns the time left until the window expires, or `None` if it has expired.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.remaining_at(UnixTimestamp::now())
+    }
+
+    /// Returns the time left after `t` until the window expires, or `None` if it has
+    /// expired at `t`.
+    pub fn remaining_at(&self, t: UnixTimestamp) -> Option<Duration> {
+        (t < self.valid_before).then(|| Duration::from_secs(self.valid_before.0 - t.0))
+    }
+
+    /// Returns the window with its expiry moved `d` later.
+    pub fn extend(self, d: Duration) -> Self {
+        Self::new(self.valid_after, self.valid_before + d)
+    }
+}
+
+/// Moves the whole window later.
+impl Add<Duration> for ValidityWindow {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Self::new(self.valid_after + rhs, self.valid_before + rhs)
+    }
+}
+
+/// Moves the whole window earlier.
+impl Sub<Duration> for ValidityWindow {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        Self::new(self.valid_after - rhs, self.valid_before - rhs)
+    }
+}
+
+/// A source of the current time.
+///
+/// Time-dependent verification takes a clock rather than reading the system time, so that
+/// tests can check it at exact instants.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> UnixTimestamp;
+}
+
+/// The system clock, see [`UnixTimestamp::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> UnixTimestamp {
+        UnixTimestamp::now()
+    }
+}
+
+/// A clock stopped at the given number of seconds since the Unix epoch.
+///
+/// # Example
+///
+/// ```
+/// use x402_types::timestamp::{Clock, FixedClock, UnixTimestamp};
+///
+/// let clock = FixedClock(1699999999);
+/// assert_eq!(clock.now(), UnixTimestamp::from_secs(1699999999));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> UnixTimestamp {
+        UnixTimestamp::from_secs(self.0)
+    }
+}
+
+/// A clock moved by hand, in seconds since the Unix epoch. Clones share the same time.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use x402_types::timestamp::{AdvancingClock, Clock, UnixTimestamp};
+///
+/// let clock = AdvancingClock::new(1699999999);
+/// let shared = clock.clone();
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(shared.now(), UnixTimestamp::from_secs(1700000059));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AdvancingClock(pub Arc<AtomicU64>);
+
+impl AdvancingClock {
+    /// Creates a clock at `secs` seconds since the Unix epoch.
+    pub fn new(secs: u64) -> Self {
+        Self(Arc::new(AtomicU64::new(secs)))
+    }
+
+    /// Moves the clock `d` later, in whole seconds.
+    pub fn advance(&self, d: Duration) {
+        self.0.fetch_add(d.as_secs(), Ordering::SeqCst);
+    }
+
+    /// Sets the clock to `secs` seconds since the Unix epoch.
+    pub fn set(&self, secs: u64) {
+        self.0.store(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for AdvancingClock {
+    fn now(&self) -> UnixTimestamp {
+        UnixTimestamp::from_secs(self.0.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECS: u64 = 1_700_000_000;
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    const RFC3339: &str = "2023-11-14T22:13:20Z";
+
+    fn ts(secs: u64) -> UnixTimestamp {
+        UnixTimestamp::from_secs(secs)
+    }
+
+    #[test]
+    fn window_bounds() {
+        let window = ValidityWindow::new(ts(SECS), ts(SECS + 60));
+        assert!(!window.is_valid_at(ts(SECS - 1)));
+        assert!(window.is_valid_at(ts(SECS)));
+        assert!(window.is_valid_at(ts(SECS + 59)));
+        assert!(!window.is_valid_at(ts(SECS + 60)));
+
+        // Empty and inverted windows are never valid
+        assert!(!ValidityWindow::new(ts(SECS), ts(SECS)).is_valid_at(ts(SECS)));
+        assert!(!ValidityWindow::new(ts(SECS + 1), ts(SECS)).is_valid_at(ts(SECS)));
+    }
+
+    #[test]
+    fn clocks() {
+        assert_eq!(FixedClock(SECS).now(), ts(SECS));
+        assert!(SystemClock.now() >= UnixTimestamp::now() - Duration::from_secs(1));
+
+        let clock = AdvancingClock::new(SECS);
+        let shared = clock.clone();
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(shared.now(), ts(SECS + 6));
+        shared.set(SECS);
+        assert_eq!(clock.now(), ts(SECS));
+
+        // Trait objects, as taken by verification
+        let clock: Box<dyn Clock> = Box::new(FixedClock(SECS));
+        let window = ValidityWindow::new(ts(SECS - 60), ts(SECS + 60));
+        assert!(window.is_valid_at(clock.now()));
+    }
+
+    #[test]
+    fn window_remaining() {
+        let window = ValidityWindow::new(ts(SECS), ts(SECS + 60));
+        assert_eq!(
+            window.remaining_at(ts(SECS - 10)),
+            Some(Duration::from_secs(70))
+        );
+        assert_eq!(
+            window.remaining_at(ts(SECS + 59)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(window.remaining_at(ts(SECS + 60)), None);
+        assert_eq!(window.remaining_at(ts(u64::MAX)), None);
+    }
+
+    #[test]
+    fn window_grace() {
+        let window = ValidityWindow::new(ts(SECS), ts(SECS + 60));
+        let grace = Duration::from_secs(6);
+        assert!(!window.grace_adjusted_is_valid_at(ts(SECS - 1), grace));
+        assert!(window.grace_adjusted_is_valid_at(ts(SECS), grace));
+        assert!(window.grace_adjusted_is_valid_at(ts(SECS + 54), grace));
+        assert!(!window.grace_adjusted_is_valid_at(ts(SECS + 55), grace));
+        // Without grace, the same as `is_valid_at`
+        assert!(window.grace_adjusted_is_valid_at(ts(SECS + 59), Duration::ZERO));
+        assert!(!window.grace_adjusted_is_valid_at(ts(SECS + 60), Duration::ZERO));
+
+        let now = ValidityWindow::from_now_for(Duration::from_secs(60));
+        assert!(now.grace_adjusted_is_valid(grace));
+        assert!(!now.grace_adjusted_is_valid(Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn window_arithmetic() {
+        let window = ValidityWindow::new(ts(SECS), ts(SECS + 60));
+        let minute = Duration::from_secs(60);
+        assert_eq!(
+            window.extend(minute),
+            ValidityWindow::new(ts(SECS), ts(SECS + 120))
+        );
+        assert_eq!(
+            window + minute,
+            ValidityWindow::new(ts(SECS + 60), ts(SECS + 120))
+        );
+        assert_eq!(
+            window - minute,
+            ValidityWindow::new(ts(SECS - 60), ts(SECS))
+        );
+        assert_eq!(window + minute - minute, window);
+
+        // Saturates at both ends of the timestamp range
+        assert_eq!(ts(10) - minute, ts(0));
+        assert_eq!(ts(u64::MAX) + minute, ts(u64::MAX));
+        assert_eq!(window.extend(Duration::MAX).valid_before, ts(u64::MAX));
+        assert_eq!(ts(SECS) + Duration::from_millis(1_999), ts(SECS + 1));
+    }
+
+    #[test]
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    fn rfc3339_round_trip() {
+        let ts = UnixTimestamp::from_secs(SECS);
+        assert_eq!(ts.to_rfc3339(), RFC3339);
+        assert_eq!(UnixTimestamp::from_rfc3339(RFC3339).unwrap(), ts);
+        assert_eq!(
+            UnixTimestamp::from_rfc3339("2023-11-15T00:13:20.750+02:00").unwrap(),
+            ts
+        );
+        assert!(matches!(
+            UnixTimestamp::from_rfc3339("1969-12-31T23:59:59Z"),
+            Err(TimestampParseError::BeforeEpoch)
+        ));
+        assert!(matches!(
+            UnixTimestamp::from_rfc3339("yesterday"),
+            Err(TimestampParseError::Invalid(_))
+        ));
+        assert_eq!(
+            UnixTimestamp::from_secs(u64::MAX).to_rfc3339(),
+            "9999-12-31T23:59:59Z"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono_round_trip() {
+        let ts = UnixTimestamp::from_secs(SECS);
+        let datetime = chrono::DateTime::<chrono::Utc>::try_from(ts).unwrap();
+        assert_eq!(datetime.timestamp(), SECS as i64);
+        assert_eq!(UnixTimestamp::from(datetime), ts);
+        let before_epoch = chrono::DateTime::from_timestamp(-1, 0).unwrap();
+        assert_eq!(UnixTimestamp::from(before_epoch).as_secs(), 0);
+        assert_eq!(
+            chrono::DateTime::<chrono::Utc>::try_from(UnixTimestamp::from_secs(u64::MAX)),
+            Err(TimestampOutOfRange(u64::MAX))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_round_trip() {
+        let ts = UnixTimestamp::from_secs(SECS);
+        let datetime = time::OffsetDateTime::try_from(ts).unwrap();
+        assert_eq!(datetime.unix_timestamp(), SECS as i64);
+        assert_eq!(UnixTimestamp::from(datetime), ts);
+        let before_epoch = time::OffsetDateTime::from_unix_timestamp(-1).unwrap();
+        assert_eq!(UnixTimestamp::from(before_epoch).as_secs(), 0);
+        assert_eq!(
+            time::OffsetDateTime::try_from(UnixTimestamp::from_secs(u64::MAX)),
+            Err(TimestampOutOfRange(u64::MAX))
+        );
+    }
 }
@@ -2,12 +2,24 @@
 //!
 //! This module provides [`Base64Bytes`], a wrapper type for working with
 //! base64-encoded data in the x402 protocol.
+//!
+//! Data is encoded with the standard alphabet (`+` and `/`) by default. The URL-safe
+//! alphabet (`-` and `_`) is friendlier to HTTP parsers and logging systems, and
+//! [`Base64Bytes::auto_decode`] accepts either.
 
 use base64::Engine;
+use base64::alphabet;
 use base64::engine::general_purpose::STANDARD as b64;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
 use std::borrow::Cow;
 use std::fmt::Display;
 
+/// URL-safe engine, padding on encode and accepting unpadded input.
+const URL_SAFE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
 /// A wrapper for base64-encoded byte data.
 ///
 /// This type holds bytes that represent base64-encoded data and provides
@@ -45,6 +57,38 @@ impl Base64Bytes<'_> {
         let encoded = b64.encode(input.as_ref());
         Base64Bytes(Cow::Owned(encoded.into_bytes()))
     }
+
+    /// Decodes base64 string bytes in the URL-safe alphabet to raw binary data.
+    ///
+    /// Padding is optional.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not valid URL-safe base64.
+    pub fn decode_url_safe(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        URL_SAFE.decode(&self.0)
+    }
+
+    /// Encodes raw binary data into base64 string bytes in the URL-safe alphabet.
+    pub fn encode_url_safe<T: AsRef<[u8]>>(input: T) -> Base64Bytes<'static> {
+        let encoded = URL_SAFE.encode(input.as_ref());
+        Base64Bytes(Cow::Owned(encoded.into_bytes()))
+    }
+
+    /// Decodes base64 string bytes in either the standard or the URL-safe alphabet.
+    ///
+    /// Data containing `-` or `_` is decoded as URL-safe, anything else as standard.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not valid base64 in the detected alphabet.
+    pub fn auto_decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        if self.0.iter().any(|byte| matches!(byte, b'-' | b'_')) {
+            self.decode_url_safe()
+        } else {
+            self.decode()
+        }
+    }
 }
 
 impl AsRef<[u8]> for Base64Bytes<'_> {
@@ -64,3 +108,38 @@ impl Display for Base64Bytes<'_> {
         write!(f, "{}", String::from_utf8_lossy(self.0.as_ref()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Encodes to `+/8=` in the standard alphabet.
+    const BYTES: [u8; 2] = [0xfb, 0xff];
+
+    #[test]
+    fn url_safe_alphabet() {
+        assert_eq!(Base64Bytes::encode(BYTES).to_string(), "+/8=");
+        let encoded = Base64Bytes::encode_url_safe(BYTES);
+        assert_eq!(encoded.to_string(), "-_8=");
+        assert_eq!(encoded.decode_url_safe().unwrap(), BYTES);
+        assert_eq!(
+            Base64Bytes::from(b"-_8".as_slice())
+                .decode_url_safe()
+                .unwrap(),
+            BYTES
+        );
+        assert!(encoded.decode().is_err());
+    }
+
+    #[test]
+    fn auto_decode_detects_alphabet() {
+        for encoded in ["+/8=", "-_8=", "-_8"] {
+            let decoded = Base64Bytes::from(encoded.as_bytes()).auto_decode().unwrap();
+            assert_eq!(decoded, BYTES, "{encoded}");
+        }
+        let json = br#"{"x402Version":2}"#;
+        let encoded = Base64Bytes::encode(json);
+        assert_eq!(encoded.auto_decode().unwrap(), json);
+        assert!(Base64Bytes::from(b"+_8=".as_slice()).auto_decode().is_err());
+    }
+}
@@ -25,7 +25,7 @@
 /// The generated type:
 /// - Has a `VALUE` constant with the string
 /// - Implements `FromStr` (only accepts the exact string)
-/// - Implements `Serialize`/`Deserialize` (as the string)
+/// - Implements `Serialize`/`Deserialize` (as the string) with the `serde` feature
 /// - Implements `Display` (outputs the string)
 #[macro_export]
 macro_rules! lit_str {
@@ -55,6 +55,22 @@ macro_rules! lit_str {
             }
         }
 
+        $crate::__lit_str_serde!($struct_name);
+
+        impl std::fmt::Display for $struct_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, $val)
+            }
+        }
+    };
+}
+
+/// Implements `Serialize`/`Deserialize` for a [`lit_str!`](macro@lit_str) type.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lit_str_serde {
+    ($struct_name:ident) => {
         impl serde::Serialize for $struct_name {
             fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
                 serializer.serialize_str(Self::VALUE)
@@ -75,11 +91,13 @@ macro_rules! lit_str {
                 }
             }
         }
-
-        impl std::fmt::Display for $struct_name {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, $val)
-            }
-        }
     };
 }
+
+/// Without the `serde` feature, [`lit_str!`](macro@lit_str) types are not serializable.
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lit_str_serde {
+    ($struct_name:ident) => {};
+}
@@ -5,11 +5,19 @@
 //! - [`b64`] - Base64 encoding/decoding utilities
 //! - [`lit_str`] - Compile-time string literal types
 //! - [`money_amount`] - Human-readable currency amount parsing
+//! - [`revert`] - Decoding of Solidity revert reasons from EVM revert data
+//! - [`span`] - Tracing spans of payment verification and settlement
 
 pub mod b64;
+#[cfg(feature = "serde")]
 pub mod decimal_u256;
 pub mod lit_str;
 pub mod money_amount;
+pub mod revert;
+#[cfg(feature = "serde")]
+pub mod span;
 
 pub use b64::*;
+#[cfg(feature = "serde")]
 pub use decimal_u256::*;
+pub use revert::RevertReason;
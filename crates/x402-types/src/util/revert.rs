@@ -0,0 +1,164 @@
+//! Decoding of standard Solidity revert reasons.
+//!
+//! A reverted EVM call returns ABI-encoded revert data: `Error(string)` for `require` and
+//! `revert` with a message, and `Panic(uint256)` for failed assertions, arithmetic
+//! overflows and similar. RPC nodes pass that data along as hex in their error responses,
+//! e.g. `0x08c379a0…` for `FiatTokenV2: invalid signature`. [`RevertReason`] turns it back
+//! into a human-readable reason.
+//!
+//! # Example
+//!
+//! ```
+//! use x402_types::util::RevertReason;
+//!
+//! let message = "execution reverted, data: \"0x08c379a0\
+//!     0000000000000000000000000000000000000000000000000000000000000020\
+//!     000000000000000000000000000000000000000000000000000000000000001e\
+//!     46696174546f6b656e56323a20696e76616c6964207369676e61747572650000\"";
+//! let reason = RevertReason::find_in(message).unwrap();
+//! assert_eq!(reason.to_string(), "FiatTokenV2: invalid signature");
+//! ```
+
+use alloy_primitives::{U256, hex};
+use std::fmt::{Display, Formatter};
+
+/// Selector of `Error(string)`.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// A decoded Solidity revert reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertReason {
+    /// `Error(string)`, raised by `require` and `revert` with a message.
+    Error(String),
+    /// `Panic(uint256)`, raised by the compiler-inserted checks, with its code.
+    Panic(U256),
+}
+
+impl RevertReason {
+    /// Decodes ABI-encoded revert data, starting with its 4-byte selector.
+    ///
+    /// Returns `None` for data that is neither `Error(string)` nor `Panic(uint256)`,
+    /// e.g. custom errors.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (selector, args) = data.split_first_chunk::<4>()?;
+        match *selector {
+            ERROR_SELECTOR => {
+                let offset = word_as_usize(args, 0)?;
+                let len = word_as_usize(args, offset)?;
+                let start = offset.checked_add(32)?;
+                let bytes = args.get(start..start.checked_add(len)?)?;
+                String::from_utf8(bytes.to_vec()).ok().map(Self::Error)
+            }
+            PANIC_SELECTOR => {
+                let word = args.get(..32)?;
+                Some(Self::Panic(U256::from_be_slice(word)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes hex-encoded revert data, with or without the `0x` prefix.
+    pub fn decode_hex(data: &str) -> Option<Self> {
+        Self::decode(&hex::decode(data.trim()).ok()?)
+    }
+
+    /// Finds and decodes revert data embedded in an error message.
+    ///
+    /// Looks for the first `0x`-prefixed hex string starting with the `Error(string)` or
+    /// `Panic(uint256)` selector, as found in the error messages of RPC nodes and clients.
+    pub fn find_in(message: &str) -> Option<Self> {
+        message.match_indices("0x").find_map(|(start, _)| {
+            let rest = &message[start + 2..];
+            let end = rest
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .unwrap_or(rest.len());
+            Self::decode_hex(&rest[..end])
+        })
+    }
+
+    /// Returns the description of a `Panic(uint256)` code, as documented by Solidity.
+    pub fn panic_description(code: U256) -> Option<&'static str> {
+        let description = match code.try_into().ok()? {
+            0x00u8 => "generic compiler panic",
+            0x01 => "assertion failed",
+            0x11 => "arithmetic overflow or underflow",
+            0x12 => "division or modulo by zero",
+            0x21 => "invalid enum value",
+            0x22 => "invalid storage byte array encoding",
+            0x31 => "pop on empty array",
+            0x32 => "array index out of bounds",
+            0x41 => "out of memory",
+            0x51 => "call to uninitialized function",
+            _ => return None,
+        };
+        Some(description)
+    }
+}
+
+impl Display for RevertReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RevertReason::Error(reason) => f.write_str(reason),
+            RevertReason::Panic(code) => match Self::panic_description(*code) {
+                Some(description) => write!(f, "panic: {description} ({code:#x})"),
+                None => write!(f, "panic: code {code:#x}"),
+            },
+        }
+    }
+}
+
+/// Reads the 32-byte word at `offset` of `data` as a `usize`.
+fn word_as_usize(data: &[u8], offset: usize) -> Option<usize> {
+    let word = data.get(offset..offset.checked_add(32)?)?;
+    U256::from_be_slice(word).try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVALID_SIGNATURE: &str = "0x08c379a0\
+        0000000000000000000000000000000000000000000000000000000000000020\
+        000000000000000000000000000000000000000000000000000000000000001e\
+        46696174546f6b656e56323a20696e76616c6964207369676e61747572650000";
+
+    #[test]
+    fn decodes_error_strings() {
+        assert_eq!(
+            RevertReason::decode_hex(INVALID_SIGNATURE),
+            Some(RevertReason::Error("FiatTokenV2: invalid signature".into()))
+        );
+        // Truncated data
+        assert_eq!(
+            RevertReason::decode_hex(&INVALID_SIGNATURE[..INVALID_SIGNATURE.len() - 8]),
+            None
+        );
+        // Custom errors are not decoded
+        assert_eq!(RevertReason::decode_hex("0xdeadbeef"), None);
+    }
+
+    #[test]
+    fn decodes_panics() {
+        let data = format!("0x4e487b71{:064x}", 0x11);
+        let reason = RevertReason::decode_hex(&data).unwrap();
+        assert_eq!(reason, RevertReason::Panic(U256::from(0x11)));
+        assert_eq!(
+            reason.to_string(),
+            "panic: arithmetic overflow or underflow (0x11)"
+        );
+    }
+
+    #[test]
+    fn finds_revert_data_in_messages() {
+        let message = format!(
+            "server returned an error response: error code 3: execution reverted, data: \"{INVALID_SIGNATURE}\""
+        );
+        assert_eq!(
+            RevertReason::find_in(&message).map(|reason| reason.to_string()),
+            Some("FiatTokenV2: invalid signature".into())
+        );
+        assert_eq!(RevertReason::find_in("from 0xd8dA6BF2 failed"), None);
+    }
+}
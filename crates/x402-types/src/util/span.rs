@@ -0,0 +1,277 @@
+//! Tracing spans shared by every scheme's verification and settlement.
+//!
+//! Scheme facilitators run their [`verify`](crate::scheme::X402SchemeFacilitator::verify) in
+//! [`verify_in_span`] and their [`settle`](crate::scheme::X402SchemeFacilitator::settle) in
+//! [`settle_in_span`], so that traces carry the same attributes whichever chain served the
+//! payment:
+//!
+//! | Field          | Source                                              |
+//! |----------------|-----------------------------------------------------|
+//! | `scheme`       | Scheme of the payment payload                       |
+//! | `x402_version` | Protocol version of the request                     |
+//! | `network`      | CAIP-2 chain ID, also for V1 network names          |
+//! | `payer`        | `payer` of the verify or settle response            |
+//! | `recipient`    | `payTo` of the payment requirements                 |
+//! | `asset`        | `asset` of the payment requirements                 |
+//! | `amount`       | Required amount of the payment requirements         |
+//! | `tx_hash`      | `transaction` of the settle response (settle only)  |
+//!
+//! Fields missing from a malformed request or a failed response are left empty.
+//! Without the `telemetry` feature, the helpers only await the future.
+
+use std::future::Future;
+
+use crate::proto;
+
+/// Runs `verify`, the verification of `request`, in an `x402.verify` span.
+pub async fn verify_in_span<F, E>(
+    request: &proto::VerifyRequest,
+    verify: F,
+) -> Result<proto::VerifyResponse, E>
+where
+    F: Future<Output = Result<proto::VerifyResponse, E>>,
+{
+    #[cfg(feature = "telemetry")]
+    {
+        use tracing::Instrument;
+
+        let span = tracing::info_span!(
+            "x402.verify",
+            scheme = tracing::field::Empty,
+            x402_version = tracing::field::Empty,
+            network = tracing::field::Empty,
+            payer = tracing::field::Empty,
+            recipient = tracing::field::Empty,
+            asset = tracing::field::Empty,
+            amount = tracing::field::Empty,
+        );
+        record_request(&span, request);
+        let result = verify.instrument(span.clone()).await;
+        if let Ok(response) = &result {
+            record_response(&span, &response.0);
+        }
+        result
+    }
+    #[cfg(not(feature = "telemetry"))]
+    {
+        let _ = request;
+        verify.await
+    }
+}
+
+/// Runs `settle`, the settlement of `request`, in an `x402.settle` span.
+pub async fn settle_in_span<F, E>(
+    request: &proto::SettleRequest,
+    settle: F,
+) -> Result<proto::SettleResponse, E>
+where
+    F: Future<Output = Result<proto::SettleResponse, E>>,
+{
+    #[cfg(feature = "telemetry")]
+    {
+        use tracing::Instrument;
+
+        let span = tracing::info_span!(
+            "x402.settle",
+            scheme = tracing::field::Empty,
+            x402_version = tracing::field::Empty,
+            network = tracing::field::Empty,
+            payer = tracing::field::Empty,
+            recipient = tracing::field::Empty,
+            asset = tracing::field::Empty,
+            amount = tracing::field::Empty,
+            tx_hash = tracing::field::Empty,
+        );
+        record_request(&span, request);
+        let result = settle.instrument(span.clone()).await;
+        if let Ok(response) = &result {
+            record_response(&span, &response.0);
+        }
+        result
+    }
+    #[cfg(not(feature = "telemetry"))]
+    {
+        let _ = request;
+        settle.await
+    }
+}
+
+/// Attributes of a payment request, as recorded on its span.
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RequestFields {
+    scheme: Option<String>,
+    x402_version: Option<u8>,
+    network: Option<String>,
+    recipient: Option<String>,
+    asset: Option<String>,
+    amount: Option<String>,
+}
+
+#[cfg(feature = "telemetry")]
+impl RequestFields {
+    fn of(request: &proto::VerifyRequest) -> Self {
+        let slug = request.scheme_handler_slug();
+        Self {
+            x402_version: slug.as_ref().map(|slug| slug.x402_version),
+            network: slug.as_ref().map(|slug| slug.chain_id.to_string()),
+            scheme: slug.map(|slug| slug.name),
+            recipient: request.pay_to(),
+            asset: request.asset(),
+            amount: request.amount(),
+        }
+    }
+}
+
+/// Attributes of a verify or settle response, as recorded on its span.
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ResponseFields<'a> {
+    payer: Option<&'a str>,
+    tx_hash: Option<&'a str>,
+}
+
+#[cfg(feature = "telemetry")]
+impl<'a> ResponseFields<'a> {
+    fn of(response: &'a serde_json::Value) -> Self {
+        let non_empty = |field: &str| {
+            response
+                .get(field)
+                .and_then(serde_json::Value::as_str)
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            payer: non_empty("payer"),
+            tx_hash: non_empty("transaction"),
+        }
+    }
+}
+
+#[cfg(feature = "telemetry")]
+fn record_request(span: &tracing::Span, request: &proto::VerifyRequest) {
+    let fields = RequestFields::of(request);
+    if let Some(scheme) = fields.scheme {
+        span.record("scheme", scheme);
+    }
+    if let Some(x402_version) = fields.x402_version {
+        span.record("x402_version", x402_version);
+    }
+    if let Some(network) = fields.network {
+        span.record("network", network);
+    }
+    if let Some(recipient) = fields.recipient {
+        span.record("recipient", recipient);
+    }
+    if let Some(asset) = fields.asset {
+        span.record("asset", asset);
+    }
+    if let Some(amount) = fields.amount {
+        span.record("amount", amount);
+    }
+}
+
+#[cfg(feature = "telemetry")]
+fn record_response(span: &tracing::Span, response: &serde_json::Value) {
+    let fields = ResponseFields::of(response);
+    if let Some(payer) = fields.payer {
+        span.record("payer", payer);
+    }
+    if let Some(tx_hash) = fields.tx_hash {
+        span.record("tx_hash", tx_hash);
+    }
+}
+
+#[cfg(all(test, feature = "telemetry"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(request: serde_json::Value) -> proto::VerifyRequest {
+        serde_json::from_str(&request.to_string()).unwrap()
+    }
+
+    #[test]
+    fn reads_v2_requests() {
+        let request = request(json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "x402Version": 2,
+                "accepted": { "scheme": "exact", "network": "eip155:84532" },
+                "payload": {},
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "eip155:84532",
+                "amount": "10000",
+                "payTo": "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+                "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+            },
+        }));
+        assert_eq!(
+            RequestFields::of(&request),
+            RequestFields {
+                scheme: Some("exact".into()),
+                x402_version: Some(2),
+                network: Some("eip155:84532".into()),
+                recipient: Some("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07".into()),
+                asset: Some("0x036CbD53842c5426634e7929541eC2318f3dCF7e".into()),
+                amount: Some("10000".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn reads_v1_requests() {
+        let request = request(json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {},
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "maxAmountRequired": "10000",
+                "payTo": "0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+                "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+            },
+        }));
+        let fields = RequestFields::of(&request);
+        assert_eq!(fields.x402_version, Some(1));
+        assert_eq!(fields.network.as_deref(), Some("eip155:84532"));
+        assert_eq!(fields.amount.as_deref(), Some("10000"));
+    }
+
+    #[test]
+    fn leaves_malformed_requests_empty() {
+        let request = request(json!({ "x402Version": 2 }));
+        assert_eq!(RequestFields::of(&request), RequestFields::default());
+    }
+
+    #[test]
+    fn reads_responses() {
+        let settled = json!({
+            "success": true,
+            "payer": "0x857b06519E91e3A54538791bDbb0E22373e36b66",
+            "transaction": "0x1234",
+            "network": "eip155:84532",
+        });
+        assert_eq!(
+            ResponseFields::of(&settled),
+            ResponseFields {
+                payer: Some("0x857b06519E91e3A54538791bDbb0E22373e36b66"),
+                tx_hash: Some("0x1234"),
+            }
+        );
+
+        let failed = json!({
+            "success": false,
+            "errorReason": "insufficient_funds",
+            "transaction": "",
+            "network": "eip155:84532",
+        });
+        assert_eq!(ResponseFields::of(&failed), ResponseFields::default());
+    }
+}
@@ -0,0 +1,191 @@
+//! Schemes defined outside this repository are routed to by the [`SchemeRegistry`].
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use x402_types::chain::{ChainId, ChainIdPattern, ChainProviderOps, ChainRegistry};
+use x402_types::proto;
+use x402_types::scheme::{
+    SchemeBlueprints, SchemeConfig, SchemeHandlerSlug, SchemeRegistry, X402SchemeFacilitator,
+    X402SchemeFacilitatorBuilder, X402SchemeFacilitatorError, X402SchemeId,
+};
+
+/// A chain provider the built-in schemes know nothing about.
+struct XdcProvider(ChainId);
+
+impl ChainProviderOps for XdcProvider {
+    fn signer_addresses(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.0.clone()
+    }
+}
+
+/// Chains handlers were built for, with their scheme configuration.
+type Built = Arc<Mutex<Vec<(ChainId, Option<serde_json::Value>)>>>;
+
+/// The `v2-xdc-exact` scheme, recording the chains it built handlers for.
+#[derive(Default)]
+struct V2XdcExact {
+    built: Built,
+}
+
+impl X402SchemeId for V2XdcExact {
+    fn namespace(&self) -> &str {
+        "xdc"
+    }
+
+    fn scheme(&self) -> &str {
+        "exact"
+    }
+}
+
+impl X402SchemeFacilitatorBuilder<&XdcProvider> for V2XdcExact {
+    fn build(
+        &self,
+        provider: &XdcProvider,
+        config: Option<serde_json::Value>,
+    ) -> Result<Box<dyn X402SchemeFacilitator>, Box<dyn std::error::Error>> {
+        self.built
+            .lock()
+            .unwrap()
+            .push((provider.0.clone(), config));
+        Ok(Box::new(V2XdcExactFacilitator(provider.0.clone())))
+    }
+}
+
+/// Handler answering verifications with the chain it was built for.
+struct V2XdcExactFacilitator(ChainId);
+
+#[async_trait::async_trait]
+impl X402SchemeFacilitator for V2XdcExactFacilitator {
+    async fn verify(
+        &self,
+        _request: &proto::VerifyRequest,
+    ) -> Result<proto::VerifyResponse, X402SchemeFacilitatorError> {
+        Ok(proto::VerifyResponse(json!({
+            "isValid": true,
+            "payer": self.0.to_string(),
+        })))
+    }
+
+    async fn settle(
+        &self,
+        _request: &proto::SettleRequest,
+    ) -> Result<proto::SettleResponse, X402SchemeFacilitatorError> {
+        Err(X402SchemeFacilitatorError::OnchainFailure(
+            "not settled".into(),
+        ))
+    }
+
+    async fn supported(&self) -> Result<proto::SupportedResponse, X402SchemeFacilitatorError> {
+        Ok(proto::SupportedResponse::default())
+    }
+}
+
+/// Polls a future that completes without waiting, as the handlers above do.
+fn ready<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future is not ready"),
+    }
+}
+
+fn chains(chain_ids: &[ChainId]) -> ChainRegistry<XdcProvider> {
+    ChainRegistry::new(
+        chain_ids
+            .iter()
+            .map(|chain_id| (chain_id.clone(), XdcProvider(chain_id.clone())))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+fn verify_request(chain_id: &ChainId) -> proto::VerifyRequest {
+    let request = json!({
+        "x402Version": 2,
+        "paymentPayload": {
+            "x402Version": 2,
+            "accepted": { "scheme": "exact", "network": chain_id.to_string() },
+            "payload": {},
+        },
+        "paymentRequirements": {
+            "scheme": "exact",
+            "network": chain_id.to_string(),
+            "amount": "10000",
+            "payTo": "xdcBAc675C310721717Cd4A37F6cbeA1F081b1C2a07",
+            "asset": "xdc036CbD53842c5426634e7929541eC2318f3dCF7e",
+        },
+    });
+    serde_json::from_str(&request.to_string()).unwrap()
+}
+
+#[test]
+fn registry_routes_to_external_schemes() {
+    let mainnet = ChainId::new("xdc", "50");
+    let apothem = ChainId::new("xdc", "51");
+    let scheme = V2XdcExact::default();
+    let built = scheme.built.clone();
+    let blueprints = SchemeBlueprints::new().and_register(scheme);
+    let config = vec![SchemeConfig {
+        enabled: true,
+        id: "v2-xdc-exact".into(),
+        chains: ChainIdPattern::wildcard("xdc"),
+        config: Some(json!({ "gasPrice": "auto" })),
+    }];
+
+    let registry = SchemeRegistry::build(
+        chains(&[mainnet.clone(), apothem.clone()]),
+        blueprints,
+        &config,
+    );
+
+    let mut built = built.lock().unwrap().clone();
+    built.sort_by_key(|(chain_id, _)| chain_id.to_string());
+    assert_eq!(
+        built,
+        vec![
+            (mainnet.clone(), Some(json!({ "gasPrice": "auto" }))),
+            (apothem.clone(), Some(json!({ "gasPrice": "auto" }))),
+        ]
+    );
+
+    for chain_id in [&mainnet, &apothem] {
+        let request = verify_request(chain_id);
+        let slug = request.scheme_handler_slug().unwrap();
+        assert_eq!(
+            slug,
+            SchemeHandlerSlug::new(chain_id.clone(), 2, "exact".into())
+        );
+        let handler = registry.by_slug(&slug).unwrap();
+        let response = ready(handler.verify(&request)).unwrap();
+        assert_eq!(response.0["payer"], chain_id.to_string());
+    }
+
+    let v1 = SchemeHandlerSlug::new(mainnet, 1, "exact".into());
+    assert!(registry.by_slug(&v1).is_none());
+}
+
+#[test]
+fn disabled_external_schemes_are_not_built() {
+    let mainnet = ChainId::new("xdc", "50");
+    let scheme = V2XdcExact::default();
+    let built = scheme.built.clone();
+    let blueprints = SchemeBlueprints::default().and_register(scheme);
+    let config = vec![SchemeConfig {
+        enabled: false,
+        id: "v2-xdc-exact".into(),
+        chains: ChainIdPattern::wildcard("xdc"),
+        config: None,
+    }];
+
+    let registry =
+        SchemeRegistry::build(chains(std::slice::from_ref(&mainnet)), blueprints, &config);
+
+    assert!(built.lock().unwrap().is_empty());
+    let slug = SchemeHandlerSlug::new(mainnet, 2, "exact".into());
+    assert!(registry.by_slug(&slug).is_none());
+}
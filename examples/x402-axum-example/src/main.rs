@@ -1,8 +1,9 @@
 use alloy_primitives::address;
 use axum::Extension;
 use axum::Router;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use dotenvy::dotenv;
 use solana_pubkey::pubkey;
@@ -65,6 +66,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 )),
             ),
         )
+        // Mixed protocol versions: V1 on Base Sepolia or V2 on Solana Devnet
+        .route(
+            "/static-price-mixed",
+            get(my_handler).layer(
+                x402.with_price_tag(V1Eip155Exact::price_tag(
+                    address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"),
+                    USDC::base_sepolia().parse("0.01")?,
+                ))
+                .or_price_tag(V2SolanaExact::price_tag(
+                    pubkey!("EGBQqKn968sVv5cQh5Cr72pSTHfxsuzq7o7asqYB5uEV"),
+                    USDC::solana_devnet().amount(100),
+                )),
+            ),
+        )
         .route(
             "/static-price-v2-permit2",
             get(static_price_v2_permit2_handler).layer(x402.with_price_tag(
@@ -143,6 +158,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 ))
                 .with_extension(Eip2612GasSponsoring::server()),
             ),
+        )
+        // Paid WebSocket: the upgrade request carries the payment, settled before the
+        // connection switches protocols
+        .route(
+            "/ws-echo",
+            get(ws_echo_handler).layer(x402.with_price_tag(V2Eip155Exact::price_tag(
+                address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"),
+                USDC::base_sepolia().amount(10u64),
+            ))),
         );
 
     tracing::info!("Using facilitator on {}", x402.facilitator_url());
@@ -197,3 +221,21 @@ fn init_tracing() {
         .with_thread_names(false)
         .init();
 }
+
+#[instrument(skip_all)]
+async fn ws_echo_handler(
+    ws: WebSocketUpgrade,
+    Extension(settlement): Extension<Option<SettleResponse>>,
+) -> Response {
+    tracing::info!(settlement = ?settlement, "WebSocket payment settled");
+    ws.on_upgrade(|mut socket: WebSocket| async move {
+        let _ = socket
+            .send(Message::text("Paid connection open, echoing messages"))
+            .await;
+        while let Some(Ok(message)) = socket.recv().await {
+            if socket.send(message).await.is_err() {
+                break;
+            }
+        }
+    })
+}
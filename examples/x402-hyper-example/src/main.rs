@@ -0,0 +1,55 @@
+use alloy_primitives::address;
+use hyper::server::conn::http1;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use std::convert::Infallible;
+use std::env;
+use tokio::net::TcpListener;
+use tower::Layer;
+use x402_axum::X402Middleware;
+use x402_axum::tower::Body;
+use x402_chain_eip155::{KnownNetworkEip155, V2Eip155Exact};
+use x402_types::networks::USDC;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let facilitator_url =
+        env::var("FACILITATOR_URL").unwrap_or("https://facilitator.x402.rs".to_string());
+    let port = env::var("PORT").unwrap_or("3000".to_string());
+
+    let x402 = X402Middleware::try_from(facilitator_url)?;
+
+    // Every request to the gateway costs 0.00001 USDC on Base Sepolia
+    let service = x402
+        .with_price_tag(V2Eip155Exact::price_tag(
+            address!("0xBAc675C310721717Cd4A37F6cbeA1F081b1C2a07"),
+            USDC::base_sepolia().amount(10u64),
+        ))
+        .with_description("Paid gateway".to_string())
+        .into_tower()
+        .layer(tower::service_fn(handler));
+
+    let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let service = TowerToHyperService::new(service.clone());
+        tokio::spawn(async move {
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("Connection error: {e}");
+            }
+        });
+    }
+}
+
+/// Runs once the payment is verified. The payment is settled after it returns
+/// a successful response.
+async fn handler(req: http::Request<Body>) -> Result<http::Response<String>, Infallible> {
+    Ok(http::Response::new(format!(
+        "Paid content for {}",
+        req.uri().path()
+    )))
+}
@@ -0,0 +1,40 @@
+use alloy_signer_local::PrivateKeySigner;
+use lambda_runtime::{Error, LambdaEvent, service_fn};
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::env;
+use std::sync::Arc;
+use x402_chain_eip155::{V1Eip155ExactClient, V2Eip155ExactClient};
+use x402_reqwest::{ReqwestWithPaymentsBuild, ReqwestWithPaymentsBuilder, X402Client};
+
+/// Invocation payload: the paid endpoint to call.
+#[derive(Debug, Deserialize)]
+struct Request {
+    endpoint: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // Built once per execution environment, reused by every invocation.
+    let signer: PrivateKeySigner = env::var("EVM_PRIVATE_KEY")?.parse()?;
+    let signer = Arc::new(signer);
+    let x402_client = X402Client::new()
+        .register(V1Eip155ExactClient::new(signer.clone()))
+        .register(V2Eip155ExactClient::new(signer));
+    let http_client = ReqwestWithPaymentsBuilder::for_lambda(x402_client).build()?;
+    let http_client = &http_client;
+
+    lambda_runtime::run(service_fn(move |event: LambdaEvent<Request>| async move {
+        handle(http_client, event.payload).await
+    }))
+    .await
+}
+
+async fn handle(http_client: &ClientWithMiddleware, request: Request) -> Result<Value, Error> {
+    let response = http_client.get(&request.endpoint).send().await?;
+    Ok(json!({
+        "status": response.status().as_u16(),
+        "body": response.text().await?,
+    }))
+}
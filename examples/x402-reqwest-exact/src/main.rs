@@ -6,8 +6,10 @@ use solana_keypair::Keypair;
 use std::env;
 use std::sync::Arc;
 use x402_chain_eip155::{V1Eip155ExactClient, V2Eip155ExactClient};
-use x402_chain_solana::{V1SolanaExactClient, V2SolanaExactClient};
-use x402_reqwest::{ReqwestWithPayments, ReqwestWithPaymentsBuild, X402Client};
+use x402_chain_solana::chain::rpc::RpcClients;
+use x402_chain_solana::{KnownNetworkSolana, V1SolanaExactClient, V2SolanaExactClient};
+use x402_reqwest::{ReqwestWithPayments, ReqwestWithPaymentsBuild, ResponseExt, X402Client};
+use x402_types::chain::ChainId;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -34,16 +36,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let keypair = env::var("SOLANA_PRIVATE_KEY")
             .ok()
             .map(|v| Keypair::from_base58_string(&v));
-        let rpc_client = env::var("SOLANA_RPC_URL").ok().map(RpcClient::new);
-        if let Some((keypair, rpc_client)) = keypair.zip(rpc_client) {
+        // Each network is paid with a blockhash from its own cluster
+        let rpc_clients = [
+            (ChainId::solana(), "SOLANA_RPC_URL"),
+            (ChainId::solana_devnet(), "SOLANA_DEVNET_RPC_URL"),
+        ]
+        .into_iter()
+        .filter_map(|(chain_id, var)| {
+            let rpc_client = RpcClient::new(env::var(var).ok()?);
+            Some((chain_id, Arc::new(rpc_client)))
+        })
+        .collect::<Vec<_>>();
+        if let Some(keypair) = keypair.filter(|_| !rpc_clients.is_empty()) {
             let keypair = Arc::new(keypair);
-            let rpc_client = Arc::new(rpc_client);
+            let rpc_clients = RpcClients::from_iter(rpc_clients);
             x402_client = x402_client
-                .register(V1SolanaExactClient::new(
+                .register(V1SolanaExactClient::from_rpc_clients(
                     keypair.clone(),
-                    rpc_client.clone(),
+                    rpc_clients.clone(),
                 ))
-                .register(V2SolanaExactClient::new(keypair, rpc_client));
+                .register(V2SolanaExactClient::from_rpc_clients(keypair, rpc_clients));
             println!("Enabled solana exact scheme")
         }
     }
@@ -56,6 +68,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let response = http_client.get(endpoint).send().await?;
 
     println!("Status: {}", response.status());
+    if let Some(receipt) = response.payment_receipt() {
+        println!(
+            "Paid {} of {} on {} in transaction {}",
+            receipt.amount, receipt.asset, receipt.chain_id, receipt.transaction
+        );
+    }
     println!("Response: {:?}", response.text().await?);
 
     Ok(())
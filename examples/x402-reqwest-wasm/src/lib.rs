@@ -0,0 +1,70 @@
+//! Pays for HTTP requests from the browser, with an EVM key kept in `localStorage`.
+//!
+//! Exports `saveKey(privateKey)` and `fetchPaid(url)` to JavaScript. See `www/index.html`.
+
+use alloy_signer_local::PrivateKeySigner;
+use wasm_bindgen::prelude::*;
+use web_sys::Storage;
+use x402_chain_eip155::V2Eip155ExactClient;
+use x402_reqwest::{ReqwestWithPayments, ReqwestWithPaymentsBuild, ResponseExt, X402Client};
+
+/// `localStorage` item holding the hex-encoded EVM private key.
+const PRIVATE_KEY_ITEM: &str = "x402.evmPrivateKey";
+
+/// A response to [`fetch_paid`].
+#[wasm_bindgen(getter_with_clone)]
+pub struct PaidResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The settlement transaction, if a payment was made.
+    pub transaction: Option<String>,
+    /// The response body.
+    pub body: String,
+}
+
+/// Saves the EVM private key to pay with, returning its address.
+#[wasm_bindgen(js_name = saveKey)]
+pub fn save_key(private_key: &str) -> Result<String, JsError> {
+    let signer: PrivateKeySigner = private_key.parse()?;
+    local_storage()?
+        .set_item(PRIVATE_KEY_ITEM, private_key)
+        .map_err(js_error)?;
+    Ok(signer.address().to_string())
+}
+
+/// Fetches `url`, paying a `402 Payment Required` with the saved key.
+#[wasm_bindgen(js_name = fetchPaid)]
+pub async fn fetch_paid(url: String) -> Result<PaidResponse, JsError> {
+    let private_key = local_storage()?
+        .get_item(PRIVATE_KEY_ITEM)
+        .map_err(js_error)?
+        .ok_or_else(|| JsError::new("No private key saved, call saveKey first"))?;
+    let signer: PrivateKeySigner = private_key.parse()?;
+
+    let x402_client = X402Client::new().register(V2Eip155ExactClient::new(signer));
+    let http_client = reqwest::Client::new().with_payments(x402_client).build();
+
+    let response = http_client.get(url).send().await?;
+    let status = response.status().as_u16();
+    let transaction = response
+        .payment_receipt()
+        .map(|receipt| receipt.transaction.clone());
+    let body = response.text().await?;
+    Ok(PaidResponse {
+        status,
+        transaction,
+        body,
+    })
+}
+
+fn local_storage() -> Result<Storage, JsError> {
+    web_sys::window()
+        .ok_or_else(|| JsError::new("Not running in a browser window"))?
+        .local_storage()
+        .map_err(js_error)?
+        .ok_or_else(|| JsError::new("localStorage is not available"))
+}
+
+fn js_error(error: JsValue) -> JsError {
+    JsError::new(&format!("{error:?}"))
+}
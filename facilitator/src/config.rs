@@ -30,10 +30,24 @@
 //!   }
 //! }
 //! ```
+//!
+//! The same section in a TOML config file:
+//!
+//! ```toml
+//! [chains."eip155:84532"]
+//! rpc_url = "https://sepolia.base.org"
+//! signer_private_key = "0x..."
+//!
+//! [chains."solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp"]
+//! rpc_url = "https://api.devnet.solana.com"
+//! signer_private_key = "base58..."
+//! ```
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ops::Deref;
 use x402_types::chain::ChainId;
+use x402_types::util::DecimalU256;
 
 #[cfg(feature = "chain-aptos")]
 use x402_chain_aptos::chain as aptos;
@@ -79,6 +93,73 @@ pub enum ChainConfig {
     Tron(Box<TronChainConfig>),
 }
 
+impl ChainConfig {
+    /// Returns the CAIP-2 identifier of the chain.
+    pub fn chain_id(&self) -> ChainId {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            ChainConfig::Eip155(config) => config.chain_id(),
+            #[cfg(feature = "chain-solana")]
+            ChainConfig::Solana(config) => config.chain_id(),
+            #[cfg(feature = "chain-aptos")]
+            ChainConfig::Aptos(config) => config.chain_id(),
+            #[cfg(feature = "chain-tron")]
+            ChainConfig::Tron(config) => config.chain_id(),
+            #[allow(unreachable_patterns)] // For when no chain features enabled
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        }
+    }
+
+    /// Returns how long (seconds) a successful verification on this chain may be cached,
+    /// `0` if caching is disabled.
+    ///
+    /// Only EVM and TRON payments carry the `validBefore` bounding the TTL; other chain
+    /// families are never cached.
+    pub fn verify_cache_ttl_secs(&self) -> u64 {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            ChainConfig::Eip155(config) => config.inner.verify_cache_ttl_secs,
+            #[cfg(feature = "chain-tron")]
+            ChainConfig::Tron(config) => config.inner.verify_cache_ttl_secs,
+            #[allow(unreachable_patterns)] // Other chain families, or no chain features enabled
+            _ => 0,
+        }
+    }
+
+    /// Returns the asset addresses payments on this chain are restricted to, empty if any
+    /// asset is allowed.
+    pub fn allowed_assets(&self) -> &[String] {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            ChainConfig::Eip155(config) => &config.inner.allowed_assets,
+            #[cfg(feature = "chain-solana")]
+            ChainConfig::Solana(config) => &config.inner.allowed_assets,
+            #[cfg(feature = "chain-aptos")]
+            ChainConfig::Aptos(config) => &config.inner.allowed_assets,
+            #[cfg(feature = "chain-tron")]
+            ChainConfig::Tron(config) => &config.inner.allowed_assets,
+            #[allow(unreachable_patterns)] // For when no chain features enabled
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        }
+    }
+
+    /// Returns the smallest amount, in base units, accepted per asset address on this chain.
+    pub fn min_payment_amount(&self) -> &HashMap<String, DecimalU256> {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            ChainConfig::Eip155(config) => &config.inner.min_payment_amount,
+            #[cfg(feature = "chain-solana")]
+            ChainConfig::Solana(config) => &config.inner.min_payment_amount,
+            #[cfg(feature = "chain-aptos")]
+            ChainConfig::Aptos(config) => &config.inner.min_payment_amount,
+            #[cfg(feature = "chain-tron")]
+            ChainConfig::Tron(config) => &config.inner.min_payment_amount,
+            #[allow(unreachable_patterns)] // For when no chain features enabled
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        }
+    }
+}
+
 /// Configuration for chains.
 ///
 /// This is a wrapper around `Vec<ChainConfig>` that provides custom serialization
@@ -18,8 +18,8 @@
 //!
 //! # Configuration
 //!
-//! The server loads configuration from a JSON file. See [`config`](crate::config) module
-//! for the configuration format and environment variables.
+//! The server loads configuration from a JSON or TOML file, detected by file extension.
+//! See [`config`](crate::config) module for the configuration format and environment variables.
 //!
 //! # Supported Blockchains
 //!
@@ -13,13 +13,15 @@
 //! | `POST` | `/settle` | Settle an accepted payment payload on-chain |
 //! | `GET` | `/supported` | List supported payment kinds (version/scheme/network) |
 //! | `GET` | `/health` | Health check endpoint |
+//! | `GET` | `/events` | Server-Sent Events stream of verifications and settlements |
 //!
 //! # Features
 //!
 //! - **Multi-chain support**: EIP-155 (EVM), Solana, and Aptos networks
 //! - **OpenTelemetry tracing** (with `telemetry` feature): Distributed tracing and metrics
 //! - **CORS support**: Cross-origin requests for browser-based clients
-//! - **Graceful shutdown**: Signal-based shutdown with cleanup
+//! - **Graceful shutdown**: Signal-based shutdown that waits for in-flight settlements, up to
+//!   `drain_timeout_secs` from the configuration
 //!
 //! # Environment Variables
 //!
@@ -31,19 +33,23 @@
 use axum::Router;
 use axum::http::Method;
 use dotenvy::dotenv;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::cors;
 use x402_facilitator_local::util::SigDown;
-use x402_facilitator_local::{FacilitatorLocal, handlers};
+use x402_facilitator_local::{FacilitatorLocal, PaymentEvents, handlers};
 use x402_types::chain::ChainRegistry;
 use x402_types::chain::FromConfig;
+use x402_types::networks::definitions::NetworkDefinitions;
+use x402_types::receipt::ReceiptSigner;
 use x402_types::scheme::{SchemeBlueprints, SchemeRegistry};
 
 #[cfg(feature = "chain-aptos")]
 use x402_chain_aptos::V2AptosExact;
 #[cfg(feature = "chain-eip155")]
-use x402_chain_eip155::{V1Eip155Exact, V2Eip155Exact, V2Eip155Upto};
+use x402_chain_eip155::{V1Eip155Exact, V2Eip155CrossChain, V2Eip155Exact, V2Eip155Upto};
 #[cfg(feature = "chain-solana")]
 use x402_chain_solana::{V1SolanaExact, V2SolanaExact};
 #[cfg(feature = "chain-tron")]
@@ -79,6 +85,18 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     let config = Config::load()?;
 
+    if let Some(path) = config.network_definitions() {
+        let definitions = NetworkDefinitions::load_from_path(path)?;
+        #[cfg(feature = "telemetry")]
+        tracing::info!(
+            path = %path.display(),
+            networks = definitions.networks.len(),
+            tokens = definitions.tokens.len(),
+            "Registering network definitions"
+        );
+        definitions.register()?;
+    }
+
     let chain_registry = ChainRegistry::from_config(config.chains()).await?;
     let scheme_blueprints = {
         #[allow(unused_mut)] // For when no chain features enabled
@@ -88,6 +106,7 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
             scheme_blueprints.register(V1Eip155Exact);
             scheme_blueprints.register(V2Eip155Exact);
             scheme_blueprints.register(V2Eip155Upto);
+            scheme_blueprints.register(V2Eip155CrossChain);
         }
         #[cfg(feature = "chain-solana")]
         {
@@ -107,10 +126,41 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let scheme_registry =
         SchemeRegistry::build(chain_registry, scheme_blueprints, config.schemes());
 
-    let facilitator = FacilitatorLocal::new(scheme_registry);
+    let payment_events = PaymentEvents::new(config.max_sse_connections());
+    let facilitator = config.chains().iter().fold(
+        FacilitatorLocal::new(scheme_registry).with_payment_events(payment_events.clone()),
+        |facilitator, chain| {
+            facilitator
+                .with_verify_cache_ttl(chain.chain_id(), chain.verify_cache_ttl_secs())
+                .with_allowed_assets(chain.chain_id(), chain.allowed_assets())
+                .with_min_payment_amounts(
+                    chain.chain_id(),
+                    chain
+                        .min_payment_amount()
+                        .iter()
+                        .map(|(asset, minimum)| (asset, minimum.0)),
+                )
+        },
+    );
+    let facilitator = match config.receipt_signing_key() {
+        Some(receipt_signing_key) => {
+            let receipt_signer: ReceiptSigner = receipt_signing_key.parse()?;
+            #[cfg(feature = "telemetry")]
+            tracing::info!(
+                public_key = %receipt_signer.public_key_hex(),
+                "Signing settlement receipts"
+            );
+            facilitator.with_receipt_signer(receipt_signer)
+        }
+        None => facilitator,
+    };
+    let settlements = facilitator.settlement_counter().clone();
+    let drain_timeout = Duration::from_secs(config.drain_timeout_secs());
     let axum_state = Arc::new(facilitator);
 
-    let http_endpoints = Router::new().merge(handlers::routes().with_state(axum_state));
+    let http_endpoints = Router::new()
+        .merge(handlers::routes().with_state(axum_state))
+        .merge(handlers::event_routes(payment_events));
     #[cfg(feature = "telemetry")]
     let http_endpoints = http_endpoints.layer(telemetry_layer);
     let http_endpoints = http_endpoints.layer(
@@ -132,9 +182,34 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let sig_down = SigDown::try_new()?;
     let axum_cancellation_token = sig_down.cancellation_token();
     let axum_graceful_shutdown = async move { axum_cancellation_token.cancelled().await };
-    axum::serve(listener, http_endpoints)
+    let server = axum::serve(listener, http_endpoints)
         .with_graceful_shutdown(axum_graceful_shutdown)
-        .await?;
+        .into_future();
+
+    // Once the listener stops accepting requests, settlements already submitted are given
+    // `drain_timeout` to complete. Long-lived connections, like event streams, are not waited for.
+    let drain_cancellation_token = sig_down.cancellation_token();
+    let drained = async move {
+        drain_cancellation_token.cancelled().await;
+        settlements.drain(drain_timeout).await
+    };
+    tokio::select! {
+        result = server => result?,
+        drained = drained => match drained {
+            Ok(()) => {
+                #[cfg(feature = "telemetry")]
+                tracing::info!("All in-flight settlements completed");
+            }
+            Err(_remaining) => {
+                #[cfg(feature = "telemetry")]
+                tracing::warn!(
+                    remaining = _remaining,
+                    timeout_secs = drain_timeout.as_secs(),
+                    "Shutting down with settlements still in flight"
+                );
+            }
+        },
+    }
 
     Ok(())
 }